@@ -12,9 +12,19 @@ use std::marker::PhantomData;
 
 use bevy::prelude::*;
 use prelude::storage::chunk_pointers::ChunkEntityPointers;
+use prelude::storage::{
+    bump_chunk_versions,
+    clear_neighborhood_cache,
+    send_inserted_storage_events,
+};
 use prelude::*;
+use util::block_update::BlockUpdatePlugin;
+use util::chunk_transform::ChunkTransformPlugin;
+use util::pointer_validation::ChunkPointerReport;
+use util::write_queue::{apply_queued_writes, VoxelWriteQueue};
 
 pub mod math;
+pub mod persistence;
 pub mod query;
 pub mod storage;
 pub mod util;
@@ -22,6 +32,7 @@ pub mod util;
 /// Used to import common components and systems for Bones Cubed.
 pub mod prelude {
     pub use super::math::*;
+    pub use super::persistence::*;
     pub use super::query::*;
     pub use super::storage::*;
     pub use super::util::*;
@@ -45,7 +56,73 @@ where
     fn build(&self, app: &mut App) {
         app.register_type::<VoxelWorld>()
             .register_type::<VoxelChunk>()
+            .register_type::<UpAxis>()
+            .register_type::<BlockScale>()
+            .register_type::<WorldTopology>()
+            .register_type::<WorldSector>()
             .register_type::<VoxelStorage<T>>()
-            .register_type::<ChunkEntityPointers>();
+            .register_type::<ChunkEntityPointers>()
+            .register_type::<BlockEntity>()
+            .add_event::<WorldDespawned>()
+            .add_event::<ChunkDespawned>()
+            .add_event::<BlockBroken<T>>()
+            .add_event::<ChunkStorageReplaced<T>>()
+            .add_event::<ChunkVersionConflict>()
+            .add_event::<ChunkPointerReport>()
+            .init_resource::<BlockTags>()
+            .init_resource::<NeighborhoodCache<T>>()
+            .init_resource::<VoxelWriteQueue<T>>()
+            .add_systems(First, clear_neighborhood_cache::<T>)
+            .add_systems(
+                PostUpdate,
+                (
+                    apply_queued_writes::<T>.in_set(Bones3CoreSet::BlockWrites),
+                    (send_inserted_storage_events::<T>, bump_chunk_versions::<T>)
+                        .in_set(Bones3CoreSet::BlockUpdates),
+                ),
+            );
+
+        if !app.is_plugin_added::<BlockUpdatePlugin>() {
+            app.add_plugins(BlockUpdatePlugin);
+        }
+
+        if !app.is_plugin_added::<ChunkTransformPlugin>() {
+            app.add_plugins(ChunkTransformPlugin);
+        }
     }
 }
+
+/// The system sets that define when block data is written and processed
+/// within the `PostUpdate` schedule.
+///
+/// These sets run in the order that they are listed. Other Bones Cubed crates
+/// order their own system sets relative to these, so gameplay systems that
+/// write blocks and need those changes to be visible within the same frame
+/// should be placed within [`Bones3CoreSet::BlockWrites`]. For example, a
+/// system that must run after all block writes and block updates can be
+/// scheduled with:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::Bones3CoreSet;
+/// # fn my_system() {}
+/// # let mut app = App::new();
+/// app.add_systems(PostUpdate, my_system.after(Bones3CoreSet::BlockUpdates));
+/// ```
+///
+/// To also run before chunks are remeshed, such a system can additionally be
+/// ordered before the `RemeshSet` of the `bones3_remesh` crate.
+#[derive(Debug, SystemSet, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Bones3CoreSet {
+    /// This system set is used for systems that write block data using voxel
+    /// commands or voxel queries.
+    BlockWrites,
+
+    /// This system set applies all deferred voxel commands that were queued
+    /// by systems within [`Bones3CoreSet::BlockWrites`].
+    FlushBlockWrites,
+
+    /// This system set is used for systems that react to block changes, such
+    /// as block update propagation and heightmap tracking.
+    BlockUpdates,
+}
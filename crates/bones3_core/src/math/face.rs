@@ -0,0 +1,70 @@
+//! The six faces of a block.
+
+use bevy::prelude::*;
+
+/// One of the six faces of a block, named after the direction that the face
+/// points in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum BlockFace {
+    /// The face that points towards negative X.
+    NegX,
+
+    /// The face that points towards positive X.
+    PosX,
+
+    /// The face that points towards negative Y.
+    NegY,
+
+    /// The face that points towards positive Y.
+    PosY,
+
+    /// The face that points towards negative Z.
+    NegZ,
+
+    /// The face that points towards positive Z.
+    PosZ,
+}
+
+impl BlockFace {
+    /// All six block faces.
+    pub const ALL: [BlockFace; 6] = [
+        BlockFace::NegX,
+        BlockFace::PosX,
+        BlockFace::NegY,
+        BlockFace::PosY,
+        BlockFace::NegZ,
+        BlockFace::PosZ,
+    ];
+
+    /// Gets the unit offset that this face points towards.
+    pub fn normal(self) -> IVec3 {
+        match self {
+            BlockFace::NegX => IVec3::NEG_X,
+            BlockFace::PosX => IVec3::X,
+            BlockFace::NegY => IVec3::NEG_Y,
+            BlockFace::PosY => IVec3::Y,
+            BlockFace::NegZ => IVec3::NEG_Z,
+            BlockFace::PosZ => IVec3::Z,
+        }
+    }
+
+    /// Gets the face that points in the opposite direction.
+    pub fn opposite(self) -> BlockFace {
+        match self {
+            BlockFace::NegX => BlockFace::PosX,
+            BlockFace::PosX => BlockFace::NegX,
+            BlockFace::NegY => BlockFace::PosY,
+            BlockFace::PosY => BlockFace::NegY,
+            BlockFace::NegZ => BlockFace::PosZ,
+            BlockFace::PosZ => BlockFace::NegZ,
+        }
+    }
+
+    /// Gets the face that points in the given unit direction, or `None` if the
+    /// direction is not a unit offset along a single axis.
+    pub fn from_normal(normal: IVec3) -> Option<BlockFace> {
+        BlockFace::ALL
+            .into_iter()
+            .find(|face| face.normal() == normal)
+    }
+}
@@ -0,0 +1,86 @@
+//! Greedy merging of solid cells into axis-aligned boxes.
+
+use bevy::prelude::*;
+
+use super::Region;
+
+/// Converts all solid cells within the given region into a small set of
+/// non-overlapping, axis-aligned boxes using a greedy merge.
+///
+/// Each box is returned as a pair of its minimum and maximum corners, both
+/// inclusive. The resulting boxes exactly cover all solid cells, which makes
+/// them suitable for building colliders, navmeshes, or light blockers.
+///
+/// Boxes are first grown along the Z axis, then the Y axis, and finally the X
+/// axis. While this does not always produce the smallest possible number of
+/// boxes, it is fast and works well for typical terrain.
+pub fn greedy_boxes<F>(region: Region, is_solid: F) -> Vec<(IVec3, IVec3)>
+where
+    F: Fn(IVec3) -> bool,
+{
+    let mut visited = vec![false; region.count()];
+    let mut boxes = vec![];
+
+    let max = region.max();
+    let index = |p: IVec3| region.point_to_index(p).unwrap();
+
+    for start in region.iter() {
+        if visited[index(start)] || !is_solid(start) {
+            continue;
+        }
+
+        let open = |p: IVec3, visited: &[bool]| !visited[index(p)] && is_solid(p);
+        let mut end = start;
+
+        while end.z < max.z && open(IVec3::new(start.x, start.y, end.z + 1), &visited) {
+            end.z += 1;
+        }
+
+        while end.y < max.y
+            && (start.z ..= end.z).all(|z| open(IVec3::new(start.x, end.y + 1, z), &visited))
+        {
+            end.y += 1;
+        }
+
+        while end.x < max.x
+            && (start.y ..= end.y)
+                .all(|y| (start.z ..= end.z).all(|z| open(IVec3::new(end.x + 1, y, z), &visited)))
+        {
+            end.x += 1;
+        }
+
+        for p in Region::from_points(start, end).iter() {
+            visited[index(p)] = true;
+        }
+
+        boxes.push((start, end));
+    }
+
+    boxes
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn merge_boxes() {
+        let floor = Region::from_points(IVec3::ZERO, IVec3::new(15, 1, 15));
+        let pillar = Region::from_points(IVec3::new(4, 2, 4), IVec3::new(5, 9, 5));
+        let is_solid = |p: IVec3| floor.contains(p) || pillar.contains(p);
+
+        let boxes = greedy_boxes(Region::CHUNK, is_solid);
+        assert_eq!(boxes, vec![
+            (IVec3::ZERO, IVec3::new(15, 1, 15)),
+            (IVec3::new(4, 2, 4), IVec3::new(5, 9, 5)),
+        ]);
+
+        let volume: i32 = boxes
+            .iter()
+            .map(|(a, b)| Region::from_points(*a, *b).count() as i32)
+            .sum();
+        assert_eq!(volume, floor.count() as i32 + pillar.count() as i32);
+    }
+}
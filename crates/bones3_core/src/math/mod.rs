@@ -1,7 +1,17 @@
 //! A collection of simple math utilities for working with voxel environments.
 
+mod face;
+mod greedy;
 mod iterators;
+mod raycast;
 mod region;
+mod rotation;
+mod wide_coords;
 
+pub use face::*;
+pub use greedy::*;
 pub use iterators::*;
+pub use raycast::*;
 pub use region::*;
+pub use rotation::*;
+pub use wide_coords::*;
@@ -0,0 +1,197 @@
+//! Stepping through the blocks that are crossed by a ray.
+
+use bevy::prelude::*;
+
+use super::BlockFace;
+
+/// A single block that was crossed by a [`BlockRaycast`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastStep {
+    /// The coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The face of the block that the ray entered through, or `None` for the
+    /// block that contains the ray origin.
+    pub face: Option<BlockFace>,
+
+    /// The distance along the ray at which the block was entered.
+    pub distance: f32,
+}
+
+/// An iterator over all blocks that are crossed by a ray, in order, up to a
+/// maximum distance.
+///
+/// The ray is given in block coordinates, where each block is one unit wide.
+/// Rays in world space must be converted into the local space of the voxel
+/// world, and divided by its [`BlockScale`](crate::storage::BlockScale),
+/// first.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::math::{BlockFace, BlockRaycast};
+/// # let is_solid = |block_coords: IVec3| block_coords.y < 0;
+/// let ray = Ray {
+///     origin:    Vec3::new(0.5, 10.5, 0.5),
+///     direction: Vec3::NEG_Y,
+/// };
+///
+/// let hit =
+///     BlockRaycast::new(ray, 64.0).find(|step| is_solid(step.block_coords));
+/// # let hit = hit.unwrap();
+/// # assert_eq!(hit.block_coords, IVec3::new(0, -1, 0));
+/// # assert_eq!(hit.face, Some(BlockFace::PosY));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockRaycast {
+    /// The coordinates of the next block to return.
+    block_coords: IVec3,
+
+    /// The face of the next block that the ray entered through.
+    face: Option<BlockFace>,
+
+    /// The distance along the ray at which the next block is entered.
+    distance: f32,
+
+    /// The direction that the block coordinates step along each axis.
+    step: IVec3,
+
+    /// The distance along the ray at which the next block boundary along each
+    /// axis is crossed.
+    next_boundary: Vec3,
+
+    /// The distance along the ray between block boundaries along each axis.
+    boundary_delta: Vec3,
+
+    /// The maximum distance along the ray.
+    max_distance: f32,
+}
+
+impl BlockRaycast {
+    /// Creates a new block raycast from the given ray, up to the given maximum
+    /// distance.
+    pub fn new(ray: Ray, max_distance: f32) -> Self {
+        let direction = ray.direction.normalize_or_zero();
+        let block_coords = ray.origin.floor().as_ivec3();
+        let step =
+            Vec3::select(direction.cmpeq(Vec3::ZERO), Vec3::ZERO, direction.signum()).as_ivec3();
+
+        let boundary_delta = (1.0 / direction).abs();
+        let next_boundary = Vec3::select(
+            direction.cmpgt(Vec3::ZERO),
+            (block_coords.as_vec3() + 1.0 - ray.origin) * boundary_delta,
+            (ray.origin - block_coords.as_vec3()) * boundary_delta,
+        );
+
+        // Axes that the ray does not move along never cross a boundary. This
+        // also avoids `0 * inf = NaN` when the origin lies on a block boundary.
+        let next_boundary = Vec3::select(
+            direction.cmpeq(Vec3::ZERO),
+            Vec3::splat(f32::INFINITY),
+            next_boundary,
+        );
+
+        Self {
+            block_coords,
+            face: None,
+            distance: 0.0,
+            step,
+            next_boundary,
+            boundary_delta,
+            max_distance: if direction == Vec3::ZERO { 0.0 } else { max_distance },
+        }
+    }
+}
+
+impl Iterator for BlockRaycast {
+    type Item = RaycastStep;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.distance > self.max_distance {
+            return None;
+        }
+
+        let current = RaycastStep {
+            block_coords: self.block_coords,
+            face:         self.face,
+            distance:     self.distance,
+        };
+
+        let axis = match self.next_boundary {
+            b if b.x <= b.y && b.x <= b.z => 0,
+            b if b.y <= b.z => 1,
+            _ => 2,
+        };
+
+        let mut offset = IVec3::ZERO;
+        offset[axis] = self.step[axis];
+
+        self.block_coords += offset;
+        self.face = BlockFace::from_normal(-offset);
+        self.distance = self.next_boundary[axis];
+        self.next_boundary[axis] += self.boundary_delta[axis];
+
+        if self.face.is_none() {
+            self.distance = f32::INFINITY;
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn step_through_blocks() {
+        let ray = Ray {
+            origin:    Vec3::new(0.5, 0.5, 0.5),
+            direction: Vec3::new(1.0, 0.5, 0.0),
+        };
+
+        let steps: Vec<_> = BlockRaycast::new(ray, 3.0)
+            .map(|step| (step.block_coords.to_array(), step.face))
+            .collect();
+
+        assert_eq!(steps, vec![
+            ([0, 0, 0], None),
+            ([1, 0, 0], Some(BlockFace::NegX)),
+            ([1, 1, 0], Some(BlockFace::NegY)),
+            ([2, 1, 0], Some(BlockFace::NegX)),
+            ([3, 1, 0], Some(BlockFace::NegX)),
+        ]);
+
+        let backwards = Ray {
+            origin:    Vec3::new(0.5, 0.5, 0.5),
+            direction: Vec3::NEG_Z,
+        };
+        let hit = BlockRaycast::new(backwards, 10.0)
+            .find(|step| step.block_coords.z == -3)
+            .unwrap();
+        assert_eq!(hit.face, Some(BlockFace::PosZ));
+        assert_eq!(hit.distance, 2.5);
+    }
+
+    #[test]
+    fn axis_aligned_ray_on_block_boundary() {
+        let ray = Ray {
+            origin:    Vec3::new(0.5, 0.0, 0.0),
+            direction: Vec3::X,
+        };
+
+        let steps: Vec<_> = BlockRaycast::new(ray, 5.0)
+            .map(|step| step.block_coords.to_array())
+            .collect();
+
+        assert_eq!(steps, vec![
+            [0, 0, 0],
+            [1, 0, 0],
+            [2, 0, 0],
+            [3, 0, 0],
+            [4, 0, 0],
+            [5, 0, 0],
+        ]);
+    }
+}
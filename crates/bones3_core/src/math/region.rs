@@ -2,6 +2,7 @@
 
 use std::fmt::Display;
 
+use bevy::math::I64Vec3;
 use bevy::prelude::*;
 use thiserror::Error;
 
@@ -35,6 +36,19 @@ impl Region {
         size: IVec3::new(256, 256, 256),
     };
 
+    /// Gets the region of chunk coordinates that are within the sector at the
+    /// given sector coordinates.
+    ///
+    /// Each sector is a 16x16x16 grid of chunks, covering the same number of
+    /// blocks as [`Region::SECTOR`]. The coordinates of the sector that
+    /// contains a chunk are the chunk coordinates shifted right by 4.
+    pub fn sector_chunks(sector_coords: IVec3) -> Self {
+        Self {
+            pos:  sector_coords << 4,
+            size: IVec3::new(16, 16, 16),
+        }
+    }
+
     /// Creates a new region from two points within the grid.
     ///
     /// Each point is an opposite corner of the grid.
@@ -118,9 +132,97 @@ impl Region {
             return Err(RegionError::OutOfBounds(point));
         }
 
-        let p = point - self.pos;
-        let index = p.x * self.size.y * self.size.z + p.y * self.size.z + p.z;
-        Ok(index as usize)
+        let p = (point - self.pos).as_uvec3();
+        let size = self.size.as_uvec3();
+        let index =
+            (p.x as usize * size.y as usize + p.y as usize) * size.z as usize + p.z as usize;
+        Ok(index)
+    }
+
+    /// Converts an array index back into the position within this region that
+    /// it was created from, using the same layout as
+    /// [`Region::point_to_index`].
+    ///
+    /// If the given index is not within this region, an error is returned.
+    pub fn index_to_point(&self, index: usize) -> Result<IVec3, RegionError> {
+        if index >= self.count() {
+            return Err(RegionError::IndexOutOfBounds(index));
+        }
+
+        let size = self.size.as_uvec3();
+        let x = index / (size.y as usize * size.z as usize);
+        let y = (index / size.z as usize) % size.y as usize;
+        let z = index % size.z as usize;
+        Ok(self.pos + IVec3::new(x as i32, y as i32, z as i32))
+    }
+
+    /// Checks whether or not this region is valid.
+    ///
+    /// A region is valid if it has a size greater than zero along all axes,
+    /// and neither its maximum corner nor its element count overflow.
+    pub fn is_valid(&self) -> bool {
+        if self.size.cmple(IVec3::ZERO).any() {
+            return false;
+        }
+
+        let max = self.pos.as_i64vec3() + self.size.as_i64vec3() - 1;
+        let size = self.size.as_uvec3();
+
+        max.cmple(I64Vec3::splat(i32::MAX as i64)).all()
+            && (size.x as usize)
+                .checked_mul(size.y as usize)
+                .and_then(|count| count.checked_mul(size.z as usize))
+                .is_some()
+    }
+
+    /// Asserts that this region is valid.
+    ///
+    /// See [`Region::is_valid`] for more information.
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        assert!(self.is_valid(), "Region {self} is not valid");
+    }
+
+    /// Asserts that the given point, which must be within this region, maps to
+    /// an array index within this region that maps back to the same point.
+    #[track_caller]
+    pub fn assert_point_round_trip(&self, point: IVec3) {
+        let index = match self.point_to_index(point) {
+            Ok(index) => index,
+            Err(err) => panic!("Failed to get index of {point} in region {self}: {err}"),
+        };
+
+        assert!(
+            index < self.count(),
+            "Point {point} maps to index {index}, which is outside of region {self}"
+        );
+
+        let result = self.index_to_point(index).ok();
+        assert!(
+            result == Some(point),
+            "Point {point} maps to index {index} in region {self}, which maps back to {result:?}"
+        );
+    }
+
+    /// Asserts that the given array index, which must be within this region,
+    /// maps to a point within this region that maps back to the same index.
+    #[track_caller]
+    pub fn assert_index_round_trip(&self, index: usize) {
+        let point = match self.index_to_point(index) {
+            Ok(point) => point,
+            Err(err) => panic!("Failed to get point of index {index} in region {self}: {err}"),
+        };
+
+        assert!(
+            self.contains(point),
+            "Index {index} maps to point {point}, which is outside of region {self}"
+        );
+
+        let result = self.point_to_index(point).ok();
+        assert!(
+            result == Some(index),
+            "Index {index} maps to point {point} in region {self}, which maps back to {result:?}"
+        );
     }
 
     /// Creates a new cuboid iterator over this region.
@@ -130,7 +232,8 @@ impl Region {
 
     /// Gets the number of elements within this region.
     pub fn count(&self) -> usize {
-        (self.size.x * self.size.y * self.size.z) as usize
+        let size = self.size.as_uvec3();
+        size.x as usize * size.y as usize * size.z as usize
     }
 
     /// Shifts this region's position by the given amount.
@@ -195,14 +298,64 @@ pub enum RegionError {
     /// lies outside of the region bounds.
     #[error("Point is outside of region: {0}")]
     OutOfBounds(IVec3),
+
+    /// An error that is thrown when attempting to get the point of an array
+    /// index that lies outside of the region bounds.
+    #[error("Index is outside of region: {0}")]
+    IndexOutOfBounds(usize),
 }
 
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
 
     use super::*;
 
+    fn point() -> impl Strategy<Value = IVec3> {
+        prop::array::uniform3(-1000 .. 1000).prop_map(IVec3::from_array)
+    }
+
+    fn region() -> impl Strategy<Value = Region> {
+        (point(), prop::array::uniform3(1 .. 24))
+            .prop_map(|(pos, size)| Region::from_size(pos, IVec3::from_array(size)).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_contained_by_both(a in region(), b in region()) {
+            match Region::intersection(&a, &b) {
+                Ok(i) => {
+                    i.assert_valid();
+                    prop_assert!(a.intersects(b) && b.intersects(a));
+                    prop_assert!(a.contains(i.min()) && a.contains(i.max()));
+                    prop_assert!(b.contains(i.min()) && b.contains(i.max()));
+                    prop_assert_eq!(Region::intersection(&b, &a).unwrap(), i);
+                },
+                Err(_) => prop_assert!(!a.intersects(b) && !b.intersects(a)),
+            }
+        }
+
+        #[test]
+        fn expand_contains_point(region in region(), p in point()) {
+            let expanded = region.expand(p);
+            expanded.assert_valid();
+            prop_assert!(expanded.contains(p));
+            prop_assert!(expanded.contains(region.min()) && expanded.contains(region.max()));
+            prop_assert_eq!(expanded.min(), region.min().min(p));
+            prop_assert_eq!(expanded.max(), region.max().max(p));
+        }
+
+        #[test]
+        fn index_round_trip(region in region(), offset in prop::array::uniform3(0 .. 24)) {
+            let p = region.min() + (IVec3::from_array(offset) % region.size());
+            region.assert_point_round_trip(p);
+            region.assert_index_round_trip(region.point_to_index(p).unwrap());
+            prop_assert!(region.point_to_index(region.max() + 1).is_err());
+            prop_assert!(region.index_to_point(region.count()).is_err());
+        }
+    }
+
     #[test]
     fn index_is_unique() {
         let a = IVec3::new(-17, 2, -3);
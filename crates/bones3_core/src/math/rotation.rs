@@ -0,0 +1,160 @@
+//! The 24 rotations of a block that keep it aligned with the world axes.
+
+use std::ops::Mul;
+
+use bevy::prelude::*;
+
+use super::BlockFace;
+
+/// One of the 24 rotations of a block that map each axis onto another axis,
+/// such as the facing of a log, stair, or piston block.
+///
+/// A rotation is described by the directions that the positive X, Y, and Z
+/// axes point towards after it is applied. Rotations may be combined using
+/// multiplication, where `a * b` applies `b` first, then `a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct BlockRotation {
+    /// The direction that the positive X axis is rotated towards.
+    x_axis: IVec3,
+
+    /// The direction that the positive Y axis is rotated towards.
+    y_axis: IVec3,
+
+    /// The direction that the positive Z axis is rotated towards.
+    z_axis: IVec3,
+}
+
+impl Default for BlockRotation {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl BlockRotation {
+    /// The rotation that leaves all axes unchanged.
+    pub const IDENTITY: BlockRotation = BlockRotation {
+        x_axis: IVec3::X,
+        y_axis: IVec3::Y,
+        z_axis: IVec3::Z,
+    };
+
+    /// Creates the rotation that turns the positive X face towards `x` and the
+    /// positive Y face towards `y`.
+    ///
+    /// Returns `None` if the two faces are not perpendicular.
+    pub fn from_faces(x: BlockFace, y: BlockFace) -> Option<Self> {
+        let (x_axis, y_axis) = (x.normal(), y.normal());
+        if x_axis.dot(y_axis) != 0 {
+            return None;
+        }
+
+        Some(Self {
+            x_axis,
+            y_axis,
+            z_axis: x_axis.cross(y_axis),
+        })
+    }
+
+    /// Creates a rotation of the given number of clockwise quarter turns around
+    /// the given face, when looking at that face from outside of the block.
+    pub fn from_quarter_turns(axis: BlockFace, turns: u8) -> Self {
+        let normal = axis.normal();
+        let turn = |v: IVec3| normal * normal.dot(v) - normal.cross(v);
+
+        let mut rotation = Self::IDENTITY;
+        for _ in 0 .. turns % 4 {
+            rotation = Self {
+                x_axis: turn(rotation.x_axis),
+                y_axis: turn(rotation.y_axis),
+                z_axis: turn(rotation.z_axis),
+            };
+        }
+        rotation
+    }
+
+    /// Gets an iterator over all 24 block rotations, starting with the
+    /// identity rotation.
+    pub fn all() -> impl Iterator<Item = BlockRotation> {
+        let others = BlockFace::ALL
+            .into_iter()
+            .flat_map(|x| {
+                BlockFace::ALL
+                    .into_iter()
+                    .filter_map(move |y| BlockRotation::from_faces(x, y))
+            })
+            .filter(|rotation| *rotation != BlockRotation::IDENTITY);
+
+        std::iter::once(BlockRotation::IDENTITY).chain(others)
+    }
+
+    /// Rotates the given offset.
+    pub fn rotate(self, offset: IVec3) -> IVec3 {
+        self.x_axis * offset.x + self.y_axis * offset.y + self.z_axis * offset.z
+    }
+
+    /// Gets the face that the given face points towards after this rotation.
+    pub fn rotate_face(self, face: BlockFace) -> BlockFace {
+        BlockFace::from_normal(self.rotate(face.normal())).unwrap()
+    }
+
+    /// Gets the rotation that undoes this rotation.
+    pub fn inverse(self) -> Self {
+        // The inverse of an orthogonal matrix is its transpose.
+        let row = |axis: fn(IVec3) -> i32| {
+            IVec3::new(axis(self.x_axis), axis(self.y_axis), axis(self.z_axis))
+        };
+
+        Self {
+            x_axis: row(|v| v.x),
+            y_axis: row(|v| v.y),
+            z_axis: row(|v| v.z),
+        }
+    }
+}
+
+impl Mul for BlockRotation {
+    type Output = BlockRotation;
+
+    fn mul(self, rhs: BlockRotation) -> Self::Output {
+        Self {
+            x_axis: self.rotate(rhs.x_axis),
+            y_axis: self.rotate(rhs.y_axis),
+            z_axis: self.rotate(rhs.z_axis),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::utils::HashSet;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn rotation_group() {
+        let all: Vec<_> = BlockRotation::all().collect();
+        assert_eq!(all.len(), 24);
+        assert_eq!(all[0], BlockRotation::IDENTITY);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 24);
+
+        for rotation in all.iter() {
+            assert_eq!(*rotation * rotation.inverse(), BlockRotation::IDENTITY);
+            for other in all.iter() {
+                assert!(all.contains(&(*rotation * *other)));
+            }
+        }
+
+        let turn = BlockRotation::from_quarter_turns(BlockFace::PosY, 1);
+        assert_eq!(turn.rotate_face(BlockFace::PosX), BlockFace::PosZ);
+        assert_eq!(turn.rotate_face(BlockFace::PosY), BlockFace::PosY);
+        assert_eq!(
+            BlockRotation::from_quarter_turns(BlockFace::PosY, 4),
+            BlockRotation::IDENTITY
+        );
+        assert_eq!(
+            BlockRotation::from_quarter_turns(BlockFace::PosY, 3),
+            turn.inverse()
+        );
+    }
+}
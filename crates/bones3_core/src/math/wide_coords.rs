@@ -0,0 +1,153 @@
+//! Wide block coordinates for worlds that extend beyond the range of `i32`.
+
+use bevy::prelude::*;
+
+/// The number of bits of a wide block coordinate that are stored within the
+/// local block coordinates of a world sector, along each axis.
+///
+/// Each world sector is `2^24` blocks wide along each axis, which leaves
+/// plenty of headroom within `i32` for chunk, neighbor, and region arithmetic
+/// near the edges of the sector.
+pub const WORLD_SECTOR_BITS: u32 = 24;
+
+/// The width of a single world sector along each axis, in blocks.
+pub const WORLD_SECTOR_SIZE: i64 = 1 << WORLD_SECTOR_BITS;
+
+/// A block position using 64 bit coordinates.
+///
+/// Voxel worlds store blocks using `i32` block coordinates. Worlds that need
+/// to extend further than that are split into world sectors, where each sector
+/// is a separate voxel world with a
+/// [`WorldSector`](crate::storage::WorldSector) component. Wide block
+/// coordinates can be split into the coordinates of the sector that contains
+/// them, and the local block coordinates within that sector.
+///
+/// Sector coordinates are stored as `i32`, so only wide block coordinates
+/// within the range `-2^55 .. 2^55` along each axis belong to a sector.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub struct WideBlockCoords {
+    /// The x coordinate of this block position.
+    pub x: i64,
+
+    /// The y coordinate of this block position.
+    pub y: i64,
+
+    /// The z coordinate of this block position.
+    pub z: i64,
+}
+
+impl WideBlockCoords {
+    /// Creates a new wide block position from the given coordinates.
+    pub const fn new(x: i64, y: i64, z: i64) -> Self {
+        Self {
+            x,
+            y,
+            z,
+        }
+    }
+
+    /// Creates a new wide block position from the given sector coordinates and
+    /// local block coordinates within that sector.
+    ///
+    /// Local block coordinates outside of the sector are allowed, and simply
+    /// refer to blocks within the neighboring sectors.
+    pub fn from_parts(sector_coords: IVec3, local_coords: IVec3) -> Self {
+        let combine = |sector: i32, local: i32| sector as i64 * WORLD_SECTOR_SIZE + local as i64;
+        Self {
+            x: combine(sector_coords.x, local_coords.x),
+            y: combine(sector_coords.y, local_coords.y),
+            z: combine(sector_coords.z, local_coords.z),
+        }
+    }
+
+    /// Gets the coordinates of the world sector that contains this block.
+    ///
+    /// Each axis of this block position must be within the range
+    /// `-2^55 .. 2^55`, which covers every `i32` sector coordinate. Sector
+    /// coordinates of blocks outside of this range wrap around.
+    pub fn sector_coords(self) -> IVec3 {
+        let sector = |v: i64| (v >> WORLD_SECTOR_BITS) as i32;
+        IVec3::new(sector(self.x), sector(self.y), sector(self.z))
+    }
+
+    /// Gets the local block coordinates of this block within the world sector
+    /// that contains it. Each axis is within the range
+    /// `0 .. WORLD_SECTOR_SIZE`.
+    pub fn local_coords(self) -> IVec3 {
+        let local = |v: i64| (v & (WORLD_SECTOR_SIZE - 1)) as i32;
+        IVec3::new(local(self.x), local(self.y), local(self.z))
+    }
+
+    /// Splits this block position into the coordinates of the world sector
+    /// that contains it, and its local block coordinates within that sector.
+    ///
+    /// See [`WideBlockCoords::sector_coords`] for the supported range.
+    pub fn split(self) -> (IVec3, IVec3) {
+        (self.sector_coords(), self.local_coords())
+    }
+
+    /// Converts this block position into `i32` block coordinates, or `None` if
+    /// any axis is out of range.
+    pub fn try_as_ivec3(self) -> Option<IVec3> {
+        Some(IVec3::new(
+            self.x.try_into().ok()?,
+            self.y.try_into().ok()?,
+            self.z.try_into().ok()?,
+        ))
+    }
+
+    /// Gets the block position that is offset from this one by the given
+    /// amount.
+    pub fn offset(self, offset: IVec3) -> Self {
+        Self {
+            x: self.x + offset.x as i64,
+            y: self.y + offset.y as i64,
+            z: self.z + offset.z as i64,
+        }
+    }
+
+    /// Gets the offset from this block position to the given block position,
+    /// or `None` if it does not fit within `i32` coordinates.
+    pub fn delta(self, other: Self) -> Option<IVec3> {
+        Self::new(other.x - self.x, other.y - self.y, other.z - self.z).try_as_ivec3()
+    }
+}
+
+impl From<IVec3> for WideBlockCoords {
+    fn from(value: IVec3) -> Self {
+        Self::new(value.x as i64, value.y as i64, value.z as i64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn split_wide_coords() {
+        let pos = WideBlockCoords::new(5_000_000_000, -3, i32::MAX as i64 + 7);
+        let (sector, local) = pos.split();
+
+        assert_eq!(sector, IVec3::new(298, -1, 128));
+        assert_eq!(local, IVec3::new(389_632, 16_777_213, 6));
+        assert_eq!(WideBlockCoords::from_parts(sector, local), pos);
+        assert_eq!(pos.try_as_ivec3(), None);
+
+        let edge = WideBlockCoords::from_parts(sector, IVec3::new(-1, 0, 0));
+        assert_eq!(edge.sector_coords(), sector - IVec3::X);
+        assert_eq!(pos.delta(edge), Some(IVec3::new(-389_633, -16_777_213, -6)));
+    }
+
+    #[test]
+    fn sector_coords_cover_supported_range() {
+        let limit = 1_i64 << 55;
+        let min = WideBlockCoords::new(-limit, -limit, -limit);
+        let max = WideBlockCoords::new(limit - 1, limit - 1, limit - 1);
+
+        assert_eq!(min.sector_coords(), IVec3::MIN);
+        assert_eq!(max.sector_coords(), IVec3::MAX);
+        assert_eq!(WideBlockCoords::from_parts(IVec3::MIN, IVec3::ZERO), min);
+    }
+}
@@ -0,0 +1,236 @@
+//! The binary format that is used to store chunks.
+
+use crate::math::Region;
+use crate::persistence::PersistenceError;
+use crate::storage::{BlockData, VoxelStorage};
+
+/// The bytes that every chunk record starts with.
+const CHUNK_MAGIC: &[u8; 4] = b"B3CK";
+
+/// The current version of the chunk record format.
+///
+/// Version 2 added the block layout version to the header. Records using
+/// version 1 are read as block layout version `0`.
+const CHUNK_VERSION: u8 = 2;
+
+/// A block data type that can be written to and read from persistent storage.
+///
+/// Implementations must be able to read back every value that they write.
+/// Implementations are provided for all primitive integer types and `bool`.
+pub trait PersistentBlock: BlockData + PartialEq {
+    /// The version of the binary representation of this block, which is
+    /// stamped into every chunk record.
+    ///
+    /// This should be increased whenever the encoding of this block changes,
+    /// along with registering a migration from the previous version within
+    /// the [`ChunkMigrations`](crate::persistence::ChunkMigrations) of each
+    /// persistent world, so that existing saves can still be loaded.
+    ///
+    /// Defaults to `0`.
+    const LAYOUT_VERSION: u32 = 0;
+
+    /// Appends the binary representation of this block to the given buffer.
+    fn encode_block(&self, out: &mut Vec<u8>);
+
+    /// Reads a block from the start of the given buffer, advancing the buffer
+    /// past the bytes that were read.
+    fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError>;
+}
+
+/// Takes the given number of bytes from the start of the buffer.
+pub(crate) fn take_bytes<'a>(
+    input: &mut &'a [u8],
+    len: usize,
+) -> Result<&'a [u8], PersistenceError> {
+    if input.len() < len {
+        return Err(PersistenceError::Truncated);
+    }
+
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+/// Implements [`PersistentBlock`] for primitive integer types, using their
+/// little endian byte representation.
+macro_rules! impl_persistent_int {
+    ($($ty:ty),*) => {
+        $(
+            impl PersistentBlock for $ty {
+                fn encode_block(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+                    let bytes = take_bytes(input, std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_persistent_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl PersistentBlock for bool {
+    fn encode_block(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+        match take_bytes(input, 1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PersistenceError::InvalidBlock),
+        }
+    }
+}
+
+/// Encodes the given chunk storage into a chunk record.
+///
+/// Blocks are written in the iteration order of [`Region::CHUNK`], and runs of
+/// identical blocks are stored only once, so mostly uniform chunks stay small.
+pub fn encode_chunk<T>(storage: &VoxelStorage<T>) -> Vec<u8>
+where
+    T: PersistentBlock,
+{
+    let mut out = vec![];
+    write_header(&mut out, T::LAYOUT_VERSION);
+
+    let mut run: Option<(T, u16)> = None;
+    for block_pos in Region::CHUNK.iter() {
+        let block = storage.get_block(block_pos);
+        run = match run {
+            Some((data, len)) if data == block => Some((data, len + 1)),
+            Some((data, len)) => {
+                write_run(&mut out, data, len);
+                Some((block, 1))
+            },
+            None => Some((block, 1)),
+        };
+    }
+
+    if let Some((data, len)) = run {
+        write_run(&mut out, data, len);
+    }
+
+    out
+}
+
+/// Writes a single run of identical blocks to a chunk record.
+fn write_run<T>(out: &mut Vec<u8>, data: T, len: u16)
+where
+    T: PersistentBlock,
+{
+    out.extend_from_slice(&len.to_le_bytes());
+    data.encode_block(out);
+}
+
+/// Writes the header of a chunk record with the given block layout version.
+pub(crate) fn write_header(out: &mut Vec<u8>, layout_version: u32) {
+    out.extend_from_slice(CHUNK_MAGIC);
+    out.push(CHUNK_VERSION);
+    out.extend_from_slice(&layout_version.to_le_bytes());
+}
+
+/// Reads the header of a chunk record, advancing the buffer to the start of
+/// the block runs, and returns the block layout version of the record.
+pub(crate) fn read_header(input: &mut &[u8]) -> Result<u32, PersistenceError> {
+    if take_bytes(input, 4)? != CHUNK_MAGIC {
+        return Err(PersistenceError::InvalidHeader);
+    }
+
+    match take_bytes(input, 1)?[0] {
+        1 => Ok(0),
+        CHUNK_VERSION => {
+            Ok(u32::from_le_bytes(
+                take_bytes(input, 4)?.try_into().unwrap(),
+            ))
+        },
+        version => Err(PersistenceError::UnsupportedVersion(version)),
+    }
+}
+
+/// Decodes a chunk record that was created using [`encode_chunk`].
+///
+/// The record must use the current [`PersistentBlock::LAYOUT_VERSION`] of the
+/// block data type. Records using older layouts may be decoded using
+/// [`ChunkMigrations::decode_chunk`](crate::persistence::ChunkMigrations::decode_chunk).
+pub fn decode_chunk<T>(mut input: &[u8]) -> Result<VoxelStorage<T>, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let layout_version = read_header(&mut input)?;
+    if layout_version != T::LAYOUT_VERSION {
+        return Err(PersistenceError::LayoutMismatch {
+            found:    layout_version,
+            expected: T::LAYOUT_VERSION,
+        });
+    }
+
+    decode_runs(input)
+}
+
+/// Decodes the block runs of a chunk record, after its header.
+pub(crate) fn decode_runs<T>(mut input: &[u8]) -> Result<VoxelStorage<T>, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let mut storage = VoxelStorage::default();
+    let mut positions = Region::CHUNK.iter();
+    let mut count = 0;
+
+    while !input.is_empty() {
+        let len = u16::from_le_bytes(take_bytes(&mut input, 2)?.try_into().unwrap()) as usize;
+        let data = T::decode_block(&mut input)?;
+
+        count += len;
+        if count > 4096 {
+            return Err(PersistenceError::InvalidBlockCount(count));
+        }
+
+        for block_pos in positions.by_ref().take(len) {
+            if data != T::default() {
+                storage.set_block(block_pos, data);
+            }
+        }
+    }
+
+    if count != 4096 {
+        return Err(PersistenceError::InvalidBlockCount(count));
+    }
+
+    Ok(storage)
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn chunk_round_trip() {
+        let mut storage = VoxelStorage::<u16>::default();
+        storage.set_block(IVec3::new(1, 2, 3), 7);
+        storage.set_block(IVec3::new(15, 15, 15), 300);
+
+        let record = encode_chunk(&storage);
+        assert_eq!(record.len(), 9 + 4 * 4);
+
+        let decoded = decode_chunk::<u16>(&record).unwrap();
+        for block_pos in Region::CHUNK.iter() {
+            assert_eq!(decoded.get_block(block_pos), storage.get_block(block_pos));
+        }
+
+        assert!(matches!(
+            decode_chunk::<u16>(&record[.. record.len() - 1]),
+            Err(PersistenceError::Truncated)
+        ));
+        assert!(matches!(
+            decode_chunk::<u16>(&encode_chunk(&VoxelStorage::<u8>::default())),
+            Err(PersistenceError::Truncated)
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+//! Converting persisted voxel worlds from one block data type to another.
+
+use bevy::prelude::*;
+
+use crate::math::Region;
+use crate::persistence::{
+    encode_chunk,
+    ChunkMigrations,
+    ChunkStore,
+    PersistenceError,
+    PersistentBlock,
+};
+use crate::storage::VoxelStorage;
+
+/// The outcome of converting a persisted voxel world using
+/// [`convert_world`] or [`convert_world_with_migrations`].
+#[derive(Debug, Default)]
+pub struct ConversionReport {
+    /// The number of chunks that were converted.
+    pub converted: usize,
+
+    /// The chunks that could not be converted, along with the error that
+    /// occurred for each.
+    pub failed: Vec<(IVec3, PersistenceError)>,
+}
+
+/// Converts all chunks within the source chunk store from block data type `T`
+/// to block data type `U`, writing the converted chunks into the target chunk
+/// store.
+///
+/// Every block is passed through the given mapper function, which is where
+/// changes to the block layout, such as remapped block registry ids, should be
+/// handled. Chunks are converted one at a time, so this does not require an
+/// app or a loaded voxel world, and only a single chunk is held in memory at
+/// once.
+///
+/// The source and target may be the same chunk store, in which case the world
+/// is converted in place. Chunks that fail to convert are skipped and listed
+/// within the returned report. An error is only returned if the list of chunks
+/// could not be read from the source chunk store.
+///
+/// Only chunks that were saved using the current
+/// [`PersistentBlock::LAYOUT_VERSION`] of `T` can be converted. Use
+/// [`convert_world_with_migrations`] for chunk stores that also contain chunks
+/// saved using older block layouts.
+pub fn convert_world<T, U, M>(
+    source: &dyn ChunkStore,
+    target: &dyn ChunkStore,
+    mapper: M,
+) -> Result<ConversionReport, PersistenceError>
+where
+    T: PersistentBlock,
+    U: PersistentBlock,
+    M: FnMut(T) -> U,
+{
+    convert_world_with_migrations(source, target, &ChunkMigrations::default(), mapper)
+}
+
+/// Converts all chunks within the source chunk store from block data type `T`
+/// to block data type `U`, writing the converted chunks into the target chunk
+/// store.
+///
+/// This behaves like [`convert_world`], except that chunks that were saved
+/// using an older block layout are first upgraded to the current
+/// [`PersistentBlock::LAYOUT_VERSION`] of `T` using the given migrations.
+pub fn convert_world_with_migrations<T, U, M>(
+    source: &dyn ChunkStore,
+    target: &dyn ChunkStore,
+    migrations: &ChunkMigrations,
+    mut mapper: M,
+) -> Result<ConversionReport, PersistenceError>
+where
+    T: PersistentBlock,
+    U: PersistentBlock,
+    M: FnMut(T) -> U,
+{
+    let mut report = ConversionReport::default();
+
+    for chunk_coords in source.chunk_list()? {
+        match convert_chunk(source, target, migrations, chunk_coords, &mut mapper) {
+            Ok(()) => report.converted += 1,
+            Err(err) => report.failed.push((chunk_coords, err)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Converts a single chunk from the source chunk store into the target chunk
+/// store.
+fn convert_chunk<T, U, M>(
+    source: &dyn ChunkStore,
+    target: &dyn ChunkStore,
+    migrations: &ChunkMigrations,
+    chunk_coords: IVec3,
+    mapper: &mut M,
+) -> Result<(), PersistenceError>
+where
+    T: PersistentBlock,
+    U: PersistentBlock,
+    M: FnMut(T) -> U,
+{
+    let Some(record) = source.read_chunk(chunk_coords)? else {
+        return Ok(());
+    };
+
+    let storage = migrations.decode_chunk::<T>(&record)?;
+    let mut converted = VoxelStorage::<U>::default();
+    for local_pos in Region::CHUNK.iter() {
+        converted.set_block(local_pos, mapper(storage.get_block(local_pos)));
+    }
+
+    target.write_chunk(chunk_coords, &encode_chunk(&converted))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::persistence::{decode_chunk, MemoryChunkStore};
+
+    #[test]
+    fn remap_block_ids() {
+        let source = MemoryChunkStore::default();
+        let target = MemoryChunkStore::default();
+
+        let mut storage = VoxelStorage::<u8>::default();
+        storage.set_block(IVec3::new(1, 2, 3), 1);
+        storage.set_block(IVec3::new(4, 5, 6), 2);
+        source
+            .write_chunk(IVec3::ZERO, &encode_chunk(&storage))
+            .unwrap();
+        source.write_chunk(IVec3::X, b"garbage").unwrap();
+
+        let report = convert_world(&source, &target, |block: u8| {
+            match block {
+                0 => 0u16,
+                id => id as u16 + 1000,
+            }
+        })
+        .unwrap();
+
+        assert_eq!(report.converted, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, IVec3::X);
+
+        let record = target.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let converted = decode_chunk::<u16>(&record).unwrap();
+        assert_eq!(converted.get_block(IVec3::new(1, 2, 3)), 1001);
+        assert_eq!(converted.get_block(IVec3::new(4, 5, 6)), 1002);
+        assert_eq!(converted.get_block(IVec3::ZERO), 0);
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    struct Block(u16);
+
+    impl PersistentBlock for Block {
+        const LAYOUT_VERSION: u32 = 1;
+
+        fn encode_block(&self, out: &mut Vec<u8>) {
+            self.0.encode_block(out);
+        }
+
+        fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+            Ok(Block(u16::decode_block(input)?))
+        }
+    }
+
+    #[test]
+    fn convert_chunks_saved_with_older_layouts() {
+        let source = MemoryChunkStore::default();
+        let target = MemoryChunkStore::default();
+
+        let mut old = VoxelStorage::<u8>::default();
+        old.set_block(IVec3::new(1, 2, 3), 7);
+        source
+            .write_chunk(IVec3::ZERO, &encode_chunk(&old))
+            .unwrap();
+
+        let mut current = VoxelStorage::<Block>::default();
+        current.set_block(IVec3::new(4, 5, 6), Block(200));
+        source
+            .write_chunk(IVec3::Y, &encode_chunk(&current))
+            .unwrap();
+
+        let report = convert_world(&source, &target, |block: Block| block.0).unwrap();
+        assert_eq!(report.converted, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, IVec3::ZERO);
+
+        let migrations =
+            ChunkMigrations::default().with_migration(0, |id: u8| Block(id as u16 + 100));
+        let report =
+            convert_world_with_migrations(&source, &target, &migrations, |block: Block| block.0)
+                .unwrap();
+        assert_eq!(report.converted, 2);
+        assert!(report.failed.is_empty());
+
+        let record = target.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let converted = decode_chunk::<u16>(&record).unwrap();
+        assert_eq!(converted.get_block(IVec3::new(1, 2, 3)), 107);
+        assert_eq!(converted.get_block(IVec3::ZERO), 100);
+
+        let record = target.read_chunk(IVec3::Y).unwrap().unwrap();
+        let converted = decode_chunk::<u16>(&record).unwrap();
+        assert_eq!(converted.get_block(IVec3::new(4, 5, 6)), 200);
+    }
+}
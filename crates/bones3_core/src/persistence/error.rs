@@ -0,0 +1,60 @@
+//! Errors that can be triggered while saving or loading chunks.
+
+use std::io;
+
+use thiserror::Error;
+
+/// An error type that is thrown while saving or loading persistent chunks.
+#[derive(Debug, Error)]
+pub enum PersistenceError {
+    /// Thrown when the underlying chunk store fails to read or write data.
+    #[error("Failed to access chunk store: {0}")]
+    Io(#[from] io::Error),
+
+    /// Thrown when a chunk record does not start with the expected header.
+    #[error("Chunk record has an invalid header")]
+    InvalidHeader,
+
+    /// Thrown when a chunk record was written using an unknown format
+    /// version.
+    #[error("Chunk record uses unsupported format version {0}")]
+    UnsupportedVersion(u8),
+
+    /// Thrown when a chunk record uses a different block layout version than
+    /// the block data type it is decoded as, and cannot be migrated.
+    #[error("Chunk record uses block layout version {found} instead of {expected}")]
+    LayoutMismatch {
+        /// The block layout version of the chunk record.
+        found: u32,
+
+        /// The block layout version of the block data type.
+        expected: u32,
+    },
+
+    /// Thrown when a chunk record needs to be migrated from the given block
+    /// layout version, but no migration was registered for that version.
+    #[error("No migration is registered for block layout version {0}")]
+    MissingMigration(u32),
+
+    /// Thrown when a chunk record ends before all of its data was read.
+    #[error("Chunk record is truncated")]
+    Truncated,
+
+    /// Thrown when the block runs within a chunk record do not add up to a
+    /// full chunk.
+    #[error("Chunk record contains {0} blocks instead of 4096")]
+    InvalidBlockCount(usize),
+
+    /// Thrown when a block within a chunk record cannot be decoded.
+    #[error("Chunk record contains an invalid block value")]
+    InvalidBlock,
+
+    /// Thrown when a journal entry does not match its checksum.
+    #[error("Journal entry has an invalid checksum")]
+    InvalidChecksum,
+
+    /// Thrown when a world metadata record contains a value that cannot be
+    /// decoded.
+    #[error("World metadata record contains an invalid value")]
+    InvalidMetaValue,
+}
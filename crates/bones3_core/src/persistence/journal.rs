@@ -0,0 +1,1087 @@
+//! A write-ahead journal that records block edits as soon as they happen, so
+//! that a crash only loses the edits of the last few frames.
+
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bevy::ecs::query::Has;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::math::Region;
+use crate::persistence::codec::take_bytes;
+use crate::persistence::{
+    encode_chunk,
+    ChunkLoadedFromStore,
+    ChunkMigrations,
+    ChunkSaveFailed,
+    ChunkStore,
+    DirtyChunk,
+    PersistenceError,
+    PersistenceSettings,
+    PersistentBlock,
+    PersistentWorld,
+};
+use crate::storage::{StableHasher, VoxelChunk, VoxelStorage};
+
+/// A storage backend for an append-only journal.
+pub trait JournalBackend: Send + Sync + 'static {
+    /// Appends the given bytes to the end of the journal, and makes sure that
+    /// they are durable before returning.
+    fn append(&self, bytes: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Reads the full contents of the journal.
+    fn read_all(&self) -> Result<Vec<u8>, PersistenceError>;
+
+    /// Removes all contents from the journal.
+    fn clear(&self) -> Result<(), PersistenceError>;
+
+    /// Replaces the full contents of the journal with the given bytes.
+    ///
+    /// By default, this clears the journal and then appends the given bytes,
+    /// so the given bytes are lost if the journal is closed in between.
+    /// Backends that can replace their contents atomically should override
+    /// this.
+    fn replace(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+        self.clear()?;
+        if !bytes.is_empty() {
+            self.append(bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// A journal backend that keeps the journal in memory.
+#[derive(Debug, Default)]
+pub struct MemoryJournal {
+    /// The contents of the journal.
+    bytes: Mutex<Vec<u8>>,
+}
+
+impl JournalBackend for MemoryJournal {
+    fn append(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+        self.bytes.lock().unwrap().extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn read_all(&self) -> Result<Vec<u8>, PersistenceError> {
+        Ok(self.bytes.lock().unwrap().clone())
+    }
+
+    fn clear(&self) -> Result<(), PersistenceError> {
+        self.bytes.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn replace(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+        *self.bytes.lock().unwrap() = bytes.to_vec();
+        Ok(())
+    }
+}
+
+/// A journal backend that appends to a single file.
+#[derive(Debug)]
+pub struct FileJournal {
+    /// The path of the journal file.
+    path: PathBuf,
+
+    /// The open journal file.
+    file: Mutex<File>,
+}
+
+impl FileJournal {
+    /// Opens the journal file at the given path, creating it if it does not
+    /// exist yet.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl JournalBackend for FileJournal {
+    fn append(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+        let mut file = self.file.lock().unwrap();
+        file.write_all(bytes)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn read_all(&self) -> Result<Vec<u8>, PersistenceError> {
+        let _file = self.file.lock().unwrap();
+        Ok(fs::read(&self.path)?)
+    }
+
+    fn clear(&self) -> Result<(), PersistenceError> {
+        let file = self.file.lock().unwrap();
+        file.set_len(0)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn replace(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+        let mut file = self.file.lock().unwrap();
+
+        // The new contents are written to a temporary file first, so the
+        // journal is never left partially written.
+        let temp_path = self.path.with_extension("tmp");
+        let mut temp = File::create(&temp_path)?;
+        temp.write_all(bytes)?;
+        temp.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+
+        *file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// A component that enables write-ahead journaling for a persistent voxel
+/// world.
+///
+/// When this component is attached alongside a
+/// [`PersistentWorld`](crate::persistence::PersistentWorld), every block edit
+/// is appended to the journal at the end of the frame it was made in. The
+/// journal is periodically compacted into the chunk store of the world, and is
+/// replayed into the chunk store when this component is added, recovering any
+/// edits that were not saved before a crash.
+///
+/// Since all edits are journaled, chunks within journaled worlds may also be
+/// despawned without being saved first.
+#[derive(Component, Clone)]
+pub struct WriteAheadJournal {
+    /// The journal backend.
+    backend: Arc<dyn JournalBackend>,
+}
+
+impl WriteAheadJournal {
+    /// Creates a new write-ahead journal component using the given backend.
+    pub fn new<J>(backend: J) -> Self
+    where
+        J: JournalBackend,
+    {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Creates a new write-ahead journal component using the given shared
+    /// backend.
+    pub fn from_shared(backend: Arc<dyn JournalBackend>) -> Self {
+        Self {
+            backend,
+        }
+    }
+
+    /// Gets the backend of this journal.
+    pub fn backend(&self) -> &Arc<dyn JournalBackend> {
+        &self.backend
+    }
+}
+
+/// The journaling state of a chunk within a journaled world.
+#[derive(Component)]
+pub(crate) struct JournalState<T>
+where
+    T: PersistentBlock,
+{
+    /// Whether the chunk storage can be rebuilt from the chunk store and the
+    /// journal. This is false for chunks that were spawned or generated, until
+    /// their full storage has been journaled.
+    persisted: bool,
+
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> JournalState<T>
+where
+    T: PersistentBlock,
+{
+    /// Creates a new journal state for a chunk.
+    fn new(persisted: bool) -> Self {
+        Self {
+            persisted,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Packs local block coordinates into a 12 bit index.
+fn pack_local_pos(local_pos: IVec3) -> u16 {
+    let p = (local_pos & 15).as_uvec3();
+    (p.x | (p.y << 4) | (p.z << 8)) as u16
+}
+
+/// Unpacks local block coordinates from a 12 bit index.
+fn unpack_local_pos(index: u16) -> IVec3 {
+    let index = index as i32;
+    IVec3::new(index & 15, (index >> 4) & 15, (index >> 8) & 15)
+}
+
+/// Encodes the given edited blocks of a chunk into a journal entry, or returns
+/// `None` if no blocks were edited.
+///
+/// Each entry is framed with its length and a checksum, so entries that were
+/// only partially written before a crash are detected and ignored. Entries are
+/// stamped with the [`PersistentBlock::LAYOUT_VERSION`] of the block data
+/// type, so they can still be replayed after the block layout changes.
+pub(crate) fn encode_journal_entry<T>(
+    chunk_coords: IVec3,
+    current: &VoxelStorage<T>,
+    edited: &[IVec3],
+) -> Option<Vec<u8>>
+where
+    T: PersistentBlock,
+{
+    let edits = edited
+        .iter()
+        .map(|local_pos| (*local_pos, current.get_block(*local_pos)));
+    encode_entry(chunk_coords, edits)
+}
+
+/// Encodes every block of the given chunk storage into a journal entry, so
+/// that the chunk can be rebuilt from the journal alone.
+///
+/// This is used for chunks that were spawned or generated rather than loaded
+/// from their chunk store, since the chunk store does not contain the blocks
+/// that their edits were made on top of.
+pub(crate) fn encode_full_journal_entry<T>(
+    chunk_coords: IVec3,
+    current: &VoxelStorage<T>,
+) -> Vec<u8>
+where
+    T: PersistentBlock,
+{
+    let blocks = Region::CHUNK
+        .iter()
+        .map(|local_pos| (local_pos, current.get_block(local_pos)));
+    encode_entry(chunk_coords, blocks).unwrap()
+}
+
+/// Encodes the given block edits of a chunk into a framed journal entry, or
+/// returns `None` if there are no edits.
+fn encode_entry<T>(chunk_coords: IVec3, edits: impl Iterator<Item = (IVec3, T)>) -> Option<Vec<u8>>
+where
+    T: PersistentBlock,
+{
+    let mut payload = vec![];
+    for v in chunk_coords.to_array() {
+        payload.extend_from_slice(&v.to_le_bytes());
+    }
+    payload.extend_from_slice(&T::LAYOUT_VERSION.to_le_bytes());
+
+    let mut count = 0usize;
+    for (local_pos, block) in edits {
+        payload.extend_from_slice(&pack_local_pos(local_pos).to_le_bytes());
+        block.encode_block(&mut payload);
+        count += 1;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    let mut hasher = StableHasher::new();
+    hasher.write(&payload);
+
+    let mut entry = vec![];
+    entry.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    entry.extend_from_slice(&hasher.finish().to_le_bytes());
+    entry.extend_from_slice(&payload);
+    Some(entry)
+}
+
+/// The decoded contents of a write-ahead journal.
+pub(crate) struct DecodedJournal<T>
+where
+    T: PersistentBlock,
+{
+    /// The block edits of each chunk, in the order that they were made.
+    pub(crate) edits: HashMap<IVec3, Vec<(IVec3, T)>>,
+
+    /// The chunks that have an entry whose blocks could not be decoded, such
+    /// as an entry that uses a block layout without a registered migration.
+    /// Each chunk holds the journal entries that must be kept for it, along
+    /// with the error that occurred.
+    pub(crate) undecodable: HashMap<IVec3, (Vec<u8>, PersistenceError)>,
+}
+
+/// Decodes all complete journal entries, returning the block edits of each
+/// chunk in the order that they were made. Entries that were written using an
+/// older block layout are upgraded using the given migrations.
+///
+/// Decoding stops at the first entry that is incomplete or has an invalid
+/// checksum, since that entry was being written when the journal was last
+/// closed. All entries before it are still returned.
+///
+/// Entries that are intact, but whose blocks cannot be decoded, are not
+/// discarded. Instead, all entries of that chunk are kept as they are, so that
+/// they can be replayed once the missing migration is registered.
+pub(crate) fn decode_journal<T>(mut input: &[u8], migrations: &ChunkMigrations) -> DecodedJournal<T>
+where
+    T: PersistentBlock,
+{
+    let mut journal = DecodedJournal {
+        edits:       HashMap::new(),
+        undecodable: HashMap::new(),
+    };
+
+    while !input.is_empty() {
+        let start = input;
+        let (chunk_coords, layout, blocks) = match read_journal_entry(&mut input) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("Discarding the end of the write-ahead journal: {}", err);
+                break;
+            },
+        };
+        let raw_entry = &start[.. start.len() - input.len()];
+
+        // Later edits must not be replayed before the entries that could not
+        // be decoded, so they are kept as well.
+        if let Some((retained, _)) = journal.undecodable.get_mut(&chunk_coords) {
+            retained.extend_from_slice(raw_entry);
+            continue;
+        }
+
+        match decode_journal_blocks::<T>(blocks, layout, migrations) {
+            Ok(chunk_edits) => {
+                journal
+                    .edits
+                    .entry(chunk_coords)
+                    .or_default()
+                    .extend(chunk_edits);
+            },
+            Err(err) => {
+                warn!(
+                    "Keeping the write-ahead journal entries of chunk {} that could not be \
+                     decoded: {}",
+                    chunk_coords, err
+                );
+
+                let mut retained = journal
+                    .edits
+                    .remove(&chunk_coords)
+                    .and_then(|edits| encode_entry(chunk_coords, edits.into_iter()))
+                    .unwrap_or_default();
+                retained.extend_from_slice(raw_entry);
+                journal.undecodable.insert(chunk_coords, (retained, err));
+            },
+        }
+    }
+
+    journal
+}
+
+/// Reads a single framed journal entry from the start of the given buffer,
+/// returning the chunk coordinates, the block layout version, and the encoded
+/// block edits of that entry.
+fn read_journal_entry<'a>(
+    input: &mut &'a [u8],
+) -> Result<(IVec3, u32, &'a [u8]), PersistenceError> {
+    let len = u32::from_le_bytes(take_bytes(input, 4)?.try_into().unwrap()) as usize;
+    let checksum = u64::from_le_bytes(take_bytes(input, 8)?.try_into().unwrap());
+    let mut payload = take_bytes(input, len)?;
+
+    let mut hasher = StableHasher::new();
+    hasher.write(payload);
+    if hasher.finish() != checksum {
+        return Err(PersistenceError::InvalidChecksum);
+    }
+
+    let mut coord = || -> Result<i32, PersistenceError> {
+        Ok(i32::from_le_bytes(
+            take_bytes(&mut payload, 4)?.try_into().unwrap(),
+        ))
+    };
+    let chunk_coords = IVec3::new(coord()?, coord()?, coord()?);
+    let layout = u32::from_le_bytes(take_bytes(&mut payload, 4)?.try_into().unwrap());
+
+    Ok((chunk_coords, layout, payload))
+}
+
+/// Decodes the block edits of a single journal entry, which were encoded using
+/// the given layout version.
+fn decode_journal_blocks<T>(
+    mut blocks: &[u8],
+    layout: u32,
+    migrations: &ChunkMigrations,
+) -> Result<Vec<(IVec3, T)>, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let mut chunk_edits = vec![];
+    while !blocks.is_empty() {
+        let index = u16::from_le_bytes(take_bytes(&mut blocks, 2)?.try_into().unwrap());
+        let block = migrations.decode_block::<T>(&mut blocks, layout)?;
+        chunk_edits.push((unpack_local_pos(index), block));
+    }
+
+    Ok(chunk_edits)
+}
+
+/// The outcome of compacting a write-ahead journal using
+/// [`compact_journal`].
+#[derive(Debug, Default)]
+pub struct CompactionReport {
+    /// The number of chunks that were updated within the chunk store.
+    pub compacted: usize,
+
+    /// The chunks that could not be updated within the chunk store, along with
+    /// the error that occurred for each. The journal entries of these chunks
+    /// are kept within the journal.
+    pub failed: Vec<(IVec3, PersistenceError)>,
+}
+
+/// Replays all edits within the given journal into the chunk store, and then
+/// removes the replayed edits from the journal.
+///
+/// Chunks are compacted one at a time. Chunks that fail to compact, such as
+/// chunks whose saved record is damaged, are listed within the returned report
+/// and their edits are kept within the journal. An error is only returned if
+/// the journal itself could not be read or rewritten.
+pub fn compact_journal<T>(
+    journal: &dyn JournalBackend,
+    store: &dyn ChunkStore,
+) -> Result<CompactionReport, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    compact_journal_with_migrations::<T>(journal, store, &ChunkMigrations::default())
+}
+
+/// Replays all edits within the given journal into the chunk store, upgrading
+/// saved chunks that use an older block layout using the given migrations, and
+/// then removes the replayed edits from the journal.
+///
+/// If the end of the journal is damaged, only the valid entries before it are
+/// replayed, and the rest of the journal is discarded. Chunks with entries
+/// that cannot be decoded are listed as failed, and their entries are kept.
+fn compact_journal_with_migrations<T>(
+    journal: &dyn JournalBackend,
+    store: &dyn ChunkStore,
+    migrations: &ChunkMigrations,
+) -> Result<CompactionReport, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let decoded = decode_journal::<T>(&journal.read_all()?, migrations);
+
+    let mut report = CompactionReport::default();
+    let mut retained = vec![];
+
+    for (chunk_coords, (entries, err)) in decoded.undecodable {
+        retained.extend_from_slice(&entries);
+        report.failed.push((chunk_coords, err));
+    }
+
+    for (chunk_coords, chunk_edits) in decoded.edits {
+        match compact_chunk(store, migrations, chunk_coords, &chunk_edits) {
+            Ok(()) => report.compacted += 1,
+            Err(err) => {
+                if let Some(entry) = encode_entry(chunk_coords, chunk_edits.into_iter()) {
+                    retained.extend_from_slice(&entry);
+                }
+                report.failed.push((chunk_coords, err));
+            },
+        }
+    }
+
+    journal.replace(&retained)?;
+    Ok(report)
+}
+
+/// Applies the given block edits to a single chunk within the chunk store.
+fn compact_chunk<T>(
+    store: &dyn ChunkStore,
+    migrations: &ChunkMigrations,
+    chunk_coords: IVec3,
+    chunk_edits: &[(IVec3, T)],
+) -> Result<(), PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let mut storage = match store.read_chunk(chunk_coords)? {
+        Some(record) => migrations.decode_chunk::<T>(&record)?,
+        None => VoxelStorage::default(),
+    };
+
+    for (local_pos, block) in chunk_edits {
+        storage.set_block(*local_pos, *block);
+    }
+
+    store.write_chunk(chunk_coords, &encode_chunk(&storage))
+}
+
+/// This system replays the journals of all newly journaled worlds into their
+/// chunk stores, recovering any edits that were lost in a crash.
+///
+/// A [`ChunkSaveFailed`] event is sent for each chunk that could not be
+/// recovered. The edits of those chunks stay within the journal.
+pub(crate) fn recover_journals<T>(
+    worlds: Query<(Entity, &PersistentWorld, &WriteAheadJournal), Added<WriteAheadJournal>>,
+    mut failed: EventWriter<ChunkSaveFailed>,
+) where
+    T: PersistentBlock,
+{
+    for (world_id, persistent, journal) in worlds.iter() {
+        let (backend, store) = (journal.backend().as_ref(), persistent.store().as_ref());
+        match compact_journal_with_migrations::<T>(backend, store, persistent.migrations()) {
+            Ok(report) => {
+                if report.compacted > 0 {
+                    info!(
+                        "Recovered {} chunks from the write-ahead journal",
+                        report.compacted
+                    );
+                }
+                send_compaction_failures(world_id, report, &mut failed);
+            },
+            Err(err) => error!("Failed to recover the write-ahead journal: {}", err),
+        }
+    }
+}
+
+/// Sends a [`ChunkSaveFailed`] event for each chunk that failed to compact
+/// within the given report.
+fn send_compaction_failures(
+    world_id: Entity,
+    report: CompactionReport,
+    failed: &mut EventWriter<ChunkSaveFailed>,
+) {
+    for (chunk_coords, err) in report.failed {
+        failed.send(ChunkSaveFailed {
+            world_id,
+            chunk_coords,
+            message: err.to_string(),
+        });
+    }
+}
+
+/// This system appends the blocks that were modified this frame within all
+/// journaled worlds to their journals.
+///
+/// Chunks that were just spawned, generated, or loaded from their chunk store
+/// are not journaled. Only the blocks that are set after that are tracked by
+/// their storage and journaled.
+///
+/// The chunk store does not contain spawned or generated chunks, so the first
+/// edit of such a chunk journals its full storage instead of only the edited
+/// blocks. The same happens when the storage of a chunk is replaced as a whole.
+/// If appending to the journal fails, the full storage of the affected chunks
+/// is journaled again with their next edit.
+pub(crate) fn journal_block_edits<T>(
+    worlds: Query<&WriteAheadJournal>,
+    mut chunks: Query<
+        (
+            Entity,
+            &VoxelChunk,
+            &mut VoxelStorage<T>,
+            Option<&mut JournalState<T>>,
+            Has<ChunkLoadedFromStore>,
+        ),
+        Changed<VoxelStorage<T>>,
+    >,
+    mut commands: Commands,
+) where
+    T: PersistentBlock,
+{
+    let mut entries: HashMap<Entity, (Vec<u8>, Vec<Entity>)> = HashMap::new();
+
+    for (chunk_id, chunk_meta, mut storage, state, loaded) in chunks.iter_mut() {
+        if !worlds.contains(chunk_meta.world_id()) {
+            continue;
+        }
+
+        let edited = storage.bypass_change_detection().take_edited_blocks();
+        let Some(mut state) = state.filter(|_| !storage.is_added() && !loaded) else {
+            commands.entity(chunk_id).insert(JournalState::<T>::new(loaded));
+            continue;
+        };
+
+        let chunk_coords = chunk_meta.chunk_coords();
+        let entry = match edited {
+            Some(edited) if edited.is_empty() => None,
+            Some(edited) if state.persisted => {
+                encode_journal_entry(chunk_coords, &storage, &edited)
+            },
+            _ => Some(encode_full_journal_entry(chunk_coords, &storage)),
+        };
+
+        if let Some(entry) = entry {
+            let (bytes, chunk_ids) = entries.entry(chunk_meta.world_id()).or_default();
+            bytes.extend_from_slice(&entry);
+            chunk_ids.push(chunk_id);
+            state.persisted = true;
+        }
+    }
+
+    for (world_id, (bytes, chunk_ids)) in entries {
+        let journal = worlds.get(world_id).unwrap();
+        if let Err(err) = journal.backend().append(&bytes) {
+            error!("Failed to append to the write-ahead journal: {}", err);
+
+            // The state is updated in place rather than through commands, so
+            // that a compaction later within this frame does not mark these
+            // chunks as clean.
+            for chunk_id in chunk_ids {
+                chunks.get_mut(chunk_id).unwrap().3.unwrap().persisted = false;
+            }
+        }
+    }
+}
+
+/// This system periodically compacts the journals of all journaled worlds
+/// into their chunk stores.
+///
+/// Dirty chunks whose full storage has been written to the chunk store are
+/// marked as clean. Chunks whose full storage is not within the journal, such
+/// as chunks that were edited before the journal was added, stay dirty so that
+/// they are still saved normally. Chunks that fail to compact also stay dirty,
+/// and a [`ChunkSaveFailed`] event is sent for each of them.
+pub(crate) fn compact_journals<T>(
+    time: Res<Time>,
+    settings: Res<PersistenceSettings>,
+    worlds: Query<(Entity, &PersistentWorld, &WriteAheadJournal)>,
+    chunks: Query<(Entity, &VoxelChunk, &JournalState<T>), With<DirtyChunk>>,
+    mut failed: EventWriter<ChunkSaveFailed>,
+    mut last_compaction: Local<f32>,
+    mut commands: Commands,
+) where
+    T: PersistentBlock,
+{
+    let now = time.elapsed_seconds();
+    if now - *last_compaction < settings.journal_compact_interval {
+        return;
+    }
+    *last_compaction = now;
+
+    for (world_id, persistent, journal) in worlds.iter() {
+        let (backend, store) = (journal.backend().as_ref(), persistent.store().as_ref());
+        let report =
+            match compact_journal_with_migrations::<T>(backend, store, persistent.migrations()) {
+                Ok(report) => report,
+                Err(err) => {
+                    error!("Failed to compact the write-ahead journal: {}", err);
+                    continue;
+                },
+            };
+
+        for (chunk_id, chunk_meta, state) in chunks.iter() {
+            let chunk_coords = chunk_meta.chunk_coords();
+            if chunk_meta.world_id() == world_id
+                && state.persisted
+                && !report
+                    .failed
+                    .iter()
+                    .any(|(coords, _)| *coords == chunk_coords)
+            {
+                commands.entity(chunk_id).remove::<DirtyChunk>();
+            }
+        }
+
+        send_compaction_failures(world_id, report, &mut failed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::persistence::{decode_chunk, MemoryChunkStore, PersistencePlugin};
+    use crate::prelude::*;
+
+    #[test]
+    fn replay_journal_after_crash() {
+        let journal = MemoryJournal::default();
+        let store = MemoryChunkStore::default();
+
+        let mut current = VoxelStorage::<u8>::default();
+        current.set_block(IVec3::new(1, 2, 3), 4);
+        current.set_block(IVec3::new(15, 0, 9), 5);
+
+        let edited = [IVec3::new(1, 2, 3), IVec3::new(15, 0, 9)];
+        let entry = encode_journal_entry(IVec3::NEG_ONE, &current, &edited).unwrap();
+        journal.append(&entry).unwrap();
+
+        current.set_block(IVec3::new(1, 2, 3), 6);
+        let entry = encode_journal_entry(IVec3::NEG_ONE, &current, &edited[.. 1]).unwrap();
+        journal.append(&entry[.. entry.len() - 1]).unwrap();
+
+        let report = compact_journal::<u8>(&journal, &store).unwrap();
+        assert_eq!(report.compacted, 1);
+        assert!(report.failed.is_empty());
+        assert!(journal.read_all().unwrap().is_empty());
+
+        let record = store.read_chunk(IVec3::NEG_ONE).unwrap().unwrap();
+        let saved = decode_chunk::<u8>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(1, 2, 3)), 4);
+        assert_eq!(saved.get_block(IVec3::new(15, 0, 9)), 5);
+    }
+
+    #[test]
+    fn replay_valid_prefix_of_damaged_journal() {
+        let journal = MemoryJournal::default();
+        let store = MemoryChunkStore::default();
+
+        let mut current = VoxelStorage::<u8>::default();
+        current.set_block(IVec3::new(2, 2, 2), 3);
+        let entry = encode_journal_entry(IVec3::ZERO, &current, &[IVec3::new(2, 2, 2)]).unwrap();
+        journal.append(&entry).unwrap();
+
+        // A damaged entry whose checksum matches, but whose payload ends in the
+        // middle of its chunk coordinates.
+        let payload = [1, 0, 0, 0];
+        let mut hasher = StableHasher::new();
+        hasher.write(&payload);
+        let mut damaged = vec![];
+        damaged.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        damaged.extend_from_slice(&hasher.finish().to_le_bytes());
+        damaged.extend_from_slice(&payload);
+        journal.append(&damaged).unwrap();
+        journal.append(&entry[.. 7]).unwrap();
+
+        assert_eq!(
+            compact_journal::<u8>(&journal, &store).unwrap().compacted,
+            1
+        );
+        assert!(journal.read_all().unwrap().is_empty());
+
+        let record = store.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let saved = decode_chunk::<u8>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(2, 2, 2)), 3);
+    }
+
+    #[test]
+    fn keep_journal_entries_of_chunks_that_fail_to_compact() {
+        let journal = MemoryJournal::default();
+        let store = MemoryChunkStore::default();
+        store.write_chunk(IVec3::X, b"garbage").unwrap();
+
+        let mut current = VoxelStorage::<u8>::default();
+        current.set_block(IVec3::new(1, 1, 1), 2);
+        let entry = encode_journal_entry(IVec3::X, &current, &[IVec3::new(1, 1, 1)]).unwrap();
+        journal.append(&entry).unwrap();
+
+        let mut healthy = VoxelStorage::<u8>::default();
+        healthy.set_block(IVec3::new(3, 3, 3), 4);
+        let entry = encode_journal_entry(IVec3::ZERO, &healthy, &[IVec3::new(3, 3, 3)]).unwrap();
+        journal.append(&entry).unwrap();
+
+        let report = compact_journal::<u8>(&journal, &store).unwrap();
+        assert_eq!(report.compacted, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, IVec3::X);
+
+        let record = store.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let saved = decode_chunk::<u8>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(3, 3, 3)), 4);
+        assert_eq!(store.read_chunk(IVec3::X).unwrap().unwrap(), b"garbage");
+
+        let edits = decode_journal::<u8>(&journal.read_all().unwrap(), &default()).edits;
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits.get(&IVec3::X), Some(&vec![(IVec3::new(1, 1, 1), 2)]));
+    }
+
+    /// A journal backend that fails to append while `failing` is set.
+    #[derive(Default)]
+    struct FlakyJournal {
+        inner:   MemoryJournal,
+        failing: AtomicBool,
+    }
+
+    impl JournalBackend for FlakyJournal {
+        fn append(&self, bytes: &[u8]) -> Result<(), PersistenceError> {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(io::Error::from(io::ErrorKind::WriteZero).into());
+            }
+            self.inner.append(bytes)
+        }
+
+        fn read_all(&self) -> Result<Vec<u8>, PersistenceError> {
+            self.inner.read_all()
+        }
+
+        fn clear(&self) -> Result<(), PersistenceError> {
+            self.inner.clear()
+        }
+    }
+
+    #[test]
+    fn keep_chunks_dirty_when_journal_append_fails() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(PersistenceSettings {
+            journal_compact_interval: f32::MAX,
+            ..default()
+        });
+
+        let journal = Arc::new(FlakyJournal::default());
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                PersistentWorld::new(MemoryChunkStore::default()),
+                WriteAheadJournal::from_shared(journal.clone()),
+            ))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                commands
+                    .get_world(world_id)
+                    .unwrap()
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::filled(1))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelStorage<u8>>>()
+            .single(&app.world);
+        let edit = |app: &mut App, local_pos: IVec3, block: u8| {
+            app.world
+                .get_mut::<VoxelStorage<u8>>(chunk_id)
+                .unwrap()
+                .set_block(local_pos, block);
+            app.update();
+        };
+
+        edit(&mut app, IVec3::new(1, 2, 3), 7);
+        assert!(app.world.entity(chunk_id).contains::<DirtyChunk>());
+
+        // The next edit fails to be journaled, while the journal is compacted
+        // within the same frame.
+        journal.failing.store(true, Ordering::Relaxed);
+        app.world
+            .resource_mut::<PersistenceSettings>()
+            .journal_compact_interval = 0.0;
+        edit(&mut app, IVec3::new(4, 5, 6), 8);
+        assert!(app.world.entity(chunk_id).contains::<DirtyChunk>());
+        app.update();
+        assert!(app.world.entity(chunk_id).contains::<DirtyChunk>());
+
+        // Once appending works again, the next edit journals the full chunk.
+        journal.failing.store(false, Ordering::Relaxed);
+        edit(&mut app, IVec3::new(7, 8, 9), 9);
+        app.update();
+        assert!(!app.world.entity(chunk_id).contains::<DirtyChunk>());
+
+        let store = app
+            .world
+            .get::<PersistentWorld>(world_id)
+            .unwrap()
+            .store()
+            .clone();
+        let record = store.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let saved = decode_chunk::<u8>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(1, 2, 3)), 7);
+        assert_eq!(saved.get_block(IVec3::new(4, 5, 6)), 8);
+        assert_eq!(saved.get_block(IVec3::new(7, 8, 9)), 9);
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    struct Block(u16);
+
+    impl PersistentBlock for Block {
+        const LAYOUT_VERSION: u32 = 1;
+
+        fn encode_block(&self, out: &mut Vec<u8>) {
+            self.0.encode_block(out);
+        }
+
+        fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+            Ok(Block(u16::decode_block(input)?))
+        }
+    }
+
+    #[test]
+    fn migrate_journal_after_layout_change() {
+        let journal = MemoryJournal::default();
+        let store = MemoryChunkStore::default();
+
+        let mut current = VoxelStorage::<u8>::default();
+        current.set_block(IVec3::new(4, 5, 6), 9);
+        let entry = encode_journal_entry(IVec3::ZERO, &current, &[IVec3::new(4, 5, 6)]).unwrap();
+        journal.append(&entry).unwrap();
+
+        let mut healthy = VoxelStorage::<Block>::default();
+        healthy.set_block(IVec3::new(1, 1, 1), Block(300));
+        let entry = encode_journal_entry(IVec3::X, &healthy, &[IVec3::new(1, 1, 1)]).unwrap();
+        journal.append(&entry).unwrap();
+
+        let decoded = decode_journal::<Block>(&journal.read_all().unwrap(), &default());
+        assert_eq!(decoded.edits.len(), 1);
+        assert!(decoded.undecodable.contains_key(&IVec3::ZERO));
+
+        // Without a migration, the old entry is kept while the entry after it
+        // is still compacted.
+        let report = compact_journal::<Block>(&journal, &store).unwrap();
+        assert_eq!(report.compacted, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert!(matches!(
+            report.failed[0],
+            (IVec3::ZERO, PersistenceError::MissingMigration(0))
+        ));
+        assert!(store.read_chunk(IVec3::ZERO).unwrap().is_none());
+
+        let record = store.read_chunk(IVec3::X).unwrap().unwrap();
+        let saved = decode_chunk::<Block>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(1, 1, 1)), Block(300));
+
+        let migrations =
+            ChunkMigrations::default().with_migration(0, |id: u8| Block(id as u16 + 100));
+        let report =
+            compact_journal_with_migrations::<Block>(&journal, &store, &migrations).unwrap();
+        assert_eq!(report.compacted, 1);
+        assert!(report.failed.is_empty());
+        assert!(journal.read_all().unwrap().is_empty());
+
+        let record = store.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let saved = decode_chunk::<Block>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(4, 5, 6)), Block(109));
+    }
+
+    #[test]
+    fn only_journal_edits_after_chunk_spawns() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>();
+
+        let journal = Arc::new(MemoryJournal::default());
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                PersistentWorld::new(MemoryChunkStore::default()),
+                WriteAheadJournal::from_shared(journal.clone()),
+            ))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                commands
+                    .get_world(world_id)
+                    .unwrap()
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::filled(1))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+        assert!(journal.read_all().unwrap().is_empty());
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelStorage<u8>>>()
+            .single(&app.world);
+        app.world
+            .get_mut::<VoxelStorage<u8>>(chunk_id)
+            .unwrap()
+            .set_block(IVec3::new(1, 2, 3), 7);
+        app.update();
+
+        // The spawned chunk is not within the chunk store, so its first edit
+        // journals the full chunk.
+        let edits = decode_journal::<u8>(&journal.read_all().unwrap(), &default()).edits;
+        let chunk_edits = edits.get(&IVec3::ZERO).unwrap();
+        assert_eq!(chunk_edits.len(), 4096);
+        assert!(chunk_edits.contains(&(IVec3::new(1, 2, 3), 7)));
+        assert!(chunk_edits.contains(&(IVec3::new(0, 0, 0), 1)));
+    }
+
+    #[test]
+    fn compact_generated_chunk_that_was_never_saved() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(PersistenceSettings {
+            journal_compact_interval: 0.0,
+            ..default()
+        });
+
+        let journal = Arc::new(MemoryJournal::default());
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                PersistentWorld::new(MemoryChunkStore::default()),
+                WriteAheadJournal::from_shared(journal.clone()),
+            ))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                commands
+                    .get_world(world_id)
+                    .unwrap()
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::filled(1))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelStorage<u8>>>()
+            .single(&app.world);
+        let edit = |app: &mut App, local_pos: IVec3, block: u8| {
+            app.world
+                .get_mut::<VoxelStorage<u8>>(chunk_id)
+                .unwrap()
+                .set_block(local_pos, block);
+            app.update();
+        };
+
+        // Chunks are only marked as dirty at the end of the frame, so they are
+        // marked as clean by the compaction in the next frame.
+        edit(&mut app, IVec3::new(1, 2, 3), 7);
+        assert!(app.world.entity(chunk_id).contains::<DirtyChunk>());
+        app.update();
+        assert!(!app.world.entity(chunk_id).contains::<DirtyChunk>());
+
+        let store = app
+            .world
+            .get::<PersistentWorld>(world_id)
+            .unwrap()
+            .store()
+            .clone();
+        let record = store.read_chunk(IVec3::ZERO).unwrap().unwrap();
+        let saved = decode_chunk::<u8>(&record).unwrap();
+        assert_eq!(saved.get_block(IVec3::new(1, 2, 3)), 7);
+        assert_eq!(saved.get_block(IVec3::new(0, 0, 0)), 1);
+        assert_eq!(saved.get_block(IVec3::new(15, 15, 15)), 1);
+
+        // Once the full chunk is within the chunk store, only edits are
+        // journaled.
+        app.world
+            .resource_mut::<PersistenceSettings>()
+            .journal_compact_interval = f32::MAX;
+        edit(&mut app, IVec3::new(4, 5, 6), 8);
+        let edits = decode_journal::<u8>(&journal.read_all().unwrap(), &default()).edits;
+        assert_eq!(
+            edits.get(&IVec3::ZERO),
+            Some(&vec![(IVec3::new(4, 5, 6), 8)])
+        );
+    }
+}
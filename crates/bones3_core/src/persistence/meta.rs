@@ -0,0 +1,285 @@
+//! World-level metadata, such as the world seed and spawn point, that is saved
+//! alongside the chunks of a persistent world.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::persistence::codec::take_bytes;
+use crate::persistence::PersistenceError;
+
+/// The bytes that every world metadata record starts with.
+const META_MAGIC: &[u8; 4] = b"B3WM";
+
+/// The current version of the world metadata record format.
+const META_VERSION: u8 = 1;
+
+/// A custom value that is stored within the metadata of a world.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MetaValue {
+    /// A string value.
+    String(String),
+
+    /// A raw byte value.
+    Bytes(Vec<u8>),
+}
+
+impl MetaValue {
+    /// Gets this value as a string, or `None` if it is not a string value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MetaValue::String(value) => Some(value),
+            MetaValue::Bytes(_) => None,
+        }
+    }
+
+    /// Gets this value as raw bytes, or `None` if it is not a byte value.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            MetaValue::String(_) => None,
+            MetaValue::Bytes(value) => Some(value),
+        }
+    }
+}
+
+/// A component that holds the metadata of a persistent voxel world, such as
+/// its seed, its spawn point, and any custom values that a game needs to
+/// remember about the world.
+///
+/// When a [`PersistentWorld`](crate::persistence::PersistentWorld) is added to
+/// a world that does not have a metadata component yet, the metadata that was
+/// saved within its chunk store is loaded and inserted automatically. Every
+/// change to this component is saved back into the chunk store at the end of
+/// the frame.
+#[derive(Debug, Default, Component, Clone, PartialEq)]
+pub struct WorldMeta {
+    /// The seed that was used to generate the world.
+    seed: Option<u64>,
+
+    /// The position at which players spawn within the world.
+    spawn_point: Option<Vec3>,
+
+    /// The custom values of the world, by key.
+    custom: BTreeMap<String, MetaValue>,
+}
+
+impl WorldMeta {
+    /// Gets the seed of the world, if one has been set.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Sets the seed of the world.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Gets the spawn point of the world, if one has been set.
+    pub fn spawn_point(&self) -> Option<Vec3> {
+        self.spawn_point
+    }
+
+    /// Sets the spawn point of the world.
+    pub fn set_spawn_point(&mut self, spawn_point: Option<Vec3>) {
+        self.spawn_point = spawn_point;
+    }
+
+    /// Gets the custom value with the given key.
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.custom.get(key)
+    }
+
+    /// Gets the custom string value with the given key, or `None` if there is
+    /// no value with that key, or if it is not a string value.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(MetaValue::as_str)
+    }
+
+    /// Gets the custom byte value with the given key, or `None` if there is no
+    /// value with that key, or if it is not a byte value.
+    pub fn get_bytes(&self, key: &str) -> Option<&[u8]> {
+        self.get(key).and_then(MetaValue::as_bytes)
+    }
+
+    /// Sets the custom value with the given key, returning the value that was
+    /// previously stored with that key.
+    pub fn insert(&mut self, key: impl Into<String>, value: MetaValue) -> Option<MetaValue> {
+        self.custom.insert(key.into(), value)
+    }
+
+    /// Sets the custom string value with the given key.
+    pub fn insert_str(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.insert(key, MetaValue::String(value.into()));
+    }
+
+    /// Sets the custom byte value with the given key.
+    pub fn insert_bytes(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) {
+        self.insert(key, MetaValue::Bytes(value.into()));
+    }
+
+    /// Removes the custom value with the given key, returning it.
+    pub fn remove(&mut self, key: &str) -> Option<MetaValue> {
+        self.custom.remove(key)
+    }
+
+    /// Gets an iterator over all custom values, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MetaValue)> {
+        self.custom.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Encodes this metadata into a world metadata record.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        out.extend_from_slice(META_MAGIC);
+        out.push(META_VERSION);
+
+        match self.seed {
+            Some(seed) => {
+                out.push(1);
+                out.extend_from_slice(&seed.to_le_bytes());
+            },
+            None => out.push(0),
+        }
+
+        match self.spawn_point {
+            Some(spawn_point) => {
+                out.push(1);
+                for value in spawn_point.to_array() {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            },
+            None => out.push(0),
+        }
+
+        out.extend_from_slice(&(self.custom.len() as u32).to_le_bytes());
+        for (key, value) in self.custom.iter() {
+            write_bytes(&mut out, key.as_bytes());
+            match value {
+                MetaValue::String(value) => {
+                    out.push(0);
+                    write_bytes(&mut out, value.as_bytes());
+                },
+                MetaValue::Bytes(value) => {
+                    out.push(1);
+                    write_bytes(&mut out, value);
+                },
+            }
+        }
+
+        out
+    }
+
+    /// Decodes a world metadata record that was created using
+    /// [`WorldMeta::encode`].
+    pub fn decode(mut input: &[u8]) -> Result<Self, PersistenceError> {
+        let input = &mut input;
+        if take_bytes(input, 4)? != META_MAGIC {
+            return Err(PersistenceError::InvalidHeader);
+        }
+
+        match take_bytes(input, 1)?[0] {
+            META_VERSION => {},
+            version => return Err(PersistenceError::UnsupportedVersion(version)),
+        }
+
+        let mut meta = WorldMeta::default();
+        if read_flag(input)? {
+            meta.seed = Some(u64::from_le_bytes(
+                take_bytes(input, 8)?.try_into().unwrap(),
+            ));
+        }
+
+        if read_flag(input)? {
+            let mut spawn_point = [0.0; 3];
+            for value in spawn_point.iter_mut() {
+                *value = f32::from_le_bytes(take_bytes(input, 4)?.try_into().unwrap());
+            }
+            meta.spawn_point = Some(Vec3::from_array(spawn_point));
+        }
+
+        let count = read_len(input)?;
+        for _ in 0 .. count {
+            let key = read_string(input)?;
+            let value = match take_bytes(input, 1)?[0] {
+                0 => MetaValue::String(read_string(input)?),
+                1 => MetaValue::Bytes(read_bytes(input)?.to_vec()),
+                _ => return Err(PersistenceError::InvalidMetaValue),
+            };
+            meta.custom.insert(key, value);
+        }
+
+        if !input.is_empty() {
+            return Err(PersistenceError::InvalidMetaValue);
+        }
+
+        Ok(meta)
+    }
+}
+
+/// Writes a length prefixed byte string to a world metadata record.
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Reads a length prefix from a world metadata record.
+fn read_len(input: &mut &[u8]) -> Result<usize, PersistenceError> {
+    Ok(u32::from_le_bytes(take_bytes(input, 4)?.try_into().unwrap()) as usize)
+}
+
+/// Reads a flag byte, that marks whether an optional value is present, from a
+/// world metadata record.
+fn read_flag(input: &mut &[u8]) -> Result<bool, PersistenceError> {
+    match take_bytes(input, 1)?[0] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(PersistenceError::InvalidMetaValue),
+    }
+}
+
+/// Reads a length prefixed byte string from a world metadata record.
+fn read_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], PersistenceError> {
+    let len = read_len(input)?;
+    take_bytes(input, len)
+}
+
+/// Reads a length prefixed UTF-8 string from a world metadata record.
+fn read_string(input: &mut &[u8]) -> Result<String, PersistenceError> {
+    let bytes = read_bytes(input)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| PersistenceError::InvalidMetaValue)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn world_meta_round_trip() {
+        let mut meta = WorldMeta::default();
+        meta.set_seed(Some(0xDEAD_BEEF_1234));
+        meta.set_spawn_point(Some(Vec3::new(8.5, 64.0, -3.25)));
+        meta.insert_str("name", "Overworld");
+        meta.insert_bytes("rules", vec![1, 2, 3]);
+
+        let record = meta.encode();
+        let decoded = WorldMeta::decode(&record).unwrap();
+        assert_eq!(decoded, meta);
+        assert_eq!(decoded.get_str("name"), Some("Overworld"));
+        assert_eq!(decoded.get_bytes("rules"), Some(&[1, 2, 3][..]));
+        assert_eq!(decoded.get_str("rules"), None);
+
+        let empty = WorldMeta::decode(&WorldMeta::default().encode()).unwrap();
+        assert_eq!(empty, WorldMeta::default());
+
+        assert!(matches!(
+            WorldMeta::decode(&record[.. record.len() - 1]),
+            Err(PersistenceError::Truncated)
+        ));
+        assert!(matches!(
+            WorldMeta::decode(b"B3CK\x01"),
+            Err(PersistenceError::InvalidHeader)
+        ));
+    }
+}
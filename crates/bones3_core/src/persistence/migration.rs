@@ -0,0 +1,266 @@
+//! Migrating chunk records between block layout versions.
+
+use std::sync::Arc;
+
+use bevy::utils::HashMap;
+
+use crate::persistence::codec::{decode_runs, read_header, take_bytes, write_header};
+use crate::persistence::{PersistenceError, PersistentBlock};
+use crate::storage::VoxelStorage;
+
+/// A single migration step, which reads one block in the previous layout from
+/// the start of the input buffer and writes it in the next layout.
+type MigrationStep =
+    Arc<dyn Fn(&mut &[u8], &mut Vec<u8>) -> Result<(), PersistenceError> + Send + Sync>;
+
+/// A set of migrations that upgrade chunk records from older block layout
+/// versions to newer ones while they are loaded.
+///
+/// Each migration upgrades records from one layout version to the next, by
+/// decoding every block using the block data type of the old layout and
+/// converting it into the block data type of the new layout. Migrations are
+/// chained, so records that are several versions behind are upgraded one
+/// version at a time. Types that describe old layouts only need to implement
+/// [`PersistentBlock`], and may be kept around purely for migrations.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::persistence::*;
+/// # type LegacyBlock = u8;
+/// # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+/// # struct BlockState(u16);
+/// # impl BlockState {
+/// #     fn from_legacy(old: LegacyBlock) -> Self {
+/// #         Self(old as u16)
+/// #     }
+/// # }
+/// # impl PersistentBlock for BlockState {
+/// #     const LAYOUT_VERSION: u32 = 1;
+/// #     fn encode_block(&self, out: &mut Vec<u8>) {
+/// #         self.0.encode_block(out);
+/// #     }
+/// #     fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+/// #         u16::decode_block(input).map(Self)
+/// #     }
+/// # }
+/// # let store = MemoryChunkStore::default();
+/// let migrations = ChunkMigrations::default()
+///     .with_migration(0, |old: LegacyBlock| BlockState::from_legacy(old));
+/// let persistent = PersistentWorld::new(store).with_migrations(migrations);
+/// ```
+#[derive(Clone, Default)]
+pub struct ChunkMigrations {
+    /// The migration steps, by the layout version that they upgrade from.
+    steps: HashMap<u32, MigrationStep>,
+}
+
+impl ChunkMigrations {
+    /// Registers a migration that upgrades records from the given layout
+    /// version to the next version, by converting each block of type `A` into
+    /// a block of type `B`.
+    ///
+    /// Any existing migration for the same layout version is replaced.
+    pub fn add_migration<A, B, F>(&mut self, from_layout: u32, migrate: F) -> &mut Self
+    where
+        A: PersistentBlock,
+        B: PersistentBlock,
+        F: Fn(A) -> B + Send + Sync + 'static,
+    {
+        let step: MigrationStep = Arc::new(move |input, out| {
+            migrate(A::decode_block(input)?).encode_block(out);
+            Ok(())
+        });
+
+        self.steps.insert(from_layout, step);
+        self
+    }
+
+    /// Registers a migration that upgrades records from the given layout
+    /// version to the next version, by converting each block of type `A` into
+    /// a block of type `B`.
+    ///
+    /// This is the builder form of [`ChunkMigrations::add_migration`].
+    pub fn with_migration<A, B, F>(mut self, from_layout: u32, migrate: F) -> Self
+    where
+        A: PersistentBlock,
+        B: PersistentBlock,
+        F: Fn(A) -> B + Send + Sync + 'static,
+    {
+        self.add_migration(from_layout, migrate);
+        self
+    }
+
+    /// Checks whether any migrations have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Upgrades the given chunk record to the given layout version, returning
+    /// the upgraded record.
+    ///
+    /// Records that already use the target layout are returned unchanged.
+    /// Records that use a newer layout than the target, or that would require
+    /// a migration that has not been registered, return an error.
+    pub fn migrate_record(
+        &self,
+        mut record: &[u8],
+        target_layout: u32,
+    ) -> Result<Vec<u8>, PersistenceError> {
+        let original = record;
+        let mut layout = read_header(&mut record)?;
+        if layout == target_layout {
+            return Ok(original.to_vec());
+        }
+
+        if layout > target_layout {
+            return Err(PersistenceError::LayoutMismatch {
+                found:    layout,
+                expected: target_layout,
+            });
+        }
+
+        let mut runs = record.to_vec();
+        while layout < target_layout {
+            let step = self
+                .steps
+                .get(&layout)
+                .ok_or(PersistenceError::MissingMigration(layout))?;
+
+            let mut input = runs.as_slice();
+            let mut out = Vec::with_capacity(runs.len());
+            while !input.is_empty() {
+                out.extend_from_slice(take_bytes(&mut input, 2)?);
+                step(&mut input, &mut out)?;
+            }
+
+            runs = out;
+            layout += 1;
+        }
+
+        let mut out = Vec::with_capacity(runs.len() + 9);
+        write_header(&mut out, target_layout);
+        out.extend_from_slice(&runs);
+        Ok(out)
+    }
+
+    /// Decodes a single block from the start of the given buffer, which was
+    /// encoded using the given layout version, first upgrading it to the
+    /// current [`PersistentBlock::LAYOUT_VERSION`] of the block data type if
+    /// it uses an older layout.
+    pub(crate) fn decode_block<T>(
+        &self,
+        input: &mut &[u8],
+        layout: u32,
+    ) -> Result<T, PersistenceError>
+    where
+        T: PersistentBlock,
+    {
+        if layout == T::LAYOUT_VERSION {
+            return T::decode_block(input);
+        }
+
+        if layout > T::LAYOUT_VERSION {
+            return Err(PersistenceError::LayoutMismatch {
+                found:    layout,
+                expected: T::LAYOUT_VERSION,
+            });
+        }
+
+        let mut block = vec![];
+        for version in layout .. T::LAYOUT_VERSION {
+            let step = self
+                .steps
+                .get(&version)
+                .ok_or(PersistenceError::MissingMigration(version))?;
+
+            let mut out = vec![];
+            match version == layout {
+                true => step(input, &mut out)?,
+                false => step(&mut block.as_slice(), &mut out)?,
+            }
+            block = out;
+        }
+
+        T::decode_block(&mut block.as_slice())
+    }
+
+    /// Decodes the given chunk record, first upgrading it to the current
+    /// [`PersistentBlock::LAYOUT_VERSION`] of the block data type if it uses an
+    /// older layout.
+    pub fn decode_chunk<T>(&self, record: &[u8]) -> Result<VoxelStorage<T>, PersistenceError>
+    where
+        T: PersistentBlock,
+    {
+        let mut input = record;
+        if read_header(&mut input)? == T::LAYOUT_VERSION {
+            return decode_runs(input);
+        }
+
+        let migrated = self.migrate_record(record, T::LAYOUT_VERSION)?;
+        let mut input = migrated.as_slice();
+        read_header(&mut input)?;
+        decode_runs(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::persistence::{decode_chunk, encode_chunk};
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    struct Block(u16);
+
+    impl PersistentBlock for Block {
+        const LAYOUT_VERSION: u32 = 2;
+
+        fn encode_block(&self, out: &mut Vec<u8>) {
+            self.0.encode_block(out);
+        }
+
+        fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+            Ok(Block(u16::decode_block(input)?))
+        }
+    }
+
+    #[test]
+    fn migrate_old_layouts() {
+        let mut storage = VoxelStorage::<u8>::default();
+        storage.set_block(IVec3::new(1, 2, 3), 7);
+        let record = encode_chunk(&storage);
+
+        assert!(matches!(
+            decode_chunk::<Block>(&record),
+            Err(PersistenceError::LayoutMismatch {
+                found:    0,
+                expected: 2,
+            })
+        ));
+
+        // Layout 0 stored 8-bit ids, layout 1 widened them, and layout 2
+        // shifted all non-empty ids.
+        let mut migrations = ChunkMigrations::default().with_migration(0, |id: u8| id as u16);
+        assert!(matches!(
+            migrations.decode_chunk::<Block>(&record),
+            Err(PersistenceError::MissingMigration(1))
+        ));
+
+        migrations.add_migration(1, |id: u16| {
+            match id {
+                0 => Block(0),
+                id => Block(id + 100),
+            }
+        });
+
+        let migrated = migrations.decode_chunk::<Block>(&record).unwrap();
+        assert_eq!(migrated.get_block(IVec3::new(1, 2, 3)), Block(107));
+        assert_eq!(migrated.get_block(IVec3::ZERO), Block(0));
+
+        let current = encode_chunk(&migrated);
+        assert_eq!(migrations.migrate_record(&current, 2).unwrap(), current);
+        assert!(migrations.migrate_record(&current, 1).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Saving and loading voxel chunks to and from persistent storage.
+//!
+//! Chunks within a voxel world that has a [`PersistentWorld`] component are
+//! tracked for changes, and saved into the [`ChunkStore`] of that world after
+//! they have been modified. Worlds with a [`WriteAheadJournal`] additionally
+//! record every block edit as it happens, so that edits survive a crash. Block
+//! data is encoded using the [`PersistentBlock`] trait, so the storage format
+//! does not depend on the memory layout of the block data type, and records
+//! that were saved using an older block layout are upgraded while they are
+//! loaded using [`ChunkMigrations`]. World-level settings, such as the seed and
+//! spawn point, are saved alongside the chunks using a [`WorldMeta`]
+//! component.
+
+mod codec;
+mod convert;
+mod error;
+mod journal;
+mod meta;
+mod migration;
+mod plugin;
+mod store;
+
+pub use codec::*;
+pub use convert::*;
+pub use error::*;
+pub use journal::*;
+pub use meta::*;
+pub use migration::*;
+pub use plugin::*;
+pub use store::*;
@@ -0,0 +1,590 @@
+//! Tracking, saving, and flushing modified chunks.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::persistence::journal::{compact_journals, journal_block_edits, recover_journals};
+use crate::persistence::{
+    encode_chunk,
+    ChunkMigrations,
+    ChunkStore,
+    PersistenceError,
+    PersistentBlock,
+    WorldMeta,
+};
+use crate::storage::{VoxelChunk, VoxelStorage};
+use crate::util::sector::sector_chunks;
+use crate::Bones3CoreSet;
+
+/// This plugin saves modified chunks of the given block data type within all
+/// voxel worlds that have a [`PersistentWorld`] component.
+///
+/// Chunks are marked with a [`DirtyChunk`] component whenever their storage is
+/// modified, and are saved once they have been dirty for longer than the
+/// autosave delay within the [`PersistenceSettings`]. When the app exits, all
+/// remaining dirty chunks are flushed before the app closes.
+///
+/// Chunks are not saved automatically when they are despawned, so dirty chunks
+/// should be saved using [`flush_all`] or [`PersistentWorld::save_chunk`]
+/// before they are unloaded.
+///
+/// Worlds that also have a [`WriteAheadJournal`] append every block edit to
+/// their journal at the end of each frame, and compact the journal into their
+/// chunk store periodically.
+///
+/// The [`WorldMeta`] of each persistent world is loaded from its chunk store
+/// when the [`PersistentWorld`] component is added, and saved whenever it
+/// changes.
+#[derive(Default)]
+pub struct PersistencePlugin<T>
+where
+    T: PersistentBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for PersistencePlugin<T>
+where
+    T: PersistentBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.register_type::<DirtyChunk>()
+            .register_type::<ChunkLoadedFromStore>()
+            .register_type::<PersistenceSettings>()
+            .init_resource::<PersistenceSettings>()
+            .add_event::<ChunkSaveFailed>()
+            .add_systems(PreUpdate, load_world_meta)
+            .add_systems(
+                PostUpdate,
+                (
+                    mark_dirty_chunks::<T>,
+                    recover_journals::<T>,
+                    journal_block_edits::<T>,
+                    compact_journals::<T>,
+                    autosave_dirty_chunks::<T>,
+                    save_world_meta,
+                )
+                    .chain()
+                    .after(Bones3CoreSet::BlockUpdates),
+            )
+            .add_systems(Last, flush_on_exit::<T>);
+    }
+}
+
+/// The settings that control when modified chunks are saved.
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct PersistenceSettings {
+    /// The number of seconds that a chunk must be dirty for before it is
+    /// saved automatically. This allows multiple edits to the same chunk to be
+    /// saved together.
+    ///
+    /// Defaults to `5.0`.
+    pub autosave_delay: f32,
+
+    /// The maximum number of chunks that are saved automatically each frame.
+    ///
+    /// Defaults to `16`.
+    pub max_saves_per_frame: usize,
+
+    /// The maximum amount of time that may be spent flushing dirty chunks when
+    /// the app exits. Chunks that could not be saved within this time are
+    /// lost.
+    ///
+    /// Defaults to 10 seconds.
+    pub exit_flush_budget: Duration,
+
+    /// The number of seconds between compactions of write-ahead journals into
+    /// their chunk stores.
+    ///
+    /// Defaults to `30.0`.
+    pub journal_compact_interval: f32,
+}
+
+impl Default for PersistenceSettings {
+    fn default() -> Self {
+        Self {
+            autosave_delay:           5.0,
+            max_saves_per_frame:      16,
+            exit_flush_budget:        Duration::from_secs(10),
+            journal_compact_interval: 30.0,
+        }
+    }
+}
+
+/// A component that connects a voxel world to the [`ChunkStore`] that its
+/// chunks are saved into.
+///
+/// Chunks that were saved using an older block layout are upgraded using the
+/// [`ChunkMigrations`] of this world while they are loaded. Upgraded chunks are
+/// not written back into the chunk store until they are saved again.
+#[derive(Component, Clone)]
+pub struct PersistentWorld {
+    /// The chunk store of this world.
+    store: Arc<dyn ChunkStore>,
+
+    /// The migrations that are applied to chunks while they are loaded.
+    migrations: ChunkMigrations,
+}
+
+impl PersistentWorld {
+    /// Creates a new persistent world component that saves chunks into the
+    /// given chunk store.
+    pub fn new<S>(store: S) -> Self
+    where
+        S: ChunkStore,
+    {
+        Self {
+            store:      Arc::new(store),
+            migrations: ChunkMigrations::default(),
+        }
+    }
+
+    /// Creates a new persistent world component that saves chunks into the
+    /// given shared chunk store.
+    pub fn from_shared(store: Arc<dyn ChunkStore>) -> Self {
+        Self {
+            store,
+            migrations: ChunkMigrations::default(),
+        }
+    }
+
+    /// Sets the migrations that are applied to chunks while they are loaded.
+    pub fn with_migrations(mut self, migrations: ChunkMigrations) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
+    /// Gets the chunk store of this world.
+    pub fn store(&self) -> &Arc<dyn ChunkStore> {
+        &self.store
+    }
+
+    /// Gets the migrations that are applied to chunks while they are loaded.
+    pub fn migrations(&self) -> &ChunkMigrations {
+        &self.migrations
+    }
+
+    /// Encodes the given chunk storage and saves it into the chunk store.
+    pub fn save_chunk<T>(
+        &self,
+        chunk_coords: IVec3,
+        storage: &VoxelStorage<T>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: PersistentBlock,
+    {
+        self.store.write_chunk(chunk_coords, &encode_chunk(storage))
+    }
+
+    /// Loads the chunk at the given chunk coordinates from the chunk store, or
+    /// returns `None` if that chunk has not been saved.
+    ///
+    /// Chunks that were saved using an older block layout are upgraded using
+    /// the migrations of this world.
+    pub fn load_chunk<T>(
+        &self,
+        chunk_coords: IVec3,
+    ) -> Result<Option<VoxelStorage<T>>, PersistenceError>
+    where
+        T: PersistentBlock,
+    {
+        match self.store.read_chunk(chunk_coords)? {
+            Some(record) => Ok(Some(self.migrations.decode_chunk(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Encodes the given world metadata and saves it into the chunk store.
+    pub fn save_meta(&self, meta: &WorldMeta) -> Result<(), PersistenceError> {
+        self.store.write_meta(&meta.encode())
+    }
+
+    /// Loads the world metadata from the chunk store, or returns `None` if no
+    /// metadata has been saved.
+    pub fn load_meta(&self) -> Result<Option<WorldMeta>, PersistenceError> {
+        match self.store.read_meta()? {
+            Some(record) => Ok(Some(WorldMeta::decode(&record)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A marker component for chunks within a persistent world that have been
+/// modified since they were last saved.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct DirtyChunk {
+    /// The elapsed app time, in seconds, at which the chunk was first modified
+    /// after it was last saved.
+    pub since: f32,
+}
+
+/// A marker component for chunks whose storage was just loaded from the chunk
+/// store of their world.
+///
+/// Chunk loaders should insert this component along with the loaded storage,
+/// so that loading the chunk does not mark it as dirty and save the same data
+/// back into the chunk store. It is removed once the storage change has been
+/// seen.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct ChunkLoadedFromStore;
+
+/// An event that is sent whenever a dirty chunk could not be saved.
+///
+/// The chunk stays dirty, so saving it is attempted again later.
+#[derive(Debug, Event, Clone, PartialEq, Eq)]
+pub struct ChunkSaveFailed {
+    /// The id of the world the chunk is in.
+    pub world_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub chunk_coords: IVec3,
+
+    /// A description of the error that occurred.
+    pub message: String,
+}
+
+/// The outcome of flushing dirty chunks using [`flush_all`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FlushReport {
+    /// The number of chunks that were saved.
+    pub saved: usize,
+
+    /// The number of chunks that failed to save.
+    pub failed: usize,
+
+    /// The number of chunks that were not saved because the time budget ran
+    /// out.
+    pub remaining: usize,
+}
+
+/// Synchronously saves all dirty chunks of the given block data type within
+/// all persistent worlds, until the given time budget runs out.
+///
+/// A [`ChunkSaveFailed`] event is sent for each chunk that failed to save.
+/// Chunks that failed, or that were skipped because the budget ran out, stay
+/// dirty.
+pub fn flush_all<T>(world: &mut World, budget: Duration) -> FlushReport
+where
+    T: PersistentBlock,
+{
+    let start = Instant::now();
+    let mut report = FlushReport::default();
+
+    let dirty: Vec<_> = world
+        .query_filtered::<(Entity, &VoxelChunk), (With<DirtyChunk>, With<VoxelStorage<T>>)>()
+        .iter(world)
+        .map(|(chunk_id, meta)| (chunk_id, meta.world_id(), meta.chunk_coords()))
+        .collect();
+
+    for (chunk_id, world_id, chunk_coords) in dirty {
+        if start.elapsed() > budget {
+            report.remaining += 1;
+            continue;
+        }
+
+        let Some(persistent) = world.get::<PersistentWorld>(world_id) else {
+            continue;
+        };
+
+        let storage = world.get::<VoxelStorage<T>>(chunk_id).unwrap();
+        match persistent.save_chunk(chunk_coords, storage) {
+            Ok(()) => {
+                world.entity_mut(chunk_id).remove::<DirtyChunk>();
+                report.saved += 1;
+            },
+            Err(err) => {
+                world.send_event(ChunkSaveFailed {
+                    world_id,
+                    chunk_coords,
+                    message: err.to_string(),
+                });
+                report.failed += 1;
+            },
+        }
+    }
+
+    report
+}
+
+/// Synchronously saves all dirty chunks of the given block data type within
+/// the given sector of the given persistent world.
+///
+/// This is useful for saving a region of the world before unloading it using
+/// [`despawn_sector`](crate::util::sector::despawn_sector). A
+/// [`ChunkSaveFailed`] event is sent for each chunk that failed to save, and
+/// those chunks stay dirty.
+pub fn save_sector<T>(world: &mut World, world_id: Entity, sector_coords: IVec3) -> FlushReport
+where
+    T: PersistentBlock,
+{
+    let mut report = FlushReport::default();
+    let Some(persistent) = world.get::<PersistentWorld>(world_id).cloned() else {
+        return report;
+    };
+
+    for (chunk_coords, chunk_id) in sector_chunks(world, world_id, sector_coords) {
+        let Some(chunk) = world.get_entity(chunk_id) else {
+            continue;
+        };
+
+        if !chunk.contains::<DirtyChunk>() {
+            continue;
+        }
+
+        let Some(storage) = chunk.get::<VoxelStorage<T>>() else {
+            continue;
+        };
+
+        match persistent.save_chunk(chunk_coords, storage) {
+            Ok(()) => {
+                world.entity_mut(chunk_id).remove::<DirtyChunk>();
+                report.saved += 1;
+            },
+            Err(err) => {
+                world.send_event(ChunkSaveFailed {
+                    world_id,
+                    chunk_coords,
+                    message: err.to_string(),
+                });
+                report.failed += 1;
+            },
+        }
+    }
+
+    report
+}
+
+/// This system marks all modified chunks within persistent worlds as dirty.
+///
+/// Chunks that were just loaded from their chunk store, as well as newly
+/// spawned or generated chunks, are skipped until they are edited.
+fn mark_dirty_chunks<T>(
+    time: Res<Time>,
+    worlds: Query<(), With<PersistentWorld>>,
+    chunks: Query<
+        (
+            Entity,
+            &VoxelChunk,
+            Ref<VoxelStorage<T>>,
+            Option<&ChunkLoadedFromStore>,
+        ),
+        (Changed<VoxelStorage<T>>, Without<DirtyChunk>),
+    >,
+    mut commands: Commands,
+) where
+    T: PersistentBlock,
+{
+    for (chunk_id, chunk_meta, storage, loaded) in chunks.iter() {
+        if loaded.is_some() {
+            commands.entity(chunk_id).remove::<ChunkLoadedFromStore>();
+            continue;
+        }
+
+        if storage.is_added() {
+            continue;
+        }
+
+        if worlds.contains(chunk_meta.world_id()) {
+            commands.entity(chunk_id).insert(DirtyChunk {
+                since: time.elapsed_seconds(),
+            });
+        }
+    }
+}
+
+/// This system saves the chunks that have been dirty for the longest, once
+/// they have been dirty for longer than the autosave delay.
+fn autosave_dirty_chunks<T>(
+    time: Res<Time>,
+    settings: Res<PersistenceSettings>,
+    worlds: Query<&PersistentWorld>,
+    chunks: Query<(Entity, &VoxelChunk, &VoxelStorage<T>, &DirtyChunk)>,
+    mut failed: EventWriter<ChunkSaveFailed>,
+    mut commands: Commands,
+) where
+    T: PersistentBlock,
+{
+    let now = time.elapsed_seconds();
+    let mut ready: Vec<_> = chunks
+        .iter()
+        .filter(|(.., dirty)| now - dirty.since >= settings.autosave_delay)
+        .collect();
+
+    ready.sort_unstable_by(|a, b| a.3.since.total_cmp(&b.3.since));
+
+    for (chunk_id, chunk_meta, storage, _) in ready.into_iter().take(settings.max_saves_per_frame) {
+        let Ok(persistent) = worlds.get(chunk_meta.world_id()) else {
+            continue;
+        };
+
+        match persistent.save_chunk(chunk_meta.chunk_coords(), storage) {
+            Ok(()) => {
+                commands.entity(chunk_id).remove::<DirtyChunk>();
+            },
+            Err(err) => {
+                failed.send(ChunkSaveFailed {
+                    world_id:     chunk_meta.world_id(),
+                    chunk_coords: chunk_meta.chunk_coords(),
+                    message:      err.to_string(),
+                });
+            },
+        }
+    }
+}
+
+/// This system loads the saved metadata of all newly added persistent worlds
+/// that do not have a [`WorldMeta`] component yet.
+fn load_world_meta(
+    worlds: Query<(Entity, &PersistentWorld), (Added<PersistentWorld>, Without<WorldMeta>)>,
+    mut commands: Commands,
+) {
+    for (world_id, persistent) in worlds.iter() {
+        match persistent.load_meta() {
+            Ok(Some(meta)) => {
+                commands.entity(world_id).insert(meta);
+            },
+            Ok(None) => {},
+            Err(err) => error!("Failed to load the world metadata: {}", err),
+        }
+    }
+}
+
+/// This system saves the metadata of all persistent worlds whose metadata has
+/// changed.
+fn save_world_meta(worlds: Query<(&PersistentWorld, &WorldMeta), Changed<WorldMeta>>) {
+    for (persistent, meta) in worlds.iter() {
+        if let Err(err) = persistent.save_meta(meta) {
+            error!("Failed to save the world metadata: {}", err);
+        }
+    }
+}
+
+/// This system flushes all dirty chunks when the app is about to exit.
+fn flush_on_exit<T>(world: &mut World, mut reader: Local<ManualEventReader<AppExit>>)
+where
+    T: PersistentBlock,
+{
+    let Some(events) = world.get_resource::<Events<AppExit>>() else {
+        return;
+    };
+
+    if reader.iter(events).last().is_none() {
+        return;
+    }
+
+    let budget = world.resource::<PersistenceSettings>().exit_flush_budget;
+    let report = flush_all::<T>(world, budget);
+
+    if report.failed > 0 || report.remaining > 0 {
+        warn!(
+            "Failed to save {} chunks on exit, and skipped {} chunks",
+            report.failed, report.remaining
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::persistence::MemoryChunkStore;
+    use crate::prelude::*;
+
+    #[test]
+    fn flush_dirty_chunks_on_exit() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .add_event::<AppExit>();
+
+        let store = Arc::new(MemoryChunkStore::default());
+        let persistent = PersistentWorld::from_shared(store.clone());
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                commands
+                    .spawn_world(persistent.clone())
+                    .spawn_chunk(IVec3::new(1, 0, -1), VoxelStorage::<u8>::default())
+                    .unwrap();
+            })
+            .run(&mut app.world);
+
+        app.update();
+        assert_eq!(app.world.query::<&DirtyChunk>().iter(&app.world).count(), 0);
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelStorage<u8>>>()
+            .single(&app.world);
+        app.world
+            .get_mut::<VoxelStorage<u8>>(chunk_id)
+            .unwrap()
+            .set_block(IVec3::new(4, 5, 6), 9);
+        app.update();
+        assert_eq!(store.chunk_list().unwrap(), vec![]);
+        assert_eq!(app.world.query::<&DirtyChunk>().iter(&app.world).count(), 1);
+
+        app.world.send_event(AppExit);
+        app.update();
+
+        assert_eq!(app.world.query::<&DirtyChunk>().iter(&app.world).count(), 0);
+        assert_eq!(store.chunk_list().unwrap(), vec![IVec3::new(1, 0, -1)]);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        let loaded = app
+            .world
+            .get::<PersistentWorld>(world_id)
+            .unwrap()
+            .load_chunk::<u8>(IVec3::new(1, 0, -1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.get_block(IVec3::new(4, 5, 6)), 9);
+    }
+
+    #[test]
+    fn load_and_save_world_meta() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>();
+
+        let store = Arc::new(MemoryChunkStore::default());
+        let mut meta = WorldMeta::default();
+        meta.set_seed(Some(42));
+        store.write_meta(&meta.encode()).unwrap();
+
+        let persistent = PersistentWorld::from_shared(store.clone());
+        let world_id = app.world.spawn((VoxelWorld, persistent)).id();
+        app.update();
+
+        let mut loaded = app.world.get_mut::<WorldMeta>(world_id).unwrap();
+        assert_eq!(loaded.seed(), Some(42));
+        loaded.insert_str("difficulty", "hard");
+        app.update();
+
+        let saved = WorldMeta::decode(&store.read_meta().unwrap().unwrap()).unwrap();
+        assert_eq!(saved.seed(), Some(42));
+        assert_eq!(saved.get_str("difficulty"), Some("hard"));
+    }
+}
@@ -0,0 +1,200 @@
+//! Backends for storing encoded chunk records.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::persistence::PersistenceError;
+
+/// A storage backend that holds the encoded chunk records of a single voxel
+/// world.
+///
+/// Chunk stores are shared between the main world and background tasks, so
+/// all methods take `&self`, and implementations must handle their own
+/// synchronization.
+pub trait ChunkStore: Send + Sync + 'static {
+    /// Reads the record of the chunk at the given chunk coordinates, or
+    /// `None` if that chunk has not been saved.
+    fn read_chunk(&self, chunk_coords: IVec3) -> Result<Option<Vec<u8>>, PersistenceError>;
+
+    /// Writes the record of the chunk at the given chunk coordinates,
+    /// replacing any record that was previously saved for that chunk.
+    fn write_chunk(&self, chunk_coords: IVec3, record: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Removes the record of the chunk at the given chunk coordinates, if it
+    /// exists.
+    fn remove_chunk(&self, chunk_coords: IVec3) -> Result<(), PersistenceError>;
+
+    /// Gets the coordinates of all chunks that have been saved.
+    fn chunk_list(&self) -> Result<Vec<IVec3>, PersistenceError>;
+
+    /// Reads the world metadata record, or `None` if no metadata has been
+    /// saved.
+    fn read_meta(&self) -> Result<Option<Vec<u8>>, PersistenceError>;
+
+    /// Writes the world metadata record, replacing any record that was
+    /// previously saved.
+    fn write_meta(&self, record: &[u8]) -> Result<(), PersistenceError>;
+}
+
+/// A chunk store that keeps all chunk records in memory.
+///
+/// This is mostly useful for tests, or for temporarily holding worlds that are
+/// later written elsewhere.
+#[derive(Debug, Default)]
+pub struct MemoryChunkStore {
+    /// The stored chunk records, by chunk coordinates.
+    chunks: Mutex<HashMap<IVec3, Vec<u8>>>,
+
+    /// The stored world metadata record.
+    meta: Mutex<Option<Vec<u8>>>,
+}
+
+impl ChunkStore for MemoryChunkStore {
+    fn read_chunk(&self, chunk_coords: IVec3) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self.chunks.lock().unwrap().get(&chunk_coords).cloned())
+    }
+
+    fn write_chunk(&self, chunk_coords: IVec3, record: &[u8]) -> Result<(), PersistenceError> {
+        self.chunks
+            .lock()
+            .unwrap()
+            .insert(chunk_coords, record.to_vec());
+        Ok(())
+    }
+
+    fn remove_chunk(&self, chunk_coords: IVec3) -> Result<(), PersistenceError> {
+        self.chunks.lock().unwrap().remove(&chunk_coords);
+        Ok(())
+    }
+
+    fn chunk_list(&self) -> Result<Vec<IVec3>, PersistenceError> {
+        Ok(self.chunks.lock().unwrap().keys().copied().collect())
+    }
+
+    fn read_meta(&self) -> Result<Option<Vec<u8>>, PersistenceError> {
+        Ok(self.meta.lock().unwrap().clone())
+    }
+
+    fn write_meta(&self, record: &[u8]) -> Result<(), PersistenceError> {
+        *self.meta.lock().unwrap() = Some(record.to_vec());
+        Ok(())
+    }
+}
+
+/// A chunk store that writes each chunk record to its own file within a
+/// directory. The world metadata record is written to a `world.meta` file
+/// within the same directory.
+///
+/// Records are first written to a temporary file, which then replaces the
+/// previous record, so a crash while saving never leaves a partially written
+/// record behind.
+#[derive(Debug, Clone)]
+pub struct DirectoryChunkStore {
+    /// The directory that contains the chunk records.
+    root: PathBuf,
+}
+
+impl DirectoryChunkStore {
+    /// Opens the chunk store within the given directory, creating the
+    /// directory if it does not exist yet.
+    pub fn open(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        Ok(Self {
+            root,
+        })
+    }
+
+    /// Gets the directory that contains the chunk records.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Gets the path of the record file for the chunk at the given chunk
+    /// coordinates.
+    fn chunk_path(&self, chunk_coords: IVec3) -> PathBuf {
+        let [x, y, z] = chunk_coords.to_array();
+        self.root.join(format!("{x}_{y}_{z}.chunk"))
+    }
+
+    /// Gets the path of the world metadata record file.
+    fn meta_path(&self) -> PathBuf {
+        self.root.join("world.meta")
+    }
+}
+
+impl ChunkStore for DirectoryChunkStore {
+    fn read_chunk(&self, chunk_coords: IVec3) -> Result<Option<Vec<u8>>, PersistenceError> {
+        match fs::read(self.chunk_path(chunk_coords)) {
+            Ok(record) => Ok(Some(record)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write_chunk(&self, chunk_coords: IVec3, record: &[u8]) -> Result<(), PersistenceError> {
+        write_atomic(&self.chunk_path(chunk_coords), record)
+    }
+
+    fn remove_chunk(&self, chunk_coords: IVec3) -> Result<(), PersistenceError> {
+        match fs::remove_file(self.chunk_path(chunk_coords)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn chunk_list(&self) -> Result<Vec<IVec3>, PersistenceError> {
+        let mut chunks = vec![];
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "chunk") {
+                if let Some(chunk_coords) = parse_chunk_name(&path) {
+                    chunks.push(chunk_coords);
+                }
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    fn read_meta(&self) -> Result<Option<Vec<u8>>, PersistenceError> {
+        match fs::read(self.meta_path()) {
+            Ok(record) => Ok(Some(record)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write_meta(&self, record: &[u8]) -> Result<(), PersistenceError> {
+        write_atomic(&self.meta_path(), record)
+    }
+}
+
+/// Writes the given record to a temporary file next to the given path, which
+/// then replaces the file at that path.
+fn write_atomic(path: &Path, record: &[u8]) -> Result<(), PersistenceError> {
+    let temp_path = path.with_extension("tmp");
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(record)?;
+    file.sync_all()?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Parses the chunk coordinates from the file name of a chunk record.
+fn parse_chunk_name(path: &Path) -> Option<IVec3> {
+    let mut coords = path.file_stem()?.to_str()?.split('_').map(str::parse);
+    let chunk_coords = IVec3::new(
+        coords.next()?.ok()?,
+        coords.next()?.ok()?,
+        coords.next()?.ok()?,
+    );
+
+    coords.next().is_none().then_some(chunk_coords)
+}
@@ -1,11 +1,49 @@
 //! A system parameter helper for executing voxel-specific commands.
 
+use std::marker::PhantomData;
+
 use bevy::ecs::system::{Command, EntityCommands, SystemParam};
+use bevy::hierarchy::despawn_with_children_recursive;
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 
 use super::VoxelQueryError;
+use crate::persistence::{save_sector, PersistentBlock};
 use crate::storage::chunk_pointers::ChunkEntityPointers;
-use crate::storage::{VoxelChunk, VoxelWorld};
+use crate::storage::template::apply_chunk_template;
+use crate::storage::{
+    bump_chunk_version,
+    BlockBroken,
+    BlockData,
+    BlockEntity,
+    ChunkDespawned,
+    ChunkStorageReplaced,
+    ChunkVersionConflict,
+    VoxelChunk,
+    VoxelStorage,
+    VoxelWorld,
+    WorldDespawned,
+    WorldTopology,
+};
+use crate::util::block_update::BlockUpdateQueue;
+use crate::util::brush::{
+    apply_brush,
+    redo_edit,
+    undo_edit,
+    BrushOp,
+    VoxelBrush,
+    VoxelEditHistory,
+};
+use crate::util::multiblock::{
+    place_multi_block,
+    MultiBlock,
+    MultiBlockPlaced,
+    MultiBlockPlacementFailed,
+};
+use crate::util::pointer_validation::{validate_chunk_pointers, ChunkPointerReport};
+use crate::util::replace::BlockReplaceJob;
+use crate::util::sector::{despawn_sector, fill_sector};
+use crate::util::transaction::{run_transaction, VoxelTransaction};
 
 /// A Bevy command queue helper for working with Voxel-based actions.
 #[derive(SystemParam)]
@@ -21,6 +59,48 @@ pub struct VoxelCommands<'w, 's> {
 }
 
 impl<'w, 's, 'cmd_ref> VoxelCommands<'w, 's> {
+    /// Undoes the most recent edit within the [`VoxelEditHistory`] for the
+    /// given block data type when the command queue is executed.
+    pub fn undo_edit<T>(&mut self)
+    where
+        T: BlockData,
+    {
+        self.commands.add(|world: &mut World| {
+            undo_edit::<T>(world);
+        });
+    }
+
+    /// Redoes the most recently undone edit within the [`VoxelEditHistory`]
+    /// for the given block data type when the command queue is executed.
+    pub fn redo_edit<T>(&mut self)
+    where
+        T: BlockData,
+    {
+        self.commands.add(|world: &mut World| {
+            redo_edit::<T>(world);
+        });
+    }
+
+    /// Runs the given function as a transaction within the given voxel world
+    /// when the command queue is executed.
+    ///
+    /// Every block write and chunk spawn made through the transaction is
+    /// rolled back if the function returns an error, such as when writing to
+    /// a chunk that does not exist. Otherwise, the changes are recorded within
+    /// the [`VoxelEditHistory`] for `T`, if it exists. See [`run_transaction`]
+    /// for more information.
+    pub fn transaction<T, F>(&mut self, world_id: Entity, build: F)
+    where
+        T: BlockData,
+        F: FnOnce(&mut VoxelTransaction<T>) -> Result<(), VoxelQueryError> + Send + 'static,
+    {
+        self.commands.add(move |world: &mut World| {
+            if let Err(err) = run_transaction(world, world_id, build) {
+                warn!("Voxel transaction was rolled back: {err}");
+            }
+        });
+    }
+
     /// Gets whether or not the given world id is valid and queryable.
     ///
     /// This method will return false if the provided entity is not a valid
@@ -92,10 +172,16 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
     /// Spawns a new chunk within the voxel world at the given chunk
     /// coordinates.
     ///
-    /// The voxel chunk will spawn with the given component bundle attached.
+    /// The voxel chunk will spawn with the given component bundle attached,
+    /// along with all components from the
+    /// [`ChunkTemplate`](crate::storage::ChunkTemplate) resource, if it
+    /// exists.
     ///
     /// This method will return an error if there is already an existing chunk
     /// at the given chunk coordinates.
+    ///
+    /// If the world is wrapped, the chunk coordinates are wrapped to their
+    /// canonical form before the chunk is spawned.
     pub fn spawn_chunk<B>(
         &'chunk_ref mut self,
         chunk_coords: IVec3,
@@ -104,6 +190,7 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
     where
         B: Bundle,
     {
+        let chunk_coords = self.topology().wrap_chunk_coords(chunk_coords);
         if self.get_chunk_id(chunk_coords).is_some() {
             return Err(VoxelQueryError::ChunkAlreadyExists(
                 self.world_id,
@@ -118,6 +205,10 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
             .set_parent(self.world_id)
             .id();
 
+        self.voxel_commands
+            .commands
+            .add(move |world: &mut World| apply_chunk_template(world, &[chunk_id]));
+
         self.voxel_commands.commands.add(UpdateChunkPointersAction {
             world_id: self.world_id,
             chunk_id: Some(chunk_id),
@@ -132,6 +223,36 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
         })
     }
 
+    /// Spawns many new chunks within the voxel world at once, each with their
+    /// own component bundle attached.
+    ///
+    /// Each chunk also receives all components from the
+    /// [`ChunkTemplate`](crate::storage::ChunkTemplate) resource, if it exists.
+    ///
+    /// Unlike [`VoxelWorldCommands::spawn_chunk`], all chunks are spawned
+    /// together using a single command, and the chunk pointer cache is updated
+    /// in a single pass. This is much faster when spawning a large number of
+    /// chunks on the same frame.
+    ///
+    /// Chunks whose coordinates are already occupied when the command queue is
+    /// executed, or that appear more than once within the iterator, are
+    /// skipped.
+    pub fn spawn_chunks<I, B>(&mut self, chunks: I)
+    where
+        I: IntoIterator<Item = (IVec3, B)>,
+        B: Bundle,
+    {
+        let chunks: Vec<(IVec3, B)> = chunks.into_iter().collect();
+        if chunks.is_empty() {
+            return;
+        }
+
+        self.voxel_commands.commands.add(SpawnChunksAction {
+            world_id: self.world_id,
+            chunks,
+        });
+    }
+
     /// Gets the chunk id of the given within this voxel world at the given
     /// chunk coordinates.
     ///
@@ -168,6 +289,7 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
         let chunk_id = self
             .get_chunk_id(chunk_coords)
             .ok_or(VoxelQueryError::ChunkNotFound(self.world_id, chunk_coords))?;
+        let chunk_coords = self.topology().wrap_chunk_coords(chunk_coords);
 
         Ok(VoxelChunkCommands {
             voxel_commands: self.voxel_commands,
@@ -177,11 +299,269 @@ impl<'w, 's, 'cmd_ref, 'chunk_ref> VoxelWorldCommands<'w, 's, 'cmd_ref> {
         })
     }
 
+    /// Sets the block data at the given block coordinates within this voxel
+    /// world.
+    ///
+    /// The block is written when the command queue is executed. If there is no
+    /// chunk with a `VoxelStorage<T>` component at the given coordinates at
+    /// that time, the change is discarded. Otherwise, the six neighboring
+    /// blocks will be notified with a
+    /// [`NeighborChangedEvent`](crate::util::block_update::NeighborChangedEvent)
+    /// at the end of the frame.
+    pub fn set_block<T>(&mut self, block_coords: IVec3, data: T)
+    where
+        T: BlockData,
+    {
+        self.voxel_commands.commands.add(SetBlockAction {
+            world_id: self.world_id,
+            block_coords,
+            data,
+        });
+    }
+
+    /// Breaks the block at the given block coordinates within this world,
+    /// replacing it with the default value for `T`.
+    ///
+    /// This behaves like [`VoxelWorldCommands::set_block`], but additionally
+    /// sends a [`BlockBroken`] event containing the removed block value and all
+    /// [`BlockEntity`] entities that are attached to the block, so that item
+    /// drops can be spawned. Nothing happens if the chunk containing the block
+    /// does not have a `VoxelStorage<T>` component.
+    pub fn break_block<T>(&mut self, block_coords: IVec3)
+    where
+        T: BlockData,
+    {
+        self.voxel_commands.commands.add(BreakBlockAction::<T> {
+            world_id: self.world_id,
+            block_coords,
+            _phantom: PhantomData,
+        });
+    }
+
+    /// Places the given multi-block structure, with its master block at the
+    /// given block coordinates within this world.
+    ///
+    /// The master block and all of its parts are written together, only if
+    /// every block they occupy is loaded and replaceable. A
+    /// [`MultiBlockPlaced`] event is sent if the structure was placed, and a
+    /// [`MultiBlockPlacementFailed`] event is sent otherwise. See
+    /// [`place_multi_block`] for more information.
+    pub fn place_multi_block<T>(&mut self, block_coords: IVec3, data: T)
+    where
+        T: MultiBlock,
+    {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            match place_multi_block(world, world_id, block_coords, data) {
+                Ok(()) => {
+                    if let Some(mut events) =
+                        world.get_resource_mut::<Events<MultiBlockPlaced<T>>>()
+                    {
+                        events.send(MultiBlockPlaced {
+                            world_id,
+                            master_coords: block_coords,
+                            block: data,
+                        });
+                    }
+                },
+                Err(error) => {
+                    if let Some(mut events) =
+                        world.get_resource_mut::<Events<MultiBlockPlacementFailed<T>>>()
+                    {
+                        events.send(MultiBlockPlacementFailed {
+                            world_id,
+                            master_coords: block_coords,
+                            block: data,
+                            error,
+                        });
+                    }
+                },
+            }
+        });
+    }
+
+    /// Applies the given brush operation around the given block coordinates
+    /// within this world.
+    ///
+    /// The edit is applied when the command queue is executed, and is
+    /// recorded within the [`VoxelEditHistory`] for `T` if that resource
+    /// exists. See [`apply_brush`] for more information.
+    pub fn apply_brush<T>(&mut self, center: IVec3, brush: VoxelBrush, op: BrushOp<T>)
+    where
+        T: BlockData + PartialEq,
+    {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            let Some(edit) = apply_brush(world, world_id, center, &brush, op) else {
+                return;
+            };
+
+            if let Some(mut history) = world.get_resource_mut::<VoxelEditHistory<T>>() {
+                history.push(edit);
+            }
+        });
+    }
+
+    /// Marks the block at the given block coordinates as changed, notifying
+    /// all six neighboring blocks at the end of the frame.
+    ///
+    /// This only needs to be called when the block storage was edited directly
+    /// rather than through [`VoxelWorldCommands::set_block`].
+    pub fn notify_block_changed(&mut self, block_coords: IVec3) {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+                queue.push(world_id, block_coords);
+            }
+        });
+    }
+
+    /// Gets the topology of this voxel world.
+    ///
+    /// Note that this method will only account for topology changes that were
+    /// applied since the previous frame.
+    pub fn topology(&self) -> WorldTopology {
+        self.voxel_commands
+            .chunk_pointers
+            .get(self.world_id)
+            .map(|p| p.topology())
+            .unwrap_or_default()
+    }
+
+    /// Sets the topology of this voxel world.
+    ///
+    /// This should be called right after the world is spawned, before any
+    /// chunks are added to it. Chunks that are spawned on the same frame are
+    /// wrapped when the command queue is executed.
+    pub fn set_topology(&mut self, topology: WorldTopology) {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            if let Some(mut pointers) = world.get_mut::<ChunkEntityPointers>(world_id) {
+                pointers.set_topology(topology);
+            }
+        });
+    }
+
     /// Gets the id of the voxel world being handled.
     pub fn id(&self) -> Entity {
         self.world_id
     }
 
+    /// Despawns all chunks within this voxel world, along with their child
+    /// entities, while keeping the world entity itself.
+    ///
+    /// The chunk pointer cache of the world is reset, pending block updates for
+    /// this world are discarded, and any in-flight tasks that are stored on the
+    /// chunk entities are cancelled. A [`ChunkDespawned`] event is sent for
+    /// each chunk that was removed.
+    pub fn clear_all_chunks(&mut self) {
+        self.voxel_commands.commands.add(ClearChunksAction {
+            world_id: self.world_id,
+        });
+    }
+
+    /// Starts the given block replace job within this world, which replaces
+    /// matching blocks across all chunks of the world over the next few
+    /// frames.
+    ///
+    /// This requires the
+    /// [`BlockReplacePlugin`](crate::util::replace::BlockReplacePlugin) for
+    /// `T`. Any job of the same block data type that is still running
+    /// within this world is replaced. See [`BlockReplaceJob`] for more
+    /// information.
+    pub fn replace_blocks<T>(&mut self, job: BlockReplaceJob<T>)
+    where
+        T: BlockData,
+    {
+        self.voxel_commands
+            .commands
+            .entity(self.world_id)
+            .insert(job);
+    }
+
+    /// Despawns all loaded chunks within the given sector of this world, along
+    /// with their child entities, when the command queue is executed.
+    ///
+    /// A [`ChunkDespawned`] event is sent for each chunk that was removed.
+    /// Modified chunks are not saved, so [`VoxelWorldCommands::save_sector`]
+    /// should be called first for persistent worlds. See [`despawn_sector`] for
+    /// more information.
+    pub fn despawn_sector(&mut self, sector_coords: IVec3) {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            despawn_sector(world, world_id, sector_coords);
+        });
+    }
+
+    /// Saves all dirty chunks of the given block data type within the given
+    /// sector of this world, when the command queue is executed.
+    ///
+    /// Nothing happens if this world does not have a
+    /// [`PersistentWorld`](crate::persistence::PersistentWorld) component. See
+    /// [`save_sector`] for more information.
+    pub fn save_sector<T>(&mut self, sector_coords: IVec3)
+    where
+        T: PersistentBlock,
+    {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            save_sector::<T>(world, world_id, sector_coords);
+        });
+    }
+
+    /// Fills every loaded chunk within the given sector of this world with the
+    /// given block, when the command queue is executed.
+    ///
+    /// All filled chunks share a single copy of their block data until they
+    /// are modified. See [`fill_sector`] for more information.
+    pub fn fill_sector<T>(&mut self, sector_coords: IVec3, block: T)
+    where
+        T: BlockData,
+    {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            fill_sector(world, world_id, sector_coords, block);
+        });
+    }
+
+    /// Despawns this voxel world, along with all of its chunks and their child
+    /// entities, recursively.
+    ///
+    /// Any in-flight tasks that are stored on the chunk entities, such as world
+    /// generation or remeshing tasks, are cancelled when they are dropped.
+    /// Pending block updates for this world are discarded, and a
+    /// [`WorldDespawned`] event is sent so that other plugins may clean up any
+    /// external data they store for this world.
+    pub fn despawn_world(self) {
+        self.voxel_commands.commands.add(DespawnWorldAction {
+            world_id: self.world_id,
+        });
+    }
+
+    /// Validates the chunk pointer cache of this voxel world against the chunk
+    /// entities that exist within it, once this command is applied.
+    ///
+    /// If any discrepancies are found, a warning is logged and a
+    /// [`ChunkPointerReport`] event is sent. If `repair` is true, stale
+    /// pointers are removed and orphaned chunks are re-added to the cache.
+    pub fn validate_chunk_pointers(&mut self, repair: bool) {
+        let world_id = self.world_id;
+        self.voxel_commands.commands.add(move |world: &mut World| {
+            let Some(report) = validate_chunk_pointers(world, world_id, repair) else {
+                return;
+            };
+
+            if report.is_consistent() {
+                return;
+            }
+
+            report.log();
+            if let Some(mut events) = world.get_resource_mut::<Events<ChunkPointerReport>>() {
+                events.send(report);
+            }
+        });
+    }
+
     /// Gets the entity command queue for this voxel world object.
     pub fn as_entity_commands(self) -> EntityCommands<'w, 's, 'cmd_ref> {
         self.voxel_commands
@@ -224,6 +604,55 @@ impl<'world, 'state, 'cmd_ref> VoxelChunkCommands<'world, 'state, 'cmd_ref> {
         })
     }
 
+    /// Replaces the entire `VoxelStorage<T>` component of this chunk with the
+    /// given storage when the command queue is executed.
+    ///
+    /// Unlike inserting the component directly, this sends a
+    /// [`ChunkStorageReplaced`] event if the chunk already had a storage
+    /// component, so that caches derived from the old blocks can be
+    /// invalidated. This should be used for whole-chunk updates, such as
+    /// applying generated chunks, chunks loaded from disk, or full chunk syncs.
+    pub fn replace_storage<T>(&mut self, storage: VoxelStorage<T>)
+    where
+        T: BlockData,
+    {
+        self.voxel_commands.commands.add(ReplaceStorageAction {
+            world_id: self.world_id,
+            chunk_id: self.chunk_id,
+            chunk_coords: self.chunk_coords,
+            storage,
+        });
+    }
+
+    /// Writes the given blocks to the `VoxelStorage<T>` component of this
+    /// chunk when the command queue is executed, but only if the edit version
+    /// of the chunk is still equal to the expected version at that time.
+    ///
+    /// The block coordinates are local to this chunk. Coordinates outside of
+    /// the chunk are wrapped, so world block coordinates that lie within this
+    /// chunk may also be used. All blocks are written at once, and the chunk
+    /// version is increased by one. If the version does not match, no blocks
+    /// are written and a [`ChunkVersionConflict`] event is sent instead.
+    ///
+    /// This allows networking and persistence layers to apply a delta that
+    /// was computed against a known version of the chunk without overwriting
+    /// any edits that were made since then.
+    ///
+    /// See [`VoxelChunk::version`] for more information.
+    pub fn set_blocks_if_version<T, I>(&mut self, expected_version: u64, blocks: I)
+    where
+        T: BlockData,
+        I: IntoIterator<Item = (IVec3, T)>,
+    {
+        self.voxel_commands.commands.add(SetBlocksIfVersionAction {
+            world_id: self.world_id,
+            chunk_id: self.chunk_id,
+            chunk_coords: self.chunk_coords,
+            expected_version,
+            blocks: blocks.into_iter().collect(),
+        });
+    }
+
     /// Gets the entity command queue for this voxel chunk object.
     pub fn as_entity_commands(self) -> EntityCommands<'world, 'state, 'cmd_ref> {
         self.voxel_commands
@@ -269,21 +698,407 @@ struct UpdateChunkPointersAction {
 impl Command for UpdateChunkPointersAction {
     fn apply(self, world: &mut World) {
         let mut pointers = world.get_mut::<ChunkEntityPointers>(self.world_id).unwrap();
+        let chunk_coords = pointers.topology().wrap_chunk_coords(self.chunk_coords);
 
-        if pointers.get_chunk_entity(self.chunk_coords).is_some() && self.chunk_id.is_some() {
+        if pointers.get_chunk_entity(chunk_coords).is_some() && self.chunk_id.is_some() {
             panic!(
                 "Tried to spawn chunk at {}, in world {:?}, but it already exists!",
-                self.chunk_coords, self.world_id
+                chunk_coords, self.world_id
             )
         };
 
-        pointers.set_chunk_entity(self.chunk_coords, self.chunk_id);
+        pointers.set_chunk_entity(chunk_coords, self.chunk_id);
+
+        match self.chunk_id {
+            Some(chunk_id) => {
+                if chunk_coords != self.chunk_coords {
+                    world
+                        .entity_mut(chunk_id)
+                        .insert(VoxelChunk::new(self.world_id, chunk_coords));
+                }
+            },
+            None => {
+                if let Some(mut events) = world.get_resource_mut::<Events<ChunkDespawned>>() {
+                    events.send(ChunkDespawned {
+                        world_id: self.world_id,
+                        chunk_coords,
+                    });
+                }
+            },
+        }
+    }
+}
+
+/// Despawns all chunks that belong to the given world, returning the
+/// coordinates of each despawned chunk.
+fn despawn_world_chunks(world: &mut World, world_id: Entity) -> Vec<IVec3> {
+    // Chunks are normally children of the world, but may have been reparented
+    // by the user.
+    let chunks: Vec<(Entity, IVec3)> = world
+        .query::<(Entity, &VoxelChunk)>()
+        .iter(world)
+        .filter(|(_, c)| c.world_id() == world_id)
+        .map(|(e, c)| (e, c.chunk_coords()))
+        .collect();
+
+    for (chunk_id, _) in &chunks {
+        if world.get_entity(*chunk_id).is_some() {
+            despawn_with_children_recursive(world, *chunk_id);
+        }
+    }
+
+    if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+        queue.remove_world(world_id);
+    }
+
+    chunks.into_iter().map(|(_, c)| c).collect()
+}
+
+/// A Bevy command that despawns all chunks within a voxel world, without
+/// despawning the world itself.
+struct ClearChunksAction {
+    /// The id of the world that is being cleared.
+    world_id: Entity,
+}
+
+impl Command for ClearChunksAction {
+    fn apply(self, world: &mut World) {
+        let Some(world_entity) = world.get_entity(self.world_id) else {
+            return;
+        };
+
+        if !world_entity.contains::<VoxelWorld>() {
+            return;
+        }
+
+        let chunks = despawn_world_chunks(world, self.world_id);
+
+        if let Some(mut pointers) = world.get_mut::<ChunkEntityPointers>(self.world_id) {
+            pointers.clear();
+        }
+
+        if let Some(mut events) = world.get_resource_mut::<Events<ChunkDespawned>>() {
+            events.extend(chunks.into_iter().map(|chunk_coords| {
+                ChunkDespawned {
+                    world_id: self.world_id,
+                    chunk_coords,
+                }
+            }));
+        }
+    }
+}
+
+/// A Bevy command that despawns a voxel world and all data associated with
+/// it.
+struct DespawnWorldAction {
+    /// The id of the world that is being despawned.
+    world_id: Entity,
+}
+
+impl Command for DespawnWorldAction {
+    fn apply(self, world: &mut World) {
+        let Some(world_entity) = world.get_entity(self.world_id) else {
+            return;
+        };
+
+        if !world_entity.contains::<VoxelWorld>() {
+            return;
+        }
+
+        despawn_world_chunks(world, self.world_id);
+        despawn_with_children_recursive(world, self.world_id);
+
+        if let Some(mut events) = world.get_resource_mut::<Events<WorldDespawned>>() {
+            events.send(WorldDespawned {
+                world_id: self.world_id,
+            });
+        }
+    }
+}
+
+/// A Bevy command that spawns a batch of chunks within a voxel world, and
+/// updates the chunk pointer cache for all of them at once.
+struct SpawnChunksAction<B>
+where
+    B: Bundle,
+{
+    /// The id of the world that is being edited.
+    world_id: Entity,
+
+    /// The coordinates and component bundle of each chunk to spawn.
+    chunks: Vec<(IVec3, B)>,
+}
+
+impl<B> Command for SpawnChunksAction<B>
+where
+    B: Bundle,
+{
+    fn apply(self, world: &mut World) {
+        let Some(pointers) = world.get::<ChunkEntityPointers>(self.world_id) else {
+            return;
+        };
+
+        let topology = pointers.topology();
+        let mut seen = HashSet::new();
+        let chunks: Vec<_> = self
+            .chunks
+            .into_iter()
+            .map(|(coords, bundle)| (topology.wrap_chunk_coords(coords), bundle))
+            .filter(|(coords, _)| pointers.get_chunk_entity(*coords).is_none())
+            .filter(|(coords, _)| seen.insert(*coords))
+            .collect();
+
+        let coords: Vec<IVec3> = chunks.iter().map(|(c, _)| *c).collect();
+        let world_id = self.world_id;
+        let chunk_ids: Vec<Entity> = world
+            .spawn_batch(
+                chunks
+                    .into_iter()
+                    .map(|(coords, bundle)| (VoxelChunk::new(world_id, coords), bundle)),
+            )
+            .collect();
+
+        world.entity_mut(world_id).push_children(&chunk_ids);
+        apply_chunk_template(world, &chunk_ids);
+
+        let mut pointers = world.get_mut::<ChunkEntityPointers>(world_id).unwrap();
+        for (chunk_coords, chunk_id) in coords.into_iter().zip(chunk_ids) {
+            pointers.set_chunk_entity(chunk_coords, Some(chunk_id));
+        }
+    }
+}
+
+/// A Bevy command that writes a single block value to the chunk that contains
+/// it, and marks the block as changed.
+struct SetBlockAction<T>
+where
+    T: BlockData,
+{
+    /// The id of the world that is being edited.
+    world_id: Entity,
+
+    /// The coordinates of the block within the world.
+    block_coords: IVec3,
+
+    /// The new block data value.
+    data: T,
+}
+
+impl<T> Command for SetBlockAction<T>
+where
+    T: BlockData,
+{
+    fn apply(self, world: &mut World) {
+        let Some(pointers) = world.get::<ChunkEntityPointers>(self.world_id) else {
+            return;
+        };
+
+        let block_coords = pointers.topology().wrap_block_coords(self.block_coords);
+        let Some(chunk_id) = pointers.get_chunk_entity(block_coords >> 4) else {
+            return;
+        };
+
+        let Some(mut storage) = world.get_mut::<VoxelStorage<T>>(chunk_id) else {
+            return;
+        };
+
+        storage.set_block(block_coords, self.data);
+        bump_chunk_version::<T>(world, chunk_id);
+
+        if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+            queue.push(self.world_id, block_coords);
+        }
+    }
+}
+
+/// A Bevy command that writes a set of blocks to a chunk, if the chunk is
+/// still at the expected edit version.
+struct SetBlocksIfVersionAction<T>
+where
+    T: BlockData,
+{
+    /// The id of the world the chunk is in.
+    world_id: Entity,
+
+    /// The id of the chunk.
+    chunk_id: Entity,
+
+    /// The coordinates of the chunk.
+    chunk_coords: IVec3,
+
+    /// The edit version that the chunk is expected to be at.
+    expected_version: u64,
+
+    /// The local block coordinates and new values of the blocks to write.
+    blocks: Vec<(IVec3, T)>,
+}
+
+impl<T> Command for SetBlocksIfVersionAction<T>
+where
+    T: BlockData,
+{
+    fn apply(self, world: &mut World) {
+        let Some(version) = world.get::<VoxelChunk>(self.chunk_id).map(|c| c.version()) else {
+            return;
+        };
+
+        if version != self.expected_version {
+            if let Some(mut events) = world.get_resource_mut::<Events<ChunkVersionConflict>>() {
+                events.send(ChunkVersionConflict {
+                    world_id:     self.world_id,
+                    chunk_id:     self.chunk_id,
+                    chunk_coords: self.chunk_coords,
+                    expected:     self.expected_version,
+                    actual:       version,
+                });
+            }
+            return;
+        }
+
+        let Some(mut storage) = world.get_mut::<VoxelStorage<T>>(self.chunk_id) else {
+            return;
+        };
+
+        let origin = self.chunk_coords << 4;
+        let mut changed = Vec::with_capacity(self.blocks.len());
+        for (local_pos, data) in self.blocks {
+            storage.set_block(local_pos, data);
+            changed.push(origin + (local_pos & 15));
+        }
+
+        bump_chunk_version::<T>(world, self.chunk_id);
+
+        if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+            for block_coords in changed {
+                queue.push(self.world_id, block_coords);
+            }
+        }
+    }
+}
+
+/// A Bevy command that replaces the entire block storage of a chunk.
+pub(crate) struct ReplaceStorageAction<T>
+where
+    T: BlockData,
+{
+    /// The id of the world the chunk is in.
+    pub(crate) world_id: Entity,
+
+    /// The id of the chunk.
+    pub(crate) chunk_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub(crate) chunk_coords: IVec3,
+
+    /// The new block storage of the chunk.
+    pub(crate) storage: VoxelStorage<T>,
+}
+
+impl<T> Command for ReplaceStorageAction<T>
+where
+    T: BlockData,
+{
+    fn apply(self, world: &mut World) {
+        let Some(mut chunk) = world.get_entity_mut(self.chunk_id) else {
+            return;
+        };
+
+        // Newly inserted storage is reported by the core plugin instead.
+        let replaced = chunk.contains::<VoxelStorage<T>>();
+        chunk.insert(self.storage);
+        bump_chunk_version::<T>(world, self.chunk_id);
+
+        if !replaced {
+            return;
+        }
+
+        if let Some(mut events) = world.get_resource_mut::<Events<ChunkStorageReplaced<T>>>() {
+            events.send(ChunkStorageReplaced::new(
+                self.world_id,
+                self.chunk_id,
+                self.chunk_coords,
+            ));
+        }
+    }
+}
+
+/// A Bevy command that breaks a single block within a voxel world and reports
+/// the removed block.
+struct BreakBlockAction<T>
+where
+    T: BlockData,
+{
+    /// The id of the world that is being edited.
+    world_id: Entity,
+
+    /// The coordinates of the block within the world.
+    block_coords: IVec3,
+
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Command for BreakBlockAction<T>
+where
+    T: BlockData,
+{
+    fn apply(self, world: &mut World) {
+        let Some(pointers) = world.get::<ChunkEntityPointers>(self.world_id) else {
+            return;
+        };
+
+        let block_coords = pointers.topology().wrap_block_coords(self.block_coords);
+        let Some(chunk_id) = pointers.get_chunk_entity(block_coords >> 4) else {
+            return;
+        };
+
+        let Some(mut storage) = world.get_mut::<VoxelStorage<T>>(chunk_id) else {
+            return;
+        };
+
+        let block = storage.get_block(block_coords);
+        storage.set_block(block_coords, T::default());
+        bump_chunk_version::<T>(world, chunk_id);
+
+        if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+            queue.push(self.world_id, block_coords);
+        }
+
+        let block_entities = world
+            .get::<Children>(chunk_id)
+            .map(|children| {
+                children
+                    .iter()
+                    .filter(|child| {
+                        world
+                            .get::<BlockEntity>(**child)
+                            .is_some_and(|b| b.block_coords == block_coords)
+                    })
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(mut events) = world.get_resource_mut::<Events<BlockBroken<T>>>() {
+            events.send(BlockBroken {
+                world_id: self.world_id,
+                block_coords,
+                block,
+                block_entities,
+            });
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
+
     use super::*;
+    use crate::math::Region;
+    use crate::util::transaction::run_transaction;
+    use crate::util::write_queue::VoxelWriteQueue;
+    use crate::Bones3CoreSet;
 
     #[test]
     fn build_world() {
@@ -308,6 +1123,247 @@ mod test {
         Schedule::new().add_systems(validate).run(&mut app.world);
     }
 
+    #[test]
+    fn break_block() {
+        let mut app = App::new();
+        app.add_event::<BlockBroken<u8>>();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            storage.set_block(IVec3::new(1, 2, 3), 5);
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelChunk>>()
+            .single(&app.world);
+        let block_entity = app
+            .world
+            .spawn(BlockEntity {
+                block_coords: IVec3::new(1, 2, 3),
+            })
+            .set_parent(chunk_id)
+            .id();
+
+        fn break_block(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .break_block::<u8>(IVec3::new(1, 2, 3));
+        }
+        Schedule::new().add_systems(break_block).run(&mut app.world);
+
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 3)), 0);
+
+        let events = app.world.resource::<Events<BlockBroken<u8>>>();
+        let broken: Vec<_> = events.get_reader().iter(events).cloned().collect();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].block, 5);
+        assert_eq!(broken[0].block_entities, vec![block_entity]);
+    }
+
+    #[test]
+    fn replace_chunk_storage() {
+        let mut app = App::new();
+        app.add_plugins(crate::Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+            world.spawn_chunk(IVec3::X, ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+        app.update();
+
+        fn replace(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            let mut world = commands.get_world(world_id).unwrap();
+
+            let mut storage = VoxelStorage::<u8>::default();
+            storage.set_block(IVec3::ONE, 3);
+            world
+                .get_chunk(IVec3::ZERO)
+                .unwrap()
+                .replace_storage(storage);
+        }
+        Schedule::new().add_systems(replace).run(&mut app.world);
+
+        let events = app.world.resource::<Events<ChunkStorageReplaced<u8>>>();
+        let replaced: Vec<_> = events
+            .get_reader()
+            .iter(events)
+            .map(|e| e.chunk_coords)
+            .collect();
+        assert_eq!(replaced, vec![IVec3::ZERO, IVec3::ZERO]);
+    }
+
+    #[test]
+    fn despawn_world() {
+        let mut app = App::new();
+        app.add_event::<WorldDespawned>();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            world.spawn_chunk(IVec3::ONE, ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn despawn(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            commands.get_world(world_id).unwrap().despawn_world();
+        }
+        Schedule::new().add_systems(despawn).run(&mut app.world);
+
+        assert_eq!(app.world.entities().len(), 0);
+
+        let events = app.world.resource::<Events<WorldDespawned>>();
+        assert_eq!(events.get_reader().iter(events).count(), 1);
+    }
+
+    #[test]
+    fn despawn_world_twice() {
+        let mut app = App::new();
+        app.add_event::<WorldDespawned>();
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, ())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn despawn(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            commands.get_world(world_id).unwrap().despawn_world();
+            commands.get_world(world_id).unwrap().despawn_world();
+        }
+        Schedule::new().add_systems(despawn).run(&mut app.world);
+
+        assert_eq!(app.world.entities().len(), 0);
+
+        let events = app.world.resource::<Events<WorldDespawned>>();
+        assert_eq!(events.get_reader().iter(events).count(), 1);
+    }
+
+    #[test]
+    fn clear_all_chunks() {
+        let mut app = App::new();
+        app.add_event::<ChunkDespawned>();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            world.spawn_chunk(IVec3::ONE, ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn clear(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            commands.get_world(world_id).unwrap().clear_all_chunks();
+        }
+        Schedule::new().add_systems(clear).run(&mut app.world);
+
+        fn respawn(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            let mut world = commands.get_world(world_id).unwrap();
+            assert!(world.get_chunk_id(IVec3::ZERO).is_none());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+        }
+        Schedule::new().add_systems(respawn).run(&mut app.world);
+
+        assert_eq!(app.world.entities().len(), 2);
+
+        let events = app.world.resource::<Events<ChunkDespawned>>();
+        assert_eq!(events.get_reader().iter(events).count(), 2);
+    }
+
+    #[test]
+    fn clear_chunks_of_despawned_world() {
+        let mut app = App::new();
+        app.add_event::<ChunkDespawned>();
+        app.add_event::<WorldDespawned>();
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, ())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn despawn(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            commands.get_world(world_id).unwrap().despawn_world();
+            commands.get_world(world_id).unwrap().clear_all_chunks();
+        }
+        Schedule::new().add_systems(despawn).run(&mut app.world);
+
+        assert_eq!(app.world.entities().len(), 0);
+
+        let events = app.world.resource::<Events<ChunkDespawned>>();
+        assert_eq!(events.get_reader().iter(events).count(), 0);
+    }
+
+    #[test]
+    fn spawn_chunk_batch() {
+        let mut app = App::new();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            world.spawn_chunks(
+                Region::from_points(IVec3::ZERO, IVec3::ONE)
+                    .iter()
+                    .map(|c| (c, ())),
+            );
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn validate(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            let world = commands.get_world(world_id).unwrap();
+            assert!(world.get_chunk_id(IVec3::ONE).is_some());
+        }
+        Schedule::new().add_systems(validate).run(&mut app.world);
+
+        let chunks = app.world.query::<&VoxelChunk>().iter(&app.world).count();
+        assert_eq!(chunks, 8);
+    }
+
+    #[test]
+    fn wrapped_world_lookup() {
+        let mut app = App::new();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.set_topology(WorldTopology::wrapped(UVec2::new(4, 4)));
+            world.spawn_chunk(IVec3::new(5, 1, -1), ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn validate(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            let mut world = commands.get_world(world_id).unwrap();
+            let chunk = world.get_chunk(IVec3::new(-3, 1, 7)).unwrap();
+            assert_eq!(chunk.chunk_coords(), IVec3::new(1, 1, 3));
+        }
+        Schedule::new().add_systems(validate).run(&mut app.world);
+
+        let chunk = app.world.query::<&VoxelChunk>().single(&app.world);
+        assert_eq!(chunk.chunk_coords(), IVec3::new(1, 1, 3));
+    }
+
     #[test]
     #[should_panic(
         expected = "Tried to spawn chunk at [0, 0, 0], in world 0v0, but it already exists!"
@@ -343,4 +1399,129 @@ mod test {
             .add_systems(b)
             .run(&mut app.world);
     }
+
+    #[test]
+    fn chunk_version_compare_and_set() {
+        let mut app = App::new();
+        app.add_plugins(crate::Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::new(0, 1, 0), VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+        app.update();
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelChunk>>()
+            .single(&app.world);
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 0);
+
+        fn write(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let mut world = commands.get_world(world_query.single()).unwrap();
+            let mut chunk = world.get_chunk(IVec3::new(0, 1, 0)).unwrap();
+            chunk.set_blocks_if_version(0, [(IVec3::new(1, 2, 3), 4u8), (IVec3::ONE, 5)]);
+            chunk.set_blocks_if_version(0, [(IVec3::new(1, 2, 3), 6u8)]);
+        }
+        app.add_systems(Update, write.run_if(run_once()));
+        app.update();
+
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 3)), 4);
+        assert_eq!(storage.get_block(IVec3::ONE), 5);
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 1);
+
+        let events = app.world.resource::<Events<ChunkVersionConflict>>();
+        let conflicts: Vec<_> = events.get_reader().iter(events).copied().collect();
+        assert_eq!(conflicts, vec![ChunkVersionConflict {
+            world_id: app.world.get::<VoxelChunk>(chunk_id).unwrap().world_id(),
+            chunk_id,
+            chunk_coords: IVec3::new(0, 1, 0),
+            expected: 0,
+            actual: 1,
+        }]);
+
+        app.world
+            .get_mut::<VoxelStorage<u8>>(chunk_id)
+            .unwrap()
+            .set_block(IVec3::ZERO, 7);
+        app.update();
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 2);
+
+        app.update();
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 2);
+    }
+
+    #[test]
+    fn chunk_version_counts_every_write_within_a_frame() {
+        let mut app = App::new();
+        app.add_plugins(crate::Bones3CorePlugin::<u8>::default());
+
+        let world_id = app
+            .world
+            .spawn((VoxelWorld, ChunkEntityPointers::default()))
+            .id();
+        let chunk_id = app
+            .world
+            .spawn((
+                VoxelChunk::new(world_id, IVec3::ZERO),
+                VoxelStorage::<u8>::default(),
+            ))
+            .id();
+        app.world
+            .get_mut::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .set_chunk_entity(IVec3::ZERO, Some(chunk_id));
+        app.update();
+
+        fn transaction(world: &mut World) {
+            let world_id = world
+                .query_filtered::<Entity, With<VoxelWorld>>()
+                .single(world);
+            run_transaction::<u8, _>(world, world_id, |tx| tx.set_block(IVec3::ONE, 2)).unwrap();
+        }
+
+        fn compare_and_set(
+            world_query: Query<Entity, With<VoxelWorld>>,
+            mut commands: VoxelCommands,
+        ) {
+            let mut world = commands.get_world(world_query.single()).unwrap();
+            let mut chunk = world.get_chunk(IVec3::ZERO).unwrap();
+            chunk.set_blocks_if_version(1, [(IVec3::ONE, 3u8)]);
+        }
+
+        app.add_systems(
+            PostUpdate,
+            (transaction, compare_and_set)
+                .chain()
+                .after(Bones3CoreSet::BlockWrites)
+                .before(Bones3CoreSet::FlushBlockWrites)
+                .run_if(run_once()),
+        );
+        app.world
+            .resource::<VoxelWriteQueue<u8>>()
+            .push(world_id, IVec3::ZERO, 1);
+        app.update();
+
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.get_block(IVec3::ZERO), 1);
+        assert_eq!(storage.get_block(IVec3::ONE), 2);
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 2);
+
+        let events = app.world.resource::<Events<ChunkVersionConflict>>();
+        let conflicts: Vec<_> = events.get_reader().iter(events).copied().collect();
+        assert_eq!(conflicts, vec![ChunkVersionConflict {
+            world_id,
+            chunk_id,
+            chunk_coords: IVec3::ZERO,
+            expected: 1,
+            actual: 2,
+        }]);
+
+        app.update();
+        assert_eq!(app.world.get::<VoxelChunk>(chunk_id).unwrap().version(), 2);
+    }
 }
@@ -1,12 +1,30 @@
 //! The Bevy system parameter value.
 
-use bevy::ecs::query::{QueryItem, ROQueryItem, ReadOnlyWorldQuery, WorldQuery};
+use std::hash::Hash;
+
+use bevy::ecs::query::{
+    BatchingStrategy,
+    QueryItem,
+    QueryParIter,
+    ROQueryItem,
+    ReadOnlyWorldQuery,
+    WorldQuery,
+};
 use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
 use super::VoxelQueryError;
+use crate::math::{BlockFace, BlockRaycast, Region};
 use crate::storage::chunk_pointers::ChunkEntityPointers;
-use crate::storage::{VoxelChunk, VoxelWorld};
+use crate::storage::{
+    BlockData,
+    ChunkNeighborhood,
+    RegionHash,
+    VoxelChunk,
+    VoxelStorage,
+    VoxelWorld,
+    WorldTopology,
+};
 
 /// A system parameter designed for quickly querying and reading and writing to
 /// voxel worlds and voxel chunks.
@@ -40,6 +58,24 @@ where
         self.query.iter_mut().map(|(_, q)| q)
     }
 
+    /// Creates a readonly parallel iterator over all chunks that match the
+    /// given system query.
+    pub fn par_iter(&self) -> VoxelParIter<'_, '_, Q::ReadOnly, F> {
+        VoxelParIter {
+            inner:    self.query.par_iter(),
+            world_id: None,
+        }
+    }
+
+    /// Creates a mutable parallel iterator over all chunks that match the
+    /// given system query.
+    pub fn par_iter_mut(&mut self) -> VoxelParIter<'_, '_, Q, F> {
+        VoxelParIter {
+            inner:    self.query.par_iter_mut(),
+            world_id: None,
+        }
+    }
+
     /// Gets a readonly reference to the voxel world with the given world id.
     /// The world may or may not have any chunks in it that match the given
     /// system query.
@@ -121,6 +157,18 @@ where
             .map(|(_, q)| q)
     }
 
+    /// Creates a readonly parallel iterator over all chunks within this world
+    /// that match the query.
+    ///
+    /// Like [`VoxelWorldQuery::iter`], this visits all chunks that match the
+    /// query and skips the chunks of other worlds.
+    pub fn par_iter(&self) -> VoxelParIter<'_, '_, Q::ReadOnly, F> {
+        VoxelParIter {
+            inner:    self.voxel_query.query.par_iter(),
+            world_id: Some(self.world_id),
+        }
+    }
+
     /// Gets the chunk at the given chunk coordinates within this world, if it
     /// is both loaded and matches the indicated system query. Otherwise,
     /// this method returns None.
@@ -136,6 +184,78 @@ where
         self.voxel_query.query.get(chunk_id).ok().map(|(_, q)| q)
     }
 
+    /// Creates a readonly iterator over all chunks within this world that
+    /// match the query, and whose bounds pass the given predicate, along with
+    /// their chunk coordinates.
+    ///
+    /// The predicate is given the minimum and maximum corners of each chunk,
+    /// in block coordinates. Only the chunk pointers of this world are
+    /// scanned, so this is much faster than iterating over all chunks when
+    /// there are many voxel worlds.
+    pub fn iter_chunks_where<P>(
+        &'a self,
+        mut predicate: P,
+    ) -> impl Iterator<Item = (IVec3, ROQueryItem<'_, Q>)> + '_
+    where
+        P: FnMut(Vec3, Vec3) -> bool + 'a,
+    {
+        let (_, pointers) = self.voxel_query.chunk_pointers.get(self.world_id).unwrap();
+        pointers
+            .iter()
+            .filter(move |(chunk_coords, _)| {
+                let min = chunk_coords.as_vec3() * 16.0;
+                predicate(min, min + 16.0)
+            })
+            .filter_map(|(chunk_coords, chunk_id)| {
+                let (_, q) = self.voxel_query.query.get(chunk_id).ok()?;
+                Some((chunk_coords, q))
+            })
+    }
+
+    /// Creates a readonly iterator over all loaded chunks within the given
+    /// sector of this world that match the query, along with their chunk
+    /// coordinates.
+    ///
+    /// See [`Region::sector_chunks`] for more information about sectors.
+    pub fn iter_sector(
+        &'a self,
+        sector_coords: IVec3,
+    ) -> impl Iterator<Item = (IVec3, ROQueryItem<'_, Q>)> + '_ {
+        let (_, pointers) = self.voxel_query.chunk_pointers.get(self.world_id).unwrap();
+        pointers
+            .iter_sector(sector_coords)
+            .filter_map(|(chunk_coords, chunk_id)| {
+                let (_, q) = self.voxel_query.query.get(chunk_id).ok()?;
+                Some((chunk_coords, q))
+            })
+    }
+
+    /// Creates a readonly iterator over all chunks within this world that
+    /// match the query, and that intersect the sphere with the given center
+    /// and radius, along with their chunk coordinates.
+    ///
+    /// The sphere is defined in block coordinates. Rather than scanning all
+    /// chunks, each chunk coordinate within the bounds of the sphere is looked
+    /// up directly, so this is best suited for small spheres, such as those
+    /// used for audio occlusion or AI sensing.
+    pub fn iter_chunks_in_sphere(
+        &'a self,
+        center: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (IVec3, ROQueryItem<'_, Q>)> + '_ {
+        let min = ((center - radius) / 16.0).floor().as_ivec3();
+        let max = ((center + radius) / 16.0).floor().as_ivec3();
+
+        Region::from_points(min, max)
+            .iter()
+            .filter(move |chunk_coords| {
+                let min = chunk_coords.as_vec3() * 16.0;
+                let closest = center.clamp(min, min + 16.0);
+                closest.distance_squared(center) <= radius * radius
+            })
+            .filter_map(|chunk_coords| Some((chunk_coords, self.get_chunk(chunk_coords)?)))
+    }
+
     /// Gets the chunk at the given block coordinates within this world, if it
     /// is both loaded and matches the indicated system query. Otherwise,
     pub fn get_chunk_at_block(&'a mut self, block_coords: IVec3) -> Option<ROQueryItem<'_, Q>> {
@@ -146,6 +266,83 @@ where
     pub fn world_id(&self) -> Entity {
         self.world_id
     }
+
+    /// Gets the topology of the voxel world being handled.
+    pub fn topology(&self) -> WorldTopology {
+        self.voxel_query
+            .chunk_pointers
+            .get(self.world_id)
+            .map(|(_, p)| p.topology())
+            .unwrap()
+    }
+}
+
+impl<'w, 's, 'a, T, F> VoxelWorldQuery<'w, 's, 'a, &'static VoxelStorage<T>, F>
+where
+    T: BlockData,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Creates a snapshot of the chunk at the given chunk coordinates along
+    /// with all 26 of its neighbors. Chunks that are not loaded are left
+    /// empty within the neighborhood.
+    pub fn get_neighborhood(&'a self, chunk_coords: IVec3) -> ChunkNeighborhood<T> {
+        ChunkNeighborhood::from_fn(|offset| {
+            self.get_chunk(chunk_coords + offset)
+                .map(|storage| storage.snapshot())
+        })
+    }
+
+    /// Calculates the Merkle-style hash of the given region of chunk
+    /// coordinates within this world. Chunks that are not loaded are hashed
+    /// as missing.
+    pub fn region_hash(&'a self, chunk_region: Region) -> RegionHash
+    where
+        T: Hash,
+    {
+        RegionHash::new(chunk_region, |chunk_coords| {
+            self.get_chunk(chunk_coords)
+                .map(|storage| storage.content_hash())
+        })
+    }
+
+    /// Casts a ray through this world and returns the first loaded block that
+    /// matches the given predicate, up to the given maximum distance.
+    ///
+    /// The ray is given in block coordinates. Blocks within unloaded chunks
+    /// are skipped.
+    pub fn raycast<P>(&'a self, ray: Ray, max_distance: f32, mut is_hit: P) -> Option<BlockHit>
+    where
+        P: FnMut(T) -> bool,
+    {
+        BlockRaycast::new(ray, max_distance).find_map(|step| {
+            let block = self
+                .get_chunk(step.block_coords >> 4)?
+                .get_block(step.block_coords);
+            is_hit(block).then_some(BlockHit {
+                world_id:     self.world_id,
+                block_coords: step.block_coords,
+                face:         step.face,
+                distance:     step.distance,
+            })
+        })
+    }
+}
+
+/// A block that was hit by a voxel raycast.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockHit {
+    /// The id of the world that contains the block.
+    pub world_id: Entity,
+
+    /// The coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The face of the block that was hit, or `None` if the ray started
+    /// inside the block.
+    pub face: Option<BlockFace>,
+
+    /// The distance along the ray, in blocks, at which the block was hit.
+    pub distance: f32,
 }
 
 /// A mutable utility handler for querying chunks within a specific voxel world.
@@ -180,6 +377,18 @@ where
             .map(|(_, q)| q)
     }
 
+    /// Creates a mutable parallel iterator over all chunks within this world
+    /// that match the query.
+    ///
+    /// Like [`VoxelWorldQueryMut::iter_mut`], this visits all chunks that
+    /// match the query and skips the chunks of other worlds.
+    pub fn par_iter_mut(&mut self) -> VoxelParIter<'_, '_, Q, F> {
+        VoxelParIter {
+            inner:    self.voxel_query.query.par_iter_mut(),
+            world_id: Some(self.world_id),
+        }
+    }
+
     /// Gets the chunk at the given chunk coordinates within this world,
     /// mutably, if it is both loaded and matches the indicated system query.
     /// Otherwise, this method returns None.
@@ -210,6 +419,78 @@ where
     pub fn world_id(&self) -> Entity {
         self.world_id
     }
+
+    /// Gets the topology of the voxel world being handled.
+    pub fn topology(&self) -> WorldTopology {
+        self.voxel_query
+            .chunk_pointers
+            .get(self.world_id)
+            .map(|(_, p)| p.topology())
+            .unwrap()
+    }
+}
+
+/// A parallel iterator over the chunks that match a [`VoxelQuery`], optionally
+/// limited to the chunks of a single voxel world.
+///
+/// This forwards to Bevy's parallel query iteration, so chunks are processed
+/// in batches on the compute task pool.
+pub struct VoxelParIter<'w, 's, Q, F>
+where
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// The parallel iterator of the underlying chunk query.
+    inner: QueryParIter<'w, 's, (&'static VoxelChunk, Q), (With<VoxelChunk>, F)>,
+
+    /// The id of the world that chunks must be in, or `None` to visit chunks
+    /// within all worlds.
+    world_id: Option<Entity>,
+}
+
+impl<'w, 's, Q, F> VoxelParIter<'w, 's, Q, F>
+where
+    Q: WorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Changes the batching strategy that is used when iterating.
+    pub fn batching_strategy(mut self, strategy: BatchingStrategy) -> Self {
+        self.inner = self.inner.batching_strategy(strategy);
+        self
+    }
+
+    /// Runs the given function on each chunk in parallel, with mutable access
+    /// to the query items.
+    pub fn for_each_mut<FN>(&mut self, func: FN)
+    where
+        FN: Fn(QueryItem<'w, Q>) + Send + Sync + Clone,
+    {
+        let world_id = self.world_id;
+        self.inner.for_each_mut(move |(chunk, item)| {
+            if world_id.map(|id| id == chunk.world_id()).unwrap_or(true) {
+                func(item);
+            }
+        });
+    }
+}
+
+impl<'w, 's, Q, F> VoxelParIter<'w, 's, Q, F>
+where
+    Q: ReadOnlyWorldQuery + 'static,
+    F: ReadOnlyWorldQuery + 'static,
+{
+    /// Runs the given function on each chunk in parallel.
+    pub fn for_each<FN>(&self, func: FN)
+    where
+        FN: Fn(ROQueryItem<'w, Q>) + Send + Sync + Clone,
+    {
+        let world_id = self.world_id;
+        self.inner.for_each(move |(chunk, item)| {
+            if world_id.map(|id| id == chunk.world_id()).unwrap_or(true) {
+                func(item);
+            }
+        });
+    }
 }
 
 #[cfg(test)]
@@ -219,6 +500,38 @@ mod test {
     use super::*;
     use crate::prelude::VoxelCommands;
 
+    #[test]
+    fn iter_chunks_in_shapes() {
+        let mut app = App::new();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            for x in -3 ..= 3 {
+                world.spawn_chunk(IVec3::new(x, 0, 0), ()).unwrap();
+            }
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn update(world_query: Query<Entity, With<VoxelWorld>>, chunk_query: VoxelQuery<()>) {
+            let world = chunk_query.get_world(world_query.single()).unwrap();
+
+            let mut found: Vec<_> = world
+                .iter_chunks_in_sphere(Vec3::new(8.0, 8.0, 8.0), 20.0)
+                .map(|(coords, _)| coords.x)
+                .collect();
+            found.sort();
+            assert_eq!(found, vec![-1, 0, 1]);
+
+            let mut found: Vec<_> = world
+                .iter_chunks_where(|min, _| min.x >= 32.0)
+                .map(|(coords, _)| coords.x)
+                .collect();
+            found.sort();
+            assert_eq!(found, vec![2, 3]);
+        }
+        Schedule::new().add_systems(update).run(&mut app.world);
+    }
+
     #[test]
     fn iter_chunks_in_world() {
         let mut app = App::new();
@@ -256,4 +569,51 @@ mod test {
         }
         Schedule::new().add_systems(update).run(&mut app.world);
     }
+
+    #[test]
+    fn par_iter_chunks_in_world() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut app = App::new();
+
+        #[derive(Component)]
+        struct WorldMarker;
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world_a = commands.spawn_world(WorldMarker);
+            for x in 0 .. 4 {
+                world_a
+                    .spawn_chunk(IVec3::new(x, 0, 0), VoxelStorage::<u8>::default())
+                    .unwrap();
+            }
+
+            let mut world_b = commands.spawn_world(());
+            world_b
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn update(
+            world_query: Query<Entity, With<WorldMarker>>,
+            mut chunk_query: VoxelQuery<&mut VoxelStorage<u8>>,
+        ) {
+            let world_id = world_query.single();
+            chunk_query
+                .get_world_mut(world_id)
+                .unwrap()
+                .par_iter_mut()
+                .for_each_mut(|mut storage| storage.set_block(IVec3::ZERO, 1));
+
+            let total = AtomicUsize::new(0);
+            let set = AtomicUsize::new(0);
+            chunk_query.par_iter().for_each(|storage| {
+                total.fetch_add(1, Ordering::Relaxed);
+                set.fetch_add(storage.get_block(IVec3::ZERO) as usize, Ordering::Relaxed);
+            });
+            assert_eq!(total.into_inner(), 5);
+            assert_eq!(set.into_inner(), 4);
+        }
+        Schedule::new().add_systems(update).run(&mut app.world);
+    }
 }
@@ -0,0 +1,37 @@
+//! A component for defining the size of a single block within a voxel world.
+
+use bevy::prelude::*;
+
+/// Defines the size of a single block within a voxel world, in world units.
+///
+/// This component may be attached to a voxel world entity in order to create
+/// worlds with blocks that are smaller or larger than one unit. Worlds without
+/// this component use a block scale of `1.0`.
+///
+/// Chunk transforms are scaled by this value, so chunk meshes, which are
+/// always built in block units, are scaled along with them. Chunk anchors
+/// that use transforms also account for this value when calculating their
+/// chunk coordinates.
+#[derive(Debug, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component, Default)]
+pub struct BlockScale(pub f32);
+
+impl Default for BlockScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl BlockScale {
+    /// Converts a position relative to the voxel world entity into block
+    /// coordinates.
+    pub fn to_block_space(self, local_pos: Vec3) -> Vec3 {
+        local_pos / self.0
+    }
+
+    /// Converts a position in block coordinates into a position relative to
+    /// the voxel world entity.
+    pub fn to_local_space(self, block_pos: Vec3) -> Vec3 {
+        block_pos * self.0
+    }
+}
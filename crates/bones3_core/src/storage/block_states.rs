@@ -0,0 +1,137 @@
+//! An interning layer for storing complex, non-`Copy` block states within
+//! voxel storage.
+
+use std::hash::Hash;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// A small, copyable key that refers to a block state interned within a
+/// [`BlockStates`] registry.
+///
+/// This can be used as the block data type of a voxel storage in order to
+/// store block states that contain strings, lists, or other heap data, while
+/// only storing a single `u32` per block. The default handle always refers to
+/// the default block state.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub struct BlockHandle(u32);
+
+impl BlockHandle {
+    /// Gets the raw index of this handle within its registry.
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A resource that interns rich block states, mapping each unique state to a
+/// [`BlockHandle`].
+///
+/// States are never removed once interned, so handles remain valid for the
+/// lifetime of the registry.
+#[derive(Debug, Resource)]
+pub struct BlockStates<S>
+where
+    S: Clone + Eq + Hash + Default + Send + Sync + 'static,
+{
+    /// All interned states, indexed by handle.
+    states: Vec<S>,
+
+    /// The handle of each interned state.
+    handles: HashMap<S, BlockHandle>,
+}
+
+impl<S> Default for BlockStates<S>
+where
+    S: Clone + Eq + Hash + Default + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        let mut registry = Self {
+            states:  vec![],
+            handles: HashMap::new(),
+        };
+        registry.intern(S::default());
+        registry
+    }
+}
+
+impl<S> BlockStates<S>
+where
+    S: Clone + Eq + Hash + Default + Send + Sync + 'static,
+{
+    /// Gets the handle for the given block state, interning it if it has not
+    /// been seen before.
+    pub fn intern(&mut self, state: S) -> BlockHandle {
+        if let Some(handle) = self.handles.get(&state) {
+            return *handle;
+        }
+
+        let handle = BlockHandle(self.states.len() as u32);
+        self.states.push(state.clone());
+        self.handles.insert(state, handle);
+        handle
+    }
+
+    /// Gets the handle for the given block state, if it has been interned.
+    pub fn get_handle(&self, state: &S) -> Option<BlockHandle> {
+        self.handles.get(state).copied()
+    }
+
+    /// Gets the block state that the given handle refers to, or `None` if the
+    /// handle was not created by this registry.
+    pub fn resolve(&self, handle: BlockHandle) -> Option<&S> {
+        self.states.get(handle.0 as usize)
+    }
+
+    /// Gets the number of unique block states that have been interned,
+    /// including the default state.
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Creates an iterator over all interned block states and their handles.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockHandle, &S)> {
+        self.states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (BlockHandle(index as u32), state))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+    struct Sign {
+        text:   String,
+        facing: u8,
+    }
+
+    #[test]
+    fn intern_states() {
+        let mut states = BlockStates::<Sign>::default();
+        let sign = Sign {
+            text:   "Hello".to_string(),
+            facing: 2,
+        };
+
+        let handle = states.intern(sign.clone());
+        assert_eq!(states.intern(sign.clone()), handle);
+        assert_eq!(states.resolve(handle), Some(&sign));
+        assert_eq!(
+            states.resolve(BlockHandle::default()),
+            Some(&Sign::default())
+        );
+        assert_eq!(states.state_count(), 2);
+
+        let mut storage = VoxelStorage::<BlockHandle>::default();
+        storage.set_block(IVec3::new(1, 2, 3), handle);
+        assert_eq!(
+            states.resolve(storage.get_block(IVec3::new(1, 2, 3))),
+            Some(&sign)
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! A registry of block tags, such as "solid" or "flammable", that generic
+//! systems can use to find matching blocks without hardcoding block values.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::BlockHandle;
+
+/// A block data type that can be identified by a numeric block id, in order to
+/// look up its tags within the [`BlockTags`] registry.
+pub trait BlockId {
+    /// Gets the numeric id of this block.
+    fn block_id(&self) -> u32;
+}
+
+impl BlockId for u8 {
+    fn block_id(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl BlockId for u16 {
+    fn block_id(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl BlockId for u32 {
+    fn block_id(&self) -> u32 {
+        *self
+    }
+}
+
+impl BlockId for BlockHandle {
+    fn block_id(&self) -> u32 {
+        self.index()
+    }
+}
+
+/// A handle to a tag that is registered within a [`BlockTags`] registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect)]
+pub struct BlockTag(u16);
+
+impl BlockTag {
+    /// The built-in tag for blocks that can catch fire.
+    pub const FLAMMABLE: BlockTag = BlockTag(1);
+    /// The built-in tag for blocks that are ores.
+    pub const ORE: BlockTag = BlockTag(2);
+    /// The built-in tag for blocks that are solid.
+    pub const SOLID: BlockTag = BlockTag(0);
+
+    /// Gets the raw index of this tag within its registry.
+    pub fn index(&self) -> u16 {
+        self.0
+    }
+}
+
+/// The names of the built-in tags, indexed by their tag.
+const BUILT_IN_TAGS: [&str; 3] = ["solid", "flammable", "ore"];
+
+/// A resource that assigns tags to blocks by their [`BlockId`].
+///
+/// The built-in "solid", "flammable", and "ore" tags are always registered,
+/// and any number of user-defined tags may be added. Each tag stores the ids of
+/// its blocks as a bitset, so checking whether a block has a tag is a single
+/// bit lookup. This allows generic systems, such as fire spreading to every
+/// flammable block, to work with any block registry.
+#[derive(Debug, Resource, Clone)]
+pub struct BlockTags {
+    /// The name of each tag, indexed by tag.
+    names: Vec<String>,
+
+    /// The tag with each name.
+    tags: HashMap<String, BlockTag>,
+
+    /// The bitset of block ids that have each tag, indexed by tag.
+    blocks: Vec<Vec<u64>>,
+}
+
+impl Default for BlockTags {
+    fn default() -> Self {
+        let mut tags = Self {
+            names:  vec![],
+            tags:   HashMap::new(),
+            blocks: vec![],
+        };
+
+        for name in BUILT_IN_TAGS {
+            tags.register_tag(name);
+        }
+
+        tags
+    }
+}
+
+impl BlockTags {
+    /// Gets the tag with the given name, registering it if it does not exist
+    /// yet.
+    pub fn register_tag(&mut self, name: impl Into<String>) -> BlockTag {
+        let name = name.into();
+        if let Some(tag) = self.tags.get(&name) {
+            return *tag;
+        }
+
+        let tag = BlockTag(self.names.len() as u16);
+        self.names.push(name.clone());
+        self.tags.insert(name, tag);
+        self.blocks.push(vec![]);
+        tag
+    }
+
+    /// Gets the tag with the given name, if it has been registered.
+    pub fn get_tag(&self, name: &str) -> Option<BlockTag> {
+        self.tags.get(name).copied()
+    }
+
+    /// Gets the name of the given tag, or `None` if the tag was not created by
+    /// this registry.
+    pub fn tag_name(&self, tag: BlockTag) -> Option<&str> {
+        self.names.get(tag.0 as usize).map(String::as_str)
+    }
+
+    /// Gets the number of registered tags, including the built-in tags.
+    pub fn tag_count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Adds the given tag to the given block.
+    ///
+    /// Panics if the tag was not created by this registry.
+    pub fn add_tag(&mut self, block: impl BlockId, tag: BlockTag) {
+        let (word, bit) = bit_index(block.block_id());
+        let bits = &mut self.blocks[tag.0 as usize];
+        if bits.len() <= word {
+            bits.resize(word + 1, 0);
+        }
+        bits[word] |= bit;
+    }
+
+    /// Removes the given tag from the given block.
+    pub fn remove_tag(&mut self, block: impl BlockId, tag: BlockTag) {
+        let (word, bit) = bit_index(block.block_id());
+        if let Some(bits) = self.blocks.get_mut(tag.0 as usize) {
+            if let Some(value) = bits.get_mut(word) {
+                *value &= !bit;
+            }
+        }
+    }
+
+    /// Checks whether the given block has the given tag.
+    pub fn has_tag(&self, block: impl BlockId, tag: BlockTag) -> bool {
+        let (word, bit) = bit_index(block.block_id());
+        self.blocks
+            .get(tag.0 as usize)
+            .and_then(|bits| bits.get(word))
+            .is_some_and(|value| value & bit != 0)
+    }
+
+    /// Checks whether the given block has the tag with the given name.
+    ///
+    /// This is slower than [`BlockTags::has_tag`], as the tag is looked up by
+    /// name each time.
+    pub fn has_named_tag(&self, block: impl BlockId, name: &str) -> bool {
+        self.get_tag(name)
+            .is_some_and(|tag| self.has_tag(block, tag))
+    }
+
+    /// Creates an iterator over the ids of all blocks with the given tag, in
+    /// ascending order.
+    pub fn blocks_with_tag(&self, tag: BlockTag) -> impl Iterator<Item = u32> + '_ {
+        self.blocks
+            .get(tag.0 as usize)
+            .into_iter()
+            .flat_map(|bits| bits.iter().enumerate())
+            .flat_map(|(word, value)| {
+                (0 .. 64)
+                    .filter(move |bit| value & (1 << bit) != 0)
+                    .map(move |bit| (word * 64 + bit) as u32)
+            })
+    }
+
+    /// Creates an iterator over all tags of the given block.
+    pub fn tags_of(&self, block: impl BlockId) -> impl Iterator<Item = BlockTag> + '_ {
+        let block_id = block.block_id();
+        (0 .. self.names.len())
+            .map(|index| BlockTag(index as u16))
+            .filter(move |tag| self.has_tag(block_id, *tag))
+    }
+}
+
+/// Gets the bitset word index and bit mask of the given block id.
+fn bit_index(block_id: u32) -> (usize, u64) {
+    ((block_id / 64) as usize, 1 << (block_id % 64))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn tag_blocks() {
+        let mut tags = BlockTags::default();
+        assert_eq!(tags.get_tag("flammable"), Some(BlockTag::FLAMMABLE));
+
+        let glowing = tags.register_tag("glowing");
+        assert_eq!(tags.register_tag("glowing"), glowing);
+        assert_eq!(tags.tag_name(glowing), Some("glowing"));
+        assert_eq!(tags.tag_count(), 4);
+
+        tags.add_tag(3u8, BlockTag::FLAMMABLE);
+        tags.add_tag(200u32, BlockTag::FLAMMABLE);
+        tags.add_tag(3u8, glowing);
+        assert!(tags.has_tag(3u8, BlockTag::FLAMMABLE));
+        assert!(tags.has_named_tag(200u32, "flammable"));
+        assert!(!tags.has_tag(4u8, BlockTag::FLAMMABLE));
+        assert!(!tags.has_tag(3u8, BlockTag::ORE));
+        assert!(!tags.has_tag(100_000u32, BlockTag::FLAMMABLE));
+
+        assert_eq!(
+            tags.blocks_with_tag(BlockTag::FLAMMABLE)
+                .collect::<Vec<_>>(),
+            vec![3, 200]
+        );
+        assert_eq!(tags.tags_of(3u8).collect::<Vec<_>>(), vec![
+            BlockTag::FLAMMABLE,
+            glowing
+        ]);
+
+        tags.remove_tag(3u8, BlockTag::FLAMMABLE);
+        assert!(!tags.has_tag(3u8, BlockTag::FLAMMABLE));
+        assert!(!tags.has_tag(BlockHandle::default(), BlockTag::SOLID));
+    }
+}
@@ -0,0 +1,267 @@
+//! Component bundles for spawning voxel worlds and chunks manually, without
+//! going through voxel commands.
+
+use bevy::prelude::*;
+
+use super::chunk_pointers::ChunkEntityPointers;
+use super::{BlockData, BlockScale, UpAxis, VoxelChunk, VoxelStorage, VoxelWorld, WorldTopology};
+use crate::util::chunk_transform::chunk_transform;
+
+/// A bundle containing all of the components that are required for a voxel
+/// world that exists within the scene.
+///
+/// When the `render` feature is enabled, this bundle also contains a
+/// `VisibilityBundle`, so that rendered chunk meshes within the world are
+/// visible.
+///
+/// This bundle is an alternative to
+/// [`VoxelCommands::spawn_world`](crate::query::VoxelCommands::spawn_world)
+/// for cases where voxel commands are not available, such as when building
+/// scenes or spawning from exclusive systems. Additional components, such as a
+/// world generator, may be spawned alongside it within a tuple.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::prelude::*;
+/// #[derive(Component)]
+/// struct Overworld;
+///
+/// fn spawn_world(mut commands: Commands) {
+///     commands.spawn((
+///         VoxelWorldBundle::new().with_block_scale(BlockScale(0.5)),
+///         Overworld,
+///     ));
+/// }
+/// # bevy::ecs::system::assert_is_system(spawn_world);
+/// ```
+#[derive(Bundle, Default)]
+pub struct VoxelWorldBundle {
+    /// The voxel world marker.
+    world: VoxelWorld,
+
+    /// The chunk pointer cache of the world.
+    pointers: ChunkEntityPointers,
+
+    /// The size of a single block within the world.
+    block_scale: BlockScale,
+
+    /// The vertical orientation of the world.
+    up_axis: UpAxis,
+
+    /// The transform of the world.
+    transform: TransformBundle,
+
+    /// The visibility of the world.
+    #[cfg(feature = "render")]
+    visibility: VisibilityBundle,
+}
+
+impl VoxelWorldBundle {
+    /// Creates a new voxel world bundle at the origin, with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the transform of the world.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform.local = transform;
+        self
+    }
+
+    /// Sets the topology of the world.
+    pub fn with_topology(mut self, topology: WorldTopology) -> Self {
+        self.pointers.set_topology(topology);
+        self
+    }
+
+    /// Sets the size of a single block within the world.
+    pub fn with_block_scale(mut self, block_scale: BlockScale) -> Self {
+        self.block_scale = block_scale;
+        self
+    }
+
+    /// Sets the vertical orientation of the world.
+    pub fn with_up_axis(mut self, up_axis: UpAxis) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+}
+
+/// A bundle containing all of the components that are required for a voxel
+/// chunk with block storage that exists within the scene.
+///
+/// Chunks that are spawned with this bundle are registered within the chunk
+/// pointers of their world and made children of it at the start of the
+/// `PostUpdate` schedule, so they may be queried through voxel queries from
+/// that point on. Unlike chunks spawned through
+/// [`VoxelWorldCommands::spawn_chunk`](crate::query::VoxelWorldCommands::spawn_chunk),
+/// components from the [`ChunkTemplate`](super::ChunkTemplate) resource are
+/// not inserted.
+///
+/// If there is already another chunk at the same coordinates within the
+/// world, the new chunk is not registered and a warning is logged.
+///
+/// Like [`VoxelWorldBundle`], this bundle also contains a `VisibilityBundle`
+/// when the `render` feature is enabled.
+#[derive(Bundle)]
+pub struct VoxelChunkBundle<T>
+where
+    T: BlockData,
+{
+    /// The chunk coordinates pointer.
+    chunk: VoxelChunk,
+
+    /// The block data of the chunk.
+    storage: VoxelStorage<T>,
+
+    /// The transform of the chunk.
+    transform: TransformBundle,
+
+    /// The visibility of the chunk.
+    #[cfg(feature = "render")]
+    visibility: VisibilityBundle,
+}
+
+impl<T> VoxelChunkBundle<T>
+where
+    T: BlockData,
+{
+    /// Creates a new, empty chunk bundle for the chunk at the given chunk
+    /// coordinates within the given world.
+    pub fn new(world_id: Entity, chunk_coords: IVec3) -> Self {
+        Self {
+            chunk: VoxelChunk::new(world_id, chunk_coords),
+            storage: VoxelStorage::default(),
+            transform: TransformBundle::from_transform(chunk_transform(
+                chunk_coords,
+                BlockScale::default(),
+            )),
+            #[cfg(feature = "render")]
+            visibility: VisibilityBundle::default(),
+        }
+    }
+
+    /// Sets the block data of the chunk.
+    pub fn with_storage(mut self, storage: VoxelStorage<T>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Sets the transform of the chunk to match the given block scale of its
+    /// world.
+    ///
+    /// Chunk transforms are corrected automatically once the chunk is spawned,
+    /// so this only avoids a single frame with an incorrect transform.
+    pub fn with_block_scale(mut self, block_scale: BlockScale) -> Self {
+        self.transform.local = chunk_transform(self.chunk.chunk_coords(), block_scale);
+        self
+    }
+}
+
+/// This system registers all newly spawned chunks that are missing from the
+/// chunk pointers of their world, such as chunks spawned with a
+/// [`VoxelChunkBundle`], and parents them to their world.
+pub(crate) fn register_spawned_chunks(
+    mut worlds: Query<&mut ChunkEntityPointers, With<VoxelWorld>>,
+    mut chunks: Query<(Entity, &mut VoxelChunk, Option<&Parent>), Added<VoxelChunk>>,
+    mut commands: Commands,
+) {
+    for (chunk_id, mut chunk_meta, parent) in chunks.iter_mut() {
+        let world_id = chunk_meta.world_id();
+        let Ok(mut pointers) = worlds.get_mut(world_id) else {
+            continue;
+        };
+
+        let chunk_coords = pointers
+            .topology()
+            .wrap_chunk_coords(chunk_meta.chunk_coords());
+        match pointers.get_chunk_entity(chunk_coords) {
+            Some(existing) if existing == chunk_id => continue,
+            Some(existing) => {
+                warn!(
+                    "Chunk {chunk_id:?} was spawned at {chunk_coords}, in world {world_id:?}, but \
+                     chunk {existing:?} already exists there"
+                );
+                continue;
+            },
+            None => {},
+        }
+
+        pointers.set_chunk_entity(chunk_coords, Some(chunk_id));
+        if chunk_coords != chunk_meta.chunk_coords() {
+            *chunk_meta = VoxelChunk::new(world_id, chunk_coords);
+        }
+
+        if parent.map(|p| p.get()) != Some(world_id) {
+            commands.entity(chunk_id).set_parent(world_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::block_update::BlockUpdatePlugin;
+
+    #[test]
+    fn spawn_from_bundles() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        let world_id = app
+            .world
+            .spawn(VoxelWorldBundle::new().with_block_scale(BlockScale(2.0)))
+            .id();
+
+        let mut storage = VoxelStorage::default();
+        storage.set_block(IVec3::ONE, 5);
+        let chunk_id = app
+            .world
+            .spawn(
+                VoxelChunkBundle::<u8>::new(world_id, IVec3::new(1, 0, -1)).with_storage(storage),
+            )
+            .id();
+
+        app.update();
+
+        let pointers = app.world.get::<ChunkEntityPointers>(world_id).unwrap();
+        assert_eq!(
+            pointers.get_chunk_entity(IVec3::new(1, 0, -1)),
+            Some(chunk_id)
+        );
+        assert_eq!(
+            app.world.get::<Parent>(chunk_id).map(|p| p.get()),
+            Some(world_id)
+        );
+        assert_eq!(
+            app.world.get::<Transform>(chunk_id).unwrap().translation,
+            Vec3::new(32.0, 0.0, -32.0)
+        );
+
+        fn read(query: VoxelQuery<&VoxelStorage<u8>>, worlds: Query<Entity, With<VoxelWorld>>) {
+            let world = query.get_world(worlds.single()).unwrap();
+            let storage = world.get_chunk(IVec3::new(1, 0, -1)).unwrap();
+            assert_eq!(storage.get_block(IVec3::ONE), 5);
+        }
+        Schedule::new().add_systems(read).run(&mut app.world);
+    }
+
+    #[test]
+    fn register_bundles_when_block_updates_added_first() {
+        let mut app = App::new();
+        app.add_plugins((BlockUpdatePlugin, Bones3CorePlugin::<u8>::default()));
+
+        let world_id = app.world.spawn(VoxelWorldBundle::new()).id();
+        let chunk_id = app
+            .world
+            .spawn(VoxelChunkBundle::<u8>::new(world_id, IVec3::ZERO))
+            .id();
+        app.update();
+
+        let pointers = app.world.get::<ChunkEntityPointers>(world_id).unwrap();
+        assert_eq!(pointers.get_chunk_entity(IVec3::ZERO), Some(chunk_id));
+    }
+}
@@ -1,20 +1,85 @@
 //! A voxel chunk component.
 
+use bevy::ecs::component::Tick;
 use bevy::prelude::*;
 
 /// A voxel world marker component.
 #[derive(Debug, Component, Default, Reflect)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub struct VoxelWorld;
 
+/// An event that is sent after a voxel world, along with all of its chunks,
+/// has been despawned using
+/// [`VoxelWorldCommands::despawn_world`](crate::query::VoxelWorldCommands::despawn_world).
+///
+/// Plugins that store data for a voxel world outside of the world entity
+/// should listen for this event in order to clean up that data.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WorldDespawned {
+    /// The id of the world that was despawned.
+    pub world_id: Entity,
+}
+
+/// An event that is sent after a chunk has been despawned through voxel
+/// commands, either individually or when all chunks of a world are cleared.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkDespawned {
+    /// The id of the world the chunk was in.
+    pub world_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub chunk_coords: IVec3,
+}
+
+/// An event that is sent when a compare-and-set write made with
+/// [`VoxelChunkCommands::set_blocks_if_version`](crate::query::VoxelChunkCommands::set_blocks_if_version)
+/// was rejected, because the chunk had been edited since the expected version.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkVersionConflict {
+    /// The id of the world the chunk is in.
+    pub world_id: Entity,
+
+    /// The id of the chunk.
+    pub chunk_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub chunk_coords: IVec3,
+
+    /// The version that the write expected the chunk to be at.
+    pub expected: u64,
+
+    /// The actual version of the chunk.
+    pub actual: u64,
+}
+
 /// A pointer to indicate the coordinates of a chunk.
+///
+/// This component also tracks the edit version of the chunk, which starts at
+/// zero when the chunk is spawned and increases whenever its block storage is
+/// written to. This can be used by networking and persistence layers to
+/// detect concurrent edits.
 #[derive(Debug, Component, Reflect, PartialEq, Eq, Hash)]
+#[reflect(Component)]
 pub struct VoxelChunk {
     /// The world id this chunk is in.
     world_id: Entity,
 
     /// The coordinates of this chunk.
     chunk_coords: IVec3,
+
+    /// The edit version of this chunk.
+    version: u64,
+
+    /// The change tick of the latest block storage write that has already
+    /// been counted within the edit version.
+    #[reflect(ignore)]
+    counted_tick: u32,
+}
+
+impl FromWorld for VoxelChunk {
+    fn from_world(_: &mut World) -> Self {
+        Self::new(Entity::PLACEHOLDER, IVec3::ZERO)
+    }
 }
 
 impl VoxelChunk {
@@ -23,6 +88,8 @@ impl VoxelChunk {
         Self {
             world_id,
             chunk_coords,
+            version: 0,
+            counted_tick: 0,
         }
     }
 
@@ -35,4 +102,44 @@ impl VoxelChunk {
     pub fn chunk_coords(&self) -> IVec3 {
         self.chunk_coords
     }
+
+    /// Gets the edit version of this chunk.
+    ///
+    /// Writes made through voxel commands, transactions, brushes, replace
+    /// jobs, or the
+    /// [`VoxelWriteQueue`](crate::util::write_queue::VoxelWriteQueue) increase
+    /// the version as soon as they are applied. Writes made directly to the
+    /// block storage, such as through voxel queries, are detected at the end
+    /// of the frame within
+    /// [`Bones3CoreSet::BlockUpdates`](crate::Bones3CoreSet::BlockUpdates),
+    /// and increase the version once per frame.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Increases the edit version of this chunk by one, for a block storage
+    /// write that was made at the given change tick.
+    pub(crate) fn bump_version(&mut self, written: Tick) {
+        self.version += 1;
+        self.counted_tick = written.get();
+    }
+
+    /// Checks whether the block storage write made at the given change tick
+    /// has already been counted within the edit version.
+    pub(crate) fn is_counted(&self, written: Tick) -> bool {
+        self.counted_tick == written.get()
+    }
+}
+
+/// A component that attaches an entity to a single block within a chunk, such
+/// as a chest inventory or a sign text.
+///
+/// Entities with this component should be children of the chunk entity that
+/// contains the block. They are reported when the block is broken using
+/// [`VoxelWorldCommands::break_block`](crate::query::VoxelWorldCommands::break_block).
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub struct BlockEntity {
+    /// The block coordinates that this entity is attached to.
+    pub block_coords: IVec3,
 }
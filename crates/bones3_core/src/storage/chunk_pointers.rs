@@ -4,6 +4,7 @@
 use bevy::prelude::*;
 
 use crate::math::Region;
+use crate::storage::WorldTopology;
 
 /// The depth value of the cache, to determine the memory size of one block.
 const CACHE_DEPTH: u8 = 5;
@@ -57,6 +58,14 @@ impl Sector {
         self.chunks[index] = entity;
     }
 
+    /// Iterates over all chunk pointers within this sector.
+    fn iter(&self) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        self.region()
+            .iter()
+            .zip(self.chunks.iter())
+            .filter_map(|(coords, entity)| entity.map(|e| (coords, e)))
+    }
+
     /// Checks if this sector is currently empty.
     fn is_empty(&self) -> bool {
         self.active_chunks == 0
@@ -68,19 +77,67 @@ impl Sector {
 ///
 /// This component works by caching the entity ids of chunks, and must be
 /// updated each time a new chunk entity is spawned or despawned.
+///
+/// All chunk coordinates are wrapped according to the topology of the world
+/// before being looked up.
 #[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct ChunkEntityPointers {
     /// A list of sectors that are currently active.
     #[reflect(ignore)]
     sectors: Vec<Sector>,
+
+    /// The topology of the world.
+    topology: WorldTopology,
 }
 
 impl ChunkEntityPointers {
+    /// Gets the topology of the world.
+    pub fn topology(&self) -> WorldTopology {
+        self.topology
+    }
+
+    /// Sets the topology of the world.
+    ///
+    /// Existing chunk pointers are not moved, so this should be called before
+    /// any chunks are spawned.
+    pub fn set_topology(&mut self, topology: WorldTopology) {
+        self.topology = topology;
+    }
+
+    /// Iterates over the chunk coordinates and entity ids of all chunk pointers
+    /// within this cache.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        self.sectors.iter().flat_map(|s| s.iter())
+    }
+
+    /// Iterates over the chunk coordinates and entity ids of all chunk pointers
+    /// within the sector at the given sector coordinates.
+    ///
+    /// See [`Region::sector_chunks`] for more information about sectors.
+    pub fn iter_sector(&self, sector_coords: IVec3) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        // Cache blocks are larger than sectors, so each sector is entirely
+        // within a single cache block.
+        let region = Region::sector_chunks(sector_coords);
+        let cache_coords = region.min() >> CACHE_DEPTH;
+        self.sectors
+            .iter()
+            .filter(move |s| s.sector_coords == cache_coords)
+            .flat_map(|s| s.iter())
+            .filter(move |(chunk_coords, _)| region.contains(*chunk_coords))
+    }
+
+    /// Removes all chunk entity pointers, keeping the world topology.
+    pub fn clear(&mut self) {
+        self.sectors.clear();
+    }
+
     /// Gets the entity id of the chunk at the given chunk coordinates.
     ///
     /// If there is no known chunk at the given coordinates, then None is
     /// returned.
     pub fn get_chunk_entity(&self, chunk_coords: IVec3) -> Option<Entity> {
+        let chunk_coords = self.topology.wrap_chunk_coords(chunk_coords);
         let sector_coords = chunk_coords >> CACHE_DEPTH;
         self.sectors
             .iter()
@@ -90,6 +147,7 @@ impl ChunkEntityPointers {
 
     /// Sets the entity id of the chunk at the given coordinates.
     pub fn set_chunk_entity(&mut self, chunk_coords: IVec3, entity: Option<Entity>) {
+        let chunk_coords = self.topology.wrap_chunk_coords(chunk_coords);
         let sector_coords = chunk_coords >> CACHE_DEPTH;
         let sector = match self
             .sectors
@@ -1,20 +1,98 @@
 //! Handler components for storing data within a chunk.
 
+use std::marker::PhantomData;
+use std::sync::Arc;
+
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 
-use crate::math::Region;
+use super::VoxelChunk;
+use crate::math::{greedy_boxes, Region};
 
 /// A blanket trait for data types that can be safely stored within a voxel
 /// world.
 pub trait BlockData: Default + Copy + Send + Sync + TypePath + 'static {}
 impl<T> BlockData for T where T: Default + Copy + Send + Sync + TypePath + 'static {}
 
+/// An event that is sent when a block is broken using
+/// [`VoxelWorldCommands::break_block`](crate::query::VoxelWorldCommands::break_block).
+///
+/// This can be used to spawn item drops, or to clean up any data associated
+/// with the block.
+#[derive(Debug, Event, Clone, PartialEq, Eq)]
+pub struct BlockBroken<T>
+where
+    T: BlockData,
+{
+    /// The id of the world the block was in.
+    pub world_id: Entity,
+
+    /// The coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The block data value that was removed.
+    pub block: T,
+
+    /// All [`BlockEntity`](super::BlockEntity) entities that were attached to
+    /// the block. These entities are not despawned automatically.
+    pub block_entities: Vec<Entity>,
+}
+
+/// An event that is sent whenever the `VoxelStorage<T>` component of a chunk
+/// is inserted or replaced as a whole, rather than edited block by block.
+///
+/// This happens when a chunk finishes generating, is loaded from disk, or is
+/// synced from a server in full. Systems that cache data derived from the
+/// blocks of a chunk, such as lighting, heightmaps, or collision shapes,
+/// should invalidate that data for the chunk when receiving this event, as no
+/// per-block events are sent for the new blocks.
+///
+/// Insertions are detected automatically. Replacing an existing storage
+/// component is only reported when done through
+/// [`VoxelChunkCommands::replace_storage`](crate::query::VoxelChunkCommands::replace_storage).
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkStorageReplaced<T>
+where
+    T: BlockData,
+{
+    /// The id of the world the chunk is in.
+    pub world_id: Entity,
+
+    /// The id of the chunk.
+    pub chunk_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub chunk_coords: IVec3,
+
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ChunkStorageReplaced<T>
+where
+    T: BlockData,
+{
+    /// Creates a new storage replaced event for the given chunk.
+    pub(crate) fn new(world_id: Entity, chunk_id: Entity, chunk_coords: IVec3) -> Self {
+        Self {
+            world_id,
+            chunk_id,
+            chunk_coords,
+            _phantom: PhantomData,
+        }
+    }
+}
+
 /// A storage component for containing a 16x16x16 grid of block data. This is
 /// usually intended to be used on a voxel chunk component.
 ///
 /// By default it is filled with the default value for `T`.
-#[derive(Debug, Component, Reflect)]
+///
+/// The block data is shared between clones of this storage until one of them
+/// is modified, at which point that clone copies the data. This makes it cheap
+/// to take a [`VoxelStorage::snapshot`] for reading within async tasks.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct VoxelStorage<T>
 where
     T: BlockData,
@@ -22,7 +100,12 @@ where
     // TODO: Do not ignore this. It makes serialization of worlds impossible.
     /// The block data array for this chunk.
     #[reflect(ignore)]
-    blocks: Option<Box<[T; 4096]>>,
+    blocks: Option<Arc<[T; 4096]>>,
+
+    /// A bitmask of the blocks that were set since the edited blocks were last
+    /// taken, or `None` if edits are not being tracked.
+    #[reflect(ignore)]
+    edited: Option<Box<[u64; 64]>>,
 }
 
 impl<T> Default for VoxelStorage<T>
@@ -32,6 +115,7 @@ where
     fn default() -> Self {
         Self {
             blocks: None,
+            edited: None,
         }
     }
 }
@@ -40,6 +124,33 @@ impl<T> VoxelStorage<T>
 where
     T: BlockData,
 {
+    /// Creates a new storage where every block is set to the given value.
+    ///
+    /// The block data is shared between all clones of the returned storage
+    /// until they are modified, so many chunks may be filled with the same
+    /// block, such as when filling a whole sector, at the cost of a single
+    /// allocation.
+    pub fn filled(data: T) -> Self {
+        Self {
+            blocks: Some(Arc::new([data; 4096])),
+            edited: None,
+        }
+    }
+
+    /// Gets the block that every block within this storage is set to, or
+    /// `None` if this storage contains more than one kind of block.
+    pub fn uniform_block(&self) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let Some(arr) = &self.blocks else {
+            return Some(T::default());
+        };
+
+        let first = arr[0];
+        arr.iter().all(|block| *block == first).then_some(first)
+    }
+
     /// Gets the block data at the local grid coordinates within this storage
     /// component.
     ///
@@ -61,12 +172,195 @@ where
     pub fn set_block(&mut self, local_pos: IVec3, data: T) {
         let index = Region::CHUNK.point_to_index(local_pos & 15).unwrap();
         match &mut self.blocks {
-            Some(arr) => arr[index] = data,
+            Some(arr) => Arc::make_mut(arr)[index] = data,
             None => {
-                let mut chunk = Box::new([T::default(); 4096]);
-                chunk[index] = data;
+                let mut chunk = Arc::new([T::default(); 4096]);
+                Arc::make_mut(&mut chunk)[index] = data;
                 self.blocks = Some(chunk);
             },
         }
+
+        if let Some(edited) = &mut self.edited {
+            edited[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    /// Takes the local coordinates of all blocks that were set since this was
+    /// last called, and starts tracking edits if they were not tracked yet.
+    ///
+    /// Returns `None` if edits were not being tracked, such as for a newly
+    /// created storage, in which case any block may have changed.
+    pub(crate) fn take_edited_blocks(&mut self) -> Option<Vec<IVec3>> {
+        let Some(edited) = &mut self.edited else {
+            self.edited = Some(Box::new([0; 64]));
+            return None;
+        };
+
+        let mut blocks = vec![];
+        for (word_index, word) in edited.iter_mut().enumerate() {
+            while *word != 0 {
+                let index = word_index * 64 + word.trailing_zeros() as usize;
+                blocks.push(Region::CHUNK.index_to_point(index).unwrap());
+                *word &= *word - 1;
+            }
+        }
+
+        Some(blocks)
+    }
+
+    /// Creates an immutable snapshot of this storage.
+    ///
+    /// This does not copy the block data. Instead, the data is shared until
+    /// either this storage or the snapshot is modified. As such, snapshots are
+    /// cheap to create and can be sent to async tasks while the original
+    /// storage continues to be edited.
+    pub fn snapshot(&self) -> VoxelStorage<T> {
+        Self {
+            blocks: self.blocks.clone(),
+            edited: None,
+        }
+    }
+
+    /// Checks whether this storage currently shares its block data with
+    /// another storage, such as a snapshot.
+    pub fn is_shared(&self) -> bool {
+        self.blocks
+            .as_ref()
+            .is_some_and(|arr| Arc::strong_count(arr) > 1)
+    }
+
+    /// Checks whether this storage shares the same block data as the given
+    /// storage, such as when one is an unmodified snapshot of the other.
+    ///
+    /// Storages that share their block data are always equal, so this can be
+    /// used to skip comparing their blocks.
+    pub fn shares_data_with(&self, other: &VoxelStorage<T>) -> bool {
+        match (&self.blocks, &other.blocks) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Converts all solid blocks within this storage into a small set of
+    /// axis-aligned boxes, using [`greedy_boxes`].
+    ///
+    /// Each box is returned as a pair of its minimum and maximum local block
+    /// coordinates, both inclusive.
+    pub fn solid_boxes<F>(&self, is_solid: F) -> Vec<(IVec3, IVec3)>
+    where
+        F: Fn(T) -> bool,
+    {
+        if self.blocks.is_none() && !is_solid(T::default()) {
+            return vec![];
+        }
+
+        greedy_boxes(Region::CHUNK, |pos| is_solid(self.get_block(pos)))
+    }
+}
+
+/// This system sends a [`ChunkStorageReplaced`] event for every chunk that
+/// has had a `VoxelStorage<T>` component inserted since the last frame.
+pub(crate) fn send_inserted_storage_events<T>(
+    chunks: Query<(Entity, &VoxelChunk), Added<VoxelStorage<T>>>,
+    mut events: EventWriter<ChunkStorageReplaced<T>>,
+) where
+    T: BlockData,
+{
+    for (chunk_id, chunk_meta) in chunks.iter() {
+        events.send(ChunkStorageReplaced::new(
+            chunk_meta.world_id(),
+            chunk_id,
+            chunk_meta.chunk_coords(),
+        ));
+    }
+}
+
+/// Increases the edit version of the given chunk for the latest write to its
+/// `VoxelStorage<T>` component, if the chunk exists.
+pub(crate) fn bump_chunk_version<T>(world: &mut World, chunk_id: Entity)
+where
+    T: BlockData,
+{
+    let Some(mut chunk) = world.get_entity_mut(chunk_id) else {
+        return;
+    };
+
+    let Some(ticks) = chunk.get_change_ticks::<VoxelStorage<T>>() else {
+        return;
+    };
+
+    if let Some(mut chunk_meta) = chunk.get_mut::<VoxelChunk>() {
+        chunk_meta.bump_version(ticks.last_changed_tick());
+    }
+}
+
+/// This system increases the edit version of every chunk that has had its
+/// `VoxelStorage<T>` written to directly since the last frame.
+///
+/// Writes that were already counted by the write path that made them, as well
+/// as newly spawned chunks, are skipped.
+pub(crate) fn bump_chunk_versions<T>(
+    mut chunks: Query<(&mut VoxelChunk, Ref<VoxelStorage<T>>), Changed<VoxelStorage<T>>>,
+) where
+    T: BlockData,
+{
+    for (mut chunk_meta, storage) in chunks.iter_mut() {
+        let written = storage.last_changed();
+        if !chunk_meta.is_added() && !chunk_meta.is_counted(written) {
+            chunk_meta.bump_version(written);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_copy_on_write() {
+        let mut storage = VoxelStorage::<u8>::default();
+        storage.set_block(IVec3::new(1, 2, 3), 4);
+
+        let snapshot = storage.snapshot();
+        assert!(storage.is_shared());
+
+        storage.set_block(IVec3::new(1, 2, 3), 5);
+        assert!(!storage.is_shared());
+        assert_eq!(snapshot.get_block(IVec3::new(1, 2, 3)), 4);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 3)), 5);
+    }
+
+    #[test]
+    fn uniform_storage() {
+        assert_eq!(VoxelStorage::<u8>::default().uniform_block(), Some(0));
+
+        let mut storage = VoxelStorage::<u8>::filled(7);
+        let copy = storage.clone();
+        assert!(storage.is_shared());
+        assert_eq!(storage.uniform_block(), Some(7));
+
+        storage.set_block(IVec3::new(15, 15, 15), 2);
+        assert_eq!(storage.uniform_block(), None);
+        assert_eq!(copy.uniform_block(), Some(7));
+    }
+
+    #[test]
+    fn track_edited_blocks() {
+        let mut storage = VoxelStorage::<u8>::default();
+        storage.set_block(IVec3::new(1, 2, 3), 4);
+        assert_eq!(storage.take_edited_blocks(), None);
+
+        storage.set_block(IVec3::new(15, 15, 15), 5);
+        storage.set_block(IVec3::new(0, 0, 0), 6);
+        storage.set_block(IVec3::new(15, 15, 15), 7);
+        assert!(storage.snapshot().take_edited_blocks().is_none());
+
+        let mut edited = storage.take_edited_blocks().unwrap();
+        edited.sort_by_key(|pos| pos.to_array());
+        assert_eq!(edited, vec![IVec3::new(0, 0, 0), IVec3::new(15, 15, 15)]);
+        assert_eq!(storage.take_edited_blocks(), Some(vec![]));
     }
 }
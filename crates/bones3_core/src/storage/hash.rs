@@ -0,0 +1,258 @@
+//! Deterministic hashing of block data, for testing, desync detection, and
+//! integrity checks.
+
+use std::hash::{Hash, Hasher};
+
+use bevy::prelude::*;
+
+use super::{BlockData, VoxelStorage, WorldSnapshot};
+use crate::math::Region;
+
+/// The number of chunks along each axis of a hash cell.
+const HASH_CELL_SIZE: i32 = 4;
+
+/// A hasher that produces the same output on every platform.
+///
+/// This hasher uses the 64 bit FNV-1a algorithm. All integers are written in
+/// little endian byte order, and `usize` and `isize` values are always written
+/// as 64 bit integers, so types that derive [`Hash`] produce stable hashes
+/// regardless of the platform.
+///
+/// This hasher is not resistant to collision attacks, and should not be used
+/// for hash maps with untrusted keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableHasher {
+    /// The current hash value.
+    hash: u64,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self {
+            hash: 0xCBF2_9CE4_8422_2325,
+        }
+    }
+}
+
+impl StableHasher {
+    /// Creates a new stable hasher.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+impl<T> VoxelStorage<T>
+where
+    T: BlockData + Hash,
+{
+    /// Gets a hash of all block data within this storage.
+    ///
+    /// This hash is stable across platforms and program runs, as long as the
+    /// [`Hash`] implementation of `T` only writes its fields, as derived
+    /// implementations do. An unmodified storage has the same hash as a
+    /// storage that is filled with the default value for `T`.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        for local_pos in Region::CHUNK.iter() {
+            self.get_block(local_pos).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// A Merkle-style hash of a region of chunks.
+///
+/// The region is split into hash cells of 4x4x4 chunks, aligned to the chunk
+/// grid. Each cell is hashed from the content hashes of its chunks, and the
+/// root hash is created from the cell hashes. Comparing the cell hashes of two
+/// region hashes finds which parts of a world differ without comparing every
+/// chunk.
+///
+/// Hash cells are unrelated to the world sectors used by
+/// [`WorldSector`](crate::storage::WorldSector).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionHash {
+    /// The root hash of the region.
+    pub root: u64,
+
+    /// The coordinates and hashes of every hash cell that overlaps the
+    /// region, sorted by their coordinates. Cell coordinates are chunk
+    /// coordinates divided by 4.
+    pub cells: Vec<(IVec3, u64)>,
+}
+
+impl RegionHash {
+    /// Calculates the region hash for the given region of chunk coordinates,
+    /// using the given function to get the content hash of each chunk.
+    ///
+    /// Chunks within the region that are missing should return `None`, which
+    /// is hashed differently than any chunk content.
+    pub fn new<F>(chunk_region: Region, mut chunk_hash: F) -> Self
+    where
+        F: FnMut(IVec3) -> Option<u64>,
+    {
+        let to_cell = |chunk_coords: IVec3| {
+            IVec3::new(
+                chunk_coords.x.div_euclid(HASH_CELL_SIZE),
+                chunk_coords.y.div_euclid(HASH_CELL_SIZE),
+                chunk_coords.z.div_euclid(HASH_CELL_SIZE),
+            )
+        };
+        let cell_region =
+            Region::from_points(to_cell(chunk_region.min()), to_cell(chunk_region.max()));
+
+        let mut cells = Vec::with_capacity(cell_region.count());
+        for cell_coords in cell_region.iter() {
+            let cell =
+                Region::from_size(cell_coords * HASH_CELL_SIZE, IVec3::splat(HASH_CELL_SIZE))
+                    .unwrap();
+
+            let mut hasher = StableHasher::new();
+            cell_coords.to_array().hash(&mut hasher);
+            for chunk_coords in cell.iter().filter(|c| chunk_region.contains(*c)) {
+                chunk_coords.to_array().hash(&mut hasher);
+                chunk_hash(chunk_coords).hash(&mut hasher);
+            }
+
+            cells.push((cell_coords, hasher.finish()));
+        }
+
+        cells.sort_by_key(|(coords, _)| coords.to_array());
+
+        let mut hasher = StableHasher::new();
+        chunk_region.min().to_array().hash(&mut hasher);
+        chunk_region.max().to_array().hash(&mut hasher);
+        for (coords, hash) in cells.iter() {
+            coords.to_array().hash(&mut hasher);
+            hash.hash(&mut hasher);
+        }
+
+        Self {
+            root: hasher.finish(),
+            cells,
+        }
+    }
+
+    /// Gets the coordinates of all hash cells that have a different hash than
+    /// the same cell within the given region hash, or that only exist within
+    /// one of the two region hashes.
+    pub fn diff(&self, other: &RegionHash) -> Vec<IVec3> {
+        if self.root == other.root {
+            return vec![];
+        }
+
+        let mut changed: Vec<IVec3> = self
+            .cells
+            .iter()
+            .filter(|cell| !other.cells.contains(cell))
+            .map(|(coords, _)| *coords)
+            .collect();
+
+        for (coords, _) in other.cells.iter() {
+            if !self.cells.iter().any(|(c, _)| c == coords) {
+                changed.push(*coords);
+            }
+        }
+
+        changed
+    }
+}
+
+impl<T> WorldSnapshot<T>
+where
+    T: BlockData + Hash,
+{
+    /// Calculates the Merkle-style hash of the given region of chunk
+    /// coordinates within this snapshot.
+    pub fn region_hash(&self, chunk_region: Region) -> RegionHash {
+        RegionHash::new(chunk_region, |chunk_coords| {
+            self.get_chunk(chunk_coords)
+                .map(|storage| storage.content_hash())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn stable_region_hash() {
+        let empty = VoxelStorage::<u16>::default();
+        let mut filled = VoxelStorage::<u16>::default();
+        filled.set_block(IVec3::new(1, 2, 3), 0);
+        assert_eq!(empty.content_hash(), filled.content_hash());
+
+        filled.set_block(IVec3::new(1, 2, 3), 7);
+        assert_ne!(empty.content_hash(), filled.content_hash());
+
+        let region = Region::from_points(IVec3::splat(-2), IVec3::splat(5));
+        let mut a = WorldSnapshot::<u16>::new();
+        for chunk_coords in region.iter() {
+            a.insert_chunk(chunk_coords, VoxelStorage::default());
+        }
+
+        let mut b = a.clone();
+        assert_eq!(a.region_hash(region), b.region_hash(region));
+
+        b.set_block(IVec3::new(70, 0, 0), 1);
+        let (hash_a, hash_b) = (a.region_hash(region), b.region_hash(region));
+        assert_ne!(hash_a.root, hash_b.root);
+        assert_eq!(hash_a.diff(&hash_b), vec![IVec3::new(1, 0, 0)]);
+    }
+}
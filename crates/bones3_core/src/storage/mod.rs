@@ -4,9 +4,33 @@
 //! Unloaded sections of the world must be loaded before they can be properly
 //! manipulated.
 
+mod block_scale;
+mod block_states;
+mod block_tags;
+mod bundles;
 mod chunk;
 pub(crate) mod chunk_pointers;
 mod data;
+mod hash;
+mod neighborhood;
+pub(crate) mod template;
+mod topology;
+mod up_axis;
+mod world_diff;
+mod world_sector;
+mod world_snapshot;
 
+pub use block_scale::*;
+pub use block_states::*;
+pub use block_tags::*;
+pub use bundles::*;
 pub use chunk::*;
 pub use data::*;
+pub use hash::*;
+pub use neighborhood::*;
+pub use template::*;
+pub use topology::*;
+pub use up_axis::*;
+pub use world_diff::*;
+pub use world_sector::*;
+pub use world_snapshot::*;
@@ -0,0 +1,219 @@
+//! A snapshot of the 3x3x3 group of chunks surrounding a chunk.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::{BlockData, VoxelStorage};
+use crate::math::Region;
+
+/// An immutable snapshot of the block data within a chunk and all 26 of its
+/// neighboring chunks.
+///
+/// This is useful for any algorithm that needs to read blocks slightly past
+/// the edges of a chunk, such as meshing, lighting, or cellular automatons.
+/// Missing neighbors are treated as if they were filled with the default
+/// value for `T`.
+///
+/// As voxel storage snapshots share their block data, creating a
+/// neighborhood does not copy any blocks.
+#[derive(Debug, Clone)]
+pub struct ChunkNeighborhood<T>
+where
+    T: BlockData,
+{
+    /// The chunk storage snapshots, indexed by their offset within
+    /// [`Region::NEIGHBORS`].
+    chunks: Vec<Option<VoxelStorage<T>>>,
+}
+
+impl<T> ChunkNeighborhood<T>
+where
+    T: BlockData,
+{
+    /// Creates a new chunk neighborhood by calling the given function for each
+    /// chunk offset from `(-1, -1, -1)` to `(1, 1, 1)`.
+    pub fn from_fn<F>(mut get_chunk: F) -> Self
+    where
+        F: FnMut(IVec3) -> Option<VoxelStorage<T>>,
+    {
+        Self {
+            chunks: Region::NEIGHBORS.iter().map(&mut get_chunk).collect(),
+        }
+    }
+
+    /// Checks whether this neighborhood still matches the chunks returned by
+    /// the given function for each chunk offset, meaning that no chunk has
+    /// been modified, loaded, or unloaded since this neighborhood was created.
+    pub fn is_current<'a, F>(&self, mut get_chunk: F) -> bool
+    where
+        F: FnMut(IVec3) -> Option<&'a VoxelStorage<T>>,
+        T: 'a,
+    {
+        Region::NEIGHBORS
+            .iter()
+            .zip(self.chunks.iter())
+            .all(|(offset, snapshot)| {
+                match (snapshot, get_chunk(offset)) {
+                    (Some(snapshot), Some(storage)) => snapshot.shares_data_with(storage),
+                    (None, None) => true,
+                    _ => false,
+                }
+            })
+    }
+
+    /// Gets the storage of the chunk at the given offset from the center
+    /// chunk, or `None` if that chunk is missing.
+    ///
+    /// This function panics if the offset is not within `(-1, -1, -1)` to
+    /// `(1, 1, 1)`.
+    pub fn get_chunk(&self, offset: IVec3) -> Option<&VoxelStorage<T>> {
+        let index = Region::NEIGHBORS.point_to_index(offset).unwrap();
+        self.chunks[index].as_ref()
+    }
+
+    /// Gets the storage of the center chunk, or `None` if it is missing.
+    pub fn center(&self) -> Option<&VoxelStorage<T>> {
+        self.get_chunk(IVec3::ZERO)
+    }
+
+    /// Gets the block at the given block coordinates, relative to the minimum
+    /// corner of the center chunk.
+    ///
+    /// Coordinates may range from `-16` to `31` along each axis. Blocks within
+    /// missing chunks return the default value for `T`.
+    pub fn get_block(&self, local_pos: IVec3) -> T {
+        match self.get_chunk(local_pos >> 4) {
+            Some(chunk) => chunk.get_block(local_pos),
+            None => T::default(),
+        }
+    }
+}
+
+/// A resource that caches chunk neighborhoods, so that multiple systems can
+/// share them without assembling them repeatedly.
+///
+/// Cached neighborhoods are only reused while none of their chunks have been
+/// modified, loaded, or unloaded, so they never return stale block data. The
+/// cache is cleared at the start of each frame.
+#[derive(Debug, Resource)]
+pub struct NeighborhoodCache<T>
+where
+    T: BlockData,
+{
+    /// The cached neighborhoods, by world id and chunk coordinates.
+    neighborhoods: HashMap<(Entity, IVec3), Arc<ChunkNeighborhood<T>>>,
+}
+
+impl<T> Default for NeighborhoodCache<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self {
+            neighborhoods: HashMap::new(),
+        }
+    }
+}
+
+impl<T> NeighborhoodCache<T>
+where
+    T: BlockData,
+{
+    /// Gets the cached neighborhood of the given chunk, or assembles it from
+    /// the chunks returned by the given function if it has not been cached yet
+    /// or if any of its chunks have changed since it was cached.
+    ///
+    /// The function is called with the chunk coordinates of each chunk within
+    /// the neighborhood.
+    pub fn get_or_insert<'a, F>(
+        &mut self,
+        world_id: Entity,
+        chunk_coords: IVec3,
+        mut get_chunk: F,
+    ) -> Arc<ChunkNeighborhood<T>>
+    where
+        F: FnMut(IVec3) -> Option<&'a VoxelStorage<T>>,
+        T: 'a,
+    {
+        let key = (world_id, chunk_coords);
+        if let Some(neighborhood) = self.neighborhoods.get(&key) {
+            if neighborhood.is_current(|offset| get_chunk(chunk_coords + offset)) {
+                return neighborhood.clone();
+            }
+        }
+
+        let neighborhood = Arc::new(ChunkNeighborhood::from_fn(|offset| {
+            get_chunk(chunk_coords + offset).map(VoxelStorage::snapshot)
+        }));
+        self.neighborhoods.insert(key, neighborhood.clone());
+        neighborhood
+    }
+
+    /// Removes all cached neighborhoods.
+    pub fn clear(&mut self) {
+        self.neighborhoods.clear();
+    }
+}
+
+/// This system clears the neighborhood cache at the start of each frame.
+pub(crate) fn clear_neighborhood_cache<T>(mut cache: ResMut<NeighborhoodCache<T>>)
+where
+    T: BlockData,
+{
+    cache.clear();
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn read_across_borders() {
+        let neighborhood = ChunkNeighborhood::<u8>::from_fn(|offset| {
+            (offset != IVec3::NEG_X).then(|| {
+                let mut storage = VoxelStorage::default();
+                storage.set_block(
+                    IVec3::ZERO,
+                    1 + Region::NEIGHBORS.point_to_index(offset).unwrap() as u8,
+                );
+                storage
+            })
+        });
+
+        assert_eq!(neighborhood.get_block(IVec3::ZERO), 14);
+        assert_eq!(neighborhood.get_block(IVec3::new(16, 0, 0)), 23);
+        assert_eq!(neighborhood.get_block(IVec3::new(-16, 0, 0)), 0);
+        assert_eq!(neighborhood.get_block(IVec3::new(-16, -16, -16)), 1);
+        assert!(neighborhood.get_chunk(IVec3::NEG_X).is_none());
+    }
+
+    #[test]
+    fn reassemble_changed_neighborhoods() {
+        let world_id = Entity::from_raw(0);
+        let mut chunks = HashMap::new();
+        chunks.insert(IVec3::ZERO, VoxelStorage::<u8>::filled(1));
+        chunks.insert(IVec3::X, VoxelStorage::<u8>::filled(2));
+
+        let mut cache = NeighborhoodCache::<u8>::default();
+        let first = cache.get_or_insert(world_id, IVec3::ZERO, |coords| chunks.get(&coords));
+        let second = cache.get_or_insert(world_id, IVec3::ZERO, |coords| chunks.get(&coords));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second.get_block(IVec3::new(16, 0, 0)), 2);
+
+        chunks
+            .get_mut(&IVec3::ZERO)
+            .unwrap()
+            .set_block(IVec3::ZERO, 3);
+        let third = cache.get_or_insert(world_id, IVec3::ZERO, |coords| chunks.get(&coords));
+        assert!(!Arc::ptr_eq(&second, &third));
+        assert_eq!(third.get_block(IVec3::ZERO), 3);
+
+        chunks.insert(IVec3::NEG_X, VoxelStorage::<u8>::filled(4));
+        let fourth = cache.get_or_insert(world_id, IVec3::ZERO, |coords| chunks.get(&coords));
+        assert_eq!(fourth.get_block(IVec3::new(-1, 0, 0)), 4);
+    }
+}
@@ -0,0 +1,168 @@
+//! A template of additional components that are inserted into every chunk
+//! when it is spawned.
+
+use std::sync::Arc;
+
+use bevy::ecs::world::EntityMut;
+use bevy::prelude::*;
+
+use super::VoxelChunk;
+
+/// A function that inserts components into a newly spawned chunk entity.
+type ChunkInitializer = Arc<dyn Fn(&mut EntityMut) + Send + Sync>;
+
+/// A resource that lists additional components that should be inserted into
+/// every chunk when it is spawned through voxel commands.
+///
+/// Components are inserted within the same command as the chunk itself, so
+/// they are always present by the time any system can see the new chunk. This
+/// removes the need for systems that attach components to chunks that are
+/// missing them.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::storage::ChunkTemplate;
+/// # #[derive(Component, Default, Clone)]
+/// # struct ChunkStats;
+/// # #[derive(Component)]
+/// # struct ChunkLabel(Entity, IVec3);
+/// # impl ChunkLabel {
+/// #     fn new(world_id: Entity, chunk_coords: IVec3) -> Self {
+/// #         Self(world_id, chunk_coords)
+/// #     }
+/// # }
+/// # let mut app = App::new();
+/// app.insert_resource(
+///     ChunkTemplate::default()
+///         .with_bundle(ChunkStats::default())
+///         .with_bundle_fn(|world_id, chunk_coords| {
+///             ChunkLabel::new(world_id, chunk_coords)
+///         }),
+/// );
+/// ```
+#[derive(Default, Clone, Resource)]
+pub struct ChunkTemplate {
+    /// The initializers that are run for each new chunk, in order.
+    initializers: Vec<ChunkInitializer>,
+}
+
+impl ChunkTemplate {
+    /// Adds a bundle that is cloned into every new chunk.
+    pub fn with_bundle<B>(mut self, bundle: B) -> Self
+    where
+        B: Bundle + Clone,
+    {
+        self.add_bundle(bundle);
+        self
+    }
+
+    /// Adds a function that creates a bundle for every new chunk, given the
+    /// id of the world and the coordinates of the chunk.
+    pub fn with_bundle_fn<B, F>(mut self, create: F) -> Self
+    where
+        B: Bundle,
+        F: Fn(Entity, IVec3) -> B + Send + Sync + 'static,
+    {
+        self.add_bundle_fn(create);
+        self
+    }
+
+    /// Adds a bundle that is cloned into every new chunk.
+    pub fn add_bundle<B>(&mut self, bundle: B)
+    where
+        B: Bundle + Clone,
+    {
+        self.initializers.push(Arc::new(move |chunk| {
+            chunk.insert(bundle.clone());
+        }));
+    }
+
+    /// Adds a function that creates a bundle for every new chunk, given the
+    /// id of the world and the coordinates of the chunk.
+    pub fn add_bundle_fn<B, F>(&mut self, create: F)
+    where
+        B: Bundle,
+        F: Fn(Entity, IVec3) -> B + Send + Sync + 'static,
+    {
+        self.initializers.push(Arc::new(move |chunk| {
+            let Some(meta) = chunk.get::<VoxelChunk>() else {
+                return;
+            };
+
+            let bundle = create(meta.world_id(), meta.chunk_coords());
+            chunk.insert(bundle);
+        }));
+    }
+
+    /// Checks whether this template does not add any components.
+    pub fn is_empty(&self) -> bool {
+        self.initializers.is_empty()
+    }
+}
+
+/// Inserts all components from the [`ChunkTemplate`] resource into the given
+/// chunks, if that resource exists.
+pub(crate) fn apply_chunk_template(world: &mut World, chunk_ids: &[Entity]) {
+    let Some(template) = world.get_resource::<ChunkTemplate>() else {
+        return;
+    };
+
+    if template.is_empty() {
+        return;
+    }
+
+    let initializers = template.initializers.clone();
+    for chunk_id in chunk_ids {
+        let Some(mut chunk) = world.get_entity_mut(*chunk_id) else {
+            continue;
+        };
+
+        for initializer in initializers.iter() {
+            initializer(&mut chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug, Default, Clone, Component, PartialEq)]
+    struct Tag(u32);
+
+    #[derive(Debug, Component, PartialEq)]
+    struct Coords(IVec3);
+
+    #[test]
+    fn insert_template_components() {
+        let mut app = App::new();
+        app.insert_resource(
+            ChunkTemplate::default()
+                .with_bundle(Tag(7))
+                .with_bundle_fn(|_, chunk_coords| Coords(chunk_coords)),
+        );
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            world.spawn_chunks([(IVec3::X, ()), (IVec3::Y, ())]);
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let mut chunks = app.world.query::<(&VoxelChunk, &Tag, &Coords)>();
+        let mut found: Vec<_> = chunks
+            .iter(&app.world)
+            .map(|(chunk, tag, coords)| {
+                assert_eq!(tag, &Tag(7));
+                assert_eq!(coords.0, chunk.chunk_coords());
+                chunk.chunk_coords().to_array()
+            })
+            .collect();
+        found.sort();
+
+        assert_eq!(found, vec![[0, 0, 0], [0, 1, 0], [1, 0, 0]]);
+    }
+}
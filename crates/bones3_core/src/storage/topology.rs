@@ -0,0 +1,130 @@
+//! Defines how chunk coordinates are connected together within a voxel world.
+
+use bevy::prelude::*;
+
+/// The topology of a voxel world, which determines how coordinates at the
+/// edges of the world are connected.
+///
+/// The topology of a world is stored within the chunk pointer cache of that
+/// world, and can be changed using
+/// [`VoxelWorldCommands::set_topology`](crate::query::VoxelWorldCommands::set_topology).
+#[derive(Debug, Default, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WorldTopology {
+    /// The world extends infinitely in all directions.
+    #[default]
+    Infinite,
+
+    /// The world wraps around along the X and Z axes, forming a torus. The
+    /// wrapped size of the world is measured in chunks, where the x component
+    /// is the size along the X axis and the y component is the size along the
+    /// Z axis.
+    ///
+    /// The Y axis is not wrapped.
+    Wrapped(UVec2),
+}
+
+impl WorldTopology {
+    /// Creates a new wrapped world topology with the given size in chunks
+    /// along the X and Z axes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either component of the size is zero.
+    pub fn wrapped(size: UVec2) -> Self {
+        assert!(
+            size.x > 0 && size.y > 0,
+            "Wrapped world size must be non-zero"
+        );
+        Self::Wrapped(size)
+    }
+
+    /// Gets the size of the world in chunks along the X and Z axes, or `None`
+    /// if the world is infinite. The y component of the size is always zero.
+    pub fn wrapped_size(&self) -> Option<IVec3> {
+        match self {
+            WorldTopology::Infinite => None,
+            WorldTopology::Wrapped(size) => Some(IVec3::new(size.x as i32, 0, size.y as i32)),
+        }
+    }
+
+    /// Converts the given chunk coordinates into their canonical form, where
+    /// wrapped axes lie within the range `0 .. size`.
+    pub fn wrap_chunk_coords(&self, chunk_coords: IVec3) -> IVec3 {
+        let Some(size) = self.wrapped_size() else {
+            return chunk_coords;
+        };
+
+        IVec3::new(
+            chunk_coords.x.rem_euclid(size.x),
+            chunk_coords.y,
+            chunk_coords.z.rem_euclid(size.z),
+        )
+    }
+
+    /// Converts the given block coordinates into their canonical form, where
+    /// wrapped axes lie within the range `0 .. size * 16`.
+    pub fn wrap_block_coords(&self, block_coords: IVec3) -> IVec3 {
+        let Some(size) = self.wrapped_size() else {
+            return block_coords;
+        };
+
+        IVec3::new(
+            block_coords.x.rem_euclid(size.x * 16),
+            block_coords.y,
+            block_coords.z.rem_euclid(size.z * 16),
+        )
+    }
+
+    /// Gets the shortest offset from one set of chunk coordinates to another,
+    /// taking wrapped axes into account.
+    pub fn chunk_delta(&self, from: IVec3, to: IVec3) -> IVec3 {
+        let delta = to - from;
+        let Some(size) = self.wrapped_size() else {
+            return delta;
+        };
+
+        let shortest = |d: i32, s: i32| {
+            let d = d.rem_euclid(s);
+            if d * 2 > s {
+                d - s
+            } else {
+                d
+            }
+        };
+
+        IVec3::new(
+            shortest(delta.x, size.x),
+            delta.y,
+            shortest(delta.z, size.z),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn wrap_coords() {
+        let topology = WorldTopology::wrapped(UVec2::new(4, 8));
+
+        assert_eq!(
+            topology.wrap_chunk_coords(IVec3::new(-1, -3, 9)),
+            IVec3::new(3, -3, 1)
+        );
+        assert_eq!(
+            topology.wrap_block_coords(IVec3::new(-1, 70, 130)),
+            IVec3::new(63, 70, 2)
+        );
+        assert_eq!(
+            topology.chunk_delta(IVec3::new(0, 0, 0), IVec3::new(3, 5, 7)),
+            IVec3::new(-1, 5, -1)
+        );
+        assert_eq!(
+            WorldTopology::Infinite.chunk_delta(IVec3::ZERO, IVec3::new(3, 5, 7)),
+            IVec3::new(3, 5, 7)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! A component for defining the vertical orientation of a voxel world.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+/// Defines which direction is considered "up" within a voxel world.
+///
+/// This component may be attached to a voxel world entity in order to support
+/// rotated or planetary-style worlds. Worlds without this component are
+/// considered to be Y-up.
+///
+/// Helpers that work with block columns, such as heightmaps and minimaps,
+/// use this axis to split block coordinates into a 2D column coordinate and a
+/// height along the up axis.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub enum UpAxis {
+    /// The positive X axis is up.
+    PosX,
+
+    /// The negative X axis is up.
+    NegX,
+
+    /// The positive Y axis is up.
+    #[default]
+    PosY,
+
+    /// The negative Y axis is up.
+    NegY,
+
+    /// The positive Z axis is up.
+    PosZ,
+
+    /// The negative Z axis is up.
+    NegZ,
+}
+
+impl UpAxis {
+    /// Gets the unit direction vector that points up.
+    pub fn up(self) -> IVec3 {
+        match self {
+            UpAxis::PosX => IVec3::X,
+            UpAxis::NegX => IVec3::NEG_X,
+            UpAxis::PosY => IVec3::Y,
+            UpAxis::NegY => IVec3::NEG_Y,
+            UpAxis::PosZ => IVec3::Z,
+            UpAxis::NegZ => IVec3::NEG_Z,
+        }
+    }
+
+    /// Gets whether or not this axis points along a positive direction.
+    pub fn is_positive(self) -> bool {
+        matches!(self, UpAxis::PosX | UpAxis::PosY | UpAxis::PosZ)
+    }
+
+    /// Gets the height of the given coordinates along this axis.
+    pub fn height(self, coords: IVec3) -> i32 {
+        coords.dot(self.up())
+    }
+
+    /// Gets the column coordinates of the given coordinates, by removing the
+    /// component along this axis.
+    ///
+    /// For the X axis, the column is `(y, z)`. For the Y axis, the column is
+    /// `(x, z)`. For the Z axis, the column is `(x, y)`.
+    pub fn column(self, coords: IVec3) -> IVec2 {
+        match self {
+            UpAxis::PosX | UpAxis::NegX => IVec2::new(coords.y, coords.z),
+            UpAxis::PosY | UpAxis::NegY => IVec2::new(coords.x, coords.z),
+            UpAxis::PosZ | UpAxis::NegZ => IVec2::new(coords.x, coords.y),
+        }
+    }
+
+    /// Combines the given column coordinates and height back into a set of
+    /// coordinates. This is the inverse of [`UpAxis::column`] and
+    /// [`UpAxis::height`].
+    pub fn compose(self, column: IVec2, height: i32) -> IVec3 {
+        let along = if self.is_positive() { height } else { -height };
+
+        match self {
+            UpAxis::PosX | UpAxis::NegX => IVec3::new(along, column.x, column.y),
+            UpAxis::PosY | UpAxis::NegY => IVec3::new(column.x, along, column.y),
+            UpAxis::PosZ | UpAxis::NegZ => IVec3::new(column.x, column.y, along),
+        }
+    }
+
+    /// Gets the range of block heights that are contained within a chunk with
+    /// the given chunk height.
+    pub fn block_heights(self, chunk_height: i32) -> RangeInclusive<i32> {
+        if self.is_positive() {
+            chunk_height * 16 ..= chunk_height * 16 + 15
+        } else {
+            chunk_height * 16 - 15 ..= chunk_height * 16
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn column_round_trip() {
+        let coords = IVec3::new(3, -7, 12);
+
+        for axis in [
+            UpAxis::PosX,
+            UpAxis::NegX,
+            UpAxis::PosY,
+            UpAxis::NegY,
+            UpAxis::PosZ,
+            UpAxis::NegZ,
+        ] {
+            let column = axis.column(coords);
+            let height = axis.height(coords);
+            assert_eq!(axis.compose(column, height), coords);
+            assert!(axis
+                .block_heights(axis.height(coords >> 4))
+                .contains(&height));
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Comparing the loaded blocks of two voxel worlds, chunk by chunk.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::chunk_pointers::ChunkEntityPointers;
+use super::{BlockData, VoxelStorage, WorldSnapshot};
+use crate::math::Region;
+
+/// A single block that differs between two voxel worlds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockDiff<T>
+where
+    T: BlockData,
+{
+    /// The block coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The block within the first world.
+    pub a: T,
+
+    /// The block within the second world.
+    pub b: T,
+}
+
+/// The difference between a single chunk within two voxel worlds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkDiff<T>
+where
+    T: BlockData,
+{
+    /// The chunk is only loaded within the first world.
+    MissingInB,
+
+    /// The chunk is only loaded within the second world.
+    MissingInA,
+
+    /// The chunk is loaded within both worlds, but the given blocks differ.
+    Blocks(Vec<BlockDiff<T>>),
+}
+
+/// The differences between the loaded chunks of two voxel worlds, as found by
+/// [`compare_worlds`] or [`compare_snapshots`].
+///
+/// Chunks that are loaded within both worlds and contain the same blocks are
+/// not listed. The differing chunks are sorted by their chunk coordinates, so
+/// the same two worlds always produce the same diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldDiff<T>
+where
+    T: BlockData,
+{
+    /// The differing chunks, sorted by their chunk coordinates.
+    chunks: Vec<(IVec3, ChunkDiff<T>)>,
+}
+
+impl<T> WorldDiff<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Compares two sets of chunk storages, indexed by their chunk
+    /// coordinates.
+    fn new(a: HashMap<IVec3, &VoxelStorage<T>>, b: HashMap<IVec3, &VoxelStorage<T>>) -> Self {
+        let mut chunks = vec![];
+
+        for (chunk_coords, a_storage) in a.iter() {
+            let Some(b_storage) = b.get(chunk_coords) else {
+                chunks.push((*chunk_coords, ChunkDiff::MissingInB));
+                continue;
+            };
+
+            let blocks = diff_storages(*chunk_coords, a_storage, b_storage);
+            if !blocks.is_empty() {
+                chunks.push((*chunk_coords, ChunkDiff::Blocks(blocks)));
+            }
+        }
+
+        for chunk_coords in b.keys() {
+            if !a.contains_key(chunk_coords) {
+                chunks.push((*chunk_coords, ChunkDiff::MissingInA));
+            }
+        }
+
+        chunks.sort_unstable_by_key(|(chunk_coords, _)| chunk_coords.to_array());
+        Self {
+            chunks,
+        }
+    }
+}
+
+impl<T> WorldDiff<T>
+where
+    T: BlockData,
+{
+    /// Checks whether both worlds contain the same loaded chunks and blocks.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Gets the number of chunks that differ between both worlds.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Gets the difference of the chunk at the given chunk coordinates, or
+    /// `None` if that chunk does not differ.
+    pub fn get(&self, chunk_coords: IVec3) -> Option<&ChunkDiff<T>> {
+        self.chunks
+            .binary_search_by_key(&chunk_coords.to_array(), |(c, _)| c.to_array())
+            .ok()
+            .map(|index| &self.chunks[index].1)
+    }
+
+    /// Creates an iterator over all differing chunks and their chunk
+    /// coordinates, sorted by their chunk coordinates.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, &ChunkDiff<T>)> {
+        self.chunks.iter().map(|(c, diff)| (*c, diff))
+    }
+
+    /// Creates an iterator over all blocks that differ between chunks that
+    /// are loaded within both worlds.
+    pub fn blocks(&self) -> impl Iterator<Item = &BlockDiff<T>> {
+        self.chunks.iter().flat_map(|(_, diff)| {
+            match diff {
+                ChunkDiff::Blocks(blocks) => blocks.as_slice(),
+                _ => &[],
+            }
+        })
+    }
+}
+
+/// Compares all loaded blocks within the given voxel worlds.
+///
+/// Chunks are matched by their chunk coordinates, so the worlds should use
+/// the same topology. Chunks that do not have a `VoxelStorage<T>` component
+/// yet, such as chunks that are still being generated, are treated as not
+/// being loaded. This is useful for checking replicated worlds against their
+/// source, or a reloaded world against the world that was saved.
+pub fn compare_worlds<T>(world: &World, a: Entity, b: Entity) -> WorldDiff<T>
+where
+    T: BlockData + PartialEq,
+{
+    WorldDiff::new(loaded_chunks(world, a), loaded_chunks(world, b))
+}
+
+/// Compares all blocks within the given world snapshots.
+///
+/// See [`compare_worlds`] for more information.
+pub fn compare_snapshots<T>(a: &WorldSnapshot<T>, b: &WorldSnapshot<T>) -> WorldDiff<T>
+where
+    T: BlockData + PartialEq,
+{
+    WorldDiff::new(snapshot_chunks(a), snapshot_chunks(b))
+}
+
+/// Gets the storages of all chunks within the given snapshot, by their chunk
+/// coordinates.
+fn snapshot_chunks<T>(snapshot: &WorldSnapshot<T>) -> HashMap<IVec3, &VoxelStorage<T>>
+where
+    T: BlockData,
+{
+    snapshot
+        .chunk_coords()
+        .map(|chunk_coords| (chunk_coords, snapshot.get_chunk(chunk_coords).unwrap()))
+        .collect()
+}
+
+/// Gets the storages of all loaded chunks within the given world, by their
+/// chunk coordinates.
+fn loaded_chunks<T>(world: &World, world_id: Entity) -> HashMap<IVec3, &VoxelStorage<T>>
+where
+    T: BlockData,
+{
+    let Some(pointers) = world.get::<ChunkEntityPointers>(world_id) else {
+        return HashMap::new();
+    };
+
+    pointers
+        .iter()
+        .filter_map(|(chunk_coords, chunk_id)| {
+            Some((chunk_coords, world.get::<VoxelStorage<T>>(chunk_id)?))
+        })
+        .collect()
+}
+
+/// Finds all blocks that differ between the given storages of the chunk at the
+/// given chunk coordinates.
+fn diff_storages<T>(
+    chunk_coords: IVec3,
+    a: &VoxelStorage<T>,
+    b: &VoxelStorage<T>,
+) -> Vec<BlockDiff<T>>
+where
+    T: BlockData + PartialEq,
+{
+    if a.shares_data_with(b) {
+        return vec![];
+    }
+
+    Region::CHUNK
+        .iter()
+        .filter_map(|local_pos| {
+            let (a, b) = (a.get_block(local_pos), b.get_block(local_pos));
+            (a != b).then_some(BlockDiff {
+                block_coords: (chunk_coords << 4) + local_pos,
+                a,
+                b,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn compare_loaded_chunks() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        let a = app.world.spawn(VoxelWorldBundle::new()).id();
+        let b = app.world.spawn(VoxelWorldBundle::new()).id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let shared = VoxelStorage::<u8>::filled(1);
+                let mut edited = shared.clone();
+                edited.set_block(IVec3::new(3, 4, 5), 2);
+
+                let mut world_a = commands.get_world(a).unwrap();
+                world_a.spawn_chunk(IVec3::ZERO, shared.clone()).unwrap();
+                world_a.spawn_chunk(IVec3::X, shared.clone()).unwrap();
+                world_a.spawn_chunk(IVec3::Y, ()).unwrap();
+
+                let mut world_b = commands.get_world(b).unwrap();
+                world_b.spawn_chunk(IVec3::ZERO, shared).unwrap();
+                world_b.spawn_chunk(IVec3::X, edited).unwrap();
+                world_b
+                    .spawn_chunk(IVec3::Z, VoxelStorage::<u8>::default())
+                    .unwrap();
+            })
+            .run(&mut app.world);
+
+        let diff = compare_worlds::<u8>(&app.world, a, b);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff.get(IVec3::ZERO), None);
+        assert_eq!(diff.get(IVec3::Y), None);
+        assert_eq!(diff.get(IVec3::Z), Some(&ChunkDiff::MissingInA));
+        assert_eq!(diff.blocks().collect::<Vec<_>>(), vec![&BlockDiff {
+            block_coords: IVec3::new(19, 4, 5),
+            a:            1,
+            b:            2,
+        }]);
+
+        assert!(compare_worlds::<u8>(&app.world, a, a).is_empty());
+        let reversed = compare_worlds::<u8>(&app.world, b, a);
+        assert_eq!(reversed.get(IVec3::Z), Some(&ChunkDiff::MissingInB));
+    }
+}
@@ -0,0 +1,49 @@
+//! A component for placing a voxel world within a larger, sector based world.
+
+use bevy::prelude::*;
+
+use crate::math::{WideBlockCoords, WORLD_SECTOR_SIZE};
+
+/// Marks a voxel world as a single sector of a larger world that uses 64 bit
+/// [`WideBlockCoords`].
+///
+/// Each sector is a separate voxel world that covers `WORLD_SECTOR_SIZE`
+/// blocks along each axis, starting at local block coordinates `(0, 0, 0)`.
+/// This keeps all block and chunk arithmetic within the sector far away from
+/// the limits of `i32`, no matter how far the sector is from the origin.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub struct WorldSector(pub IVec3);
+
+impl WorldSector {
+    /// Gets the world sector that contains the given wide block position.
+    ///
+    /// See [`WideBlockCoords::sector_coords`] for the supported range.
+    pub fn containing(block_coords: WideBlockCoords) -> Self {
+        Self(block_coords.sector_coords())
+    }
+
+    /// Converts the given wide block position into local block coordinates
+    /// within this sector, or `None` if the block is within a different
+    /// sector.
+    pub fn to_local(self, block_coords: WideBlockCoords) -> Option<IVec3> {
+        let (sector_coords, local_coords) = block_coords.split();
+        (sector_coords == self.0).then_some(local_coords)
+    }
+
+    /// Converts the given local block coordinates within this sector into a
+    /// wide block position.
+    pub fn to_wide(self, local_coords: IVec3) -> WideBlockCoords {
+        WideBlockCoords::from_parts(self.0, local_coords)
+    }
+
+    /// Gets the translation of the origin of this sector relative to the
+    /// origin of the given sector, in blocks.
+    ///
+    /// This can be used to place neighboring sectors next to each other
+    /// within the Bevy world, relative to the sector that currently contains
+    /// the camera.
+    pub fn offset_from(self, origin: WorldSector) -> Vec3 {
+        ((self.0 - origin.0).as_dvec3() * WORLD_SECTOR_SIZE as f64).as_vec3()
+    }
+}
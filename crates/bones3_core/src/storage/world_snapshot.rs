@@ -0,0 +1,107 @@
+//! A standalone collection of chunk data that exists outside of the ECS.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::{BlockData, ChunkNeighborhood, VoxelStorage};
+use crate::math::Region;
+
+/// A collection of chunk storages, indexed by their chunk coordinates, that
+/// exists outside of the ECS.
+///
+/// World snapshots do not require any entities, so they are useful for tools,
+/// previews, and tests that need to work with a small section of a world
+/// without spinning up the full chunk loading pipeline. Chunks that are not
+/// within the snapshot are treated as if they were filled with the default
+/// value for `T`.
+#[derive(Debug, Clone)]
+pub struct WorldSnapshot<T>
+where
+    T: BlockData,
+{
+    /// The chunk storages within this snapshot.
+    chunks: HashMap<IVec3, VoxelStorage<T>>,
+}
+
+impl<T> Default for WorldSnapshot<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+}
+
+impl<T> WorldSnapshot<T>
+where
+    T: BlockData,
+{
+    /// Creates a new, empty world snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the given chunk storage at the given chunk coordinates,
+    /// returning the previous storage at those coordinates, if any.
+    pub fn insert_chunk(
+        &mut self,
+        chunk_coords: IVec3,
+        storage: VoxelStorage<T>,
+    ) -> Option<VoxelStorage<T>> {
+        self.chunks.insert(chunk_coords, storage)
+    }
+
+    /// Gets the chunk storage at the given chunk coordinates, if it exists.
+    pub fn get_chunk(&self, chunk_coords: IVec3) -> Option<&VoxelStorage<T>> {
+        self.chunks.get(&chunk_coords)
+    }
+
+    /// Gets the block at the given block coordinates.
+    pub fn get_block(&self, block_coords: IVec3) -> T {
+        match self.chunks.get(&(block_coords >> 4)) {
+            Some(chunk) => chunk.get_block(block_coords),
+            None => T::default(),
+        }
+    }
+
+    /// Sets the block at the given block coordinates, creating an empty chunk
+    /// if needed.
+    pub fn set_block(&mut self, block_coords: IVec3, block: T) {
+        self.chunks
+            .entry(block_coords >> 4)
+            .or_default()
+            .set_block(block_coords, block);
+    }
+
+    /// Creates a neighborhood snapshot of the chunk at the given chunk
+    /// coordinates and all of its neighboring chunks.
+    pub fn neighborhood(&self, chunk_coords: IVec3) -> ChunkNeighborhood<T> {
+        ChunkNeighborhood::from_fn(|offset| self.chunks.get(&(chunk_coords + offset)).cloned())
+    }
+
+    /// Creates an iterator over the coordinates of all chunks within this
+    /// snapshot, in no particular order.
+    pub fn chunk_coords(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Gets the smallest region of chunk coordinates that contains every chunk
+    /// within this snapshot, or `None` if this snapshot is empty.
+    pub fn chunk_region(&self) -> Option<Region> {
+        let mut coords = self.chunk_coords();
+        let first = coords.next()?;
+        Some(coords.fold(Region::from_points(first, first), Region::expand))
+    }
+
+    /// Gets the number of chunks within this snapshot.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Checks whether this snapshot does not contain any chunks.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
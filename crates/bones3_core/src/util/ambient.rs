@@ -0,0 +1,314 @@
+//! Random sampling of surface blocks near chunk anchors, for use with ambient
+//! effects such as particles, sounds, or wildlife.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::prelude::{BlockData, UpAxis, VoxelChunk, VoxelQuery, VoxelStorage, VoxelWorld};
+use crate::util::anchor::ChunkAnchorRecipient;
+
+/// This plugin picks random surface blocks for all [`AmbientSampler`]
+/// components with the given block data type, sending an [`AmbientSample`]
+/// event for each block that is found.
+///
+/// Only chunks that are within range of a chunk anchor of type `A` are
+/// sampled.
+pub struct AmbientSamplerPlugin<T, A>
+where
+    T: BlockData + PartialEq,
+    A: Send + Sync + 'static,
+{
+    /// Phantom data for T and A.
+    _phantom: PhantomData<(T, A)>,
+}
+
+impl<T, A> Default for AmbientSamplerPlugin<T, A>
+where
+    T: BlockData + PartialEq,
+    A: Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Plugin for AmbientSamplerPlugin<T, A>
+where
+    T: BlockData + PartialEq,
+    A: Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<AmbientSample<T>>()
+            .add_systems(Update, sample_ambient_blocks::<T, A>);
+    }
+}
+
+/// A component that requests random surface blocks within a voxel world at a
+/// fixed rate.
+///
+/// A surface block is a non-default block where the block directly above it,
+/// along the [`UpAxis`] of the world, is the default block or is not loaded.
+#[derive(Component)]
+pub struct AmbientSampler<T>
+where
+    T: BlockData,
+{
+    /// The id of the world to sample blocks from.
+    pub world_id: Entity,
+
+    /// If set, only chunks where this chunk anchor is the nearest anchor are
+    /// sampled.
+    pub anchor: Option<Entity>,
+
+    /// Checks whether the given surface block should be reported.
+    pub predicate: Arc<dyn Fn(IVec3, T) -> bool + Send + Sync>,
+
+    /// The average number of samples to report per second.
+    pub rate: f32,
+
+    /// The maximum number of samples to report within a single frame.
+    pub max_per_frame: u32,
+
+    /// The maximum number of block columns that are checked for each sample
+    /// before giving up.
+    pub attempts: u32,
+
+    /// The fractional number of samples that are carried over to the next
+    /// frame.
+    accumulated: f32,
+
+    /// The current state of the random number generator.
+    seed: u64,
+}
+
+impl<T> AmbientSampler<T>
+where
+    T: BlockData,
+{
+    /// Creates a new ambient sampler for the given world that reports the
+    /// given number of samples per second, on average.
+    pub fn new<F>(world_id: Entity, rate: f32, predicate: F) -> Self
+    where
+        F: Fn(IVec3, T) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            world_id,
+            anchor: None,
+            predicate: Arc::new(predicate),
+            rate,
+            max_per_frame: 16,
+            attempts: 8,
+            accumulated: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Only samples chunks where the given chunk anchor is the nearest anchor.
+    pub fn near_anchor(mut self, anchor: Entity) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Sets the maximum number of samples that are reported within a single
+    /// frame.
+    pub fn with_max_per_frame(mut self, max_per_frame: u32) -> Self {
+        self.max_per_frame = max_per_frame;
+        self
+    }
+
+    /// Sets the seed of the random number generator that is used to pick
+    /// blocks. If the seed is zero, a seed is picked based off the sampler's
+    /// entity id.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Gets the next random value from this sampler's generator.
+    fn next_random(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 32) as u32
+    }
+}
+
+/// An event that is sent for each surface block that is picked by an
+/// [`AmbientSampler`].
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub struct AmbientSample<T>
+where
+    T: BlockData,
+{
+    /// The entity containing the sampler that picked the block.
+    pub sampler: Entity,
+
+    /// The id of the world containing the block.
+    pub world_id: Entity,
+
+    /// The block coordinates of the surface block.
+    pub block_coords: IVec3,
+
+    /// The value of the surface block.
+    pub block: T,
+}
+
+/// This system picks random surface blocks for all ambient samplers.
+fn sample_ambient_blocks<T, A>(
+    time: Res<Time>,
+    mut samplers: Query<(Entity, &mut AmbientSampler<T>)>,
+    candidates: Query<(&VoxelChunk, &ChunkAnchorRecipient<A>), With<VoxelStorage<T>>>,
+    worlds: Query<Option<&UpAxis>, With<VoxelWorld>>,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut events: EventWriter<AmbientSample<T>>,
+) where
+    T: BlockData + PartialEq,
+    A: Send + Sync + 'static,
+{
+    for (sampler_id, mut sampler) in samplers.iter_mut() {
+        let (Ok(up), Ok(world)) = (
+            worlds.get(sampler.world_id),
+            chunks.get_world(sampler.world_id),
+        ) else {
+            continue;
+        };
+        let up = up.copied().unwrap_or_default();
+
+        sampler.accumulated += sampler.rate * time.delta_seconds();
+        let count = (sampler.accumulated.floor() as u32).min(sampler.max_per_frame);
+        sampler.accumulated = sampler.accumulated.fract();
+        if count == 0 {
+            continue;
+        }
+
+        let chunk_list: Vec<IVec3> = candidates
+            .iter()
+            .filter(|(chunk, _)| chunk.world_id() == sampler.world_id)
+            .filter(|(_, recipient)| recipient.priority.is_some())
+            .filter(|(_, recipient)| {
+                sampler.anchor.is_none() || recipient.nearest_anchor == sampler.anchor
+            })
+            .map(|(chunk, _)| chunk.chunk_coords())
+            .collect();
+
+        if chunk_list.is_empty() {
+            continue;
+        }
+
+        if sampler.seed == 0 {
+            sampler.seed = sampler_id.to_bits() | 1;
+        }
+
+        let get_block = |block_coords: IVec3| {
+            world
+                .get_chunk(block_coords >> 4)
+                .map(|storage| storage.get_block(block_coords))
+        };
+
+        for _ in 0 .. count {
+            for _ in 0 .. sampler.attempts {
+                let chunk_coords = chunk_list[sampler.next_random() as usize % chunk_list.len()];
+                let local = sampler.next_random();
+                let column = up.column(chunk_coords) * 16
+                    + IVec2::new((local & 15) as i32, ((local >> 4) & 15) as i32);
+
+                let surface = up
+                    .block_heights(up.height(chunk_coords))
+                    .rev()
+                    .map(|height| up.compose(column, height))
+                    .find_map(|block_coords| {
+                        let block = get_block(block_coords)?;
+                        let above = get_block(block_coords + up.up());
+                        let open = above.is_none() || above == Some(T::default());
+                        (block != T::default() && open).then_some((block_coords, block))
+                    });
+
+                let Some((block_coords, block)) = surface else {
+                    continue;
+                };
+
+                if !(sampler.predicate)(block_coords, block) {
+                    continue;
+                }
+
+                events.send(AmbientSample {
+                    sampler: sampler_id,
+                    world_id: sampler.world_id,
+                    block_coords,
+                    block,
+                });
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::anchor::{ChunkAnchor, ChunkAnchorPlugin};
+
+    #[derive(Default, Reflect)]
+    struct Ambient;
+
+    #[test]
+    fn sample_surface() {
+        let mut app = App::new();
+        app.init_resource::<Time>().add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            ChunkAnchorPlugin::<Ambient>::default(),
+            AmbientSamplerPlugin::<u8, Ambient>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            for x in 0 .. 16 {
+                for z in 0 .. 16 {
+                    storage.set_block(IVec3::new(x, 2, z), 1);
+                    storage.set_block(IVec3::new(x, 3, z), 2);
+                }
+            }
+
+            let world_id = commands
+                .spawn_world(TransformBundle::default())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap()
+                .world_id();
+
+            commands.commands().spawn((
+                TransformBundle::default(),
+                ChunkAnchor::<Ambient>::new(world_id, UVec3::ONE),
+                AmbientSampler::<u8>::new(world_id, 10.0, |_, block| block == 2),
+            ));
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let start = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(start);
+
+        // Chunk priorities are only available after the chunk recipients
+        // have been attached.
+        app.update();
+        app.update();
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_secs(1));
+        app.update();
+
+        let events = app.world.resource::<Events<AmbientSample<u8>>>();
+        let samples: Vec<_> = events.get_reader().iter(events).cloned().collect();
+        assert_eq!(samples.len(), 10);
+        assert!(samples
+            .iter()
+            .all(|s| s.block == 2 && s.block_coords.y == 3));
+    }
+}
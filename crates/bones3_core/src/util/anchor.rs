@@ -3,10 +3,14 @@
 
 use std::marker::PhantomData;
 
+use bevy::core::FrameCount;
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 
-use crate::prelude::{Region, VoxelChunk, VoxelWorld};
+use super::interest::{update_chunk_interest, ChunkInterest, ChunkInterestEvent};
+use super::pinned::{is_chunk_pinned, PinnedChunks};
+use crate::prelude::{BlockScale, Region, VoxelChunk, VoxelCommands, VoxelWorld, WorldTopology};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
 
 /// This plugin can be used to create a new chunk anchor component for easily
 /// querying and prioritizing chunks around the anchor.
@@ -17,6 +21,24 @@ where
 {
     /// Default placeholder for T.
     _phantom: PhantomData<T>,
+
+    /// The strategy used to combine the priorities of multiple anchors.
+    combiner: PriorityCombiner,
+}
+
+impl<T> ChunkAnchorPlugin<T>
+where
+    T: Send + Sync + Default + TypePath,
+{
+    /// Sets the strategy that is used to combine the priorities of multiple
+    /// chunk anchors of this type that are in range of the same chunk.
+    ///
+    /// If the [`ChunkAnchorSettings`] resource for this anchor type has
+    /// already been inserted, that resource is kept instead.
+    pub fn with_combiner(mut self, combiner: PriorityCombiner) -> Self {
+        self.combiner = combiner;
+        self
+    }
 }
 
 impl<T> Plugin for ChunkAnchorPlugin<T>
@@ -24,14 +46,27 @@ where
     T: Send + Sync + Default + TypePath + 'static,
 {
     fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<ChunkAnchorSettings<T>>() {
+            app.insert_resource(ChunkAnchorSettings::<T>::new(self.combiner));
+        }
+
         app.register_type::<ChunkAnchor<T>>()
             .register_type::<ChunkAnchorRecipient<T>>()
+            .register_type::<LogicalAnchorPosition>()
+            .register_type::<ChunkInterest<T>>()
+            .register_type::<ChunkAnchorSettings<T>>()
+            .register_type::<ChunkMemoryBudget<T>>()
+            .register_type::<PriorityCombiner>()
+            .add_event::<ChunkInterestEvent>()
             .add_systems(
                 PostUpdate,
                 (
-                    (clear_coords_without_transform::<T>, update_coords::<T>)
-                        .in_set(ChunkAnchorSet::UpdateCoords),
-                    update_chunk_priorities::<T>.in_set(ChunkAnchorSet::UpdatePriorities),
+                    update_coords::<T>.in_set(ChunkAnchorSet::UpdateCoords),
+                    (update_chunk_priorities::<T>, update_chunk_interest::<T>)
+                        .in_set(ChunkAnchorSet::UpdatePriorities),
+                    enforce_chunk_memory_budget::<T>
+                        .after(ChunkAnchorSet::UpdatePriorities)
+                        .run_if(resource_exists::<ChunkMemoryBudget<T>>()),
                     attach_chunk_recipient_comp::<T>.in_set(ChunkAnchorSet::AttachChunkComponents),
                 ),
             )
@@ -58,12 +93,67 @@ pub enum ChunkAnchorSet {
     AttachChunkComponents,
 }
 
+/// The strategy that is used to combine the priorities of multiple chunk
+/// anchors that are in range of the same chunk.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum PriorityCombiner {
+    /// The highest priority of all anchors is used.
+    #[default]
+    Max,
+
+    /// The priorities of all anchors are added together.
+    Sum,
+
+    /// The average of all anchor priorities, weighted by the weight of each
+    /// anchor.
+    Weighted,
+}
+
+/// The settings for all chunk anchors of a single anchor type.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct ChunkAnchorSettings<T>
+where
+    T: Send + Sync,
+{
+    /// Default placeholder for T.
+    #[reflect(ignore)]
+    _phantom: PhantomData<T>,
+
+    /// The strategy used to combine the priorities of multiple anchors.
+    pub combiner: PriorityCombiner,
+}
+
+impl<T> ChunkAnchorSettings<T>
+where
+    T: Send + Sync,
+{
+    /// Creates a new chunk anchor settings resource with the given priority
+    /// combiner.
+    pub fn new(combiner: PriorityCombiner) -> Self {
+        Self {
+            _phantom: PhantomData,
+            combiner,
+        }
+    }
+}
+
+impl<T> Default for ChunkAnchorSettings<T>
+where
+    T: Send + Sync,
+{
+    fn default() -> Self {
+        Self::new(PriorityCombiner::default())
+    }
+}
+
 /// A basic chunk anchor component that can be used to process and weight nearby
 /// chunks.
 ///
 /// This component should be attached to an entity with a SpatialBundle
 /// attached, otherwise it will not perform any actions.
 #[derive(Debug, Reflect, Component, Clone)]
+#[reflect(Component)]
 pub struct ChunkAnchor<T>
 where
     T: Send + Sync,
@@ -99,12 +189,21 @@ where
     /// The current coordinates of this chunk anchor relative to the world. This
     /// value is internally updated each frame.
     ///
-    /// If the chunk anchor is not attached to an entity with a SpatialBundle,
-    /// or the world cannot be accessed, then the coordinates are set to
-    /// `None`.
+    /// If the chunk anchor is not attached to an entity with a SpatialBundle or
+    /// a [`LogicalAnchorPosition`], or the world cannot be accessed, then the
+    /// coordinates are set to `None`.
     pub coords: Option<IVec3>,
 }
 
+impl<T> FromWorld for ChunkAnchor<T>
+where
+    T: Send + Sync,
+{
+    fn from_world(_: &mut World) -> Self {
+        Self::new(Entity::PLACEHOLDER, UVec3::ZERO)
+    }
+}
+
 impl<T> ChunkAnchor<T>
 where
     T: Send + Sync,
@@ -128,21 +227,30 @@ where
     /// This value returns `None` if the chunk is out of range, or if this chunk
     /// anchor has not yet calculated its current coordinates.
     pub fn get_priority(&self, target: IVec3) -> Option<f32> {
+        self.get_wrapped_priority(target, WorldTopology::Infinite)
+    }
+
+    /// Calculates the current priority value of the chunk at the given target
+    /// coordinates, measuring distances across the edges of the given world
+    /// topology.
+    ///
+    /// This value returns `None` if the chunk is out of range, or if this chunk
+    /// anchor has not yet calculated its current coordinates.
+    pub fn get_wrapped_priority(&self, target: IVec3, topology: WorldTopology) -> Option<f32> {
         let Some(coords) = self.coords else {
             return None;
         };
 
-        let delta = (coords - target).abs().as_uvec3();
+        let offset = topology.chunk_delta(coords, target);
+        let delta = offset.abs().as_uvec3();
         let radius = self.radius;
         if delta.x > radius.x || delta.y > radius.y || delta.z > radius.z {
             return None;
         };
 
-        let a = coords.as_vec3();
-        let b = target.as_vec3();
-
-        let distance = a.distance(b);
-        let view_dir = (b - a).normalize_or_zero();
+        let offset = offset.as_vec3();
+        let distance = offset.length();
+        let view_dir = offset.normalize_or_zero();
         let weight = view_dir.dot(self.dir_bias);
         let priority = (-distance + weight) * self.weight;
         Some(priority)
@@ -163,9 +271,21 @@ where
     }
 }
 
+/// A component that sets the position of a chunk anchor directly, in block
+/// coordinates relative to its world, instead of reading it from the anchor's
+/// transform.
+///
+/// This allows chunk anchors to be used with purely logical voxel worlds that
+/// do not have any transform or render presence, such as worlds that only
+/// exist for simulation.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component, Default)]
+pub struct LogicalAnchorPosition(pub Vec3);
+
 /// This component is attached to new chunks entities and is used to hold the
 /// current priority levels as determined by all existing chunk anchors.
-#[derive(Debug, Default, Reflect, Component, Clone)]
+#[derive(Debug, Reflect, Component, Clone)]
+#[reflect(Component, Default)]
 pub struct ChunkAnchorRecipient<T>
 where
     T: Send + Sync,
@@ -180,77 +300,300 @@ where
     ///
     /// This value is updated internally each frame.
     pub priority: Option<f32>,
+
+    /// The chunk anchor entity that is closest to this chunk recipient, out of
+    /// all chunk anchors that are within range. This value is set to `None`
+    /// if there are currently no chunk anchors within range.
+    ///
+    /// This value is updated internally each frame.
+    pub nearest_anchor: Option<Entity>,
+
+    /// The chunk anchor entity with the highest individual priority value for
+    /// this chunk recipient, out of all chunk anchors that are within range.
+    /// This value is set to `None` if there are currently no chunk anchors
+    /// within range.
+    ///
+    /// Unlike [`ChunkAnchorRecipient::nearest_anchor`], this accounts for the
+    /// weight and directional bias of each anchor, which makes it suitable for
+    /// deciding which anchor "owns" this chunk.
+    ///
+    /// This value is updated internally each frame.
+    pub top_anchor: Option<Entity>,
+
+    /// The number of chunk anchors that are currently within range of this
+    /// chunk recipient.
+    ///
+    /// This value is updated internally each frame.
+    pub anchor_count: usize,
+
+    /// The elapsed time, in seconds since startup, of the most recent frame
+    /// in which at least one chunk anchor was within range of this chunk
+    /// recipient. This value is `None` if no chunk anchor has ever been within
+    /// range, or if the app does not have a [`Time`] resource.
+    ///
+    /// This value is updated internally each frame.
+    pub last_relevant_time: Option<f32>,
+
+    /// The [`FrameCount`] of the most recent frame in which at least one
+    /// chunk anchor was within range of this chunk recipient. This value is
+    /// `None` if no chunk anchor has ever been within range, or if the app
+    /// does not have a [`FrameCount`] resource.
+    ///
+    /// This value is updated internally each frame.
+    pub last_relevant_frame: Option<u32>,
 }
 
-/// This system checks to see if there are any chunk anchors without an attached
-/// SpatialBundle. If so, it clears the internal chunk coordinates of that
-/// anchor.
-pub(crate) fn clear_coords_without_transform<T>(
-    mut anchors: Query<&mut ChunkAnchor<T>, Without<GlobalTransform>>,
-) where
-    T: Send + Sync + 'static,
+impl<T> Default for ChunkAnchorRecipient<T>
+where
+    T: Send + Sync,
 {
-    for mut anchor in anchors.iter_mut() {
-        anchor.coords = None;
+    fn default() -> Self {
+        Self {
+            _phantom:            PhantomData,
+            priority:            None,
+            nearest_anchor:      None,
+            top_anchor:          None,
+            anchor_count:        0,
+            last_relevant_time:  None,
+            last_relevant_frame: None,
+        }
+    }
+}
+
+impl<T> ChunkAnchorRecipient<T>
+where
+    T: Send + Sync,
+{
+    /// Checks whether at least one chunk anchor is currently within range of
+    /// this chunk recipient.
+    pub fn is_covered(&self) -> bool {
+        self.anchor_count > 0
+    }
+}
+
+/// A resource that limits the number of chunks with a
+/// [`ChunkAnchorRecipient`] of the given anchor type that may be loaded at
+/// once.
+///
+/// When this resource exists and more chunks are loaded than the budget
+/// allows, the chunks that have gone the longest without a chunk anchor in
+/// range, as tracked by [`ChunkAnchorRecipient::last_relevant_time`], are
+/// despawned until the budget is met. Chunks that have never been in range
+/// of an anchor are despawned first. This applies regardless of the radii of
+/// the anchors, which makes it useful for capping memory usage when many
+/// anchors move around a world.
+///
+/// Chunks that are currently within range of an anchor are never despawned,
+/// as they would immediately be loaded again, so the budget should be larger
+/// than the number of chunks that all anchors cover at once. Chunks that are
+/// within the [`PinnedChunks`] of their world are never despawned either, but
+/// still count towards the budget.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct ChunkMemoryBudget<T>
+where
+    T: Send + Sync,
+{
+    /// Default placeholder for T.
+    #[reflect(ignore)]
+    _phantom: PhantomData<T>,
+
+    /// The maximum number of chunks that may be loaded at once.
+    pub max_chunks: usize,
+}
+
+impl<T> ChunkMemoryBudget<T>
+where
+    T: Send + Sync,
+{
+    /// Creates a new memory budget that allows up to the given number of
+    /// chunks to be loaded at once.
+    pub fn new(max_chunks: usize) -> Self {
+        Self {
+            _phantom: PhantomData,
+            max_chunks,
+        }
+    }
+}
+
+impl<T> Default for ChunkMemoryBudget<T>
+where
+    T: Send + Sync,
+{
+    fn default() -> Self {
+        Self::new(usize::MAX)
     }
 }
 
 /// This system is called every frame to update the internal chunk coordinates
 /// within all chunk anchors, where a value can be calculated.
+///
+/// Anchors with a [`LogicalAnchorPosition`] use that position directly. Other
+/// anchors use their global transform, relative to the global transform of
+/// their world if it has one, divided by the [`BlockScale`] of the world.
+/// Anchors without either have their coordinates cleared.
 pub(crate) fn update_coords<T>(
-    worlds: Query<&GlobalTransform, With<VoxelWorld>>,
-    mut anchors: Query<(&mut ChunkAnchor<T>, &GlobalTransform)>,
+    worlds: Query<(Option<&GlobalTransform>, Option<&BlockScale>), With<VoxelWorld>>,
+    mut anchors: Query<(
+        &mut ChunkAnchor<T>,
+        Option<&GlobalTransform>,
+        Option<&LogicalAnchorPosition>,
+    )>,
 ) where
     T: Send + Sync + 'static,
 {
     anchors
         .par_iter_mut()
-        .for_each_mut(|(mut anchor, anchor_transform)| {
-            let Ok(world_transform) = worlds.get(anchor.world_id) else {
+        .for_each_mut(|(mut anchor, anchor_transform, logical_pos)| {
+            let Ok((world_transform, block_scale)) = worlds.get(anchor.world_id) else {
                 anchor.coords = None;
                 return;
             };
+            let block_scale = block_scale.copied().unwrap_or_default();
 
-            anchor.coords = Some(
-                anchor_transform
-                    .reparented_to(world_transform)
-                    .translation
-                    .as_ivec3()
-                    >> 4,
-            );
+            let position = match (logical_pos, anchor_transform, world_transform) {
+                (Some(logical_pos), ..) => logical_pos.0,
+                (None, Some(anchor_transform), Some(world_transform)) => {
+                    let local_pos = anchor_transform.reparented_to(world_transform).translation;
+                    block_scale.to_block_space(local_pos)
+                },
+                (None, Some(anchor_transform), None) => {
+                    block_scale.to_block_space(anchor_transform.translation())
+                },
+                (None, None, _) => {
+                    anchor.coords = None;
+                    return;
+                },
+            };
+
+            anchor.coords = Some(position.as_ivec3() >> 4);
         });
 }
 
 /// This system is called every frame in order to update the current chunk
 /// priorities as determined by all nearby chunk anchors.
 pub(crate) fn update_chunk_priorities<T>(
-    anchors: Query<&ChunkAnchor<T>>,
+    settings: Res<ChunkAnchorSettings<T>>,
+    time: Option<Res<Time>>,
+    frame_count: Option<Res<FrameCount>>,
+    worlds: Query<&ChunkEntityPointers, With<VoxelWorld>>,
+    anchors: Query<(Entity, &ChunkAnchor<T>)>,
     mut chunks: Query<(&mut ChunkAnchorRecipient<T>, &VoxelChunk)>,
 ) where
     T: Send + Sync + 'static,
 {
+    let combiner = settings.combiner;
+    let now = time.map(|t| t.elapsed_seconds());
+    let frame = frame_count.map(|f| f.0);
+
     chunks
         .par_iter_mut()
         .for_each_mut(|(mut anchor_recipient, chunk_meta)| {
-            anchor_recipient.priority = None;
+            let topology = worlds
+                .get(chunk_meta.world_id())
+                .map(|p| p.topology())
+                .unwrap_or_default();
+
+            let mut combined: Option<f32> = None;
+            let mut total_weight = 0.0;
+            let mut nearest: Option<(Entity, i32)> = None;
+            let mut top: Option<(Entity, f32)> = None;
+            let mut anchor_count = 0;
 
-            for anchor in anchors.iter() {
+            for (anchor_id, anchor) in anchors.iter() {
                 if anchor.world_id != chunk_meta.world_id() {
                     continue;
                 }
 
-                let Some(priority) = anchor.get_priority(chunk_meta.chunk_coords()) else {
+                let Some(priority) =
+                    anchor.get_wrapped_priority(chunk_meta.chunk_coords(), topology)
+                else {
                     continue;
                 };
 
-                anchor_recipient.priority = Some(match anchor_recipient.priority {
-                    Some(old_priority) => f32::max(priority, old_priority),
-                    None => priority,
+                combined = Some(match (combined, combiner) {
+                    (None, _) => priority,
+                    (Some(old), PriorityCombiner::Max) => f32::max(priority, old),
+                    (Some(old), PriorityCombiner::Sum | PriorityCombiner::Weighted) => {
+                        old + priority
+                    },
                 });
+                total_weight += anchor.weight;
+                anchor_count += 1;
+
+                if !matches!(top, Some((_, p)) if p >= priority) {
+                    top = Some((anchor_id, priority));
+                }
+
+                // Anchors that are in range always have coordinates.
+                let coords = anchor.coords.unwrap();
+                let distance = topology
+                    .chunk_delta(coords, chunk_meta.chunk_coords())
+                    .length_squared();
+                if !matches!(nearest, Some((_, d)) if d <= distance) {
+                    nearest = Some((anchor_id, distance));
+                }
+            }
+
+            if combiner == PriorityCombiner::Weighted && total_weight != 0.0 {
+                combined = combined.map(|p| p / total_weight);
+            }
+
+            anchor_recipient.priority = combined;
+            anchor_recipient.nearest_anchor = nearest.map(|(e, _)| e);
+            anchor_recipient.top_anchor = top.map(|(e, _)| e);
+            anchor_recipient.anchor_count = anchor_count;
+
+            if anchor_count > 0 {
+                anchor_recipient.last_relevant_time = now;
+                anchor_recipient.last_relevant_frame = frame;
             }
         });
 }
 
+/// This system despawns the least recently relevant chunks that are not
+/// within range of any chunk anchor, and are not pinned, while more chunks are
+/// loaded than the [`ChunkMemoryBudget`] allows.
+pub(crate) fn enforce_chunk_memory_budget<T>(
+    budget: Res<ChunkMemoryBudget<T>>,
+    chunks: Query<(&ChunkAnchorRecipient<T>, &VoxelChunk)>,
+    pinned: Query<&PinnedChunks>,
+    mut commands: VoxelCommands,
+) where
+    T: Send + Sync + 'static,
+{
+    let loaded = chunks.iter().len();
+    if loaded <= budget.max_chunks {
+        return;
+    }
+
+    let mut candidates: Vec<_> = chunks
+        .iter()
+        .filter(|(recipient, chunk_meta)| {
+            !recipient.is_covered() && !is_chunk_pinned(&pinned, chunk_meta)
+        })
+        .map(|(recipient, chunk_meta)| {
+            let last_relevant = recipient.last_relevant_time.unwrap_or(f32::NEG_INFINITY);
+            (
+                last_relevant,
+                chunk_meta.world_id(),
+                chunk_meta.chunk_coords(),
+            )
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, world_id, chunk_coords) in candidates.into_iter().take(loaded - budget.max_chunks) {
+        let Ok(mut world) = commands.get_world(world_id) else {
+            continue;
+        };
+
+        if let Ok(chunk) = world.get_chunk(chunk_coords) {
+            chunk.despawn();
+        }
+    }
+}
+
 /// This system automatically adds the `ChunkAnchorRecipient` component to all
 /// chunks that have been created without this component already.
 pub(crate) fn attach_chunk_recipient_comp<T>(
@@ -265,3 +608,131 @@ pub(crate) fn attach_chunk_recipient_comp<T>(
             .insert(ChunkAnchorRecipient::<T>::default());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::VoxelCommands;
+
+    #[derive(Debug, Default, Reflect)]
+    struct TestAnchor;
+
+    #[test]
+    fn sum_priorities() {
+        let mut app = App::new();
+        app.add_plugins(
+            ChunkAnchorPlugin::<TestAnchor>::default().with_combiner(PriorityCombiner::Sum),
+        );
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(GlobalTransform::default());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            let world_id = world.id();
+
+            for pos in [Vec3::ZERO, Vec3::new(32.0, 0.0, 0.0)] {
+                commands.commands().spawn((
+                    GlobalTransform::from_translation(pos),
+                    ChunkAnchor::<TestAnchor>::new(world_id, UVec3::splat(4)),
+                ));
+            }
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        app.update();
+        app.update();
+
+        let nearest = app
+            .world
+            .query::<(Entity, &ChunkAnchor<TestAnchor>)>()
+            .iter(&app.world)
+            .find(|(_, a)| a.coords == Some(IVec3::ZERO))
+            .map(|(e, _)| e);
+
+        let recipient = app
+            .world
+            .query::<&ChunkAnchorRecipient<TestAnchor>>()
+            .single(&app.world);
+        assert_eq!(recipient.priority, Some(-2.0));
+        assert_eq!(recipient.nearest_anchor, nearest);
+        assert_eq!(recipient.top_anchor, nearest);
+        assert_eq!(recipient.anchor_count, 2);
+    }
+
+    #[test]
+    fn unload_least_recently_relevant() {
+        let mut app = App::new();
+        app.add_plugins(ChunkAnchorPlugin::<TestAnchor>::default())
+            .insert_resource(ChunkMemoryBudget::<TestAnchor>::new(2));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            for x in 0 .. 4 {
+                world.spawn_chunk(IVec3::new(x, 0, 0), ()).unwrap();
+            }
+            let world_id = world.id();
+
+            commands.commands().spawn((
+                LogicalAnchorPosition(Vec3::ZERO),
+                ChunkAnchor::<TestAnchor>::new(world_id, UVec3::ZERO),
+            ));
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        // Attach the recipients without enforcing the budget yet.
+        let budget = app.world.remove_resource::<ChunkMemoryBudget<TestAnchor>>();
+        app.update();
+        app.insert_resource(budget.unwrap());
+
+        let mut recipients = app
+            .world
+            .query::<(&VoxelChunk, &mut ChunkAnchorRecipient<TestAnchor>)>();
+        for (chunk, mut recipient) in recipients.iter_mut(&mut app.world) {
+            if chunk.chunk_coords().x == 2 {
+                recipient.last_relevant_time = Some(5.0);
+            }
+        }
+
+        app.update();
+
+        let mut loaded: Vec<_> = app
+            .world
+            .query::<&VoxelChunk>()
+            .iter(&app.world)
+            .map(|c| c.chunk_coords().x)
+            .collect();
+        loaded.sort();
+        assert_eq!(loaded, vec![0, 2]);
+    }
+
+    #[test]
+    fn logical_world_anchors() {
+        let mut app = App::new();
+        app.add_plugins(ChunkAnchorPlugin::<TestAnchor>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let world_id = commands.spawn_world(()).id();
+            commands.commands().spawn((
+                LogicalAnchorPosition(Vec3::new(40.0, -3.0, 0.0)),
+                ChunkAnchor::<TestAnchor>::new(world_id, UVec3::ONE),
+            ));
+            commands.commands().spawn((
+                GlobalTransform::from_translation(Vec3::new(16.0, 0.0, 0.0)),
+                ChunkAnchor::<TestAnchor>::new(world_id, UVec3::ONE),
+            ));
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        app.update();
+
+        let mut coords: Vec<_> = app
+            .world
+            .query::<&ChunkAnchor<TestAnchor>>()
+            .iter(&app.world)
+            .map(|a| a.coords.unwrap().to_array())
+            .collect();
+        coords.sort();
+        assert_eq!(coords, vec![[1, 0, 0], [2, -1, 0]]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Readiness tracking for chunk anchors, which reports when every chunk within
+//! the range of an anchor has been loaded.
+//!
+//! This is primarily intended for loading screens, where the player should be
+//! released as soon as the chunks around them are ready, without polling the
+//! state of each chunk.
+
+use std::marker::PhantomData;
+
+use bevy::ecs::query::ReadOnlyWorldQuery;
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::HashSet;
+
+use super::anchor::ChunkAnchor;
+use crate::math::Region;
+use crate::prelude::{BlockData, ChunkDespawned, VoxelStorage, VoxelWorld};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+/// This plugin updates the [`AnchorReadiness`] of all chunk anchors of type
+/// `A`, and sends an [`AnchorReadyEvent`] whenever one of them becomes ready.
+///
+/// A chunk is considered to be ready once it has been spawned, has a
+/// `VoxelStorage<T>` component, and matches the query filter `F`. Other crates
+/// may provide filters that add their own requirements, such as the chunk
+/// meshes of the chunk having been uploaded.
+///
+/// The chunks within range of an anchor are only counted again when the region
+/// of the anchor changes, or when a chunk within its world is despawned. Until
+/// the anchor is ready, only the chunks that were not ready yet are checked
+/// each frame, and once it is ready, no chunks are checked at all.
+pub struct AnchorReadyPlugin<A, T, F = ()>
+where
+    A: Send + Sync + Default + TypePath,
+    T: BlockData,
+    F: ReadOnlyWorldQuery + Send + Sync + 'static,
+{
+    /// Phantom data for A, T, and F.
+    _phantom: PhantomData<(A, T, F)>,
+}
+
+impl<A, T, F> Default for AnchorReadyPlugin<A, T, F>
+where
+    A: Send + Sync + Default + TypePath,
+    T: BlockData,
+    F: ReadOnlyWorldQuery + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<A, T, F> Plugin for AnchorReadyPlugin<A, T, F>
+where
+    A: Send + Sync + Default + TypePath + 'static,
+    T: BlockData,
+    F: ReadOnlyWorldQuery + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.register_type::<AnchorReadiness<A>>()
+            .add_event::<ChunkDespawned>()
+            .add_event::<AnchorReadyEvent>()
+            .add_systems(Last, update_anchor_readiness::<A, T, F>);
+    }
+}
+
+/// This component can be attached to an entity with a chunk anchor in order to
+/// track whether all chunks within the range of that anchor are ready.
+///
+/// When the anchor becomes ready, an [`AnchorReadyEvent`] is sent. If the
+/// anchor later moves into chunks that are not ready yet, or a chunk within its
+/// range is despawned, it is no longer ready, and another event is sent once
+/// those chunks are ready as well.
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct AnchorReadiness<T>
+where
+    T: Send + Sync,
+{
+    /// Default placeholder for T.
+    #[reflect(ignore)]
+    _phantom: PhantomData<T>,
+
+    /// Whether all chunks within range of the anchor were ready as of the last
+    /// update.
+    ready: bool,
+
+    /// The number of chunks within range of the anchor that are ready.
+    ready_chunks: usize,
+
+    /// The total number of chunks within range of the anchor.
+    total_chunks: usize,
+
+    /// The world and region that the chunks were last counted in.
+    #[reflect(ignore)]
+    counted: Option<(Entity, Region)>,
+
+    /// The coordinates of the chunks within range of the anchor that were not
+    /// ready as of the last update.
+    #[reflect(ignore)]
+    waiting: Vec<IVec3>,
+}
+
+impl<T> Default for AnchorReadiness<T>
+where
+    T: Send + Sync,
+{
+    fn default() -> Self {
+        Self {
+            _phantom:     PhantomData,
+            ready:        false,
+            ready_chunks: 0,
+            total_chunks: 0,
+            counted:      None,
+            waiting:      Vec::new(),
+        }
+    }
+}
+
+impl<T> AnchorReadiness<T>
+where
+    T: Send + Sync,
+{
+    /// Checks whether all chunks within range of the anchor are ready.
+    ///
+    /// Anchors without coordinates are never ready.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Gets the number of chunks within range of the anchor that are ready.
+    pub fn ready_chunks(&self) -> usize {
+        self.ready_chunks
+    }
+
+    /// Gets the total number of chunks within range of the anchor.
+    pub fn total_chunks(&self) -> usize {
+        self.total_chunks
+    }
+
+    /// Gets the fraction of chunks within range of the anchor that are ready,
+    /// between `0.0` and `1.0`, which is useful for loading bars.
+    pub fn progress(&self) -> f32 {
+        if self.total_chunks == 0 {
+            return 0.0;
+        }
+
+        self.ready_chunks as f32 / self.total_chunks as f32
+    }
+}
+
+/// An event that is sent when all chunks within range of an anchor with an
+/// [`AnchorReadiness`] component become ready.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnchorReadyEvent {
+    /// The id of the anchor entity.
+    pub anchor_id: Entity,
+
+    /// The id of the world the anchor is in.
+    pub world_id: Entity,
+}
+
+/// This system counts the ready chunks within range of all chunk anchors with
+/// an anchor readiness component, and sends events for all anchors that became
+/// ready.
+///
+/// All chunks within range of an anchor are only counted when its region has
+/// changed, or when a chunk within its world has been despawned. Otherwise,
+/// only the chunks that were not ready yet are checked again.
+pub(crate) fn update_anchor_readiness<A, T, F>(
+    worlds: Query<&ChunkEntityPointers, With<VoxelWorld>>,
+    mut anchors: Query<(Entity, &ChunkAnchor<A>, &mut AnchorReadiness<A>)>,
+    chunks: Query<(), (With<VoxelStorage<T>>, F)>,
+    mut despawned: EventReader<ChunkDespawned>,
+    mut events: EventWriter<AnchorReadyEvent>,
+) where
+    A: Send + Sync + 'static,
+    T: BlockData,
+    F: ReadOnlyWorldQuery + Send + Sync + 'static,
+{
+    let despawned: HashSet<Entity> = despawned.iter().map(|event| event.world_id).collect();
+
+    for (anchor_id, anchor, mut readiness) in anchors.iter_mut() {
+        let world_id = anchor.world_id;
+        let (Ok(pointers), Some(region)) = (worlds.get(world_id), anchor.get_region()) else {
+            if readiness.ready || readiness.total_chunks > 0 || readiness.counted.is_some() {
+                *readiness = AnchorReadiness::default();
+            }
+            continue;
+        };
+
+        let is_ready = |chunk_coords: &IVec3| {
+            pointers
+                .get_chunk_entity(*chunk_coords)
+                .is_some_and(|chunk_id| chunks.contains(chunk_id))
+        };
+
+        let recount =
+            readiness.counted != Some((world_id, region)) || despawned.contains(&world_id);
+        if !recount && readiness.waiting.is_empty() {
+            continue;
+        }
+
+        let (total_chunks, waiting) = match recount {
+            true => {
+                let topology = pointers.topology();
+                let canonical: HashSet<IVec3> = region
+                    .iter()
+                    .map(|c| topology.wrap_chunk_coords(c))
+                    .collect();
+
+                let total_chunks = canonical.len();
+                let waiting: Vec<IVec3> = canonical.into_iter().filter(|c| !is_ready(c)).collect();
+                (total_chunks, waiting)
+            },
+            false => {
+                let mut waiting = std::mem::take(&mut readiness.bypass_change_detection().waiting);
+                waiting.retain(|c| !is_ready(c));
+                (readiness.total_chunks, waiting)
+            },
+        };
+
+        let ready_chunks = total_chunks - waiting.len();
+        let ready = waiting.is_empty();
+
+        if ready && !readiness.ready {
+            events.send(AnchorReadyEvent {
+                anchor_id,
+                world_id,
+            });
+        }
+
+        let changed = readiness.ready != ready
+            || readiness.ready_chunks != ready_chunks
+            || readiness.total_chunks != total_chunks;
+
+        let state = readiness.bypass_change_detection();
+        state.counted = Some((world_id, region));
+        state.waiting = waiting;
+        state.ready = ready;
+        state.ready_chunks = ready_chunks;
+        state.total_chunks = total_chunks;
+
+        if changed {
+            readiness.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::anchor::{ChunkAnchorPlugin, LogicalAnchorPosition};
+
+    #[derive(Debug, Default, Reflect)]
+    struct TestAnchor;
+
+    #[test]
+    fn ready_once_all_chunks_loaded() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            ChunkAnchorPlugin::<TestAnchor>::default(),
+            AnchorReadyPlugin::<TestAnchor, u8>::default(),
+        ));
+
+        let world_id = app.world.spawn(VoxelWorldBundle::new()).id();
+        let anchor_id = app
+            .world
+            .spawn((
+                LogicalAnchorPosition(Vec3::ZERO),
+                ChunkAnchor::<TestAnchor>::new(world_id, UVec3::new(1, 0, 0)),
+                AnchorReadiness::<TestAnchor>::default(),
+            ))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                world
+                    .spawn_chunk(IVec3::NEG_X, VoxelStorage::<u8>::default())
+                    .unwrap();
+                world
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                    .unwrap();
+                world.spawn_chunk(IVec3::X, ()).unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        let readiness = app
+            .world
+            .get::<AnchorReadiness<TestAnchor>>(anchor_id)
+            .unwrap();
+        assert!(!readiness.is_ready());
+        assert_eq!(readiness.ready_chunks(), 2);
+        assert_eq!(readiness.total_chunks(), 3);
+        assert!(app.world.resource::<Events<AnchorReadyEvent>>().is_empty());
+
+        let chunk_id = app
+            .world
+            .get::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .get_chunk_entity(IVec3::X)
+            .unwrap();
+        app.world
+            .entity_mut(chunk_id)
+            .insert(VoxelStorage::<u8>::default());
+        app.update();
+
+        let readiness = app
+            .world
+            .get::<AnchorReadiness<TestAnchor>>(anchor_id)
+            .unwrap();
+        assert!(readiness.is_ready());
+        assert_eq!(readiness.progress(), 1.0);
+
+        let events = app.world.resource::<Events<AnchorReadyEvent>>();
+        assert_eq!(
+            events.iter_current_update_events().collect::<Vec<_>>(),
+            vec![&AnchorReadyEvent {
+                anchor_id,
+                world_id,
+            }]
+        );
+
+        app.update();
+        let events = app.world.resource::<Events<AnchorReadyEvent>>();
+        assert_eq!(events.iter_current_update_events().count(), 0);
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                commands
+                    .get_world(world_id)
+                    .unwrap()
+                    .get_chunk(IVec3::X)
+                    .unwrap()
+                    .despawn();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        let readiness = app
+            .world
+            .get::<AnchorReadiness<TestAnchor>>(anchor_id)
+            .unwrap();
+        assert!(!readiness.is_ready());
+        assert_eq!(readiness.ready_chunks(), 2);
+        assert_eq!(readiness.total_chunks(), 3);
+    }
+}
@@ -0,0 +1,357 @@
+//! A generic cellular automaton framework that runs over voxel data.
+//!
+//! This provides the shared machinery for block simulations such as fluids,
+//! gases, fire, or temperature. Only active cells are evaluated each tick,
+//! reads across chunk borders are resolved through the chunk pointers of the
+//! world, and all changes within a tick are double-buffered so that the
+//! outcome does not depend on the order in which cells are visited.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::math::BlockFace;
+use crate::prelude::{BlockData, ChunkDespawned, VoxelQuery, VoxelStorage, WorldDespawned};
+use crate::util::block_update::BlockUpdateQueue;
+use crate::util::simulation::VoxelSimulationSet;
+
+/// This plugin runs the given automaton rule on the [`FixedUpdate`] schedule,
+/// for all active cells of the given block data type.
+///
+/// The automaton runs within [`VoxelSimulationSet::Automata`], so when the
+/// [`VoxelSimulationPlugin`](crate::util::simulation::VoxelSimulationPlugin)
+/// is added, it runs after all gameplay ticks and before their block updates
+/// are sent.
+pub struct VoxelAutomatonPlugin<T, R>
+where
+    T: BlockData,
+    R: AutomatonRule<T>,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// The rule that is run for each active cell.
+    rule: R,
+}
+
+impl<T, R> VoxelAutomatonPlugin<T, R>
+where
+    T: BlockData,
+    R: AutomatonRule<T> + Clone,
+{
+    /// Creates a new automaton plugin that runs the given rule.
+    pub fn new(rule: R) -> Self {
+        Self {
+            _phantom: PhantomData,
+            rule,
+        }
+    }
+}
+
+impl<T, R> Plugin for VoxelAutomatonPlugin<T, R>
+where
+    T: BlockData,
+    R: AutomatonRule<T> + Clone,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(VoxelAutomaton::<T>::new(self.rule.clone()))
+            .add_event::<WorldDespawned>()
+            .add_event::<ChunkDespawned>()
+            .add_systems(
+                FixedUpdate,
+                (remove_despawned_cells::<T>, tick_automaton::<T>)
+                    .chain()
+                    .in_set(VoxelSimulationSet::Automata),
+            );
+    }
+}
+
+/// The update rule of a cellular automaton.
+pub trait AutomatonRule<T>: Send + Sync + 'static
+where
+    T: BlockData,
+{
+    /// Calculates the next value of the given cell, based on the state of the
+    /// world at the start of the current tick.
+    ///
+    /// Returns `None` if the cell does not change, in which case the cell
+    /// becomes inactive until it or one of its neighbors is changed again.
+    fn update(&self, cell: &AutomatonCell<T>) -> Option<T>;
+}
+
+/// A read-only view of a single cell and its surroundings, as of the start of
+/// the current tick.
+pub struct AutomatonCell<'a, T>
+where
+    T: BlockData,
+{
+    /// The block coordinates of the cell.
+    block_coords: IVec3,
+
+    /// Reads a block from the world.
+    get_block: &'a dyn Fn(IVec3) -> Option<T>,
+}
+
+impl<'a, T> AutomatonCell<'a, T>
+where
+    T: BlockData,
+{
+    /// Gets the block coordinates of this cell.
+    pub fn block_coords(&self) -> IVec3 {
+        self.block_coords
+    }
+
+    /// Gets the current value of this cell.
+    pub fn value(&self) -> T {
+        self.neighbor(IVec3::ZERO).unwrap_or_default()
+    }
+
+    /// Gets the value of the block at the given offset from this cell, or
+    /// `None` if the chunk containing that block is not loaded.
+    pub fn neighbor(&self, offset: IVec3) -> Option<T> {
+        (self.get_block)(self.block_coords + offset)
+    }
+}
+
+/// A resource that stores the rule and the active cells of a cellular
+/// automaton for the given block data type.
+#[derive(Resource)]
+pub struct VoxelAutomaton<T>
+where
+    T: BlockData,
+{
+    /// The rule that is run for each active cell.
+    rule: Box<dyn AutomatonRule<T>>,
+
+    /// The set of active cells, grouped by world id and chunk coordinates.
+    active: HashMap<(Entity, IVec3), HashSet<IVec3>>,
+
+    /// The number of ticks that have been run.
+    ticks: u64,
+}
+
+impl<T> VoxelAutomaton<T>
+where
+    T: BlockData,
+{
+    /// Creates a new automaton with the given rule and no active cells.
+    pub fn new<R>(rule: R) -> Self
+    where
+        R: AutomatonRule<T>,
+    {
+        Self {
+            rule:   Box::new(rule),
+            active: HashMap::new(),
+            ticks:  0,
+        }
+    }
+
+    /// Marks the cell at the given block coordinates as active, so that it is
+    /// evaluated on the next tick.
+    pub fn activate(&mut self, world_id: Entity, block_coords: IVec3) {
+        self.active
+            .entry((world_id, block_coords >> 4))
+            .or_default()
+            .insert(block_coords);
+    }
+
+    /// Marks the cell at the given block coordinates, along with its six
+    /// neighbors, as active.
+    pub fn activate_with_neighbors(&mut self, world_id: Entity, block_coords: IVec3) {
+        self.activate(world_id, block_coords);
+        for offset in BlockFace::ALL.map(BlockFace::normal) {
+            self.activate(world_id, block_coords + offset);
+        }
+    }
+
+    /// Checks whether the cell at the given block coordinates is active.
+    pub fn is_active(&self, world_id: Entity, block_coords: IVec3) -> bool {
+        self.active
+            .get(&(world_id, block_coords >> 4))
+            .is_some_and(|cells| cells.contains(&block_coords))
+    }
+
+    /// Gets the number of active cells within the given chunk.
+    pub fn active_in_chunk(&self, world_id: Entity, chunk_coords: IVec3) -> usize {
+        self.active
+            .get(&(world_id, chunk_coords))
+            .map_or(0, |cells| cells.len())
+    }
+
+    /// Gets the total number of active cells across all worlds.
+    pub fn active_count(&self) -> usize {
+        self.active.values().map(|cells| cells.len()).sum()
+    }
+
+    /// Gets the number of ticks that have been run.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Removes all active cells within the given world.
+    pub fn remove_world(&mut self, world_id: Entity) {
+        self.active.retain(|(w, _), _| *w != world_id);
+    }
+
+    /// Removes all active cells within the given chunk.
+    pub fn remove_chunk(&mut self, world_id: Entity, chunk_coords: IVec3) {
+        self.active.remove(&(world_id, chunk_coords));
+    }
+}
+
+/// This system removes all active cells within chunks and worlds that have
+/// been despawned.
+fn remove_despawned_cells<T>(
+    mut worlds: EventReader<WorldDespawned>,
+    mut chunks: EventReader<ChunkDespawned>,
+    mut automaton: ResMut<VoxelAutomaton<T>>,
+) where
+    T: BlockData,
+{
+    for ev in worlds.iter() {
+        automaton.remove_world(ev.world_id);
+    }
+
+    for ev in chunks.iter() {
+        automaton.remove_chunk(ev.world_id, ev.chunk_coords);
+    }
+}
+
+/// This system runs a single tick of the automaton over all active cells.
+///
+/// Cells within chunks that are not loaded are kept active, and are evaluated
+/// once their chunk is available.
+fn tick_automaton<T>(
+    mut automaton: ResMut<VoxelAutomaton<T>>,
+    mut chunks: VoxelQuery<&mut VoxelStorage<T>>,
+    mut block_updates: Option<ResMut<BlockUpdateQueue>>,
+) where
+    T: BlockData,
+{
+    automaton.ticks += 1;
+    if automaton.active.is_empty() {
+        return;
+    }
+
+    let active = std::mem::take(&mut automaton.active);
+    let mut writes = vec![];
+
+    for ((world_id, chunk_coords), cells) in active {
+        let Ok(world) = chunks.get_world(world_id) else {
+            continue;
+        };
+
+        if world.get_chunk(chunk_coords).is_none() {
+            automaton.active.insert((world_id, chunk_coords), cells);
+            continue;
+        }
+
+        let topology = world.topology();
+        let get_block = |block_coords: IVec3| {
+            let block_coords = topology.wrap_block_coords(block_coords);
+            world
+                .get_chunk(block_coords >> 4)
+                .map(|storage| storage.get_block(block_coords))
+        };
+
+        for block_coords in cells {
+            let cell = AutomatonCell {
+                block_coords,
+                get_block: &get_block,
+            };
+
+            if let Some(value) = automaton.rule.update(&cell) {
+                writes.push((world_id, block_coords, value));
+            }
+        }
+    }
+
+    for (world_id, block_coords, value) in writes {
+        let Ok(mut world) = chunks.get_world_mut(world_id) else {
+            continue;
+        };
+
+        let topology = world.topology();
+        let Some(mut storage) = world.get_chunk_at_block_mut(block_coords) else {
+            continue;
+        };
+        storage.set_block(block_coords, value);
+
+        if let Some(queue) = block_updates.as_mut() {
+            queue.push(world_id, block_coords);
+        }
+
+        automaton.activate(world_id, block_coords);
+        for offset in BlockFace::ALL.map(BlockFace::normal) {
+            let neighbor = topology.wrap_block_coords(block_coords + offset);
+            automaton.activate(world_id, neighbor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    /// A rule where sand falls down into empty space below it.
+    #[derive(Clone)]
+    struct Gravity;
+
+    impl AutomatonRule<u8> for Gravity {
+        fn update(&self, cell: &AutomatonCell<u8>) -> Option<u8> {
+            let below = cell.neighbor(IVec3::NEG_Y);
+            let above = cell.neighbor(IVec3::Y);
+
+            match (cell.value(), below, above) {
+                (1, Some(0), _) => Some(0),
+                (0, _, Some(1)) => Some(1),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn falling_sand() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            VoxelAutomatonPlugin::<u8, _>::new(Gravity),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            storage.set_block(IVec3::new(3, 2, 3), 1);
+
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, storage).unwrap();
+            world
+                .spawn_chunk(IVec3::NEG_Y, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        app.world
+            .resource_mut::<VoxelAutomaton<u8>>()
+            .activate_with_neighbors(world_id, IVec3::new(3, 2, 3));
+
+        for _ in 0 .. 4 {
+            app.world.run_schedule(FixedUpdate);
+        }
+
+        let mut storages = app.world.query::<(&VoxelChunk, &VoxelStorage<u8>)>();
+        let (_, below) = storages
+            .iter(&app.world)
+            .find(|(c, _)| c.chunk_coords() == IVec3::NEG_Y)
+            .unwrap();
+        assert_eq!(below.get_block(IVec3::new(3, -2, 3)), 1);
+        assert_eq!(app.world.resource::<VoxelAutomaton<u8>>().ticks(), 4);
+    }
+}
@@ -0,0 +1,125 @@
+//! The shared world access that is provided to block callbacks, such as block
+//! interaction handlers and block scripts.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::prelude::{BlockData, VoxelCommands, VoxelQuery, VoxelStorage};
+
+/// A block callback function, which is run with the given trigger type.
+pub type BlockCallback<T, E> = Arc<dyn Fn(&mut BlockContext<T, E>) + Send + Sync>;
+
+/// The world access that is provided to a block callback, along with the
+/// trigger that caused the callback to run.
+///
+/// Callbacks may read any loaded block within the same world, but block writes
+/// are only queued, and are applied through voxel commands after the callback
+/// has finished.
+pub struct BlockContext<'a, T, E>
+where
+    T: BlockData,
+{
+    /// The trigger that caused the callback to run.
+    trigger: E,
+
+    /// The coordinates of the block that triggered the callback.
+    block_coords: IVec3,
+
+    /// The block that triggered the callback.
+    block: T,
+
+    /// Reads a block from the world.
+    get_block: &'a dyn Fn(IVec3) -> Option<T>,
+
+    /// The queued block writes.
+    writes: Vec<(IVec3, T)>,
+}
+
+impl<'a, T, E> BlockContext<'a, T, E>
+where
+    T: BlockData,
+{
+    /// Gets the trigger that caused the callback to run.
+    pub fn trigger(&self) -> &E {
+        &self.trigger
+    }
+
+    /// Gets the coordinates of the block that triggered the callback.
+    pub fn block_coords(&self) -> IVec3 {
+        self.block_coords
+    }
+
+    /// Gets the value of the block that triggered the callback.
+    pub fn block(&self) -> T {
+        self.block
+    }
+
+    /// Gets the block at the given block coordinates, or `None` if the chunk
+    /// containing it is not loaded.
+    pub fn get_block(&self, block_coords: IVec3) -> Option<T> {
+        (self.get_block)(block_coords)
+    }
+
+    /// Queues a block write at the given block coordinates.
+    pub fn set_block(&mut self, block_coords: IVec3, data: T) {
+        self.writes.push((block_coords, data));
+    }
+
+    /// Queues a write that replaces the block that triggered the callback.
+    pub fn replace_block(&mut self, data: T) {
+        self.set_block(self.block_coords, data);
+    }
+}
+
+/// Runs the callback that is found for the block at the given block
+/// coordinates, and applies its queued block writes through voxel commands.
+///
+/// Nothing is run if the world does not exist, the chunk containing the block
+/// is not loaded, or no callback is found for the block.
+pub(crate) fn run_block_callback<T, E, F>(
+    chunks: &VoxelQuery<&VoxelStorage<T>>,
+    commands: &mut VoxelCommands,
+    world_id: Entity,
+    block_coords: IVec3,
+    trigger: E,
+    find_callback: F,
+) where
+    T: BlockData,
+    F: FnOnce(T) -> Option<BlockCallback<T, E>>,
+{
+    let Ok(world) = chunks.get_world(world_id) else {
+        return;
+    };
+
+    let get_block = |block_coords: IVec3| {
+        world
+            .get_chunk(block_coords >> 4)
+            .map(|storage| storage.get_block(block_coords))
+    };
+
+    let Some(block) = get_block(block_coords) else {
+        return;
+    };
+
+    let Some(callback) = find_callback(block) else {
+        return;
+    };
+
+    let mut ctx = BlockContext {
+        trigger,
+        block_coords,
+        block,
+        get_block: &get_block,
+        writes: vec![],
+    };
+    callback(&mut ctx);
+
+    let Ok(mut world_commands) = commands.get_world(world_id) else {
+        return;
+    };
+
+    for (block_coords, data) in ctx.writes {
+        world_commands.set_block(block_coords, data);
+    }
+}
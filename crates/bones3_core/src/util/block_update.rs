@@ -0,0 +1,198 @@
+//! A handler for notifying blocks when one of their neighboring blocks has been
+//! changed.
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::math::BlockFace;
+use crate::storage::register_spawned_chunks;
+use crate::Bones3CoreSet;
+
+/// This plugin handles the propagation of block updates to neighboring blocks,
+/// configures the order of the [`Bones3CoreSet`] system sets, and registers
+/// chunks that were spawned from bundles within their worlds.
+///
+/// It is automatically added by the core plugin, and only needs to be added
+/// once regardless of how many block data types are in use.
+#[derive(Default)]
+pub struct BlockUpdatePlugin;
+
+impl Plugin for BlockUpdatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NeighborChangedEvent>()
+            .init_resource::<BlockUpdateQueue>()
+            .configure_sets(
+                PostUpdate,
+                (
+                    Bones3CoreSet::BlockWrites,
+                    Bones3CoreSet::FlushBlockWrites,
+                    Bones3CoreSet::BlockUpdates,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    register_spawned_chunks.before(Bones3CoreSet::BlockWrites),
+                    apply_deferred.in_set(Bones3CoreSet::FlushBlockWrites),
+                    propagate_block_updates.in_set(Bones3CoreSet::BlockUpdates),
+                ),
+            );
+    }
+}
+
+/// An event that is triggered for each of the six blocks that are adjacent to
+/// a block that has been changed.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NeighborChangedEvent {
+    /// The id of the world the blocks are in.
+    pub world_id: Entity,
+
+    /// The coordinates of the block that is being notified.
+    pub block_coords: IVec3,
+
+    /// The coordinates of the neighboring block that was changed.
+    pub source_coords: IVec3,
+}
+
+/// A resource that collects all blocks that have been changed over the course
+/// of the current frame.
+///
+/// Each block is only stored once, regardless of how many times it was
+/// changed, and all neighbor notifications are sent together at the end of the
+/// frame.
+#[derive(Debug, Default, Resource)]
+pub struct BlockUpdateQueue {
+    /// The set of world ids and block coordinates that have been changed.
+    changed: HashSet<(Entity, IVec3)>,
+}
+
+impl BlockUpdateQueue {
+    /// Marks the block at the given block coordinates within the indicated
+    /// world as changed.
+    ///
+    /// Block writes performed through voxel commands are marked automatically.
+    /// This method only needs to be called when block storage is edited
+    /// directly.
+    pub fn push(&mut self, world_id: Entity, block_coords: IVec3) {
+        self.changed.insert((world_id, block_coords));
+    }
+
+    /// Gets an iterator over all pending block updates, as pairs of world ids
+    /// and block coordinates.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, IVec3)> + '_ {
+        self.changed.iter().copied()
+    }
+
+    /// Removes all pending block updates for the given world.
+    pub(crate) fn remove_world(&mut self, world_id: Entity) {
+        self.changed.retain(|(w, _)| *w != world_id);
+    }
+
+    /// Gets whether or not there are any pending block updates.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+/// This system drains the block update queue and sends a neighbor changed
+/// event to each of the blocks adjacent to a changed block.
+pub(crate) fn propagate_block_updates(
+    mut queue: ResMut<BlockUpdateQueue>,
+    mut events: EventWriter<NeighborChangedEvent>,
+) {
+    if queue.is_empty() {
+        return;
+    }
+
+    for (world_id, source_coords) in queue.changed.drain() {
+        events.send_batch(BlockFace::ALL.map(BlockFace::normal).map(|offset| {
+            NeighborChangedEvent {
+                world_id,
+                block_coords: source_coords + offset,
+                source_coords,
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn flush_writes_when_added_first() {
+        let mut app = App::new();
+        app.add_plugins((BlockUpdatePlugin, Bones3CorePlugin::<u8>::default()));
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+        app.update();
+
+        fn write(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            let mut world_commands = commands.get_world(world_id).unwrap();
+            world_commands.set_block(IVec3::new(1, 2, 3), 4u8);
+        }
+        app.add_systems(PostUpdate, write.in_set(Bones3CoreSet::BlockWrites));
+        app.update();
+
+        let storage = app.world.query::<&VoxelStorage<u8>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 3)), 4);
+
+        let events = app.world.resource::<Events<NeighborChangedEvent>>();
+        assert_eq!(events.len(), 6);
+    }
+
+    #[test]
+    fn notify_neighbors_once() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn write(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.get_single().unwrap();
+            let mut world_commands = commands.get_world(world_id).unwrap();
+            world_commands.set_block(IVec3::new(3, 4, 5), 7u8);
+            world_commands.set_block(IVec3::new(3, 4, 5), 8u8);
+        }
+        Schedule::new().add_systems(write).run(&mut app.world);
+
+        app.update();
+
+        let events = app.world.resource::<Events<NeighborChangedEvent>>();
+        let mut reader = events.get_reader();
+        let mut neighbors = reader
+            .iter(events)
+            .map(|ev| ev.block_coords)
+            .collect::<Vec<_>>();
+        neighbors.sort_by_key(|p| (p.x, p.y, p.z));
+
+        assert_eq!(neighbors, vec![
+            IVec3::new(2, 4, 5),
+            IVec3::new(3, 3, 5),
+            IVec3::new(3, 4, 4),
+            IVec3::new(3, 4, 6),
+            IVec3::new(3, 5, 5),
+            IVec3::new(4, 4, 5),
+        ]);
+
+        let storage = app.world.query::<&VoxelStorage<u8>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(3, 4, 5)), 8);
+    }
+}
@@ -0,0 +1,549 @@
+//! A brush tool for painting and sculpting blocks within a voxel world, with
+//! support for undoing and redoing edits.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::math::Region;
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{bump_chunk_version, BlockData, VoxelStorage};
+use crate::util::block_update::BlockUpdateQueue;
+
+/// The shape of a voxel brush.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum BrushShape {
+    /// A sphere with the brush radius.
+    #[default]
+    Sphere,
+
+    /// An axis-aligned cube with a half-width equal to the brush radius.
+    Cube,
+}
+
+/// A brush that describes the area of a voxel world that is affected by a
+/// single edit.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct VoxelBrush {
+    /// The shape of the brush.
+    pub shape: BrushShape,
+
+    /// The radius of the brush, in blocks.
+    pub radius: f32,
+
+    /// The fraction of the radius, from `0.0` to `1.0`, that is fully
+    /// affected by the brush. Past this point, the brush strength falls off
+    /// smoothly to zero at the edge of the brush.
+    ///
+    /// A hardness of `1.0` creates a brush with a hard edge.
+    pub hardness: f32,
+}
+
+impl Default for VoxelBrush {
+    fn default() -> Self {
+        Self {
+            shape:    BrushShape::Sphere,
+            radius:   3.0,
+            hardness: 1.0,
+        }
+    }
+}
+
+impl VoxelBrush {
+    /// Creates a new spherical brush with a hard edge.
+    pub fn sphere(radius: f32) -> Self {
+        Self {
+            shape: BrushShape::Sphere,
+            radius,
+            hardness: 1.0,
+        }
+    }
+
+    /// Creates a new cube brush with a hard edge.
+    pub fn cube(radius: f32) -> Self {
+        Self {
+            shape: BrushShape::Cube,
+            radius,
+            hardness: 1.0,
+        }
+    }
+
+    /// Sets the hardness of this brush.
+    pub fn with_hardness(mut self, hardness: f32) -> Self {
+        self.hardness = hardness.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Gets the region of block coordinates that may be affected by this
+    /// brush when centered on the given block.
+    pub fn region(&self, center: IVec3) -> Region {
+        let extent = IVec3::splat(self.radius.max(0.0).floor() as i32);
+        Region::from_points(center - extent, center + extent)
+    }
+
+    /// Gets the strength of this brush, from `0.0` to `1.0`, at the given
+    /// offset from the center of the brush.
+    pub fn weight(&self, offset: Vec3) -> f32 {
+        if self.radius <= 0.0 {
+            return if offset == Vec3::ZERO { 1.0 } else { 0.0 };
+        }
+
+        let distance = match self.shape {
+            BrushShape::Sphere => offset.length(),
+            BrushShape::Cube => offset.abs().max_element(),
+        } / self.radius;
+
+        if distance > 1.0 {
+            0.0
+        } else if distance <= self.hardness {
+            1.0
+        } else {
+            let t = (1.0 - distance) / (1.0 - self.hardness);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+
+    /// Checks whether the block at the given offset from the center of the
+    /// brush should be edited.
+    ///
+    /// Within the soft edge of the brush, blocks are selected using a stable
+    /// dither pattern based off their world coordinates, so that the density
+    /// of edited blocks follows the brush strength.
+    pub fn affects(&self, center: IVec3, block_coords: IVec3) -> bool {
+        let weight = self.weight((block_coords - center).as_vec3());
+        weight >= 1.0 || (weight > 0.0 && weight > dither(block_coords))
+    }
+}
+
+/// Gets a stable pseudo-random value from `0.0` to `1.0` for the given block
+/// coordinates.
+fn dither(block_coords: IVec3) -> f32 {
+    let mut hash = (block_coords.x as u32).wrapping_mul(0x8DA6_B343)
+        ^ (block_coords.y as u32).wrapping_mul(0xD816_3841)
+        ^ (block_coords.z as u32).wrapping_mul(0xCB1A_B31F);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
+
+/// The operation that a brush applies to each affected block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushOp<T>
+where
+    T: BlockData,
+{
+    /// Sets all affected blocks to the given value.
+    Set(T),
+
+    /// Replaces all affected blocks that match `from` with `to`.
+    Replace {
+        /// The block value to replace.
+        from: T,
+
+        /// The new block value.
+        to: T,
+    },
+
+    /// Sets all affected blocks to the default value for `T`.
+    Erase,
+
+    /// Replaces each affected block with the most common block among itself
+    /// and its 26 neighbors, rounding off sharp edges and filling small holes.
+    Smooth,
+}
+
+/// A single block that was changed by an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChange<T>
+where
+    T: BlockData,
+{
+    /// The block coordinates of the changed block.
+    pub block_coords: IVec3,
+
+    /// The value of the block before the edit.
+    pub old: T,
+
+    /// The value of the block after the edit.
+    pub new: T,
+}
+
+/// A list of all block changes that were made to a voxel world by a single
+/// edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelEdit<T>
+where
+    T: BlockData,
+{
+    /// The id of the world that was edited.
+    pub world_id: Entity,
+
+    /// The blocks that were changed.
+    pub changes: Vec<BlockChange<T>>,
+}
+
+impl<T> VoxelEdit<T>
+where
+    T: BlockData,
+{
+    /// Checks whether this edit did not change any blocks.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Writes the new value of every changed block into the world.
+    pub fn apply(&self, world: &mut World) {
+        let blocks = self.changes.iter().map(|c| (c.block_coords, c.new));
+        write_blocks(world, self.world_id, blocks);
+    }
+
+    /// Writes the old value of every changed block into the world, undoing
+    /// this edit.
+    pub fn revert(&self, world: &mut World) {
+        let blocks = self.changes.iter().rev().map(|c| (c.block_coords, c.old));
+        write_blocks(world, self.world_id, blocks);
+    }
+}
+
+/// A resource that stores the history of edits made to voxel worlds with the
+/// given block data type, allowing them to be undone and redone.
+///
+/// Brush edits made using
+/// [`VoxelWorldCommands::apply_brush`](crate::query::VoxelWorldCommands::apply_brush)
+/// are only recorded if this resource exists.
+#[derive(Debug, Resource)]
+pub struct VoxelEditHistory<T>
+where
+    T: BlockData,
+{
+    /// The edits that can be undone, from oldest to newest.
+    undo: VecDeque<VoxelEdit<T>>,
+
+    /// The edits that can be redone, from oldest to newest.
+    redo: Vec<VoxelEdit<T>>,
+
+    /// The maximum number of edits that can be undone.
+    capacity: usize,
+}
+
+impl<T> Default for VoxelEditHistory<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+impl<T> VoxelEditHistory<T>
+where
+    T: BlockData,
+{
+    /// Creates a new, empty edit history that remembers up to the given
+    /// number of edits.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            capacity,
+        }
+    }
+
+    /// Records a new edit, discarding all edits that could be redone. Empty
+    /// edits are ignored.
+    pub fn push(&mut self, edit: VoxelEdit<T>) {
+        if edit.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Gets the number of edits that can be undone.
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Gets the number of edits that can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Gets the edit that would be reverted by the next undo, if any.
+    pub fn peek_undo(&self) -> Option<&VoxelEdit<T>> {
+        self.undo.back()
+    }
+
+    /// Gets the edit that would be applied by the next redo, if any.
+    pub fn peek_redo(&self) -> Option<&VoxelEdit<T>> {
+        self.redo.last()
+    }
+
+    /// Removes all recorded edits.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Applies the given brush operation to all affected blocks around the given
+/// center, returning the resulting edit.
+///
+/// See [`edit_blocks`] for more information on how blocks are read and
+/// written.
+///
+/// This function returns `None` if the given world does not exist.
+pub fn apply_brush<T>(
+    world: &mut World,
+    world_id: Entity,
+    center: IVec3,
+    brush: &VoxelBrush,
+    op: BrushOp<T>,
+) -> Option<VoxelEdit<T>>
+where
+    T: BlockData + PartialEq,
+{
+    edit_blocks(
+        world,
+        world_id,
+        brush.region(center),
+        |block_coords, old, get_block| {
+            if !brush.affects(center, block_coords) {
+                return old;
+            }
+
+            match op {
+                BrushOp::Set(value) => value,
+                BrushOp::Replace {
+                    from,
+                    to,
+                } if old == from => to,
+                BrushOp::Replace {
+                    ..
+                } => old,
+                BrushOp::Erase => T::default(),
+                BrushOp::Smooth => most_common_neighbor(block_coords, old, get_block),
+            }
+        },
+    )
+}
+
+/// Calls the given function for every block within the given region of a
+/// voxel world, writing each returned value back into the world and returning
+/// the resulting edit.
+///
+/// The function is given the block coordinates, the current block value, and
+/// a function for reading any other block within the world. All blocks are
+/// read before any are written, so edits such as smoothing are not affected
+/// by the order in which blocks are processed. Blocks within chunks that are
+/// not loaded, or that do not have a `VoxelStorage<T>` component, are skipped.
+/// All changed blocks are pushed to the [`BlockUpdateQueue`].
+///
+/// This function returns `None` if the given world does not exist.
+pub fn edit_blocks<T, F>(
+    world: &mut World,
+    world_id: Entity,
+    region: Region,
+    mut edit_block: F,
+) -> Option<VoxelEdit<T>>
+where
+    T: BlockData + PartialEq,
+    F: FnMut(IVec3, T, &dyn Fn(IVec3) -> Option<T>) -> T,
+{
+    let pointers = world.get::<ChunkEntityPointers>(world_id)?;
+    let topology = pointers.topology();
+
+    let get_block = |block_coords: IVec3| {
+        let block_coords = topology.wrap_block_coords(block_coords);
+        let chunk_id = pointers.get_chunk_entity(block_coords >> 4)?;
+        let storage = world.get::<VoxelStorage<T>>(chunk_id)?;
+        Some(storage.get_block(block_coords))
+    };
+
+    let mut changes = vec![];
+    for block_coords in region.iter() {
+        let Some(old) = get_block(block_coords) else {
+            continue;
+        };
+
+        let new = edit_block(block_coords, old, &get_block);
+        if old != new {
+            changes.push(BlockChange {
+                block_coords: topology.wrap_block_coords(block_coords),
+                old,
+                new,
+            });
+        }
+    }
+
+    let edit = VoxelEdit {
+        world_id,
+        changes,
+    };
+    edit.apply(world);
+    Some(edit)
+}
+
+/// Gets the most common block among the given block and its 26 neighbors.
+/// Ties are resolved in favor of the original block.
+fn most_common_neighbor<T>(
+    block_coords: IVec3,
+    block: T,
+    get_block: &dyn Fn(IVec3) -> Option<T>,
+) -> T
+where
+    T: BlockData + PartialEq,
+{
+    let mut counts: Vec<(T, usize)> = vec![(block, 0)];
+    for offset in Region::NEIGHBORS.iter() {
+        let Some(neighbor) = get_block(block_coords + offset) else {
+            continue;
+        };
+
+        match counts.iter_mut().find(|(value, _)| *value == neighbor) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((neighbor, 1)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        .map_or(block, |(value, _)| value)
+}
+
+/// Writes the given block values into a voxel world, pushing each block to
+/// the block update queue. Blocks within missing chunks are skipped.
+fn write_blocks<T, I>(world: &mut World, world_id: Entity, blocks: I)
+where
+    T: BlockData,
+    I: IntoIterator<Item = (IVec3, T)>,
+{
+    let Some(pointers) = world.get::<ChunkEntityPointers>(world_id) else {
+        return;
+    };
+
+    let writes: Vec<_> = blocks
+        .into_iter()
+        .filter_map(|(block_coords, value)| {
+            let chunk_id = pointers.get_chunk_entity(block_coords >> 4)?;
+            Some((chunk_id, block_coords, value))
+        })
+        .collect();
+
+    for (chunk_id, block_coords, value) in writes {
+        let Some(mut storage) = world.get_mut::<VoxelStorage<T>>(chunk_id) else {
+            continue;
+        };
+
+        storage.set_block(block_coords, value);
+        bump_chunk_version::<T>(world, chunk_id);
+
+        if let Some(mut queue) = world.get_resource_mut::<BlockUpdateQueue>() {
+            queue.push(world_id, block_coords);
+        }
+    }
+}
+
+/// Undoes the most recent edit within the [`VoxelEditHistory`] for the given
+/// block data type.
+///
+/// Returns false if there was nothing to undo.
+pub fn undo_edit<T>(world: &mut World) -> bool
+where
+    T: BlockData,
+{
+    let Some(edit) = world
+        .get_resource_mut::<VoxelEditHistory<T>>()
+        .and_then(|mut history| history.undo.pop_back())
+    else {
+        return false;
+    };
+
+    edit.revert(world);
+    world.resource_mut::<VoxelEditHistory<T>>().redo.push(edit);
+    true
+}
+
+/// Redoes the most recently undone edit within the [`VoxelEditHistory`] for
+/// the given block data type.
+///
+/// Returns false if there was nothing to redo.
+pub fn redo_edit<T>(world: &mut World) -> bool
+where
+    T: BlockData,
+{
+    let Some(edit) = world
+        .get_resource_mut::<VoxelEditHistory<T>>()
+        .and_then(|mut history| history.redo.pop())
+    else {
+        return false;
+    };
+
+    edit.apply(world);
+    world
+        .resource_mut::<VoxelEditHistory<T>>()
+        .undo
+        .push_back(edit);
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn brush_undo_redo() {
+        let mut app = App::new();
+        app.init_resource::<VoxelEditHistory<u8>>();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            for x in 0 .. 16 {
+                for z in 0 .. 16 {
+                    storage.set_block(IVec3::new(x, 0, z), 1);
+                }
+            }
+
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, storage).unwrap();
+            world.apply_brush(
+                IVec3::new(8, 0, 8),
+                VoxelBrush::sphere(2.0),
+                BrushOp::<u8>::Erase,
+            );
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let chunk_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelChunk>>()
+            .single(&app.world);
+        let count_solid = |app: &App| {
+            let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+            Region::CHUNK
+                .iter()
+                .filter(|p| storage.get_block(*p) == 1)
+                .count()
+        };
+
+        // A sphere with a radius of 2 erases a disc of 13 blocks in the floor.
+        assert_eq!(count_solid(&app), 256 - 13);
+        assert_eq!(app.world.resource::<VoxelEditHistory<u8>>().undo_len(), 1);
+
+        assert!(undo_edit::<u8>(&mut app.world));
+        assert_eq!(count_solid(&app), 256);
+        assert!(!undo_edit::<u8>(&mut app.world));
+
+        assert!(redo_edit::<u8>(&mut app.world));
+        assert_eq!(count_solid(&app), 256 - 13);
+    }
+}
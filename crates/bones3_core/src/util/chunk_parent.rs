@@ -0,0 +1,318 @@
+//! Parents arbitrary entities, such as props or dropped items, to the chunk
+//! that contains them, so they are unloaded and saved along with that chunk.
+
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy::transform::commands::AddChildInPlace;
+use bevy::transform::TransformSystem;
+
+use crate::prelude::{BlockScale, VoxelChunk, VoxelWorld};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+/// This plugin keeps every entity with a [`ChunkChild`] component parented to
+/// the chunk that contains it, and moves it to a new chunk whenever it crosses
+/// a chunk border.
+///
+/// Entities are re-parented in place, so their global transform is preserved.
+/// Entities that move into a chunk that is not currently loaded stay parented
+/// to their previous chunk until the new chunk is spawned.
+///
+/// The position of each entity is read from its global transform, relative to
+/// the global transform of its voxel world. Voxel worlds without a transform
+/// are treated as if they were placed at the origin. Entities are only parented
+/// to chunks that have a global transform.
+#[derive(Default)]
+pub struct ChunkParentPlugin;
+
+impl Plugin for ChunkParentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            migrate_chunk_children.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A component for entities that should always be parented to the chunk that
+/// contains them, within the given voxel world.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkChild {
+    /// The id of the world that the entity is in.
+    pub world_id: Entity,
+}
+
+/// A command that parents an entity to the chunk that contains it, while
+/// preserving its global transform.
+///
+/// Unlike [`ChunkChild`], the entity is only parented once, and is not moved
+/// to another chunk when it crosses a chunk border. The containing chunk is
+/// found the same way as for [`ChunkChild`], so voxel worlds without a
+/// transform are treated as if they were placed at the origin. Nothing happens
+/// if the containing chunk is not loaded, or if it does not have a global
+/// transform yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParentToChunk {
+    /// The entity to parent.
+    pub entity: Entity,
+
+    /// The id of the world that the entity is in.
+    pub world_id: Entity,
+}
+
+impl Command for ParentToChunk {
+    fn apply(self, world: &mut World) {
+        let Some(transform) = world.get::<GlobalTransform>(self.entity) else {
+            return;
+        };
+
+        let Some(world_ref) = world.get_entity(self.world_id) else {
+            return;
+        };
+
+        let Some(pointers) = world_ref.get::<ChunkEntityPointers>() else {
+            return;
+        };
+
+        let chunk_coords = containing_chunk_coords(
+            world_ref.get::<GlobalTransform>(),
+            world_ref.get::<BlockScale>().copied().unwrap_or_default(),
+            transform,
+        );
+
+        let Some(chunk_id) = pointers.get_chunk_entity(chunk_coords) else {
+            return;
+        };
+
+        if world.get::<GlobalTransform>(chunk_id).is_none() {
+            return;
+        }
+
+        AddChildInPlace {
+            parent: chunk_id,
+            child:  self.entity,
+        }
+        .apply(world);
+    }
+}
+
+/// Gets the coordinates of the chunk that contains the given global transform,
+/// within a world with the given global transform and block scale.
+pub fn containing_chunk_coords(
+    world_transform: Option<&GlobalTransform>,
+    block_scale: BlockScale,
+    transform: &GlobalTransform,
+) -> IVec3 {
+    let local_pos = match world_transform {
+        Some(world_transform) => transform.reparented_to(world_transform).translation,
+        None => transform.translation(),
+    };
+
+    block_scale.to_block_space(local_pos).floor().as_ivec3() >> 4
+}
+
+/// This system parents all chunk children to the chunk that contains them, if
+/// they are not already parented to it.
+fn migrate_chunk_children(
+    worlds: Query<
+        (
+            Option<&GlobalTransform>,
+            Option<&BlockScale>,
+            &ChunkEntityPointers,
+        ),
+        With<VoxelWorld>,
+    >,
+    chunks: Query<(), (With<VoxelChunk>, With<GlobalTransform>)>,
+    children: Query<(Entity, &ChunkChild, &GlobalTransform, Option<&Parent>)>,
+    mut commands: Commands,
+) {
+    for (entity, chunk_child, transform, parent) in children.iter() {
+        let Ok((world_transform, block_scale, pointers)) = worlds.get(chunk_child.world_id) else {
+            continue;
+        };
+
+        let chunk_coords = containing_chunk_coords(
+            world_transform,
+            block_scale.copied().unwrap_or_default(),
+            transform,
+        );
+
+        let Some(chunk_id) = pointers.get_chunk_entity(chunk_coords) else {
+            continue;
+        };
+
+        if parent.is_some_and(|parent| parent.get() == chunk_id) || !chunks.contains(chunk_id) {
+            continue;
+        }
+
+        commands.entity(entity).set_parent_in_place(chunk_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn migrate_across_chunk_borders() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            ChunkParentPlugin,
+        ));
+
+        Schedule::new()
+            .add_systems(|mut commands: VoxelCommands| {
+                let mut world = commands.spawn_world((TransformBundle::default(), BlockScale(0.5)));
+                world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+                world.spawn_chunk(IVec3::X, ()).unwrap();
+            })
+            .run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+
+        let prop = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(2.0, 1.0, 1.0)),
+                ChunkChild {
+                    world_id,
+                },
+            ))
+            .id();
+
+        // The chunks do not have a global transform until the next frame.
+        app.update();
+        app.update();
+
+        let chunk_id = |app: &App, coords| {
+            app.world
+                .get::<ChunkEntityPointers>(world_id)
+                .unwrap()
+                .get_chunk_entity(coords)
+                .unwrap()
+        };
+
+        assert_eq!(
+            app.world.get::<Parent>(prop).unwrap().get(),
+            chunk_id(&app, IVec3::ZERO)
+        );
+        assert_eq!(
+            app.world.get::<Transform>(prop).unwrap().translation,
+            Vec3::new(4.0, 2.0, 2.0)
+        );
+
+        // Move the prop across the border into the next chunk.
+        app.world.get_mut::<Transform>(prop).unwrap().translation = Vec3::new(20.0, 2.0, 2.0);
+        app.update();
+
+        assert_eq!(
+            app.world.get::<Parent>(prop).unwrap().get(),
+            chunk_id(&app, IVec3::X)
+        );
+        assert_eq!(
+            app.world.get::<Transform>(prop).unwrap().translation,
+            Vec3::new(4.0, 2.0, 2.0)
+        );
+
+        app.update();
+        assert_eq!(
+            app.world
+                .get::<GlobalTransform>(prop)
+                .unwrap()
+                .translation(),
+            Vec3::new(10.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn parent_to_containing_chunk() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+        ));
+
+        let scaled = app
+            .world
+            .spawn(VoxelWorldBundle::new().with_block_scale(BlockScale(0.5)))
+            .id();
+        let logical = app
+            .world
+            .spawn((VoxelWorld, ChunkEntityPointers::default()))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(scaled).unwrap();
+                world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+                world.spawn_chunk(IVec3::X, ()).unwrap();
+
+                let mut world = commands.get_world(logical).unwrap();
+                world
+                    .spawn_chunk(IVec3::X, TransformBundle::default())
+                    .unwrap();
+            })
+            .run(&mut app.world);
+
+        let spawn_prop = |app: &mut App, x: f32| {
+            app.world
+                .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                    x, 1.0, 1.0,
+                )))
+                .id()
+        };
+        let scaled_prop = spawn_prop(&mut app, 10.0);
+        let logical_prop = spawn_prop(&mut app, 20.0);
+        let unloaded_prop = spawn_prop(&mut app, 40.0);
+
+        // The chunks do not have a global transform until the next frame.
+        app.update();
+        app.update();
+
+        for (entity, world_id) in
+            [(scaled_prop, scaled), (logical_prop, logical), (unloaded_prop, logical)]
+        {
+            ParentToChunk {
+                entity,
+                world_id,
+            }
+            .apply(&mut app.world);
+        }
+        app.update();
+
+        let chunk_id = |app: &App, world_id| {
+            app.world
+                .get::<ChunkEntityPointers>(world_id)
+                .unwrap()
+                .get_chunk_entity(IVec3::X)
+                .unwrap()
+        };
+
+        assert_eq!(
+            app.world.get::<Parent>(scaled_prop).unwrap().get(),
+            chunk_id(&app, scaled)
+        );
+        assert_eq!(
+            app.world.get::<Parent>(logical_prop).unwrap().get(),
+            chunk_id(&app, logical)
+        );
+        assert!(app.world.get::<Parent>(unloaded_prop).is_none());
+
+        assert_eq!(
+            app.world
+                .get::<GlobalTransform>(scaled_prop)
+                .unwrap()
+                .translation(),
+            Vec3::new(10.0, 1.0, 1.0)
+        );
+    }
+}
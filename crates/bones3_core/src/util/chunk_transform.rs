@@ -0,0 +1,142 @@
+//! Keeps the transforms of chunk entities in sync with their chunk
+//! coordinates.
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::prelude::{BlockScale, Bones3CoreSet, VoxelChunk, VoxelWorld};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+/// This plugin guarantees that every chunk within a voxel world that has a
+/// transform is given a [`Transform`] that places it at `chunk_coords * 16`
+/// relative to its world, regardless of how the chunk was spawned. If the world
+/// has a [`BlockScale`], chunk transforms are scaled to match it, and are
+/// updated whenever it changes.
+///
+/// Chunks within logical worlds, which have no `GlobalTransform`, are left
+/// untouched.
+///
+/// It is automatically added by the core plugin, and only needs to be added
+/// once regardless of how many block data types are in use.
+#[derive(Default)]
+pub struct ChunkTransformPlugin;
+
+impl Plugin for ChunkTransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            setup_chunk_transforms
+                .after(Bones3CoreSet::FlushBlockWrites)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Gets the local transform of the chunk at the given chunk coordinates,
+/// relative to its world, for a world with the given block scale.
+pub fn chunk_transform(chunk_coords: IVec3, block_scale: BlockScale) -> Transform {
+    Transform::from_translation(block_scale.to_local_space(chunk_coords.as_vec3() * 16.0))
+        .with_scale(Vec3::splat(block_scale.0))
+}
+
+/// This system sets the transform of all newly spawned chunks, and all chunks
+/// within worlds that have had their block scale changed, within worlds that
+/// have a transform. A transform bundle is inserted for chunks that do not
+/// have one yet.
+fn setup_chunk_transforms(
+    worlds: Query<Option<&BlockScale>, (With<VoxelWorld>, With<GlobalTransform>)>,
+    rescaled_worlds: Query<
+        &ChunkEntityPointers,
+        (With<VoxelWorld>, With<GlobalTransform>, Changed<BlockScale>),
+    >,
+    added_chunks: Query<Entity, Added<VoxelChunk>>,
+    mut chunks: Query<(&VoxelChunk, Option<&mut Transform>)>,
+    mut commands: Commands,
+) {
+    let rescaled_chunks = rescaled_worlds
+        .iter()
+        .flat_map(|pointers| pointers.iter().map(|(_, chunk_id)| chunk_id));
+
+    for chunk_id in added_chunks.iter().chain(rescaled_chunks) {
+        let Ok((chunk_meta, transform)) = chunks.get_mut(chunk_id) else {
+            continue;
+        };
+
+        let Ok(block_scale) = worlds.get(chunk_meta.world_id()) else {
+            continue;
+        };
+
+        let block_scale = block_scale.copied().unwrap_or_default();
+        let expected = chunk_transform(chunk_meta.chunk_coords(), block_scale);
+        match transform {
+            Some(mut transform) => *transform = expected,
+            None => {
+                commands
+                    .entity(chunk_id)
+                    .insert(TransformBundle::from_transform(expected));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn place_new_chunks() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world((TransformBundle::default(), BlockScale(0.5)));
+            world.spawn_chunk(IVec3::new(1, 2, 3), ()).unwrap();
+            world
+                .spawn_chunk(IVec3::new(-1, 0, 0), TransformBundle::default())
+                .unwrap();
+
+            let mut logical = commands.spawn_world(());
+            logical.spawn_chunk(IVec3::ONE, ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        app.update();
+
+        let mut placed: Vec<_> = app
+            .world
+            .query::<(&VoxelChunk, &Transform)>()
+            .iter(&app.world)
+            .map(|(chunk, transform)| {
+                assert_eq!(
+                    transform,
+                    &chunk_transform(chunk.chunk_coords(), BlockScale(0.5))
+                );
+                chunk.chunk_coords().to_array()
+            })
+            .collect();
+        placed.sort();
+        assert_eq!(placed, vec![[-1, 0, 0], [1, 2, 3]]);
+
+        let mut chunk_transforms = app.world.query::<(&VoxelChunk, &Transform)>();
+        let (_, transform) = chunk_transforms
+            .iter(&app.world)
+            .find(|(chunk, _)| chunk.chunk_coords() == IVec3::new(1, 2, 3))
+            .unwrap();
+        assert_eq!(transform.translation, Vec3::new(8.0, 16.0, 24.0));
+
+        let mut worlds = app
+            .world
+            .query_filtered::<&mut BlockScale, With<GlobalTransform>>();
+        *worlds.single_mut(&mut app.world) = BlockScale(2.0);
+        app.update();
+
+        let (_, transform) = chunk_transforms
+            .iter(&app.world)
+            .find(|(chunk, _)| chunk.chunk_coords() == IVec3::new(1, 2, 3))
+            .unwrap();
+        assert_eq!(transform.translation, Vec3::new(32.0, 64.0, 96.0));
+    }
+}
@@ -0,0 +1,188 @@
+//! A floating origin that keeps the primary camera or player close to the
+//! origin of the Bevy world, in order to avoid floating point precision issues
+//! within very large voxel worlds.
+
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+/// This plugin periodically moves all top level entities so that the entity
+/// with the [`FloatingOrigin`] component is placed back at the origin.
+///
+/// Single precision floats start to noticeably lose precision at around
+/// 100,000 units away from the origin, which causes rendering and physics to
+/// jitter. Since voxel worlds are moved along with everything else, all chunk
+/// anchors, chunk meshes, and colliders keep the same positions relative to
+/// their world, and block coordinates never change.
+///
+/// Only entities without a parent are moved, as child entities follow their
+/// parents. Entities with an [`IgnoreFloatingOrigin`] component are never
+/// moved. Chunk anchors that follow a transform must belong to a voxel world
+/// that also has a transform, as chunk anchors within logical worlds use their
+/// absolute translation.
+#[derive(Default)]
+pub struct FloatingOriginPlugin;
+
+impl Plugin for FloatingOriginPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<FloatingOrigin>()
+            .register_type::<IgnoreFloatingOrigin>()
+            .register_type::<FloatingOriginSettings>()
+            .register_type::<FloatingOriginOffset>()
+            .init_resource::<FloatingOriginSettings>()
+            .init_resource::<FloatingOriginOffset>()
+            .add_event::<OriginShifted>()
+            .add_systems(
+                PostUpdate,
+                recenter_floating_origin.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// A marker component for the entity that the world is re-centered around,
+/// such as the primary camera or player.
+///
+/// Only a single entity should have this component at a time, and that entity
+/// should not have a parent.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct FloatingOrigin;
+
+/// A marker component for top level entities that should never be moved when
+/// the world is re-centered, such as UI or screen space elements.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct IgnoreFloatingOrigin;
+
+/// The settings for the [`FloatingOriginPlugin`].
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct FloatingOriginSettings {
+    /// The distance along any axis that the floating origin entity may move
+    /// away from the origin before the world is re-centered around it.
+    ///
+    /// Defaults to `4096.0`.
+    pub threshold: f32,
+}
+
+impl Default for FloatingOriginSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 4096.0,
+        }
+    }
+}
+
+/// A resource that stores the total offset that has been removed from all
+/// entities by the floating origin, in double precision.
+///
+/// The true position of a top level entity is its translation plus this
+/// offset.
+#[derive(Debug, Default, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct FloatingOriginOffset(pub DVec3);
+
+impl FloatingOriginOffset {
+    /// Converts a translation within the Bevy world into its true position.
+    pub fn to_true_position(&self, translation: Vec3) -> DVec3 {
+        self.0 + translation.as_dvec3()
+    }
+
+    /// Converts a true position into a translation within the Bevy world.
+    pub fn to_translation(&self, position: DVec3) -> Vec3 {
+        (position - self.0).as_vec3()
+    }
+}
+
+/// An event that is sent whenever the world is re-centered around the
+/// floating origin.
+#[derive(Debug, Event, Clone, Copy, PartialEq)]
+pub struct OriginShifted {
+    /// The offset that was subtracted from the translation of all top level
+    /// entities.
+    pub shift: Vec3,
+}
+
+/// This system re-centers the world around the floating origin entity once it
+/// has moved further than the threshold away from the origin.
+///
+/// The shift is rounded to whole units, so that entities which are aligned to
+/// the block grid stay aligned.
+fn recenter_floating_origin(
+    settings: Res<FloatingOriginSettings>,
+    mut offset: ResMut<FloatingOriginOffset>,
+    mut roots: Query<
+        (&mut Transform, Option<&FloatingOrigin>),
+        (Without<Parent>, Without<IgnoreFloatingOrigin>),
+    >,
+    mut events: EventWriter<OriginShifted>,
+) {
+    let Some(translation) = roots
+        .iter()
+        .find_map(|(transform, origin)| origin.map(|_| transform.translation))
+    else {
+        return;
+    };
+
+    if translation.abs().max_element() <= settings.threshold {
+        return;
+    }
+
+    let shift = translation.round();
+    for (mut transform, _) in roots.iter_mut() {
+        transform.translation -= shift;
+    }
+
+    offset.0 += shift.as_dvec3();
+    events.send(OriginShifted {
+        shift,
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+    #[test]
+    fn recenter_around_anchor() {
+        let mut app = App::new();
+        app.add_plugins((Bones3CorePlugin::<u8>::default(), FloatingOriginPlugin));
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorld,
+                ChunkEntityPointers::default(),
+                TransformBundle::default(),
+            ))
+            .id();
+        let camera_id = app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(5000.4, 10.0, -20.0)),
+                FloatingOrigin,
+            ))
+            .id();
+        let ui_id = app
+            .world
+            .spawn((TransformBundle::default(), IgnoreFloatingOrigin))
+            .id();
+
+        app.update();
+
+        let translation = |app: &App, id| app.world.get::<Transform>(id).unwrap().translation;
+        assert_eq!(translation(&app, world_id), Vec3::new(-5000.0, -10.0, 20.0));
+        assert_eq!(translation(&app, ui_id), Vec3::ZERO);
+        assert!(translation(&app, camera_id).length() < 1.0);
+
+        let offset = app.world.resource::<FloatingOriginOffset>();
+        assert_eq!(offset.0, DVec3::new(5000.0, 10.0, -20.0));
+
+        app.update();
+        assert_eq!(translation(&app, world_id), Vec3::new(-5000.0, -10.0, 20.0));
+    }
+}
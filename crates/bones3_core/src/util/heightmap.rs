@@ -0,0 +1,332 @@
+//! A per-world heightmap that tracks the highest non-empty block of each block
+//! column, and is updated incrementally as chunks are loaded and modified.
+
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::prelude::{
+    BlockData,
+    Bones3CoreSet,
+    ChunkDespawned,
+    UpAxis,
+    VoxelChunk,
+    VoxelStorage,
+    VoxelWorld,
+};
+
+/// This plugin attaches a [`WorldHeightmap`] to every voxel world, and keeps
+/// it up to date as chunks with the given block data type are spawned,
+/// modified, and despawned.
+#[derive(Default)]
+pub struct HeightmapPlugin<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for HeightmapPlugin<T>
+where
+    T: BlockData + PartialEq,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<ChunkDespawned>().add_systems(
+            PostUpdate,
+            (
+                insert_world_heightmaps::<T>,
+                apply_deferred,
+                remove_despawned_chunks::<T>,
+                update_heightmaps::<T>,
+            )
+                .chain()
+                .in_set(Bones3CoreSet::BlockUpdates),
+        );
+    }
+}
+
+/// The highest non-empty block height within each block column of a single
+/// chunk, or `None` for columns that are completely empty.
+type ColumnHeights = Box<[Option<i32>; 256]>;
+
+/// The heightmap data for a single chunk column.
+#[derive(Debug, Default)]
+struct ChunkColumn {
+    /// The heights within each chunk of this column, by chunk height.
+    chunks: BTreeMap<i32, ColumnHeights>,
+
+    /// The combined heights of all chunks within this column.
+    top: Option<ColumnHeights>,
+}
+
+impl ChunkColumn {
+    /// Recalculates the combined heights of this column.
+    fn rebuild(&mut self) {
+        let mut top = Box::new([None; 256]);
+        for (index, height) in top.iter_mut().enumerate() {
+            *height = self.chunks.values().rev().find_map(|chunk| chunk[index]);
+        }
+        self.top = Some(top);
+    }
+}
+
+/// A component that is attached to voxel worlds to store the height of the
+/// highest non-default block within each block column of the world.
+///
+/// Columns run along the [`UpAxis`] of the world. Changing the up axis of a
+/// world does not update the existing heightmap data, so it should be set
+/// before any chunks are spawned.
+#[derive(Debug, Component)]
+pub struct WorldHeightmap<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// The up axis that was used to build this heightmap.
+    up: UpAxis,
+
+    /// The heightmap data, by chunk column coordinates.
+    columns: HashMap<IVec2, ChunkColumn>,
+}
+
+impl<T> Default for WorldHeightmap<T>
+where
+    T: BlockData + PartialEq,
+{
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+            up:       UpAxis::default(),
+            columns:  HashMap::new(),
+        }
+    }
+}
+
+impl<T> WorldHeightmap<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Gets the height of the highest non-default block within the given block
+    /// column, out of all loaded chunks.
+    ///
+    /// Returns `None` if there are no loaded, non-default blocks within the
+    /// column.
+    pub fn get_height(&self, block_column: IVec2) -> Option<i32> {
+        self.columns.get(&(block_column >> 4))?.top.as_ref()?[column_index(block_column)]
+    }
+
+    /// Gets the block coordinates of the highest non-default block within the
+    /// given block column, out of all loaded chunks.
+    pub fn get_surface(&self, block_column: IVec2) -> Option<IVec3> {
+        let height = self.get_height(block_column)?;
+        Some(self.up.compose(block_column, height))
+    }
+
+    /// Checks whether the block at the given block coordinates has an open
+    /// view of the sky, meaning that there are no non-default blocks above it
+    /// along the up axis of the world.
+    ///
+    /// Only loaded chunks are considered, so blocks beneath unloaded chunks
+    /// may be reported as visible.
+    pub fn is_sky_visible(&self, block_coords: IVec3) -> bool {
+        match self.get_height(self.up.column(block_coords)) {
+            Some(height) => self.up.height(block_coords) >= height,
+            None => true,
+        }
+    }
+
+    /// Gets the up axis that this heightmap was built with.
+    pub fn up_axis(&self) -> UpAxis {
+        self.up
+    }
+
+    /// Checks whether any chunks within the given chunk column have been
+    /// loaded into this heightmap.
+    pub fn has_chunk_column(&self, chunk_column: IVec2) -> bool {
+        self.columns
+            .get(&chunk_column)
+            .is_some_and(|c| !c.chunks.is_empty())
+    }
+
+    /// Updates the heights for the given chunk.
+    fn update_chunk(&mut self, chunk_coords: IVec3, storage: &VoxelStorage<T>) {
+        let up = self.up;
+        let chunk_height = up.height(chunk_coords);
+        let chunk_column = up.column(chunk_coords);
+        let origin = chunk_column << 4;
+
+        let mut heights = Box::new([None; 256]);
+        for (index, height) in heights.iter_mut().enumerate() {
+            let column = origin + IVec2::new(index as i32 / 16, index as i32 % 16);
+            *height = up.block_heights(chunk_height).rev().find(|h| {
+                let block_coords = up.compose(column, *h);
+                storage.get_block(block_coords) != T::default()
+            });
+        }
+
+        let column = self.columns.entry(chunk_column).or_default();
+        column.chunks.insert(chunk_height, heights);
+        column.rebuild();
+    }
+
+    /// Removes the heights for the given chunk.
+    fn remove_chunk(&mut self, chunk_coords: IVec3) {
+        let chunk_column = self.up.column(chunk_coords);
+        let Some(column) = self.columns.get_mut(&chunk_column) else {
+            return;
+        };
+
+        column.chunks.remove(&self.up.height(chunk_coords));
+        if column.chunks.is_empty() {
+            self.columns.remove(&chunk_column);
+        } else {
+            column.rebuild();
+        }
+    }
+}
+
+/// A system parameter for reading the heightmaps of all voxel worlds.
+#[derive(SystemParam)]
+pub struct WorldHeightmaps<'w, 's, T>
+where
+    T: BlockData + PartialEq,
+{
+    /// The heightmaps of all voxel worlds.
+    heightmaps: Query<'w, 's, &'static WorldHeightmap<T>>,
+}
+
+impl<'w, 's, T> WorldHeightmaps<'w, 's, T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Gets the heightmap of the given world, if it has one.
+    pub fn get(&self, world_id: Entity) -> Option<&WorldHeightmap<T>> {
+        self.heightmaps.get(world_id).ok()
+    }
+
+    /// Checks whether the block at the given block coordinates within the
+    /// given world has an open view of the sky. See
+    /// [`WorldHeightmap::is_sky_visible`] for more information.
+    ///
+    /// Worlds without a heightmap are considered to have no blocks, so this
+    /// always returns true for them.
+    pub fn is_sky_visible(&self, world_id: Entity, block_coords: IVec3) -> bool {
+        match self.get(world_id) {
+            Some(heightmap) => heightmap.is_sky_visible(block_coords),
+            None => true,
+        }
+    }
+}
+
+/// Gets the index of the given block column within the heights of a chunk
+/// column.
+fn column_index(block_column: IVec2) -> usize {
+    let local = block_column & 15;
+    (local.x * 16 + local.y) as usize
+}
+
+/// This system attaches an empty heightmap to all voxel worlds that do not
+/// have one yet.
+fn insert_world_heightmaps<T>(
+    worlds: Query<(Entity, Option<&UpAxis>), (With<VoxelWorld>, Without<WorldHeightmap<T>>)>,
+    mut commands: Commands,
+) where
+    T: BlockData + PartialEq,
+{
+    for (world_id, up) in worlds.iter() {
+        commands.entity(world_id).insert(WorldHeightmap::<T> {
+            up: up.copied().unwrap_or_default(),
+            ..default()
+        });
+    }
+}
+
+/// This system removes the heights of all chunks that have been despawned.
+fn remove_despawned_chunks<T>(
+    mut despawned: EventReader<ChunkDespawned>,
+    mut worlds: Query<&mut WorldHeightmap<T>>,
+) where
+    T: BlockData + PartialEq,
+{
+    for ev in despawned.iter() {
+        if let Ok(mut heightmap) = worlds.get_mut(ev.world_id) {
+            heightmap.remove_chunk(ev.chunk_coords);
+        }
+    }
+}
+
+/// This system updates the heights of all chunks that have been spawned or
+/// modified since the last update.
+fn update_heightmaps<T>(
+    chunks: Query<(&VoxelChunk, &VoxelStorage<T>), Changed<VoxelStorage<T>>>,
+    mut worlds: Query<&mut WorldHeightmap<T>>,
+) where
+    T: BlockData + PartialEq,
+{
+    for (chunk_meta, storage) in chunks.iter() {
+        if let Ok(mut heightmap) = worlds.get_mut(chunk_meta.world_id()) {
+            heightmap.update_chunk(chunk_meta.chunk_coords(), storage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn track_column_heights() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            HeightmapPlugin::<u8>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut lower = VoxelStorage::<u8>::default();
+            lower.set_block(IVec3::new(3, 5, 4), 1);
+
+            let mut upper = VoxelStorage::<u8>::default();
+            upper.set_block(IVec3::new(3, 20, 4), 1);
+
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, lower).unwrap();
+            world.spawn_chunk(IVec3::Y, upper).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+        app.update();
+
+        let heightmap = app.world.query::<&WorldHeightmap<u8>>().single(&app.world);
+        assert_eq!(heightmap.get_height(IVec2::new(3, 4)), Some(20));
+        assert_eq!(heightmap.get_height(IVec2::new(4, 4)), None);
+        assert_eq!(
+            heightmap.get_surface(IVec2::new(3, 4)),
+            Some(IVec3::new(3, 20, 4))
+        );
+        assert!(heightmap.is_sky_visible(IVec3::new(3, 21, 4)));
+        assert!(!heightmap.is_sky_visible(IVec3::new(3, 6, 4)));
+        assert!(heightmap.is_sky_visible(IVec3::new(4, -30, 4)));
+
+        fn despawn(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            let mut world = commands.get_world(world_id).unwrap();
+            world.get_chunk(IVec3::Y).unwrap().despawn();
+            world.set_block(IVec3::new(4, 2, 4), 1u8);
+        }
+        Schedule::new().add_systems(despawn).run(&mut app.world);
+        app.update();
+
+        let heightmap = app.world.query::<&WorldHeightmap<u8>>().single(&app.world);
+        assert_eq!(heightmap.get_height(IVec2::new(3, 4)), Some(5));
+        assert_eq!(heightmap.get_height(IVec2::new(4, 4)), Some(2));
+    }
+}
@@ -0,0 +1,261 @@
+//! A standard entry point for interacting with blocks, such as opening doors
+//! or pressing buttons.
+//!
+//! Input handling, such as raycasting from the camera, is left to the game.
+//! Once the targeted block is known, the game sends a [`BlockInteractEvent`],
+//! which is routed to the handler that is registered for the interacted
+//! block within the [`BlockInteractHandlers`] resource. When the `scripting`
+//! feature is enabled, the same event also triggers the interact hook of block
+//! scripts.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::prelude::{BlockData, BlockFace, VoxelCommands, VoxelQuery, VoxelStorage};
+use crate::util::block_context::{run_block_callback, BlockCallback, BlockContext};
+
+/// This plugin routes block interaction events to the registered interaction
+/// handlers for the given block data type.
+#[derive(Default)]
+pub struct BlockInteractPlugin<T>
+where
+    T: InteractiveBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for BlockInteractPlugin<T>
+where
+    T: InteractiveBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.register_type::<InteractButton>()
+            .register_type::<BlockFace>()
+            .add_event::<BlockInteractEvent>()
+            .init_resource::<BlockInteractHandlers<T>>()
+            .add_systems(Update, dispatch_block_interactions::<T>);
+    }
+}
+
+/// A block data type that can be interacted with.
+pub trait InteractiveBlock: BlockData {
+    /// Gets the ID of the interaction handler for this block, or `None` if
+    /// this block cannot be interacted with.
+    ///
+    /// Blocks of the same type, such as all doors, should share the same ID,
+    /// regardless of their current state.
+    fn interaction_id(&self) -> Option<u32>;
+}
+
+/// The input that was used to interact with a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum InteractButton {
+    /// The primary action, usually the left mouse button.
+    Primary,
+
+    /// The secondary action, usually the right mouse button.
+    Secondary,
+
+    /// The middle action, usually the middle mouse button.
+    Middle,
+}
+
+/// An event that requests an interaction with the block at the given
+/// coordinates.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockInteractEvent {
+    /// The entity that is interacting with the block, such as a player.
+    pub player: Entity,
+
+    /// The id of the world the block is in.
+    pub world_id: Entity,
+
+    /// The coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The face of the block that was interacted with.
+    pub face: BlockFace,
+
+    /// The input that was used to interact with the block.
+    pub button: InteractButton,
+}
+
+/// A block interaction handler function.
+pub type BlockInteractFn<T> = BlockCallback<T, BlockInteractEvent>;
+
+/// A resource that stores the interaction handlers for the given block data
+/// type, by interaction ID.
+#[derive(Resource)]
+pub struct BlockInteractHandlers<T>
+where
+    T: InteractiveBlock,
+{
+    /// The registered handlers, by interaction ID.
+    handlers: HashMap<u32, BlockInteractFn<T>>,
+}
+
+impl<T> Default for BlockInteractHandlers<T>
+where
+    T: InteractiveBlock,
+{
+    fn default() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<T> BlockInteractHandlers<T>
+where
+    T: InteractiveBlock,
+{
+    /// Registers a handler for the given interaction ID, replacing any handler
+    /// that was previously registered for it.
+    pub fn register<F>(&mut self, interaction_id: u32, handler: F)
+    where
+        F: Fn(&mut BlockInteractContext<T>) + Send + Sync + 'static,
+    {
+        self.handlers.insert(interaction_id, Arc::new(handler));
+    }
+
+    /// Removes the handler for the given interaction ID.
+    pub fn unregister(&mut self, interaction_id: u32) {
+        self.handlers.remove(&interaction_id);
+    }
+
+    /// Gets the handler for the given interaction ID, if one exists.
+    pub fn get(&self, interaction_id: u32) -> Option<BlockInteractFn<T>> {
+        self.handlers.get(&interaction_id).cloned()
+    }
+}
+
+/// The world access that is provided to a block interaction handler.
+pub type BlockInteractContext<'a, T> = BlockContext<'a, T, BlockInteractEvent>;
+
+impl<'a, T> BlockContext<'a, T, BlockInteractEvent>
+where
+    T: BlockData,
+{
+    /// Gets the event that triggered this interaction.
+    pub fn event(&self) -> &BlockInteractEvent {
+        self.trigger()
+    }
+}
+
+/// This system routes all block interaction events to the handler that is
+/// registered for the interacted block.
+///
+/// Events for blocks within unloaded chunks, or for blocks without a
+/// registered handler, are ignored.
+fn dispatch_block_interactions<T>(
+    mut events: EventReader<BlockInteractEvent>,
+    handlers: Res<BlockInteractHandlers<T>>,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut commands: VoxelCommands,
+) where
+    T: InteractiveBlock,
+{
+    for ev in events.iter() {
+        run_block_callback(
+            &chunks,
+            &mut commands,
+            ev.world_id,
+            ev.block_coords,
+            *ev,
+            |block: T| block.interaction_id().and_then(|id| handlers.get(id)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    enum Block {
+        #[default]
+        Air,
+        DoorClosed,
+        DoorOpen,
+    }
+
+    impl InteractiveBlock for Block {
+        fn interaction_id(&self) -> Option<u32> {
+            match self {
+                Block::Air => None,
+                Block::DoorClosed | Block::DoorOpen => Some(1),
+            }
+        }
+    }
+
+    #[test]
+    fn toggle_door() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Block>::default(),
+            BlockInteractPlugin::<Block>::default(),
+        ));
+
+        app.world
+            .resource_mut::<BlockInteractHandlers<Block>>()
+            .register(1, |ctx| {
+                if ctx.event().button != InteractButton::Secondary {
+                    return;
+                }
+
+                match ctx.block() {
+                    Block::DoorClosed => ctx.replace_block(Block::DoorOpen),
+                    _ => ctx.replace_block(Block::DoorClosed),
+                }
+            });
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::default();
+            storage.set_block(IVec3::new(1, 1, 1), Block::DoorClosed);
+
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        let player = app.world.spawn_empty().id();
+
+        let interact = |app: &mut App, button| {
+            app.world.send_event(BlockInteractEvent {
+                player,
+                world_id,
+                block_coords: IVec3::new(1, 1, 1),
+                face: BlockFace::PosX,
+                button,
+            });
+            app.update();
+            app.world
+                .query::<&VoxelStorage<Block>>()
+                .single(&app.world)
+                .get_block(IVec3::new(1, 1, 1))
+        };
+
+        assert_eq!(
+            interact(&mut app, InteractButton::Secondary),
+            Block::DoorOpen
+        );
+        assert_eq!(interact(&mut app, InteractButton::Primary), Block::DoorOpen);
+        assert_eq!(
+            interact(&mut app, InteractButton::Secondary),
+            Block::DoorClosed
+        );
+    }
+}
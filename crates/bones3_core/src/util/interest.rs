@@ -0,0 +1,244 @@
+//! Interest management for chunk anchors, tracking which chunks enter and
+//! leave the range of each anchor from frame to frame.
+//!
+//! This is primarily intended for driving per-client network replication,
+//! where each client is represented by a chunk anchor.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use super::anchor::ChunkAnchor;
+use crate::math::Region;
+use crate::prelude::{VoxelWorld, WorldTopology};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+/// This component can be attached to an entity with a chunk anchor in order to
+/// track the chunks that enter and leave the range of that anchor each frame.
+///
+/// For each change, a [`ChunkInterestEvent`] is also sent.
+#[derive(Debug, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct ChunkInterest<T>
+where
+    T: Send + Sync,
+{
+    /// Default placeholder for T.
+    #[reflect(ignore)]
+    _phantom: PhantomData<T>,
+
+    /// The region of interest as of the last update, and the world topology
+    /// it was calculated for.
+    #[reflect(ignore)]
+    last: Option<(Entity, Region, WorldTopology)>,
+
+    /// The chunks that entered the area of interest this frame.
+    entered: Vec<IVec3>,
+
+    /// The chunks that left the area of interest this frame.
+    left: Vec<IVec3>,
+}
+
+impl<T> Default for ChunkInterest<T>
+where
+    T: Send + Sync,
+{
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+            last:     None,
+            entered:  vec![],
+            left:     vec![],
+        }
+    }
+}
+
+impl<T> ChunkInterest<T>
+where
+    T: Send + Sync,
+{
+    /// Gets an iterator over the coordinates of all chunks that entered the
+    /// area of interest of the anchor this frame.
+    pub fn entered(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.entered.iter().copied()
+    }
+
+    /// Gets an iterator over the coordinates of all chunks that left the area
+    /// of interest of the anchor this frame.
+    pub fn left(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.left.iter().copied()
+    }
+
+    /// Gets the current region of chunk coordinates that is within the area of
+    /// interest of the anchor, or `None` if the anchor has no coordinates.
+    ///
+    /// For wrapped worlds, chunk coordinates within this region must be
+    /// wrapped to find their canonical coordinates.
+    pub fn region(&self) -> Option<Region> {
+        self.last.map(|(_, region, _)| region)
+    }
+}
+
+/// Describes whether a chunk entered or left the area of interest of an
+/// anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum InterestChange {
+    /// The chunk entered the area of interest.
+    Entered,
+
+    /// The chunk left the area of interest.
+    Left,
+}
+
+/// An event that is sent for each chunk that enters or leaves the area of
+/// interest of an anchor with a [`ChunkInterest`] component.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkInterestEvent {
+    /// The entity id of the chunk anchor.
+    pub anchor_id: Entity,
+
+    /// The id of the world the chunk is in.
+    pub world_id: Entity,
+
+    /// The canonical coordinates of the chunk.
+    pub chunk_coords: IVec3,
+
+    /// Whether the chunk entered or left the area of interest.
+    pub change: InterestChange,
+}
+
+/// Gets the set of canonical chunk coordinates within the given region.
+fn canonical_chunks(region: Region, topology: WorldTopology) -> HashSet<IVec3> {
+    region
+        .iter()
+        .map(|c| topology.wrap_chunk_coords(c))
+        .collect()
+}
+
+/// This system updates the area of interest of all chunk anchors with a chunk
+/// interest component, and sends events for all chunks that entered or left
+/// that area.
+pub(crate) fn update_chunk_interest<T>(
+    worlds: Query<&ChunkEntityPointers, With<VoxelWorld>>,
+    mut anchors: Query<(Entity, &ChunkAnchor<T>, &mut ChunkInterest<T>)>,
+    mut events: EventWriter<ChunkInterestEvent>,
+) where
+    T: Send + Sync + 'static,
+{
+    for (anchor_id, anchor, mut interest) in anchors.iter_mut() {
+        let world_id = anchor.world_id;
+        let topology = worlds
+            .get(world_id)
+            .map(|p| p.topology())
+            .unwrap_or_default();
+        let current = anchor.get_region().map(|r| (world_id, r, topology));
+
+        if current == interest.last {
+            if !interest.entered.is_empty() || !interest.left.is_empty() {
+                interest.entered.clear();
+                interest.left.clear();
+            }
+            continue;
+        }
+
+        let old = match interest.last {
+            Some((_, region, topology)) => canonical_chunks(region, topology),
+            None => HashSet::new(),
+        };
+        let new = match current {
+            Some((_, region, topology)) => canonical_chunks(region, topology),
+            None => HashSet::new(),
+        };
+
+        // Chunks that change worlds always leave the old area of interest.
+        let same_world = interest.last.map(|(w, ..)| w) == Some(world_id);
+        let old_world = interest.last.map_or(world_id, |(w, ..)| w);
+
+        let interest = interest.as_mut();
+        interest.left = old
+            .iter()
+            .filter(|c| !same_world || !new.contains(*c))
+            .copied()
+            .collect();
+        interest.entered = new
+            .iter()
+            .filter(|c| !same_world || !old.contains(*c))
+            .copied()
+            .collect();
+        interest.last = current;
+
+        events.send_batch(interest.left.iter().map(|&chunk_coords| {
+            ChunkInterestEvent {
+                anchor_id,
+                world_id: old_world,
+                chunk_coords,
+                change: InterestChange::Left,
+            }
+        }));
+        events.send_batch(interest.entered.iter().map(|&chunk_coords| {
+            ChunkInterestEvent {
+                anchor_id,
+                world_id,
+                chunk_coords,
+                change: InterestChange::Entered,
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::anchor::ChunkAnchorPlugin;
+
+    #[derive(Debug, Default, Reflect)]
+    struct TestAnchor;
+
+    #[test]
+    fn interest_diffs() {
+        let mut app = App::new();
+        app.add_plugins(ChunkAnchorPlugin::<TestAnchor>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let world_id = commands.spawn_world(GlobalTransform::default()).id();
+            commands.commands().spawn((
+                GlobalTransform::default(),
+                ChunkAnchor::<TestAnchor>::new(world_id, UVec3::ZERO),
+                ChunkInterest::<TestAnchor>::default(),
+            ));
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        app.update();
+        let interest = app
+            .world
+            .query::<&ChunkInterest<TestAnchor>>()
+            .single(&app.world);
+        assert_eq!(interest.entered().collect::<Vec<_>>(), vec![IVec3::ZERO]);
+
+        *app.world
+            .query_filtered::<&mut GlobalTransform, With<ChunkAnchor<TestAnchor>>>()
+            .single_mut(&mut app.world) =
+            GlobalTransform::from_translation(Vec3::new(16.0, 0.0, 0.0));
+
+        app.update();
+        let interest = app
+            .world
+            .query::<&ChunkInterest<TestAnchor>>()
+            .single(&app.world);
+        assert_eq!(interest.entered().collect::<Vec<_>>(), vec![IVec3::X]);
+        assert_eq!(interest.left().collect::<Vec<_>>(), vec![IVec3::ZERO]);
+
+        app.update();
+        let interest = app
+            .world
+            .query::<&ChunkInterest<TestAnchor>>()
+            .single(&app.world);
+        assert_eq!(interest.entered().count(), 0);
+        assert_eq!(interest.left().count(), 0);
+    }
+}
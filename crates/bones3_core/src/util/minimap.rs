@@ -0,0 +1,462 @@
+//! Utilities for downsampling voxel world data into small 2D grids, for use
+//! with minimap and world map rendering.
+
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::utils::{HashMap, HashSet};
+use futures_lite::future;
+
+use crate::prelude::{BlockData, UpAxis, VoxelChunk, VoxelStorage, VoxelWorld, WorldDespawned};
+
+/// This plugin maintains a per-world, per-chunk-column cache of downsampled
+/// minimap data for the given block data type.
+///
+/// Columns are only sampled after being requested through the
+/// [`MinimapCache`] resource, and are resampled automatically whenever one of
+/// the chunks within that column is modified. Columns run along the
+/// [`UpAxis`] of each world.
+#[derive(Default)]
+pub struct MinimapPlugin<T>
+where
+    T: MinimapBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for MinimapPlugin<T>
+where
+    T: MinimapBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapCache<T>>()
+            .add_event::<WorldDespawned>()
+            .add_systems(
+                Update,
+                (
+                    remove_despawned_worlds::<T>,
+                    invalidate_minimap_columns::<T>,
+                    queue_minimap_tasks::<T>,
+                    finish_minimap_tasks::<T>,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// A block data type that can be displayed on a minimap.
+pub trait MinimapBlock: BlockData {
+    /// The type of value that is stored within each minimap cell. This is
+    /// usually a color or a block ID.
+    type Cell: Copy + Eq + Hash + Send + Sync + 'static;
+
+    /// Gets the minimap cell value for this block, or `None` if this block is
+    /// empty and should be skipped when searching for the top-most block of a
+    /// column.
+    fn minimap_cell(&self) -> Option<Self::Cell>;
+}
+
+/// The method that is used to combine multiple block columns into a single
+/// minimap cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum DownsampleMode {
+    /// Each cell uses the highest non-empty block out of all the block columns
+    /// that it covers.
+    #[default]
+    TopMost,
+
+    /// Each cell uses the most common value out of the top-most non-empty
+    /// blocks of all the block columns that it covers.
+    Majority,
+}
+
+/// A downsampled grid of minimap cells for a single chunk column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimapColumn<C> {
+    /// The number of cells along each side of this column.
+    resolution: usize,
+
+    /// The cell values, stored in x-major order.
+    cells: Vec<Option<C>>,
+}
+
+impl<C> MinimapColumn<C>
+where
+    C: Copy,
+{
+    /// Gets the number of cells along each side of this column.
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// Gets the value of the cell at the given local cell coordinates, or
+    /// `None` if the cell is empty or out of bounds.
+    pub fn get(&self, x: usize, z: usize) -> Option<C> {
+        if x >= self.resolution || z >= self.resolution {
+            return None;
+        }
+
+        self.cells[x * self.resolution + z]
+    }
+
+    /// Gets all cell values in this column, stored in x-major order.
+    pub fn cells(&self) -> &[Option<C>] {
+        &self.cells
+    }
+}
+
+/// Downsamples a single chunk column into an NxN grid of minimap cells.
+///
+/// The chunks within the column are provided as a list of chunk heights along
+/// the world's up axis, paired with their block storage, in any order. Missing
+/// chunks are treated as empty. Cell coordinates follow the column axes that
+/// are described by [`UpAxis::column`].
+///
+/// The resolution must be a divisor of 16, such that each cell covers an equal
+/// number of block columns.
+///
+/// # Panics
+///
+/// Panics if the resolution is not a divisor of 16.
+pub fn downsample_column<T>(
+    chunks: &[(i32, &VoxelStorage<T>)],
+    up: UpAxis,
+    resolution: usize,
+    mode: DownsampleMode,
+) -> MinimapColumn<T::Cell>
+where
+    T: MinimapBlock,
+{
+    assert!(
+        resolution > 0 && 16 % resolution == 0,
+        "Resolution must be a divisor of 16"
+    );
+
+    let mut sorted = chunks.to_vec();
+    sorted.sort_by_key(|(y, _)| -*y);
+    let top_most = |x: i32, z: i32| top_most_block(&sorted, up, IVec2::new(x, z));
+
+    let scale = (16 / resolution) as i32;
+    let mut cells = Vec::with_capacity(resolution * resolution);
+    for cell_x in 0 .. resolution as i32 {
+        for cell_z in 0 .. resolution as i32 {
+            let columns = (0 .. scale)
+                .flat_map(|x| (0 .. scale).map(move |z| (x, z)))
+                .filter_map(|(x, z)| top_most(cell_x * scale + x, cell_z * scale + z));
+
+            let cell = match mode {
+                DownsampleMode::TopMost => {
+                    columns
+                        .fold(None, |best: Option<(i32, T::Cell)>, (y, cell)| {
+                            match best {
+                                Some((best_y, _)) if best_y >= y => best,
+                                _ => Some((y, cell)),
+                            }
+                        })
+                        .map(|(_, cell)| cell)
+                },
+                DownsampleMode::Majority => {
+                    let mut votes: Vec<(T::Cell, usize)> = Vec::new();
+                    for (_, cell) in columns {
+                        match votes.iter_mut().find(|(c, _)| *c == cell) {
+                            Some((_, count)) => *count += 1,
+                            None => votes.push((cell, 1)),
+                        }
+                    }
+
+                    votes
+                        .into_iter()
+                        .fold(None, |best: Option<(T::Cell, usize)>, (cell, count)| {
+                            match best {
+                                Some((_, best_count)) if best_count >= count => best,
+                                _ => Some((cell, count)),
+                            }
+                        })
+                        .map(|(cell, _)| cell)
+                },
+            };
+
+            cells.push(cell);
+        }
+    }
+
+    MinimapColumn {
+        resolution,
+        cells,
+    }
+}
+
+/// Finds the top-most non-empty block within the local block column at the
+/// given column coordinates, returning its height along the up axis and its
+/// minimap cell value.
+///
+/// The chunks must be sorted from the highest to the lowest chunk height.
+pub(crate) fn top_most_block<T>(
+    sorted_chunks: &[(i32, &VoxelStorage<T>)],
+    up: UpAxis,
+    column: IVec2,
+) -> Option<(i32, T::Cell)>
+where
+    T: MinimapBlock,
+{
+    sorted_chunks.iter().find_map(|(chunk_height, storage)| {
+        up.block_heights(*chunk_height).rev().find_map(|height| {
+            let block_coords = up.compose(column, height);
+            let cell = storage.get_block(block_coords).minimap_cell()?;
+            Some((height, cell))
+        })
+    })
+}
+
+/// A resource that stores the downsampled minimap data for all requested
+/// chunk columns.
+#[derive(Resource)]
+pub struct MinimapCache<T>
+where
+    T: MinimapBlock,
+{
+    /// The number of cells along each side of a chunk column.
+    resolution: usize,
+
+    /// The method used to combine block columns into cells.
+    mode: DownsampleMode,
+
+    /// The cached minimap columns, by world id and chunk column coordinates.
+    columns: HashMap<(Entity, IVec2), MinimapColumn<T::Cell>>,
+
+    /// The chunk columns that are waiting to be sampled.
+    requested: HashSet<(Entity, IVec2)>,
+
+    /// The chunk columns that are currently being sampled.
+    tasks: HashMap<(Entity, IVec2), Task<MinimapColumn<T::Cell>>>,
+}
+
+impl<T> Default for MinimapCache<T>
+where
+    T: MinimapBlock,
+{
+    fn default() -> Self {
+        Self::new(16, DownsampleMode::default())
+    }
+}
+
+impl<T> MinimapCache<T>
+where
+    T: MinimapBlock,
+{
+    /// Creates a new, empty minimap cache with the given column resolution and
+    /// downsample mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resolution is not a divisor of 16.
+    pub fn new(resolution: usize, mode: DownsampleMode) -> Self {
+        assert!(
+            resolution > 0 && 16 % resolution == 0,
+            "Resolution must be a divisor of 16"
+        );
+
+        Self {
+            resolution,
+            mode,
+            columns: HashMap::new(),
+            requested: HashSet::new(),
+            tasks: HashMap::new(),
+        }
+    }
+
+    /// Gets the number of cells along each side of a chunk column.
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// Gets the method used to combine block columns into cells.
+    pub fn mode(&self) -> DownsampleMode {
+        self.mode
+    }
+
+    /// Gets the cached minimap data for the chunk column at the given x and z
+    /// chunk coordinates within the indicated world, if it has been sampled.
+    ///
+    /// Cached data may be slightly outdated while a column is being resampled.
+    pub fn get(&self, world_id: Entity, column: IVec2) -> Option<&MinimapColumn<T::Cell>> {
+        self.columns.get(&(world_id, column))
+    }
+
+    /// Requests the chunk column at the given x and z chunk coordinates within
+    /// the indicated world to be sampled asynchronously.
+    ///
+    /// Once sampled, the column is kept up to date until it is removed.
+    pub fn request(&mut self, world_id: Entity, column: IVec2) {
+        self.requested.insert((world_id, column));
+    }
+
+    /// Removes the cached minimap data for the given chunk column and stops it
+    /// from being resampled.
+    pub fn remove(&mut self, world_id: Entity, column: IVec2) {
+        self.columns.remove(&(world_id, column));
+        self.requested.remove(&(world_id, column));
+        self.tasks.remove(&(world_id, column));
+    }
+
+    /// Removes all cached and pending minimap data for the given world.
+    pub fn remove_world(&mut self, world_id: Entity) {
+        self.columns.retain(|(w, _), _| *w != world_id);
+        self.requested.retain(|(w, _)| *w != world_id);
+        self.tasks.retain(|(w, _), _| *w != world_id);
+    }
+
+    /// Removes all cached and pending minimap data.
+    pub fn clear(&mut self) {
+        self.columns.clear();
+        self.requested.clear();
+        self.tasks.clear();
+    }
+}
+
+/// This system removes all minimap data for worlds that have been despawned.
+fn remove_despawned_worlds<T>(
+    mut events: EventReader<WorldDespawned>,
+    mut cache: ResMut<MinimapCache<T>>,
+) where
+    T: MinimapBlock,
+{
+    for ev in events.iter() {
+        cache.remove_world(ev.world_id);
+    }
+}
+
+/// This system requests cached chunk columns to be resampled when any chunk
+/// within them is modified.
+fn invalidate_minimap_columns<T>(
+    worlds: Query<Option<&UpAxis>, With<VoxelWorld>>,
+    chunks: Query<&VoxelChunk, Changed<VoxelStorage<T>>>,
+    mut cache: ResMut<MinimapCache<T>>,
+) where
+    T: MinimapBlock,
+{
+    for chunk_meta in chunks.iter() {
+        let up = get_up_axis(&worlds, chunk_meta.world_id());
+        let key = (chunk_meta.world_id(), up.column(chunk_meta.chunk_coords()));
+
+        if cache.columns.contains_key(&key) {
+            cache.requested.insert(key);
+        }
+    }
+}
+
+/// This system collects the block data for all requested chunk columns and
+/// starts an async task to sample each one.
+fn queue_minimap_tasks<T>(
+    worlds: Query<Option<&UpAxis>, With<VoxelWorld>>,
+    chunks: Query<(&VoxelChunk, &VoxelStorage<T>)>,
+    mut cache: ResMut<MinimapCache<T>>,
+) where
+    T: MinimapBlock,
+{
+    if cache.requested.is_empty() {
+        return;
+    }
+
+    let cache = cache.as_mut();
+    let pending: HashSet<(Entity, IVec2)> = cache
+        .requested
+        .iter()
+        .filter(|key| !cache.tasks.contains_key(*key))
+        .copied()
+        .collect();
+
+    let mut column_data: HashMap<(Entity, IVec2), Vec<(i32, VoxelStorage<T>)>> =
+        pending.iter().map(|key| (*key, Vec::new())).collect();
+
+    for (chunk_meta, storage) in chunks.iter() {
+        let up = get_up_axis(&worlds, chunk_meta.world_id());
+        let coords = chunk_meta.chunk_coords();
+        let key = (chunk_meta.world_id(), up.column(coords));
+
+        if let Some(data) = column_data.get_mut(&key) {
+            data.push((up.height(coords), storage.snapshot()));
+        }
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    let (resolution, mode) = (cache.resolution, cache.mode);
+    for (key, data) in column_data {
+        cache.requested.remove(&key);
+        let up = get_up_axis(&worlds, key.0);
+        let task = pool.spawn(async move {
+            let chunks: Vec<_> = data.iter().map(|(h, storage)| (*h, storage)).collect();
+            downsample_column(&chunks, up, resolution, mode)
+        });
+        cache.tasks.insert(key, task);
+    }
+}
+
+/// This system takes all finished minimap sampling tasks and stores their
+/// results in the cache.
+fn finish_minimap_tasks<T>(mut cache: ResMut<MinimapCache<T>>)
+where
+    T: MinimapBlock,
+{
+    let cache = cache.as_mut();
+    cache.tasks.retain(|key, task| {
+        let Some(column) = future::block_on(future::poll_once(task)) else {
+            return true;
+        };
+
+        cache.columns.insert(*key, column);
+        false
+    });
+}
+
+/// Gets the up axis of the given world, defaulting to Y-up if the world does
+/// not define one.
+fn get_up_axis(worlds: &Query<Option<&UpAxis>, With<VoxelWorld>>, world_id: Entity) -> UpAxis {
+    worlds
+        .get(world_id)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    impl MinimapBlock for u8 {
+        type Cell = u8;
+
+        fn minimap_cell(&self) -> Option<Self::Cell> {
+            (*self != 0).then_some(*self)
+        }
+    }
+
+    #[test]
+    fn downsample_modes() {
+        let mut lower = VoxelStorage::<u8>::default();
+        let mut upper = VoxelStorage::<u8>::default();
+        for x in 0 .. 4 {
+            for z in 0 .. 4 {
+                lower.set_block(IVec3::new(x, 15, z), 1);
+            }
+        }
+        upper.set_block(IVec3::new(1, 2, 1), 2);
+
+        let chunks = [(1, &upper), (0, &lower)];
+
+        let top_most = downsample_column(&chunks, UpAxis::PosY, 4, DownsampleMode::TopMost);
+        assert_eq!(top_most.resolution(), 4);
+        assert_eq!(top_most.get(0, 0), Some(2));
+        assert_eq!(top_most.get(0, 1), None);
+
+        let majority = downsample_column(&chunks, UpAxis::PosY, 4, DownsampleMode::Majority);
+        assert_eq!(majority.get(0, 0), Some(1));
+        assert_eq!(majority.get(3, 3), None);
+    }
+}
@@ -1,4 +1,34 @@
 //! This module contains useful utility components and systems that might be
 //! used often while working with Bones Cubed.
 
+pub mod ambient;
 pub mod anchor;
+pub mod anchor_ready;
+pub mod automaton;
+pub mod block_context;
+pub mod block_update;
+pub mod brush;
+pub mod chunk_parent;
+pub mod chunk_transform;
+pub mod floating_origin;
+pub mod heightmap;
+pub mod interact;
+pub mod interest;
+pub mod minimap;
+pub mod multiblock;
+#[cfg(feature = "picking")]
+pub mod picking;
+pub mod pinned;
+pub mod pointer_validation;
+pub mod propagation;
+pub mod replace;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod sector;
+pub mod simulation;
+pub mod spawn_point;
+pub mod stats;
+pub mod timing;
+pub mod transaction;
+pub mod world_map;
+pub mod write_queue;
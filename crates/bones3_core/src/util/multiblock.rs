@@ -0,0 +1,438 @@
+//! Multi-block structures, such as doors, beds, or large machines, which
+//! occupy several voxels at once.
+//!
+//! A multi-block structure is made up of a single master block and a set of
+//! part blocks. The master block describes which parts it needs and where they
+//! are placed relative to it, and each part block stores the offset back to
+//! its master. Placing the master through
+//! [`VoxelWorldCommands::place_multi_block`](crate::query::VoxelWorldCommands::place_multi_block)
+//! writes all of its parts, and breaking any block of the structure with
+//! [`VoxelWorldCommands::break_block`](crate::query::VoxelWorldCommands::break_block)
+//! removes the entire structure when the [`MultiBlockPlugin`] is added.
+//!
+//! When meshing, only the master block should write a block model, covering
+//! the entire structure. Part blocks should write no shape at all, and should
+//! not report any face coverage, so that the structure is rendered exactly
+//! once.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::query::VoxelQueryError;
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{BlockBroken, BlockData, VoxelStorage};
+use crate::util::transaction::run_transaction;
+use crate::Bones3CoreSet;
+
+/// This plugin sends multi-block structure events, and removes entire
+/// structures whenever one of their blocks is broken.
+#[derive(Default)]
+pub struct MultiBlockPlugin<T>
+where
+    T: MultiBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for MultiBlockPlugin<T>
+where
+    T: MultiBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<MultiBlockPlaced<T>>()
+            .add_event::<MultiBlockPlacementFailed<T>>()
+            .add_event::<MultiBlockBroken<T>>()
+            .add_systems(
+                PostUpdate,
+                break_multi_blocks::<T>.in_set(Bones3CoreSet::BlockUpdates),
+            );
+    }
+}
+
+/// A block data type that may form structures occupying multiple voxels.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::util::multiblock::MultiBlock;
+/// # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+/// # enum Block {
+/// #     #[default]
+/// #     Air,
+/// #     Door,
+/// #     DoorTop,
+/// # }
+/// impl MultiBlock for Block {
+///     fn parts(&self) -> Vec<(IVec3, Self)> {
+///         match self {
+///             Block::Door => vec![(IVec3::Y, Block::DoorTop)],
+///             _ => vec![],
+///         }
+///     }
+///
+///     fn master_offset(&self) -> Option<IVec3> {
+///         match self {
+///             Block::DoorTop => Some(IVec3::NEG_Y),
+///             _ => None,
+///         }
+///     }
+/// }
+/// # assert_eq!(Block::Door.parts(), vec![(IVec3::Y, Block::DoorTop)]);
+/// # assert!(Block::Air.is_replaceable());
+/// ```
+pub trait MultiBlock: BlockData + PartialEq {
+    /// Gets the part blocks that make up the structure of this block, when it
+    /// is the master block, along with their offsets from this block.
+    ///
+    /// Returns an empty list for blocks that only occupy a single voxel.
+    fn parts(&self) -> Vec<(IVec3, Self)>;
+
+    /// Gets the offset from this block to the master block of its structure,
+    /// if this block is a part block.
+    fn master_offset(&self) -> Option<IVec3>;
+
+    /// Checks whether this block may be replaced when a multi-block structure
+    /// is placed over it.
+    ///
+    /// By default, only the default block value, such as air, is replaceable.
+    fn is_replaceable(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// An event that is sent when a multi-block structure has been placed.
+#[derive(Debug, Event, Clone, PartialEq)]
+pub struct MultiBlockPlaced<T>
+where
+    T: MultiBlock,
+{
+    /// The id of the world the structure is in.
+    pub world_id: Entity,
+
+    /// The coordinates of the master block.
+    pub master_coords: IVec3,
+
+    /// The master block value.
+    pub block: T,
+}
+
+/// An event that is sent when a multi-block structure could not be placed.
+#[derive(Debug, Event)]
+pub struct MultiBlockPlacementFailed<T>
+where
+    T: MultiBlock,
+{
+    /// The id of the world the structure was placed in.
+    pub world_id: Entity,
+
+    /// The coordinates the master block was placed at.
+    pub master_coords: IVec3,
+
+    /// The master block value.
+    pub block: T,
+
+    /// The reason the structure could not be placed.
+    pub error: MultiBlockError,
+}
+
+/// An event that is sent when a multi-block structure has been removed
+/// because one of its blocks was broken.
+#[derive(Debug, Event, Clone, PartialEq)]
+pub struct MultiBlockBroken<T>
+where
+    T: MultiBlock,
+{
+    /// The id of the world the structure was in.
+    pub world_id: Entity,
+
+    /// The coordinates of the master block.
+    pub master_coords: IVec3,
+
+    /// The master block value that was removed.
+    pub block: T,
+
+    /// The coordinates of the block that was broken to remove the structure.
+    pub broken_coords: IVec3,
+}
+
+/// An error that may occur when placing a multi-block structure.
+#[derive(Debug, Error)]
+pub enum MultiBlockError {
+    /// Thrown when attempting to place a part block as a master block.
+    #[error("Cannot place a multi-block part as a master block")]
+    NotAMaster,
+
+    /// Thrown when a block within the area of the structure cannot be
+    /// replaced.
+    #[error("Block at {0} is obstructed")]
+    Obstructed(IVec3),
+
+    /// Thrown when the world or a chunk within the area of the structure could
+    /// not be accessed.
+    #[error(transparent)]
+    QueryError(#[from] VoxelQueryError),
+}
+
+/// Places the given master block, along with all of its part blocks, at the
+/// given block coordinates within the given world.
+///
+/// The structure is only placed if every block it would occupy is loaded and
+/// replaceable. Otherwise, nothing is written and an error is returned. The
+/// placement is recorded within the
+/// [`VoxelEditHistory`](crate::util::brush::VoxelEditHistory) for `T`, if it
+/// exists, as a single edit.
+pub fn place_multi_block<T>(
+    world: &mut World,
+    world_id: Entity,
+    master_coords: IVec3,
+    block: T,
+) -> Result<(), MultiBlockError>
+where
+    T: MultiBlock,
+{
+    if block.master_offset().is_some() {
+        return Err(MultiBlockError::NotAMaster);
+    }
+
+    let mut blocks = vec![(master_coords, block)];
+    blocks.extend(
+        block
+            .parts()
+            .into_iter()
+            .map(|(offset, part)| (master_coords + offset, part)),
+    );
+
+    for (block_coords, _) in blocks.iter() {
+        let existing = get_block::<T>(world, world_id, *block_coords)?;
+        if !existing.is_replaceable() {
+            return Err(MultiBlockError::Obstructed(*block_coords));
+        }
+    }
+
+    run_transaction::<T, _>(world, world_id, |tx| {
+        blocks
+            .into_iter()
+            .try_for_each(|(block_coords, data)| tx.set_block(block_coords, data))
+    })?;
+
+    Ok(())
+}
+
+/// Gets the block at the given block coordinates within the given world.
+fn get_block<T>(world: &World, world_id: Entity, block_coords: IVec3) -> Result<T, VoxelQueryError>
+where
+    T: BlockData,
+{
+    let pointers = world
+        .get::<ChunkEntityPointers>(world_id)
+        .ok_or(VoxelQueryError::WorldNotFound(world_id))?;
+
+    let block_coords = pointers.topology().wrap_block_coords(block_coords);
+    let chunk_coords = block_coords >> 4;
+    pointers
+        .get_chunk_entity(chunk_coords)
+        .and_then(|chunk_id| world.get::<VoxelStorage<T>>(chunk_id))
+        .map(|storage| storage.get_block(block_coords))
+        .ok_or(VoxelQueryError::ChunkNotFound(world_id, chunk_coords))
+}
+
+/// Removes the remaining blocks of the multi-block structure that the given,
+/// already broken, block was a part of.
+///
+/// Only blocks that still match the structure are removed. Returns the
+/// coordinates and value of the master block, if a structure was removed.
+fn break_structure<T>(
+    world: &mut World,
+    world_id: Entity,
+    broken_coords: IVec3,
+    broken: T,
+) -> Option<(IVec3, T)>
+where
+    T: MultiBlock,
+{
+    let (master_coords, master) = match broken.master_offset() {
+        Some(offset) => {
+            let master_coords = broken_coords + offset;
+            let master = get_block::<T>(world, world_id, master_coords).ok()?;
+            let is_part = master
+                .parts()
+                .contains(&(broken_coords - master_coords, broken));
+            if !is_part {
+                return None;
+            }
+            (master_coords, master)
+        },
+        None => (broken_coords, broken),
+    };
+
+    let mut blocks = vec![(master_coords, master)];
+    blocks.extend(
+        master
+            .parts()
+            .into_iter()
+            .map(|(offset, part)| (master_coords + offset, part)),
+    );
+
+    if blocks.len() == 1 {
+        return None;
+    }
+
+    let _ = run_transaction::<T, _>(world, world_id, |tx| {
+        for (block_coords, expected) in blocks {
+            if block_coords == broken_coords {
+                continue;
+            }
+
+            if tx.get_block(block_coords).ok() == Some(expected) {
+                tx.set_block(block_coords, T::default())?;
+            }
+        }
+        Ok(())
+    });
+
+    Some((master_coords, master))
+}
+
+/// This system removes the remaining blocks of all multi-block structures
+/// that had one of their blocks broken this frame.
+fn break_multi_blocks<T>(mut broken: EventReader<BlockBroken<T>>, mut commands: Commands)
+where
+    T: MultiBlock,
+{
+    for event in broken.iter() {
+        let world_id = event.world_id;
+        let broken_coords = event.block_coords;
+        let block = event.block;
+
+        if block.master_offset().is_none() && block.parts().is_empty() {
+            continue;
+        }
+
+        commands.add(move |world: &mut World| {
+            let Some((master_coords, master)) =
+                break_structure(world, world_id, broken_coords, block)
+            else {
+                return;
+            };
+
+            if let Some(mut events) = world.get_resource_mut::<Events<MultiBlockBroken<T>>>() {
+                events.send(MultiBlockBroken {
+                    world_id,
+                    master_coords,
+                    block: master,
+                    broken_coords,
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    enum Block {
+        #[default]
+        Air,
+        Stone,
+        Bed,
+        BedFoot,
+    }
+
+    impl MultiBlock for Block {
+        fn parts(&self) -> Vec<(IVec3, Self)> {
+            match self {
+                Block::Bed => vec![(IVec3::X, Block::BedFoot)],
+                _ => vec![],
+            }
+        }
+
+        fn master_offset(&self) -> Option<IVec3> {
+            match self {
+                Block::BedFoot => Some(IVec3::NEG_X),
+                _ => None,
+            }
+        }
+    }
+
+    fn get_blocks(app: &mut App, world_id: Entity) -> Vec<Block> {
+        (14 .. 18)
+            .map(|x| get_block::<Block>(&app.world, world_id, IVec3::new(x, 0, 0)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn place_and_break_multi_block() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Block>::default(),
+            MultiBlockPlugin::<Block>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            for x in 0 .. 2 {
+                world
+                    .spawn_chunk(IVec3::new(x, 0, 0), VoxelStorage::<Block>::default())
+                    .unwrap();
+            }
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+
+        run_transaction::<Block, _>(&mut app.world, world_id, |tx| {
+            tx.set_block(IVec3::new(17, 0, 0), Block::Stone)
+        })
+        .unwrap();
+
+        // The bed crosses the chunk border.
+        place_multi_block(&mut app.world, world_id, IVec3::new(15, 0, 0), Block::Bed).unwrap();
+        assert!(matches!(
+            place_multi_block(&mut app.world, world_id, IVec3::new(16, 0, 0), Block::Bed),
+            Err(MultiBlockError::Obstructed(_))
+        ));
+        assert!(matches!(
+            place_multi_block(&mut app.world, world_id, IVec3::new(31, 0, 0), Block::Bed),
+            Err(MultiBlockError::QueryError(_))
+        ));
+        assert_eq!(get_blocks(&mut app, world_id), vec![
+            Block::Air,
+            Block::Bed,
+            Block::BedFoot,
+            Block::Stone
+        ]);
+
+        fn break_foot(worlds: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let mut world = commands.get_world(worlds.single()).unwrap();
+            world.break_block::<Block>(IVec3::new(16, 0, 0));
+        }
+        app.add_systems(Update, break_foot.run_if(run_once()));
+        app.update();
+
+        assert_eq!(get_blocks(&mut app, world_id), vec![
+            Block::Air,
+            Block::Air,
+            Block::Air,
+            Block::Stone
+        ]);
+
+        let events = app.world.resource::<Events<MultiBlockBroken<Block>>>();
+        let broken: Vec<_> = events.get_reader().iter(events).cloned().collect();
+        assert_eq!(broken, vec![MultiBlockBroken {
+            world_id,
+            master_coords: IVec3::new(15, 0, 0),
+            block: Block::Bed,
+            broken_coords: IVec3::new(16, 0, 0),
+        }]);
+    }
+}
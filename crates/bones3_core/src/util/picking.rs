@@ -0,0 +1,324 @@
+//! A picking backend for `bevy_mod_picking`, which reports the blocks under
+//! each pointer as pointer hits.
+//!
+//! Voxel worlds do not need any meshes or colliders to be picked. Instead,
+//! each pointer ray is cast through the block data of every voxel world, and
+//! the first pickable block is reported as a hit on the chunk entity that
+//! contains it. The [`BlockHit`] of each pointer can be read from the
+//! [`VoxelPointerHits`] resource, for example when handling a
+//! `Pointer<Click>` event.
+//!
+//! This module requires the `picking` feature.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
+use bevy_picking_core::backend::prelude::*;
+
+use crate::prelude::{BlockData, BlockHit, BlockScale, VoxelQuery, VoxelStorage, VoxelWorld};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+
+/// This plugin adds a picking backend for voxel worlds of the given block data
+/// type.
+pub struct VoxelPickingBackend<T>
+where
+    T: PickableBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// The maximum distance, in blocks, that pointer rays are cast.
+    max_distance: f32,
+}
+
+impl<T> Default for VoxelPickingBackend<T>
+where
+    T: PickableBlock,
+{
+    fn default() -> Self {
+        Self {
+            _phantom:     PhantomData,
+            max_distance: 256.0,
+        }
+    }
+}
+
+impl<T> VoxelPickingBackend<T>
+where
+    T: PickableBlock,
+{
+    /// Sets the maximum distance, in blocks, that pointer rays are cast
+    /// through each voxel world.
+    pub fn with_max_distance(mut self, max_distance: f32) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+impl<T> Plugin for VoxelPickingBackend<T>
+where
+    T: PickableBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(VoxelPointerHits::<T>::new(self.max_distance))
+            .add_event::<PointerHits>()
+            .add_systems(PreUpdate, update_voxel_hits::<T>.in_set(PickSet::Backend));
+    }
+}
+
+/// A block data type that can be picked by pointers.
+pub trait PickableBlock: BlockData {
+    /// Checks whether this block can be picked. Pointer rays pass through
+    /// blocks that cannot be picked, such as air.
+    fn is_pickable(&self) -> bool;
+}
+
+/// A resource that stores the nearest block under each pointer, as found by
+/// the [`VoxelPickingBackend`] during the current frame.
+#[derive(Debug, Resource)]
+pub struct VoxelPointerHits<T>
+where
+    T: PickableBlock,
+{
+    /// The nearest block under each pointer, along with the order of the
+    /// camera it was found with and its depth.
+    hits: HashMap<PointerId, (f32, f32, BlockHit)>,
+
+    /// The maximum distance, in blocks, that pointer rays are cast.
+    max_distance: f32,
+
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> VoxelPointerHits<T>
+where
+    T: PickableBlock,
+{
+    /// Creates a new, empty pointer hit map that casts pointer rays up to the
+    /// given distance, in blocks.
+    pub fn new(max_distance: f32) -> Self {
+        Self {
+            hits: HashMap::new(),
+            max_distance,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Gets the nearest block under the given pointer, if any.
+    ///
+    /// If the pointer is over the viewport of more than one camera, the block
+    /// that was found with the camera of the highest order is returned.
+    pub fn get(&self, pointer_id: PointerId) -> Option<BlockHit> {
+        self.hits.get(&pointer_id).map(|(.., hit)| *hit)
+    }
+
+    /// Stores the given block hit for the given pointer, if it is in front of
+    /// the block hit that is currently stored.
+    fn insert(&mut self, pointer_id: PointerId, order: f32, depth: f32, hit: BlockHit) {
+        let replace = match self.hits.get(&pointer_id) {
+            Some((o, d, _)) => order > *o || (order == *o && depth < *d),
+            None => true,
+        };
+        if replace {
+            self.hits.insert(pointer_id, (order, depth, hit));
+        }
+    }
+}
+
+/// A block that was hit by a ray in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WorldBlockHit {
+    /// The block that was hit.
+    hit: BlockHit,
+
+    /// The distance along the world space ray at which the block was hit.
+    depth: f32,
+
+    /// The world space position at which the block was hit.
+    position: Vec3,
+
+    /// The world space normal of the block face that was hit, or `None` if the
+    /// ray started inside the block.
+    normal: Option<Vec3>,
+}
+
+/// Casts the given world space ray through the voxel world with the given
+/// transform and block scale, returning the first pickable block.
+fn raycast_world<T>(
+    chunks: &VoxelQuery<&VoxelStorage<T>>,
+    world_id: Entity,
+    transform: &GlobalTransform,
+    scale: BlockScale,
+    ray: Ray,
+    max_distance: f32,
+) -> Option<WorldBlockHit>
+where
+    T: PickableBlock,
+{
+    let world = chunks.get_world(world_id).ok()?;
+
+    let to_world = transform.affine();
+    let to_local = to_world.inverse();
+    let local_ray = Ray {
+        origin:    scale.to_block_space(to_local.transform_point3(ray.origin)),
+        direction: scale
+            .to_block_space(to_local.transform_vector3(ray.direction))
+            .normalize_or_zero(),
+    };
+
+    let hit = world.raycast(local_ray, max_distance, |block| block.is_pickable())?;
+
+    let local_pos = local_ray.origin + local_ray.direction * hit.distance;
+    let position = to_world.transform_point3(scale.to_local_space(local_pos));
+    let normal = hit.face.map(|face| {
+        to_world
+            .transform_vector3(face.normal().as_vec3())
+            .normalize_or_zero()
+    });
+
+    Some(WorldBlockHit {
+        hit,
+        depth: position.distance(ray.origin),
+        position,
+        normal,
+    })
+}
+
+/// This system casts the ray of every pointer through all voxel worlds, and
+/// sends the first pickable block within each world as a pointer hit.
+fn update_voxel_hits<T>(
+    pointers: Query<(&PointerId, &PointerLocation)>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform)>,
+    worlds: Query<
+        (
+            Entity,
+            &ChunkEntityPointers,
+            Option<&GlobalTransform>,
+            Option<&BlockScale>,
+        ),
+        With<VoxelWorld>,
+    >,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut pointer_hits: ResMut<VoxelPointerHits<T>>,
+    mut output: EventWriter<PointerHits>,
+) where
+    T: PickableBlock,
+{
+    pointer_hits.hits.clear();
+
+    if primary_window.is_empty() {
+        return;
+    }
+
+    for (pointer_id, pointer_location) in pointers.iter() {
+        let Some(location) = pointer_location.location() else {
+            continue;
+        };
+
+        for (camera_id, camera, camera_transform) in cameras.iter() {
+            if !camera.is_active || !location.is_in_viewport(camera, &primary_window) {
+                continue;
+            }
+
+            let viewport_min = camera
+                .logical_viewport_rect()
+                .map_or(Vec2::ZERO, |rect| rect.min);
+            let Some(ray) = camera.viewport_to_world(camera_transform, location.position - viewport_min)
+            else {
+                continue;
+            };
+
+            let order = camera.order as f32;
+            let mut picks = vec![];
+            for (world_id, chunk_pointers, transform, scale) in worlds.iter() {
+                let transform = transform.copied().unwrap_or_default();
+                let scale = scale.copied().unwrap_or_default();
+                let Some(hit) = raycast_world(
+                    &chunks,
+                    world_id,
+                    &transform,
+                    scale,
+                    ray,
+                    pointer_hits.max_distance,
+                ) else {
+                    continue;
+                };
+
+                let Some(chunk_id) = chunk_pointers.get_chunk_entity(hit.hit.block_coords >> 4)
+                else {
+                    continue;
+                };
+
+                pointer_hits.insert(*pointer_id, order, hit.depth, hit.hit);
+                picks.push((
+                    chunk_id,
+                    HitData::new(camera_id, hit.depth, Some(hit.position), hit.normal),
+                ));
+            }
+
+            if !picks.is_empty() {
+                output.send(PointerHits::new(*pointer_id, picks, order));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    impl PickableBlock for u8 {
+        fn is_pickable(&self) -> bool {
+            *self != 0
+        }
+    }
+
+    #[test]
+    fn raycast_scaled_world() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            storage.set_block(IVec3::new(4, 0, 0), 1);
+
+            commands
+                .spawn_world(BlockScale(0.5))
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn cast(worlds: Query<Entity, With<VoxelWorld>>, chunks: VoxelQuery<&VoxelStorage<u8>>) {
+            let transform = GlobalTransform::from_translation(Vec3::new(10.0, 0.0, 0.0));
+            let ray = Ray {
+                origin:    Vec3::new(9.0, 0.25, 0.25),
+                direction: Vec3::X,
+            };
+
+            let hit = raycast_world(
+                &chunks,
+                worlds.single(),
+                &transform,
+                BlockScale(0.5),
+                ray,
+                64.0,
+            )
+            .unwrap();
+
+            assert_eq!(hit.hit.block_coords, IVec3::new(4, 0, 0));
+            assert_eq!(hit.hit.face, Some(BlockFace::NegX));
+            assert_eq!(hit.depth, 3.0);
+            assert_eq!(hit.position, Vec3::new(12.0, 0.25, 0.25));
+            assert_eq!(hit.normal, Some(Vec3::NEG_X));
+        }
+        Schedule::new().add_systems(cast).run(&mut app.world);
+    }
+}
@@ -0,0 +1,106 @@
+//! Pinned chunk regions, which are kept loaded regardless of chunk anchors.
+
+use bevy::prelude::*;
+
+use crate::math::Region;
+use crate::storage::VoxelChunk;
+
+/// A component for voxel worlds that lists regions of chunks that should stay
+/// loaded regardless of where chunk anchors are, such as quest areas, machine
+/// bases, or spawn chunks.
+///
+/// Pinned chunks are never despawned by a
+/// [`ChunkMemoryBudget`](crate::util::anchor::ChunkMemoryBudget), and chunk
+/// loaders, such as the world generator, spawn pinned chunks that are missing
+/// and never unload them. Regions are given in chunk coordinates.
+#[derive(Debug, Default, Component, Clone, PartialEq, Eq)]
+pub struct PinnedChunks {
+    /// The pinned regions, in chunk coordinates.
+    regions: Vec<Region>,
+}
+
+impl PinnedChunks {
+    /// Pins the given region of chunks.
+    pub fn pin(&mut self, region: Region) {
+        self.regions.push(region);
+    }
+
+    /// Pins the given region of chunks, returning self for chaining.
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.pin(region);
+        self
+    }
+
+    /// Removes the given region of chunks, if it was pinned.
+    ///
+    /// Chunks that are also within another pinned region stay pinned. Returns
+    /// `true` if the region was found.
+    pub fn unpin(&mut self, region: Region) -> bool {
+        let Some(index) = self.regions.iter().position(|r| *r == region) else {
+            return false;
+        };
+
+        self.regions.remove(index);
+        true
+    }
+
+    /// Removes all pinned regions.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Gets all pinned regions.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Checks whether the chunk at the given chunk coordinates is within any
+    /// pinned region.
+    pub fn contains(&self, chunk_coords: IVec3) -> bool {
+        self.regions
+            .iter()
+            .any(|region| region.contains(chunk_coords))
+    }
+
+    /// Gets an iterator over the coordinates of all pinned chunks.
+    ///
+    /// Chunks that are within multiple overlapping regions are returned once
+    /// for each region.
+    pub fn iter_chunks(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.regions.iter().flat_map(|region| region.iter())
+    }
+}
+
+/// Checks whether the given chunk is pinned by the [`PinnedChunks`] of its
+/// world.
+pub fn is_chunk_pinned(pinned: &Query<&PinnedChunks>, chunk_meta: &VoxelChunk) -> bool {
+    pinned
+        .get(chunk_meta.world_id())
+        .map(|pinned| pinned.contains(chunk_meta.chunk_coords()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn pin_and_unpin_regions() {
+        let spawn = Region::from_points(IVec3::new(-1, 0, -1), IVec3::new(1, 0, 1));
+        let base = Region::from_points(IVec3::new(10, 2, 10), IVec3::new(10, 2, 10));
+
+        let mut pinned = PinnedChunks::default().with_region(spawn);
+        pinned.pin(base);
+        assert!(pinned.contains(IVec3::new(1, 0, -1)));
+        assert!(pinned.contains(IVec3::new(10, 2, 10)));
+        assert!(!pinned.contains(IVec3::new(2, 0, 0)));
+        assert_eq!(pinned.iter_chunks().count(), 10);
+
+        assert!(pinned.unpin(spawn));
+        assert!(!pinned.unpin(spawn));
+        assert!(!pinned.contains(IVec3::ZERO));
+        assert_eq!(pinned.regions(), &[base]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Consistency checks between the chunk pointer cache of a voxel world and
+//! the chunk entities that actually exist within the Bevy world.
+//!
+//! The chunk pointer cache is updated through voxel commands, so it can fall
+//! out of sync if chunk entities are despawned or modified directly. These
+//! checks are intended for debugging, and can be run on-demand using
+//! [`VoxelWorldCommands::validate_chunk_pointers`](crate::query::VoxelWorldCommands::validate_chunk_pointers),
+//! or every frame by adding the [`ChunkPointerValidationPlugin`].
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{VoxelChunk, VoxelWorld};
+
+/// This plugin validates the chunk pointer cache of every voxel world at the
+/// end of each frame, sending a [`ChunkPointerReport`] event and logging a
+/// warning for every world where a discrepancy was found.
+///
+/// As this check visits every chunk each frame, it should only be added in
+/// debug builds.
+#[derive(Debug, Default)]
+pub struct ChunkPointerValidationPlugin {
+    /// Whether or not discrepancies should be repaired when found.
+    pub repair: bool,
+}
+
+impl Plugin for ChunkPointerValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ChunkPointerReport>()
+            .register_type::<ChunkPointerValidationSettings>()
+            .insert_resource(ChunkPointerValidationSettings {
+                repair: self.repair,
+            })
+            .add_systems(Last, validate_all_worlds);
+    }
+}
+
+/// The settings used by the [`ChunkPointerValidationPlugin`].
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct ChunkPointerValidationSettings {
+    /// Whether or not discrepancies should be repaired when found.
+    pub repair: bool,
+}
+
+/// A report of all discrepancies found between the chunk pointer cache of a
+/// voxel world and the chunk entities within that world.
+#[derive(Debug, Event, Clone, PartialEq, Eq)]
+pub struct ChunkPointerReport {
+    /// The id of the world that was validated.
+    pub world_id: Entity,
+
+    /// Chunk pointers that point to an entity that does not exist, is not a
+    /// chunk, or is a chunk with different coordinates or in a different
+    /// world.
+    ///
+    /// When repaired, these pointers are removed.
+    pub stale: Vec<(IVec3, Entity)>,
+
+    /// Chunks within this world that are not referenced by the chunk pointer
+    /// cache, while no other valid chunk occupies their coordinates.
+    ///
+    /// When repaired, pointers to these chunks are added.
+    pub orphaned: Vec<(IVec3, Entity)>,
+
+    /// Chunks within this world that share their coordinates with another
+    /// chunk that is already referenced by the chunk pointer cache.
+    ///
+    /// These are never repaired automatically, as one of the chunks would need
+    /// to be despawned.
+    pub duplicates: Vec<(IVec3, Entity)>,
+
+    /// Whether or not the stale and orphaned pointers have been repaired.
+    pub repaired: bool,
+}
+
+impl ChunkPointerReport {
+    /// Checks whether the chunk pointer cache was found to be consistent with
+    /// the chunk entities of the world.
+    pub fn is_consistent(&self) -> bool {
+        self.stale.is_empty() && self.orphaned.is_empty() && self.duplicates.is_empty()
+    }
+
+    /// Logs a warning describing all discrepancies within this report. Nothing
+    /// is logged if the report is consistent.
+    pub(crate) fn log(&self) {
+        if self.is_consistent() {
+            return;
+        }
+
+        warn!(
+            "Chunk pointers for world {:?} are inconsistent: {} stale, {} orphaned, {} duplicate \
+             (repaired: {})",
+            self.world_id,
+            self.stale.len(),
+            self.orphaned.len(),
+            self.duplicates.len(),
+            self.repaired
+        );
+    }
+}
+
+/// Validates the chunk pointer cache of the given world against all chunk
+/// entities, optionally repairing any stale or orphaned pointers.
+///
+/// Returns `None` if the given entity is not a voxel world.
+pub fn validate_chunk_pointers(
+    world: &mut World,
+    world_id: Entity,
+    repair: bool,
+) -> Option<ChunkPointerReport> {
+    world.get::<VoxelWorld>(world_id)?;
+
+    let chunks: HashMap<Entity, (Entity, IVec3)> = world
+        .query::<(Entity, &VoxelChunk)>()
+        .iter(world)
+        .map(|(e, c)| (e, (c.world_id(), c.chunk_coords())))
+        .collect();
+
+    let mut pointers = world.get_mut::<ChunkEntityPointers>(world_id)?;
+
+    let stale: Vec<(IVec3, Entity)> = pointers
+        .iter()
+        .filter(|(coords, entity)| chunks.get(entity) != Some(&(world_id, *coords)))
+        .collect();
+
+    let mut orphaned = vec![];
+    let mut duplicates = vec![];
+    for (&entity, &(_, coords)) in chunks.iter().filter(|(_, (w, _))| *w == world_id) {
+        match pointers.get_chunk_entity(coords) {
+            Some(e) if e == entity => {},
+            Some(e) if !stale.contains(&(coords, e)) => duplicates.push((coords, entity)),
+            _ => orphaned.push((coords, entity)),
+        }
+    }
+
+    // Two orphaned chunks may share the same coordinates, in which case only
+    // the first one can be repaired.
+    orphaned.sort_by_key(|(_, e)| *e);
+    let mut seen = HashMap::new();
+    orphaned.retain(|&(coords, entity)| {
+        if seen.insert(coords, entity).is_some() {
+            duplicates.push((coords, entity));
+            false
+        } else {
+            true
+        }
+    });
+
+    if repair {
+        for &(coords, _) in &stale {
+            pointers.set_chunk_entity(coords, None);
+        }
+
+        for &(coords, entity) in &orphaned {
+            pointers.set_chunk_entity(coords, Some(entity));
+        }
+    }
+
+    Some(ChunkPointerReport {
+        world_id,
+        stale,
+        orphaned,
+        duplicates,
+        repaired: repair,
+    })
+}
+
+/// This system validates the chunk pointers of all voxel worlds.
+fn validate_all_worlds(world: &mut World) {
+    let repair = world
+        .get_resource::<ChunkPointerValidationSettings>()
+        .is_some_and(|s| s.repair);
+
+    let worlds: Vec<Entity> = world
+        .query_filtered::<Entity, With<VoxelWorld>>()
+        .iter(world)
+        .collect();
+
+    for world_id in worlds {
+        let Some(report) = validate_chunk_pointers(world, world_id, repair) else {
+            continue;
+        };
+
+        if report.is_consistent() {
+            continue;
+        }
+
+        report.log();
+        world.send_event(report);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn repair_pointers() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+            world.spawn_chunk(IVec3::X, ()).unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        let report = validate_chunk_pointers(&mut app.world, world_id, false).unwrap();
+        assert!(report.is_consistent());
+
+        let mut pointers = app.world.get_mut::<ChunkEntityPointers>(world_id).unwrap();
+        let chunk_zero = pointers.get_chunk_entity(IVec3::ZERO).unwrap();
+        let chunk_x = pointers.get_chunk_entity(IVec3::X).unwrap();
+        pointers.set_chunk_entity(IVec3::X, None);
+        app.world.despawn(chunk_zero);
+
+        let report = validate_chunk_pointers(&mut app.world, world_id, true).unwrap();
+        assert_eq!(report.stale.len(), 1);
+        assert_eq!(report.orphaned, vec![(IVec3::X, chunk_x)]);
+
+        let report = validate_chunk_pointers(&mut app.world, world_id, false).unwrap();
+        assert!(report.is_consistent());
+    }
+}
@@ -0,0 +1,489 @@
+//! A flood-fill propagation queue for spreading per-block field values, such
+//! as light levels or pollution, across chunk borders.
+//!
+//! Field values are stored within a [`VoxelStorage`] of the field type, which
+//! is attached to each chunk alongside its block data. When a value would
+//! spread into a chunk that does not have field storage yet, it is deferred
+//! until that storage is added, so that values do not suddenly pop in when
+//! neighboring chunks finish loading.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::math::BlockFace;
+use crate::prelude::{
+    BlockData,
+    Bones3CoreSet,
+    ChunkDespawned,
+    VoxelChunk,
+    VoxelQuery,
+    VoxelStorage,
+    WorldDespawned,
+};
+
+/// This plugin handles the propagation of the given field type throughout all
+/// voxel worlds.
+pub struct FieldPropagationPlugin<V>
+where
+    V: PropagatedField,
+{
+    /// Phantom data for V.
+    _phantom: PhantomData<V>,
+
+    /// The maximum number of propagation steps to perform each frame.
+    max_steps: usize,
+}
+
+impl<V> Default for FieldPropagationPlugin<V>
+where
+    V: PropagatedField,
+{
+    fn default() -> Self {
+        Self {
+            _phantom:  PhantomData,
+            max_steps: 65536,
+        }
+    }
+}
+
+impl<V> FieldPropagationPlugin<V>
+where
+    V: PropagatedField,
+{
+    /// Sets the maximum number of propagation steps that are performed each
+    /// frame. Any remaining steps are continued on the next frame.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+}
+
+impl<V> Plugin for FieldPropagationPlugin<V>
+where
+    V: PropagatedField,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PropagationQueue::<V>::new(self.max_steps))
+            .add_event::<WorldDespawned>()
+            .add_event::<ChunkDespawned>()
+            .add_systems(
+                PostUpdate,
+                (
+                    remove_despawned_chunks::<V>,
+                    resume_deferred_propagation::<V>,
+                    propagate_field::<V>,
+                )
+                    .chain()
+                    .in_set(Bones3CoreSet::BlockUpdates),
+            );
+    }
+}
+
+/// A per-block field value that spreads outward to neighboring blocks.
+///
+/// Propagation only ever raises field values. A neighboring block is only
+/// updated if the value spreading into it is greater than its current value.
+pub trait PropagatedField: BlockData + PartialOrd {
+    /// Gets the value that spreads from a block with this value into each of
+    /// its neighbors, or `None` if this value does not spread any further.
+    ///
+    /// For light levels, this is usually the current level minus one.
+    fn spread(self) -> Option<Self>;
+}
+
+/// A resource that stores all pending propagation steps for a field type.
+///
+/// Values can be pushed into this queue directly in order to seed new sources,
+/// such as placed light blocks, or to drive custom propagation logic.
+#[derive(Debug, Resource)]
+pub struct PropagationQueue<V>
+where
+    V: PropagatedField,
+{
+    /// The propagation steps that are ready to be processed, along with the
+    /// coordinates of the chunk that they spread from.
+    queue: VecDeque<(Entity, IVec3, V, Option<IVec3>)>,
+
+    /// Propagation steps targeting chunks without field storage, by world id
+    /// and chunk coordinates.
+    deferred: HashMap<(Entity, IVec3), Vec<(IVec3, V, Option<IVec3>)>>,
+
+    /// The maximum number of propagation steps to perform each frame.
+    max_steps: usize,
+}
+
+impl<V> PropagationQueue<V>
+where
+    V: PropagatedField,
+{
+    /// Creates a new, empty propagation queue that performs up to the given
+    /// number of propagation steps each frame.
+    pub fn new(max_steps: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            deferred: HashMap::new(),
+            max_steps,
+        }
+    }
+
+    /// Queues the given value to be written to the block at the given block
+    /// coordinates, and spread outward from there.
+    pub fn push(&mut self, world_id: Entity, block_coords: IVec3, value: V) {
+        self.queue.push_back((world_id, block_coords, value, None));
+    }
+
+    /// Gets the number of propagation steps that are ready to be processed.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Gets whether or not there are no propagation steps that are ready to be
+    /// processed. Deferred steps are not included.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Gets the propagation steps that are waiting for the chunk at the given
+    /// chunk coordinates to receive field storage.
+    ///
+    /// Each step includes the coordinates of the chunk that it spread from, or
+    /// `None` if the value was pushed into the queue directly.
+    pub fn deferred(&self, world_id: Entity, chunk_coords: IVec3) -> &[(IVec3, V, Option<IVec3>)] {
+        self.deferred
+            .get(&(world_id, chunk_coords))
+            .map_or(&[], |steps| steps.as_slice())
+    }
+
+    /// Gets the total number of deferred propagation steps across all chunks.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred.values().map(|steps| steps.len()).sum()
+    }
+
+    /// Moves all deferred propagation steps for the given chunk back into the
+    /// queue.
+    pub fn resume(&mut self, world_id: Entity, chunk_coords: IVec3) {
+        if let Some(steps) = self.deferred.remove(&(world_id, chunk_coords)) {
+            self.queue.extend(
+                steps
+                    .into_iter()
+                    .map(|(block_coords, value, source)| (world_id, block_coords, value, source)),
+            );
+        }
+    }
+
+    /// Removes all queued and deferred propagation steps for the given world.
+    pub fn remove_world(&mut self, world_id: Entity) {
+        self.queue.retain(|(w, ..)| *w != world_id);
+        self.deferred.retain(|(w, _), _| *w != world_id);
+    }
+
+    /// Removes all queued and deferred propagation steps for the chunk at the
+    /// given chunk coordinates, as well as all steps that spread from it.
+    pub fn remove_chunk(&mut self, world_id: Entity, chunk_coords: IVec3) {
+        self.queue.retain(|(w, block_coords, _, source)| {
+            *w != world_id || (*block_coords >> 4 != chunk_coords && *source != Some(chunk_coords))
+        });
+        self.deferred.remove(&(world_id, chunk_coords));
+
+        self.deferred.retain(|(w, _), steps| {
+            if *w == world_id {
+                steps.retain(|(.., source)| *source != Some(chunk_coords));
+            }
+            !steps.is_empty()
+        });
+    }
+
+    /// Defers a propagation step until the chunk containing it receives field
+    /// storage.
+    fn defer(&mut self, world_id: Entity, block_coords: IVec3, value: V, source: Option<IVec3>) {
+        self.deferred
+            .entry((world_id, block_coords >> 4))
+            .or_default()
+            .push((block_coords, value, source));
+    }
+}
+
+/// This system removes all propagation steps for chunks and worlds that have
+/// been despawned.
+///
+/// Deferred steps that spread from a despawned chunk into its unloaded
+/// neighbors are removed as well, since they would otherwise be kept forever as
+/// the loaded area moves through the world. Deferred steps that spread from
+/// other chunks are kept.
+fn remove_despawned_chunks<V>(
+    mut despawned_worlds: EventReader<WorldDespawned>,
+    mut despawned_chunks: EventReader<ChunkDespawned>,
+    mut queue: ResMut<PropagationQueue<V>>,
+) where
+    V: PropagatedField,
+{
+    for ev in despawned_worlds.iter() {
+        queue.remove_world(ev.world_id);
+    }
+
+    for ev in despawned_chunks.iter() {
+        queue.remove_chunk(ev.world_id, ev.chunk_coords);
+    }
+}
+
+/// This system moves deferred propagation steps back into the queue when the
+/// chunk they target receives field storage.
+fn resume_deferred_propagation<V>(
+    chunks: Query<&VoxelChunk, Added<VoxelStorage<V>>>,
+    mut queue: ResMut<PropagationQueue<V>>,
+) where
+    V: PropagatedField,
+{
+    for chunk in chunks.iter() {
+        queue.resume(chunk.world_id(), chunk.chunk_coords());
+    }
+}
+
+/// This system processes queued propagation steps, up to the maximum number
+/// of steps per frame.
+fn propagate_field<V>(
+    mut storages: VoxelQuery<&mut VoxelStorage<V>>,
+    mut queue: ResMut<PropagationQueue<V>>,
+) where
+    V: PropagatedField,
+{
+    for _ in 0 .. queue.max_steps {
+        let Some((world_id, block_coords, value, source)) = queue.queue.pop_front() else {
+            return;
+        };
+
+        let Ok(mut world) = storages.get_world_mut(world_id) else {
+            continue;
+        };
+
+        let block_coords = world.topology().wrap_block_coords(block_coords);
+        let Some(mut storage) = world.get_chunk_at_block_mut(block_coords) else {
+            queue.defer(world_id, block_coords, value, source);
+            continue;
+        };
+
+        if storage.get_block(block_coords) >= value {
+            continue;
+        }
+
+        storage.set_block(block_coords, value);
+
+        if let Some(spread) = value.spread() {
+            let chunk_coords = block_coords >> 4;
+            for offset in BlockFace::ALL.map(BlockFace::normal) {
+                queue.queue.push_back((
+                    world_id,
+                    block_coords + offset,
+                    spread,
+                    Some(chunk_coords),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Reflect)]
+    struct Light(u8);
+
+    impl PropagatedField for Light {
+        fn spread(self) -> Option<Self> {
+            (self.0 > 1).then(|| Light(self.0 - 1))
+        }
+    }
+
+    #[test]
+    fn defer_across_border() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Light>::default(),
+            FieldPropagationPlugin::<Light>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<Light>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        app.world.resource_mut::<PropagationQueue<Light>>().push(
+            world_id,
+            IVec3::new(14, 5, 5),
+            Light(4),
+        );
+        app.update();
+
+        let queue = app.world.resource::<PropagationQueue<Light>>();
+        assert_eq!(queue.deferred(world_id, IVec3::X).len(), 5);
+        assert!(queue.deferred(world_id, IVec3::X).contains(&(
+            IVec3::new(16, 5, 5),
+            Light(2),
+            Some(IVec3::ZERO)
+        )));
+
+        fn load(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .spawn_chunk(IVec3::X, VoxelStorage::<Light>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(load).run(&mut app.world);
+        app.update();
+
+        let mut storages = app.world.query::<(&VoxelChunk, &VoxelStorage<Light>)>();
+        let (_, storage) = storages
+            .iter(&app.world)
+            .find(|(c, _)| c.chunk_coords() == IVec3::X)
+            .unwrap();
+        assert_eq!(storage.get_block(IVec3::new(16, 5, 5)), Light(2));
+        assert_eq!(storage.get_block(IVec3::new(17, 5, 5)), Light(1));
+        assert_eq!(
+            app.world
+                .resource::<PropagationQueue<Light>>()
+                .deferred_len(),
+            0
+        );
+    }
+
+    #[test]
+    fn drop_deferred_steps_of_despawned_chunks() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Light>::default(),
+            FieldPropagationPlugin::<Light>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<Light>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        app.world.resource_mut::<PropagationQueue<Light>>().push(
+            world_id,
+            IVec3::new(14, 1, 5),
+            Light(4),
+        );
+        app.update();
+
+        let queue = app.world.resource::<PropagationQueue<Light>>();
+        assert_eq!(queue.deferred(world_id, IVec3::X).len(), 5);
+        assert!(!queue.deferred(world_id, IVec3::NEG_Y).is_empty());
+
+        fn unload(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .get_chunk(IVec3::ZERO)
+                .unwrap()
+                .despawn();
+        }
+        Schedule::new().add_systems(unload).run(&mut app.world);
+        app.update();
+
+        let queue = app.world.resource::<PropagationQueue<Light>>();
+        assert_eq!(queue.deferred_len(), 0);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn keep_deferred_steps_of_loaded_chunks() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Light>::default(),
+            FieldPropagationPlugin::<Light>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<Light>::default())
+                .unwrap();
+            world
+                .spawn_chunk(IVec3::new(1, 1, 0), VoxelStorage::<Light>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+        app.world.resource_mut::<PropagationQueue<Light>>().push(
+            world_id,
+            IVec3::new(14, 5, 5),
+            Light(4),
+        );
+        app.update();
+        assert_eq!(
+            app.world
+                .resource::<PropagationQueue<Light>>()
+                .deferred(world_id, IVec3::X)
+                .len(),
+            5
+        );
+
+        // The despawned chunk also borders the unloaded chunk, but none of its
+        // deferred steps spread from there.
+        fn unload(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .get_chunk(IVec3::new(1, 1, 0))
+                .unwrap()
+                .despawn();
+        }
+        Schedule::new().add_systems(unload).run(&mut app.world);
+        app.update();
+        assert_eq!(
+            app.world
+                .resource::<PropagationQueue<Light>>()
+                .deferred(world_id, IVec3::X)
+                .len(),
+            5
+        );
+
+        fn load(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .spawn_chunk(IVec3::X, VoxelStorage::<Light>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(load).run(&mut app.world);
+        app.update();
+
+        let mut storages = app.world.query::<(&VoxelChunk, &VoxelStorage<Light>)>();
+        let (_, storage) = storages
+            .iter(&app.world)
+            .find(|(c, _)| c.chunk_coords() == IVec3::X)
+            .unwrap();
+        assert_eq!(storage.get_block(IVec3::new(16, 5, 5)), Light(2));
+    }
+}
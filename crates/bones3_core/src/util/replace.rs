@@ -0,0 +1,735 @@
+//! A world-wide "find and replace" pass for blocks, which is useful for fixing
+//! up existing worlds after the content of a game has changed.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::utils::{HashMap, HashSet};
+use futures_lite::future;
+
+use crate::math::Region;
+use crate::persistence::{PersistenceError, PersistentBlock, PersistentWorld};
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{bump_chunk_version, BlockData, ChunkStorageReplaced, VoxelStorage};
+use crate::Bones3CoreSet;
+
+/// A function that replaces the blocks of a chunk that is only saved within
+/// the chunk store of a persistent world, returning the number of blocks that
+/// were replaced.
+type PersistedReplacer<T> =
+    fn(&PersistentWorld, IVec3, &ReplaceRule<T>) -> Result<usize, PersistenceError>;
+
+/// This plugin runs all [`BlockReplaceJob`] components of the given block data
+/// type, a few chunks at a time.
+#[derive(Default)]
+pub struct BlockReplacePlugin<T>
+where
+    T: BlockData,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for BlockReplacePlugin<T>
+where
+    T: BlockData,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<BlockReplaceProgress>().add_systems(
+            PostUpdate,
+            (
+                run_block_replace_jobs::<T>.in_set(Bones3CoreSet::BlockWrites),
+                finish_block_replace_jobs::<T>.in_set(Bones3CoreSet::BlockUpdates),
+            ),
+        );
+    }
+}
+
+/// A component for voxel worlds that replaces every block that matches a
+/// matcher function with the output of a replacer function, across all chunks
+/// of the world.
+///
+/// The chunks to process are collected when the job first runs, and only a
+/// limited number of them are processed each frame. Loaded chunks are edited
+/// in place when voxel commands are flushed, which marks them as dirty within
+/// persistent worlds and sends a [`ChunkStorageReplaced`] event. Loaded chunks
+/// that do not have any block storage yet, such as chunks that are still being
+/// generated, are retried on a later frame, up to
+/// [`BlockReplaceJob::with_max_retries`] times before they are counted as
+/// failed. If [`BlockReplaceJob::include_persisted`] is used, chunks that are
+/// only saved within the chunk store of the world are loaded, edited, and saved
+/// back on the async compute task pool, without being spawned. Saved chunks
+/// that are loaded into the world while they are being processed in the
+/// background are processed again as loaded chunks, so the replacer should
+/// return blocks that no longer pass the matcher.
+///
+/// A [`BlockReplaceProgress`] event is sent each frame that the job makes
+/// progress, and the component is removed once the job has finished.
+#[derive(Component)]
+pub struct BlockReplaceJob<T>
+where
+    T: BlockData,
+{
+    /// The matcher and replacer functions of this job.
+    rule: ReplaceRule<T>,
+
+    /// The maximum number of chunks that are processed each frame.
+    chunks_per_frame: usize,
+
+    /// The maximum number of times that a loaded chunk without block storage
+    /// is retried.
+    max_retries: usize,
+
+    /// The number of times that each loaded chunk without block storage has
+    /// been retried so far.
+    retries: HashMap<IVec3, usize>,
+
+    /// The replacer for chunks that are only saved within the chunk store, if
+    /// they should be processed.
+    persisted: Option<PersistedReplacer<T>>,
+
+    /// The coordinates of the chunks that have not been processed yet, or
+    /// `None` if the job has not started.
+    queue: Option<Vec<IVec3>>,
+
+    /// The saved chunks that are currently being processed in the background.
+    tasks: Vec<(IVec3, Task<Result<usize, PersistenceError>>)>,
+
+    /// The progress of this job so far.
+    progress: BlockReplaceProgress,
+
+    /// The progress of this job that was last sent as an event.
+    reported: Option<BlockReplaceProgress>,
+}
+
+impl<T> BlockReplaceJob<T>
+where
+    T: BlockData,
+{
+    /// Creates a new block replace job that replaces every block that passes
+    /// the given matcher with the output of the given replacer.
+    ///
+    /// Only loaded chunks are processed, eight chunks per frame.
+    pub fn new<M, R>(matcher: M, replacer: R) -> Self
+    where
+        M: Fn(T) -> bool + Send + Sync + 'static,
+        R: Fn(T) -> T + Send + Sync + 'static,
+    {
+        Self {
+            rule:             ReplaceRule {
+                matcher:  Arc::new(matcher),
+                replacer: Arc::new(replacer),
+            },
+            chunks_per_frame: 8,
+            max_retries:      600,
+            retries:          HashMap::new(),
+            persisted:        None,
+            queue:            None,
+            tasks:            Vec::new(),
+            progress:         BlockReplaceProgress::default(),
+            reported:         None,
+        }
+    }
+
+    /// Sets the maximum number of chunks that are processed each frame,
+    /// including saved chunks that are still being processed in the
+    /// background.
+    pub fn with_chunks_per_frame(mut self, chunks_per_frame: usize) -> Self {
+        self.chunks_per_frame = chunks_per_frame.max(1);
+        self
+    }
+
+    /// Sets the maximum number of times that a loaded chunk without any block
+    /// storage is retried, before it is counted as failed. A chunk is retried
+    /// at most once per frame.
+    ///
+    /// Defaults to 600.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Also processes the chunks that are saved within the chunk store of the
+    /// world, but are not currently loaded.
+    ///
+    /// This has no effect on worlds without a [`PersistentWorld`] component.
+    pub fn include_persisted(mut self) -> Self
+    where
+        T: PersistentBlock,
+    {
+        self.persisted = Some(replace_persisted_chunk::<T>);
+        self
+    }
+
+    /// Gets the progress of this job so far.
+    pub fn progress(&self) -> BlockReplaceProgress {
+        self.progress
+    }
+
+    /// Replaces all matching blocks within the given storage, returning the
+    /// number of blocks that were replaced.
+    pub fn apply(&self, storage: &mut VoxelStorage<T>) -> usize {
+        self.rule.apply(storage)
+    }
+
+    /// Checks whether all chunks of this job have been processed.
+    fn is_done(&self) -> bool {
+        self.queue.as_ref().is_some_and(|queue| queue.is_empty()) && self.tasks.is_empty()
+    }
+}
+
+/// The matcher and replacer functions of a [`BlockReplaceJob`].
+struct ReplaceRule<T>
+where
+    T: BlockData,
+{
+    /// Checks whether a block should be replaced.
+    matcher: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// Gets the block that a matching block is replaced with.
+    replacer: Arc<dyn Fn(T) -> T + Send + Sync>,
+}
+
+impl<T> Clone for ReplaceRule<T>
+where
+    T: BlockData,
+{
+    fn clone(&self) -> Self {
+        Self {
+            matcher:  self.matcher.clone(),
+            replacer: self.replacer.clone(),
+        }
+    }
+}
+
+impl<T> ReplaceRule<T>
+where
+    T: BlockData,
+{
+    /// Replaces all matching blocks within the given storage, returning the
+    /// number of blocks that were replaced.
+    fn apply(&self, storage: &mut VoxelStorage<T>) -> usize {
+        let mut replaced = 0;
+        for local_pos in Region::CHUNK.iter() {
+            let block = storage.get_block(local_pos);
+            if (self.matcher)(block) {
+                storage.set_block(local_pos, (self.replacer)(block));
+                replaced += 1;
+            }
+        }
+        replaced
+    }
+}
+
+/// A Bevy command that replaces the matching blocks of a loaded chunk, using
+/// the block storage of the chunk at the time the command is applied.
+struct ReplaceBlocksAction<T>
+where
+    T: BlockData,
+{
+    /// The id of the world the chunk is in.
+    world_id: Entity,
+
+    /// The id of the chunk.
+    chunk_id: Entity,
+
+    /// The coordinates of the chunk.
+    chunk_coords: IVec3,
+
+    /// The matcher and replacer functions of the job.
+    rule: ReplaceRule<T>,
+}
+
+impl<T> Command for ReplaceBlocksAction<T>
+where
+    T: BlockData,
+{
+    fn apply(self, world: &mut World) {
+        let Some(mut storage) = world.get_mut::<VoxelStorage<T>>(self.chunk_id) else {
+            return;
+        };
+
+        let replaced = self.rule.apply(storage.bypass_change_detection());
+        if replaced == 0 {
+            return;
+        }
+        storage.set_changed();
+        bump_chunk_version::<T>(world, self.chunk_id);
+
+        if let Some(mut job) = world.get_mut::<BlockReplaceJob<T>>(self.world_id) {
+            job.progress.blocks_replaced += replaced;
+        }
+
+        if let Some(mut events) = world.get_resource_mut::<Events<ChunkStorageReplaced<T>>>() {
+            events.send(ChunkStorageReplaced::new(
+                self.world_id,
+                self.chunk_id,
+                self.chunk_coords,
+            ));
+        }
+    }
+}
+
+/// An event that is sent each frame that a [`BlockReplaceJob`] makes progress.
+#[derive(Debug, Default, Event, Clone, Copy, PartialEq, Eq)]
+pub struct BlockReplaceProgress {
+    /// The id of the world that the job is running in.
+    pub world_id: Option<Entity>,
+
+    /// The number of chunks that have been processed.
+    pub processed: usize,
+
+    /// The total number of chunks that the job processes.
+    pub total: usize,
+
+    /// The number of blocks that have been replaced.
+    pub blocks_replaced: usize,
+
+    /// The number of saved chunks that could not be loaded or saved, and
+    /// loaded chunks that did not receive any block storage before running
+    /// out of retries.
+    pub failed: usize,
+}
+
+impl BlockReplaceProgress {
+    /// Checks whether the job has processed all of its chunks.
+    pub fn is_finished(&self) -> bool {
+        self.processed >= self.total
+    }
+}
+
+/// Loads the given chunk from the chunk store of the given persistent world,
+/// replaces its blocks using the given job, and saves it back if any blocks
+/// were replaced.
+fn replace_persisted_chunk<T>(
+    persistent: &PersistentWorld,
+    chunk_coords: IVec3,
+    rule: &ReplaceRule<T>,
+) -> Result<usize, PersistenceError>
+where
+    T: PersistentBlock,
+{
+    let Some(mut storage) = persistent.load_chunk::<T>(chunk_coords)? else {
+        return Ok(0);
+    };
+
+    let replaced = rule.apply(&mut storage);
+    if replaced > 0 {
+        persistent.save_chunk(chunk_coords, &storage)?;
+    }
+
+    Ok(replaced)
+}
+
+/// Collects the coordinates of all chunks that the given job should process
+/// within the given world.
+fn collect_job_chunks<T>(
+    job: &BlockReplaceJob<T>,
+    pointers: &ChunkEntityPointers,
+    persistent: Option<&PersistentWorld>,
+) -> Vec<IVec3>
+where
+    T: BlockData,
+{
+    let mut chunks: HashSet<IVec3> = pointers
+        .iter()
+        .map(|(chunk_coords, _)| chunk_coords)
+        .collect();
+
+    if let (Some(_), Some(persistent)) = (job.persisted, persistent) {
+        match persistent.store().chunk_list() {
+            Ok(saved) => chunks.extend(saved),
+            Err(err) => warn!("Failed to list saved chunks for block replacement: {err}"),
+        }
+    }
+
+    // The queue is processed from the back, so sort it in reverse.
+    let mut chunks: Vec<IVec3> = chunks.into_iter().collect();
+    chunks.sort_unstable_by_key(|chunk_coords| std::cmp::Reverse(chunk_coords.to_array()));
+    chunks
+}
+
+/// This system processes the next few chunks of every block replace job.
+fn run_block_replace_jobs<T>(
+    mut worlds: Query<(
+        Entity,
+        &mut BlockReplaceJob<T>,
+        &ChunkEntityPointers,
+        Option<&PersistentWorld>,
+    )>,
+    chunks: Query<(), With<VoxelStorage<T>>>,
+    mut commands: Commands,
+) where
+    T: BlockData,
+{
+    for (world_id, mut job, pointers, persistent) in worlds.iter_mut() {
+        let job = job.as_mut();
+        let mut queue = match job.queue.take() {
+            Some(queue) => queue,
+            None => {
+                let queue = collect_job_chunks(job, pointers, persistent);
+                job.progress = BlockReplaceProgress {
+                    world_id: Some(world_id),
+                    total: queue.len(),
+                    ..default()
+                };
+                queue
+            },
+        };
+
+        let progress = &mut job.progress;
+        job.tasks.retain_mut(|(chunk_coords, task)| {
+            let Some(result) = future::block_on(future::poll_once(task)) else {
+                return true;
+            };
+
+            // The chunk was loaded from the chunk store before the edited
+            // chunk was saved, so the loaded blocks are edited instead.
+            if pointers.get_chunk_entity(*chunk_coords).is_some() {
+                queue.push(*chunk_coords);
+                return false;
+            }
+
+            progress.processed += 1;
+            match result {
+                Ok(replaced) => progress.blocks_replaced += replaced,
+                Err(err) => {
+                    warn!("Failed to replace blocks within saved chunk {chunk_coords}: {err}");
+                    progress.failed += 1;
+                },
+            }
+            false
+        });
+
+        let mut waiting = vec![];
+        for _ in job.tasks.len() .. job.chunks_per_frame {
+            let Some(chunk_coords) = queue.pop() else {
+                break;
+            };
+
+            if let Some(chunk_id) = pointers.get_chunk_entity(chunk_coords) {
+                if !chunks.contains(chunk_id) {
+                    let retries = job.retries.entry(chunk_coords).or_default();
+                    *retries += 1;
+                    if *retries <= job.max_retries {
+                        waiting.push(chunk_coords);
+                        continue;
+                    }
+
+                    warn!(
+                        "Chunk {chunk_coords} did not receive block storage for block replacement"
+                    );
+                    job.progress.processed += 1;
+                    job.progress.failed += 1;
+                    continue;
+                }
+
+                job.progress.processed += 1;
+                commands.add(ReplaceBlocksAction {
+                    world_id,
+                    chunk_id,
+                    chunk_coords,
+                    rule: job.rule.clone(),
+                });
+                continue;
+            }
+
+            let (Some(replace), Some(persistent)) = (job.persisted, persistent) else {
+                job.progress.processed += 1;
+                continue;
+            };
+
+            let persistent = persistent.clone();
+            let rule = job.rule.clone();
+            let task = AsyncComputeTaskPool::get()
+                .spawn(async move { replace(&persistent, chunk_coords, &rule) });
+            job.tasks.push((chunk_coords, task));
+        }
+
+        // Chunks that are still waiting for their block storage are retried
+        // after all other chunks have been processed.
+        queue.splice(0 .. 0, waiting);
+        job.queue = Some(queue);
+    }
+}
+
+/// This system sends the progress of every block replace job that made
+/// progress this frame, and removes the jobs that have finished.
+///
+/// This runs after voxel commands have been flushed, so that the blocks that
+/// were replaced within loaded chunks are included in the progress.
+fn finish_block_replace_jobs<T>(
+    mut worlds: Query<(Entity, &mut BlockReplaceJob<T>)>,
+    mut progress_events: EventWriter<BlockReplaceProgress>,
+    mut commands: Commands,
+) where
+    T: BlockData,
+{
+    for (world_id, mut job) in worlds.iter_mut() {
+        if job.reported != Some(job.progress) {
+            progress_events.send(job.progress);
+            job.reported = Some(job.progress);
+        }
+
+        if job.is_done() {
+            commands.entity(world_id).remove::<BlockReplaceJob<T>>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::tasks::TaskPool;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    /// Updates the given app until the block replace job of the given world
+    /// has finished, returning the number of updates that were needed.
+    fn finish_job(app: &mut App, world_id: Entity) -> usize {
+        for updates in 1 ..= 100 {
+            app.update();
+            if app.world.get::<BlockReplaceJob<u8>>(world_id).is_none() {
+                return updates;
+            }
+        }
+        panic!("Block replace job did not finish");
+    }
+
+    #[test]
+    fn replace_loaded_and_saved_chunks() {
+        AsyncComputeTaskPool::init(TaskPool::default);
+
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            BlockReplacePlugin::<u8>::default(),
+        ));
+
+        let persistent = PersistentWorld::new(MemoryChunkStore::default());
+        let mut saved = VoxelStorage::<u8>::default();
+        saved.set_block(IVec3::new(1, 1, 1), 4);
+        saved.set_block(IVec3::new(2, 2, 2), 4);
+        persistent.save_chunk(IVec3::new(5, 0, 0), &saved).unwrap();
+
+        let world_id = app
+            .world
+            .spawn((VoxelWorldBundle::new(), persistent.clone()))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                world
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::filled(4))
+                    .unwrap();
+                world
+                    .spawn_chunk(IVec3::X, VoxelStorage::<u8>::filled(1))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        app.world.entity_mut(world_id).insert(
+            BlockReplaceJob::<u8>::new(|block| block == 4, |_| 9)
+                .with_chunks_per_frame(2)
+                .include_persisted(),
+        );
+
+        app.update();
+        let progress = app
+            .world
+            .get::<BlockReplaceJob<u8>>(world_id)
+            .unwrap()
+            .progress();
+        assert_eq!(progress.processed, 2);
+        assert_eq!(progress.total, 3);
+        assert!(!progress.is_finished());
+
+        finish_job(&mut app, world_id);
+
+        let events = app.world.resource::<Events<BlockReplaceProgress>>();
+        let last = *events.iter_current_update_events().last().unwrap();
+        assert_eq!(last, BlockReplaceProgress {
+            world_id:        Some(world_id),
+            processed:       3,
+            total:           3,
+            blocks_replaced: 4096 + 2,
+            failed:          0,
+        });
+
+        let chunk_id = app
+            .world
+            .get::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .get_chunk_entity(IVec3::ZERO)
+            .unwrap();
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.uniform_block(), Some(9));
+
+        let saved = persistent
+            .load_chunk::<u8>(IVec3::new(5, 0, 0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.get_block(IVec3::new(2, 2, 2)), 9);
+        assert_eq!(saved.get_block(IVec3::ZERO), 0);
+    }
+
+    #[test]
+    fn retry_chunks_without_storage() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            BlockReplacePlugin::<u8>::default(),
+        ));
+
+        let world_id = app.world.spawn(VoxelWorldBundle::new()).id();
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+                world
+                    .spawn_chunk(IVec3::X, VoxelStorage::<u8>::filled(4))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        app.world
+            .entity_mut(world_id)
+            .insert(BlockReplaceJob::<u8>::new(|block| block == 4, |_| 9));
+        app.update();
+
+        let progress = app
+            .world
+            .get::<BlockReplaceJob<u8>>(world_id)
+            .unwrap()
+            .progress();
+        assert_eq!(progress.processed, 1);
+        assert_eq!(progress.total, 2);
+        assert_eq!(progress.blocks_replaced, 4096);
+
+        let chunk_id = app
+            .world
+            .get::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .get_chunk_entity(IVec3::ZERO)
+            .unwrap();
+        app.world
+            .entity_mut(chunk_id)
+            .insert(VoxelStorage::<u8>::filled(4));
+        finish_job(&mut app, world_id);
+
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.uniform_block(), Some(9));
+
+        let events = app.world.resource::<Events<BlockReplaceProgress>>();
+        let last = *events.iter_current_update_events().last().unwrap();
+        assert!(last.is_finished());
+        assert_eq!(last.blocks_replaced, 4096 * 2);
+    }
+
+    #[test]
+    fn give_up_on_chunks_without_storage() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            BlockReplacePlugin::<u8>::default(),
+        ));
+
+        let world_id = app.world.spawn(VoxelWorldBundle::new()).id();
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+                world
+                    .spawn_chunk(IVec3::X, VoxelStorage::<u8>::filled(4))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        app.update();
+
+        app.world
+            .entity_mut(world_id)
+            .insert(BlockReplaceJob::<u8>::new(|block| block == 4, |_| 9).with_max_retries(3));
+        assert_eq!(finish_job(&mut app, world_id), 4);
+
+        let events = app.world.resource::<Events<BlockReplaceProgress>>();
+        let last = *events.iter_current_update_events().last().unwrap();
+        assert_eq!(last, BlockReplaceProgress {
+            world_id:        Some(world_id),
+            processed:       2,
+            total:           2,
+            blocks_replaced: 4096,
+            failed:          1,
+        });
+    }
+
+    #[test]
+    fn requeue_saved_chunks_loaded_while_in_flight() {
+        AsyncComputeTaskPool::init(TaskPool::default);
+
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            BlockReplacePlugin::<u8>::default(),
+        ));
+
+        let persistent = PersistentWorld::new(MemoryChunkStore::default());
+        persistent
+            .save_chunk(IVec3::ZERO, &VoxelStorage::<u8>::filled(4))
+            .unwrap();
+
+        let world_id = app
+            .world
+            .spawn((VoxelWorldBundle::new(), persistent.clone()))
+            .id();
+        app.world
+            .entity_mut(world_id)
+            .insert(BlockReplaceJob::<u8>::new(|block| block == 4, |_| 9).include_persisted());
+        app.update();
+        assert_eq!(
+            app.world
+                .get::<BlockReplaceJob<u8>>(world_id)
+                .unwrap()
+                .tasks
+                .len(),
+            1
+        );
+
+        // The chunk is loaded with its old blocks while the saved chunk is
+        // still being edited in the background.
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                world
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::filled(4))
+                    .unwrap();
+            })
+            .run(&mut app.world);
+        finish_job(&mut app, world_id);
+
+        let chunk_id = app
+            .world
+            .get::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .get_chunk_entity(IVec3::ZERO)
+            .unwrap();
+        let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+        assert_eq!(storage.uniform_block(), Some(9));
+
+        let events = app.world.resource::<Events<BlockReplaceProgress>>();
+        let last = *events.iter_current_update_events().last().unwrap();
+        assert_eq!(last, BlockReplaceProgress {
+            world_id:        Some(world_id),
+            processed:       1,
+            total:           1,
+            blocks_replaced: 4096,
+            failed:          0,
+        });
+    }
+}
@@ -0,0 +1,752 @@
+//! A narrow hook layer for attaching scripted behaviors to blocks.
+//!
+//! This module defines the stable, sandboxed API surface that block scripts
+//! are built upon. Each script function is wrapped in a [`BlockScriptFn`] and
+//! registered against a script ID, and the scripts can then only interact with
+//! the world through a [`BlockScriptContext`]. Scripts written in rhai can be
+//! registered through a [`RhaiBlockScript`], while other languages, such as
+//! lua, can be bound in the same way.
+//!
+//! Tick hooks are run for every loaded block with a script each time block
+//! scripts are run, and place hooks are run whenever a block with a script is
+//! written through voxel commands. Interact hooks are triggered by sending a
+//! [`BlockInteractEvent`], the same event that runs the handlers of the
+//! [`BlockInteractPlugin`](crate::util::interact::BlockInteractPlugin).
+
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use rhai::{Dynamic, Engine, ParseError, Scope, AST};
+
+use crate::math::Region;
+use crate::prelude::{
+    BlockData,
+    ChunkDespawned,
+    ChunkStorageReplaced,
+    VoxelCommands,
+    VoxelQuery,
+    VoxelStorage,
+    WorldDespawned,
+};
+use crate::storage::send_inserted_storage_events;
+use crate::util::block_context::{run_block_callback, BlockCallback, BlockContext};
+use crate::util::block_update::{propagate_block_updates, BlockUpdateQueue};
+use crate::util::interact::BlockInteractEvent;
+use crate::util::simulation::VoxelSimulationSet;
+use crate::Bones3CoreSet;
+
+/// This plugin runs block script callbacks for the given block data type.
+///
+/// By default, scripts are run once per frame within the `Update` schedule.
+#[derive(Default)]
+pub struct BlockScriptPlugin<T>
+where
+    T: ScriptedBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// Whether scripts are run on the fixed timestep.
+    fixed_timestep: bool,
+}
+
+impl<T> BlockScriptPlugin<T>
+where
+    T: ScriptedBlock,
+{
+    /// Runs block scripts within [`VoxelSimulationSet::Tick`] on the
+    /// [`FixedUpdate`] schedule instead, for use with the
+    /// [`VoxelSimulationPlugin`](crate::util::simulation::VoxelSimulationPlugin).
+    pub fn with_fixed_timestep(mut self) -> Self {
+        self.fixed_timestep = true;
+        self
+    }
+}
+
+impl<T> Plugin for BlockScriptPlugin<T>
+where
+    T: ScriptedBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<BlockScriptEvent>()
+            .add_event::<BlockInteractEvent>()
+            .add_event::<WorldDespawned>()
+            .add_event::<ChunkDespawned>()
+            .add_event::<ChunkStorageReplaced<T>>()
+            .init_resource::<BlockScripts<T>>()
+            .init_resource::<ScriptedBlocks<T>>()
+            .add_systems(
+                PostUpdate,
+                (
+                    remove_despawned_scripted_blocks::<T>,
+                    track_scripted_blocks::<T>,
+                )
+                    .chain()
+                    .in_set(Bones3CoreSet::BlockUpdates)
+                    .after(send_inserted_storage_events::<T>)
+                    .before(propagate_block_updates),
+            );
+
+        let run_scripts = (tick_scripted_blocks::<T>, run_block_scripts::<T>).chain();
+        if self.fixed_timestep {
+            app.add_systems(
+                FixedUpdate,
+                (
+                    run_scripts.in_set(VoxelSimulationSet::Tick),
+                    track_scripted_blocks::<T>
+                        .in_set(VoxelSimulationSet::BlockUpdates)
+                        .before(propagate_block_updates),
+                ),
+            );
+        } else {
+            app.add_systems(Update, run_scripts);
+        }
+    }
+}
+
+/// A block data type that can have scripted behaviors attached to it.
+pub trait ScriptedBlock: BlockData {
+    /// Gets the ID of the script set that handles this block, or `None` if
+    /// this block does not have any scripted behaviors.
+    fn script_id(&self) -> Option<u32>;
+}
+
+/// The type of block action that triggers a script callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum BlockScriptHook {
+    /// Triggered for every loaded block with a script, each time block scripts
+    /// are run.
+    Tick,
+
+    /// Triggered when the block is interacted with, by sending a
+    /// [`BlockInteractEvent`].
+    Interact,
+
+    /// Triggered when the block is written through voxel commands.
+    Place,
+}
+
+/// An event that requests the script callback for the given hook to be run
+/// for the block at the given coordinates.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockScriptEvent {
+    /// The id of the world the block is in.
+    pub world_id: Entity,
+
+    /// The coordinates of the block.
+    pub block_coords: IVec3,
+
+    /// The hook that triggered this event.
+    pub hook: BlockScriptHook,
+}
+
+/// A script callback function.
+pub type BlockScriptFn<T> = BlockCallback<T, BlockScriptHook>;
+
+/// A resource that stores all registered block script callbacks.
+#[derive(Resource)]
+pub struct BlockScripts<T>
+where
+    T: ScriptedBlock,
+{
+    /// The registered callbacks, by script ID and hook.
+    callbacks: HashMap<(u32, BlockScriptHook), BlockScriptFn<T>>,
+}
+
+impl<T> Default for BlockScripts<T>
+where
+    T: ScriptedBlock,
+{
+    fn default() -> Self {
+        Self {
+            callbacks: HashMap::new(),
+        }
+    }
+}
+
+impl<T> BlockScripts<T>
+where
+    T: ScriptedBlock,
+{
+    /// Registers a callback for the given script ID and hook, replacing any
+    /// callback that was previously registered for them.
+    pub fn register<F>(&mut self, script_id: u32, hook: BlockScriptHook, callback: F)
+    where
+        F: Fn(&mut BlockScriptContext<T>) + Send + Sync + 'static,
+    {
+        self.callbacks.insert((script_id, hook), Arc::new(callback));
+    }
+
+    /// Removes the callback for the given script ID and hook.
+    pub fn unregister(&mut self, script_id: u32, hook: BlockScriptHook) {
+        self.callbacks.remove(&(script_id, hook));
+    }
+
+    /// Gets the callback for the given script ID and hook, if one exists.
+    pub fn get(&self, script_id: u32, hook: BlockScriptHook) -> Option<BlockScriptFn<T>> {
+        self.callbacks.get(&(script_id, hook)).cloned()
+    }
+}
+
+/// A resource that tracks all loaded blocks with a script, which receive the
+/// [`BlockScriptHook::Tick`] hook each time block scripts are run.
+///
+/// Blocks are tracked when they are written through voxel commands, and when
+/// the storage of their chunk is inserted or replaced.
+#[derive(Resource)]
+pub struct ScriptedBlocks<T>
+where
+    T: ScriptedBlock,
+{
+    /// The block coordinates of all tracked blocks, grouped by world id and
+    /// chunk coordinates.
+    blocks: HashMap<(Entity, IVec3), HashSet<IVec3>>,
+
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Default for ScriptedBlocks<T>
+where
+    T: ScriptedBlock,
+{
+    fn default() -> Self {
+        Self {
+            blocks:   HashMap::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> ScriptedBlocks<T>
+where
+    T: ScriptedBlock,
+{
+    /// Checks whether the block at the given block coordinates is tracked.
+    pub fn contains(&self, world_id: Entity, block_coords: IVec3) -> bool {
+        self.blocks
+            .get(&(world_id, block_coords >> 4))
+            .is_some_and(|blocks| blocks.contains(&block_coords))
+    }
+
+    /// Starts or stops tracking the block at the given block coordinates.
+    fn set(&mut self, world_id: Entity, block_coords: IVec3, scripted: bool) {
+        let key = (world_id, block_coords >> 4);
+        if scripted {
+            self.blocks.entry(key).or_default().insert(block_coords);
+        } else if let Some(blocks) = self.blocks.get_mut(&key) {
+            blocks.remove(&block_coords);
+            if blocks.is_empty() {
+                self.blocks.remove(&key);
+            }
+        }
+    }
+}
+
+/// The sandboxed world access that is provided to a script callback.
+pub type BlockScriptContext<'a, T> = BlockContext<'a, T, BlockScriptHook>;
+
+impl<'a, T> BlockContext<'a, T, BlockScriptHook>
+where
+    T: BlockData,
+{
+    /// Gets the hook that triggered the script.
+    pub fn hook(&self) -> BlockScriptHook {
+        *self.trigger()
+    }
+}
+
+/// This system stops tracking the scripted blocks of all chunks and worlds that
+/// have been despawned.
+fn remove_despawned_scripted_blocks<T>(
+    mut worlds: EventReader<WorldDespawned>,
+    mut chunks: EventReader<ChunkDespawned>,
+    mut scripted: ResMut<ScriptedBlocks<T>>,
+) where
+    T: ScriptedBlock,
+{
+    for ev in worlds.iter() {
+        scripted.blocks.retain(|(w, _), _| *w != ev.world_id);
+    }
+
+    for ev in chunks.iter() {
+        scripted.blocks.remove(&(ev.world_id, ev.chunk_coords));
+    }
+}
+
+/// This system tracks all blocks with a script that were written or loaded,
+/// and sends the place hook for all written blocks with a script.
+///
+/// This runs before the block update queue is drained.
+fn track_scripted_blocks<T>(
+    queue: Res<BlockUpdateQueue>,
+    mut replaced: EventReader<ChunkStorageReplaced<T>>,
+    storages: Query<&VoxelStorage<T>>,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut scripted: ResMut<ScriptedBlocks<T>>,
+    mut events: EventWriter<BlockScriptEvent>,
+) where
+    T: ScriptedBlock,
+{
+    for ev in replaced.iter() {
+        let Ok(storage) = storages.get(ev.chunk_id) else {
+            continue;
+        };
+
+        let key = (ev.world_id, ev.chunk_coords);
+        scripted.blocks.remove(&key);
+
+        let origin = ev.chunk_coords << 4;
+        let blocks: HashSet<IVec3> = Region::CHUNK
+            .iter()
+            .filter(|local_pos| storage.get_block(*local_pos).script_id().is_some())
+            .map(|local_pos| origin + local_pos)
+            .collect();
+
+        if !blocks.is_empty() {
+            scripted.blocks.insert(key, blocks);
+        }
+    }
+
+    for (world_id, block_coords) in queue.iter() {
+        let Ok(world) = chunks.get_world(world_id) else {
+            continue;
+        };
+
+        let Some(block) = world
+            .get_chunk(block_coords >> 4)
+            .map(|storage| storage.get_block(block_coords))
+        else {
+            continue;
+        };
+
+        let has_script = block.script_id().is_some();
+        scripted.set(world_id, block_coords, has_script);
+
+        if has_script {
+            events.send(BlockScriptEvent {
+                world_id,
+                block_coords,
+                hook: BlockScriptHook::Place,
+            });
+        }
+    }
+}
+
+/// This system sends the tick hook for all tracked blocks with a script.
+fn tick_scripted_blocks<T>(
+    scripted: Res<ScriptedBlocks<T>>,
+    mut events: EventWriter<BlockScriptEvent>,
+) where
+    T: ScriptedBlock,
+{
+    for ((world_id, _), blocks) in scripted.blocks.iter() {
+        events.send_batch(blocks.iter().map(|block_coords| {
+            BlockScriptEvent {
+                world_id:     *world_id,
+                block_coords: *block_coords,
+                hook:         BlockScriptHook::Tick,
+            }
+        }));
+    }
+}
+
+/// This system runs the script callbacks for all block script events, and the
+/// interact hook for all block interaction events.
+fn run_block_scripts<T>(
+    mut events: EventReader<BlockScriptEvent>,
+    mut interactions: EventReader<BlockInteractEvent>,
+    scripts: Res<BlockScripts<T>>,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut commands: VoxelCommands,
+) where
+    T: ScriptedBlock,
+{
+    let interactions = interactions.iter().map(|ev| {
+        BlockScriptEvent {
+            world_id:     ev.world_id,
+            block_coords: ev.block_coords,
+            hook:         BlockScriptHook::Interact,
+        }
+    });
+
+    for ev in events.iter().copied().chain(interactions) {
+        run_block_callback(
+            &chunks,
+            &mut commands,
+            ev.world_id,
+            ev.block_coords,
+            ev.hook,
+            |block: T| block.script_id().and_then(|id| scripts.get(id, ev.hook)),
+        );
+    }
+}
+
+/// A block data type that can be passed to and from rhai scripts, as the
+/// registry ID of the block.
+pub trait RhaiBlock: ScriptedBlock {
+    /// Gets the registry ID of this block.
+    fn to_script_value(&self) -> i64;
+
+    /// Gets the block with the given registry ID, or `None` if the ID is not
+    /// valid. Block writes with invalid IDs are ignored.
+    fn from_script_value(value: i64) -> Option<Self>;
+}
+
+/// A block script that is written in rhai.
+///
+/// The script may define the functions `on_tick`, `on_interact`, and
+/// `on_place`, which are called for their respective hooks with the block
+/// coordinates of the triggering block as three integers. Within them, the
+/// script can call `get_block(x, y, z)` to get the registry ID of a block
+/// within the read radius around the triggering block, which returns `()` for
+/// blocks out of range or within unloaded chunks, and `set_block(x, y, z, id)`
+/// to queue a block write. Scripts have no other access to the world.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::util::scripting::*;
+/// # #[derive(Debug, Default, Clone, Copy, Reflect)]
+/// # struct Block(u32);
+/// # impl ScriptedBlock for Block {
+/// #     fn script_id(&self) -> Option<u32> {
+/// #         Some(self.0)
+/// #     }
+/// # }
+/// # impl RhaiBlock for Block {
+/// #     fn to_script_value(&self) -> i64 {
+/// #         self.0 as i64
+/// #     }
+/// #     fn from_script_value(value: i64) -> Option<Self> {
+/// #         u32::try_from(value).ok().map(Block)
+/// #     }
+/// # }
+/// # let mut block_scripts = BlockScripts::<Block>::default();
+/// let script = RhaiBlockScript::new(
+///     r#"
+///         fn on_interact(x, y, z) {
+///             if get_block(x, y + 1, z) == 0 {
+///                 set_block(x, y + 1, z, 2);
+///             }
+///         }
+///     "#,
+/// )?;
+/// script.register(&mut block_scripts, 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct RhaiBlockScript {
+    /// The sandboxed engine that runs the script.
+    engine: Arc<Engine>,
+
+    /// The compiled script.
+    ast: Arc<AST>,
+
+    /// The world access of the script function that is currently running.
+    state: Arc<Mutex<RhaiScriptState>>,
+
+    /// The distance from the triggering block, along each axis, within which
+    /// the script can read blocks.
+    read_radius: i32,
+}
+
+/// The world access of a running rhai block script.
+#[derive(Default)]
+struct RhaiScriptState {
+    /// The registry IDs of all loaded blocks within the read radius.
+    reads: HashMap<IVec3, i64>,
+
+    /// The queued block writes, as registry IDs.
+    writes: Vec<(IVec3, i64)>,
+}
+
+impl RhaiBlockScript {
+    /// The names of the script functions for each hook.
+    const HOOK_FUNCTIONS: [(BlockScriptHook, &'static str); 3] = [
+        (BlockScriptHook::Tick, "on_tick"),
+        (BlockScriptHook::Interact, "on_interact"),
+        (BlockScriptHook::Place, "on_place"),
+    ];
+    /// The maximum number of operations that a single script function may run
+    /// before it is aborted.
+    pub const MAX_OPERATIONS: u64 = 100_000;
+
+    /// Compiles the given rhai script, with a read radius of `1`.
+    pub fn new(source: &str) -> Result<Self, ParseError> {
+        let state = Arc::new(Mutex::new(RhaiScriptState::default()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(Self::MAX_OPERATIONS);
+
+        let reads = state.clone();
+        engine.register_fn("get_block", move |x: i64, y: i64, z: i64| {
+            let block_coords = IVec3::new(x as i32, y as i32, z as i32);
+            match reads.lock().unwrap().reads.get(&block_coords) {
+                Some(value) => Dynamic::from(*value),
+                None => Dynamic::UNIT,
+            }
+        });
+
+        let writes = state.clone();
+        engine.register_fn("set_block", move |x: i64, y: i64, z: i64, value: i64| {
+            let block_coords = IVec3::new(x as i32, y as i32, z as i32);
+            writes.lock().unwrap().writes.push((block_coords, value));
+        });
+
+        let ast = engine.compile(source)?;
+        Ok(Self {
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+            state,
+            read_radius: 1,
+        })
+    }
+
+    /// Sets the distance from the triggering block, along each axis, within
+    /// which the script can read blocks.
+    pub fn with_read_radius(mut self, read_radius: u32) -> Self {
+        self.read_radius = read_radius as i32;
+        self
+    }
+
+    /// Registers the hook functions that are defined by this script for the
+    /// given script ID.
+    pub fn register<T>(&self, scripts: &mut BlockScripts<T>, script_id: u32)
+    where
+        T: RhaiBlock,
+    {
+        for (hook, name) in Self::HOOK_FUNCTIONS {
+            let defined = self
+                .ast
+                .iter_functions()
+                .any(|func| func.name == name && func.params.len() == 3);
+            if !defined {
+                continue;
+            }
+
+            let script = self.clone();
+            scripts.register(script_id, hook, move |ctx| script.call(name, ctx));
+        }
+    }
+
+    /// Calls the given script function for the block that triggered the
+    /// script.
+    ///
+    /// If the script function fails, all of its block writes are discarded.
+    fn call<T>(&self, name: &str, ctx: &mut BlockScriptContext<T>)
+    where
+        T: RhaiBlock,
+    {
+        let origin = ctx.block_coords();
+        let radius = IVec3::splat(self.read_radius);
+        let reads = Region::from_points(origin - radius, origin + radius)
+            .iter()
+            .filter_map(|block_coords| {
+                let block = ctx.get_block(block_coords)?;
+                Some((block_coords, block.to_script_value()))
+            })
+            .collect();
+
+        *self.state.lock().unwrap() = RhaiScriptState {
+            reads,
+            writes: vec![],
+        };
+
+        let args = (origin.x as i64, origin.y as i64, origin.z as i64);
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args);
+
+        let state = std::mem::take(&mut *self.state.lock().unwrap());
+        if let Err(err) = result {
+            warn!("Block script function `{name}` failed at {origin}: {err}");
+            return;
+        }
+
+        for (block_coords, value) in state.writes {
+            if let Some(block) = T::from_script_value(value) {
+                ctx.set_block(block_coords, block);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::interact::InteractButton;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    struct Block(u32);
+
+    impl ScriptedBlock for Block {
+        fn script_id(&self) -> Option<u32> {
+            (self.0 != 0).then_some(self.0)
+        }
+    }
+
+    impl RhaiBlock for Block {
+        fn to_script_value(&self) -> i64 {
+            self.0 as i64
+        }
+
+        fn from_script_value(value: i64) -> Option<Self> {
+            u32::try_from(value).ok().map(Block)
+        }
+    }
+
+    fn world_id(app: &mut App) -> Entity {
+        app.world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world)
+    }
+
+    #[test]
+    fn interact_script() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Block>::default(),
+            BlockScriptPlugin::<Block>::default(),
+        ));
+
+        app.world.resource_mut::<BlockScripts<Block>>().register(
+            1,
+            BlockScriptHook::Interact,
+            |ctx| {
+                let above = ctx.block_coords() + IVec3::Y;
+                ctx.set_block(above, Block(2));
+            },
+        );
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::default();
+            storage.set_block(IVec3::new(1, 1, 1), Block(1));
+
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = world_id(&mut app);
+        let player = app.world.spawn_empty().id();
+        app.world.send_event(BlockInteractEvent {
+            player,
+            world_id,
+            block_coords: IVec3::new(1, 1, 1),
+            face: BlockFace::PosY,
+            button: InteractButton::Secondary,
+        });
+        app.update();
+
+        let storage = app.world.query::<&VoxelStorage<Block>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 1)), Block(2));
+    }
+
+    #[test]
+    fn place_and_tick_hooks() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Block>::default(),
+            BlockScriptPlugin::<Block>::default(),
+        ));
+
+        let ticks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut scripts = app.world.resource_mut::<BlockScripts<Block>>();
+        scripts.register(1, BlockScriptHook::Place, |ctx| {
+            let above = ctx.block_coords() + IVec3::Y;
+            ctx.set_block(above, Block(3));
+        });
+        let counter = ticks.clone();
+        scripts.register(1, BlockScriptHook::Tick, move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::default();
+            storage.set_block(IVec3::new(5, 5, 5), Block(2));
+
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+        app.update();
+
+        let world_id = world_id(&mut app);
+        let scripted = app.world.resource::<ScriptedBlocks<Block>>();
+        assert!(scripted.contains(world_id, IVec3::new(5, 5, 5)));
+
+        fn place(world_query: Query<Entity, With<VoxelWorld>>, mut commands: VoxelCommands) {
+            let world_id = world_query.single();
+            let mut world_commands = commands.get_world(world_id).unwrap();
+            world_commands.set_block(IVec3::new(1, 1, 1), Block(1));
+        }
+        Schedule::new().add_systems(place).run(&mut app.world);
+        app.update();
+        app.update();
+
+        let storage = app.world.query::<&VoxelStorage<Block>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 1)), Block(3));
+        assert_eq!(ticks.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        let scripted = app.world.resource::<ScriptedBlocks<Block>>();
+        assert!(scripted.contains(world_id, IVec3::new(1, 2, 1)));
+    }
+
+    #[test]
+    fn rhai_interact_script() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<Block>::default(),
+            BlockScriptPlugin::<Block>::default(),
+        ));
+
+        let script = RhaiBlockScript::new(
+            r#"
+                fn on_interact(x, y, z) {
+                    if get_block(x, y + 2, z) == () {
+                        set_block(x, y + 1, z, get_block(x, y, z) + 4);
+                    }
+                }
+            "#,
+        )
+        .unwrap();
+        let mut scripts = app.world.resource_mut::<BlockScripts<Block>>();
+        script.register(&mut scripts, 1);
+        assert!(scripts.get(1, BlockScriptHook::Interact).is_some());
+        assert!(scripts.get(1, BlockScriptHook::Tick).is_none());
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::default();
+            storage.set_block(IVec3::new(1, 1, 1), Block(1));
+
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = world_id(&mut app);
+        let player = app.world.spawn_empty().id();
+        app.world.send_event(BlockInteractEvent {
+            player,
+            world_id,
+            block_coords: IVec3::new(1, 1, 1),
+            face: BlockFace::PosY,
+            button: InteractButton::Secondary,
+        });
+        app.update();
+
+        let storage = app.world.query::<&VoxelStorage<Block>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 1)), Block(5));
+    }
+}
@@ -0,0 +1,171 @@
+//! Bulk operations that act on a whole sector of chunks at once, such as
+//! unloading or filling a 256x256x256 block region of a world.
+//!
+//! Each sector is a 16x16x16 grid of chunks. The chunk coordinates within a
+//! sector are given by
+//! [`Region::sector_chunks`](crate::math::Region::sector_chunks), and the
+//! sector that contains a chunk is given by [`chunk_to_sector_coords`]. Sectors
+//! are usually managed
+//! through [`VoxelWorldCommands`](crate::query::VoxelWorldCommands), and can
+//! be saved using [`save_sector`](crate::persistence::save_sector).
+
+use bevy::ecs::system::Command;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+
+use crate::query::ReplaceStorageAction;
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{BlockData, ChunkDespawned, VoxelStorage};
+
+/// Gets the coordinates of the sector that contains the chunk at the given
+/// chunk coordinates.
+pub fn chunk_to_sector_coords(chunk_coords: IVec3) -> IVec3 {
+    chunk_coords >> 4
+}
+
+/// Gets the chunk coordinates and entity ids of all loaded chunks within the
+/// given sector of the given world.
+pub fn sector_chunks(
+    world: &World,
+    world_id: Entity,
+    sector_coords: IVec3,
+) -> Vec<(IVec3, Entity)> {
+    world
+        .get::<ChunkEntityPointers>(world_id)
+        .map(|pointers| pointers.iter_sector(sector_coords).collect())
+        .unwrap_or_default()
+}
+
+/// Despawns all loaded chunks within the given sector of the given world, along
+/// with their child entities, and returns the coordinates of each despawned
+/// chunk.
+///
+/// The chunk pointers of the despawned chunks are removed, and a
+/// [`ChunkDespawned`] event is sent for each of them. Modified chunks are not
+/// saved, so they should be saved using
+/// [`save_sector`](crate::persistence::save_sector) first.
+pub fn despawn_sector(world: &mut World, world_id: Entity, sector_coords: IVec3) -> Vec<IVec3> {
+    let chunks = sector_chunks(world, world_id, sector_coords);
+
+    for (_, chunk_id) in &chunks {
+        if world.get_entity(*chunk_id).is_some() {
+            despawn_with_children_recursive(world, *chunk_id);
+        }
+    }
+
+    if let Some(mut pointers) = world.get_mut::<ChunkEntityPointers>(world_id) {
+        for (chunk_coords, _) in &chunks {
+            pointers.set_chunk_entity(*chunk_coords, None);
+        }
+    }
+
+    if let Some(mut events) = world.get_resource_mut::<Events<ChunkDespawned>>() {
+        events.extend(chunks.iter().map(|(chunk_coords, _)| {
+            ChunkDespawned {
+                world_id,
+                chunk_coords: *chunk_coords,
+            }
+        }));
+    }
+
+    chunks
+        .into_iter()
+        .map(|(chunk_coords, _)| chunk_coords)
+        .collect()
+}
+
+/// Replaces the `VoxelStorage<T>` component of every loaded chunk within the
+/// given sector of the given world with a storage that is filled with the
+/// given block, and returns the number of chunks that were filled.
+///
+/// All filled chunks share a single copy of the block data until they are
+/// modified, so filling a sector costs about as much as filling one chunk. A
+/// [`ChunkStorageReplaced`](crate::storage::ChunkStorageReplaced) event is
+/// sent for each chunk that already had a storage component.
+pub fn fill_sector<T>(world: &mut World, world_id: Entity, sector_coords: IVec3, block: T) -> usize
+where
+    T: BlockData,
+{
+    let chunks = sector_chunks(world, world_id, sector_coords);
+    let storage = VoxelStorage::filled(block);
+
+    for (chunk_coords, chunk_id) in &chunks {
+        ReplaceStorageAction {
+            world_id,
+            chunk_id: *chunk_id,
+            chunk_coords: *chunk_coords,
+            storage: storage.clone(),
+        }
+        .apply(world);
+    }
+
+    chunks.len()
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn fill_save_and_despawn_sector() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        let persistent = PersistentWorld::new(MemoryChunkStore::default());
+        let world_id = app
+            .world
+            .spawn((VoxelWorldBundle::new(), persistent.clone()))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                let mut world = commands.get_world(world_id).unwrap();
+                for x in [0, 15, 16] {
+                    let storage = VoxelStorage::<u8>::default();
+                    world.spawn_chunk(IVec3::new(x, 0, 0), storage).unwrap();
+                }
+            })
+            .run(&mut app.world);
+        app.update();
+
+        assert_eq!(
+            chunk_to_sector_coords(IVec3::new(16, 0, -1)),
+            IVec3::new(1, 0, -1)
+        );
+        assert_eq!(sector_chunks(&app.world, world_id, IVec3::ZERO).len(), 2);
+
+        assert_eq!(fill_sector(&mut app.world, world_id, IVec3::ZERO, 3u8), 2);
+        for (_, chunk_id) in sector_chunks(&app.world, world_id, IVec3::ZERO) {
+            let storage = app.world.get::<VoxelStorage<u8>>(chunk_id).unwrap();
+            assert!(storage.is_shared());
+            assert_eq!(storage.uniform_block(), Some(3));
+            app.world.entity_mut(chunk_id).insert(DirtyChunk::default());
+        }
+
+        let report = save_sector::<u8>(&mut app.world, world_id, IVec3::ZERO);
+        assert_eq!(report.saved, 2);
+        let saved = persistent.load_chunk::<u8>(IVec3::new(15, 0, 0)).unwrap();
+        assert_eq!(saved.unwrap().uniform_block(), Some(3));
+        assert!(persistent
+            .load_chunk::<u8>(IVec3::new(16, 0, 0))
+            .unwrap()
+            .is_none());
+
+        let mut despawned = despawn_sector(&mut app.world, world_id, IVec3::ZERO);
+        despawned.sort_by_key(|c| c.x);
+        assert_eq!(despawned, vec![IVec3::ZERO, IVec3::new(15, 0, 0)]);
+        assert_eq!(app.world.resource::<Events<ChunkDespawned>>().len(), 2);
+
+        let remaining: Vec<IVec3> = app
+            .world
+            .get::<ChunkEntityPointers>(world_id)
+            .unwrap()
+            .iter()
+            .map(|(chunk_coords, _)| chunk_coords)
+            .collect();
+        assert_eq!(remaining, vec![IVec3::new(16, 0, 0)]);
+    }
+}
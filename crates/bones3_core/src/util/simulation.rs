@@ -0,0 +1,186 @@
+//! An optional lock-step schedule for running all voxel simulations on a fixed
+//! timestep.
+//!
+//! Gameplay systems that mutate blocks every frame run at a variable rate,
+//! which makes their outcome depend on the frame rate of each client. For
+//! deterministic simulations, such as within lock-step multiplayer games, all
+//! block mutations should instead be placed within the [`FixedUpdate`]
+//! schedule, using the [`VoxelSimulationSet`] system sets. Chunk meshing and
+//! rendering continue to run once per frame within `PostUpdate`, and only ever
+//! observe the state of the world after the most recent tick.
+
+use bevy::prelude::*;
+
+use crate::util::block_update::propagate_block_updates;
+
+/// This plugin configures the [`VoxelSimulationSet`] system sets within the
+/// [`FixedUpdate`] schedule, and tracks the [`VoxelSimulationClock`].
+///
+/// Voxel commands that are queued within [`VoxelSimulationSet::Tick`] or
+/// [`VoxelSimulationSet::Automata`] are applied before the tick ends, and
+/// neighbor notifications for all changed blocks are sent within the same
+/// tick, so every tick observes the complete result of the previous one.
+///
+/// Block scripts are moved onto the fixed timestep using
+/// `BlockScriptPlugin::with_fixed_timestep`, while the
+/// [`VoxelAutomatonPlugin`](crate::util::automaton::VoxelAutomatonPlugin)
+/// always runs on the fixed timestep.
+#[derive(Default)]
+pub struct VoxelSimulationPlugin;
+
+impl Plugin for VoxelSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VoxelSimulationClock>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    VoxelSimulationSet::Tick,
+                    VoxelSimulationSet::Automata,
+                    VoxelSimulationSet::Flush,
+                    VoxelSimulationSet::BlockUpdates,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_simulation_clock.before(VoxelSimulationSet::Tick),
+                    apply_deferred.in_set(VoxelSimulationSet::Flush),
+                    propagate_block_updates.in_set(VoxelSimulationSet::BlockUpdates),
+                ),
+            )
+            .add_systems(First, update_simulation_alpha);
+    }
+}
+
+/// The system sets that define the order of voxel mutations within the
+/// [`FixedUpdate`] schedule, when using the [`VoxelSimulationPlugin`].
+///
+/// These sets run in the order that they are listed.
+#[derive(Debug, SystemSet, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum VoxelSimulationSet {
+    /// This system set is used for gameplay systems and block ticks that
+    /// write block data.
+    Tick,
+
+    /// This system set is used for cellular automata, such as fluids, which
+    /// react to the blocks written during [`VoxelSimulationSet::Tick`].
+    Automata,
+
+    /// This system set applies all deferred voxel commands that were queued
+    /// during the current tick.
+    Flush,
+
+    /// This system set sends neighbor notifications for all blocks that were
+    /// changed during the current tick.
+    BlockUpdates,
+}
+
+/// A resource that tracks the progress of the fixed timestep voxel
+/// simulation.
+#[derive(Debug, Default, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct VoxelSimulationClock {
+    /// The number of simulation ticks that have been run.
+    tick: u64,
+
+    /// How far the current frame is between the most recent tick and the
+    /// next one, in the range `0.0 .. 1.0`.
+    alpha: f32,
+}
+
+impl VoxelSimulationClock {
+    /// Gets the number of simulation ticks that have been run.
+    ///
+    /// Within the [`FixedUpdate`] schedule, this is the index of the current
+    /// tick, starting at `1`.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Gets how far the current frame is between the most recent simulation
+    /// tick and the next one, in the range `0.0 .. 1.0`.
+    ///
+    /// This can be used to interpolate the visual state of simulated objects,
+    /// such as falling blocks, between ticks.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+}
+
+/// This system increments the simulation tick counter at the start of each
+/// fixed timestep.
+fn advance_simulation_clock(mut clock: ResMut<VoxelSimulationClock>) {
+    clock.tick += 1;
+}
+
+/// This system updates the interpolation factor of the simulation clock based
+/// off the time that has accumulated towards the next fixed timestep.
+fn update_simulation_alpha(
+    fixed_time: Option<Res<FixedTime>>,
+    mut clock: ResMut<VoxelSimulationClock>,
+) {
+    let Some(fixed_time) = fixed_time else {
+        return;
+    };
+
+    let period = fixed_time.period.as_secs_f32();
+    if period <= 0.0 {
+        return;
+    }
+
+    let alpha = (fixed_time.accumulated().as_secs_f32() / period).clamp(0.0, 1.0);
+    if clock.alpha != alpha {
+        clock.alpha = alpha;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::block_update::NeighborChangedEvent;
+
+    #[test]
+    fn flush_writes_within_tick() {
+        let mut app = App::new();
+        app.add_plugins((Bones3CorePlugin::<u8>::default(), VoxelSimulationPlugin));
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn write(
+            clock: Res<VoxelSimulationClock>,
+            world_query: Query<Entity, With<VoxelWorld>>,
+            mut commands: VoxelCommands,
+        ) {
+            let world_id = world_query.single();
+            let x = clock.tick() as i32;
+            commands
+                .get_world(world_id)
+                .unwrap()
+                .set_block(IVec3::new(x, 0, 0), 1u8);
+        }
+        app.add_systems(FixedUpdate, write.in_set(VoxelSimulationSet::Tick));
+
+        fn read(chunks: Query<&VoxelStorage<u8>>, clock: Res<VoxelSimulationClock>) {
+            let x = clock.tick() as i32;
+            assert_eq!(chunks.single().get_block(IVec3::new(x, 0, 0)), 1);
+        }
+        app.add_systems(FixedUpdate, read.after(VoxelSimulationSet::Flush));
+
+        app.world.run_schedule(FixedUpdate);
+        app.world.run_schedule(FixedUpdate);
+
+        assert_eq!(app.world.resource::<VoxelSimulationClock>().tick(), 2);
+        let events = app.world.resource::<Events<NeighborChangedEvent>>();
+        assert_eq!(events.len(), 12);
+    }
+}
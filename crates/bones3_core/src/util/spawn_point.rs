@@ -0,0 +1,330 @@
+//! Utilities for locating standable positions on the surface of a voxel world,
+//! such as player spawn points.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use super::heightmap::WorldHeightmap;
+use crate::prelude::{BlockData, VoxelQuery, VoxelStorage};
+
+/// This plugin resolves all [`FindSpawnPoint`] requests for the given block
+/// data type, sending a [`SpawnPointFound`] event once a position has been
+/// found. Requests are retried each frame until the chunks they need are
+/// loaded.
+///
+/// This plugin requires the
+/// [`HeightmapPlugin`](super::heightmap::HeightmapPlugin) for the same block
+/// data type.
+#[derive(Default)]
+pub struct SpawnPointPlugin<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for SpawnPointPlugin<T>
+where
+    T: BlockData + PartialEq,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnPointFound>()
+            .add_systems(Update, resolve_spawn_points::<T>);
+    }
+}
+
+/// The requirements for a position to be considered a safe spawn point.
+pub struct SpawnRequirements<T>
+where
+    T: BlockData,
+{
+    /// Checks whether the given block can be stood upon.
+    pub is_ground: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// Checks whether the given block can be occupied.
+    pub is_passable: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// The number of passable blocks that are required above the ground.
+    pub clearance: u32,
+
+    /// The maximum distance, in blocks, to search from the requested position
+    /// along each horizontal axis.
+    pub search_radius: u32,
+}
+
+impl<T> Clone for SpawnRequirements<T>
+where
+    T: BlockData,
+{
+    fn clone(&self) -> Self {
+        Self {
+            is_ground:     self.is_ground.clone(),
+            is_passable:   self.is_passable.clone(),
+            clearance:     self.clearance,
+            search_radius: self.search_radius,
+        }
+    }
+}
+
+impl<T> Default for SpawnRequirements<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Any non-default block is considered to be ground, only default blocks
+    /// are passable, and two blocks of clearance are required.
+    fn default() -> Self {
+        Self {
+            is_ground:     Arc::new(|block| block != T::default()),
+            is_passable:   Arc::new(|block| block == T::default()),
+            clearance:     2,
+            search_radius: 16,
+        }
+    }
+}
+
+/// The result of searching for a position within a voxel world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpawnSearch {
+    /// A position was found. This is the block coordinates of the first
+    /// passable block above the ground.
+    Found(IVec3),
+
+    /// No position has been found yet, but the search touched chunk columns
+    /// that are not loaded. The search should be retried after they load.
+    Pending(Vec<IVec2>),
+
+    /// No position exists within the search area.
+    NotFound,
+}
+
+/// Finds the highest position within the given block column where the block
+/// below matches the given predicate.
+///
+/// Blocks are read using the `get_block` function, which should return `None`
+/// for blocks within chunks that are not loaded.
+pub fn find_surface_position<T, G, P>(
+    get_block: G,
+    heightmap: &WorldHeightmap<T>,
+    column: IVec2,
+    predicate: P,
+) -> SpawnSearch
+where
+    T: BlockData + PartialEq,
+    G: Fn(IVec3) -> Option<T>,
+    P: Fn(IVec3, T) -> bool,
+{
+    if !heightmap.has_chunk_column(column >> 4) {
+        return SpawnSearch::Pending(vec![column >> 4]);
+    }
+
+    let Some(top) = heightmap.get_height(column) else {
+        return SpawnSearch::NotFound;
+    };
+
+    let up = heightmap.up_axis();
+    let mut height = top;
+    loop {
+        let block_coords = up.compose(column, height);
+        let Some(block) = get_block(block_coords) else {
+            return SpawnSearch::NotFound;
+        };
+
+        if predicate(block_coords, block) {
+            return SpawnSearch::Found(up.compose(column, height + 1));
+        }
+
+        height -= 1;
+    }
+}
+
+/// Finds the closest safe spawn position to the given block coordinates that
+/// satisfies the given requirements.
+///
+/// Columns are checked in order of their horizontal distance from the given
+/// position. Blocks are read using the `get_block` function, which should
+/// return `None` for blocks within chunks that are not loaded. Columns within
+/// chunk columns that are not loaded are skipped, and only reported as
+/// pending if no position could be found elsewhere.
+pub fn find_safe_spawn<T, G>(
+    get_block: G,
+    heightmap: &WorldHeightmap<T>,
+    near: IVec3,
+    requirements: &SpawnRequirements<T>,
+) -> SpawnSearch
+where
+    T: BlockData + PartialEq,
+    G: Fn(IVec3) -> Option<T>,
+{
+    let up = heightmap.up_axis();
+    let center = up.column(near);
+    let radius = requirements.search_radius as i32;
+
+    let mut columns: Vec<IVec2> = (-radius ..= radius)
+        .flat_map(|x| (-radius ..= radius).map(move |y| center + IVec2::new(x, y)))
+        .collect();
+    columns.sort_by_key(|c| (*c - center).length_squared());
+
+    let mut missing = vec![];
+    for column in columns {
+        let search = find_surface_position(&get_block, heightmap, column, |coords, block| {
+            if !(requirements.is_ground)(block) {
+                return false;
+            }
+
+            let height = up.height(coords);
+            (1 ..= requirements.clearance as i32).all(|offset| {
+                get_block(up.compose(column, height + offset))
+                    .is_some_and(|b| (requirements.is_passable)(b))
+            })
+        });
+
+        match search {
+            SpawnSearch::Found(pos) => return SpawnSearch::Found(pos),
+            SpawnSearch::Pending(chunks) => {
+                for chunk_column in chunks {
+                    if !missing.contains(&chunk_column) {
+                        missing.push(chunk_column);
+                    }
+                }
+            },
+            SpawnSearch::NotFound => {},
+        }
+    }
+
+    if missing.is_empty() {
+        SpawnSearch::NotFound
+    } else {
+        SpawnSearch::Pending(missing)
+    }
+}
+
+/// A component that requests a safe spawn position to be found near the given
+/// coordinates.
+///
+/// This component is removed once the request is resolved, and a
+/// [`SpawnPointFound`] event is sent.
+#[derive(Component)]
+pub struct FindSpawnPoint<T>
+where
+    T: BlockData,
+{
+    /// The id of the world to search in.
+    pub world_id: Entity,
+
+    /// The block coordinates to search near.
+    pub near: IVec3,
+
+    /// The requirements for a safe spawn position.
+    pub requirements: SpawnRequirements<T>,
+}
+
+/// An event that is sent when a [`FindSpawnPoint`] request has been resolved.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpawnPointFound {
+    /// The entity that requested the spawn point.
+    pub entity: Entity,
+
+    /// The id of the world that was searched.
+    pub world_id: Entity,
+
+    /// The spawn position that was found, or `None` if no safe position exists
+    /// within the search area.
+    pub position: Option<IVec3>,
+}
+
+/// This system attempts to resolve all pending spawn point requests.
+fn resolve_spawn_points<T>(
+    requests: Query<(Entity, &FindSpawnPoint<T>)>,
+    heightmaps: Query<&WorldHeightmap<T>>,
+    chunks: VoxelQuery<&VoxelStorage<T>>,
+    mut events: EventWriter<SpawnPointFound>,
+    mut commands: Commands,
+) where
+    T: BlockData + PartialEq,
+{
+    for (entity, request) in requests.iter() {
+        let (Ok(heightmap), Ok(world)) = (
+            heightmaps.get(request.world_id),
+            chunks.get_world(request.world_id),
+        ) else {
+            continue;
+        };
+
+        let get_block = |block_coords: IVec3| {
+            world
+                .get_chunk(block_coords >> 4)
+                .map(|storage| storage.get_block(block_coords))
+        };
+
+        let position =
+            match find_safe_spawn(get_block, heightmap, request.near, &request.requirements) {
+                SpawnSearch::Found(pos) => Some(pos),
+                SpawnSearch::Pending(_) => continue,
+                SpawnSearch::NotFound => None,
+            };
+
+        commands.entity(entity).remove::<FindSpawnPoint<T>>();
+        events.send(SpawnPointFound {
+            entity,
+            world_id: request.world_id,
+            position,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::heightmap::HeightmapPlugin;
+
+    #[test]
+    fn spawn_on_surface() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            HeightmapPlugin::<u8>::default(),
+            SpawnPointPlugin::<u8>::default(),
+        ));
+
+        fn init(mut commands: VoxelCommands) {
+            let mut storage = VoxelStorage::<u8>::default();
+            for x in 0 .. 16 {
+                for z in 0 .. 16 {
+                    storage.set_block(IVec3::new(x, 3, z), 1);
+                }
+            }
+
+            // The top of this column has no clearance, as the chunk above it is
+            // not loaded.
+            storage.set_block(IVec3::new(8, 15, 8), 1);
+
+            let world_id = commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, storage)
+                .unwrap()
+                .world_id();
+
+            commands.commands().spawn(FindSpawnPoint::<u8> {
+                world_id,
+                near: IVec3::new(8, 0, 8),
+                requirements: SpawnRequirements::default(),
+            });
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        // The heightmap is built at the end of the first frame.
+        app.update();
+        app.update();
+
+        let events = app.world.resource::<Events<SpawnPointFound>>();
+        let found: Vec<_> = events.iter_current_update_events().collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].position, Some(IVec3::new(8, 4, 8)));
+    }
+}
@@ -0,0 +1,248 @@
+//! Aggregated statistics about the voxel worlds within the app, such as how
+//! many chunks are being loaded and meshed each second.
+//!
+//! The statistics are gathered by the Bones Cubed plugins from within their
+//! own systems, and are published periodically as a [`Bones3Stats`] resource
+//! and event, which games may log or graph.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::storage::{ChunkDespawned, VoxelChunk};
+use crate::util::pinned::{is_chunk_pinned, PinnedChunks};
+
+/// This plugin publishes a [`Bones3Stats`] resource and event at a fixed
+/// interval, summarizing the chunk activity within that interval.
+#[derive(Debug, Clone, Copy)]
+pub struct Bones3StatsPlugin {
+    /// The length of each statistics window.
+    ///
+    /// Defaults to one second.
+    pub interval: Duration,
+}
+
+impl Default for Bones3StatsPlugin {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Plugin for Bones3StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Bones3Stats>()
+            .init_resource::<Time>()
+            .init_resource::<Bones3Stats>()
+            .insert_resource(Bones3StatsCollector::new(self.interval))
+            .add_event::<Bones3Stats>()
+            .add_systems(Last, (count_loaded_chunks, publish_stats).chain());
+    }
+}
+
+/// A summary of the chunk activity within the most recent statistics window.
+///
+/// This is available as a resource, and is also sent as an event each time a
+/// window is completed.
+#[derive(Debug, Default, Resource, Event, Reflect, Clone, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct Bones3Stats {
+    /// The length of the window that these statistics cover.
+    pub window: Duration,
+
+    /// The number of chunks that were spawned.
+    pub chunks_loaded: u32,
+
+    /// The number of chunks that were despawned.
+    pub chunks_unloaded: u32,
+
+    /// The number of chunks that were generated by a world generator.
+    pub chunks_generated: u32,
+
+    /// The number of chunks that were meshed.
+    pub chunks_meshed: u32,
+
+    /// The number of loaded chunks that are pinned by the [`PinnedChunks`] of
+    /// their world, at the end of the window.
+    pub pinned_chunks: u32,
+
+    /// The average time that it took to generate a single chunk, or zero if
+    /// no chunks were generated.
+    pub avg_gen_time: Duration,
+
+    /// The average time that it took to mesh a single chunk, or zero if no
+    /// chunks were meshed.
+    pub avg_mesh_time: Duration,
+}
+
+/// This resource collects the chunk activity within the current statistics
+/// window.
+///
+/// Plugins record their activity within this resource, if it exists, and it
+/// is summarized into a [`Bones3Stats`] once the window has passed.
+#[derive(Debug, Resource)]
+pub struct Bones3StatsCollector {
+    /// The length of each statistics window.
+    interval: Duration,
+
+    /// The time that has passed within the current window.
+    elapsed: Duration,
+
+    /// The statistics gathered within the current window, so far.
+    current: Bones3Stats,
+
+    /// The total time spent generating chunks within the current window.
+    gen_time: Duration,
+
+    /// The total time spent meshing chunks within the current window.
+    mesh_time: Duration,
+}
+
+impl Bones3StatsCollector {
+    /// Creates a new, empty statistics collector with the given window length.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            elapsed: Duration::ZERO,
+            current: Bones3Stats::default(),
+            gen_time: Duration::ZERO,
+            mesh_time: Duration::ZERO,
+        }
+    }
+
+    /// Records that the given number of chunks were spawned.
+    pub fn record_chunks_loaded(&mut self, count: u32) {
+        self.current.chunks_loaded += count;
+    }
+
+    /// Records that the given number of chunks were despawned.
+    pub fn record_chunks_unloaded(&mut self, count: u32) {
+        self.current.chunks_unloaded += count;
+    }
+
+    /// Records that a chunk was generated, taking the given amount of time.
+    pub fn record_chunk_generated(&mut self, duration: Duration) {
+        self.current.chunks_generated += 1;
+        self.gen_time += duration;
+    }
+
+    /// Records that a chunk was meshed, taking the given amount of time.
+    pub fn record_chunk_meshed(&mut self, duration: Duration) {
+        self.current.chunks_meshed += 1;
+        self.mesh_time += duration;
+    }
+
+    /// Advances the current window by the given amount of time, returning the
+    /// statistics of the window if it has been completed.
+    fn tick(&mut self, delta: Duration) -> Option<Bones3Stats> {
+        self.elapsed += delta;
+        if self.elapsed < self.interval {
+            return None;
+        }
+
+        let mut stats = std::mem::take(&mut self.current);
+        stats.window = std::mem::take(&mut self.elapsed);
+        stats.avg_gen_time = average(self.gen_time, stats.chunks_generated);
+        stats.avg_mesh_time = average(self.mesh_time, stats.chunks_meshed);
+        self.gen_time = Duration::ZERO;
+        self.mesh_time = Duration::ZERO;
+
+        Some(stats)
+    }
+}
+
+/// Gets the average of the given total time over the given number of samples.
+fn average(total: Duration, count: u32) -> Duration {
+    if count == 0 {
+        Duration::ZERO
+    } else {
+        total / count
+    }
+}
+
+/// This system records all chunks that were spawned or despawned since the
+/// last frame.
+fn count_loaded_chunks(
+    added: Query<(), Added<VoxelChunk>>,
+    mut despawned: EventReader<ChunkDespawned>,
+    mut collector: ResMut<Bones3StatsCollector>,
+) {
+    collector.record_chunks_loaded(added.iter().count() as u32);
+    collector.record_chunks_unloaded(despawned.iter().len() as u32);
+}
+
+/// This system advances the current statistics window, and publishes the
+/// statistics once the window has been completed.
+fn publish_stats(
+    time: Res<Time>,
+    chunks: Query<&VoxelChunk>,
+    pinned: Query<&PinnedChunks>,
+    mut collector: ResMut<Bones3StatsCollector>,
+    mut stats: ResMut<Bones3Stats>,
+    mut events: EventWriter<Bones3Stats>,
+) {
+    if let Some(mut completed) = collector.tick(time.delta()) {
+        if !pinned.is_empty() {
+            completed.pinned_chunks = chunks
+                .iter()
+                .filter(|chunk_meta| is_chunk_pinned(&pinned, chunk_meta))
+                .count() as u32;
+        }
+
+        *stats = completed.clone();
+        events.send(completed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn publish_stats_each_interval() {
+        let mut app = App::new();
+        app.add_plugins((Bones3CorePlugin::<u8>::default(), Bones3StatsPlugin {
+            interval: Duration::from_millis(500),
+        }));
+
+        let pinned = PinnedChunks::default().with_region(Region::from_points(
+            IVec3::new(1, 0, 0),
+            IVec3::new(4, 0, 0),
+        ));
+        let world_id = app.world.spawn((VoxelWorldBundle::new(), pinned)).id();
+        for x in 0 .. 3 {
+            app.world
+                .spawn(VoxelChunkBundle::<u8>::new(world_id, IVec3::new(x, 0, 0)));
+        }
+
+        let mut collector = app.world.resource_mut::<Bones3StatsCollector>();
+        collector.record_chunk_generated(Duration::from_millis(4));
+        collector.record_chunk_generated(Duration::from_millis(8));
+
+        let start = app.world.resource::<Time>().startup();
+        let mut frame = 0;
+        let mut step = |app: &mut App| {
+            frame += 1;
+            let now = start + Duration::from_millis(300 * frame);
+            app.world.resource_mut::<Time>().update_with_instant(now);
+            app.update();
+        };
+
+        step(&mut app);
+        step(&mut app);
+        assert_eq!(app.world.resource::<Bones3Stats>(), &Bones3Stats::default());
+
+        step(&mut app);
+        let stats = app.world.resource::<Bones3Stats>();
+        assert_eq!(stats.window, Duration::from_millis(600));
+        assert_eq!(stats.chunks_loaded, 3);
+        assert_eq!(stats.pinned_chunks, 2);
+        assert_eq!(stats.chunks_generated, 2);
+        assert_eq!(stats.avg_gen_time, Duration::from_millis(6));
+        assert_eq!(app.world.resource::<Events<Bones3Stats>>().len(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! Timing measurements of per-chunk work, such as generating or meshing
+//! chunks, and task budgets that adapt to them.
+//!
+//! The time it takes to process a single chunk can vary widely. A chunk deep
+//! within a cave system may take ten times longer to generate than a chunk of
+//! empty sky, so a fixed number of chunks per frame is either too slow or
+//! causes frame spikes. A [`TaskBudget`] instead derives the number of chunks
+//! to process from a moving average of recent [`ChunkTimings`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// The number of buckets within a [`ChunkTimings`] histogram.
+pub const TIMING_BUCKETS: usize = 16;
+
+/// The upper bound of the first histogram bucket. Each following bucket
+/// doubles this bound.
+const FIRST_BUCKET_BOUND: Duration = Duration::from_micros(125);
+
+/// A moving average and histogram of the time it took to process individual
+/// chunks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkTimings {
+    /// The weight of each new sample within the moving average, from `0.0` to
+    /// `1.0`.
+    smoothing: f32,
+
+    /// The moving average of all samples, if any samples were recorded.
+    average: Option<Duration>,
+
+    /// The longest sample that was recorded.
+    max: Duration,
+
+    /// The total number of samples that were recorded.
+    samples: u64,
+
+    /// The number of samples within each bucket.
+    histogram: [u64; TIMING_BUCKETS],
+}
+
+impl Default for ChunkTimings {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl ChunkTimings {
+    /// Creates a new, empty set of chunk timings, where each new sample has the
+    /// given weight within the moving average.
+    pub fn new(smoothing: f32) -> Self {
+        Self {
+            smoothing: smoothing.clamp(0.0, 1.0),
+            average:   None,
+            max:       Duration::ZERO,
+            samples:   0,
+            histogram: [0; TIMING_BUCKETS],
+        }
+    }
+
+    /// Records the time it took to process a single chunk.
+    pub fn record(&mut self, duration: Duration) {
+        self.average = Some(match self.average {
+            Some(average) => {
+                average.mul_f32(1.0 - self.smoothing) + duration.mul_f32(self.smoothing)
+            },
+            None => duration,
+        });
+
+        self.max = self.max.max(duration);
+        self.samples += 1;
+        self.histogram[bucket_index(duration)] += 1;
+    }
+
+    /// Gets the moving average of the recorded samples, or `None` if no
+    /// samples have been recorded yet.
+    pub fn average(&self) -> Option<Duration> {
+        self.average
+    }
+
+    /// Gets the longest sample that was recorded.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Gets the total number of samples that were recorded.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Gets an iterator over all histogram buckets, as the upper bound of each
+    /// bucket along with the number of samples within it.
+    ///
+    /// The last bucket has no upper bound, and is given as `Duration::MAX`.
+    pub fn histogram(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.histogram
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (bucket_bound(index), *count))
+    }
+
+    /// Gets an estimate of the given percentile, from `0.0` to `1.0`, of all
+    /// recorded samples, as the upper bound of the histogram bucket that
+    /// contains it. Returns `None` if no samples have been recorded.
+    pub fn percentile(&self, percentile: f32) -> Option<Duration> {
+        if self.samples == 0 {
+            return None;
+        }
+
+        let target = ((self.samples as f64) * percentile.clamp(0.0, 1.0) as f64).ceil() as u64;
+        let mut seen = 0;
+        for (bound, count) in self.histogram() {
+            seen += count;
+            if seen >= target.max(1) {
+                return Some(bound.min(self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    /// Removes all recorded samples.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.smoothing);
+    }
+}
+
+/// Gets the index of the histogram bucket that contains the given duration.
+fn bucket_index(duration: Duration) -> usize {
+    let mut bound = FIRST_BUCKET_BOUND;
+    let mut index = 0;
+    while duration > bound && index < TIMING_BUCKETS - 1 {
+        bound *= 2;
+        index += 1;
+    }
+    index
+}
+
+/// Gets the upper bound of the histogram bucket with the given index.
+fn bucket_bound(index: usize) -> Duration {
+    match index {
+        i if i >= TIMING_BUCKETS - 1 => Duration::MAX,
+        i => FIRST_BUCKET_BOUND * (1 << i),
+    }
+}
+
+/// A per-frame budget for processing chunks, which adapts the number of chunks
+/// to the average time it takes to process one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct TaskBudget {
+    /// The total amount of processing time that the chunks should take.
+    pub target_time: Duration,
+
+    /// The minimum number of chunks, which is always allowed regardless of how
+    /// long chunks take.
+    pub min_tasks: usize,
+
+    /// The maximum number of chunks, regardless of how quickly chunks are
+    /// processed.
+    pub max_tasks: usize,
+
+    /// The number of chunks to use before any timings have been recorded.
+    pub initial_tasks: usize,
+}
+
+impl TaskBudget {
+    /// Gets the number of chunks that fit within this budget, based off the
+    /// average time within the given chunk timings.
+    pub fn tasks(&self, timings: &ChunkTimings) -> usize {
+        let tasks = match timings.average() {
+            Some(average) if !average.is_zero() => {
+                (self.target_time.as_secs_f64() / average.as_secs_f64()).floor() as usize
+            },
+            Some(_) => self.max_tasks,
+            None => self.initial_tasks,
+        };
+
+        tasks.clamp(self.min_tasks, self.max_tasks.max(self.min_tasks))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn adapt_budget_to_timings() {
+        let budget = TaskBudget {
+            target_time:   Duration::from_millis(10),
+            min_tasks:     1,
+            max_tasks:     8,
+            initial_tasks: 3,
+        };
+
+        let mut timings = ChunkTimings::new(0.5);
+        assert_eq!(budget.tasks(&timings), 3);
+        assert_eq!(timings.percentile(0.5), None);
+
+        timings.record(Duration::from_millis(2));
+        assert_eq!(timings.average(), Some(Duration::from_millis(2)));
+        assert_eq!(budget.tasks(&timings), 5);
+
+        // A few slow chunks quickly lower the budget.
+        timings.record(Duration::from_millis(20));
+        timings.record(Duration::from_millis(20));
+        assert_eq!(timings.average(), Some(Duration::from_micros(15_500)));
+        assert_eq!(budget.tasks(&timings), 1);
+
+        for _ in 0 .. 10 {
+            timings.record(Duration::from_micros(100));
+        }
+        assert_eq!(budget.tasks(&timings), 8);
+
+        assert_eq!(timings.samples(), 13);
+        assert_eq!(timings.max(), Duration::from_millis(20));
+        assert_eq!(timings.percentile(0.5), Some(Duration::from_micros(125)));
+        assert_eq!(timings.percentile(1.0), Some(Duration::from_millis(20)));
+        assert_eq!(timings.histogram().map(|(_, c)| c).sum::<u64>(), 13);
+    }
+}
@@ -0,0 +1,266 @@
+//! Transactional batches of block writes and chunk spawns, which are either
+//! applied in full or rolled back.
+
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+
+use crate::query::VoxelQueryError;
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::template::apply_chunk_template;
+use crate::storage::{bump_chunk_version, BlockData, VoxelChunk, VoxelStorage};
+use crate::util::block_update::BlockUpdateQueue;
+use crate::util::brush::{BlockChange, VoxelEdit, VoxelEditHistory};
+
+/// A batch of block writes and chunk spawns within a single voxel world that
+/// is applied directly to the Bevy world.
+///
+/// Every change made through a transaction is recorded, so that all of them can
+/// be rolled back if any step of the transaction fails. Block updates are only
+/// queued once the transaction has been committed.
+pub struct VoxelTransaction<'w, T>
+where
+    T: BlockData,
+{
+    /// The Bevy world that is being edited.
+    world: &'w mut World,
+
+    /// The id of the voxel world that is being edited.
+    world_id: Entity,
+
+    /// The blocks that have been changed so far, in order.
+    changes: Vec<BlockChange<T>>,
+
+    /// The chunks that have been spawned so far, in order.
+    spawned: Vec<(IVec3, Entity)>,
+}
+
+impl<'w, T> VoxelTransaction<'w, T>
+where
+    T: BlockData,
+{
+    /// Gets the id of the voxel world that is being edited.
+    pub fn world_id(&self) -> Entity {
+        self.world_id
+    }
+
+    /// Gets the chunk pointers of the voxel world that is being edited.
+    fn pointers(&self) -> &ChunkEntityPointers {
+        self.world
+            .get::<ChunkEntityPointers>(self.world_id)
+            .unwrap()
+    }
+
+    /// Gets the id and storage of the chunk that contains the given block.
+    fn find_chunk(&self, block_coords: IVec3) -> Result<(Entity, IVec3), VoxelQueryError> {
+        let pointers = self.pointers();
+        let block_coords = pointers.topology().wrap_block_coords(block_coords);
+        let chunk_coords = block_coords >> 4;
+        let chunk_id = pointers
+            .get_chunk_entity(chunk_coords)
+            .filter(|chunk_id| self.world.get::<VoxelStorage<T>>(*chunk_id).is_some())
+            .ok_or(VoxelQueryError::ChunkNotFound(self.world_id, chunk_coords))?;
+        Ok((chunk_id, block_coords))
+    }
+
+    /// Gets the current value of the block at the given block coordinates.
+    ///
+    /// Returns an error if the chunk containing the block does not exist, or
+    /// does not have a storage component for `T`.
+    pub fn get_block(&self, block_coords: IVec3) -> Result<T, VoxelQueryError> {
+        let (chunk_id, block_coords) = self.find_chunk(block_coords)?;
+        let storage = self.world.get::<VoxelStorage<T>>(chunk_id).unwrap();
+        Ok(storage.get_block(block_coords))
+    }
+
+    /// Sets the value of the block at the given block coordinates.
+    ///
+    /// Returns an error if the chunk containing the block does not exist, or
+    /// does not have a storage component for `T`.
+    pub fn set_block(&mut self, block_coords: IVec3, data: T) -> Result<(), VoxelQueryError> {
+        let (chunk_id, block_coords) = self.find_chunk(block_coords)?;
+        let mut storage = self.world.get_mut::<VoxelStorage<T>>(chunk_id).unwrap();
+        let old = storage.get_block(block_coords);
+        storage.set_block(block_coords, data);
+        bump_chunk_version::<T>(self.world, chunk_id);
+
+        self.changes.push(BlockChange {
+            block_coords,
+            old,
+            new: data,
+        });
+        Ok(())
+    }
+
+    /// Spawns a new, empty chunk at the given chunk coordinates with the
+    /// given component bundle attached, along with all components from the
+    /// [`ChunkTemplate`](crate::storage::ChunkTemplate) resource.
+    ///
+    /// The new chunk is given an empty storage component for `T`, so that
+    /// blocks may be written to it within the same transaction, unless the
+    /// bundle already contains one.
+    ///
+    /// Returns an error if there is already a chunk at the given coordinates.
+    pub fn spawn_chunk<B>(
+        &mut self,
+        chunk_coords: IVec3,
+        bundle: B,
+    ) -> Result<Entity, VoxelQueryError>
+    where
+        B: Bundle,
+    {
+        let pointers = self.pointers();
+        let chunk_coords = pointers.topology().wrap_chunk_coords(chunk_coords);
+        if pointers.get_chunk_entity(chunk_coords).is_some() {
+            return Err(VoxelQueryError::ChunkAlreadyExists(
+                self.world_id,
+                chunk_coords,
+            ));
+        }
+
+        let chunk_id = self
+            .world
+            .spawn((
+                VoxelChunk::new(self.world_id, chunk_coords),
+                VoxelStorage::<T>::default(),
+            ))
+            .insert(bundle)
+            .id();
+
+        self.world
+            .entity_mut(self.world_id)
+            .push_children(&[chunk_id]);
+        apply_chunk_template(self.world, &[chunk_id]);
+
+        self.world
+            .get_mut::<ChunkEntityPointers>(self.world_id)
+            .unwrap()
+            .set_chunk_entity(chunk_coords, Some(chunk_id));
+
+        self.spawned.push((chunk_coords, chunk_id));
+        Ok(chunk_id)
+    }
+
+    /// Commits all changes made by this transaction, queueing block updates
+    /// for all changed blocks and recording the edit within the edit history.
+    fn commit(self) -> VoxelEdit<T> {
+        if let Some(mut queue) = self.world.get_resource_mut::<BlockUpdateQueue>() {
+            for change in self.changes.iter() {
+                queue.push(self.world_id, change.block_coords);
+            }
+        }
+
+        let edit = VoxelEdit {
+            world_id: self.world_id,
+            changes:  self.changes,
+        };
+
+        if !edit.is_empty() {
+            if let Some(mut history) = self.world.get_resource_mut::<VoxelEditHistory<T>>() {
+                history.push(edit.clone());
+            }
+        }
+
+        edit
+    }
+
+    /// Reverts all changes made by this transaction, in reverse order.
+    fn rollback(self) {
+        for change in self.changes.iter().rev() {
+            let Ok((chunk_id, _)) = self.find_chunk(change.block_coords) else {
+                continue;
+            };
+
+            let mut storage = self.world.get_mut::<VoxelStorage<T>>(chunk_id).unwrap();
+            storage.set_block(change.block_coords, change.old);
+            bump_chunk_version::<T>(self.world, chunk_id);
+        }
+
+        for (chunk_coords, chunk_id) in self.spawned.into_iter().rev() {
+            despawn_with_children_recursive(self.world, chunk_id);
+            self.world
+                .get_mut::<ChunkEntityPointers>(self.world_id)
+                .unwrap()
+                .set_chunk_entity(chunk_coords, None);
+        }
+    }
+}
+
+/// Runs the given function as a transaction within the given voxel world.
+///
+/// If the function returns an error, every block write and chunk spawn made
+/// by the transaction is rolled back, and the error is returned. Otherwise,
+/// block updates are queued for all changed blocks, and the resulting edit is
+/// returned. If a [`VoxelEditHistory`] exists for `T` and the edit changed any
+/// blocks, the edit is also recorded within it so it can be undone.
+///
+/// Chunk spawns are not recorded within the edit history.
+pub fn run_transaction<T, F>(
+    world: &mut World,
+    world_id: Entity,
+    build: F,
+) -> Result<VoxelEdit<T>, VoxelQueryError>
+where
+    T: BlockData,
+    F: FnOnce(&mut VoxelTransaction<T>) -> Result<(), VoxelQueryError>,
+{
+    if world.get::<ChunkEntityPointers>(world_id).is_none() {
+        return Err(VoxelQueryError::WorldNotFound(world_id));
+    }
+
+    let mut tx = VoxelTransaction {
+        world,
+        world_id,
+        changes: vec![],
+        spawned: vec![],
+    };
+
+    if let Err(err) = build(&mut tx) {
+        tx.rollback();
+        return Err(err);
+    }
+
+    Ok(tx.commit())
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn rollback_failed_transaction() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        let world_id = app
+            .world
+            .spawn((VoxelWorld, ChunkEntityPointers::default()))
+            .id();
+
+        let result = run_transaction::<u8, _>(&mut app.world, world_id, |tx| {
+            tx.spawn_chunk(IVec3::ZERO, ())?;
+            tx.set_block(IVec3::new(1, 2, 3), 5)?;
+            tx.set_block(IVec3::new(20, 0, 0), 5)
+        });
+
+        assert!(matches!(
+            result,
+            Err(VoxelQueryError::ChunkNotFound(_, coords)) if coords == IVec3::X
+        ));
+        assert_eq!(app.world.query::<&VoxelChunk>().iter(&app.world).count(), 0);
+
+        let edit = run_transaction::<u8, _>(&mut app.world, world_id, |tx| {
+            tx.spawn_chunk(IVec3::ZERO, ())?;
+            tx.set_block(IVec3::new(1, 2, 3), 5)?;
+            assert_eq!(tx.get_block(IVec3::new(1, 2, 3))?, 5);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(edit.changes.len(), 1);
+        let storage = app.world.query::<&VoxelStorage<u8>>().single(&app.world);
+        assert_eq!(storage.get_block(IVec3::new(1, 2, 3)), 5);
+    }
+}
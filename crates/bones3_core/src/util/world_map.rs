@@ -0,0 +1,346 @@
+//! An exporter for rendering a top-down color map of a voxel world to a PNG
+//! image, for debugging world generators or creating world maps.
+//!
+//! Encoding world maps as PNG images requires the `png_export` feature.
+
+#[cfg(feature = "png_export")]
+use std::io;
+#[cfg(feature = "png_export")]
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use thiserror::Error;
+
+use super::minimap::{top_most_block, MinimapBlock};
+use crate::prelude::{UpAxis, VoxelStorage};
+
+/// The maximum number of pixels that a single world map image may contain.
+///
+/// This is equal to a square image of 4096 by 4096 pixels, or 256 by 256
+/// chunks. Each pixel stores both a color and a height, so an image of this
+/// size already takes up roughly 200 MB of memory.
+pub const MAX_WORLD_MAP_PIXELS: u64 = 4096 * 4096;
+
+/// An error that may occur when rendering a world map image.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WorldMapError {
+    /// Thrown when the area covered by the provided chunks would produce an
+    /// image with more than [`MAX_WORLD_MAP_PIXELS`] pixels.
+    #[error(
+        "World map of {width}x{height} pixels exceeds the limit of {MAX_WORLD_MAP_PIXELS} pixels"
+    )]
+    TooLarge {
+        /// The width the image would have had, in pixels.
+        width: u64,
+
+        /// The height the image would have had, in pixels.
+        height: u64,
+    },
+
+    /// Thrown when the block coordinates of a chunk column cannot be
+    /// represented.
+    #[error("Chunk column {0} is out of range")]
+    OutOfRange(IVec2),
+}
+
+/// A top-down image of a voxel world, where each pixel represents the top-most
+/// non-empty block of a single block column.
+///
+/// The image is projected along the world's [`UpAxis`], with the image x and y
+/// axes following the column axes described by [`UpAxis::column`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldMapImage {
+    /// The column coordinates of the top-left pixel of this image.
+    origin: IVec2,
+
+    /// The width of this image, along the x axis.
+    width: usize,
+
+    /// The height of this image, along the z axis.
+    height: usize,
+
+    /// The RGBA color of each pixel, stored in row-major order.
+    colors: Vec<[u8; 4]>,
+
+    /// The height of the top-most block of each pixel along the up axis,
+    /// stored in row-major order.
+    heights: Vec<Option<i32>>,
+}
+
+impl WorldMapImage {
+    /// Renders a new world map image from the given list of chunk coordinates
+    /// and their block storage, looking down along the given up axis.
+    ///
+    /// The image covers the smallest area that contains all provided chunks.
+    /// The color function converts the minimap cell value of each top-most
+    /// block into an RGBA color. Block columns without any non-empty blocks
+    /// are left fully transparent.
+    ///
+    /// Returns an error if the image would contain more than
+    /// [`MAX_WORLD_MAP_PIXELS`] pixels, such as when the chunks are spread far
+    /// apart. Large worlds should be rendered in several smaller images.
+    pub fn from_chunks<'a, T, I, F>(chunks: I, up: UpAxis, color: F) -> Result<Self, WorldMapError>
+    where
+        T: MinimapBlock,
+        I: IntoIterator<Item = (IVec3, &'a VoxelStorage<T>)>,
+        F: Fn(T::Cell) -> [u8; 4],
+    {
+        let mut columns: HashMap<IVec2, Vec<(i32, &VoxelStorage<T>)>> = HashMap::new();
+        for (chunk_coords, storage) in chunks {
+            columns
+                .entry(up.column(chunk_coords))
+                .or_default()
+                .push((up.height(chunk_coords), storage));
+        }
+
+        if columns.is_empty() {
+            return Ok(Self {
+                origin:  IVec2::ZERO,
+                width:   0,
+                height:  0,
+                colors:  vec![],
+                heights: vec![],
+            });
+        }
+
+        let min = columns.keys().copied().reduce(IVec2::min).unwrap();
+        let max = columns.keys().copied().reduce(IVec2::max).unwrap();
+        let width = (max.x as i64 - min.x as i64 + 1) as u64 * 16;
+        let height = (max.y as i64 - min.y as i64 + 1) as u64 * 16;
+        if width.saturating_mul(height) > MAX_WORLD_MAP_PIXELS {
+            return Err(WorldMapError::TooLarge {
+                width,
+                height,
+            });
+        }
+
+        let origin = match (min.x.checked_mul(16), min.y.checked_mul(16)) {
+            (Some(x), Some(y)) => IVec2::new(x, y),
+            _ => return Err(WorldMapError::OutOfRange(min)),
+        };
+
+        let width = width as usize;
+        let height = height as usize;
+
+        let mut colors = vec![[0; 4]; width * height];
+        let mut heights = vec![None; width * height];
+
+        for (column, mut chunks) in columns {
+            chunks.sort_by_key(|(y, _)| -*y);
+
+            let offset = (column - min) * 16;
+            for u in 0 .. 16 {
+                for v in 0 .. 16 {
+                    let Some((h, cell)) = top_most_block(&chunks, up, IVec2::new(u, v)) else {
+                        continue;
+                    };
+
+                    let index = (offset.y + v) as usize * width + (offset.x + u) as usize;
+                    colors[index] = color(cell);
+                    heights[index] = Some(h);
+                }
+            }
+        }
+
+        Ok(Self {
+            origin,
+            width,
+            height,
+            colors,
+            heights,
+        })
+    }
+
+    /// Gets the column coordinates of the top-left pixel of this image.
+    pub fn origin(&self) -> IVec2 {
+        self.origin
+    }
+
+    /// Gets the width of this image, in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of this image, in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the color of the pixel at the given image coordinates.
+    pub fn get_color(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.colors[y * self.width + x])
+    }
+
+    /// Gets the height of the top-most block of the pixel at the given image
+    /// coordinates along the up axis, or `None` if that block column is empty.
+    pub fn get_height(&self, x: usize, y: usize) -> Option<i32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.heights[y * self.width + x]
+    }
+
+    /// Encodes the colors of this world map as an RGBA PNG image.
+    ///
+    /// Returns an error if this world map is empty, as PNG images cannot have a
+    /// width or height of zero.
+    #[cfg(feature = "png_export")]
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let data = self.colors.iter().flatten().copied().collect::<Vec<_>>();
+        encode_png(self.width, self.height, png::ColorType::Rgba, &data)
+    }
+
+    /// Encodes the heights of this world map as a grayscale PNG image.
+    ///
+    /// Heights are normalized such that the lowest block is black and the
+    /// highest block is white. Empty block columns are black.
+    ///
+    /// Returns an error if this world map is empty, as PNG images cannot have a
+    /// width or height of zero.
+    #[cfg(feature = "png_export")]
+    pub fn encode_heightmap_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let min = self.heights.iter().flatten().copied().min().unwrap_or(0);
+        let max = self.heights.iter().flatten().copied().max().unwrap_or(0);
+        let range = (max - min).max(1) as f32;
+
+        let data = self
+            .heights
+            .iter()
+            .map(|h| {
+                match h {
+                    Some(h) => ((h - min) as f32 / range * 255.0).round() as u8,
+                    None => 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        encode_png(self.width, self.height, png::ColorType::Grayscale, &data)
+    }
+
+    /// Saves the colors of this world map to a PNG file at the given path.
+    #[cfg(feature = "png_export")]
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.encode_png()?)
+    }
+
+    /// Saves the heights of this world map to a grayscale PNG file at the given
+    /// path.
+    #[cfg(feature = "png_export")]
+    pub fn save_heightmap_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.encode_heightmap_png()?)
+    }
+}
+
+/// Encodes raw 8-bit pixel data with the given color type as a PNG image.
+#[cfg(feature = "png_export")]
+fn encode_png(
+    width: usize,
+    height: usize,
+    color_type: png::ColorType,
+    data: &[u8],
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut out = vec![];
+    let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn render_world_map() {
+        let mut a = VoxelStorage::<u8>::default();
+        let mut b = VoxelStorage::<u8>::default();
+        a.set_block(IVec3::new(2, 3, 4), 5);
+        b.set_block(IVec3::new(0, 1, 0), 9);
+
+        let chunks = [(IVec3::new(0, 0, 0), &a), (IVec3::new(1, -2, 1), &b)];
+        let map =
+            WorldMapImage::from_chunks(chunks, UpAxis::PosY, |cell| [cell, 0, 0, 255]).unwrap();
+
+        assert_eq!(map.origin(), IVec2::ZERO);
+        assert_eq!((map.width(), map.height()), (32, 32));
+        assert_eq!(map.get_color(2, 4), Some([5, 0, 0, 255]));
+        assert_eq!(map.get_height(2, 4), Some(3));
+        assert_eq!(map.get_height(16, 16), Some(-31));
+        assert_eq!(map.get_color(0, 0), Some([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn reject_world_map_of_distant_chunks() {
+        let storage = VoxelStorage::<u8>::default();
+
+        let chunks = [(IVec3::new(0, 0, 0), &storage), (IVec3::new(300, 0, 300), &storage)];
+        let err = WorldMapImage::from_chunks(chunks, UpAxis::PosY, |cell| [cell, 0, 0, 255]);
+        assert_eq!(
+            err,
+            Err(WorldMapError::TooLarge {
+                width:  301 * 16,
+                height: 301 * 16,
+            })
+        );
+
+        let chunks =
+            [(IVec3::new(i32::MIN, 0, 0), &storage), (IVec3::new(i32::MAX, 0, 0), &storage)];
+        let err = WorldMapImage::from_chunks(chunks, UpAxis::PosY, |cell| [cell, 0, 0, 255]);
+        assert!(matches!(err, Err(WorldMapError::TooLarge { .. })));
+
+        let chunks = [(IVec3::new(i32::MIN, 0, 0), &storage)];
+        let err = WorldMapImage::from_chunks(chunks, UpAxis::PosY, |cell| [cell, 0, 0, 255]);
+        assert_eq!(err, Err(WorldMapError::OutOfRange(IVec2::new(i32::MIN, 0))));
+    }
+
+    #[cfg(feature = "png_export")]
+    #[test]
+    fn encode_world_map_png() {
+        let mut storage = VoxelStorage::<u8>::default();
+        storage.set_block(IVec3::new(2, 3, 4), 5);
+
+        let map = WorldMapImage::from_chunks([(IVec3::ZERO, &storage)], UpAxis::PosY, |cell| {
+            [cell, 0, 0, 255]
+        })
+        .unwrap();
+
+        let png = map.encode_png().unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+
+        assert_eq!((info.width, info.height), (16, 16));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        let pixel = (4 * 16 + 2) * 4;
+        assert_eq!(&data[pixel .. pixel + 4], &[5, 0, 0, 255]);
+        assert_eq!(&data[.. 4], &[0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "png_export")]
+    #[test]
+    fn encode_empty_world_map_png() {
+        let map =
+            WorldMapImage::from_chunks::<u8, _, _>([], UpAxis::PosY, |cell| [cell, 0, 0, 255])
+                .unwrap();
+        assert_eq!((map.width(), map.height()), (0, 0));
+
+        assert!(map.encode_png().is_err());
+        assert!(map.encode_heightmap_png().is_err());
+
+        let path = std::env::temp_dir().join("bones3_empty_world_map.png");
+        let err = map.save_png(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+}
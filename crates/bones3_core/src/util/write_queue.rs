@@ -0,0 +1,225 @@
+//! A thread-safe queue for writing blocks from outside of the ECS, such as
+//! from async tasks or networking threads.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bevy::prelude::*;
+
+use crate::storage::chunk_pointers::ChunkEntityPointers;
+use crate::storage::{BlockData, VoxelChunk, VoxelStorage, VoxelWorld};
+use crate::util::block_update::BlockUpdateQueue;
+
+/// A single block write that is waiting to be applied.
+struct QueuedWrite<T> {
+    /// The id of the world to write to.
+    world_id: Entity,
+
+    /// The coordinates of the block to write.
+    block_coords: IVec3,
+
+    /// The new block data value.
+    data: T,
+}
+
+/// A resource for queuing block writes from any thread.
+///
+/// Direct access to block storage requires a system with a mutable borrow of
+/// the chunk components, which is not available from async tasks. Instead,
+/// tasks may hold a clone of this resource and push block edits into it at any
+/// time. All queued writes are applied by the core plugin within
+/// [`Bones3CoreSet::BlockWrites`](crate::Bones3CoreSet::BlockWrites), in the
+/// order that they were pushed, and are treated the same as writes made with
+/// [`VoxelWorldCommands::set_block`](crate::query::VoxelWorldCommands::set_block).
+///
+/// Writes to chunks that do not exist, or do not have a `VoxelStorage<T>`
+/// component, when the queue is applied are discarded.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy::tasks::AsyncComputeTaskPool;
+/// # use bones3_core::storage::VoxelWorld;
+/// # use bones3_core::util::write_queue::VoxelWriteQueue;
+/// # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+/// # enum BlockState {
+/// #     #[default]
+/// #     Air,
+/// #     Stone,
+/// # }
+/// fn place_stone_async(
+///     write_queue: Res<VoxelWriteQueue<BlockState>>,
+///     worlds: Query<Entity, With<VoxelWorld>>,
+/// ) {
+///     for world_id in worlds.iter() {
+///         let queue = write_queue.clone();
+///         AsyncComputeTaskPool::get()
+///             .spawn(async move {
+///                 queue.push(
+///                     world_id,
+///                     IVec3::new(4, 12, -3),
+///                     BlockState::Stone,
+///                 );
+///             })
+///             .detach();
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(place_stone_async);
+/// ```
+#[derive(Resource)]
+pub struct VoxelWriteQueue<T>
+where
+    T: BlockData,
+{
+    /// The shared list of pending writes.
+    writes: Arc<Mutex<Vec<QueuedWrite<T>>>>,
+}
+
+impl<T> Default for VoxelWriteQueue<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self {
+            writes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<T> Clone for VoxelWriteQueue<T>
+where
+    T: BlockData,
+{
+    fn clone(&self) -> Self {
+        Self {
+            writes: self.writes.clone(),
+        }
+    }
+}
+
+impl<T> VoxelWriteQueue<T>
+where
+    T: BlockData,
+{
+    /// Queues the block at the given block coordinates within the given world
+    /// to be set to the given block data value.
+    pub fn push(&self, world_id: Entity, block_coords: IVec3, data: T) {
+        self.lock().push(QueuedWrite {
+            world_id,
+            block_coords,
+            data,
+        });
+    }
+
+    /// Queues many block writes within the given world at once.
+    pub fn extend<I>(&self, world_id: Entity, blocks: I)
+    where
+        I: IntoIterator<Item = (IVec3, T)>,
+    {
+        self.lock()
+            .extend(blocks.into_iter().map(|(block_coords, data)| {
+                QueuedWrite {
+                    world_id,
+                    block_coords,
+                    data,
+                }
+            }));
+    }
+
+    /// Gets the number of writes that are waiting to be applied.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Gets whether or not there are no writes waiting to be applied.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Locks the list of pending writes.
+    ///
+    /// A panic within another thread while pushing writes cannot leave the
+    /// list in an invalid state, so a poisoned lock is ignored.
+    fn lock(&self) -> MutexGuard<'_, Vec<QueuedWrite<T>>> {
+        self.writes.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// This system applies all writes within the [`VoxelWriteQueue`] and marks
+/// the written blocks within the [`BlockUpdateQueue`].
+pub(crate) fn apply_queued_writes<T>(
+    queue: Res<VoxelWriteQueue<T>>,
+    worlds: Query<&ChunkEntityPointers, With<VoxelWorld>>,
+    mut chunks: Query<(&mut VoxelStorage<T>, &mut VoxelChunk)>,
+    mut updates: ResMut<BlockUpdateQueue>,
+) where
+    T: BlockData,
+{
+    let writes = std::mem::take(&mut *queue.lock());
+    for write in writes {
+        let Ok(pointers) = worlds.get(write.world_id) else {
+            continue;
+        };
+
+        let block_coords = pointers.topology().wrap_block_coords(write.block_coords);
+        let Some(chunk_id) = pointers.get_chunk_entity(block_coords >> 4) else {
+            continue;
+        };
+
+        let Ok((mut storage, mut chunk_meta)) = chunks.get_mut(chunk_id) else {
+            continue;
+        };
+
+        storage.set_block(block_coords, write.data);
+        chunk_meta.bump_version(storage.last_changed());
+        updates.push(write.world_id, block_coords);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::util::block_update::NeighborChangedEvent;
+
+    #[test]
+    fn apply_writes_from_threads() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default());
+
+        fn init(mut commands: VoxelCommands) {
+            commands
+                .spawn_world(())
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                .unwrap();
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let world_id = app
+            .world
+            .query_filtered::<Entity, With<VoxelWorld>>()
+            .single(&app.world);
+
+        let queue = app.world.resource::<VoxelWriteQueue<u8>>().clone();
+        std::thread::scope(|scope| {
+            for x in 0 .. 4 {
+                let queue = queue.clone();
+                scope.spawn(move || queue.push(world_id, IVec3::new(x, 0, 0), x as u8 + 1));
+            }
+        });
+        queue.push(world_id, IVec3::new(40, 0, 0), 9);
+        assert_eq!(queue.len(), 5);
+
+        app.update();
+        assert!(queue.is_empty());
+
+        let mut chunks = app.world.query::<&VoxelStorage<u8>>();
+        let storage = chunks.single(&app.world);
+        for x in 0 .. 4 {
+            assert_eq!(storage.get_block(IVec3::new(x, 0, 0)), x as u8 + 1);
+        }
+
+        let events = app.world.resource::<Events<NeighborChangedEvent>>();
+        assert_eq!(events.len(), 4 * 6);
+    }
+}
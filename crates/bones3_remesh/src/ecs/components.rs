@@ -1,21 +1,296 @@
 //! This module contains the components that may be used to generate chunk
 //! meshes and interact with the remesh systems.
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy::tasks::Task;
 use bones3_core::storage::{BlockData, VoxelStorage};
 
+use crate::mesh::imposter::ImposterSource;
+
 /// A temporary marker component that indicates that the target chunk needs to
 /// be remeshed.
-#[derive(Component, Reflect)]
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
 #[component(storage = "SparseSet")]
 pub struct RemeshChunk;
 
 /// An entity with this marker indicates that the entity exists only as a child
 /// of a chunk to render it's physical mesh object.
-#[derive(Component, Reflect)]
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct ChunkMesh;
 
+/// A component for chunk mesh entities whose mesh uses the
+/// [quantized](crate::vertex_data::quantized) vertex format.
+///
+/// Quantized chunk meshes are rendered using a
+/// [`QuantizedChunkMaterial`](crate::mesh::quantized_material::QuantizedChunkMaterial)
+/// instead of a `StandardMaterial`, so this component stores the material from
+/// the [`ChunkMaterialList`](crate::ecs::resources::ChunkMaterialList) that
+/// the mesh was built with.
+#[derive(Debug, Component, Clone)]
+pub struct QuantizedChunkMesh(pub Handle<StandardMaterial>);
+
+/// A temporary component that indicates that the meshes of the target chunk
+/// have been built, but are still waiting to be uploaded.
+///
+/// This stores the number of times the meshes of the chunk have been built
+/// without being uploaded yet, and is removed once all of them have been
+/// uploaded.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct ChunkMeshPending(pub u32);
+
+/// A query filter for chunks whose latest meshes have been built and uploaded.
+///
+/// This can be used with the
+/// [`AnchorReadyPlugin`](bones3_core::util::anchor_ready::AnchorReadyPlugin)
+/// to wait for the chunk meshes around an anchor before it is considered to
+/// be ready. Chunks are only remeshed while they are in range of a
+/// [`RemeshAnchor`](crate::RemeshAnchor), so the radius of the waiting anchor
+/// should not be larger than that.
+pub type ChunkMeshesReady = (Without<RemeshChunk>, Without<ChunkMeshPending>);
+
+/// A component that stores how long it took to build the meshes of a chunk the
+/// last time it was remeshed, for profiling purposes.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct ChunkMeshTime(pub Duration);
+
+/// A component that stores information about the meshes of a chunk the last
+/// time it was remeshed.
+///
+/// This is used to only remesh the chunks that are affected when a material
+/// within the [`ChunkMaterialList`](crate::ecs::resources::ChunkMaterialList)
+/// is added or removed.
+#[derive(Debug, Default, Component, Reflect, Clone, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct ChunkMeshInfo {
+    /// The sorted indices of all materials that are used by blocks within the
+    /// chunk.
+    material_indices: Vec<u16>,
+}
+
+impl ChunkMeshInfo {
+    /// Creates a new chunk mesh info component for a chunk that uses the
+    /// given material indices.
+    pub fn new(material_indices: impl IntoIterator<Item = u16>) -> Self {
+        let mut material_indices: Vec<u16> = material_indices.into_iter().collect();
+        material_indices.sort_unstable();
+        material_indices.dedup();
+
+        Self {
+            material_indices,
+        }
+    }
+
+    /// Gets the sorted indices of all materials that are used by blocks within
+    /// the chunk, including materials that are missing from the material
+    /// list.
+    pub fn material_indices(&self) -> &[u16] {
+        &self.material_indices
+    }
+
+    /// Checks whether blocks within the chunk use the material with the given
+    /// index.
+    pub fn uses_material(&self, index: u16) -> bool {
+        self.material_indices.binary_search(&index).is_ok()
+    }
+}
+
+/// An animation component that is attached to a chunk when its first mesh
+/// appears, while the [`ChunkFadePlugin`](crate::ChunkFadePlugin) is in use.
+///
+/// The chunk meshes of the chunk are scaled up from the center of the chunk
+/// until the animation is finished, at which point this component is removed.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct ChunkFadeIn {
+    /// The time since the animation started, in seconds.
+    pub elapsed: f32,
+
+    /// The total duration of the animation, in seconds.
+    pub duration: f32,
+}
+
+impl ChunkFadeIn {
+    /// Creates a new fade in animation with the given duration, in seconds.
+    pub fn new(duration: f32) -> Self {
+        Self {
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    /// Gets the current progress of this animation, between `0.0` and `1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    /// Gets the scale that should be applied to the chunk meshes at the
+    /// current point of this animation.
+    pub fn scale(&self) -> f32 {
+        let t = 1.0 - self.progress();
+        1.0 - t * t * t
+    }
+}
+
+/// Determines which meshing algorithm is used to generate the chunk meshes of
+/// all chunks within a voxel world.
+///
+/// This component should be attached to the voxel world entity. Worlds without
+/// this component use [`ChunkMesher::Blocks`].
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub enum ChunkMesher {
+    /// Chunk meshes are generated from block shapes, using the
+    /// [`BlockShape`](crate::mesh::block_model::BlockShape) trait.
+    #[default]
+    Blocks,
+
+    /// Chunk meshes are generated as a smooth surface using the surface nets
+    /// algorithm, using the [`BlockDensity`](crate::mesh::smooth::BlockDensity)
+    /// trait.
+    SurfaceNets,
+
+    /// Chunk meshes are generated as a smooth surface using the dual
+    /// contouring algorithm, using the
+    /// [`BlockDensity`](crate::mesh::smooth::BlockDensity) trait. Unlike
+    /// surface nets, this preserves sharp features when the block data
+    /// provides density gradients.
+    DualContouring,
+}
+
+/// Determines whether the vertex positions of chunk meshes within a voxel world
+/// are relative to their chunk or to the voxel world itself.
+///
+/// This component should be attached to the voxel world entity. Worlds without
+/// this component use [`ChunkMeshOrigin::Chunk`]. Changing this component
+/// remeshes all chunks within the world.
+///
+/// In both cases, chunk mesh entities are given a transform that places their
+/// vertices at the correct location in the world, so the choice only affects
+/// the values that are stored within the mesh assets.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub enum ChunkMeshOrigin {
+    /// Vertex positions are given in block units relative to the origin of
+    /// their chunk, and chunk mesh entities use an identity transform.
+    ///
+    /// Vertex positions always stay within the range `0.0 ..= 16.0`, so no
+    /// precision is lost regardless of how far the chunk is from the world
+    /// origin. This should be preferred unless the vertex data itself must
+    /// be absolute.
+    #[default]
+    Chunk,
+
+    /// Vertex positions are given in block units relative to the origin of
+    /// the voxel world, and chunk mesh entities are given a transform that
+    /// cancels out the translation of their chunk.
+    ///
+    /// This is useful for render pipelines or exporters that require absolute
+    /// vertex positions, but vertex positions lose precision as chunks get
+    /// further from the world origin.
+    World,
+}
+
+impl ChunkMeshOrigin {
+    /// Gets the offset that is added to the vertex positions of the chunk
+    /// meshes of the chunk at the given chunk coordinates.
+    pub fn vertex_offset(self, chunk_coords: IVec3) -> Vec3 {
+        match self {
+            ChunkMeshOrigin::Chunk => Vec3::ZERO,
+            ChunkMeshOrigin::World => (chunk_coords * 16).as_vec3(),
+        }
+    }
+
+    /// Gets the local transform of the chunk mesh entities of the chunk at the
+    /// given chunk coordinates, relative to that chunk.
+    pub fn mesh_transform(self, chunk_coords: IVec3) -> Transform {
+        Transform::from_translation(-self.vertex_offset(chunk_coords))
+    }
+}
+
+/// Determines how block faces at the boundary of the loaded chunks within a
+/// voxel world are meshed, where the neighboring chunk is not loaded.
+///
+/// This component should be attached to the voxel world entity. Worlds without
+/// this component use [`BoundaryFaces::Empty`]. Changing this component
+/// remeshes all chunks within the world.
+///
+/// Underground games usually want to hide the walls of stone at the edge of
+/// the loaded area, while floating island games usually want to keep the
+/// sides of islands visible. This only affects the
+/// [`ChunkMesher::Blocks`] mesher.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component, Default)]
+pub enum BoundaryFaces {
+    /// Blocks within unloaded chunks are treated as empty, so block faces at
+    /// the boundary are rendered.
+    #[default]
+    Empty,
+
+    /// Blocks within unloaded chunks are treated as solid, so block faces at
+    /// the boundary are hidden.
+    Solid,
+
+    /// Blocks within unloaded chunks are treated as solid, and a wall using
+    /// the material with the given index within the
+    /// [`ChunkMaterialList`](crate::ecs::resources::ChunkMaterialList) is
+    /// rendered along the boundary instead, facing the loaded chunks.
+    FogWall(u16),
+}
+
+/// A component wrapper for the [`ImposterSource`] that is used to build the
+/// far-field imposters of a voxel world.
+///
+/// This component should be attached to the voxel world entity. Worlds without
+/// this component do not have any imposters. Changing this component rebuilds
+/// all imposters within the world.
+#[derive(Component, Reflect)]
+#[reflect(from_reflect = false)]
+pub struct ImposterSourceHandler(#[reflect(ignore)] Arc<dyn ImposterSource>);
+
+impl ImposterSourceHandler {
+    /// Creates a new ImposterSourceHandler instance.
+    pub fn from<S>(source: S) -> Self
+    where
+        S: ImposterSource,
+    {
+        Self(Arc::new(source))
+    }
+
+    /// Gets a reference to the imposter source instance.
+    pub fn source(&self) -> Arc<dyn ImposterSource> {
+        self.0.clone()
+    }
+}
+
+/// A component that marks an entity as the far-field imposter of a single
+/// chunk column.
+///
+/// Imposter entities are spawned as children of their voxel world by the
+/// [`ImposterPlugin`](crate::ImposterPlugin), and are despawned once their
+/// chunk column leaves the imposter ring of all imposter anchors.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkImposter {
+    /// The id of the world that this imposter belongs to.
+    pub world_id: Entity,
+
+    /// The coordinates of the chunk column, along the column axes of the
+    /// world's up axis.
+    pub chunk_column: IVec2,
+}
+
 /// this component represents an active chunk that is currently being remeshed.
 #[derive(Debug, Component, Reflect)]
 #[reflect(from_reflect = false)]
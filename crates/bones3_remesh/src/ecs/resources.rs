@@ -1,19 +1,388 @@
 //! This module contains the resources that may be used to generate chunk meshes
 //! and interact with the remesh systems.
 
+use std::collections::VecDeque;
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy::utils::HashMap;
+use bones3_core::util::timing::{ChunkTimings, TaskBudget};
+
+use crate::mesh::block_model::BlockOcclusion;
+use crate::mesh::seams::SeamMismatch;
+
+/// The render settings that are applied to a material within the chunk
+/// material list.
+///
+/// Whenever the material list changes, these settings overwrite the matching
+/// fields of the underlying material asset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+pub struct ChunkMaterialSettings {
+    /// If true, back face culling is disabled for this material, so that all
+    /// geometry using it is visible from both sides.
+    pub double_sided: bool,
+
+    /// If true, this material ignores all lighting.
+    pub unlit: bool,
+
+    /// The alpha blending mode of this material.
+    pub alpha_mode: AlphaMode,
+}
+
+/// The settings for the fade in animation of newly meshed chunks that is
+/// played by the [`ChunkFadePlugin`](crate::ChunkFadePlugin).
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct ChunkFadeSettings {
+    /// The duration of the fade in animation, in seconds.
+    ///
+    /// Defaults to `0.3`.
+    pub duration: f32,
+}
+
+impl Default for ChunkFadeSettings {
+    fn default() -> Self {
+        Self {
+            duration: 0.3,
+        }
+    }
+}
+
+/// The settings for the far-field imposters that are maintained by the
+/// [`ImposterPlugin`](crate::ImposterPlugin).
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource, Default)]
+pub struct ImposterSettings {
+    /// The number of cells along each side of an imposter. This must be a
+    /// divisor of 16.
+    ///
+    /// Defaults to `4`.
+    pub resolution: usize,
+
+    /// The radius, in chunk columns around each imposter anchor, that does
+    /// not receive any imposters. This should match the range in which real
+    /// chunks are loaded and meshed.
+    ///
+    /// Defaults to `8`.
+    pub inner_radius: u32,
+
+    /// The maximum number of imposters that are built within a single frame.
+    /// The imposters closest to an imposter anchor are built first.
+    ///
+    /// Defaults to `16`.
+    pub max_builds_per_frame: usize,
+}
+
+impl Default for ImposterSettings {
+    fn default() -> Self {
+        Self {
+            resolution:           4,
+            inner_radius:         8,
+            max_builds_per_frame: 16,
+        }
+    }
+}
+
+/// The material that is used to render all far-field imposters.
+///
+/// Imposter meshes store their surface colors as vertex colors, so the base
+/// color of this material tints all imposters. By default, this is a plain
+/// white, fully rough material.
+#[derive(Debug, Resource, Clone)]
+pub struct ImposterMaterial(pub Handle<StandardMaterial>);
+
+impl FromWorld for ImposterMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self(materials.add(StandardMaterial {
+            perceptual_roughness: 1.0,
+            ..default()
+        }))
+    }
+}
+
+/// The per-frame budget for building chunk meshes.
+///
+/// The number of chunks that are remeshed each frame adapts to a moving
+/// average of how long recent chunks took to mesh, such that remeshing takes
+/// about as long as the target time of the budget each frame. The recorded
+/// timings are also available for profiling purposes.
+#[derive(Debug, Resource, Reflect, Clone, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct ChunkMeshBudget {
+    /// The budget that determines the number of chunks to remesh each frame.
+    ///
+    /// Defaults to a target time of 4ms, with 1 to 16 chunks, and 4 chunks
+    /// before any chunks have been meshed.
+    pub budget: TaskBudget,
+
+    /// The timings of all chunks that have been meshed.
+    #[reflect(ignore)]
+    timings: ChunkTimings,
+}
+
+impl Default for ChunkMeshBudget {
+    fn default() -> Self {
+        Self {
+            budget:  TaskBudget {
+                target_time:   Duration::from_millis(4),
+                min_tasks:     1,
+                max_tasks:     16,
+                initial_tasks: 4,
+            },
+            timings: ChunkTimings::default(),
+        }
+    }
+}
+
+impl ChunkMeshBudget {
+    /// Gets the timings of all chunks that have been meshed.
+    pub fn timings(&self) -> &ChunkTimings {
+        &self.timings
+    }
+
+    /// Gets the maximum number of chunks that may currently be remeshed within
+    /// a single frame.
+    pub fn max_chunks(&self) -> usize {
+        self.budget.tasks(&self.timings)
+    }
+
+    /// Records the time it took to mesh a single chunk.
+    pub(crate) fn record(&mut self, duration: Duration) {
+        self.timings.record(duration);
+    }
+}
+
+/// The per-frame budget for uploading newly built chunk meshes to the mesh
+/// asset storage.
+///
+/// Uploading many large meshes within a single frame causes GPU transfer
+/// spikes, so finished chunk meshes are queued and uploaded over multiple
+/// frames instead, in the order they were built. At least one chunk is always
+/// uploaded each frame, even if it exceeds the budget on its own. Chunks keep
+/// their previous meshes until their new meshes are uploaded.
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource, Default)]
+pub struct ChunkMeshUploadSettings {
+    /// The maximum number of vertices that are uploaded each frame.
+    ///
+    /// Defaults to `usize::MAX`.
+    pub max_vertices_per_frame: usize,
+
+    /// The maximum number of vertex and index buffer bytes that are uploaded
+    /// each frame.
+    ///
+    /// Defaults to `usize::MAX`.
+    pub max_bytes_per_frame: usize,
+
+    /// Whether chunk meshes are built using the
+    /// [quantized](crate::vertex_data::quantized) vertex format, which uses
+    /// about a third of the memory and upload bandwidth of standard chunk
+    /// meshes.
+    ///
+    /// Quantized chunk meshes are only rendered while the
+    /// [`QuantizedChunkMeshPlugin`](crate::QuantizedChunkMeshPlugin) is in
+    /// use. Chunk meshes that cannot be quantized, such as meshes that use
+    /// [`ChunkMeshOrigin::World`](crate::ecs::components::ChunkMeshOrigin::World)
+    /// far from the world origin, use the standard vertex format instead.
+    /// Changing this setting only affects chunks that are remeshed afterwards.
+    ///
+    /// Defaults to `false`.
+    pub quantize_vertices: bool,
+}
+
+impl Default for ChunkMeshUploadSettings {
+    fn default() -> Self {
+        Self {
+            max_vertices_per_frame: usize::MAX,
+            max_bytes_per_frame:    usize::MAX,
+            quantize_vertices:      false,
+        }
+    }
+}
+
+/// The meshes of a single chunk that are waiting to be uploaded.
+pub(crate) struct PendingChunkMeshes {
+    /// The id of the chunk.
+    pub(crate) chunk_id: Entity,
+
+    /// The meshes of the chunk, along with their materials.
+    pub(crate) meshes: Vec<(Mesh, Handle<StandardMaterial>)>,
+
+    /// The transform of the chunk mesh entities, relative to the chunk.
+    pub(crate) transform: Transform,
+
+    /// The number of times the meshes of the chunk were built since they were
+    /// last queued, including the builds of meshes that were replaced by
+    /// these ones.
+    pub(crate) builds: u32,
+}
+
+impl PendingChunkMeshes {
+    /// Gets the total number of vertices within these meshes.
+    pub(crate) fn vertex_count(&self) -> usize {
+        self.meshes
+            .iter()
+            .map(|(mesh, _)| mesh.count_vertices())
+            .sum()
+    }
+
+    /// Gets the total size of the vertex and index buffers of these meshes,
+    /// in bytes.
+    pub(crate) fn byte_size(&self) -> usize {
+        self.meshes
+            .iter()
+            .map(|(mesh, _)| {
+                let vertices: usize = mesh
+                    .attributes()
+                    .map(|(_, values)| values.get_bytes().len())
+                    .sum();
+                let indices = mesh.get_index_buffer_bytes().map_or(0, |b| b.len());
+                vertices + indices
+            })
+            .sum()
+    }
+}
+
+/// This resource contains the chunk meshes that have been built, but have not
+/// been uploaded yet due to the [`ChunkMeshUploadSettings`].
+#[derive(Resource, Default)]
+pub struct ChunkMeshUploadQueue {
+    /// The queued chunk meshes, in the order they were built.
+    pending: VecDeque<PendingChunkMeshes>,
+}
+
+impl ChunkMeshUploadQueue {
+    /// Gets the number of chunks that are waiting for their meshes to be
+    /// uploaded.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Checks whether there are no chunks waiting for their meshes to be
+    /// uploaded.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Checks whether the given chunk is waiting for its meshes to be
+    /// uploaded.
+    pub fn contains(&self, chunk_id: Entity) -> bool {
+        self.pending.iter().any(|p| p.chunk_id == chunk_id)
+    }
+
+    /// Adds the meshes of a chunk to the end of the queue, replacing any
+    /// meshes that were already queued for the same chunk.
+    ///
+    /// The builds of the replaced meshes are added to the builds of the new
+    /// meshes, so that they are accounted for once the new meshes are
+    /// uploaded.
+    pub(crate) fn push(&mut self, mut chunk_meshes: PendingChunkMeshes) {
+        self.pending.retain(|p| {
+            if p.chunk_id != chunk_meshes.chunk_id {
+                return true;
+            }
+
+            chunk_meshes.builds += p.builds;
+            false
+        });
+        self.pending.push_back(chunk_meshes);
+    }
+
+    /// Removes the next chunk meshes from the queue, if the given number of
+    /// vertices and bytes that were already uploaded this frame leaves room
+    /// for them within the given budget.
+    pub(crate) fn pop_within(
+        &mut self,
+        settings: &ChunkMeshUploadSettings,
+        vertices: usize,
+        bytes: usize,
+    ) -> Option<PendingChunkMeshes> {
+        let next = self.pending.front()?;
+        let first = vertices == 0 && bytes == 0;
+        let fits = vertices.saturating_add(next.vertex_count()) <= settings.max_vertices_per_frame
+            && bytes.saturating_add(next.byte_size()) <= settings.max_bytes_per_frame;
+
+        match first || fits {
+            true => self.pending.pop_front(),
+            false => None,
+        }
+    }
+}
+
+/// The kind of change that was made to a material within the chunk material
+/// list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum ChunkMaterialChange {
+    /// A new material was added.
+    Added,
+
+    /// The material handle was replaced with a different handle.
+    Replaced,
+
+    /// The material was removed.
+    Removed,
+
+    /// The render settings of the material were changed.
+    SettingsChanged,
+
+    /// The material asset itself was modified, such as when it is hot
+    /// reloaded.
+    AssetModified,
+}
+
+/// An event that is sent whenever a material within the
+/// [`ChunkMaterialList`] is modified.
+///
+/// Chunk meshes that use a replaced material are automatically updated to use
+/// the new material, and only the chunks that contain blocks using an added or
+/// removed material are marked for remeshing.
+#[derive(Debug, Event, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkMaterialChanged {
+    /// The index of the material that was modified.
+    pub index: u16,
+
+    /// The kind of change that was made.
+    pub change: ChunkMaterialChange,
+}
+
+/// A change to the chunk material list that has not been reported yet, along
+/// with the material handle that was in use before the change.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingMaterialChange {
+    /// The index of the material that was modified.
+    pub(crate) index: u16,
+
+    /// The kind of change that was made.
+    pub(crate) change: ChunkMaterialChange,
+
+    /// The material handle that was in use before the change, if any.
+    pub(crate) old_material: Option<Handle<StandardMaterial>>,
+}
 
 /// This resource contains an indexed list of material handles that are used by
 /// blocks when generating chunk meshes.
-#[derive(Resource, Default)]
+///
+/// Removing a material leaves an empty slot behind, so the indices of all other
+/// materials never change. Blocks that use a removed material are not
+/// included in chunk meshes.
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource, Default)]
 pub struct ChunkMaterialList {
-    /// The indexed list of material handles.
-    materials: Vec<Handle<StandardMaterial>>,
+    /// The indexed list of material handles, or `None` for removed materials.
+    materials: Vec<Option<Handle<StandardMaterial>>>,
+
+    /// The render settings for each material, using the same indices as the
+    /// material handles.
+    settings: Vec<ChunkMaterialSettings>,
 
     /// Material names and their corresponding index values within the material
     /// list.
     material_keys: HashMap<String, u16>,
+
+    /// The changes that have been made since the last time they were reported.
+    #[reflect(ignore)]
+    pending_changes: Vec<PendingMaterialChange>,
 }
 
 impl ChunkMaterialList {
@@ -25,19 +394,105 @@ impl ChunkMaterialList {
         material: Handle<StandardMaterial>,
         name: Option<String>,
     ) -> u16 {
-        self.materials.push(material);
+        self.materials.push(Some(material));
+        self.settings.push(ChunkMaterialSettings::default());
         let index = (self.materials.len() - 1) as u16;
 
         if let Some(material_name) = name {
             self.material_keys.insert(material_name, index);
         }
 
+        self.push_change(index, ChunkMaterialChange::Added, None);
         index
     }
 
+    /// Replaces the material handle at the given material index, keeping its
+    /// name and render settings.
+    ///
+    /// Returns the previous material handle, or `None` if there was no
+    /// material at the given index.
+    pub fn replace_material(
+        &mut self,
+        index: u16,
+        material: Handle<StandardMaterial>,
+    ) -> Option<Handle<StandardMaterial>> {
+        let slot = self.materials.get_mut(index as usize)?;
+        let old_material = slot.replace(material)?;
+        self.push_change(
+            index,
+            ChunkMaterialChange::Replaced,
+            Some(old_material.clone()),
+        );
+        Some(old_material)
+    }
+
+    /// Removes the material at the given material index, along with its name.
+    ///
+    /// Returns the removed material handle, or `None` if there was no material
+    /// at the given index.
+    pub fn remove_material(&mut self, index: u16) -> Option<Handle<StandardMaterial>> {
+        let old_material = self.materials.get_mut(index as usize)?.take()?;
+        self.settings[index as usize] = ChunkMaterialSettings::default();
+        self.material_keys.retain(|_, i| *i != index);
+        self.push_change(
+            index,
+            ChunkMaterialChange::Removed,
+            Some(old_material.clone()),
+        );
+        Some(old_material)
+    }
+
     /// Gets a copy of the material handle at the given material index.
+    ///
+    /// This function panics if there is no material at the given index.
     pub fn get_material(&self, index: u16) -> Handle<StandardMaterial> {
-        self.materials[index as usize].clone()
+        self.try_get_material(index).unwrap()
+    }
+
+    /// Gets a copy of the material handle at the given material index, or
+    /// `None` if there is no material at that index.
+    pub fn try_get_material(&self, index: u16) -> Option<Handle<StandardMaterial>> {
+        self.materials.get(index as usize)?.clone()
+    }
+
+    /// Gets the index of the first material within this material list that
+    /// uses the given material handle.
+    pub fn index_of(&self, material: &Handle<StandardMaterial>) -> Option<u16> {
+        self.materials
+            .iter()
+            .position(|m| m.as_ref() == Some(material))
+            .map(|index| index as u16)
+    }
+
+    /// Gets the render settings of the material at the given material index.
+    pub fn get_settings(&self, index: u16) -> ChunkMaterialSettings {
+        self.settings[index as usize]
+    }
+
+    /// Sets the render settings of the material at the given material index.
+    ///
+    /// These settings are written to the underlying material asset at the end
+    /// of the frame. Note that this affects all entities that share the same
+    /// material handle. Nothing happens if the settings are unchanged.
+    pub fn set_settings(&mut self, index: u16, settings: ChunkMaterialSettings) {
+        if self.settings[index as usize] == settings {
+            return;
+        }
+
+        self.settings[index as usize] = settings;
+        self.push_change(index, ChunkMaterialChange::SettingsChanged, None);
+    }
+
+    /// Creates an iterator over all material handles and their corresponding
+    /// render settings within this material list. Removed materials are
+    /// skipped.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&Handle<StandardMaterial>, &ChunkMaterialSettings)> {
+        self.materials
+            .iter()
+            .zip(self.settings.iter())
+            .filter_map(|(material, settings)| Some((material.as_ref()?, settings)))
     }
 
     /// Tries to find a material within this material list with the given name.
@@ -47,4 +502,213 @@ impl ChunkMaterialList {
     pub fn find_material(&self, name: &str) -> Option<u16> {
         self.material_keys.get(name).copied()
     }
+
+    /// Records a change to this material list so that it can be reported.
+    fn push_change(
+        &mut self,
+        index: u16,
+        change: ChunkMaterialChange,
+        old_material: Option<Handle<StandardMaterial>>,
+    ) {
+        self.pending_changes.push(PendingMaterialChange {
+            index,
+            change,
+            old_material,
+        });
+    }
+
+    /// Takes all changes that have been made since the last time this method
+    /// was called.
+    pub(crate) fn take_changes(&mut self) -> Vec<PendingMaterialChange> {
+        std::mem::take(&mut self.pending_changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::asset::HandleId;
+    use bevy::render::render_resource::PrimitiveTopology;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn remove_and_replace_materials() {
+        let mut list = ChunkMaterialList::default();
+        let stone = Handle::<StandardMaterial>::weak(HandleId::random::<StandardMaterial>());
+        let dirt = Handle::<StandardMaterial>::weak(HandleId::random::<StandardMaterial>());
+        let grass = Handle::<StandardMaterial>::weak(HandleId::random::<StandardMaterial>());
+
+        let a = list.add_material(stone.clone(), Some("stone".to_string()));
+        let b = list.add_material(dirt.clone(), None);
+        assert_eq!(list.index_of(&dirt), Some(b));
+
+        assert_eq!(list.replace_material(b, grass.clone()), Some(dirt.clone()));
+        assert_eq!(list.index_of(&dirt), None);
+        assert_eq!(list.index_of(&grass), Some(b));
+
+        assert_eq!(list.remove_material(a), Some(stone));
+        assert_eq!(list.try_get_material(a), None);
+        assert_eq!(list.find_material("stone"), None);
+        assert_eq!(list.iter().count(), 1);
+
+        let changes: Vec<_> = list.take_changes().iter().map(|c| c.change).collect();
+        assert_eq!(changes, vec![
+            ChunkMaterialChange::Added,
+            ChunkMaterialChange::Added,
+            ChunkMaterialChange::Replaced,
+            ChunkMaterialChange::Removed,
+        ]);
+
+        // Settings that do not change anything are not reported.
+        list.set_settings(b, list.get_settings(b));
+        assert!(list.take_changes().is_empty());
+
+        let settings = ChunkMaterialSettings {
+            unlit: true,
+            ..default()
+        };
+        list.set_settings(b, settings);
+        list.set_settings(b, settings);
+        let changes: Vec<_> = list.take_changes().iter().map(|c| c.change).collect();
+        assert_eq!(changes, vec![ChunkMaterialChange::SettingsChanged]);
+    }
+
+    #[test]
+    fn stagger_mesh_uploads() {
+        let pending = |index: u32, vertices: usize| {
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; vertices]);
+            PendingChunkMeshes {
+                chunk_id:  Entity::from_raw(index),
+                meshes:    vec![(mesh, Handle::default())],
+                transform: Transform::IDENTITY,
+                builds:    1,
+            }
+        };
+
+        let mut queue = ChunkMeshUploadQueue::default();
+        queue.push(pending(0, 60));
+        queue.push(pending(1, 30));
+        queue.push(pending(2, 50));
+        queue.push(pending(0, 40));
+        assert_eq!(queue.len(), 3);
+
+        let settings = ChunkMeshUploadSettings {
+            max_vertices_per_frame: 75,
+            ..default()
+        };
+
+        let mut frame = vec![];
+        let mut vertices = 0;
+        while let Some(next) = queue.pop_within(&settings, vertices, 0) {
+            vertices += next.vertex_count();
+            frame.push(next.chunk_id.index());
+        }
+        assert_eq!(frame, vec![1]);
+
+        let next = queue.pop_within(&settings, 0, 0).unwrap();
+        assert_eq!(next.chunk_id.index(), 2);
+        assert_eq!(next.byte_size(), 50 * 12);
+        assert!(queue.contains(Entity::from_raw(0)));
+
+        let next = queue.pop_within(&settings, 0, 0).unwrap();
+        assert_eq!(next.chunk_id.index(), 0);
+        assert_eq!(next.builds, 2);
+    }
+}
+
+/// The settings of the [`ChunkSeamDebugPlugin`](crate::ChunkSeamDebugPlugin).
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct ChunkSeamDebugSettings {
+    /// If true, a warning is logged for each chunk seam that contains
+    /// mismatched faces when it is validated.
+    ///
+    /// Defaults to `true`.
+    pub log_mismatches: bool,
+
+    /// If true, all mismatched faces are highlighted using gizmos.
+    ///
+    /// Defaults to `true`.
+    pub draw_gizmos: bool,
+}
+
+impl Default for ChunkSeamDebugSettings {
+    fn default() -> Self {
+        Self {
+            log_mismatches: true,
+            draw_gizmos:    true,
+        }
+    }
+}
+
+/// This resource contains all mismatched faces along the seams between meshed
+/// chunks that were found by the
+/// [`ChunkSeamDebugPlugin`](crate::ChunkSeamDebugPlugin).
+///
+/// A seam is validated again whenever either of its chunks is remeshed, and is
+/// removed once either of its chunks is despawned.
+#[derive(Debug, Resource, Default)]
+pub struct ChunkSeamReport {
+    /// The mismatched faces of each seam, keyed by the world id, the chunk
+    /// coordinates of the chunk on the negative side of the seam, and the
+    /// positive face of that chunk that touches the seam.
+    seams: HashMap<(Entity, IVec3, BlockOcclusion), Vec<SeamMismatch>>,
+}
+
+impl ChunkSeamReport {
+    /// Gets an iterator over all mismatched faces, along with the id of the
+    /// world they are in.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &SeamMismatch)> {
+        self.seams
+            .iter()
+            .flat_map(|((world_id, ..), mismatches)| mismatches.iter().map(|m| (*world_id, m)))
+    }
+
+    /// Gets the total number of mismatched faces.
+    pub fn len(&self) -> usize {
+        self.seams.values().map(|mismatches| mismatches.len()).sum()
+    }
+
+    /// Checks whether no mismatched faces were found.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replaces the mismatched faces of the seam between the given chunk and
+    /// its neighbor in the direction of the given face.
+    pub(crate) fn set_seam(
+        &mut self,
+        world_id: Entity,
+        chunk_coords: IVec3,
+        face: BlockOcclusion,
+        mismatches: Vec<SeamMismatch>,
+    ) {
+        let key = match face.into_offset().cmplt(IVec3::ZERO).any() {
+            true => {
+                (
+                    world_id,
+                    chunk_coords + face.into_offset(),
+                    face.opposite_face(),
+                )
+            },
+            false => (world_id, chunk_coords, face),
+        };
+
+        if mismatches.is_empty() {
+            self.seams.remove(&key);
+        } else {
+            self.seams.insert(key, mismatches);
+        }
+    }
+
+    /// Removes all seams that touch the given chunk.
+    pub(crate) fn remove_chunk(&mut self, world_id: Entity, chunk_coords: IVec3) {
+        self.seams.retain(|(seam_world, seam_coords, face), _| {
+            *seam_world != world_id
+                || (*seam_coords != chunk_coords
+                    && *seam_coords + face.into_offset() != chunk_coords)
+        });
+    }
 }
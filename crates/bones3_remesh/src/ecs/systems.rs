@@ -1,97 +1,1227 @@
 //! This module contains systems that will automatically trigger chunks marked
 //! as dirty to be remeshed and keeping everything up to date.
 
+use std::time::Instant;
+
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bones3_core::prelude::Region;
+use bevy::render::render_resource::Face;
+use bevy::utils::{HashMap, HashSet};
+use bones3_core::math::BlockFace;
 use bones3_core::query::VoxelQuery;
-use bones3_core::storage::{BlockData, VoxelChunk, VoxelStorage};
-use bones3_core::util::anchor::ChunkAnchorRecipient;
+use bones3_core::storage::{
+    BlockData,
+    BlockScale,
+    ChunkDespawned,
+    NeighborhoodCache,
+    UpAxis,
+    VoxelChunk,
+    VoxelStorage,
+    VoxelWorld,
+};
+use bones3_core::util::anchor::{ChunkAnchor, ChunkAnchorRecipient};
+use bones3_core::util::chunk_transform::chunk_transform;
+use bones3_core::util::stats::Bones3StatsCollector;
 use ordered_float::OrderedFloat;
 use priority_queue::PriorityQueue;
 
-use super::components::{ChunkMesh, RemeshChunk};
-use super::resources::ChunkMaterialList;
-use crate::mesh::block_model::BlockShape;
-use crate::mesh::builder;
-use crate::RemeshAnchor;
+use super::components::{
+    BoundaryFaces,
+    ChunkFadeIn,
+    ChunkImposter,
+    ChunkMesh,
+    ChunkMeshInfo,
+    ChunkMeshOrigin,
+    ChunkMeshPending,
+    ChunkMeshTime,
+    ChunkMesher,
+    ImposterSourceHandler,
+    QuantizedChunkMesh,
+    RemeshChunk,
+};
+use super::resources::{
+    ChunkFadeSettings,
+    ChunkMaterialChange,
+    ChunkMaterialChanged,
+    ChunkMaterialList,
+    ChunkMeshBudget,
+    ChunkMeshUploadQueue,
+    ChunkMeshUploadSettings,
+    ChunkSeamDebugSettings,
+    ChunkSeamReport,
+    ImposterMaterial,
+    ImposterSettings,
+    PendingChunkMeshes,
+};
+use crate::mesh::block_model::{BlockOcclusion, BlockShape};
+use crate::mesh::builder::ChunkMeshHandles;
+use crate::mesh::direct_upload::DirectChunkMeshes;
+use crate::mesh::imposter::{build_imposter_mesh, imposter_ring};
+use crate::mesh::quantized_material::{QuantizedChunkMaterial, QuantizedChunkMaterials};
+use crate::mesh::seams::SeamMismatchKind;
+use crate::mesh::smooth::BlockDensity;
+use crate::mesh::{builder, seams, smooth};
+use crate::vertex_data::ShapeBuilder;
+use crate::{ImposterAnchor, RemeshAnchor};
 
 // pub(crate) fn push_chunk_async_queue<T>(
 //     active_tasks: Query<(Entity, &RemeshChunkTask<T>)>,
 //     chunks: Query<(&VoxelStorage)>,
 // )
 
-/// This system remeshes dirty voxel chunks. For all chunks with the RemeshChunk
-/// component, each frame, the chunk with the highest priority value
-/// will be selected for mesh generation.
-pub fn remesh_dirty_chunks<T>(
+/// The system parameters that are shared between all chunk remeshing systems.
+#[derive(SystemParam)]
+pub struct RemeshParams<'w, 's, T>
+where
+    T: BlockData,
+{
+    /// All chunks that are currently marked as dirty.
     dirty_chunks: Query<
-        (&ChunkAnchorRecipient<RemeshAnchor>, &VoxelChunk, Entity),
+        'w,
+        's,
+        (
+            &'static ChunkAnchorRecipient<RemeshAnchor>,
+            &'static VoxelChunk,
+            Entity,
+        ),
         (With<RemeshChunk>, With<VoxelStorage<T>>),
     >,
-    chunk_data: VoxelQuery<&VoxelStorage<T>>,
-    chunk_meshes: Query<(Entity, &Parent), With<ChunkMesh>>,
-    materials: Res<ChunkMaterialList>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut commands: Commands,
-) where
-    T: BlockData + BlockShape,
+
+    /// The chunk mesher that is selected for each voxel world.
+    worlds: Query<'w, 's, &'static ChunkMesher, With<VoxelWorld>>,
+
+    /// The chunk mesh origin that is selected for each voxel world.
+    mesh_origins: Query<'w, 's, &'static ChunkMeshOrigin, With<VoxelWorld>>,
+
+    /// The boundary face setting that is selected for each voxel world.
+    boundaries: Query<'w, 's, &'static BoundaryFaces, With<VoxelWorld>>,
+
+    /// The block data of all chunks.
+    chunk_data: VoxelQuery<'w, 's, &'static VoxelStorage<T>>,
+
+    /// The cached neighborhoods of chunks that were assembled this frame.
+    neighborhoods: ResMut<'w, NeighborhoodCache<T>>,
+
+    /// The list of materials that are used by blocks.
+    materials: Res<'w, ChunkMaterialList>,
+
+    /// The queue of chunk meshes that are waiting to be uploaded.
+    upload_queue: ResMut<'w, ChunkMeshUploadQueue>,
+
+    /// The settings that determine the vertex format of chunk meshes.
+    upload_settings: Res<'w, ChunkMeshUploadSettings>,
+
+    /// The budget that limits the number of chunks remeshed each frame.
+    budget: ResMut<'w, ChunkMeshBudget>,
+
+    /// The statistics collector, if statistics are enabled.
+    stats: Option<ResMut<'w, Bones3StatsCollector>>,
+
+    /// The Bevy command queue.
+    commands: Commands<'w, 's>,
+}
+
+impl<'w, 's, T> RemeshParams<'w, 's, T>
+where
+    T: BlockData,
 {
-    let max_chunks = 4;
+    /// Remeshes the dirty chunks with the highest priority values that are
+    /// within a world using one of the given chunk meshers, using the provided
+    /// mesh builder function.
+    ///
+    /// The number of chunks is limited by the [`ChunkMeshBudget`].
+    fn remesh<B>(&mut self, meshers: &[ChunkMesher], build: B)
+    where
+        B: for<'a> Fn(
+            ChunkMesher,
+            &dyn Fn(IVec3) -> T,
+            &dyn Fn(IVec3) -> bool,
+            BoundaryFaces,
+            &'a ChunkMaterialList,
+        ) -> ShapeBuilder<'a>,
+    {
+        let max_chunks = self.budget.max_chunks();
+        let chunks = get_max_chunks(&self.dirty_chunks, &self.worlds, meshers, max_chunks);
 
-    for (chunk_coords, chunk_id, world_id) in get_max_chunks(&dirty_chunks, max_chunks) {
-        let data_region = Region::from_points(IVec3::NEG_ONE, IVec3::ONE);
-        let world_data_query = chunk_data.get_world(world_id).unwrap();
+        for (chunk_coords, chunk_id, world_id, mesher) in chunks {
+            let world_data_query = self.chunk_data.get_world(world_id).unwrap();
+            let neighborhood = self
+                .neighborhoods
+                .get_or_insert(world_id, chunk_coords, |coords| {
+                    world_data_query.get_chunk(coords)
+                });
+            let get_block = |block_pos: IVec3| neighborhood.get_block(block_pos);
+            let is_loaded = |block_pos: IVec3| neighborhood.get_chunk(block_pos >> 4).is_some();
 
-        let data = data_region
-            .iter()
-            .map(|offset| world_data_query.get_chunk(chunk_coords + offset))
-            .collect::<Vec<Option<&VoxelStorage<T>>>>();
-
-        let get_block = |block_pos: IVec3| {
-            let chunk_index = data_region.point_to_index(block_pos >> 4).unwrap();
-            match &data[chunk_index] {
-                Some(chunk) => chunk.get_block(block_pos),
-                None => T::default(),
+            let origin = self.mesh_origins.get(world_id).copied().unwrap_or_default();
+            let boundary = self.boundaries.get(world_id).copied().unwrap_or_default();
+
+            let start = Instant::now();
+            let mut shape_builder =
+                build(mesher, &get_block, &is_loaded, boundary, &self.materials);
+            shape_builder.offset_vertices(origin.vertex_offset(chunk_coords));
+            let mesh_info = ChunkMeshInfo::new(shape_builder.material_indices().iter().copied());
+            let meshes = match self.upload_settings.quantize_vertices {
+                true => shape_builder.into_quantized_meshes().collect(),
+                false => shape_builder.into_meshes().collect(),
+            };
+
+            let duration = start.elapsed();
+            self.budget.record(duration);
+
+            if let Some(stats) = self.stats.as_mut() {
+                stats.record_chunk_meshed(duration);
             }
-        };
 
-        commands.entity(chunk_id).remove::<RemeshChunk>();
+            self.commands
+                .entity(chunk_id)
+                .remove::<RemeshChunk>()
+                .insert((ChunkMeshTime(duration), mesh_info))
+                .add(|chunk_id, world: &mut World| queue_pending_build(chunk_id, world));
 
-        let shape_builder = builder::build_chunk_mesh(get_block, &materials);
-        builder::apply_shape_builder(
-            chunk_id,
-            shape_builder,
+            self.upload_queue.push(PendingChunkMeshes {
+                chunk_id,
+                meshes,
+                transform: origin.mesh_transform(chunk_coords),
+                builds: 1,
+            });
+        }
+    }
+}
+
+/// This system remeshes dirty voxel chunks. For all chunks with the RemeshChunk
+/// component, each frame, the chunk with the highest priority value
+/// will be selected for mesh generation.
+///
+/// Only chunks within worlds that use the [`ChunkMesher::Blocks`] mesher are
+/// handled by this system.
+pub fn remesh_dirty_chunks<T>(mut params: RemeshParams<T>)
+where
+    T: BlockData + BlockShape,
+{
+    params.remesh(
+        &[ChunkMesher::Blocks],
+        |_, get_block, is_loaded, boundary, materials| {
+            builder::build_bounded_chunk_mesh(get_block, is_loaded, boundary, materials)
+        },
+    );
+}
+
+/// This system remeshes dirty voxel chunks as smooth surfaces. For all chunks
+/// with the RemeshChunk component, each frame, the chunk with the highest
+/// priority value will be selected for mesh generation.
+///
+/// Only chunks within worlds that use the [`ChunkMesher::SurfaceNets`] or
+/// [`ChunkMesher::DualContouring`] meshers are handled by this system.
+pub fn remesh_dirty_smooth_chunks<T>(mut params: RemeshParams<T>)
+where
+    T: BlockDensity,
+{
+    let meshers = [ChunkMesher::SurfaceNets, ChunkMesher::DualContouring];
+    params.remesh(&meshers, |mesher, get_block, _, _, materials| {
+        match mesher {
+            ChunkMesher::DualContouring => smooth::build_dual_contouring_mesh(get_block, materials),
+            _ => smooth::build_surface_nets_mesh(get_block, materials),
+        }
+    });
+}
+
+/// This system uploads queued chunk meshes to the mesh asset storage, and
+/// replaces the existing chunk meshes of their chunks, until the upload budget
+/// within the [`ChunkMeshUploadSettings`] is used up for this frame.
+///
+/// The mesh entities and mesh assets of chunks that already have meshes are
+/// reused, so constantly remeshed chunks do not churn through mesh assets. If
+/// the [`DirectChunkMeshPlugin`](crate::DirectChunkMeshPlugin) is in use, the
+/// meshes are queued within the [`DirectChunkMeshes`] resource instead of
+/// being stored as mesh assets.
+///
+/// Chunks that have been despawned since their meshes were built are skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn upload_chunk_meshes(
+    settings: Res<ChunkMeshUploadSettings>,
+    fade_settings: Option<Res<ChunkFadeSettings>>,
+    mut upload_queue: ResMut<ChunkMeshUploadQueue>,
+    chunks: Query<(), With<VoxelChunk>>,
+    chunk_meshes: Query<ChunkMeshHandles, With<ChunkMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut direct_meshes: Option<ResMut<DirectChunkMeshes>>,
+    mut commands: Commands,
+) {
+    let mut vertices = 0;
+    let mut bytes = 0;
+
+    while let Some(pending) = upload_queue.pop_within(&settings, vertices, bytes) {
+        if !chunks.contains(pending.chunk_id) {
+            continue;
+        }
+
+        vertices += pending.vertex_count();
+        bytes += pending.byte_size();
+
+        let builds = pending.builds;
+        commands
+            .entity(pending.chunk_id)
+            .add(move |chunk_id, world: &mut World| finish_pending_builds(chunk_id, world, builds));
+
+        let had_mesh = chunk_meshes
+            .iter()
+            .any(|(_, parent, ..)| parent.get() == pending.chunk_id);
+
+        let mesh_count = builder::replace_chunk_meshes(
+            pending.chunk_id,
+            pending.meshes,
+            pending.transform,
             &chunk_meshes,
             &mut meshes,
+            direct_meshes.as_deref_mut(),
             &mut commands,
         );
+
+        if let Some(fade_settings) = fade_settings.as_ref() {
+            if !had_mesh && mesh_count > 0 {
+                commands
+                    .entity(pending.chunk_id)
+                    .insert(ChunkFadeIn::new(fade_settings.duration));
+            }
+        }
+    }
+}
+
+/// This system releases the direct meshes of all chunk mesh entities that
+/// have been despawned, so that their meshes are removed from the render world.
+pub fn release_direct_chunk_meshes(
+    mut removed_chunk_meshes: RemovedComponents<ChunkMesh>,
+    mut direct_meshes: ResMut<DirectChunkMeshes>,
+) {
+    for chunk_mesh_id in removed_chunk_meshes.iter() {
+        direct_meshes.release(chunk_mesh_id);
+    }
+}
+
+/// Counts a new build of the meshes of the given chunk as pending.
+fn queue_pending_build(chunk_id: Entity, world: &mut World) {
+    let Some(mut chunk) = world.get_entity_mut(chunk_id) else {
+        return;
+    };
+
+    match chunk.get_mut::<ChunkMeshPending>() {
+        Some(mut pending) => pending.0 += 1,
+        None => {
+            chunk.insert(ChunkMeshPending(1));
+        },
+    }
+}
+
+/// Removes the given number of uploaded builds from the pending builds of the
+/// given chunk, removing the [`ChunkMeshPending`] component once all builds
+/// have been uploaded.
+fn finish_pending_builds(chunk_id: Entity, world: &mut World, builds: u32) {
+    let Some(mut chunk) = world.get_entity_mut(chunk_id) else {
+        return;
+    };
+
+    let Some(mut pending) = chunk.get_mut::<ChunkMeshPending>() else {
+        return;
+    };
+
+    pending.0 = pending.0.saturating_sub(builds);
+    if pending.0 == 0 {
+        chunk.remove::<ChunkMeshPending>();
     }
 }
 
-/// Gets the highest priority chunks to remesh.
+/// Gets the highest priority chunks to remesh that are within a world using
+/// one of the given chunk meshers.
 fn get_max_chunks<T>(
     chunks: &Query<
         (&ChunkAnchorRecipient<RemeshAnchor>, &VoxelChunk, Entity),
         (With<RemeshChunk>, With<VoxelStorage<T>>),
     >,
+    worlds: &Query<&ChunkMesher, With<VoxelWorld>>,
+    meshers: &[ChunkMesher],
     max_chunks: usize,
-) -> impl Iterator<Item = (IVec3, Entity, Entity)>
+) -> impl Iterator<Item = (IVec3, Entity, Entity, ChunkMesher)>
 where
-    T: BlockData + BlockShape,
+    T: BlockData,
 {
     let mut queue = PriorityQueue::new();
 
     for (anchor_recipient, chunk_meta, chunk_id) in chunks.iter() {
+        let world_mesher = worlds
+            .get(chunk_meta.world_id())
+            .copied()
+            .unwrap_or_default();
+        if !meshers.contains(&world_mesher) {
+            continue;
+        }
+
         let priority = match anchor_recipient.priority {
             Some(p) => p,
             None => f32::NEG_INFINITY,
         };
 
         queue.push(
-            (chunk_meta.chunk_coords(), chunk_id, chunk_meta.world_id()),
+            (
+                chunk_meta.chunk_coords(),
+                chunk_id,
+                chunk_meta.world_id(),
+                world_mesher,
+            ),
             OrderedFloat::from(priority),
         );
     }
 
     queue.into_sorted_iter().take(max_chunks).map(|(e, _)| e)
 }
+
+/// This system writes the render settings within the chunk material list to
+/// the corresponding material assets whenever the material list is modified.
+pub fn apply_chunk_material_settings(
+    material_list: Res<ChunkMaterialList>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !material_list.is_changed() {
+        return;
+    }
+
+    for (handle, settings) in material_list.iter() {
+        let cull_mode = match settings.double_sided {
+            true => None,
+            false => Some(Face::Back),
+        };
+
+        let Some(material) = materials.get(handle) else {
+            continue;
+        };
+
+        if material.double_sided == settings.double_sided
+            && material.cull_mode == cull_mode
+            && material.unlit == settings.unlit
+            && material.alpha_mode == settings.alpha_mode
+        {
+            continue;
+        }
+
+        let material = materials.get_mut(handle).unwrap();
+        material.double_sided = settings.double_sided;
+        material.cull_mode = cull_mode;
+        material.unlit = settings.unlit;
+        material.alpha_mode = settings.alpha_mode;
+    }
+}
+
+/// This system reports all changes made to the chunk material list.
+///
+/// Chunk meshes that use a replaced material have their material handle
+/// swapped in place, including quantized chunk meshes. Chunks whose
+/// [`ChunkMeshInfo`] shows that they use an added or removed material index are
+/// marked for remeshing, while all other chunks are left untouched.
+pub fn report_chunk_material_changes(
+    mut material_list: ResMut<ChunkMaterialList>,
+    mut chunk_meshes: Query<&mut Handle<StandardMaterial>, With<ChunkMesh>>,
+    mut quantized_meshes: Query<&mut QuantizedChunkMesh>,
+    chunks: Query<(Entity, &ChunkMeshInfo)>,
+    mut events: EventWriter<ChunkMaterialChanged>,
+    mut commands: Commands,
+) {
+    let changes = material_list.bypass_change_detection().take_changes();
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut remeshed_indices = HashSet::new();
+    for change in changes.iter() {
+        match change.change {
+            ChunkMaterialChange::Added | ChunkMaterialChange::Removed => {
+                remeshed_indices.insert(change.index);
+            },
+            ChunkMaterialChange::Replaced => {
+                let (Some(old_material), Some(new_material)) = (
+                    change.old_material.as_ref(),
+                    material_list.try_get_material(change.index),
+                ) else {
+                    continue;
+                };
+
+                for mut material in chunk_meshes.iter_mut() {
+                    if *material == *old_material {
+                        *material = new_material.clone();
+                    }
+                }
+
+                for mut quantized in quantized_meshes.iter_mut() {
+                    if quantized.0 == *old_material {
+                        quantized.0 = new_material.clone();
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    if !remeshed_indices.is_empty() {
+        for (chunk_id, mesh_info) in chunks.iter() {
+            let uses_material = mesh_info
+                .material_indices()
+                .iter()
+                .any(|index| remeshed_indices.contains(index));
+
+            if !uses_material {
+                continue;
+            }
+
+            if let Some(mut chunk) = commands.get_entity(chunk_id) {
+                chunk.insert(RemeshChunk);
+            }
+        }
+    }
+
+    events.send_batch(changes.into_iter().map(|change| {
+        ChunkMaterialChanged {
+            index:  change.index,
+            change: change.change,
+        }
+    }));
+}
+
+/// This system watches for modifications to the material assets within the
+/// chunk material list, and to the textures that they use.
+///
+/// Bevy does not rebuild a material when one of its textures is reloaded, so
+/// all materials that use a modified texture are marked as modified in order
+/// for the new texture to show up. A [`ChunkMaterialChanged`] event is sent
+/// for each modified material.
+pub fn refresh_modified_chunk_materials(
+    material_list: Res<ChunkMaterialList>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut material_events: EventReader<AssetEvent<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut events: EventWriter<ChunkMaterialChanged>,
+) {
+    let modified_images: Vec<_> = image_events
+        .iter()
+        .filter_map(|event| {
+            match event {
+                AssetEvent::Modified {
+                    handle,
+                } => Some(handle.clone()),
+                _ => None,
+            }
+        })
+        .collect();
+
+    let mut modified_indices = HashSet::new();
+    for event in material_events.iter() {
+        if let AssetEvent::Modified {
+            handle,
+        } = event
+        {
+            if let Some(index) = material_list.index_of(handle) {
+                modified_indices.insert(index);
+            }
+        }
+    }
+
+    if !modified_images.is_empty() {
+        for (handle, _) in material_list.iter() {
+            let Some(material) = materials.get(handle) else {
+                continue;
+            };
+
+            let uses_image = [
+                &material.base_color_texture,
+                &material.emissive_texture,
+                &material.metallic_roughness_texture,
+                &material.normal_map_texture,
+                &material.occlusion_texture,
+            ]
+            .into_iter()
+            .flatten()
+            .any(|texture| modified_images.contains(texture));
+
+            if uses_image {
+                // Accessing the material mutably marks it as modified, which
+                // causes it to be prepared again with the new texture.
+                materials.get_mut(handle);
+            }
+        }
+    }
+
+    events.send_batch(modified_indices.into_iter().map(|index| {
+        ChunkMaterialChanged {
+            index,
+            change: ChunkMaterialChange::AssetModified,
+        }
+    }));
+}
+
+/// This system gives every quantized chunk mesh the quantized chunk material
+/// that was created from its chunk material, and copies all modifications
+/// made to chunk materials into their quantized chunk materials.
+///
+/// Quantized chunk meshes whose chunk material has not been loaded yet receive
+/// their quantized chunk material once it finishes loading.
+pub fn sync_quantized_chunk_materials(
+    mut quantized: ResMut<QuantizedChunkMaterials>,
+    mut material_events: EventReader<AssetEvent<StandardMaterial>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut quantized_materials: ResMut<Assets<QuantizedChunkMaterial>>,
+    mut chunk_meshes: Query<(Ref<QuantizedChunkMesh>, &mut Handle<QuantizedChunkMaterial>)>,
+) {
+    let mut loaded = HashSet::new();
+    for event in material_events.iter() {
+        match event {
+            AssetEvent::Created {
+                handle,
+            } => {
+                loaded.insert(handle.clone());
+            },
+            AssetEvent::Modified {
+                handle,
+            } => quantized.update(handle, &standard_materials, &mut quantized_materials),
+            AssetEvent::Removed {
+                handle,
+            } => quantized.remove(handle),
+        }
+    }
+
+    for (source, mut material) in chunk_meshes.iter_mut() {
+        if !source.is_changed() && !loaded.contains(&source.0) {
+            continue;
+        }
+
+        let handle =
+            quantized.get_or_create(&source.0, &standard_materials, &mut quantized_materials);
+        *material = handle.unwrap_or_default();
+    }
+}
+
+/// This system marks all chunks within a voxel world for remeshing whenever
+/// the [`ChunkMeshOrigin`] or [`BoundaryFaces`] of that world is changed.
+pub fn remesh_on_mesh_origin_change(
+    worlds: Query<
+        (),
+        (
+            Or<(Changed<ChunkMeshOrigin>, Changed<BoundaryFaces>)>,
+            With<VoxelWorld>,
+        ),
+    >,
+    chunks: Query<(Entity, &VoxelChunk)>,
+    mut commands: Commands,
+) {
+    if worlds.is_empty() {
+        return;
+    }
+
+    for (chunk_id, chunk_meta) in chunks.iter() {
+        if worlds.contains(chunk_meta.world_id()) {
+            commands.entity(chunk_id).insert(RemeshChunk);
+        }
+    }
+}
+
+/// This system marks the neighbors of all despawned chunks for remeshing within
+/// worlds that use closed [`BoundaryFaces`], so that the boundary faces that
+/// were hidden by the despawned chunk are generated.
+pub fn remesh_despawned_chunk_neighbors(
+    mut despawned: EventReader<ChunkDespawned>,
+    worlds: Query<&BoundaryFaces, With<VoxelWorld>>,
+    chunks: VoxelQuery<Entity>,
+    mut commands: Commands,
+) {
+    for event in despawned.iter() {
+        let boundary = worlds.get(event.world_id).copied().unwrap_or_default();
+        if boundary == BoundaryFaces::Empty {
+            continue;
+        }
+
+        let Ok(world) = chunks.get_world(event.world_id) else {
+            continue;
+        };
+
+        for offset in BlockFace::ALL.map(BlockFace::normal) {
+            let Some(chunk_id) = world.get_chunk(event.chunk_coords + offset) else {
+                continue;
+            };
+
+            if let Some(mut chunk) = commands.get_entity(chunk_id) {
+                chunk.insert(RemeshChunk);
+            }
+        }
+    }
+}
+
+/// This system advances the fade in animation of all newly meshed chunks, and
+/// scales their chunk meshes up from the center of the chunk to match.
+///
+/// Once the animation is finished, the chunk meshes are reset to their normal
+/// transform and the animation component is removed.
+pub fn animate_chunk_fade_in(
+    time: Res<Time>,
+    mut chunks: Query<(Entity, &mut ChunkFadeIn, &Children, Option<&VoxelChunk>)>,
+    worlds: Query<&ChunkMeshOrigin, With<VoxelWorld>>,
+    mut chunk_meshes: Query<&mut Transform, With<ChunkMesh>>,
+    mut commands: Commands,
+) {
+    for (chunk_id, mut fade, children, chunk_meta) in chunks.iter_mut() {
+        fade.elapsed += time.delta_seconds();
+
+        let base_transform = match chunk_meta {
+            Some(chunk_meta) => {
+                let origin = worlds
+                    .get(chunk_meta.world_id())
+                    .copied()
+                    .unwrap_or_default();
+                origin.mesh_transform(chunk_meta.chunk_coords())
+            },
+            None => Transform::IDENTITY,
+        };
+
+        let scale = fade.scale();
+        let transform = Transform::from_translation(Vec3::splat(8.0) * (1.0 - scale))
+            .with_scale(Vec3::splat(scale))
+            .mul_transform(base_transform);
+
+        let mut iter = chunk_meshes.iter_many_mut(children);
+        while let Some(mut mesh_transform) = iter.fetch_next() {
+            *mesh_transform = transform;
+        }
+
+        if fade.progress() >= 1.0 {
+            commands.entity(chunk_id).remove::<ChunkFadeIn>();
+        }
+    }
+}
+
+/// This system validates the seams between all recently remeshed chunks and
+/// their meshed neighbors, storing any mismatched faces within the
+/// [`ChunkSeamReport`].
+///
+/// Chunks that have been despawned are removed from the report.
+pub fn validate_chunk_seams<T>(
+    mut remeshed: RemovedComponents<RemeshChunk>,
+    mut despawned: EventReader<ChunkDespawned>,
+    chunks: Query<&VoxelChunk>,
+    chunk_data: VoxelQuery<&VoxelStorage<T>, Without<RemeshChunk>>,
+    settings: Res<ChunkSeamDebugSettings>,
+    mut report: ResMut<ChunkSeamReport>,
+) where
+    T: BlockData + BlockShape,
+{
+    for event in despawned.iter() {
+        report.remove_chunk(event.world_id, event.chunk_coords);
+    }
+
+    let remeshed: HashSet<Entity> = remeshed.iter().collect();
+    for chunk_meta in chunks.iter_many(remeshed) {
+        let world_id = chunk_meta.world_id();
+        let chunk_coords = chunk_meta.chunk_coords();
+        let Ok(world) = chunk_data.get_world(world_id) else {
+            continue;
+        };
+
+        let Some(storage) = world.get_chunk(chunk_coords) else {
+            continue;
+        };
+
+        for face in BlockOcclusion::all().iter() {
+            let neighbor_coords = chunk_coords + face.into_offset();
+            let Some(neighbor) = world.get_chunk(neighbor_coords) else {
+                continue;
+            };
+
+            let get_block = |block_pos: IVec3| {
+                match block_pos >> 4 == chunk_coords {
+                    true => storage.get_block(block_pos),
+                    false => neighbor.get_block(block_pos),
+                }
+            };
+
+            let mismatches = seams::find_seam_mismatches(chunk_coords, face, get_block);
+            if settings.log_mismatches {
+                if let Some(first) = mismatches.first() {
+                    warn!(
+                        "Found {} mismatched faces along the seam between chunks {chunk_coords} \
+                         and {neighbor_coords} in world {world_id:?}, including a {:?} at {}",
+                        mismatches.len(),
+                        first.kind,
+                        first.block_coords
+                    );
+                }
+            }
+
+            report.set_seam(world_id, chunk_coords, face, mismatches);
+        }
+    }
+}
+
+/// This system highlights all mismatched faces within the [`ChunkSeamReport`]
+/// using gizmos.
+pub fn draw_chunk_seam_mismatches(
+    settings: Res<ChunkSeamDebugSettings>,
+    report: Res<ChunkSeamReport>,
+    worlds: Query<(&GlobalTransform, Option<&BlockScale>), With<VoxelWorld>>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.draw_gizmos {
+        return;
+    }
+
+    for (world_id, mismatch) in report.iter() {
+        let Ok((transform, block_scale)) = worlds.get(world_id) else {
+            continue;
+        };
+
+        let block_scale = block_scale.copied().unwrap_or_default();
+        let normal = mismatch.face.into_offset().as_vec3();
+        let center = mismatch.block_coords.as_vec3() + Vec3::splat(0.5) + normal * 0.5;
+        let position = transform.transform_point(block_scale.to_local_space(center));
+        let rotation =
+            transform.to_scale_rotation_translation().1 * Quat::from_rotation_arc(Vec3::Z, normal);
+
+        let color = match mismatch.kind {
+            SeamMismatchKind::Hole => Color::RED,
+            SeamMismatchKind::HiddenFaceRendered => Color::ORANGE,
+        };
+
+        gizmos.rect(position, rotation, Vec2::splat(block_scale.0 * 0.9), color);
+    }
+}
+
+/// This system spawns and despawns the far-field imposters of all chunk columns
+/// within the imposter ring of each imposter anchor.
+///
+/// Missing imposters are built closest first, up to the limit within the
+/// [`ImposterSettings`]. Imposters of chunk columns that are no longer within
+/// any imposter ring are despawned, and all imposters of a world are rebuilt
+/// whenever its [`ImposterSourceHandler`] changes.
+///
+/// The imposter rings are only recomputed when the coordinates or radius of an
+/// imposter anchor change, when an imposter source or the imposter settings
+/// change, or while imposters are still waiting to be built.
+#[allow(clippy::too_many_arguments)]
+pub fn update_imposters(
+    settings: Res<ImposterSettings>,
+    anchors: Query<&ChunkAnchor<ImposterAnchor>>,
+    worlds: Query<
+        (
+            Ref<ImposterSourceHandler>,
+            Option<&UpAxis>,
+            Option<&BlockScale>,
+        ),
+        With<VoxelWorld>,
+    >,
+    imposters: Query<(Entity, &ChunkImposter)>,
+    material: Res<ImposterMaterial>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut removed_sources: RemovedComponents<ImposterSourceHandler>,
+    mut last_anchors: Local<Vec<(Entity, Option<IVec3>, UVec3)>>,
+    mut has_pending: Local<bool>,
+    mut commands: Commands,
+) {
+    let anchor_state: Vec<_> = anchors
+        .iter()
+        .map(|anchor| (anchor.world_id, anchor.coords, anchor.radius))
+        .collect();
+    let sources_changed =
+        !removed_sources.is_empty() || worlds.iter().any(|(source, ..)| source.is_changed());
+    removed_sources.clear();
+
+    if !*has_pending && !sources_changed && !settings.is_changed() && anchor_state == *last_anchors
+    {
+        return;
+    }
+    *last_anchors = anchor_state;
+
+    let mut desired: HashMap<(Entity, IVec2), f32> = HashMap::new();
+    for anchor in anchors.iter() {
+        let (Some(coords), Ok((_, up, _))) = (anchor.coords, worlds.get(anchor.world_id)) else {
+            continue;
+        };
+
+        let up = up.copied().unwrap_or_default();
+        let center = up.column(coords);
+        let radius = up.column(anchor.radius.as_ivec3()).as_uvec2();
+        for chunk_column in imposter_ring(center, radius, settings.inner_radius) {
+            let distance = (chunk_column - center).as_vec2().length();
+            desired
+                .entry((anchor.world_id, chunk_column))
+                .and_modify(|d| *d = d.min(distance))
+                .or_insert(distance);
+        }
+    }
+
+    for (imposter_id, imposter) in imposters.iter() {
+        let key = (imposter.world_id, imposter.chunk_column);
+        let rebuild = worlds
+            .get(imposter.world_id)
+            .map(|(source, ..)| source.is_changed())
+            .unwrap_or(true);
+
+        if rebuild || !desired.contains_key(&key) {
+            commands.entity(imposter_id).despawn_recursive();
+        } else {
+            desired.remove(&key);
+        }
+    }
+
+    let mut missing: Vec<_> = desired.into_iter().collect();
+    missing.sort_unstable_by_key(|(_, distance)| OrderedFloat(*distance));
+    *has_pending = missing.len() > settings.max_builds_per_frame;
+
+    for ((world_id, chunk_column), _) in missing.into_iter().take(settings.max_builds_per_frame) {
+        let Ok((source, up, block_scale)) = worlds.get(world_id) else {
+            continue;
+        };
+
+        let up = up.copied().unwrap_or_default();
+        let source = source.source();
+        let mesh = build_imposter_mesh(chunk_column, up, settings.resolution, |block_column| {
+            source.sample_surface(block_column)
+        });
+
+        let transform = chunk_transform(
+            up.compose(chunk_column, 0),
+            block_scale.copied().unwrap_or_default(),
+        );
+
+        let imposter = ChunkImposter {
+            world_id,
+            chunk_column,
+        };
+
+        let mut imposter_commands = match mesh {
+            Some(mesh) => {
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(mesh),
+                        material: material.0.clone(),
+                        transform,
+                        ..default()
+                    },
+                    imposter,
+                ))
+            },
+            None => commands.spawn((SpatialBundle::from_transform(transform), imposter)),
+        };
+
+        imposter_commands.set_parent(world_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use bones3_core::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ecs::resources::ChunkMaterialSettings;
+    use crate::mesh::imposter::{ImposterSample, ImposterSource};
+
+    #[test]
+    fn fade_in_chunk_meshes() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_systems(Update, animate_chunk_fade_in);
+
+        let mesh_id = app.world.spawn((Transform::default(), ChunkMesh)).id();
+        let chunk_id = app
+            .world
+            .spawn(ChunkFadeIn::new(1.0))
+            .push_children(&[mesh_id])
+            .id();
+
+        let start = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(start);
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(500));
+        app.update();
+
+        let transform = *app.world.get::<Transform>(mesh_id).unwrap();
+        assert_eq!(transform.scale, Vec3::splat(0.875));
+        assert_eq!(transform.translation, Vec3::splat(1.0));
+
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(1000));
+        app.update();
+
+        assert_eq!(
+            app.world.get::<Transform>(mesh_id),
+            Some(&Transform::IDENTITY)
+        );
+        assert!(app.world.get::<ChunkFadeIn>(chunk_id).is_none());
+    }
+
+    #[test]
+    fn remesh_chunks_using_changed_material() {
+        let mut app = App::new();
+        app.init_resource::<ChunkMaterialList>()
+            .add_event::<ChunkMaterialChanged>()
+            .add_systems(Update, report_chunk_material_changes);
+
+        let mut material_list = app.world.resource_mut::<ChunkMaterialList>();
+        let stone = material_list.add_material(Handle::default(), None);
+        let grass = material_list.add_material(Handle::default(), None);
+
+        let a = app.world.spawn(ChunkMeshInfo::new([stone])).id();
+        let b = app.world.spawn(ChunkMeshInfo::new([grass, stone])).id();
+        let c = app.world.spawn(ChunkMeshInfo::new([])).id();
+        app.update();
+
+        let remeshed =
+            |app: &App| [a, b, c].map(|chunk_id| app.world.get::<RemeshChunk>(chunk_id).is_some());
+        assert_eq!(remeshed(&app), [true, true, false]);
+
+        for chunk_id in [a, b] {
+            app.world.entity_mut(chunk_id).remove::<RemeshChunk>();
+        }
+
+        let mut material_list = app.world.resource_mut::<ChunkMaterialList>();
+        material_list.remove_material(grass);
+        material_list.set_settings(stone, ChunkMaterialSettings {
+            unlit: true,
+            ..default()
+        });
+        app.update();
+        assert_eq!(remeshed(&app), [false, true, false]);
+
+        let events = app.world.resource::<Events<ChunkMaterialChanged>>();
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn swap_replaced_chunk_materials() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default())
+            .add_asset::<StandardMaterial>()
+            .init_resource::<ChunkMaterialList>()
+            .add_event::<ChunkMaterialChanged>()
+            .add_systems(Update, report_chunk_material_changes);
+
+        let mut materials = app.world.resource_mut::<Assets<StandardMaterial>>();
+        let old_material = materials.add(StandardMaterial::default());
+        let new_material = materials.add(StandardMaterial::default());
+
+        let mut material_list = app.world.resource_mut::<ChunkMaterialList>();
+        let stone = material_list.add_material(old_material.clone(), None);
+        let chunk_id = app.world.spawn(ChunkMeshInfo::new([stone])).id();
+        let mesh_id = app.world.spawn((ChunkMesh, old_material)).id();
+        app.update();
+        app.world.entity_mut(chunk_id).remove::<RemeshChunk>();
+
+        app.world
+            .resource_mut::<ChunkMaterialList>()
+            .replace_material(stone, new_material.clone());
+        app.update();
+
+        assert_eq!(
+            app.world.get::<Handle<StandardMaterial>>(mesh_id),
+            Some(&new_material)
+        );
+        assert!(app.world.get::<RemeshChunk>(chunk_id).is_none());
+
+        let events = app.world.resource::<Events<ChunkMaterialChanged>>();
+        assert_eq!(
+            events.iter_current_update_events().collect::<Vec<_>>(),
+            vec![&ChunkMaterialChanged {
+                index:  stone,
+                change: ChunkMaterialChange::Replaced,
+            }]
+        );
+    }
+
+    #[test]
+    fn refresh_materials_after_image_reload() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default())
+            .add_asset::<Image>()
+            .add_asset::<StandardMaterial>()
+            .init_resource::<ChunkMaterialList>()
+            .add_event::<ChunkMaterialChanged>()
+            .add_systems(Update, refresh_modified_chunk_materials);
+
+        let image = app
+            .world
+            .resource_mut::<Assets<Image>>()
+            .add(Image::default());
+        let mut materials = app.world.resource_mut::<Assets<StandardMaterial>>();
+        let textured = materials.add(StandardMaterial {
+            base_color_texture: Some(image.clone()),
+            ..default()
+        });
+        let plain = materials.add(StandardMaterial::default());
+
+        let mut material_list = app.world.resource_mut::<ChunkMaterialList>();
+        let stone = material_list.add_material(textured, None);
+        material_list.add_material(plain, None);
+        app.update();
+
+        let mut reader = app
+            .world
+            .resource::<Events<ChunkMaterialChanged>>()
+            .get_reader();
+        let events = app.world.resource::<Events<ChunkMaterialChanged>>();
+        assert_eq!(reader.iter(events).count(), 0);
+
+        app.world.send_event(AssetEvent::Modified {
+            handle: image,
+        });
+        for _ in 0 .. 3 {
+            app.update();
+        }
+
+        let events = app.world.resource::<Events<ChunkMaterialChanged>>();
+        assert_eq!(reader.iter(events).collect::<Vec<_>>(), vec![
+            &ChunkMaterialChanged {
+                index:  stone,
+                change: ChunkMaterialChange::AssetModified,
+            }
+        ]);
+    }
+
+    #[test]
+    fn count_pending_chunk_mesh_builds() {
+        let mut world = World::new();
+        let chunk_id = world.spawn_empty().id();
+
+        queue_pending_build(chunk_id, &mut world);
+        queue_pending_build(chunk_id, &mut world);
+        assert_eq!(world.get(chunk_id), Some(&ChunkMeshPending(2)));
+
+        finish_pending_builds(chunk_id, &mut world, 1);
+        assert_eq!(world.get(chunk_id), Some(&ChunkMeshPending(1)));
+
+        finish_pending_builds(chunk_id, &mut world, 1);
+        assert_eq!(world.get::<ChunkMeshPending>(chunk_id), None);
+    }
+
+    #[test]
+    fn remesh_neighbors_of_despawned_chunks() {
+        let mut app = App::new();
+        app.add_plugins(Bones3CorePlugin::<u8>::default())
+            .add_systems(Update, remesh_despawned_chunk_neighbors);
+
+        let solid = app
+            .world
+            .spawn((VoxelWorldBundle::new(), BoundaryFaces::Solid))
+            .id();
+        let empty = app
+            .world
+            .spawn((VoxelWorldBundle::new(), BoundaryFaces::Empty))
+            .id();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                for world_id in [solid, empty] {
+                    let mut world = commands.get_world(world_id).unwrap();
+                    world.spawn_chunk(IVec3::ZERO, ()).unwrap();
+                    world.spawn_chunk(IVec3::X, ()).unwrap();
+                }
+            })
+            .run(&mut app.world);
+        app.update();
+
+        Schedule::new()
+            .add_systems(move |mut commands: VoxelCommands| {
+                for world_id in [solid, empty] {
+                    commands
+                        .get_world(world_id)
+                        .unwrap()
+                        .get_chunk(IVec3::X)
+                        .unwrap()
+                        .despawn();
+                }
+            })
+            .run(&mut app.world);
+        app.update();
+
+        let remeshed: Vec<Entity> = app
+            .world
+            .query_filtered::<&VoxelChunk, With<RemeshChunk>>()
+            .iter(&app.world)
+            .map(|chunk_meta| chunk_meta.world_id())
+            .collect();
+        assert_eq!(remeshed, vec![solid]);
+    }
+
+    #[test]
+    fn recompute_imposters_when_anchor_moves() {
+        struct Flat;
+
+        impl ImposterSource for Flat {
+            fn sample_surface(&self, _: IVec2) -> Option<ImposterSample> {
+                Some(ImposterSample {
+                    height: 1.0,
+                    color:  Color::WHITE,
+                })
+            }
+        }
+
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .insert_resource(ImposterSettings {
+                inner_radius: 0,
+                ..default()
+            })
+            .insert_resource(ImposterMaterial(Handle::default()))
+            .add_systems(Update, update_imposters);
+
+        let world_id = app
+            .world
+            .spawn((VoxelWorld, ImposterSourceHandler::from(Flat)))
+            .id();
+        let mut anchor = ChunkAnchor::<ImposterAnchor>::new(world_id, UVec3::ONE);
+        anchor.coords = Some(IVec3::ZERO);
+        let anchor_id = app.world.spawn(anchor).id();
+        app.update();
+
+        let mut imposters = app.world.query::<(Entity, &ChunkImposter)>();
+        assert_eq!(imposters.iter(&app.world).count(), 8);
+
+        // Imposters are not recomputed while the anchor stays in place.
+        let (imposter_id, _) = imposters
+            .iter(&app.world)
+            .find(|(_, imposter)| imposter.chunk_column == IVec2::new(-1, -1))
+            .unwrap();
+        app.world.despawn(imposter_id);
+        app.update();
+        assert_eq!(imposters.iter(&app.world).count(), 7);
+
+        app.world
+            .get_mut::<ChunkAnchor<ImposterAnchor>>(anchor_id)
+            .unwrap()
+            .coords = Some(IVec3::X);
+        app.update();
+
+        let mut columns: Vec<_> = imposters
+            .iter(&app.world)
+            .map(|(_, imposter)| imposter.chunk_column)
+            .collect();
+        columns.sort_by_key(|column| (column.x, column.y));
+        assert_eq!(columns.len(), 8);
+        assert!(!columns.contains(&IVec2::new(1, 0)));
+        assert!(columns.iter().all(|column| (0 ..= 2).contains(&column.x)));
+    }
+
+    #[test]
+    fn world_origin_mesh_transform() {
+        let chunk_coords = IVec3::new(3, -2, 7);
+        let block_scale = BlockScale(0.5);
+        let local_vertex = Vec3::new(1.0, 2.0, 3.0);
+
+        let chunk = chunk_transform(chunk_coords, block_scale);
+        let expected = chunk.transform_point(local_vertex);
+
+        for origin in [ChunkMeshOrigin::Chunk, ChunkMeshOrigin::World] {
+            let vertex = local_vertex + origin.vertex_offset(chunk_coords);
+            let mesh = origin.mesh_transform(chunk_coords);
+            assert_eq!(chunk.mul_transform(mesh).transform_point(vertex), expected);
+        }
+    }
+}
@@ -8,18 +8,48 @@
 
 use std::marker::PhantomData;
 
+use bevy::asset::load_internal_asset;
 use bevy::prelude::*;
+use bevy::render::{ExtractSchedule, Render, RenderApp, RenderSet};
+use bevy::transform::TransformSystem;
 use bones3_core::storage::BlockData;
-use bones3_core::util::anchor::ChunkAnchorPlugin;
-use ecs::resources::ChunkMaterialList;
+use bones3_core::util::anchor::{ChunkAnchorPlugin, ChunkAnchorSet};
+use bones3_core::Bones3CoreSet;
+use ecs::resources::{
+    ChunkFadeSettings,
+    ChunkMaterialChange,
+    ChunkMaterialChanged,
+    ChunkMaterialList,
+    ChunkMaterialSettings,
+    ChunkMeshBudget,
+    ChunkMeshUploadQueue,
+    ChunkMeshUploadSettings,
+    ChunkSeamDebugSettings,
+    ChunkSeamReport,
+    ImposterMaterial,
+    ImposterSettings,
+};
 
 use crate::ecs::components::*;
 use crate::ecs::systems::*;
 use crate::mesh::block_model::BlockShape;
+use crate::mesh::direct_upload::{
+    extract_direct_chunk_meshes,
+    prepare_direct_chunk_meshes,
+    DirectChunkMeshes,
+    ExtractedChunkMeshes,
+};
+use crate::mesh::quantized_material::{
+    QuantizedChunkMaterial,
+    QuantizedChunkMaterials,
+    QUANTIZED_CHUNK_SHADER_HANDLE,
+};
+use crate::mesh::smooth::BlockDensity;
 
 pub mod ecs;
 pub mod mesh;
 pub mod query;
+pub mod sculpt;
 pub mod vertex_data;
 
 /// The remesh plugin for Bones Cubed.
@@ -37,12 +67,249 @@ where
     T: BlockData + BlockShape,
 {
     fn build(&self, app: &mut App) {
-        app.register_type::<RemeshChunk>()
-            .register_type::<ChunkMesh>()
-            .register_type::<RemeshChunkTask<T>>()
-            .insert_resource(ChunkMaterialList::default())
-            .add_plugins(ChunkAnchorPlugin::<RemeshAnchor>::default())
-            .add_systems(PostUpdate, remesh_dirty_chunks::<T>);
+        add_shared_remesh_systems(app);
+        app.register_type::<RemeshChunkTask<T>>()
+            .add_systems(PostUpdate, remesh_dirty_chunks::<T>.in_set(RemeshSet));
+    }
+}
+
+/// The smooth remesh plugin for Bones Cubed.
+///
+/// This plugin generates smooth chunk meshes for all voxel worlds that use the
+/// [`ChunkMesher::SurfaceNets`] or [`ChunkMesher::DualContouring`] chunk
+/// meshers. It may be used alongside the
+/// standard remesh plugin for the same block data type.
+#[derive(Default)]
+pub struct Bones3SmoothRemeshPlugin<T>
+where
+    T: BlockDensity,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for Bones3SmoothRemeshPlugin<T>
+where
+    T: BlockDensity,
+{
+    fn build(&self, app: &mut App) {
+        add_shared_remesh_systems(app);
+        app.add_systems(
+            PostUpdate,
+            remesh_dirty_smooth_chunks::<T>.in_set(RemeshSet),
+        );
+    }
+}
+
+/// A plugin that plays a short animation whenever a chunk receives its first
+/// mesh, scaling its chunk meshes up from the center of the chunk in order to
+/// hide chunks popping into view.
+///
+/// The animation may be configured using the [`ChunkFadeSettings`] resource.
+/// Chunks that are remeshed after their first mesh appears are not animated.
+#[derive(Default)]
+pub struct ChunkFadePlugin;
+
+impl Plugin for ChunkFadePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ChunkFadeIn>()
+            .register_type::<ChunkFadeSettings>()
+            .init_resource::<ChunkFadeSettings>()
+            .add_systems(
+                PostUpdate,
+                animate_chunk_fade_in
+                    .after(upload_chunk_meshes)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// A debugging plugin that validates the block faces along the seams between
+/// neighboring meshed chunks, in order to catch custom
+/// [`BlockShape::check_occlude`] implementations that disagree with the face
+/// coverage of their block shapes.
+///
+/// Whenever a chunk is remeshed, the seams between it and all of its meshed
+/// neighbors are checked for faces that are hidden without being covered, or
+/// that are rendered while being covered. All mismatched faces are stored
+/// within the [`ChunkSeamReport`] resource, logged, and highlighted using
+/// gizmos if the gizmo plugin has been added. This may be configured using the
+/// [`ChunkSeamDebugSettings`] resource.
+///
+/// See [`find_seam_mismatches`](crate::mesh::seams::find_seam_mismatches) for
+/// more information.
+#[derive(Default)]
+pub struct ChunkSeamDebugPlugin<T>
+where
+    T: BlockData + BlockShape,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for ChunkSeamDebugPlugin<T>
+where
+    T: BlockData + BlockShape,
+{
+    fn build(&self, app: &mut App) {
+        app.register_type::<ChunkSeamDebugSettings>()
+            .init_resource::<ChunkSeamDebugSettings>()
+            .init_resource::<ChunkSeamReport>()
+            .add_systems(
+                PostUpdate,
+                (
+                    validate_chunk_seams::<T>,
+                    draw_chunk_seam_mismatches.run_if(resource_exists::<GizmoConfig>()),
+                )
+                    .chain()
+                    .after(RemeshSet),
+            );
+    }
+}
+
+/// A plugin that renders distant chunk columns as low resolution heightfield
+/// imposters, in order to extend the horizon far beyond the range in which
+/// chunks are loaded.
+///
+/// Imposters are managed by a third ring of chunk anchors, using the
+/// [`ImposterAnchor`] type, alongside the anchors that load and remesh chunks.
+/// Each chunk column within the radius of an imposter anchor, but outside of
+/// the inner radius within the [`ImposterSettings`], receives an imposter that
+/// is built from the [`ImposterSourceHandler`] of its world. Worlds without an
+/// imposter source do not have any imposters.
+///
+/// See [`build_imposter_mesh`](crate::mesh::imposter::build_imposter_mesh) for
+/// more information.
+#[derive(Default)]
+pub struct ImposterPlugin;
+
+impl Plugin for ImposterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ImposterSourceHandler>()
+            .register_type::<ImposterSettings>()
+            .init_resource::<ImposterSettings>()
+            .init_resource::<ImposterMaterial>()
+            .add_plugins(ChunkAnchorPlugin::<ImposterAnchor>::default())
+            .add_systems(
+                PostUpdate,
+                update_imposters
+                    .after(ChunkAnchorSet::UpdateCoords)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// A plugin that renders chunk meshes that use the
+/// [quantized](crate::vertex_data::quantized) vertex format, using a
+/// [`QuantizedChunkMaterial`] that is created from each chunk material.
+///
+/// Chunk meshes are only built using the quantized vertex format if
+/// [`ChunkMeshUploadSettings::quantize_vertices`] is enabled. This plugin
+/// requires the Bevy render plugins.
+#[derive(Default)]
+pub struct QuantizedChunkMeshPlugin;
+
+impl Plugin for QuantizedChunkMeshPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            QUANTIZED_CHUNK_SHADER_HANDLE,
+            "mesh/quantized_chunk.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.add_plugins(MaterialPlugin::<QuantizedChunkMaterial> {
+            prepass_enabled: false,
+            ..default()
+        })
+        .init_resource::<QuantizedChunkMaterials>()
+        .add_systems(Last, sync_quantized_chunk_materials);
+    }
+}
+
+/// A plugin that hands finished chunk meshes directly to the render world,
+/// instead of storing them as mesh assets.
+///
+/// By default, every remesh overwrites a mesh asset, which sends an asset
+/// event and clones the mesh into the render world. With this plugin, chunk
+/// mesh entities are given weak mesh handles that never point to a mesh asset,
+/// and their meshes are moved into the render world during extraction, where
+/// they are prepared under the same handles. This avoids the mesh asset
+/// bookkeeping for worlds that are constantly remeshed.
+///
+/// Only chunk meshes that are uploaded by the remesh plugins use direct
+/// meshes, and their meshes cannot be read from `Assets<Mesh>`. This plugin
+/// requires the Bevy render plugins, and must be added after them.
+#[derive(Default)]
+pub struct DirectChunkMeshPlugin;
+
+impl Plugin for DirectChunkMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DirectChunkMeshes>().add_systems(
+            PostUpdate,
+            release_direct_chunk_meshes.after(upload_chunk_meshes),
+        );
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<ExtractedChunkMeshes>()
+                .add_systems(ExtractSchedule, extract_direct_chunk_meshes)
+                .add_systems(
+                    Render,
+                    prepare_direct_chunk_meshes.in_set(RenderSet::Prepare),
+                );
+        }
+    }
+}
+
+/// Registers the types, resources, and plugins that are shared between all
+/// remesh plugins, if they have not already been added.
+fn add_shared_remesh_systems(app: &mut App) {
+    app.register_type::<RemeshChunk>()
+        .register_type::<ChunkMesh>()
+        .register_type::<ChunkMeshPending>()
+        .register_type::<ChunkMesher>()
+        .register_type::<ChunkMeshOrigin>()
+        .register_type::<BoundaryFaces>()
+        .register_type::<ChunkMaterialSettings>()
+        .register_type::<ChunkMaterialList>()
+        .register_type::<ChunkMaterialChange>()
+        .register_type::<ChunkMeshUploadSettings>()
+        .register_type::<ChunkMeshTime>()
+        .register_type::<ChunkMeshInfo>()
+        .register_type::<ChunkMeshBudget>()
+        .init_resource::<ChunkMaterialList>()
+        .init_resource::<ChunkMeshBudget>()
+        .init_resource::<ChunkMeshUploadSettings>()
+        .init_resource::<ChunkMeshUploadQueue>()
+        .add_event::<ChunkMaterialChanged>()
+        .configure_set(
+            PostUpdate,
+            RemeshSet
+                .after(Bones3CoreSet::BlockUpdates)
+                .after(ChunkAnchorSet::UpdatePriorities),
+        );
+
+    if !app.is_plugin_added::<ChunkAnchorPlugin<RemeshAnchor>>() {
+        app.add_plugins(ChunkAnchorPlugin::<RemeshAnchor>::default())
+            .add_systems(
+                PostUpdate,
+                (
+                    remesh_on_mesh_origin_change,
+                    remesh_despawned_chunk_neighbors,
+                    report_chunk_material_changes,
+                    apply_chunk_material_settings,
+                    refresh_modified_chunk_materials,
+                )
+                    .chain()
+                    .in_set(RemeshSet),
+            )
+            .add_systems(
+                PostUpdate,
+                upload_chunk_meshes
+                    .after(RemeshSet)
+                    .before(TransformSystem::TransformPropagate),
+            );
     }
 }
 
@@ -50,6 +317,16 @@ where
 #[derive(Default, Reflect)]
 pub struct RemeshAnchor;
 
-/// The system set in which all chunks are remeshed.
+/// The type definition to use for the `ChunkAnchorPlugin` that manages the
+/// ring of far-field imposters.
+#[derive(Default, Reflect)]
+pub struct ImposterAnchor;
+
+/// The system set in which all chunks are remeshed, within the `PostUpdate`
+/// schedule.
+///
+/// This set always runs after [`Bones3CoreSet::BlockUpdates`], so any block
+/// changes made within [`Bones3CoreSet::BlockWrites`] are remeshed within the
+/// same frame. It also runs after chunk anchor priorities have been updated.
 #[derive(Debug, SystemSet, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct RemeshSet;
@@ -1,13 +1,16 @@
 //! Defines how a block model should be added to a chunk mesh.
 
+use std::cell::Cell;
+
 use bevy::prelude::*;
 use bitflags::bitflags;
 use bones3_core::prelude::*;
 
+use crate::mesh::face_coverage::FaceCoverage;
 use crate::vertex_data::{ShapeBuilder, TempMesh};
 
 bitflags! {
-    #[derive(Copy, Clone)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     /// A bitflag-based enum that defines how a block is currently being occluded.
     pub struct BlockOcclusion: u8 {
         /// If true, the block is occluded in the negative X direction.
@@ -31,35 +34,52 @@ bitflags! {
 }
 
 impl BlockOcclusion {
-    /// Converts this block occlusion value into a directional offset vector.
-    pub fn into_offset(self) -> IVec3 {
-        let mut offset = IVec3::ZERO;
-
-        if self.contains(BlockOcclusion::NEG_X) {
-            offset += IVec3::NEG_X;
-        }
-
-        if self.contains(BlockOcclusion::POS_X) {
-            offset += IVec3::X;
-        }
-
-        if self.contains(BlockOcclusion::NEG_Y) {
-            offset += IVec3::NEG_Y;
+    /// Gets the block occlusion value that only contains the given face.
+    pub fn from_face(face: BlockFace) -> Self {
+        match face {
+            BlockFace::NegX => BlockOcclusion::NEG_X,
+            BlockFace::PosX => BlockOcclusion::POS_X,
+            BlockFace::NegY => BlockOcclusion::NEG_Y,
+            BlockFace::PosY => BlockOcclusion::POS_Y,
+            BlockFace::NegZ => BlockOcclusion::NEG_Z,
+            BlockFace::PosZ => BlockOcclusion::POS_Z,
         }
+    }
 
-        if self.contains(BlockOcclusion::POS_Y) {
-            offset += IVec3::Y;
+    /// Gets the block face of this block occlusion value, or `None` if this
+    /// value does not contain exactly one face.
+    pub fn to_face(self) -> Option<BlockFace> {
+        let mut faces = self.iter_faces();
+        match (faces.next(), faces.next()) {
+            (Some(face), None) => Some(face),
+            _ => None,
         }
+    }
 
-        if self.contains(BlockOcclusion::NEG_Z) {
-            offset += IVec3::NEG_Z;
-        }
+    /// Gets the block occlusion value containing every face that points
+    /// along the given direction.
+    ///
+    /// Each non-zero axis of the direction adds the face on that side of the
+    /// axis, so a diagonal direction such as `(1, 0, -1)` contains both the
+    /// positive X and the negative Z faces. This is the reverse of
+    /// [`BlockOcclusion::into_offset`] for unit offsets.
+    pub fn from_direction(direction: IVec3) -> Self {
+        BlockFace::ALL
+            .into_iter()
+            .filter(|face| face.normal().dot(direction) > 0)
+            .collect()
+    }
 
-        if self.contains(BlockOcclusion::POS_Z) {
-            offset += IVec3::Z;
-        }
+    /// Gets an iterator over all faces within this block occlusion value.
+    pub fn iter_faces(self) -> impl Iterator<Item = BlockFace> {
+        BlockFace::ALL
+            .into_iter()
+            .filter(move |face| self.contains(BlockOcclusion::from_face(*face)))
+    }
 
-        offset
+    /// Converts this block occlusion value into a directional offset vector.
+    pub fn into_offset(self) -> IVec3 {
+        self.iter_faces().map(|face| face.normal()).sum()
     }
 
     /// Gets the opposite facing value for this block occlusion.
@@ -69,39 +89,102 @@ impl BlockOcclusion {
     /// the positive counter parts. This effect is applied for all defined
     /// directional values.
     pub fn opposite_face(self) -> BlockOcclusion {
-        let mut value = BlockOcclusion::empty();
+        self.iter_faces().map(|face| face.opposite()).collect()
+    }
 
-        if self.contains(BlockOcclusion::NEG_X) {
-            value |= BlockOcclusion::POS_X;
-        }
+    /// Rotates every face within this block occlusion value using the given
+    /// block rotation.
+    pub fn rotate(self, rotation: BlockRotation) -> BlockOcclusion {
+        self.iter_faces()
+            .map(|face| rotation.rotate_face(face))
+            .collect()
+    }
+}
 
-        if self.contains(BlockOcclusion::POS_X) {
-            value |= BlockOcclusion::NEG_X;
-        }
+impl From<BlockFace> for BlockOcclusion {
+    fn from(face: BlockFace) -> Self {
+        BlockOcclusion::from_face(face)
+    }
+}
 
-        if self.contains(BlockOcclusion::NEG_Y) {
-            value |= BlockOcclusion::POS_Y;
-        }
+impl FromIterator<BlockFace> for BlockOcclusion {
+    fn from_iter<I>(faces: I) -> Self
+    where
+        I: IntoIterator<Item = BlockFace>,
+    {
+        faces
+            .into_iter()
+            .fold(BlockOcclusion::empty(), |occlusion, face| {
+                occlusion | BlockOcclusion::from_face(face)
+            })
+    }
+}
 
-        if self.contains(BlockOcclusion::POS_Y) {
-            value |= BlockOcclusion::NEG_Y;
-        }
+impl Default for BlockOcclusion {
+    fn default() -> Self {
+        BlockOcclusion::empty()
+    }
+}
+
+/// A view of the block values within the 3x3x3 area surrounding a block.
+///
+/// This is provided to block shapes while building a chunk mesh, to allow for
+/// connected block models, such as fences, pipes, or glass panes, whose
+/// geometry depends on the neighboring blocks. Block values are only read when
+/// they are first requested, so block shapes that do not look at their
+/// neighbors do not pay for reading them.
+pub struct BlockNeighbors<'a, T>
+where
+    T: BlockData,
+{
+    /// The function used to read block values, by their offset relative to
+    /// the center block.
+    get_block: &'a dyn Fn(IVec3) -> T,
 
-        if self.contains(BlockOcclusion::NEG_Z) {
-            value |= BlockOcclusion::POS_Z;
+    /// The block values that have been read so far, indexed using
+    /// [`Region::NEIGHBORS`].
+    blocks: [Cell<Option<T>>; 27],
+}
+
+impl<'a, T> BlockNeighbors<'a, T>
+where
+    T: BlockData,
+{
+    /// Creates a new block neighbors view that reads each block value from
+    /// the given function. The function is called with an offset relative to
+    /// the center block, with each axis value in the range `-1..=1`, and is
+    /// called at most once for each offset.
+    pub fn from_fn(get_block: &'a dyn Fn(IVec3) -> T) -> Self {
+        Self {
+            get_block,
+            blocks: Default::default(),
         }
+    }
 
-        if self.contains(BlockOcclusion::POS_Z) {
-            value |= BlockOcclusion::NEG_Z;
+    /// Gets the block value at the given offset relative to the center block.
+    ///
+    /// This function panics if any axis of the offset is outside of the range
+    /// `-1..=1`.
+    pub fn get(&self, offset: IVec3) -> T {
+        let cached = &self.blocks[Region::NEIGHBORS.point_to_index(offset).unwrap()];
+        if let Some(block) = cached.get() {
+            return block;
         }
 
-        value
+        let block = (self.get_block)(offset);
+        cached.set(Some(block));
+        block
     }
-}
 
-impl Default for BlockOcclusion {
-    fn default() -> Self {
-        BlockOcclusion::empty()
+    /// Gets the block value that is adjacent to the center block in the
+    /// direction of the given face.
+    pub fn get_face(&self, face: BlockOcclusion) -> T {
+        self.get(face.into_offset())
+    }
+
+    /// Gets the value of the center block.
+    pub fn center(&self) -> T {
+        self.get(IVec3::ZERO)
     }
 }
 
@@ -121,7 +204,102 @@ pub trait BlockShape: BlockData {
     /// the shape builder as needed.
     fn write_shape(&self, shape_builder: &mut ShapeBuilder);
 
+    /// Writes an instance of this block shape to the provided shape builder,
+    /// with access to the values of all surrounding blocks.
+    ///
+    /// This can be overridden for block shapes whose geometry depends on the
+    /// neighboring blocks. By default, this calls [`BlockShape::write_shape`].
+    fn write_connected_shape(
+        &self,
+        shape_builder: &mut ShapeBuilder,
+        neighbors: &BlockNeighbors<'_, Self>,
+    ) {
+        let _ = neighbors;
+        self.write_shape(shape_builder);
+    }
+
+    /// Checks whether this block has no block model at all, such as air.
+    ///
+    /// Empty blocks are skipped while building a chunk mesh, without checking
+    /// their occlusion or reading their neighbors. Defaults to `false`.
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Gets how much of the given face of this block is covered by the block
+    /// model, along the block boundary.
+    ///
+    /// This is used by the default implementation of
+    /// [`BlockShape::check_occlude`]. Defaults to [`FaceCoverage::EMPTY`].
+    fn get_face_coverage(&self, face: BlockOcclusion) -> FaceCoverage {
+        let _ = face;
+        FaceCoverage::EMPTY
+    }
+
     /// Checks if one tile is to occlude another tile. Returns True if face is
     /// occluded.
-    fn check_occlude(&self, face: BlockOcclusion, other: Self) -> bool;
+    ///
+    /// Here, `self` is the neighboring block in the direction of `face`, and
+    /// `other` is the block whose face is being checked.
+    ///
+    /// By default, the face is occluded if the touching face of this block
+    /// covers every part of the face of the other block, as determined by
+    /// [`BlockShape::get_face_coverage`].
+    fn check_occlude(&self, face: BlockOcclusion, other: Self) -> bool {
+        self.get_face_coverage(face.opposite_face())
+            .occludes(other.get_face_coverage(face))
+    }
+
+    /// Gets the merge group of this block, or `None` if this block does not
+    /// merge with its neighbors.
+    ///
+    /// The faces between two neighboring blocks within the same merge group
+    /// are always hidden, regardless of [`BlockShape::check_occlude`]. This is
+    /// useful for transparent blocks, such as water or glass, that should
+    /// render as one continuous volume. Blocks of the same type should share
+    /// the same group, even if their states differ.
+    ///
+    /// Defaults to `None`.
+    fn merge_group(&self) -> Option<u32> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn occlusion_faces() {
+        let occlusion = BlockOcclusion::POS_X | BlockOcclusion::NEG_Z;
+        assert_eq!(occlusion.iter_faces().collect::<Vec<_>>(), vec![
+            BlockFace::PosX,
+            BlockFace::NegZ
+        ]);
+        assert_eq!(occlusion.into_offset(), IVec3::new(1, 0, -1));
+        assert_eq!(
+            BlockOcclusion::from_direction(IVec3::new(3, 0, -1)),
+            occlusion
+        );
+        assert_eq!(
+            occlusion.opposite_face(),
+            BlockOcclusion::NEG_X | BlockOcclusion::POS_Z
+        );
+        assert_eq!(occlusion.to_face(), None);
+        assert_eq!(BlockOcclusion::POS_Y.to_face(), Some(BlockFace::PosY));
+
+        let turn = BlockRotation::from_quarter_turns(BlockFace::PosY, 1);
+        assert_eq!(
+            occlusion.rotate(turn),
+            BlockOcclusion::POS_Z | BlockOcclusion::POS_X
+        );
+        for rotation in BlockRotation::all() {
+            assert_eq!(
+                BlockOcclusion::all().rotate(rotation),
+                BlockOcclusion::all()
+            );
+        }
+    }
 }
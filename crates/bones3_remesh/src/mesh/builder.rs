@@ -1,13 +1,18 @@
 //! This module contains the core algorithm for generating a mesh from a voxel
 //! storage chunk.
 
+use bevy::pbr::NotShadowCaster;
 use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
 use bones3_core::prelude::*;
 
-use crate::ecs::components::ChunkMesh;
+use crate::ecs::components::{BoundaryFaces, ChunkMesh, QuantizedChunkMesh};
 use crate::ecs::resources::ChunkMaterialList;
-use crate::mesh::block_model::{BlockOcclusion, BlockShape};
-use crate::vertex_data::ShapeBuilder;
+use crate::mesh::block_model::{BlockNeighbors, BlockOcclusion, BlockShape};
+use crate::mesh::direct_upload::DirectChunkMeshes;
+use crate::mesh::quantized_material::QuantizedChunkMaterial;
+use crate::vertex_data::quantized::quantized_aabb;
+use crate::vertex_data::{CubeModelBuilder, QuadFacing, ShapeBuilder};
 
 /// Builds a temp mesh for a virtual 16x16x16 chunk with support for reading
 /// block data from neighboring virtual chunks.
@@ -15,57 +20,146 @@ use crate::vertex_data::ShapeBuilder;
 /// This method will iterator over all values within the 16x16x16 local
 /// coordinates and read the corresponding block values from the `get_block`
 /// parameter function provided. For neighboring chunks, values one block
-/// outside of the standard local block coordinates in each of the six cubic
-/// directions are also read using the `get_block` parameter function with
-/// values that would lie outside of a standard chunk block coordinate.
+/// outside of the standard local block coordinates, including along edges and
+/// corners, are also read using the `get_block` parameter function with values
+/// that would lie outside of a standard chunk block coordinate.
 pub fn build_chunk_mesh<T, G>(get_block: G, material_list: &ChunkMaterialList) -> ShapeBuilder<'_>
 where
     T: BlockData + BlockShape,
     G: Fn(IVec3) -> T,
+{
+    build_bounded_chunk_mesh(get_block, |_| true, BoundaryFaces::Empty, material_list)
+}
+
+/// Builds a temp mesh for a virtual 16x16x16 chunk in the same way as
+/// [`build_chunk_mesh`], while applying the given [`BoundaryFaces`] setting to
+/// all block faces that point into a neighboring chunk that is not loaded.
+///
+/// The `is_loaded` parameter function is called with the same local block
+/// coordinates as `get_block`, and returns whether the chunk containing those
+/// coordinates is loaded.
+pub fn build_bounded_chunk_mesh<T, G, L>(
+    get_block: G,
+    is_loaded: L,
+    boundary: BoundaryFaces,
+    material_list: &ChunkMaterialList,
+) -> ShapeBuilder<'_>
+where
+    T: BlockData + BlockShape,
+    G: Fn(IVec3) -> T,
+    L: Fn(IVec3) -> bool,
 {
     let mut shape_builder = ShapeBuilder::new(material_list);
 
     for block_pos in Region::CHUNK.iter() {
-        let data = get_block(block_pos);
+        let get_neighbor = |offset: IVec3| get_block(block_pos + offset);
+        let neighbors = BlockNeighbors::from_fn(&get_neighbor);
+        let data = neighbors.center();
 
-        let check_occlusion = |occlusion: &mut BlockOcclusion, face: BlockOcclusion| {
-            if get_block(block_pos + face.into_offset()).check_occlude(face, get_block(block_pos)) {
-                occlusion.insert(face);
-            }
+        let unloaded = match boundary {
+            BoundaryFaces::Empty => BlockOcclusion::empty(),
+            _ => unloaded_faces(block_pos, &is_loaded),
         };
 
-        let mut occlusion = BlockOcclusion::empty();
-        check_occlusion(&mut occlusion, BlockOcclusion::NEG_X);
-        check_occlusion(&mut occlusion, BlockOcclusion::POS_X);
-        check_occlusion(&mut occlusion, BlockOcclusion::NEG_Y);
-        check_occlusion(&mut occlusion, BlockOcclusion::POS_Y);
-        check_occlusion(&mut occlusion, BlockOcclusion::NEG_Z);
-        check_occlusion(&mut occlusion, BlockOcclusion::POS_Z);
-
         shape_builder.set_local_pos(block_pos);
-        shape_builder.set_occlusion(occlusion);
-        data.write_shape(&mut shape_builder);
+
+        if !data.is_empty() {
+            let merge_group = data.merge_group();
+            let check_occlusion = |occlusion: &mut BlockOcclusion, face: BlockOcclusion| {
+                let neighbor = neighbors.get_face(face);
+                let merged = merge_group.is_some() && neighbor.merge_group() == merge_group;
+                if merged || neighbor.check_occlude(face, data) {
+                    occlusion.insert(face);
+                }
+            };
+
+            let mut occlusion = BlockOcclusion::empty();
+            check_occlusion(&mut occlusion, BlockOcclusion::NEG_X);
+            check_occlusion(&mut occlusion, BlockOcclusion::POS_X);
+            check_occlusion(&mut occlusion, BlockOcclusion::NEG_Y);
+            check_occlusion(&mut occlusion, BlockOcclusion::POS_Y);
+            check_occlusion(&mut occlusion, BlockOcclusion::NEG_Z);
+            check_occlusion(&mut occlusion, BlockOcclusion::POS_Z);
+
+            shape_builder.set_occlusion(occlusion | unloaded);
+            data.write_connected_shape(&mut shape_builder, &neighbors);
+        }
+
+        if let BoundaryFaces::FogWall(material_index) = boundary {
+            if !unloaded.is_empty() {
+                let wall = CubeModelBuilder::new()
+                    .set_occlusion(BlockOcclusion::all() - unloaded)
+                    .set_facing(QuadFacing::Back);
+                shape_builder.add_shape(wall, material_index);
+            }
+        }
     }
 
     shape_builder
 }
 
+/// Gets all faces of the block at the given local block coordinates that point
+/// into a chunk that is not loaded.
+fn unloaded_faces<L>(block_pos: IVec3, is_loaded: &L) -> BlockOcclusion
+where
+    L: Fn(IVec3) -> bool,
+{
+    BlockFace::ALL
+        .into_iter()
+        .filter(|face| !Region::CHUNK.contains(block_pos + face.normal()))
+        .filter(|face| !is_loaded(block_pos + face.normal()))
+        .map(BlockOcclusion::from_face)
+        .collect()
+}
+
 /// This function will update the provided chunk to use the chunk meshes
 /// generated by the shape builder instance for chunk model rendering.
+///
+/// The spawned chunk mesh entities are given the provided transform, relative
+/// to the chunk. See
+/// [`ChunkMeshOrigin`](crate::ecs::components::ChunkMeshOrigin)
+/// for more information.
+///
+/// Returns the number of chunk mesh entities that were spawned.
 pub fn apply_shape_builder(
     chunk_id: Entity,
     shape_builder: ShapeBuilder,
+    mesh_transform: Transform,
     mesh_query: &Query<(Entity, &Parent), With<ChunkMesh>>,
     meshes: &mut ResMut<Assets<Mesh>>,
     commands: &mut Commands,
-) {
+) -> usize {
+    spawn_chunk_meshes(
+        chunk_id,
+        shape_builder.into_meshes(),
+        mesh_transform,
+        mesh_query,
+        meshes,
+        commands,
+    )
+}
+
+/// Replaces all chunk mesh entities of the provided chunk with new chunk mesh
+/// entities for the given meshes and their materials.
+///
+/// Returns the number of chunk mesh entities that were spawned.
+pub(crate) fn spawn_chunk_meshes(
+    chunk_id: Entity,
+    chunk_meshes: impl IntoIterator<Item = (Mesh, Handle<StandardMaterial>)>,
+    mesh_transform: Transform,
+    mesh_query: &Query<(Entity, &Parent), With<ChunkMesh>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    commands: &mut Commands,
+) -> usize {
     for (chunk_mesh_id, parent) in mesh_query.iter() {
         if parent.get() == chunk_id {
             commands.entity(chunk_mesh_id).despawn();
         }
     }
 
-    for (mesh, material_handle) in shape_builder.into_meshes() {
+    let mut count = 0;
+    for (mesh, material_handle) in chunk_meshes {
+        count += 1;
         let mesh_handle = meshes.add(mesh);
 
         commands
@@ -73,10 +167,462 @@ pub fn apply_shape_builder(
                 PbrBundle {
                     mesh: mesh_handle,
                     material: material_handle,
+                    transform: mesh_transform,
                     ..default()
                 },
                 ChunkMesh,
             ))
             .set_parent(chunk_id);
     }
+
+    count
+}
+
+/// Updates the chunk mesh entities of the provided chunk to show the given
+/// meshes, reusing the existing entities and mesh assets where possible.
+///
+/// Each new mesh overwrites the mesh asset of an existing chunk mesh entity
+/// with the same material and vertex format, so constantly remeshed chunks
+/// keep the same mesh handles instead of adding and dropping a mesh asset for
+/// every remesh. New entities are only spawned for materials the chunk did not
+/// have before, and any leftover chunk mesh entities are despawned.
+///
+/// If direct meshes are given, the meshes are queued within them instead of
+/// being stored as mesh assets. See
+/// [`DirectChunkMeshPlugin`](crate::DirectChunkMeshPlugin) for more
+/// information.
+///
+/// Meshes that use the [quantized](crate::vertex_data::quantized) vertex
+/// format are spawned with a [`QuantizedChunkMesh`] component instead of a
+/// `StandardMaterial`, and do not cast shadows.
+///
+/// Returns the number of chunk mesh entities that the chunk now has.
+pub(crate) fn replace_chunk_meshes(
+    chunk_id: Entity,
+    chunk_meshes: impl IntoIterator<Item = (Mesh, Handle<StandardMaterial>)>,
+    mesh_transform: Transform,
+    mesh_query: &Query<ChunkMeshHandles, With<ChunkMesh>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    mut direct_meshes: Option<&mut DirectChunkMeshes>,
+    commands: &mut Commands,
+) -> usize {
+    let mut existing: Vec<_> = mesh_query
+        .iter()
+        .filter(|(_, parent, ..)| parent.get() == chunk_id)
+        .filter_map(|(chunk_mesh_id, _, mesh_handle, material, quantized)| {
+            match (material, quantized) {
+                (_, Some(quantized)) => Some((chunk_mesh_id, mesh_handle, &quantized.0, true)),
+                (Some(material), None) => Some((chunk_mesh_id, mesh_handle, material, false)),
+                (None, None) => None,
+            }
+        })
+        .collect();
+
+    let mut count = 0;
+    for (mesh, material_handle) in chunk_meshes {
+        count += 1;
+
+        // Bevy cannot calculate the bounds of quantized meshes, or of meshes
+        // that are not stored as mesh assets, so they are calculated here.
+        let quantized_aabb = quantized_aabb(&mesh);
+        let is_quantized = quantized_aabb.is_some();
+        let aabb = match direct_meshes {
+            Some(_) => quantized_aabb.or_else(|| mesh.compute_aabb()),
+            None => quantized_aabb,
+        };
+
+        let reused = existing.iter().position(|(_, _, material, quantized)| {
+            **material == material_handle && *quantized == is_quantized
+        });
+
+        let Some(index) = reused else {
+            let mut chunk_mesh = match is_quantized {
+                true => {
+                    commands.spawn((
+                        MaterialMeshBundle::<QuantizedChunkMaterial> {
+                            transform: mesh_transform,
+                            ..default()
+                        },
+                        QuantizedChunkMesh(material_handle),
+                        NotShadowCaster,
+                    ))
+                },
+                false => {
+                    commands.spawn(PbrBundle {
+                        material: material_handle,
+                        transform: mesh_transform,
+                        ..default()
+                    })
+                },
+            };
+
+            let mesh_handle = match direct_meshes.as_deref_mut() {
+                Some(direct_meshes) => direct_meshes.upload(chunk_mesh.id(), mesh),
+                None => meshes.add(mesh),
+            };
+            chunk_mesh.insert((mesh_handle, ChunkMesh));
+            if let Some(aabb) = aabb {
+                chunk_mesh.insert(aabb);
+            }
+            chunk_mesh.set_parent(chunk_id);
+            continue;
+        };
+
+        let (chunk_mesh_id, mesh_handle, ..) = existing.swap_remove(index);
+        let mut chunk_mesh = commands.entity(chunk_mesh_id);
+        match direct_meshes.as_deref_mut() {
+            Some(direct_meshes) => {
+                let direct_handle = direct_meshes.upload(chunk_mesh_id, mesh);
+                if direct_handle != *mesh_handle {
+                    chunk_mesh.insert(direct_handle);
+                }
+            },
+            None => meshes.set_untracked(mesh_handle, mesh),
+        }
+
+        // The bounds of a mesh asset are only calculated for entities without
+        // an Aabb, so it has to be removed for the new mesh to be culled
+        // properly.
+        chunk_mesh.insert(mesh_transform);
+        match aabb {
+            Some(aabb) => chunk_mesh.insert(aabb),
+            None => chunk_mesh.remove::<Aabb>(),
+        };
+    }
+
+    for (chunk_mesh_id, ..) in existing {
+        commands.entity(chunk_mesh_id).despawn();
+    }
+
+    count
+}
+
+/// The query data of a chunk mesh entity that is needed to reuse it within
+/// [`replace_chunk_meshes`].
+pub(crate) type ChunkMeshHandles<'a> = (
+    Entity,
+    &'a Parent,
+    &'a Handle<Mesh>,
+    Option<&'a Handle<StandardMaterial>>,
+    Option<&'a QuantizedChunkMesh>,
+);
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use bevy::asset::HandleId;
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+    use crate::ecs::systems::release_direct_chunk_meshes;
+    use crate::vertex_data::TempMesh;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    enum Block {
+        #[default]
+        Air,
+        Water,
+        Glass,
+    }
+
+    impl BlockShape for Block {
+        fn write_shape(&self, shape_builder: &mut ShapeBuilder) {
+            if *self != Block::Air {
+                let occlusion = shape_builder.get_occlusion();
+                shape_builder.add_shape(CubeModelBuilder::new().set_occlusion(occlusion), 0);
+            }
+        }
+
+        fn is_empty(&self) -> bool {
+            *self == Block::Air
+        }
+
+        fn merge_group(&self) -> Option<u32> {
+            match self {
+                Block::Air => None,
+                Block::Water => Some(1),
+                Block::Glass => Some(2),
+            }
+        }
+    }
+
+    #[test]
+    fn only_read_neighbors_of_solid_blocks() {
+        let mut materials = ChunkMaterialList::default();
+        materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+
+        let reads = Cell::new(0);
+        let get_block = |block_pos: IVec3| {
+            reads.set(reads.get() + 1);
+            match block_pos == IVec3::new(3, 4, 5) {
+                true => Block::Glass,
+                false => Block::Air,
+            }
+        };
+
+        let vertices: usize = build_chunk_mesh(get_block, &materials)
+            .into_temp_meshes()
+            .map(|mesh| mesh.vertices.len())
+            .sum();
+
+        // Every block is read once, and only the glass block reads its six
+        // face neighbors.
+        assert_eq!(vertices, 6 * 4);
+        assert_eq!(reads.get(), 4096 + 6);
+    }
+
+    #[test]
+    fn merge_faces_across_chunk_border() {
+        let mut materials = ChunkMaterialList::default();
+        materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+
+        let get_block = |block_pos: IVec3| {
+            match block_pos.to_array() {
+                [15 | 16, 0, 0] => Block::Water,
+                [15, 1, 0] => Block::Glass,
+                _ => Block::Air,
+            }
+        };
+
+        let vertices: usize = build_chunk_mesh(get_block, &materials)
+            .into_temp_meshes()
+            .map(|mesh| mesh.vertices.len())
+            .sum();
+
+        // The water block hides its +X face against the water in the next
+        // chunk, while the water and glass blocks keep their shared faces.
+        assert_eq!(vertices, (5 + 6) * 4);
+    }
+
+    #[test]
+    fn boundary_faces() {
+        let mut materials = ChunkMaterialList::default();
+        materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+        let fog =
+            materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+
+        let get_block = |block_pos: IVec3| {
+            match block_pos.to_array() {
+                [15, 0, 0] => Block::Glass,
+                _ => Block::Air,
+            }
+        };
+        let is_loaded = |block_pos: IVec3| block_pos.x < 16;
+
+        let vertices = |boundary: BoundaryFaces| -> Vec<usize> {
+            build_bounded_chunk_mesh(get_block, is_loaded, boundary, &materials)
+                .into_temp_meshes()
+                .map(|mesh| mesh.vertices.len())
+                .collect()
+        };
+
+        assert_eq!(vertices(BoundaryFaces::Empty), vec![6 * 4]);
+        assert_eq!(vertices(BoundaryFaces::Solid), vec![5 * 4]);
+
+        // The fog wall covers the whole +X side of the chunk, facing inwards.
+        assert_eq!(vertices(BoundaryFaces::FogWall(fog)), vec![
+            5 * 4,
+            16 * 16 * 4
+        ]);
+    }
+
+    #[test]
+    fn reuse_chunk_mesh_entities() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default()).add_asset::<Mesh>();
+
+        let stone = Handle::weak(HandleId::random::<StandardMaterial>());
+        let grass = Handle::weak(HandleId::random::<StandardMaterial>());
+        let chunk_id = app.world.spawn(SpatialBundle::default()).id();
+
+        let replace = |app: &mut App, chunk_meshes: Vec<(Mesh, Handle<StandardMaterial>)>| {
+            Schedule::new()
+                .add_systems(
+                    move |mesh_query: Query<ChunkMeshHandles, With<ChunkMesh>>,
+                          mut meshes: ResMut<Assets<Mesh>>,
+                          mut commands: Commands| {
+                        replace_chunk_meshes(
+                            chunk_id,
+                            chunk_meshes.clone(),
+                            Transform::IDENTITY,
+                            &mesh_query,
+                            &mut meshes,
+                            None,
+                            &mut commands,
+                        );
+                    },
+                )
+                .run(&mut app.world);
+
+            let mut query = app
+                .world
+                .query::<(Entity, &Handle<Mesh>, &Handle<StandardMaterial>)>();
+            let mut entities: Vec<_> = query
+                .iter(&app.world)
+                .map(|(id, mesh, material)| (id, mesh.clone(), material.clone()))
+                .collect();
+            entities.sort_by_key(|(id, ..)| *id);
+            entities
+        };
+
+        let cube = Mesh::from(shape::Cube::default());
+        let plane = Mesh::from(shape::Plane::default());
+
+        let first = replace(&mut app, vec![(cube.clone(), stone.clone())]);
+        assert_eq!(first.len(), 1);
+
+        let second = replace(&mut app, vec![
+            (plane, stone.clone()),
+            (cube, grass.clone()),
+        ]);
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[0], first[0]);
+        assert_eq!(second[1].2, grass);
+
+        let meshes = app.world.resource::<Assets<Mesh>>();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes.get(&first[0].1).unwrap().count_vertices(), 4);
+
+        let third = replace(&mut app, vec![]);
+        assert!(third.is_empty());
+    }
+
+    #[test]
+    fn quantized_chunk_mesh_entities() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default()).add_asset::<Mesh>();
+
+        let stone = Handle::weak(HandleId::random::<StandardMaterial>());
+        let chunk_id = app.world.spawn(SpatialBundle::default()).id();
+
+        let temp_mesh = || {
+            let mut temp_mesh = TempMesh {
+                material: stone.clone(),
+                ..default()
+            };
+            temp_mesh.add_quad(
+                [Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y],
+                Vec3::Z,
+                [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y],
+                Default::default(),
+            );
+            temp_mesh
+        };
+        let standard = temp_mesh().into_mesh().unwrap();
+        let quantized = temp_mesh().into_quantized_mesh().unwrap();
+
+        let replace = |app: &mut App, chunk_meshes: Vec<(Mesh, Handle<StandardMaterial>)>| {
+            Schedule::new()
+                .add_systems(
+                    move |mesh_query: Query<ChunkMeshHandles, With<ChunkMesh>>,
+                          mut meshes: ResMut<Assets<Mesh>>,
+                          mut commands: Commands| {
+                        replace_chunk_meshes(
+                            chunk_id,
+                            chunk_meshes.clone(),
+                            Transform::IDENTITY,
+                            &mesh_query,
+                            &mut meshes,
+                            None,
+                            &mut commands,
+                        );
+                    },
+                )
+                .run(&mut app.world);
+
+            app.world
+                .query_filtered::<Entity, With<ChunkMesh>>()
+                .iter(&app.world)
+                .collect::<Vec<_>>()
+        };
+
+        let first = replace(&mut app, vec![standard]);
+        assert_eq!(first.len(), 1);
+
+        let second = replace(&mut app, vec![quantized.clone()]);
+        assert_eq!(second.len(), 1);
+        assert_ne!(second, first);
+
+        let chunk_mesh = app.world.entity(second[0]);
+        assert_eq!(chunk_mesh.get::<QuantizedChunkMesh>().unwrap().0, stone);
+        assert!(!chunk_mesh.contains::<Handle<StandardMaterial>>());
+        assert_eq!(
+            Vec3::from(chunk_mesh.get::<Aabb>().unwrap().max()),
+            Vec3::ONE
+        );
+
+        let third = replace(&mut app, vec![quantized]);
+        assert_eq!(third, second);
+    }
+
+    #[test]
+    fn direct_chunk_mesh_entities() {
+        let mut app = App::new();
+        app.add_plugins(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .init_resource::<DirectChunkMeshes>();
+
+        let stone = Handle::weak(HandleId::random::<StandardMaterial>());
+        let chunk_id = app.world.spawn(SpatialBundle::default()).id();
+
+        let replace = |app: &mut App, chunk_meshes: Vec<(Mesh, Handle<StandardMaterial>)>| {
+            Schedule::new()
+                .add_systems(
+                    move |mesh_query: Query<ChunkMeshHandles, With<ChunkMesh>>,
+                          mut meshes: ResMut<Assets<Mesh>>,
+                          mut direct_meshes: ResMut<DirectChunkMeshes>,
+                          mut commands: Commands| {
+                        replace_chunk_meshes(
+                            chunk_id,
+                            chunk_meshes.clone(),
+                            Transform::IDENTITY,
+                            &mesh_query,
+                            &mut meshes,
+                            Some(&mut direct_meshes),
+                            &mut commands,
+                        );
+                    },
+                )
+                .run(&mut app.world);
+
+            app.world
+                .query_filtered::<(Entity, &Handle<Mesh>), With<ChunkMesh>>()
+                .iter(&app.world)
+                .map(|(id, mesh)| (id, mesh.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let cube = Mesh::from(shape::Cube::default());
+        let plane = Mesh::from(shape::Plane::default());
+
+        let first = replace(&mut app, vec![(cube, stone.clone())]);
+        assert_eq!(first.len(), 1);
+
+        let second = replace(&mut app, vec![(plane, stone)]);
+        assert_eq!(second, first);
+
+        let (chunk_mesh_id, mesh_handle) = first[0].clone();
+        assert!(app.world.resource::<Assets<Mesh>>().is_empty());
+        assert!(app.world.entity(chunk_mesh_id).contains::<Aabb>());
+
+        let mut direct_meshes = app.world.resource_mut::<DirectChunkMeshes>();
+        assert_eq!(direct_meshes.get(chunk_mesh_id), Some(&mesh_handle));
+
+        // Only the latest mesh of each chunk mesh entity is extracted.
+        let (uploads, removed) = direct_meshes.take();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].0, mesh_handle);
+        assert_eq!(uploads[0].1.count_vertices(), 4);
+        assert!(removed.is_empty());
+
+        assert!(replace(&mut app, vec![]).is_empty());
+        Schedule::new()
+            .add_systems(release_direct_chunk_meshes)
+            .run(&mut app.world);
+
+        let mut direct_meshes = app.world.resource_mut::<DirectChunkMeshes>();
+        assert!(direct_meshes.is_empty());
+        assert_eq!(direct_meshes.take().1, vec![mesh_handle]);
+    }
 }
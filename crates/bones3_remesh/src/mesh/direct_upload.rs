@@ -0,0 +1,139 @@
+//! This module contains the render world integration that hands finished
+//! chunk meshes directly to the renderer, without storing them as mesh assets.
+//!
+//! See [`DirectChunkMeshPlugin`](crate::DirectChunkMeshPlugin) for more
+//! information.
+
+use bevy::asset::HandleId;
+use bevy::ecs::system::StaticSystemParam;
+use bevy::prelude::*;
+use bevy::render::render_asset::{PrepareAssetError, RenderAsset, RenderAssets};
+use bevy::render::MainWorld;
+use bevy::utils::HashMap;
+
+/// This resource stores the mesh handles of all chunk mesh entities that use
+/// direct meshes, along with the meshes that are waiting to be moved into the
+/// render world.
+///
+/// Direct mesh handles are weak handles that never point to a mesh asset.
+/// Instead, their meshes are prepared under the same handle within the render
+/// world, so chunk mesh entities are drawn by the standard mesh pipelines.
+#[derive(Resource, Default)]
+pub struct DirectChunkMeshes {
+    /// The mesh handle of each chunk mesh entity that uses a direct mesh.
+    handles: HashMap<Entity, Handle<Mesh>>,
+
+    /// The meshes that have not been extracted yet, by mesh handle.
+    uploads: HashMap<Handle<Mesh>, Mesh>,
+
+    /// The mesh handles of the despawned chunk mesh entities that have not
+    /// been extracted yet.
+    removed: Vec<Handle<Mesh>>,
+}
+
+impl DirectChunkMeshes {
+    /// Gets the number of chunk mesh entities that use direct meshes.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Checks whether there are no chunk mesh entities that use direct meshes.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Gets the mesh handle of the given chunk mesh entity, if it uses a
+    /// direct mesh.
+    pub fn get(&self, chunk_mesh_id: Entity) -> Option<&Handle<Mesh>> {
+        self.handles.get(&chunk_mesh_id)
+    }
+
+    /// Queues the given mesh to be shown by the given chunk mesh entity,
+    /// replacing any mesh that is still waiting to be extracted for it.
+    ///
+    /// Returns the mesh handle of the chunk mesh entity, which is created if
+    /// the entity did not use a direct mesh yet.
+    pub(crate) fn upload(&mut self, chunk_mesh_id: Entity, mesh: Mesh) -> Handle<Mesh> {
+        let handle = self
+            .handles
+            .entry(chunk_mesh_id)
+            .or_insert_with(|| Handle::weak(HandleId::random::<Mesh>()))
+            .clone_weak();
+        self.uploads.insert(handle.clone_weak(), mesh);
+        handle
+    }
+
+    /// Releases the direct mesh of the given chunk mesh entity, if it has one.
+    pub(crate) fn release(&mut self, chunk_mesh_id: Entity) {
+        let Some(handle) = self.handles.remove(&chunk_mesh_id) else {
+            return;
+        };
+
+        self.uploads.remove(&handle);
+        self.removed.push(handle);
+    }
+
+    /// Takes all meshes and released mesh handles that are waiting to be
+    /// extracted.
+    pub(crate) fn take(&mut self) -> (Vec<(Handle<Mesh>, Mesh)>, Vec<Handle<Mesh>>) {
+        (
+            self.uploads.drain().collect(),
+            std::mem::take(&mut self.removed),
+        )
+    }
+}
+
+/// The render world resource that stores the direct chunk meshes that have
+/// been extracted, but not prepared yet.
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedChunkMeshes {
+    /// The meshes to prepare, by mesh handle.
+    uploads: Vec<(Handle<Mesh>, Mesh)>,
+
+    /// The mesh handles of the meshes to release.
+    removed: Vec<Handle<Mesh>>,
+}
+
+/// This system moves all queued direct chunk meshes out of the main world and
+/// into the render world.
+///
+/// Unlike mesh assets, the meshes are moved rather than cloned.
+pub(crate) fn extract_direct_chunk_meshes(
+    mut main_world: ResMut<MainWorld>,
+    mut extracted: ResMut<ExtractedChunkMeshes>,
+) {
+    let Some(mut direct_meshes) = main_world.get_resource_mut::<DirectChunkMeshes>() else {
+        return;
+    };
+
+    let (uploads, mut removed) = direct_meshes.bypass_change_detection().take();
+    extracted.uploads.extend(uploads);
+    extracted.removed.append(&mut removed);
+}
+
+/// This system prepares all extracted direct chunk meshes for the GPU, storing
+/// them within the render mesh assets under their direct mesh handles, and
+/// removes the render meshes of released direct mesh handles.
+pub(crate) fn prepare_direct_chunk_meshes(
+    mut extracted: ResMut<ExtractedChunkMeshes>,
+    mut render_meshes: ResMut<RenderAssets<Mesh>>,
+    param: StaticSystemParam<<Mesh as RenderAsset>::Param>,
+) {
+    let mut param = param.into_inner();
+
+    let mut retry = vec![];
+    for (handle, mesh) in std::mem::take(&mut extracted.uploads) {
+        match Mesh::prepare_asset(mesh, &mut param) {
+            Ok(gpu_mesh) => {
+                render_meshes.insert(handle, gpu_mesh);
+            },
+            Err(PrepareAssetError::RetryNextUpdate(mesh)) => retry.push((handle, mesh)),
+        }
+    }
+    extracted.uploads = retry;
+
+    for handle in std::mem::take(&mut extracted.removed) {
+        extracted.uploads.retain(|(h, _)| *h != handle);
+        render_meshes.remove(&handle);
+    }
+}
@@ -0,0 +1,140 @@
+//! Describes how much of a block face is covered by a block model.
+
+use bevy::prelude::*;
+
+/// Describes which parts of a single block face are covered by a block model,
+/// stored as a 4x4 grid of cells.
+///
+/// Each face is projected onto its axis-aligned plane using the axes `(u, v)`.
+/// For the X faces, these are `(z, y)`. For the Y faces, these are `(x, z)`.
+/// For the Z faces, these are `(x, y)`. Because opposite faces share the same
+/// projection, the coverage of two touching faces can be compared directly.
+///
+/// The cell at `(u, v)` is stored in bit `v * 4 + u`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaceCoverage(u16);
+
+impl FaceCoverage {
+    /// A face that is not covered at all.
+    pub const EMPTY: FaceCoverage = FaceCoverage(0x0000);
+    /// A face that is completely covered.
+    pub const FULL: FaceCoverage = FaceCoverage(0xFFFF);
+    /// The lower half of a face, along the `v` axis.
+    pub const HALF_BOTTOM: FaceCoverage = FaceCoverage(0x00FF);
+    /// The lower half of a face, along the `u` axis.
+    pub const HALF_LEFT: FaceCoverage = FaceCoverage(0x3333);
+    /// The upper half of a face, along the `u` axis.
+    pub const HALF_RIGHT: FaceCoverage = FaceCoverage(0xCCCC);
+    /// The upper half of a face, along the `v` axis.
+    pub const HALF_TOP: FaceCoverage = FaceCoverage(0xFF00);
+    /// The quarter of a face with the lowest `u` and `v` values.
+    pub const QUARTER_BOTTOM_LEFT: FaceCoverage = FaceCoverage(0x0033);
+    /// The quarter of a face with the highest `u` and lowest `v` values.
+    pub const QUARTER_BOTTOM_RIGHT: FaceCoverage = FaceCoverage(0x00CC);
+    /// The quarter of a face with the lowest `u` and highest `v` values.
+    pub const QUARTER_TOP_LEFT: FaceCoverage = FaceCoverage(0x3300);
+    /// The quarter of a face with the highest `u` and `v` values.
+    pub const QUARTER_TOP_RIGHT: FaceCoverage = FaceCoverage(0xCC00);
+
+    /// Creates a new face coverage value from a custom 4x4 bit mask.
+    pub const fn from_mask(mask: u16) -> Self {
+        Self(mask)
+    }
+
+    /// Creates a new face coverage value that covers all cells within the
+    /// given inclusive range of cell coordinates.
+    ///
+    /// Cell coordinates greater than 3 are clamped.
+    pub fn from_cells(min: UVec2, max: UVec2) -> Self {
+        let min = min.min(UVec2::splat(3));
+        let max = max.min(UVec2::splat(3));
+
+        let mut mask = 0;
+        for v in min.y ..= max.y {
+            for u in min.x ..= max.x {
+                mask |= 1 << (v * 4 + u);
+            }
+        }
+
+        Self(mask)
+    }
+
+    /// Gets the raw 4x4 bit mask of this face coverage.
+    pub const fn mask(self) -> u16 {
+        self.0
+    }
+
+    /// Gets whether or not this face coverage is empty.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Gets whether or not every cell that is covered by `other` is also
+    /// covered by this face coverage.
+    pub const fn covers(self, other: FaceCoverage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks whether or not a face with the coverage `other` is hidden by a
+    /// touching face with this coverage.
+    ///
+    /// Faces without any coverage are never hidden, as their geometry does not
+    /// lie along the block boundary.
+    pub const fn occludes(self, other: FaceCoverage) -> bool {
+        !other.is_empty() && self.covers(other)
+    }
+
+    /// Flips this face coverage along the `v` axis.
+    ///
+    /// This is useful for converting a bottom-aligned shape into a
+    /// top-aligned shape.
+    pub const fn flip_v(self) -> Self {
+        let m = self.0;
+        Self(
+            ((m & 0x000F) << 12) | ((m & 0x00F0) << 4) | ((m & 0x0F00) >> 4) | ((m & 0xF000) >> 12),
+        )
+    }
+}
+
+impl std::ops::BitOr for FaceCoverage {
+    type Output = FaceCoverage;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitAnd for FaceCoverage {
+    type Output = FaceCoverage;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn slab_coverage() {
+        let bottom_slab_side = FaceCoverage::HALF_BOTTOM;
+        let top_slab_side = FaceCoverage::HALF_TOP;
+
+        assert!(bottom_slab_side.occludes(bottom_slab_side));
+        assert!(!bottom_slab_side.occludes(top_slab_side));
+        assert!(FaceCoverage::FULL.occludes(top_slab_side));
+        assert!(!FaceCoverage::FULL.occludes(FaceCoverage::EMPTY));
+        assert_eq!(bottom_slab_side.flip_v(), top_slab_side);
+        assert_eq!(
+            FaceCoverage::QUARTER_BOTTOM_LEFT | FaceCoverage::QUARTER_BOTTOM_RIGHT,
+            FaceCoverage::HALF_BOTTOM
+        );
+        assert_eq!(
+            FaceCoverage::from_cells(UVec2::new(2, 2), UVec2::new(3, 3)),
+            FaceCoverage::QUARTER_TOP_RIGHT
+        );
+    }
+}
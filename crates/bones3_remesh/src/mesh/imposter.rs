@@ -0,0 +1,276 @@
+//! Far-field imposters, which render distant chunk columns as low resolution
+//! heightfield meshes instead of full chunk meshes.
+//!
+//! Imposters are built from downsampled surface data, rather than from loaded
+//! chunks, so they may cover chunk columns that have never been generated. This
+//! allows the horizon to extend for hundreds of chunks, while only the chunks
+//! near the camera are loaded and meshed.
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bones3_core::storage::UpAxis;
+use bones3_core::util::world_map::WorldMapImage;
+
+/// A source of surface data for building imposters, such as a world generator
+/// or a precomputed heightmap.
+pub trait ImposterSource: Send + Sync + 'static {
+    /// Samples the surface of the world at the given block column, using the
+    /// column axes of the world's [`UpAxis`].
+    ///
+    /// Returns `None` if the column has no surface, in which case all imposter
+    /// cells that touch this column are left empty.
+    fn sample_surface(&self, block_column: IVec2) -> Option<ImposterSample>;
+}
+
+/// World map images can be used as an imposter source, to render the stored
+/// chunks of a world that were downsampled into a map as distant scenery.
+///
+/// The surface is placed on top of the top-most block of each column. Columns
+/// outside of the image, or without any blocks, have no surface.
+impl ImposterSource for WorldMapImage {
+    fn sample_surface(&self, block_column: IVec2) -> Option<ImposterSample> {
+        let pixel = block_column - self.origin();
+        if pixel.x < 0 || pixel.y < 0 {
+            return None;
+        }
+
+        let (x, y) = (pixel.x as usize, pixel.y as usize);
+        let height = self.get_height(x, y)?;
+        let [r, g, b, a] = self.get_color(x, y)?;
+
+        Some(ImposterSample {
+            height: (height + 1) as f32,
+            color:  Color::rgba_u8(r, g, b, a),
+        })
+    }
+}
+
+/// A single surface sample of an imposter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImposterSample {
+    /// The height of the surface along the up axis of the world, in blocks.
+    pub height: f32,
+
+    /// The color of the surface.
+    pub color: Color,
+}
+
+/// Builds the heightfield imposter mesh for the chunk column at the given
+/// column coordinates, with the given number of cells along each side.
+///
+/// The surface is sampled at the corners of each cell, so neighboring
+/// imposters share the same samples along their edges. Vertex positions are
+/// given in block units, relative to the origin of the chunk at height `0`
+/// within the column. Returns `None` if the column does not contain any
+/// surface.
+///
+/// # Panics
+///
+/// Panics if the resolution is not a divisor of 16.
+pub fn build_imposter_mesh<F>(
+    chunk_column: IVec2,
+    up: UpAxis,
+    resolution: usize,
+    sample: F,
+) -> Option<Mesh>
+where
+    F: Fn(IVec2) -> Option<ImposterSample>,
+{
+    assert!(
+        resolution > 0 && 16 % resolution == 0,
+        "Resolution must be a divisor of 16"
+    );
+
+    let step = (16 / resolution) as i32;
+    let side = resolution as i32 + 1;
+    let origin = chunk_column * 16;
+
+    // Sample one extra ring of corners around the column, so that normals
+    // along the edges match those of the neighboring imposters.
+    let samples: Vec<Option<ImposterSample>> = (-1 ..= side)
+        .flat_map(|x| (-1 ..= side).map(move |z| IVec2::new(x, z)))
+        .map(|corner| sample(origin + corner * step))
+        .collect();
+    let get = |x: i32, z: i32| samples[((x + 1) * (side + 2) + z + 1) as usize];
+
+    let axis_a = up.compose(IVec2::X, 0).as_vec3();
+    let axis_b = up.compose(IVec2::Y, 0).as_vec3();
+    let axis_up = up.compose(IVec2::ZERO, 1).as_vec3();
+
+    let mut vertices: Vec<Vec3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut colors: Vec<[f32; 4]> = vec![];
+    let mut corner_indices: Vec<Option<u32>> = vec![];
+
+    for x in 0 .. side {
+        for z in 0 .. side {
+            let Some(corner) = get(x, z) else {
+                corner_indices.push(None);
+                continue;
+            };
+
+            let height_at =
+                |dx: i32, dz: i32| get(x + dx, z + dz).map_or(corner.height, |s| s.height);
+            let slope_a = (height_at(1, 0) - height_at(-1, 0)) / (2 * step) as f32;
+            let slope_b = (height_at(0, 1) - height_at(0, -1)) / (2 * step) as f32;
+            let tangent_a = axis_a + axis_up * slope_a;
+            let tangent_b = axis_b + axis_up * slope_b;
+            let mut normal = tangent_a.cross(tangent_b).normalize_or_zero();
+            if normal.dot(axis_up) < 0.0 {
+                normal = -normal;
+            }
+
+            corner_indices.push(Some(vertices.len() as u32));
+            vertices.push(
+                axis_a * (x * step) as f32 + axis_b * (z * step) as f32 + axis_up * corner.height,
+            );
+            normals.push(normal);
+            colors.push(corner.color.as_linear_rgba_f32());
+        }
+    }
+
+    // Keep the triangles facing up, regardless of the handedness of the column
+    // axes.
+    let flip = axis_a.cross(axis_b).dot(axis_up) > 0.0;
+
+    let mut indices: Vec<u32> = vec![];
+    for x in 0 .. side - 1 {
+        for z in 0 .. side - 1 {
+            let corner = |dx: i32, dz: i32| corner_indices[((x + dx) * side + z + dz) as usize];
+            let (Some(c00), Some(c01), Some(c10), Some(c11)) =
+                (corner(0, 0), corner(0, 1), corner(1, 0), corner(1, 1))
+            else {
+                continue;
+            };
+
+            if flip {
+                indices.extend([c00, c11, c01, c00, c10, c11]);
+            } else {
+                indices.extend([c00, c01, c11, c00, c11, c10]);
+            }
+        }
+    }
+
+    if indices.is_empty() {
+        return None;
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.compute_aabb();
+    Some(mesh)
+}
+
+/// Gets an iterator over all chunk columns within the ring around the given
+/// center column, between the inner radius and the outer radius along each
+/// column axis.
+///
+/// Columns within the inner radius are skipped, as they are expected to be
+/// covered by real chunk meshes.
+pub fn imposter_ring(
+    center: IVec2,
+    radius: UVec2,
+    inner_radius: u32,
+) -> impl Iterator<Item = IVec2> {
+    let radius = radius.as_ivec2();
+    let inner = inner_radius as i32;
+
+    (-radius.x ..= radius.x)
+        .flat_map(move |x| (-radius.y ..= radius.y).map(move |z| IVec2::new(x, z)))
+        .filter(move |offset| offset.x.abs() > inner || offset.y.abs() > inner)
+        .map(move |offset| center + offset)
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::reflect::TypePath;
+    use bevy::render::mesh::VertexAttributeValues;
+    use bones3_core::storage::VoxelStorage;
+    use bones3_core::util::minimap::MinimapBlock;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn hill(block_column: IVec2) -> Option<ImposterSample> {
+        if block_column.x >= 32 {
+            return None;
+        }
+
+        Some(ImposterSample {
+            height: block_column.x as f32 * 0.5,
+            color:  Color::GREEN,
+        })
+    }
+
+    #[test]
+    fn build_heightfield() {
+        let mesh = build_imposter_mesh(IVec2::ZERO, UpAxis::PosY, 4, hill).unwrap();
+        assert_eq!(mesh.count_vertices(), 25);
+        assert_eq!(mesh.indices().unwrap().len(), 4 * 4 * 6);
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("Missing positions");
+        };
+        assert_eq!(positions[0], [0.0, 0.0, 0.0]);
+        assert_eq!(positions[24], [16.0, 8.0, 16.0]);
+
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("Missing normals");
+        };
+        let expected = Vec3::new(-0.5, 1.0, 0.0).normalize();
+        assert!(Vec3::from(normals[12]).abs_diff_eq(expected, 1e-6));
+
+        // The last row of cells touches columns without a surface.
+        let mesh = build_imposter_mesh(IVec2::new(1, 0), UpAxis::PosY, 4, hill).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 3 * 4 * 6);
+        assert!(build_imposter_mesh(IVec2::new(2, 0), UpAxis::PosY, 4, hill).is_none());
+    }
+
+    #[derive(Debug, Default, Clone, Copy, TypePath)]
+    struct Stone(bool);
+
+    impl MinimapBlock for Stone {
+        type Cell = ();
+
+        fn minimap_cell(&self) -> Option<Self::Cell> {
+            self.0.then_some(())
+        }
+    }
+
+    #[test]
+    fn sample_world_map() {
+        let mut storage = VoxelStorage::<Stone>::default();
+        storage.set_block(IVec3::new(2, 5, 3), Stone(true));
+        let map =
+            WorldMapImage::from_chunks([(IVec3::new(1, 0, 0), &storage)], UpAxis::PosY, |_| {
+                [255, 0, 0, 255]
+            })
+            .unwrap();
+
+        assert_eq!(
+            map.sample_surface(IVec2::new(18, 3)),
+            Some(ImposterSample {
+                height: 6.0,
+                color:  Color::rgba_u8(255, 0, 0, 255),
+            })
+        );
+        assert_eq!(map.sample_surface(IVec2::new(18, 4)), None);
+        assert_eq!(map.sample_surface(IVec2::new(2, 3)), None);
+    }
+
+    #[test]
+    fn ring_skips_inner_columns() {
+        let ring: Vec<IVec2> = imposter_ring(IVec2::new(10, 0), UVec2::new(2, 2), 1).collect();
+        assert_eq!(ring.len(), 25 - 9);
+        assert!(!ring.contains(&IVec2::new(11, 1)));
+        assert!(ring.contains(&IVec2::new(12, -1)));
+    }
+}
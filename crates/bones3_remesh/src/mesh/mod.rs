@@ -2,4 +2,11 @@
 
 pub mod block_model;
 pub mod builder;
+pub mod direct_upload;
 pub mod error;
+pub mod face_coverage;
+pub mod imposter;
+pub mod preview;
+pub mod quantized_material;
+pub mod seams;
+pub mod smooth;
@@ -0,0 +1,55 @@
+//! Builds a single preview mesh for a world snapshot.
+
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bones3_core::storage::{BlockData, WorldSnapshot};
+
+use crate::ecs::resources::ChunkMaterialList;
+use crate::mesh::block_model::BlockShape;
+use crate::mesh::builder::build_chunk_mesh;
+
+/// Builds a single mesh containing the block shapes of every chunk within the
+/// given world snapshot, for use within previews and thumbnails.
+///
+/// Vertex positions are given in block coordinates. All materials are merged
+/// into the same mesh, so material information is discarded. Returns `None` if
+/// the snapshot does not contain any visible geometry.
+pub fn build_snapshot_mesh<T>(
+    snapshot: &WorldSnapshot<T>,
+    material_list: &ChunkMaterialList,
+) -> Option<Mesh>
+where
+    T: BlockData + BlockShape,
+{
+    let mut vertices: Vec<Vec3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<Vec2> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    for chunk_coords in snapshot.chunk_coords() {
+        let offset = chunk_coords * 16;
+        let get_block = |local_pos: IVec3| snapshot.get_block(local_pos + offset);
+        let shape_builder = build_chunk_mesh(get_block, material_list);
+
+        for temp_mesh in shape_builder.into_temp_meshes() {
+            let vertex_count = vertices.len() as u32;
+            indices.extend(temp_mesh.indices.iter().map(|i| *i as u32 + vertex_count));
+            vertices.extend(temp_mesh.vertices.iter().map(|v| *v + offset.as_vec3()));
+            normals.extend(temp_mesh.normals);
+            uvs.extend(temp_mesh.uvs);
+        }
+    }
+
+    if indices.is_empty() {
+        return None;
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh.compute_aabb();
+    Some(mesh)
+}
@@ -0,0 +1,96 @@
+#import bevy_pbr::mesh_bindings             mesh
+#import bevy_pbr::mesh_functions            as mesh_functions
+#import bevy_pbr::mesh_view_bindings        view, fog
+#import bevy_pbr::mesh_view_types           FOG_MODE_OFF
+#import bevy_pbr::pbr_functions             as pbr_functions
+#import bevy_pbr::pbr_types                 as pbr_types
+#import bevy_core_pipeline::tonemapping     tone_mapping
+
+struct QuantizedChunkMaterial {
+    base_color: vec4<f32>,
+    flags: u32,
+    alpha_cutoff: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: QuantizedChunkMaterial;
+@group(1) @binding(1)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+
+// These must match the constants within the quantized vertex data module.
+const QUANTIZED_POSITION_MIN: f32 = -16.0;
+const QUANTIZED_POSITION_STEP: f32 = 0.0009765625;
+const QUANTIZED_UV_STEP: f32 = 0.0009765625;
+
+struct Vertex {
+    @location(0) position: vec4<u32>,
+    @location(1) normal: vec4<f32>,
+    @location(2) uv: vec2<u32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let position = vec3<f32>(vertex.position.xyz) * QUANTIZED_POSITION_STEP + QUANTIZED_POSITION_MIN;
+
+    var out: VertexOutput;
+    out.world_position = mesh_functions::mesh_position_local_to_world(mesh.model, vec4<f32>(position, 1.0));
+    out.position = mesh_functions::mesh_position_world_to_clip(out.world_position);
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal.xyz);
+    out.uv = vec2<f32>(vertex.uv) * QUANTIZED_UV_STEP;
+    return out;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    var output_color = material.base_color;
+    if ((material.flags & pbr_types::STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u) {
+        output_color = output_color * textureSampleBias(base_color_texture, base_color_sampler, in.uv, view.mip_bias);
+    }
+
+    var pbr_input = pbr_functions::pbr_input_new();
+    pbr_input.material.base_color = output_color;
+    pbr_input.material.flags = material.flags;
+    pbr_input.material.alpha_cutoff = material.alpha_cutoff;
+
+    if ((material.flags & pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u) {
+        pbr_input.frag_coord = in.position;
+        pbr_input.world_position = in.world_position;
+        pbr_input.world_normal = pbr_functions::prepare_world_normal(
+            in.world_normal,
+            (material.flags & pbr_types::STANDARD_MATERIAL_FLAGS_DOUBLE_SIDED_BIT) != 0u,
+            is_front,
+        );
+        pbr_input.is_orthographic = view.projection[3].w == 1.0;
+        pbr_input.N = normalize(pbr_input.world_normal);
+        pbr_input.V = pbr_functions::calculate_view(in.world_position, pbr_input.is_orthographic);
+        pbr_input.flags = mesh.flags;
+
+        output_color = pbr_functions::pbr(pbr_input);
+    } else {
+        output_color = pbr_functions::alpha_discard(pbr_input.material, output_color);
+    }
+
+    if (fog.mode != FOG_MODE_OFF && (material.flags & pbr_types::STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT) != 0u) {
+        output_color = pbr_functions::apply_fog(fog, output_color, in.world_position.xyz, view.world_position.xyz);
+    }
+
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color, view.color_grading);
+#endif
+#ifdef PREMULTIPLY_ALPHA
+    output_color = pbr_functions::premultiply_alpha(material.flags, output_color);
+#endif
+    return output_color;
+}
@@ -0,0 +1,290 @@
+//! The material that is used to render chunk meshes that use the quantized
+//! vertex format.
+//!
+//! See the [quantized](crate::vertex_data::quantized) module for more
+//! information about the vertex format itself.
+
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey, StandardMaterialFlags};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::render::mesh::MeshVertexBufferLayout;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup,
+    AsBindGroupShaderType,
+    Face,
+    RenderPipelineDescriptor,
+    ShaderRef,
+    SpecializedMeshPipelineError,
+};
+use bevy::utils::HashMap;
+
+use crate::vertex_data::quantized::{
+    ATTRIBUTE_PACKED_NORMAL,
+    ATTRIBUTE_QUANTIZED_POSITION,
+    ATTRIBUTE_QUANTIZED_UV,
+};
+
+/// The handle of the shader that decodes quantized chunk meshes.
+pub const QUANTIZED_CHUNK_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x00B3_0001_0000_0001);
+
+/// A material for chunk meshes that use the quantized vertex format.
+///
+/// Quantized chunk materials are created automatically from the materials
+/// within the [`ChunkMaterialList`](crate::ecs::resources::ChunkMaterialList)
+/// by the [`QuantizedChunkMeshPlugin`](crate::QuantizedChunkMeshPlugin), and
+/// are kept up to date whenever their source material is modified. Only the
+/// base color, base color texture, alpha mode, and the render settings of the
+/// source material are used. Quantized chunk meshes do not cast shadows.
+#[derive(Debug, Clone, AsBindGroup, TypeUuid, TypePath)]
+#[uuid = "6b5cbf3e-5a0b-4f6d-9a61-3c1f4f5d2b07"]
+#[uniform(0, QuantizedChunkMaterialUniform)]
+#[bind_group_data(QuantizedChunkMaterialKey)]
+pub struct QuantizedChunkMaterial {
+    /// The base color of the material.
+    pub base_color: Color,
+
+    /// The texture that is multiplied with the base color.
+    #[texture(1)]
+    #[sampler(2)]
+    pub base_color_texture: Option<Handle<Image>>,
+
+    /// The alpha mode of the material.
+    pub alpha_mode: AlphaMode,
+
+    /// Whether back faces are rendered and lit as well.
+    pub double_sided: bool,
+
+    /// Whether lighting is ignored.
+    pub unlit: bool,
+
+    /// Whether fog is applied to the material.
+    pub fog_enabled: bool,
+}
+
+impl From<&StandardMaterial> for QuantizedChunkMaterial {
+    fn from(material: &StandardMaterial) -> Self {
+        Self {
+            base_color:         material.base_color,
+            base_color_texture: material.base_color_texture.clone(),
+            alpha_mode:         material.alpha_mode,
+            double_sided:       material.double_sided,
+            unlit:              material.unlit,
+            fog_enabled:        material.fog_enabled,
+        }
+    }
+}
+
+pub use self::uniform::QuantizedChunkMaterialUniform;
+
+/// Contains the uniform data of a [`QuantizedChunkMaterial`].
+///
+/// The `ShaderType` derive generates compile-time checks that are reported as
+/// unused, so they are kept within their own module.
+mod uniform {
+    #![allow(dead_code)]
+
+    use bevy::prelude::*;
+    use bevy::render::render_resource::ShaderType;
+
+    /// The GPU representation of the uniform data of a
+    /// [`QuantizedChunkMaterial`].
+    #[derive(Debug, Clone, Default, ShaderType)]
+    pub struct QuantizedChunkMaterialUniform {
+        /// The base color of the material, in linear space.
+        pub base_color: Vec4,
+
+        /// The [`StandardMaterialFlags`](bevy::pbr::StandardMaterialFlags) of
+        /// the material.
+        pub flags: u32,
+
+        /// The alpha cutoff of masked materials.
+        pub alpha_cutoff: f32,
+    }
+}
+
+impl AsBindGroupShaderType<QuantizedChunkMaterialUniform> for QuantizedChunkMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<Image>,
+    ) -> QuantizedChunkMaterialUniform {
+        let mut flags = StandardMaterialFlags::NONE;
+        if self.base_color_texture.is_some() {
+            flags |= StandardMaterialFlags::BASE_COLOR_TEXTURE;
+        }
+        if self.double_sided {
+            flags |= StandardMaterialFlags::DOUBLE_SIDED;
+        }
+        if self.unlit {
+            flags |= StandardMaterialFlags::UNLIT;
+        }
+        if self.fog_enabled {
+            flags |= StandardMaterialFlags::FOG_ENABLED;
+        }
+
+        let mut alpha_cutoff = 0.5;
+        flags |= match self.alpha_mode {
+            AlphaMode::Opaque => StandardMaterialFlags::ALPHA_MODE_OPAQUE,
+            AlphaMode::Mask(cutoff) => {
+                alpha_cutoff = cutoff;
+                StandardMaterialFlags::ALPHA_MODE_MASK
+            },
+            AlphaMode::Blend => StandardMaterialFlags::ALPHA_MODE_BLEND,
+            AlphaMode::Premultiplied => StandardMaterialFlags::ALPHA_MODE_PREMULTIPLIED,
+            AlphaMode::Add => StandardMaterialFlags::ALPHA_MODE_ADD,
+            AlphaMode::Multiply => StandardMaterialFlags::ALPHA_MODE_MULTIPLY,
+        };
+
+        QuantizedChunkMaterialUniform {
+            base_color: self.base_color.as_linear_rgba_f32().into(),
+            flags: flags.bits(),
+            alpha_cutoff,
+        }
+    }
+}
+
+/// The pipeline key of a [`QuantizedChunkMaterial`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuantizedChunkMaterialKey {
+    /// The faces that are culled.
+    cull_mode: Option<Face>,
+}
+
+impl From<&QuantizedChunkMaterial> for QuantizedChunkMaterialKey {
+    fn from(material: &QuantizedChunkMaterial) -> Self {
+        Self {
+            cull_mode: match material.double_sided {
+                true => None,
+                false => Some(Face::Back),
+            },
+        }
+    }
+}
+
+impl Material for QuantizedChunkMaterial {
+    fn vertex_shader() -> ShaderRef {
+        QUANTIZED_CHUNK_SHADER_HANDLE.typed().into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        QUANTIZED_CHUNK_SHADER_HANDLE.typed().into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.buffers = vec![layout.get_layout(&[
+            ATTRIBUTE_QUANTIZED_POSITION.at_shader_location(0),
+            ATTRIBUTE_PACKED_NORMAL.at_shader_location(1),
+            ATTRIBUTE_QUANTIZED_UV.at_shader_location(2),
+        ])?];
+        descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
+        Ok(())
+    }
+}
+
+/// This resource maps the materials within the chunk material list to the
+/// quantized chunk materials that are created from them.
+#[derive(Debug, Resource, Default)]
+pub struct QuantizedChunkMaterials {
+    /// The quantized chunk material of each source material.
+    materials: HashMap<Handle<StandardMaterial>, Handle<QuantizedChunkMaterial>>,
+}
+
+impl QuantizedChunkMaterials {
+    /// Gets the quantized chunk material that was created from the given
+    /// source material, if it exists.
+    pub fn get(
+        &self,
+        material: &Handle<StandardMaterial>,
+    ) -> Option<&Handle<QuantizedChunkMaterial>> {
+        self.materials.get(material)
+    }
+
+    /// Gets the quantized chunk material that was created from the given
+    /// source material, creating it if it does not exist yet.
+    ///
+    /// Returns `None` if the source material has not been loaded yet.
+    pub(crate) fn get_or_create(
+        &mut self,
+        material: &Handle<StandardMaterial>,
+        standard_materials: &Assets<StandardMaterial>,
+        quantized_materials: &mut Assets<QuantizedChunkMaterial>,
+    ) -> Option<Handle<QuantizedChunkMaterial>> {
+        if let Some(handle) = self.materials.get(material) {
+            return Some(handle.clone());
+        }
+
+        let source = standard_materials.get(material)?;
+        let handle = quantized_materials.add(QuantizedChunkMaterial::from(source));
+        self.materials.insert(material.clone(), handle.clone());
+        Some(handle)
+    }
+
+    /// Copies the settings of the given modified source material into its
+    /// quantized chunk material, if one was created.
+    pub(crate) fn update(
+        &self,
+        material: &Handle<StandardMaterial>,
+        standard_materials: &Assets<StandardMaterial>,
+        quantized_materials: &mut Assets<QuantizedChunkMaterial>,
+    ) {
+        let (Some(handle), Some(source)) = (
+            self.materials.get(material),
+            standard_materials.get(material),
+        ) else {
+            return;
+        };
+
+        if let Some(quantized) = quantized_materials.get_mut(handle) {
+            *quantized = QuantizedChunkMaterial::from(source);
+        }
+    }
+
+    /// Removes the quantized chunk material of the given source material.
+    pub(crate) fn remove(&mut self, material: &Handle<StandardMaterial>) {
+        self.materials.remove(material);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn mirror_standard_material() {
+        let source = StandardMaterial {
+            base_color: Color::RED,
+            alpha_mode: AlphaMode::Mask(0.25),
+            double_sided: true,
+            ..default()
+        };
+
+        let material = QuantizedChunkMaterial::from(&source);
+        assert_eq!(material.base_color, Color::RED);
+        assert_eq!(
+            QuantizedChunkMaterialKey::from(&material),
+            QuantizedChunkMaterialKey {
+                cull_mode: None,
+            }
+        );
+
+        let uniform: QuantizedChunkMaterialUniform =
+            material.as_bind_group_shader_type(&RenderAssets::<Image>::default());
+        let flags = StandardMaterialFlags::from_bits_retain(uniform.flags);
+        assert!(flags.contains(StandardMaterialFlags::DOUBLE_SIDED));
+        assert!(flags.contains(StandardMaterialFlags::ALPHA_MODE_MASK));
+        assert!(!flags.contains(StandardMaterialFlags::BASE_COLOR_TEXTURE));
+        assert_eq!(uniform.alpha_cutoff, 0.25);
+    }
+}
@@ -0,0 +1,183 @@
+//! Validation of the block faces along the seams between neighboring chunks.
+//!
+//! Each chunk is meshed on its own, so the faces along a chunk border are only
+//! correct if the block shapes on both sides agree on which faces are hidden.
+//! These checks compare the result of [`BlockShape::check_occlude`] against the
+//! face coverage reported by [`BlockShape::get_face_coverage`], in order to
+//! catch custom occlusion checks that hide faces which are not actually
+//! covered, or that keep faces which are.
+//!
+//! Blocks that do not report any face coverage are never reported, as there is
+//! no geometry information to validate their occlusion checks against.
+
+use bevy::prelude::*;
+use bones3_core::prelude::*;
+
+use crate::mesh::block_model::{BlockOcclusion, BlockShape};
+
+/// The kind of mismatch that was found for a block face along a chunk seam.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeamMismatchKind {
+    /// The face is hidden by the neighboring block, but the neighboring block
+    /// does not cover all of it, leaving a hole in the mesh.
+    Hole,
+
+    /// The face is rendered, even though the neighboring block covers all of
+    /// it.
+    HiddenFaceRendered,
+}
+
+/// A block face along a chunk seam whose occlusion does not match the face
+/// coverage of the neighboring block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeamMismatch {
+    /// The block coordinates of the block that owns the face.
+    pub block_coords: IVec3,
+
+    /// The face of the block, pointing towards the neighboring block.
+    pub face: BlockOcclusion,
+
+    /// The kind of mismatch.
+    pub kind: SeamMismatchKind,
+}
+
+/// Checks all block faces along the seam between the chunk at the given chunk
+/// coordinates and its neighbor in the direction of the given face.
+///
+/// Faces on both sides of the seam are checked. The `get_block` function is
+/// called with block coordinates within either chunk.
+pub fn find_seam_mismatches<T, G>(
+    chunk_coords: IVec3,
+    face: BlockOcclusion,
+    get_block: G,
+) -> Vec<SeamMismatch>
+where
+    T: BlockData + BlockShape,
+    G: Fn(IVec3) -> T,
+{
+    let offset = face.into_offset();
+    let origin = chunk_coords * 16;
+
+    // Select the layer of blocks that touches the seam.
+    let min = IVec3::select(offset.cmpgt(IVec3::ZERO), IVec3::splat(15), IVec3::ZERO);
+    let max = IVec3::select(offset.cmplt(IVec3::ZERO), IVec3::ZERO, IVec3::splat(15));
+    let layer = Region::from_points(origin + min, origin + max);
+
+    let mut mismatches = vec![];
+    for block_coords in layer.iter() {
+        let neighbor_coords = block_coords + offset;
+        let block = get_block(block_coords);
+        let neighbor = get_block(neighbor_coords);
+
+        if let Some(kind) = check_face(block, neighbor, face) {
+            mismatches.push(SeamMismatch {
+                block_coords,
+                face,
+                kind,
+            });
+        }
+
+        if let Some(kind) = check_face(neighbor, block, face.opposite_face()) {
+            mismatches.push(SeamMismatch {
+                block_coords: neighbor_coords,
+                face: face.opposite_face(),
+                kind,
+            });
+        }
+    }
+
+    mismatches
+}
+
+/// Checks whether the occlusion of the given face of a block matches the face
+/// coverage of the neighboring block in that direction.
+fn check_face<T>(block: T, neighbor: T, face: BlockOcclusion) -> Option<SeamMismatchKind>
+where
+    T: BlockShape,
+{
+    let coverage = block.get_face_coverage(face);
+    if coverage.is_empty() {
+        return None;
+    }
+
+    // Merged faces are hidden on purpose, regardless of their coverage.
+    if block.merge_group().is_some() && neighbor.merge_group() == block.merge_group() {
+        return None;
+    }
+
+    let hidden = neighbor.check_occlude(face, block);
+    let covered = neighbor
+        .get_face_coverage(face.opposite_face())
+        .occludes(coverage);
+
+    match (hidden, covered) {
+        (true, false) => Some(SeamMismatchKind::Hole),
+        (false, true) => Some(SeamMismatchKind::HiddenFaceRendered),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::mesh::face_coverage::FaceCoverage;
+    use crate::vertex_data::ShapeBuilder;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    enum Block {
+        #[default]
+        Air,
+        Stone,
+        Slab,
+    }
+
+    impl BlockShape for Block {
+        fn write_shape(&self, _: &mut ShapeBuilder) {}
+
+        fn get_face_coverage(&self, face: BlockOcclusion) -> FaceCoverage {
+            match self {
+                Block::Air => FaceCoverage::EMPTY,
+                Block::Stone => FaceCoverage::FULL,
+                Block::Slab if face.contains(BlockOcclusion::NEG_Y) => FaceCoverage::FULL,
+                Block::Slab if face.contains(BlockOcclusion::POS_Y) => FaceCoverage::EMPTY,
+                Block::Slab => FaceCoverage::HALF_BOTTOM,
+            }
+        }
+
+        // Slabs wrongly claim to hide every face that touches them.
+        fn check_occlude(&self, face: BlockOcclusion, other: Self) -> bool {
+            match self {
+                Block::Slab => true,
+                _ => {
+                    self.get_face_coverage(face.opposite_face())
+                        .occludes(other.get_face_coverage(face))
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn find_holes_along_seam() {
+        let get_block = |block_pos: IVec3| {
+            match block_pos.to_array() {
+                [15, 0, 0] => Block::Stone,
+                [16, 0, 0] => Block::Slab,
+                [15, 15, 3] => Block::Stone,
+                [15, 16, 3] => Block::Stone,
+                _ => Block::Air,
+            }
+        };
+
+        let mismatches = find_seam_mismatches(IVec3::ZERO, BlockOcclusion::POS_X, get_block);
+        assert_eq!(mismatches, vec![SeamMismatch {
+            block_coords: IVec3::new(15, 0, 0),
+            face:         BlockOcclusion::POS_X,
+            kind:         SeamMismatchKind::Hole,
+        }]);
+
+        let mismatches = find_seam_mismatches(IVec3::ZERO, BlockOcclusion::POS_Y, get_block);
+        assert_eq!(mismatches, vec![]);
+    }
+}
@@ -0,0 +1,454 @@
+//! This module contains the algorithms for generating smooth chunk meshes by
+//! treating block data as a density field.
+
+use bevy::prelude::*;
+use bones3_core::prelude::*;
+
+use crate::ecs::resources::ChunkMaterialList;
+use crate::mesh::block_model::BlockModelGenerator;
+use crate::vertex_data::{ShapeBuilder, TempMesh};
+
+/// A trait that can be defined for a block data object in order to treat the
+/// block data as a density field when generating smooth chunk meshes.
+pub trait BlockDensity: BlockData {
+    /// Gets the density value of this block.
+    ///
+    /// Values greater than zero are considered to be solid, and the generated
+    /// surface lies where the density crosses zero between two blocks.
+    fn get_density(&self) -> f32;
+
+    /// Gets the index of the material that is used to render the surface of
+    /// this block, if it is solid.
+    ///
+    /// If `None` is returned, no surface is written for this block.
+    fn get_material(&self) -> Option<u16>;
+
+    /// Gets the density gradient at the center of this block, if known.
+    ///
+    /// The gradient points in the direction of increasing density. When
+    /// provided alongside the density, this forms the Hermite data that is
+    /// used by the dual contouring mesher to preserve sharp features. If
+    /// `None` is returned, the gradient is estimated from the surrounding
+    /// density values instead. Defaults to `None`.
+    fn get_gradient(&self) -> Option<Vec3> {
+        None
+    }
+}
+
+/// The offsets of the eight corners of a cell, where bit 0, 1, and 2 of the
+/// corner index determine the X, Y, and Z offset respectively.
+const CORNERS: [IVec3; 8] = [
+    IVec3::new(0, 0, 0),
+    IVec3::new(1, 0, 0),
+    IVec3::new(0, 1, 0),
+    IVec3::new(1, 1, 0),
+    IVec3::new(0, 0, 1),
+    IVec3::new(1, 0, 1),
+    IVec3::new(0, 1, 1),
+    IVec3::new(1, 1, 1),
+];
+
+/// The twelve edges of a cell, as pairs of corner indices.
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// A cached density field of all blocks within a chunk, including a one block
+/// border around the chunk.
+pub(crate) struct DensityField<T>
+where
+    T: BlockDensity,
+{
+    /// The region of blocks stored within this field.
+    region: Region,
+
+    /// The block values within this field.
+    blocks: Vec<T>,
+
+    /// The density values of each block within this field.
+    densities: Vec<f32>,
+}
+
+impl<T> DensityField<T>
+where
+    T: BlockDensity,
+{
+    /// Reads all blocks within the chunk, and the one block border around it,
+    /// from the given function.
+    pub(crate) fn new<G>(get_block: G) -> Self
+    where
+        G: Fn(IVec3) -> T,
+    {
+        let region = Region::from_points(IVec3::NEG_ONE, IVec3::splat(16));
+        let blocks = region.iter().map(get_block).collect::<Vec<_>>();
+        let densities = blocks.iter().map(|b| b.get_density()).collect();
+
+        Self {
+            region,
+            blocks,
+            densities,
+        }
+    }
+
+    /// Gets the block at the given local block coordinates.
+    pub(crate) fn block(&self, pos: IVec3) -> T {
+        self.blocks[self.region.point_to_index(pos).unwrap()]
+    }
+
+    /// Gets the density at the given local block coordinates.
+    pub(crate) fn density(&self, pos: IVec3) -> f32 {
+        self.densities[self.region.point_to_index(pos).unwrap()]
+    }
+
+    /// Gets the density values of the eight corners of the given cell.
+    pub(crate) fn cell_corners(&self, cell: IVec3) -> [f32; 8] {
+        CORNERS.map(|c| self.density(cell + c))
+    }
+}
+
+/// Calculates the surface normal at the center of a cell, from the density
+/// values of its corners.
+pub(crate) fn cell_normal(corners: &[f32; 8]) -> Vec3 {
+    let mut gradient = Vec3::ZERO;
+    for (a, b) in EDGES {
+        let axis = (CORNERS[b] - CORNERS[a]).as_vec3();
+        gradient += axis * (corners[b] - corners[a]);
+    }
+
+    (-gradient).normalize_or_zero()
+}
+
+/// Iterates over all edges of a cell that cross the surface, returning the
+/// corner indices of each edge and the interpolation factor of the crossing
+/// point along that edge.
+pub(crate) fn crossing_edges(corners: &[f32; 8]) -> impl Iterator<Item = (usize, usize, f32)> + '_ {
+    EDGES.iter().filter_map(|&(a, b)| {
+        let (da, db) = (corners[a], corners[b]);
+        if (da > 0.0) == (db > 0.0) {
+            return None;
+        }
+
+        Some((a, b, da / (da - db)))
+    })
+}
+
+/// Iterates over all edges of a cell that cross the surface, returning the
+/// local position of each crossing point within the cell.
+pub(crate) fn edge_crossings(corners: &[f32; 8]) -> impl Iterator<Item = Vec3> + '_ {
+    crossing_edges(corners).map(|(a, b, t)| CORNERS[a].as_vec3().lerp(CORNERS[b].as_vec3(), t))
+}
+
+/// Calculates the local position of the surface nets vertex within a cell, as
+/// the average of all edge crossing points.
+fn surface_nets_vertex(corners: &[f32; 8]) -> Vec3 {
+    let mut sum = Vec3::ZERO;
+    let mut count = 0;
+
+    for point in edge_crossings(corners) {
+        sum += point;
+        count += 1;
+    }
+
+    sum / count as f32
+}
+
+/// The weight that is used to pull dual contouring vertices towards the mass
+/// point of the edge crossings, which keeps the solution stable for flat
+/// surfaces.
+const DC_MASS_POINT_BIAS: f32 = 0.05;
+
+/// Calculates the local position of the dual contouring vertex within a cell,
+/// by minimizing the quadratic error function of the planes defined by each
+/// edge crossing point and its surface normal.
+fn dual_contouring_vertex<T>(field: &DensityField<T>, cell: IVec3, corners: &[f32; 8]) -> Vec3
+where
+    T: BlockDensity,
+{
+    let fallback_normal = -cell_normal(corners);
+    let mut ata = Mat3::ZERO;
+    let mut atb = Vec3::ZERO;
+    let mut mass_point = Vec3::ZERO;
+    let mut count = 0;
+
+    for (a, b, t) in crossing_edges(corners) {
+        let point = CORNERS[a].as_vec3().lerp(CORNERS[b].as_vec3(), t);
+        let gradient_a = field.block(cell + CORNERS[a]).get_gradient();
+        let gradient_b = field.block(cell + CORNERS[b]).get_gradient();
+        let normal = match (gradient_a, gradient_b) {
+            (Some(ga), Some(gb)) => ga.lerp(gb, t),
+            (Some(g), None) | (None, Some(g)) => g,
+            (None, None) => fallback_normal,
+        }
+        .normalize_or_zero();
+
+        ata += Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+        atb += normal * normal.dot(point);
+        mass_point += point;
+        count += 1;
+    }
+
+    mass_point /= count as f32;
+    ata += Mat3::from_diagonal(Vec3::splat(DC_MASS_POINT_BIAS));
+    atb += mass_point * DC_MASS_POINT_BIAS;
+
+    if ata.determinant().abs() < f32::EPSILON {
+        return mass_point;
+    }
+
+    (ata.inverse() * atb).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+/// A single quad of a smooth surface, with individual vertex normals.
+struct SmoothQuad {
+    /// The vertex positions of the quad, in counter-clockwise order.
+    vertices: [Vec3; 4],
+
+    /// The vertex normals of the quad.
+    normals: [Vec3; 4],
+
+    /// The axis that the quad crosses, used for projecting texture
+    /// coordinates.
+    axis: usize,
+}
+
+impl BlockModelGenerator for SmoothQuad {
+    fn write_to_mesh(&self, mesh: &mut TempMesh, pos: IVec3) {
+        let offset = pos.as_vec3();
+        let vertex_count = mesh.vertices.len() as u16;
+        mesh.indices
+            .extend_from_slice(&[0, 1, 2, 0, 2, 3].map(|i| i + vertex_count));
+
+        for (vertex, normal) in self.vertices.iter().zip(self.normals.iter()) {
+            let uv = match self.axis {
+                0 => Vec2::new(vertex.z, vertex.y),
+                1 => Vec2::new(vertex.x, vertex.z),
+                _ => Vec2::new(vertex.x, vertex.y),
+            };
+
+            mesh.vertices.push(*vertex + offset);
+            mesh.normals.push(*normal);
+            mesh.uvs.push(uv);
+        }
+    }
+}
+
+/// Builds a smooth temp mesh for a virtual 16x16x16 chunk by treating the
+/// block data as a density field.
+///
+/// The `get_block` parameter function is called for all local coordinates
+/// within the chunk, as well as a one block border around the chunk, including
+/// along edges and corners.
+///
+/// The density of each block is sampled at the center of the block. For every
+/// 2x2x2 cell of blocks that the surface passes through, a single vertex is
+/// placed using the `place_vertex` function, which is given the corner
+/// densities of the cell and returns the local position of the vertex within
+/// the cell. Neighboring vertices are then connected by quads.
+pub(crate) fn build_smooth_mesh<'a, T, P>(
+    field: &DensityField<T>,
+    material_list: &'a ChunkMaterialList,
+    place_vertex: P,
+) -> ShapeBuilder<'a>
+where
+    T: BlockDensity,
+    P: Fn(IVec3, &[f32; 8]) -> Vec3,
+{
+    let mut shape_builder = ShapeBuilder::new(material_list);
+
+    let cell_region = Region::from_points(IVec3::NEG_ONE, IVec3::splat(15));
+    let cells = cell_region
+        .iter()
+        .map(|cell| {
+            let corners = field.cell_corners(cell);
+            let solid = corners.iter().filter(|d| **d > 0.0).count();
+            if solid == 0 || solid == 8 {
+                return None;
+            }
+
+            let pos = cell.as_vec3() + 0.5 + place_vertex(cell, &corners);
+            Some((pos, cell_normal(&corners)))
+        })
+        .collect::<Vec<_>>();
+
+    let get_cell = |cell: IVec3| cells[cell_region.point_to_index(cell).unwrap()];
+    let axes = [IVec3::X, IVec3::Y, IVec3::Z];
+
+    for block_pos in Region::CHUNK.iter() {
+        let density = field.density(block_pos);
+
+        for (axis_index, axis) in axes.iter().enumerate() {
+            let next_pos = block_pos + *axis;
+            if (density > 0.0) == (field.density(next_pos) > 0.0) {
+                continue;
+            }
+
+            let solid_pos = match density > 0.0 {
+                true => block_pos,
+                false => next_pos,
+            };
+
+            let Some(material) = field.block(solid_pos).get_material() else {
+                continue;
+            };
+
+            let a = axes[(axis_index + 1) % 3];
+            let b = axes[(axis_index + 2) % 3];
+            let quad_cells = [block_pos - a - b, block_pos - b, block_pos, block_pos - a];
+
+            let Some(mut quad) = quad_cells
+                .iter()
+                .map(|c| get_cell(*c))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            if density <= 0.0 {
+                quad.reverse();
+            }
+
+            shape_builder.set_local_pos(IVec3::ZERO);
+            shape_builder.add_shape(
+                SmoothQuad {
+                    vertices: std::array::from_fn(|i| quad[i].0),
+                    normals:  std::array::from_fn(|i| quad[i].1),
+                    axis:     axis_index,
+                },
+                material,
+            );
+        }
+    }
+
+    shape_builder
+}
+
+/// Builds a smooth temp mesh for a virtual 16x16x16 chunk using the surface
+/// nets algorithm.
+///
+/// See [`build_chunk_mesh`](crate::mesh::builder::build_chunk_mesh) for more
+/// information on how the `get_block` parameter function is used.
+pub fn build_surface_nets_mesh<T, G>(
+    get_block: G,
+    material_list: &ChunkMaterialList,
+) -> ShapeBuilder<'_>
+where
+    T: BlockDensity,
+    G: Fn(IVec3) -> T,
+{
+    let field = DensityField::new(get_block);
+    build_smooth_mesh(&field, material_list, |_, corners| {
+        surface_nets_vertex(corners)
+    })
+}
+
+/// Builds a smooth temp mesh for a virtual 16x16x16 chunk using the dual
+/// contouring algorithm.
+///
+/// Unlike surface nets, dual contouring uses the density gradient of each
+/// block, as given by [`BlockDensity::get_gradient`], to place vertices along
+/// sharp features such as cliffs and overhangs.
+///
+/// See [`build_chunk_mesh`](crate::mesh::builder::build_chunk_mesh) for more
+/// information on how the `get_block` parameter function is used.
+pub fn build_dual_contouring_mesh<T, G>(
+    get_block: G,
+    material_list: &ChunkMaterialList,
+) -> ShapeBuilder<'_>
+where
+    T: BlockDensity,
+    G: Fn(IVec3) -> T,
+{
+    let field = DensityField::new(get_block);
+    build_smooth_mesh(&field, material_list, |cell, corners| {
+        dual_contouring_vertex(&field, cell, corners)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::render::mesh::{Indices, VertexAttributeValues};
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, Copy, Reflect)]
+    struct Density(f32);
+
+    impl BlockDensity for Density {
+        fn get_density(&self) -> f32 {
+            self.0
+        }
+
+        fn get_material(&self) -> Option<u16> {
+            Some(0)
+        }
+    }
+
+    #[derive(Debug, Default, Clone, Copy, Reflect)]
+    struct Hermite(f32, Vec3);
+
+    impl BlockDensity for Hermite {
+        fn get_density(&self) -> f32 {
+            self.0
+        }
+
+        fn get_material(&self) -> Option<u16> {
+            Some(0)
+        }
+
+        fn get_gradient(&self) -> Option<Vec3> {
+            Some(self.1)
+        }
+    }
+
+    #[test]
+    fn dual_contouring_flat_plane() {
+        let field = DensityField::new(|pos: IVec3| Hermite(4.3 - pos.y as f32, Vec3::NEG_Y));
+
+        for cell in [IVec3::new(3, 4, 3), IVec3::new(7, 4, 12)] {
+            let corners = field.cell_corners(cell);
+            let vertex = cell.as_vec3() + 0.5 + dual_contouring_vertex(&field, cell, &corners);
+            assert!((vertex.y - 4.8).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn sphere_faces_outwards() {
+        let mut material_list = ChunkMaterialList::default();
+        material_list.add_material(Handle::default(), None);
+
+        let center = Vec3::splat(8.0);
+        let shape_builder = build_surface_nets_mesh(
+            |pos| Density(5.0 - (pos.as_vec3() + 0.5).distance(center)),
+            &material_list,
+        );
+
+        let (mesh, _) = shape_builder.into_meshes().next().unwrap();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("Missing vertex positions");
+        };
+        let Some(Indices::U16(indices)) = mesh.indices() else {
+            panic!("Missing indices");
+        };
+
+        assert!(!indices.is_empty());
+        for tri in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[tri[i] as usize]));
+            let face_normal = (b - a).cross(c - a);
+            let outwards = (a + b + c) / 3.0 - center;
+            assert!(face_normal.dot(outwards) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Helpers for finding the chunks of a voxel world that intersect a camera
+//! frustum.
+
+use bevy::prelude::*;
+use bevy::render::primitives::{Aabb, Frustum};
+use bones3_core::storage::BlockScale;
+
+/// Creates a chunk bounds predicate that checks whether a chunk intersects
+/// the given frustum, for use with
+/// [`VoxelWorldQuery::iter_chunks_where`](bones3_core::query::VoxelWorldQuery::iter_chunks_where).
+///
+/// The world transform and block scale are used to move the chunk bounds
+/// from block coordinates into the global space of the frustum.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy::render::primitives::Frustum;
+/// # use bones3_core::prelude::*;
+/// # use bones3_remesh::query::in_frustum;
+/// fn count_visible_chunks(
+///     cameras: Query<&Frustum, With<Camera>>,
+///     worlds: Query<
+///         (Entity, &GlobalTransform, &BlockScale),
+///         With<VoxelWorld>,
+///     >,
+///     chunks: VoxelQuery<()>,
+/// ) {
+///     let frustum = cameras.single();
+///     for (world_id, world_transform, block_scale) in worlds.iter() {
+///         let world = chunks.get_world(world_id).unwrap();
+///         let visible = world
+///             .iter_chunks_where(in_frustum(
+///                 frustum,
+///                 world_transform,
+///                 *block_scale,
+///             ))
+///             .count();
+///         info!("{visible} chunks are visible");
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(count_visible_chunks);
+/// ```
+pub fn in_frustum<'a>(
+    frustum: &'a Frustum,
+    world_transform: &GlobalTransform,
+    block_scale: BlockScale,
+) -> impl FnMut(Vec3, Vec3) -> bool + 'a {
+    let block_to_global =
+        world_transform.compute_matrix() * Mat4::from_scale(Vec3::splat(block_scale.0));
+
+    move |min, max| {
+        let aabb = Aabb::from_min_max(min, max);
+        frustum.intersects_obb(&aabb, &block_to_global, true, true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bones3_core::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn find_chunks_in_view() {
+        let mut app = App::new();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            for x in -4 ..= 4 {
+                world.spawn_chunk(IVec3::new(x, 0, -2), ()).unwrap();
+            }
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        fn update(world_query: Query<Entity, With<VoxelWorld>>, chunk_query: VoxelQuery<()>) {
+            let world = chunk_query.get_world(world_query.single()).unwrap();
+
+            let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+            let view =
+                Mat4::look_at_rh(Vec3::new(8.0, 8.0, 0.0), Vec3::new(8.0, 8.0, -1.0), Vec3::Y);
+            let frustum = Frustum::from_view_projection(&(projection * view));
+
+            let transform = GlobalTransform::default();
+            let mut found: Vec<_> = world
+                .iter_chunks_where(in_frustum(&frustum, &transform, BlockScale::default()))
+                .map(|(coords, _)| coords.x)
+                .collect();
+            found.sort();
+            assert_eq!(found, vec![-2, -1, 0, 1, 2]);
+        }
+        Schedule::new().add_systems(update).run(&mut app.world);
+    }
+}
@@ -2,5 +2,7 @@
 //! VoxelQueries that are useful for generating chunk meshes.
 
 mod commands;
+mod frustum;
 
 pub use commands::*;
+pub use frustum::*;
@@ -0,0 +1,302 @@
+//! Sculpting operations for voxel worlds that are rendered as smooth density
+//! fields.
+//!
+//! These are the smooth terrain counterpart to the block brush within
+//! [`bones3_core::util::brush`]. Instead of replacing whole blocks, each
+//! operation nudges block densities by an amount that is scaled by the brush
+//! strength, and all chunk meshes that are affected by the change are marked
+//! for remeshing.
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bones3_core::prelude::*;
+use bones3_core::util::brush::{
+    edit_blocks,
+    redo_edit,
+    undo_edit,
+    VoxelBrush,
+    VoxelEdit,
+    VoxelEditHistory,
+};
+
+use crate::ecs::components::RemeshChunk;
+use crate::mesh::smooth::BlockDensity;
+
+/// A block density type that can be modified by sculpting operations.
+pub trait SculptDensity: BlockDensity + PartialEq {
+    /// Creates a copy of this block with the given density value.
+    fn with_density(self, density: f32) -> Self;
+}
+
+/// A sculpting operation that can be applied to a density field.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum SculptOp {
+    /// Increases the density of all affected blocks by the given amount at
+    /// full brush strength, growing the surface outwards.
+    Add(f32),
+
+    /// Decreases the density of all affected blocks by the given amount at
+    /// full brush strength, carving the surface inwards.
+    Subtract(f32),
+
+    /// Moves the density of all affected blocks towards a flat plane.
+    Flatten {
+        /// A point on the plane.
+        point: Vec3,
+
+        /// The direction that the plane faces. Blocks behind the plane become
+        /// solid, and blocks in front of it become empty.
+        normal: Vec3,
+
+        /// How far to move towards the plane at full brush strength, from
+        /// `0.0` to `1.0`.
+        strength: f32,
+    },
+
+    /// Moves the density of all affected blocks towards the average density
+    /// of their six neighbors, by the given amount from `0.0` to `1.0` at full
+    /// brush strength.
+    Smooth(f32),
+}
+
+/// Applies the given sculpting operation to all blocks within the brush
+/// around the given center, returning the resulting edit.
+///
+/// All densities are read before any are written. Blocks within chunks that
+/// are not loaded are skipped. All chunks with meshes that depend on the
+/// changed blocks are marked with [`RemeshChunk`].
+///
+/// This function returns `None` if the given world does not exist.
+pub fn sculpt<T>(
+    world: &mut World,
+    world_id: Entity,
+    center: Vec3,
+    brush: &VoxelBrush,
+    op: SculptOp,
+) -> Option<VoxelEdit<T>>
+where
+    T: SculptDensity,
+{
+    let region = brush.region(center.round().as_ivec3());
+    let edit = edit_blocks(
+        world,
+        world_id,
+        region,
+        |block_coords, old: T, get_block| {
+            let weight = brush.weight(block_coords.as_vec3() - center);
+            if weight <= 0.0 {
+                return old;
+            }
+
+            let density = old.get_density();
+            let new_density = match op {
+                SculptOp::Add(amount) => density + amount * weight,
+                SculptOp::Subtract(amount) => density - amount * weight,
+                SculptOp::Flatten {
+                    point,
+                    normal,
+                    strength,
+                } => {
+                    let target = (point - block_coords.as_vec3()).dot(normal.normalize_or_zero());
+                    density + (target - density) * (strength * weight).clamp(0.0, 1.0)
+                },
+                SculptOp::Smooth(strength) => {
+                    let neighbors: Vec<f32> =
+                        [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z]
+                            .iter()
+                            .filter_map(|offset| get_block(block_coords + *offset))
+                            .map(|block| block.get_density())
+                            .collect();
+
+                    if neighbors.is_empty() {
+                        density
+                    } else {
+                        let average = neighbors.iter().sum::<f32>() / neighbors.len() as f32;
+                        density + (average - density) * (strength * weight).clamp(0.0, 1.0)
+                    }
+                },
+            };
+
+            old.with_density(new_density)
+        },
+    )?;
+
+    remesh_edit(world, &edit);
+    Some(edit)
+}
+
+/// Marks all chunks with smooth meshes that depend on any of the blocks
+/// changed by the given edit with [`RemeshChunk`].
+///
+/// As smooth meshes sample densities across chunk borders, this includes all
+/// neighboring chunks, including diagonal neighbors, of changed blocks that
+/// lie on a chunk border.
+pub fn remesh_edit<T>(world: &mut World, edit: &VoxelEdit<T>)
+where
+    T: BlockData,
+{
+    let mut dirty = HashSet::new();
+    for change in edit.changes.iter() {
+        for offset in Region::NEIGHBORS.iter() {
+            dirty.insert((change.block_coords + offset) >> 4);
+        }
+    }
+
+    let chunks: Vec<Entity> = world
+        .query::<(Entity, &VoxelChunk)>()
+        .iter(world)
+        .filter(|(_, chunk)| chunk.world_id() == edit.world_id)
+        .filter(|(_, chunk)| dirty.contains(&chunk.chunk_coords()))
+        .map(|(chunk_id, _)| chunk_id)
+        .collect();
+
+    for chunk_id in chunks {
+        world.entity_mut(chunk_id).insert(RemeshChunk);
+    }
+}
+
+/// Undoes the most recent edit within the [`VoxelEditHistory`] for the given
+/// block data type, and marks all affected chunks for remeshing.
+///
+/// Returns false if there was nothing to undo.
+pub fn undo_sculpt<T>(world: &mut World) -> bool
+where
+    T: BlockData,
+{
+    let Some(edit) = world
+        .get_resource::<VoxelEditHistory<T>>()
+        .and_then(|history| history.peek_undo().cloned())
+    else {
+        return false;
+    };
+
+    undo_edit::<T>(world);
+    remesh_edit(world, &edit);
+    true
+}
+
+/// Redoes the most recently undone edit within the [`VoxelEditHistory`] for
+/// the given block data type, and marks all affected chunks for remeshing.
+///
+/// Returns false if there was nothing to redo.
+pub fn redo_sculpt<T>(world: &mut World) -> bool
+where
+    T: BlockData,
+{
+    let Some(edit) = world
+        .get_resource::<VoxelEditHistory<T>>()
+        .and_then(|history| history.peek_redo().cloned())
+    else {
+        return false;
+    };
+
+    redo_edit::<T>(world);
+    remesh_edit(world, &edit);
+    true
+}
+
+/// An extension trait for VoxelWorldCommands that allows for sculpting smooth
+/// terrain.
+pub trait VoxelSculptCommands {
+    /// Applies the given sculpting operation around the given position within
+    /// this world when the command queue is executed.
+    ///
+    /// The edit is recorded within the [`VoxelEditHistory`] for `T` if that
+    /// resource exists. See [`sculpt`] for more information.
+    fn sculpt<T>(self, center: Vec3, brush: VoxelBrush, op: SculptOp)
+    where
+        T: SculptDensity;
+}
+
+impl<'w, 's, 'cmd_ref> VoxelSculptCommands for VoxelWorldCommands<'w, 's, 'cmd_ref> {
+    fn sculpt<T>(self, center: Vec3, brush: VoxelBrush, op: SculptOp)
+    where
+        T: SculptDensity,
+    {
+        let world_id = self.id();
+        self.as_entity_commands()
+            .commands()
+            .add(move |world: &mut World| {
+                let Some(edit) = sculpt::<T>(world, world_id, center, &brush, op) else {
+                    return;
+                };
+
+                if let Some(mut history) = world.get_resource_mut::<VoxelEditHistory<T>>() {
+                    history.push(edit);
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+    struct Density(f32);
+
+    impl BlockDensity for Density {
+        fn get_density(&self) -> f32 {
+            self.0
+        }
+
+        fn get_material(&self) -> Option<u16> {
+            Some(0)
+        }
+    }
+
+    impl SculptDensity for Density {
+        fn with_density(self, density: f32) -> Self {
+            Density(density)
+        }
+    }
+
+    #[test]
+    fn sculpt_and_undo() {
+        let mut app = App::new();
+        app.init_resource::<VoxelEditHistory<Density>>();
+
+        fn init(mut commands: VoxelCommands) {
+            let mut world = commands.spawn_world(());
+            world
+                .spawn_chunk(IVec3::ZERO, VoxelStorage::<Density>::default())
+                .unwrap();
+            world
+                .spawn_chunk(IVec3::X, VoxelStorage::<Density>::default())
+                .unwrap();
+            world.sculpt::<Density>(
+                Vec3::new(15.0, 8.0, 8.0),
+                VoxelBrush::sphere(0.5),
+                SculptOp::Add(2.0),
+            );
+        }
+        Schedule::new().add_systems(init).run(&mut app.world);
+
+        let mut chunks = app
+            .world
+            .query::<(&VoxelChunk, &VoxelStorage<Density>, Option<&RemeshChunk>)>();
+        for (chunk, storage, remesh) in chunks.iter(&app.world) {
+            assert!(remesh.is_some());
+            if chunk.chunk_coords() == IVec3::ZERO {
+                assert_eq!(storage.get_block(IVec3::new(15, 8, 8)), Density(2.0));
+            }
+        }
+
+        let chunk_ids: Vec<_> = app
+            .world
+            .query_filtered::<Entity, With<VoxelChunk>>()
+            .iter(&app.world)
+            .collect();
+        for chunk_id in chunk_ids {
+            app.world.entity_mut(chunk_id).remove::<RemeshChunk>();
+        }
+
+        assert!(undo_sculpt::<Density>(&mut app.world));
+        for (_, storage, remesh) in chunks.iter(&app.world) {
+            assert!(remesh.is_some());
+            assert_eq!(storage.get_block(IVec3::new(15, 8, 8)), Density(0.0));
+        }
+    }
+}
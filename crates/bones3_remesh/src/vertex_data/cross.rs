@@ -0,0 +1,141 @@
+//! Contains the block model builder for generating cross shapes, such as those
+//! commonly used for plants and foliage.
+
+use bevy::prelude::{IVec3, Vec2, Vec3};
+
+use crate::mesh::block_model::BlockModelGenerator;
+use crate::vertex_data::{QuadFacing, TempMesh};
+
+/// Contains the vertex data for generating a cross.
+///
+/// The cross is made up of two diagonal quads, each stored as an array of four
+/// vertex positions in counter-clockwise order, alongside the quad normal.
+const CROSS_QUADS: [([Vec3; 4], Vec3); 2] = [
+    (
+        [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ],
+        Vec3::new(
+            -std::f32::consts::FRAC_1_SQRT_2,
+            0.0,
+            std::f32::consts::FRAC_1_SQRT_2,
+        ),
+    ),
+    (
+        [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 1.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        ],
+        Vec3::new(
+            -std::f32::consts::FRAC_1_SQRT_2,
+            0.0,
+            -std::f32::consts::FRAC_1_SQRT_2,
+        ),
+    ),
+];
+
+/// The texture coordinates of each quad within the cross.
+const CROSS_UVS: [Vec2; 4] = [
+    Vec2::new(0.0, 1.0),
+    Vec2::new(1.0, 1.0),
+    Vec2::new(1.0, 0.0),
+    Vec2::new(0.0, 0.0),
+];
+
+/// A block model builder for a cross shape.
+///
+/// Unlike cubes, cross shapes are double sided by default so that they are
+/// visible from every direction.
+pub struct CrossModelBuilder {
+    /// The local position of the cross within the block.
+    local_pos: Vec3,
+
+    /// The size of the cross.
+    size: Vec3,
+
+    /// The sides of each quad that are written.
+    facing: QuadFacing,
+}
+
+impl CrossModelBuilder {
+    /// Creates a new cross model builder with default settings.
+    ///
+    /// The default settings for the cross model is a double sided 1x1x1 cross,
+    /// located at the origin.
+    pub fn new() -> Self {
+        Self {
+            local_pos: Vec3::ZERO,
+            size:      Vec3::ONE,
+            facing:    QuadFacing::DoubleSided,
+        }
+    }
+
+    /// Defines the position of this cross model within the block.
+    ///
+    /// The cross position is relative to the minimum corner of the cross.
+    pub fn set_pos(mut self, pos: Vec3) -> Self {
+        self.local_pos = pos;
+        self
+    }
+
+    /// Sets the size of this cross model.
+    pub fn set_size(mut self, size: Vec3) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets which sides of each quad are written.
+    pub fn set_facing(mut self, facing: QuadFacing) -> Self {
+        self.facing = facing;
+        self
+    }
+}
+
+impl Default for CrossModelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockModelGenerator for CrossModelBuilder {
+    fn write_to_mesh(&self, mesh: &mut TempMesh, block_pos: IVec3) {
+        let pos = block_pos.as_vec3() + self.local_pos;
+
+        for (vertices, normal) in CROSS_QUADS.iter() {
+            mesh.add_quad(
+                vertices.map(|v| v * self.size + pos),
+                *normal,
+                CROSS_UVS,
+                self.facing,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn double_sided_cross() {
+        let mut mesh = TempMesh::default();
+        CrossModelBuilder::new().write_to_mesh(&mut mesh, IVec3::ZERO);
+
+        assert_eq!(mesh.vertices.len(), 16);
+        assert_eq!(mesh.indices.len(), 24);
+
+        // Each triangle should face the same direction as its vertex normals.
+        for tri in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize]);
+            let face_normal = (b - a).cross(c - a).normalize();
+            assert!(face_normal.dot(mesh.normals[tri[0] as usize]) > 0.99);
+        }
+    }
+}
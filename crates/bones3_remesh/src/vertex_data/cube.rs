@@ -4,7 +4,7 @@
 use bevy::prelude::{IVec3, Vec2, Vec3};
 
 use crate::mesh::block_model::{BlockModelGenerator, BlockOcclusion};
-use crate::vertex_data::TempMesh;
+use crate::vertex_data::{QuadFacing, TempMesh};
 
 /// Contains the vertex data for generating a cube.
 ///
@@ -16,7 +16,7 @@ use crate::vertex_data::TempMesh;
 /// The vertex are laid out in six groups, where each group contains 4 vertices,
 /// pertaining to a single face of the cube. The face groups are stored in the
 /// order: -X, +X, -Y, +Y, -Z, +Z. In addition, each face group uses a quad
-/// index layout, as determined by [`QUAD_INDICES`].
+/// index layout, as determined by [`TempMesh::add_quad`].
 #[rustfmt::skip]
 const CUBE_VERTICES: [(Vec3, Vec3, Vec2); 24] = [
     // -X
@@ -51,10 +51,6 @@ const CUBE_VERTICES: [(Vec3, Vec3, Vec2); 24] = [
     (Vec3::new(0.0, 1.0, 1.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(1.0, 0.0)),
 ];
 
-/// The relative indices that are used to indicate how the vertices of a quad
-/// are applied to write to a mesh with the TriangleList topology.
-const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
-
 /// A block model builder for a cube.
 ///
 /// This builder is designed to make it easier to write a custom cube model to a
@@ -68,6 +64,9 @@ pub struct CubeModelBuilder {
 
     /// The occlusion of this cube.
     occlusion: BlockOcclusion,
+
+    /// The sides of each cube face that are written.
+    facing: QuadFacing,
 }
 
 impl CubeModelBuilder {
@@ -81,6 +80,7 @@ impl CubeModelBuilder {
             local_pos: Vec3::ZERO,
             size:      Vec3::ONE,
             occlusion: BlockOcclusion::empty(),
+            facing:    QuadFacing::Front,
         }
     }
 
@@ -103,6 +103,16 @@ impl CubeModelBuilder {
         self.occlusion = occlusion;
         self
     }
+
+    /// Sets which sides of each cube face are written.
+    ///
+    /// Using [`QuadFacing::Back`] turns the cube inside out, while
+    /// [`QuadFacing::DoubleSided`] makes the cube visible from both inside
+    /// and outside.
+    pub fn set_facing(mut self, facing: QuadFacing) -> Self {
+        self.facing = facing;
+        self
+    }
 }
 
 impl Default for CubeModelBuilder {
@@ -117,17 +127,16 @@ impl BlockModelGenerator for CubeModelBuilder {
         let size = self.size;
         let occlusion = self.occlusion;
 
+        let facing = self.facing;
+
         let mut quad = |offset: usize| {
-            let vertex_count = mesh.vertices.len() as u16;
-            mesh.indices
-                .extend_from_slice(&QUAD_INDICES.map(|i| i + vertex_count));
-
-            for vert_data in CUBE_VERTICES.iter().skip(offset).take(4) {
-                let (vertex, normal, uv) = *vert_data;
-                mesh.vertices.push(vertex * size + pos);
-                mesh.normals.push(normal);
-                mesh.uvs.push(uv);
-            }
+            let face = &CUBE_VERTICES[offset .. offset + 4];
+            mesh.add_quad(
+                std::array::from_fn(|i| face[i].0 * size + pos),
+                face[0].1,
+                std::array::from_fn(|i| face[i].2),
+                facing,
+            );
         };
 
         if !occlusion.contains(BlockOcclusion::NEG_X) {
@@ -1,7 +1,10 @@
 //! Contains block model generations for various block shapes.
 
+mod cross;
 mod cube;
+pub mod quantized;
 pub mod shape_builder;
 
+pub use cross::*;
 pub use cube::*;
 pub use shape_builder::*;
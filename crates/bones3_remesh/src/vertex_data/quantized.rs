@@ -0,0 +1,224 @@
+//! A compact vertex format for chunk meshes, using quantized positions and
+//! packed normals and texture coordinates.
+//!
+//! Quantized chunk meshes use about a third of the memory of standard chunk
+//! meshes, but cannot be rendered with a [`StandardMaterial`], since they do
+//! not contain the standard position, normal, and texture coordinate
+//! attributes. The remesh plugins build quantized chunk meshes when
+//! [`ChunkMeshUploadSettings::quantize_vertices`] is enabled, which are
+//! rendered using the
+//! [`QuantizedChunkMaterial`](crate::mesh::quantized_material::QuantizedChunkMaterial)
+//! of the [`QuantizedChunkMeshPlugin`](crate::QuantizedChunkMeshPlugin). Custom
+//! materials must decode the attributes below within their vertex shader:
+//!
+//! * [`ATTRIBUTE_QUANTIZED_POSITION`]: `position = vec3<f32>(value.xyz) *
+//!   QUANTIZED_POSITION_STEP + QUANTIZED_POSITION_MIN`
+//! * [`ATTRIBUTE_PACKED_NORMAL`]: `normal = value.xyz`, as a normalized signed
+//!   value.
+//! * [`ATTRIBUTE_QUANTIZED_UV`]: `uv = vec2<f32>(value) * QUANTIZED_UV_STEP`
+//!
+//! [`ChunkMeshUploadSettings::quantize_vertices`]: crate::ecs::resources::ChunkMeshUploadSettings::quantize_vertices
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::{PrimitiveTopology, VertexFormat};
+
+use crate::vertex_data::{ShapeBuilder, TempMesh};
+
+/// The quantized vertex positions of a chunk mesh, stored as fixed point
+/// values. The fourth component is always zero.
+pub const ATTRIBUTE_QUANTIZED_POSITION: MeshVertexAttribute = MeshVertexAttribute::new(
+    "Bones3_QuantizedPosition",
+    0x00B3_0001,
+    VertexFormat::Uint16x4,
+);
+
+/// The vertex normals of a chunk mesh, packed into normalized signed bytes.
+/// The fourth component is always zero.
+pub const ATTRIBUTE_PACKED_NORMAL: MeshVertexAttribute =
+    MeshVertexAttribute::new("Bones3_PackedNormal", 0x00B3_0002, VertexFormat::Snorm8x4);
+
+/// The texture coordinates of a chunk mesh, stored as fixed point values.
+pub const ATTRIBUTE_QUANTIZED_UV: MeshVertexAttribute =
+    MeshVertexAttribute::new("Bones3_QuantizedUv", 0x00B3_0003, VertexFormat::Uint16x2);
+
+/// The smallest vertex position, along each axis, that can be quantized.
+pub const QUANTIZED_POSITION_MIN: f32 = -16.0;
+
+/// The distance between two neighboring quantized vertex positions.
+pub const QUANTIZED_POSITION_STEP: f32 = 1.0 / 1024.0;
+
+/// The distance between two neighboring quantized texture coordinates.
+pub const QUANTIZED_UV_STEP: f32 = 1.0 / 1024.0;
+
+/// Quantizes the given vertex position, or returns `None` if the position is
+/// outside of the range that can be quantized.
+///
+/// Positions from `-16.0` up to `48.0` along each axis may be quantized, which
+/// covers all chunk meshes that use
+/// [`ChunkMeshOrigin::Chunk`](crate::ecs::components::ChunkMeshOrigin::Chunk).
+pub fn quantize_position(position: Vec3) -> Option<[u16; 4]> {
+    let value = ((position - QUANTIZED_POSITION_MIN) / QUANTIZED_POSITION_STEP).round();
+    if value.cmplt(Vec3::ZERO).any() || value.cmpgt(Vec3::splat(u16::MAX as f32)).any() {
+        return None;
+    }
+
+    Some([value.x as u16, value.y as u16, value.z as u16, 0])
+}
+
+/// Decodes a quantized vertex position.
+pub fn dequantize_position(value: [u16; 4]) -> Vec3 {
+    Vec3::new(value[0] as f32, value[1] as f32, value[2] as f32) * QUANTIZED_POSITION_STEP
+        + QUANTIZED_POSITION_MIN
+}
+
+/// Packs the given unit length normal into normalized signed bytes.
+pub fn pack_normal(normal: Vec3) -> [i8; 4] {
+    let value = (normal.clamp(Vec3::NEG_ONE, Vec3::ONE) * 127.0).round();
+    [value.x as i8, value.y as i8, value.z as i8, 0]
+}
+
+/// Unpacks a packed normal.
+pub fn unpack_normal(value: [i8; 4]) -> Vec3 {
+    Vec3::new(value[0] as f32, value[1] as f32, value[2] as f32) / 127.0
+}
+
+/// Quantizes the given texture coordinates, or returns `None` if they are
+/// outside of the range `0.0` up to `64.0`.
+pub fn quantize_uv(uv: Vec2) -> Option<[u16; 2]> {
+    let value = (uv / QUANTIZED_UV_STEP).round();
+    if value.cmplt(Vec2::ZERO).any() || value.cmpgt(Vec2::splat(u16::MAX as f32)).any() {
+        return None;
+    }
+
+    Some([value.x as u16, value.y as u16])
+}
+
+/// Computes the bounding box of the given mesh from its quantized vertex
+/// positions, or returns `None` if the mesh does not use the quantized vertex
+/// format.
+///
+/// Bevy only computes the bounding boxes of meshes with standard vertex
+/// positions, so quantized chunk meshes need their bounding box to be set
+/// manually in order to be frustum culled.
+pub fn quantized_aabb(mesh: &Mesh) -> Option<Aabb> {
+    let Some(VertexAttributeValues::Uint16x4(positions)) =
+        mesh.attribute(ATTRIBUTE_QUANTIZED_POSITION)
+    else {
+        return None;
+    };
+
+    let mut positions = positions.iter().map(|value| dequantize_position(*value));
+    let first = positions.next()?;
+    let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    Some(Aabb::from_min_max(min, max))
+}
+
+impl TempMesh {
+    /// Checks whether all vertex positions and texture coordinates of this
+    /// temporary mesh are within the range that can be quantized.
+    pub fn can_quantize(&self) -> bool {
+        self.vertices
+            .iter()
+            .all(|v| quantize_position(*v).is_some())
+            && self.uvs.iter().all(|uv| quantize_uv(*uv).is_some())
+    }
+
+    /// Converts this temporary mesh into a Bevy mesh that uses the quantized
+    /// vertex format described within the [module](self) documentation.
+    ///
+    /// Returns `None` if this temporary mesh is empty, or if any vertex
+    /// position or texture coordinate is outside of the range that can be
+    /// quantized.
+    pub fn into_quantized_mesh(self) -> Option<(Mesh, Handle<StandardMaterial>)> {
+        if self.indices.is_empty() {
+            return None;
+        }
+
+        let positions = self
+            .vertices
+            .iter()
+            .map(|v| quantize_position(*v))
+            .collect::<Option<Vec<_>>>()?;
+        let uvs = self
+            .uvs
+            .iter()
+            .map(|uv| quantize_uv(*uv))
+            .collect::<Option<Vec<_>>>()?;
+        let normals: Vec<_> = self.normals.iter().map(|n| pack_normal(*n)).collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            ATTRIBUTE_QUANTIZED_POSITION,
+            VertexAttributeValues::Uint16x4(positions),
+        );
+        mesh.insert_attribute(
+            ATTRIBUTE_PACKED_NORMAL,
+            VertexAttributeValues::Snorm8x4(normals),
+        );
+        mesh.insert_attribute(ATTRIBUTE_QUANTIZED_UV, VertexAttributeValues::Uint16x2(uvs));
+        mesh.set_indices(Some(Indices::U16(self.indices)));
+
+        Some((mesh, self.material))
+    }
+}
+
+impl ShapeBuilder<'_> {
+    /// Converts this shape builder into an iterator over all chunk meshes that
+    /// need to be created from this shape builder, using the quantized vertex
+    /// format described within the [module](self) documentation.
+    ///
+    /// Meshes that cannot be quantized use the standard vertex format instead.
+    pub fn into_quantized_meshes(self) -> impl Iterator<Item = (Mesh, Handle<StandardMaterial>)> {
+        self.into_temp_meshes().flat_map(|mesh| {
+            match mesh.can_quantize() {
+                true => mesh.into_quantized_mesh(),
+                false => mesh.into_mesh(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn quantize_vertices() {
+        let position = Vec3::new(0.0, 15.5, 16.0);
+        assert_eq!(
+            dequantize_position(quantize_position(position).unwrap()),
+            position
+        );
+        assert_eq!(quantize_position(Vec3::new(0.0, -17.0, 0.0)), None);
+        assert_eq!(quantize_position(Vec3::new(0.0, 48.0, 0.0)), None);
+
+        assert_eq!(unpack_normal(pack_normal(Vec3::NEG_Y)), Vec3::NEG_Y);
+        assert_eq!(quantize_uv(Vec2::new(0.5, 1.0)), Some([512, 1024]));
+
+        let mut temp_mesh = TempMesh::default();
+        temp_mesh.add_quad(
+            [Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y],
+            Vec3::Z,
+            [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y],
+            Default::default(),
+        );
+
+        assert!(temp_mesh.can_quantize());
+        let (mesh, _) = temp_mesh.into_quantized_mesh().unwrap();
+        assert_eq!(mesh.count_vertices(), 4);
+
+        let aabb = quantized_aabb(&mesh).unwrap();
+        assert_eq!(Vec3::from(aabb.min()), Vec3::ZERO);
+        assert_eq!(Vec3::from(aabb.max()), Vec3::ONE);
+        assert_eq!(
+            mesh.attributes()
+                .map(|(_, values)| values.get_bytes().len())
+                .sum::<usize>(),
+            4 * 16
+        );
+    }
+}
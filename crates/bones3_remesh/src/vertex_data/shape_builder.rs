@@ -7,6 +7,30 @@ use bevy::render::render_resource::PrimitiveTopology;
 use crate::ecs::resources::ChunkMaterialList;
 use crate::mesh::block_model::{BlockModelGenerator, BlockOcclusion};
 
+/// The relative indices that are used to indicate how the vertices of a quad
+/// are applied to write to a mesh with the TriangleList topology.
+const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+/// The relative indices that are used to write a quad with a reversed winding
+/// order to a mesh with the TriangleList topology.
+const FLIPPED_QUAD_INDICES: [u16; 6] = [0, 2, 1, 0, 3, 2];
+
+/// Determines which sides of a quad are written to a mesh.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuadFacing {
+    /// Only the front side of the quad is written.
+    #[default]
+    Front,
+
+    /// Only the back side of the quad is written. The winding order and
+    /// normals of the quad are flipped.
+    Back,
+
+    /// Both sides of the quad are written, using two separate sets of
+    /// vertices.
+    DoubleSided,
+}
+
 /// Acts as a temporary storage devices for mesh data that can be written to an
 /// actual Bevy mesh upon completion.
 #[derive(Debug, Default)]
@@ -28,6 +52,36 @@ pub struct TempMesh {
 }
 
 impl TempMesh {
+    /// Appends a single quad to this temporary mesh.
+    ///
+    /// The vertices are expected to be listed in counter-clockwise order when
+    /// viewed from the front of the quad, with the normal pointing towards the
+    /// front. The given facing determines which sides of the quad are written.
+    pub fn add_quad(
+        &mut self,
+        vertices: [Vec3; 4],
+        normal: Vec3,
+        uvs: [Vec2; 4],
+        facing: QuadFacing,
+    ) {
+        let mut write = |indices: [u16; 6], normal: Vec3| {
+            let vertex_count = self.vertices.len() as u16;
+            self.indices
+                .extend_from_slice(&indices.map(|i| i + vertex_count));
+            self.vertices.extend_from_slice(&vertices);
+            self.normals.extend_from_slice(&[normal; 4]);
+            self.uvs.extend_from_slice(&uvs);
+        };
+
+        if facing != QuadFacing::Back {
+            write(QUAD_INDICES, normal);
+        }
+
+        if facing != QuadFacing::Front {
+            write(FLIPPED_QUAD_INDICES, -normal);
+        }
+    }
+
     /// Contains this temporary mesh into a Bevy mesh.
     ///
     /// The resulting mesh is laid out using a triangle list topology. This
@@ -65,6 +119,10 @@ pub struct ShapeBuilder<'a> {
 
     /// The list of materials that might be used by the chunk.
     material_list: &'a ChunkMaterialList,
+
+    /// The sorted indices of all materials that shapes were added with,
+    /// including materials that are missing from the material list.
+    material_indices: Vec<u16>,
 }
 
 impl<'a> ShapeBuilder<'a> {
@@ -75,6 +133,7 @@ impl<'a> ShapeBuilder<'a> {
             local_pos: IVec3::ZERO,
             occlusion: BlockOcclusion::empty(),
             material_list,
+            material_indices: vec![],
         }
     }
 
@@ -110,12 +169,21 @@ impl<'a> ShapeBuilder<'a> {
 
     /// Appends a new shape to this shape builder instance with the given
     /// material, based off the provided block model generator.
+    ///
+    /// Shapes that use a material that has been removed from the material list
+    /// are skipped.
     pub fn add_shape<G>(&mut self, shape: G, material_index: u16)
     where
         G: BlockModelGenerator,
     {
         let block_pos = self.get_local_pos();
-        let material = self.material_list.get_material(material_index);
+        if let Err(index) = self.material_indices.binary_search(&material_index) {
+            self.material_indices.insert(index, material_index);
+        }
+
+        let Some(material) = self.material_list.try_get_material(material_index) else {
+            return;
+        };
 
         let mesh = match self
             .meshes
@@ -135,9 +203,38 @@ impl<'a> ShapeBuilder<'a> {
         shape.write_to_mesh(mesh, block_pos);
     }
 
+    /// Adds the given offset to the positions of all vertices that have been
+    /// written to this shape builder so far.
+    pub fn offset_vertices(&mut self, offset: Vec3) {
+        if offset == Vec3::ZERO {
+            return;
+        }
+
+        for mesh in self.meshes.iter_mut() {
+            for vertex in mesh.vertices.iter_mut() {
+                *vertex += offset;
+            }
+        }
+    }
+
+    /// Gets the sorted indices of all materials that shapes have been added
+    /// with so far, including materials that have been removed from the
+    /// material list, or that were not added to it yet.
+    pub fn material_indices(&self) -> &[u16] {
+        &self.material_indices
+    }
+
     /// Converts this shape builder into an iterator over all temporary meshes
     /// that need to be created from this shape builder.
     pub fn into_meshes(self) -> impl Iterator<Item = (Mesh, Handle<StandardMaterial>)> {
         self.meshes.into_iter().flat_map(|mesh| mesh.into_mesh())
     }
+
+    /// Converts this shape builder into an iterator over all non-empty
+    /// temporary meshes, without converting them into Bevy meshes.
+    pub fn into_temp_meshes(self) -> impl Iterator<Item = TempMesh> {
+        self.meshes
+            .into_iter()
+            .filter(|mesh| !mesh.indices.is_empty())
+    }
 }
@@ -0,0 +1,13 @@
+//! This crate contains helpers for testing code that is built on top of Bones
+//! Cubed, such as custom block shapes and world generators.
+//!
+//! It is intended to be used as a dev-dependency.
+
+#![warn(missing_docs)]
+#![warn(clippy::missing_docs_in_private_items)]
+#![warn(rustdoc::invalid_codeblock_attributes)]
+#![warn(rustdoc::invalid_html_tags)]
+#![allow(clippy::type_complexity)]
+
+pub mod mesh;
+pub mod world;
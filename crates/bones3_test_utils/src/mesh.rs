@@ -0,0 +1,335 @@
+//! Assertion helpers for checking generated chunk meshes, such as those built
+//! from custom [`BlockShape`](bones3_remesh::mesh::block_model::BlockShape)
+//! implementations.
+//!
+//! All helpers work on standard chunk meshes, which contain the position,
+//! normal, and texture coordinate attributes, and are laid out as an indexed
+//! triangle list. Quantized chunk meshes are not supported. Meshes may also
+//! be compared against golden files with [`assert_mesh_snapshot`].
+//!
+//! ```
+//! # use bevy::asset::HandleId;
+//! # use bevy::prelude::*;
+//! # use bones3_remesh::ecs::resources::ChunkMaterialList;
+//! # use bones3_remesh::mesh::block_model::BlockShape;
+//! # use bones3_remesh::mesh::builder::build_chunk_mesh;
+//! # use bones3_remesh::vertex_data::{CubeModelBuilder, ShapeBuilder};
+//! # use bones3_test_utils::mesh::*;
+//! # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+//! # enum Block {
+//! #     #[default]
+//! #     Air,
+//! #     Stone,
+//! # }
+//! # impl BlockShape for Block {
+//! #     fn write_shape(&self, shape_builder: &mut ShapeBuilder) {
+//! #         if *self == Block::Stone {
+//! #             let occlusion = shape_builder.get_occlusion();
+//! #             shape_builder.add_shape(CubeModelBuilder::new().set_occlusion(occlusion), 0);
+//! #         }
+//! #     }
+//! # }
+//! # let mut materials = ChunkMaterialList::default();
+//! # materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+//! let get_block = |block_pos: IVec3| match block_pos == IVec3::new(3, 4, 5) {
+//!     true => Block::Stone,
+//!     false => Block::Air,
+//! };
+//!
+//! let (mesh, _) = build_chunk_mesh(get_block, &materials)
+//!     .into_meshes()
+//!     .next()
+//!     .unwrap();
+//!
+//! assert_vertex_count(&mesh, 24);
+//! assert_no_degenerate_triangles(&mesh);
+//! assert_within_chunk_bounds(&mesh);
+//! ```
+
+use std::fmt::Write;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+
+/// The environment variable that, when set, causes
+/// [`assert_mesh_snapshot`] to overwrite existing snapshots instead of
+/// comparing against them.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "BONES3_UPDATE_SNAPSHOTS";
+
+/// The tolerance that is used when comparing vertex positions against bounds.
+const EPSILON: f32 = 1e-4;
+
+/// Gets the vertex positions of the given mesh.
+///
+/// Panics if the mesh does not have a position attribute.
+#[track_caller]
+pub fn mesh_positions(mesh: &Mesh) -> Vec<Vec3> {
+    float3_attribute(mesh, Mesh::ATTRIBUTE_POSITION.id, "position")
+}
+
+/// Gets the triangles of the given mesh, as sets of three vertex indices.
+///
+/// Panics if the mesh is not indexed, or if the number of indices is not a
+/// multiple of three.
+#[track_caller]
+pub fn mesh_triangles(mesh: &Mesh) -> Vec<[usize; 3]> {
+    let Some(indices) = mesh.indices() else {
+        panic!("Mesh does not have any indices");
+    };
+
+    let indices: Vec<usize> = indices.iter().collect();
+    let triangles = indices.chunks_exact(3);
+    assert!(
+        triangles.remainder().is_empty(),
+        "Mesh has {} indices, which is not a multiple of three",
+        indices.len()
+    );
+
+    triangles.map(|tri| [tri[0], tri[1], tri[2]]).collect()
+}
+
+/// Asserts that the given mesh has exactly the given number of vertices.
+#[track_caller]
+pub fn assert_vertex_count(mesh: &Mesh, expected: usize) {
+    let count = mesh.count_vertices();
+    assert!(
+        count == expected,
+        "Expected mesh to have {expected} vertices, but found {count}"
+    );
+}
+
+/// Asserts that the given mesh has exactly the given number of indices.
+#[track_caller]
+pub fn assert_index_count(mesh: &Mesh, expected: usize) {
+    let count = mesh.indices().map_or(0, |indices| indices.len());
+    assert!(
+        count == expected,
+        "Expected mesh to have {expected} indices, but found {count}"
+    );
+}
+
+/// Asserts that no triangle within the given mesh is degenerate.
+///
+/// A triangle is degenerate if it references the same vertex more than once,
+/// references a vertex that does not exist, or has no surface area.
+#[track_caller]
+pub fn assert_no_degenerate_triangles(mesh: &Mesh) {
+    let positions = mesh_positions(mesh);
+
+    for (index, [a, b, c]) in mesh_triangles(mesh).into_iter().enumerate() {
+        assert!(
+            a < positions.len() && b < positions.len() && c < positions.len(),
+            "Triangle {index} references a missing vertex: [{a}, {b}, {c}], but the mesh only \
+             has {} vertices",
+            positions.len()
+        );
+
+        assert!(
+            a != b && b != c && a != c,
+            "Triangle {index} references the same vertex more than once: [{a}, {b}, {c}]"
+        );
+
+        let (pa, pb, pc) = (positions[a], positions[b], positions[c]);
+        let area = (pb - pa).cross(pc - pa).length() * 0.5;
+        assert!(
+            area > EPSILON * EPSILON,
+            "Triangle {index} has no surface area: [{pa}, {pb}, {pc}]"
+        );
+    }
+}
+
+/// Asserts that all vertices of the given mesh lie within the bounds of a
+/// single chunk, from `(0, 0, 0)` to `(16, 16, 16)` in local coordinates.
+#[track_caller]
+pub fn assert_within_chunk_bounds(mesh: &Mesh) {
+    assert_within_bounds(mesh, Vec3::ZERO, Vec3::splat(16.0));
+}
+
+/// Asserts that all vertices of the given mesh lie within the given bounds.
+#[track_caller]
+pub fn assert_within_bounds(mesh: &Mesh, min: Vec3, max: Vec3) {
+    for (index, pos) in mesh_positions(mesh).into_iter().enumerate() {
+        assert!(
+            pos.cmpge(min - EPSILON).all() && pos.cmple(max + EPSILON).all(),
+            "Vertex {index} at {pos} lies outside of the bounds {min} to {max}"
+        );
+    }
+}
+
+/// Creates a human readable snapshot of the given mesh, containing all vertex
+/// positions, normals, and texture coordinates, followed by all triangles.
+///
+/// Values are rounded to four decimal places, so that snapshots are not
+/// affected by floating point noise.
+#[track_caller]
+pub fn mesh_snapshot(mesh: &Mesh) -> String {
+    let positions = mesh_positions(mesh);
+    let normals = float3_attribute(mesh, Mesh::ATTRIBUTE_NORMAL.id, "normal");
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(values)) => {
+            values.iter().map(|v| Vec2::from_array(*v)).collect()
+        },
+        Some(_) => panic!("Mesh uv attribute is not a Float32x2 attribute"),
+        None => vec![Vec2::ZERO; positions.len()],
+    };
+
+    let mut snapshot = String::new();
+    let _ = writeln!(snapshot, "vertices: {}", positions.len());
+    for (index, pos) in positions.iter().enumerate() {
+        let normal = normals.get(index).copied().unwrap_or_default();
+        let uv = uvs.get(index).copied().unwrap_or_default();
+        let _ = writeln!(
+            snapshot,
+            "  {index}: pos = {}, normal = {}, uv = {}",
+            format_vec(&pos.to_array()),
+            format_vec(&normal.to_array()),
+            format_vec(&uv.to_array()),
+        );
+    }
+
+    let triangles = mesh_triangles(mesh);
+    let _ = writeln!(snapshot, "triangles: {}", triangles.len());
+    for [a, b, c] in triangles {
+        let _ = writeln!(snapshot, "  {a} {b} {c}");
+    }
+
+    snapshot
+}
+
+/// Asserts that the given mesh matches the golden snapshot stored at the
+/// given file path.
+///
+/// If the snapshot file does not exist yet, or the
+/// [`UPDATE_SNAPSHOTS_VAR`] environment variable is set, the snapshot is
+/// written to the file instead, creating any missing parent directories.
+#[track_caller]
+pub fn assert_mesh_snapshot(mesh: &Mesh, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = mesh_snapshot(mesh);
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .unwrap_or_else(|err| panic!("Failed to create {}: {err}", parent.display()));
+        }
+        std::fs::write(path, actual)
+            .unwrap_or_else(|err| panic!("Failed to write {}: {err}", path.display()));
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {err}", path.display()));
+    if expected == actual {
+        return;
+    }
+
+    let mismatch = expected
+        .lines()
+        .zip(actual.lines())
+        .enumerate()
+        .find(|(_, (e, a))| e != a);
+
+    match mismatch {
+        Some((line, (e, a))) => {
+            panic!(
+                "Mesh does not match snapshot {} at line {}\n  expected: {e}\n  actual:   \
+                 {a}\nSet {UPDATE_SNAPSHOTS_VAR} to update the snapshot.",
+                path.display(),
+                line + 1
+            )
+        },
+        None => {
+            panic!(
+                "Mesh does not match snapshot {}: expected {} lines, but found {}\nSet \
+                 {UPDATE_SNAPSHOTS_VAR} to update the snapshot.",
+                path.display(),
+                expected.lines().count(),
+                actual.lines().count()
+            )
+        },
+    }
+}
+
+/// Gets the values of a three component float attribute of the given mesh.
+#[track_caller]
+fn float3_attribute(
+    mesh: &Mesh,
+    id: bevy::render::mesh::MeshVertexAttributeId,
+    name: &str,
+) -> Vec<Vec3> {
+    match mesh.attribute(id) {
+        Some(VertexAttributeValues::Float32x3(values)) => {
+            values.iter().map(|v| Vec3::from_array(*v)).collect()
+        },
+        Some(_) => panic!("Mesh {name} attribute is not a Float32x3 attribute"),
+        None => panic!("Mesh does not have a {name} attribute"),
+    }
+}
+
+/// Formats the given vector components, rounded to four decimal places.
+fn format_vec(values: &[f32]) -> String {
+    let values: Vec<_> = values
+        .iter()
+        .map(|v| {
+            let v = (v * 10_000.0).round() / 10_000.0;
+            // Avoid printing negative zero.
+            format!("{}", v + 0.0)
+        })
+        .collect();
+    format!("({})", values.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::asset::HandleId;
+    use bones3_remesh::ecs::resources::ChunkMaterialList;
+    use bones3_remesh::mesh::block_model::BlockShape;
+    use bones3_remesh::mesh::builder::build_chunk_mesh;
+    use bones3_remesh::vertex_data::{CubeModelBuilder, ShapeBuilder};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+    struct Block(bool);
+
+    impl BlockShape for Block {
+        fn write_shape(&self, shape_builder: &mut ShapeBuilder) {
+            if self.0 {
+                let occlusion = shape_builder.get_occlusion();
+                shape_builder.add_shape(CubeModelBuilder::new().set_occlusion(occlusion), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn check_single_cube_mesh() {
+        let mut materials = ChunkMaterialList::default();
+        materials.add_material(Handle::weak(HandleId::random::<StandardMaterial>()), None);
+
+        let get_block = |block_pos: IVec3| Block(block_pos == IVec3::new(15, 0, 3));
+        let (mesh, _) = build_chunk_mesh(get_block, &materials)
+            .into_meshes()
+            .next()
+            .unwrap();
+
+        assert_vertex_count(&mesh, 24);
+        assert_index_count(&mesh, 36);
+        assert_no_degenerate_triangles(&mesh);
+        assert_within_chunk_bounds(&mesh);
+        assert_within_bounds(&mesh, Vec3::new(15.0, 0.0, 3.0), Vec3::new(16.0, 1.0, 4.0));
+
+        let path = std::env::temp_dir()
+            .join(format!("bones3_snapshot_{}", std::process::id()))
+            .join("cube.txt");
+        assert_mesh_snapshot(&mesh, &path);
+        assert_mesh_snapshot(&mesh, &path);
+
+        let snapshot = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(snapshot, mesh_snapshot(&mesh));
+        assert!(snapshot.starts_with("vertices: 24\n"));
+        assert!(snapshot.contains("triangles: 12\n"));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,110 @@
+//! Assertion helpers for comparing the blocks of voxel worlds, such as a world
+//! that was reloaded from disk against the world that was saved.
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bones3_core::prelude::*;
+//! # use bones3_test_utils::world::assert_no_world_diff;
+//! # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+//! # enum Block {
+//! #     #[default]
+//! #     Air,
+//! # }
+//! # let mut app = App::new();
+//! # fn spawn_worlds(mut commands: VoxelCommands) {
+//! #     for _ in 0 .. 2 {
+//! #         let mut world = commands.spawn_world(());
+//! #         world.spawn_chunk(IVec3::ZERO, VoxelStorage::<Block>::default()).unwrap();
+//! #     }
+//! # }
+//! # Schedule::new().add_systems(spawn_worlds).run(&mut app.world);
+//! # let mut worlds = app.world.query_filtered::<Entity, With<VoxelWorld>>();
+//! # let [saved, reloaded]: [Entity; 2] = worlds.iter(&app.world).collect::<Vec<_>>().try_into().unwrap();
+//! let diff = compare_worlds::<Block>(&app.world, saved, reloaded);
+//! assert_no_world_diff(&diff);
+//! ```
+
+use std::fmt::{Debug, Write};
+
+use bones3_core::storage::{BlockData, ChunkDiff, WorldDiff};
+
+/// The maximum number of differing blocks that are listed for each chunk when
+/// an assertion fails.
+const MAX_LISTED_BLOCKS: usize = 8;
+
+/// Asserts that the given world diff is empty, listing the differing chunks
+/// and blocks if it is not.
+#[track_caller]
+pub fn assert_no_world_diff<T>(diff: &WorldDiff<T>)
+where
+    T: BlockData + Debug,
+{
+    if diff.is_empty() {
+        return;
+    }
+
+    panic!(
+        "Expected worlds to be equal, but {} chunks differ:\n{}",
+        diff.len(),
+        describe_world_diff(diff)
+    );
+}
+
+/// Creates a human readable description of the given world diff.
+pub fn describe_world_diff<T>(diff: &WorldDiff<T>) -> String
+where
+    T: BlockData + Debug,
+{
+    let mut out = String::new();
+    for (chunk_coords, chunk_diff) in diff.iter() {
+        match chunk_diff {
+            ChunkDiff::MissingInA => writeln!(out, "chunk {chunk_coords}: missing in a"),
+            ChunkDiff::MissingInB => writeln!(out, "chunk {chunk_coords}: missing in b"),
+            ChunkDiff::Blocks(blocks) => {
+                writeln!(out, "chunk {chunk_coords}: {} blocks differ", blocks.len()).unwrap();
+                for block in blocks.iter().take(MAX_LISTED_BLOCKS) {
+                    writeln!(
+                        out,
+                        "  {}: {:?} != {:?}",
+                        block.block_coords, block.a, block.b
+                    )
+                    .unwrap();
+                }
+                if blocks.len() > MAX_LISTED_BLOCKS {
+                    writeln!(out, "  ...").unwrap();
+                }
+                Ok(())
+            },
+        }
+        .unwrap();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use bones3_core::storage::{compare_snapshots, WorldSnapshot};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn describe_differing_snapshots() {
+        let mut a = WorldSnapshot::<u8>::new();
+        a.set_block(IVec3::new(1, 2, 3), 4);
+        a.set_block(IVec3::new(20, 0, 0), 1);
+
+        let mut b = a.clone();
+        assert_no_world_diff(&compare_snapshots(&a, &b));
+
+        b.set_block(IVec3::new(1, 2, 3), 5);
+        b.set_block(IVec3::new(0, -1, 0), 1);
+        assert_eq!(
+            describe_world_diff(&compare_snapshots(&a, &b)),
+            "chunk [0, -1, 0]: missing in a\nchunk [0, 0, 0]: 1 blocks differ\n  [1, 2, 3]: 4 != \
+             5\n"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! World generators that are built by composing other world generators.
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bones3_core::prelude::*;
+//! # use bones3_worldgen::combinators::*;
+//! # use bones3_worldgen::ecs::components::WorldGenerator;
+//! # use bones3_worldgen::error::WorldGenError;
+//! # use bones3_worldgen::generators::FlatWorldGenerator;
+//! # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+//! # enum BlockState {
+//! #     #[default]
+//! #     Air,
+//! #     Stone,
+//! #     Brick,
+//! # }
+//! # #[derive(Default)]
+//! # struct TerrainGenerator;
+//! # impl WorldGenerator<BlockState> for TerrainGenerator {
+//! #     fn generate_chunk(&self, _: IVec3) -> Result<VoxelStorage<BlockState>, WorldGenError> {
+//! #         Ok(VoxelStorage::filled(BlockState::Stone))
+//! #     }
+//! # }
+//! # #[derive(Default)]
+//! # struct RuinsGenerator;
+//! # impl WorldGenerator<BlockState> for RuinsGenerator {
+//! #     fn generate_chunk(&self, _: IVec3) -> Result<VoxelStorage<BlockState>, WorldGenError> {
+//! #         Ok(VoxelStorage::filled(BlockState::Brick))
+//! #     }
+//! # }
+//! # let seed = 42;
+//! # let stone = BlockState::Stone;
+//! let generator = FallbackGenerator::new(
+//!     LayeredGenerator::new(TerrainGenerator::default())
+//!         .with_layer(MaskedGenerator::new(
+//!             RuinsGenerator::default(),
+//!             GenMask::noise(seed, 0.01, 0.6),
+//!         )),
+//!     FlatWorldGenerator::new(vec![(64, stone)]),
+//! );
+//! # generator.generate_chunk(IVec3::ZERO)?;
+//! # Ok::<(), WorldGenError>(())
+//! ```
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, VoxelStorage};
+
+use crate::ecs::components::WorldGenerator;
+use crate::error::WorldGenError;
+use crate::pipeline::GradientNoise;
+
+/// A world generator that generates each chunk using a base generator, and
+/// then places the output of each layer generator on top of it.
+///
+/// Blocks that a layer leaves as the default block value are treated as empty,
+/// and keep the block from the layers below them.
+pub struct LayeredGenerator<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// The generators that are applied, from bottom to top.
+    layers: Vec<Arc<dyn WorldGenerator<T>>>,
+}
+
+impl<T> LayeredGenerator<T>
+where
+    T: BlockData + PartialEq,
+{
+    /// Creates a new layered generator with the given base generator.
+    pub fn new<G>(base: G) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        Self {
+            layers: vec![Arc::new(base)],
+        }
+    }
+
+    /// Adds a new layer on top of all existing layers.
+    pub fn with_layer<G>(mut self, layer: G) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+}
+
+impl<T> WorldGenerator<T> for LayeredGenerator<T>
+where
+    T: BlockData + PartialEq,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        let mut storage = self.layers[0].generate_chunk(chunk_coords)?;
+
+        for layer in self.layers[1 ..].iter() {
+            let layer = layer.generate_chunk(chunk_coords)?;
+            for local_pos in Region::CHUNK.iter() {
+                let block = layer.get_block(local_pos);
+                if block != T::default() {
+                    storage.set_block(local_pos, block);
+                }
+            }
+        }
+
+        Ok(storage)
+    }
+}
+
+/// A world generator that uses a fallback generator for every chunk that the
+/// primary generator fails to generate.
+pub struct FallbackGenerator<T>
+where
+    T: BlockData,
+{
+    /// The generator that is tried first.
+    primary: Arc<dyn WorldGenerator<T>>,
+
+    /// The generator that is used if the primary generator fails.
+    fallback: Arc<dyn WorldGenerator<T>>,
+}
+
+impl<T> FallbackGenerator<T>
+where
+    T: BlockData,
+{
+    /// Creates a new fallback generator from the given primary and fallback
+    /// generators.
+    pub fn new<P, F>(primary: P, fallback: F) -> Self
+    where
+        P: WorldGenerator<T> + 'static,
+        F: WorldGenerator<T> + 'static,
+    {
+        Self {
+            primary:  Arc::new(primary),
+            fallback: Arc::new(fallback),
+        }
+    }
+}
+
+impl<T> WorldGenerator<T> for FallbackGenerator<T>
+where
+    T: BlockData,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        self.primary
+            .generate_chunk(chunk_coords)
+            .or_else(|_| self.fallback.generate_chunk(chunk_coords))
+    }
+}
+
+/// A mask that selects which blocks are kept by a [`MaskedGenerator`].
+#[derive(Clone)]
+pub enum GenMask {
+    /// Keeps all blocks within the given region of block coordinates.
+    Region(Region),
+
+    /// Keeps all blocks where the given noise function, sampled at the block
+    /// coordinates multiplied by `scale`, is at least `threshold`.
+    Noise {
+        /// The noise function to sample.
+        noise: GradientNoise,
+
+        /// The frequency at which the noise function is sampled.
+        scale: f32,
+
+        /// The minimum noise value of blocks that are kept.
+        threshold: f32,
+    },
+
+    /// Keeps all blocks for which the given predicate returns true, given
+    /// their block coordinates.
+    Custom(Arc<dyn Fn(IVec3) -> bool + Send + Sync>),
+}
+
+impl GenMask {
+    /// Creates a new noise mask using a gradient noise function with the given
+    /// seed.
+    pub fn noise(seed: u64, scale: f32, threshold: f32) -> Self {
+        Self::Noise {
+            noise: GradientNoise::new(seed),
+            scale,
+            threshold,
+        }
+    }
+
+    /// Creates a new mask from the given predicate.
+    pub fn custom<F>(predicate: F) -> Self
+    where
+        F: Fn(IVec3) -> bool + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(predicate))
+    }
+
+    /// Checks whether this mask keeps the block at the given block
+    /// coordinates.
+    pub fn contains(&self, block_coords: IVec3) -> bool {
+        match self {
+            GenMask::Region(region) => region.contains(block_coords),
+            GenMask::Noise {
+                noise,
+                scale,
+                threshold,
+            } => noise.get(block_coords.as_vec3() * *scale) >= *threshold,
+            GenMask::Custom(predicate) => predicate(block_coords),
+        }
+    }
+}
+
+/// A world generator that only keeps the blocks of another generator that are
+/// within a mask. All other blocks are left as the default block value.
+///
+/// This is mostly useful as a layer within a [`LayeredGenerator`]. Chunks that
+/// are entirely outside of a region mask are not generated by the inner
+/// generator at all.
+pub struct MaskedGenerator<T>
+where
+    T: BlockData,
+{
+    /// The generator that creates the masked blocks.
+    inner: Arc<dyn WorldGenerator<T>>,
+
+    /// The mask that selects which blocks are kept.
+    mask: GenMask,
+}
+
+impl<T> MaskedGenerator<T>
+where
+    T: BlockData,
+{
+    /// Creates a new masked generator from the given generator and mask.
+    pub fn new<G>(inner: G, mask: GenMask) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        Self {
+            inner: Arc::new(inner),
+            mask,
+        }
+    }
+}
+
+impl<T> WorldGenerator<T> for MaskedGenerator<T>
+where
+    T: BlockData,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        let chunk_region = Region::CHUNK.shift(chunk_coords * 16);
+        if let GenMask::Region(region) = &self.mask {
+            if !region.intersects(chunk_region) {
+                return Ok(VoxelStorage::default());
+            }
+        }
+
+        let generated = self.inner.generate_chunk(chunk_coords)?;
+        let mut storage = VoxelStorage::default();
+        for block_coords in chunk_region.iter() {
+            if self.mask.contains(block_coords) {
+                storage.set_block(block_coords, generated.get_block(block_coords));
+            }
+        }
+
+        Ok(storage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::fill;
+
+    struct Broken;
+
+    impl WorldGenerator<u8> for Broken {
+        fn generate_chunk(&self, _: IVec3) -> Result<VoxelStorage<u8>, WorldGenError> {
+            Err(WorldGenError::new("broken"))
+        }
+    }
+
+    #[test]
+    fn compose_generators() {
+        let generator = FallbackGenerator::new(
+            LayeredGenerator::new(fill(1)).with_layer(MaskedGenerator::new(
+                fill(2),
+                GenMask::Region(Region::from_points(IVec3::ZERO, IVec3::new(3, 3, 3))),
+            )),
+            fill(3),
+        );
+
+        let chunk = generator.generate_chunk(IVec3::ZERO).unwrap();
+        assert_eq!(chunk.get_block(IVec3::new(2, 2, 2)), 2);
+        assert_eq!(chunk.get_block(IVec3::new(4, 2, 2)), 1);
+
+        let chunk = generator.generate_chunk(IVec3::ONE).unwrap();
+        assert_eq!(chunk.get_block(IVec3::new(2, 2, 2)), 1);
+
+        let fallback = FallbackGenerator::new(Broken, fill(3));
+        let chunk = fallback.generate_chunk(IVec3::ZERO).unwrap();
+        assert_eq!(chunk.get_block(IVec3::ZERO), 3);
+    }
+}
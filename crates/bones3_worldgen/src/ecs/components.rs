@@ -1,8 +1,12 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy::tasks::Task;
-use bones3_core::storage::{BlockData, VoxelStorage};
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, VoxelStorage, VoxelWorldBundle};
+
+use crate::error::WorldGenError;
 
 /// This component indicates that the chunk is currently being loaded in an
 /// async task, and will have a voxel storage component replace this component
@@ -10,14 +14,87 @@ use bones3_core::storage::{BlockData, VoxelStorage};
 #[derive(Debug, Component, Reflect)]
 #[reflect(from_reflect = false)]
 #[component(storage = "SparseSet")]
-pub struct LoadChunkTask<T: BlockData>(#[reflect(ignore)] pub(crate) Task<VoxelStorage<T>>);
+pub struct LoadChunkTask<T: BlockData>(#[reflect(ignore)] pub(crate) ChunkGenTask<T>);
+
+/// The result of loading a chunk, along with where its data came from and the
+/// time it took.
+pub(crate) type ChunkGenOutput<T> = (
+    Result<VoxelStorage<T>, WorldGenError>,
+    ChunkLoadSource,
+    Duration,
+);
+
+/// Where the data of a loaded chunk came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChunkLoadSource {
+    /// The chunk was read from the chunk store of its world.
+    Store,
+
+    /// The chunk was created by the world generator of its world.
+    Generator,
+}
+
+/// The generation task of a chunk that is being loaded.
+#[derive(Debug)]
+pub(crate) enum ChunkGenTask<T: BlockData> {
+    /// The chunk is being generated within the async compute task pool.
+    Async(Task<ChunkGenOutput<T>>),
+
+    /// The chunk has already been generated, and the result is applied once
+    /// the remaining number of updates have passed.
+    Ready {
+        /// The generation result, or `None` if it has already been applied.
+        result: Option<ChunkGenOutput<T>>,
+
+        /// The number of updates left before the result is applied.
+        remaining_updates: u32,
+    },
+}
 
 /// A marker component that indicates that the target chunk is still waiting to
 /// be loaded.
-#[derive(Debug, Component, Reflect)]
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
 #[component(storage = "SparseSet")]
 pub struct PendingLoadChunkTask;
 
+/// A marker component that indicates that the world generator failed to
+/// generate the target chunk.
+///
+/// This component is inserted once all generation attempts allowed by the
+/// [`WorldGenRetryPolicy`](crate::ecs::resources::WorldGenRetryPolicy) have
+/// failed. Chunks with this component are not queued for generation. Removing
+/// this component queues the chunk for generation again.
+#[derive(Debug, Default, Component, Reflect)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct ChunkGenFailed;
+
+/// A component that tracks the failed generation attempts of a chunk that is
+/// waiting to be generated again.
+///
+/// The chunk is queued for generation again once the elapsed app time reaches
+/// `retry_at`. This component is removed once the chunk has been generated.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct ChunkGenRetry {
+    /// The number of times that generating the chunk has failed.
+    pub attempts: u32,
+
+    /// The elapsed app time, in seconds, at which the chunk is queued for
+    /// generation again.
+    pub retry_at: f32,
+}
+
+/// A component that stores how long it took to generate the block data of a
+/// chunk, for profiling purposes.
+///
+/// This component is added to a chunk once it has been generated successfully.
+#[derive(Debug, Default, Component, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct ChunkGenTime(pub Duration);
+
 /// A trait that handles the generation of block data when new chunks are
 /// loaded.
 pub trait WorldGenerator<T>
@@ -27,7 +104,11 @@ where
 {
     /// Generates a voxel world slice containing the block data to populate a
     /// newly generated chunk at the given chunk coordinates.
-    fn generate_chunk(&self, chunk_coords: IVec3) -> VoxelStorage<T>;
+    ///
+    /// If an error is returned, the chunk is retried according to the
+    /// [`WorldGenRetryPolicy`](crate::ecs::resources::WorldGenRetryPolicy).
+    /// Panics within this function are caught and handled in the same way.
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError>;
 }
 
 /// A component wrapper for storing a WorldGenerator object.
@@ -54,3 +135,159 @@ where
         self.0.clone()
     }
 }
+
+/// A bundle for spawning a voxel world that generates its chunks using the
+/// given world generator, without going through voxel commands.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::storage::{BlockScale, VoxelWorldBundle};
+/// # use bones3_worldgen::ecs::components::GeneratedWorldBundle;
+/// # use bones3_worldgen::generators::FlatWorldGenerator;
+/// # fn spawn_world(mut commands: Commands) {
+/// # let my_generator = FlatWorldGenerator::new(vec![(4, 1u8)]);
+/// commands.spawn(GeneratedWorldBundle::new(
+///     my_generator,
+///     VoxelWorldBundle::new().with_block_scale(BlockScale(0.5)),
+/// ));
+/// # }
+/// ```
+#[derive(Bundle)]
+pub struct GeneratedWorldBundle<T>
+where
+    T: BlockData,
+{
+    /// The components of the voxel world.
+    world: VoxelWorldBundle,
+
+    /// The world generator of the voxel world.
+    generator: WorldGeneratorHandler<T>,
+}
+
+impl<T> GeneratedWorldBundle<T>
+where
+    T: BlockData,
+{
+    /// Creates a new generated world bundle with the given world generator
+    /// and world settings.
+    pub fn new<G>(generator: G, world: VoxelWorldBundle) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        Self {
+            world,
+            generator: WorldGeneratorHandler::from(generator),
+        }
+    }
+}
+
+/// A predicate that decides whether a generator zone contains the chunk at the
+/// given chunk coordinates.
+type ZonePredicate = Arc<dyn Fn(IVec3) -> bool + Send + Sync>;
+
+/// A component that assigns different world generators to different zones of
+/// a voxel world, such as a hand-authored city within procedural wilderness.
+///
+/// When a chunk is queued for generation, the zones are checked in the order
+/// they were added, and the generator of the first zone that contains the
+/// chunk is used. Chunks outside of every zone use the
+/// [`WorldGeneratorHandler`] of the world, or are left empty if the world
+/// does not have one.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::prelude::*;
+/// # use bones3_worldgen::ecs::components::{WorldGeneratorHandler, WorldGeneratorZones};
+/// # use bones3_worldgen::generators::FlatWorldGenerator;
+/// # fn spawn_world(mut commands: VoxelCommands) {
+/// # let wilderness = FlatWorldGenerator::new(vec![(64, 1u8)]);
+/// # let city = FlatWorldGenerator::new(vec![(64, 1u8), (1, 2)]);
+/// # let bedrock = FlatWorldGenerator::new(vec![(1, 3u8)]).with_base_height(-1024);
+/// commands.spawn_world((
+///     WorldGeneratorHandler::from(wilderness),
+///     WorldGeneratorZones::default()
+///         .with_region(Region::from_points(IVec3::new(-4, -1, -4), IVec3::new(4, 2, 4)), city)
+///         .with_zone(|chunk_coords| chunk_coords.y < -64, bedrock),
+/// ));
+/// # }
+/// ```
+#[derive(Component, Reflect)]
+#[reflect(from_reflect = false)]
+pub struct WorldGeneratorZones<T>(
+    #[reflect(ignore)] Vec<(ZonePredicate, Arc<dyn WorldGenerator<T>>)>,
+)
+where
+    T: BlockData;
+
+impl<T> Default for WorldGeneratorZones<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<T> WorldGeneratorZones<T>
+where
+    T: BlockData,
+{
+    /// Adds a zone that contains all chunks for which the given predicate
+    /// returns true.
+    pub fn with_zone<P, G>(mut self, predicate: P, generator: G) -> Self
+    where
+        P: Fn(IVec3) -> bool + Send + Sync + 'static,
+        G: WorldGenerator<T> + 'static,
+    {
+        self.0.push((Arc::new(predicate), Arc::new(generator)));
+        self
+    }
+
+    /// Adds a zone that contains all chunks within the given region of chunk
+    /// coordinates.
+    pub fn with_region<G>(self, chunk_region: Region, generator: G) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        self.with_zone(
+            move |chunk_coords| chunk_region.contains(chunk_coords),
+            generator,
+        )
+    }
+
+    /// Gets the generator of the first zone that contains the chunk at the
+    /// given chunk coordinates, if any.
+    pub fn generator_for(&self, chunk_coords: IVec3) -> Option<Arc<dyn WorldGenerator<T>>> {
+        self.0
+            .iter()
+            .find(|(predicate, _)| predicate(chunk_coords))
+            .map(|(_, generator)| generator.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::fill;
+
+    #[test]
+    fn select_first_matching_zone() {
+        let zones = WorldGeneratorZones::default()
+            .with_region(Region::from_points(IVec3::ZERO, IVec3::ONE), fill(1))
+            .with_zone(|chunk_coords| chunk_coords.y < 2, fill(2));
+
+        let block_at = |chunk_coords| {
+            zones.generator_for(chunk_coords).map(|g| {
+                g.generate_chunk(chunk_coords)
+                    .unwrap()
+                    .get_block(IVec3::ZERO)
+            })
+        };
+
+        assert_eq!(block_at(IVec3::ONE), Some(1));
+        assert_eq!(block_at(IVec3::new(5, 0, 5)), Some(2));
+        assert_eq!(block_at(IVec3::new(0, 3, 0)), None);
+    }
+}
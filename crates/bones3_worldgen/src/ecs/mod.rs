@@ -1,2 +1,4 @@
 pub mod components;
+pub mod queries;
+pub mod resources;
 pub mod systems;
@@ -0,0 +1,119 @@
+//! This module contains query helpers for inspecting the world generation
+//! state of chunks.
+
+use bevy::ecs::query::{Has, WorldQuery};
+use bevy::prelude::*;
+use bones3_core::storage::{BlockData, VoxelStorage};
+
+use super::components::{ChunkGenFailed, LoadChunkTask};
+
+/// The world generation state of a single chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum ChunkGenState {
+    /// The chunk is waiting for a free generation task slot.
+    Pending,
+
+    /// The chunk is currently being generated within an async task.
+    Generating,
+
+    /// The chunk has been generated and its block data is available.
+    Ready,
+
+    /// The chunk could not be generated.
+    Failed,
+}
+
+/// A query for reading the [`ChunkGenState`] of chunks with the block data
+/// type `T`, without depending on the marker components that are used
+/// internally by the world generation systems.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_worldgen::ecs::queries::{ChunkGenState, ChunkGenStateQuery};
+/// # #[derive(Debug, Default, Clone, Copy, Reflect)]
+/// # struct BlockState;
+/// fn show_loading_screen(chunks: Query<ChunkGenStateQuery<BlockState>>) {
+///     let ready = chunks
+///         .iter()
+///         .filter(|chunk| chunk.state() == ChunkGenState::Ready)
+///         .count();
+///     # let _ = ready;
+/// }
+/// # bevy::ecs::system::assert_is_system(show_loading_screen);
+/// ```
+#[derive(WorldQuery)]
+pub struct ChunkGenStateQuery<T>
+where
+    T: BlockData,
+{
+    /// Whether the chunk has block data.
+    storage: Has<VoxelStorage<T>>,
+
+    /// Whether the chunk is being generated.
+    task: Has<LoadChunkTask<T>>,
+
+    /// Whether the chunk failed to generate.
+    failed: Has<ChunkGenFailed>,
+}
+
+impl<T> ChunkGenStateQueryItem<'_, T>
+where
+    T: BlockData,
+{
+    /// Gets the world generation state of this chunk.
+    ///
+    /// Chunks without block data that have not been given a generation task
+    /// yet are considered pending, even if they have not been marked with a
+    /// [`PendingLoadChunkTask`](super::components::PendingLoadChunkTask) yet.
+    pub fn state(&self) -> ChunkGenState {
+        if self.storage {
+            ChunkGenState::Ready
+        } else if self.failed {
+            ChunkGenState::Failed
+        } else if self.task {
+            ChunkGenState::Generating
+        } else {
+            ChunkGenState::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bones3_core::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ecs::components::PendingLoadChunkTask;
+
+    #[test]
+    fn read_generation_state() {
+        let mut app = App::new();
+
+        Schedule::new()
+            .add_systems(|mut commands: VoxelCommands| {
+                let mut world = commands.spawn_world(());
+                world.spawn_chunk(IVec3::X, ()).unwrap();
+                world.spawn_chunk(IVec3::Y, PendingLoadChunkTask).unwrap();
+                world.spawn_chunk(IVec3::Z, ChunkGenFailed).unwrap();
+                world
+                    .spawn_chunk(IVec3::ZERO, VoxelStorage::<u8>::default())
+                    .unwrap();
+            })
+            .run(&mut app.world);
+
+        let mut chunks = app.world.query::<(&VoxelChunk, ChunkGenStateQuery<u8>)>();
+        let mut states: Vec<_> = chunks
+            .iter(&app.world)
+            .map(|(chunk, gen)| (chunk.chunk_coords().to_array(), gen.state()))
+            .collect();
+        states.sort_by_key(|(coords, _)| *coords);
+
+        assert_eq!(states, vec![
+            ([0, 0, 0], ChunkGenState::Ready),
+            ([0, 0, 1], ChunkGenState::Failed),
+            ([0, 1, 0], ChunkGenState::Pending),
+            ([1, 0, 0], ChunkGenState::Pending),
+        ]);
+    }
+}
@@ -0,0 +1,248 @@
+//! This module contains the resources that are used to configure chunk loading
+//! and unloading.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bones3_core::persistence::{PersistenceError, PersistentBlock, PersistentWorld};
+use bones3_core::storage::{BlockData, VoxelStorage};
+use bones3_core::util::timing::{ChunkTimings, TaskBudget};
+
+use crate::error::WorldGenError;
+
+/// This resource controls how quickly chunks that have left the range of all
+/// world generation anchors are unloaded.
+///
+/// Chunks that are out of range are despawned over multiple frames, starting
+/// with the chunks that are farthest away from any anchor. This prevents large
+/// frame spikes when an anchor teleports and many chunks leave its range at
+/// once.
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource, Default)]
+pub struct ChunkUnloadSettings {
+    /// The maximum number of chunks that may be despawned within a single
+    /// frame. If this is `0`, all chunks that are out of range are despawned
+    /// at once.
+    ///
+    /// Defaults to `64`.
+    pub max_despawns_per_frame: usize,
+}
+
+impl Default for ChunkUnloadSettings {
+    fn default() -> Self {
+        Self {
+            max_despawns_per_frame: 64,
+        }
+    }
+}
+
+/// This resource controls how chunks that failed to generate are retried.
+///
+/// After each failed attempt, the chunk waits for a backoff delay before it is
+/// queued for generation again. The delay starts at `initial_backoff` and is
+/// multiplied by `backoff_multiplier` after every attempt. Once `max_attempts`
+/// attempts have failed, the chunk is marked with
+/// [`ChunkGenFailed`](crate::ecs::components::ChunkGenFailed) and is no longer
+/// retried.
+#[derive(Debug, Resource, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct WorldGenRetryPolicy {
+    /// The maximum number of times that generating a single chunk is
+    /// attempted.
+    ///
+    /// Defaults to `3`.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry, in seconds.
+    ///
+    /// Defaults to `0.5`.
+    pub initial_backoff: f32,
+
+    /// The factor that the delay is multiplied by after each retry.
+    ///
+    /// Defaults to `2.0`.
+    pub backoff_multiplier: f32,
+}
+
+impl Default for WorldGenRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts:       3,
+            initial_backoff:    0.5,
+            backoff_multiplier: 2.0,
+        }
+    }
+}
+
+impl WorldGenRetryPolicy {
+    /// Gets the delay, in seconds, before retrying a chunk that has failed the
+    /// given number of times, or `None` if the chunk should not be retried.
+    pub fn backoff(&self, attempts: u32) -> Option<f32> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+
+        let retries = attempts.saturating_sub(1) as i32;
+        Some(self.initial_backoff * self.backoff_multiplier.powi(retries))
+    }
+}
+
+/// This resource controls how chunk generation tasks are executed.
+///
+/// By default, chunks are generated within the async compute task pool, and
+/// the results are applied on whichever update they happen to finish. This
+/// makes it difficult to write deterministic tests for world generation, so
+/// the other modes generate chunks synchronously, on the main thread, as soon
+/// as their task is started. These modes do not require the task pools to be
+/// initialized.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_worldgen::ecs::resources::WorldGenTaskMode;
+/// # use bones3_worldgen::Bones3WorldGenPlugin;
+/// # #[derive(Debug, Default, Clone, Copy, Reflect)]
+/// # struct BlockState;
+/// # let mut app = App::new();
+/// app.add_plugins(Bones3WorldGenPlugin::<BlockState>::default())
+///     .insert_resource(WorldGenTaskMode::Immediate);
+/// ```
+#[derive(Debug, Default, Resource, Reflect, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource, Default)]
+pub enum WorldGenTaskMode {
+    /// Chunks are generated within the async compute task pool.
+    #[default]
+    Async,
+
+    /// Chunks are generated and applied within the same update that their
+    /// generation task is started.
+    Immediate,
+
+    /// Chunks are generated as soon as their generation task is started, but
+    /// the results are only applied after the given number of additional
+    /// updates have passed.
+    Deferred(u32),
+}
+
+/// This resource controls how many chunk generation tasks may run at once.
+///
+/// The number of tasks adapts to a moving average of how long recent chunks
+/// took to generate, such that the tasks together take about as long as the
+/// target time of the budget. Chunks over deep cave systems may take much
+/// longer to generate than chunks of open sky, so a fixed number of tasks would
+/// either generate chunks slowly or stall the task pool.
+///
+/// The recorded timings are also available for profiling purposes.
+#[derive(Debug, Resource, Reflect, Clone, PartialEq)]
+#[reflect(Resource, Default)]
+pub struct WorldGenBudget {
+    /// The budget that determines the number of tasks.
+    ///
+    /// Defaults to a target time of 12ms, with 1 to 16 tasks, and 3 tasks
+    /// before any chunks have been generated.
+    pub budget: TaskBudget,
+
+    /// The timings of all chunks that have been generated.
+    #[reflect(ignore)]
+    timings: ChunkTimings,
+}
+
+impl Default for WorldGenBudget {
+    fn default() -> Self {
+        Self {
+            budget:  TaskBudget {
+                target_time:   Duration::from_millis(12),
+                min_tasks:     1,
+                max_tasks:     16,
+                initial_tasks: 3,
+            },
+            timings: ChunkTimings::default(),
+        }
+    }
+}
+
+impl WorldGenBudget {
+    /// Gets the timings of all chunks that have been generated.
+    pub fn timings(&self) -> &ChunkTimings {
+        &self.timings
+    }
+
+    /// Gets the maximum number of chunk generation tasks that may currently
+    /// exist at once.
+    pub fn max_tasks(&self) -> usize {
+        self.budget.tasks(&self.timings)
+    }
+
+    /// Records the time it took to generate a single chunk.
+    pub(crate) fn record(&mut self, duration: Duration) {
+        self.timings.record(duration);
+    }
+}
+
+/// This resource allows chunks of the given block data type to be read from the
+/// [`PersistentWorld`](bones3_core::persistence::PersistentWorld) chunk store
+/// of their world before they are generated.
+///
+/// It is inserted by the
+/// [`WorldGenPersistencePlugin`](crate::WorldGenPersistencePlugin), and
+/// exists so that the chunk queue does not require block data types to be
+/// persistent.
+#[derive(Resource)]
+pub struct PersistentChunkLoader<T>
+where
+    T: BlockData,
+{
+    /// The function that reads saved chunks from a persistent world.
+    pub(crate) load:
+        fn(&PersistentWorld, IVec3) -> Result<Option<VoxelStorage<T>>, PersistenceError>,
+}
+
+impl<T> Default for PersistentChunkLoader<T>
+where
+    T: PersistentBlock,
+{
+    fn default() -> Self {
+        Self {
+            load: PersistentWorld::load_chunk::<T>,
+        }
+    }
+}
+
+/// An event that is sent whenever a world generator fails to generate a chunk.
+#[derive(Debug, Event, Clone, PartialEq)]
+pub struct ChunkGenFailedEvent {
+    /// The id of the world the chunk is in.
+    pub world_id: Entity,
+
+    /// The coordinates of the chunk.
+    pub chunk_coords: IVec3,
+
+    /// The number of times that generating the chunk has failed so far.
+    pub attempts: u32,
+
+    /// The error that occurred.
+    pub error: WorldGenError,
+
+    /// Whether the chunk will be retried, or has been marked as failed.
+    pub will_retry: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = WorldGenRetryPolicy {
+            max_attempts:       4,
+            initial_backoff:    0.5,
+            backoff_multiplier: 3.0,
+        };
+
+        assert_eq!(policy.backoff(1), Some(0.5));
+        assert_eq!(policy.backoff(2), Some(1.5));
+        assert_eq!(policy.backoff(3), Some(4.5));
+        assert_eq!(policy.backoff(4), None);
+    }
+}
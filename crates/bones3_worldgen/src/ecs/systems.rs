@@ -1,65 +1,172 @@
+use std::any::Any;
+use std::cmp::Reverse;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
+
 use bevy::prelude::*;
 use bevy::tasks::AsyncComputeTaskPool;
+use bevy::utils::HashSet;
+use bones3_core::persistence::{ChunkLoadedFromStore, PersistenceError, PersistentWorld};
 use bones3_core::query::VoxelCommands;
 use bones3_core::storage::{BlockData, VoxelChunk, VoxelStorage, VoxelWorld};
 use bones3_core::util::anchor::{ChunkAnchor, ChunkAnchorRecipient};
+use bones3_core::util::pinned::{is_chunk_pinned, PinnedChunks};
+use bones3_core::util::stats::Bones3StatsCollector;
 #[cfg(feature = "meshing")]
 use bones3_remesh::{ecs::components::RemeshChunk, query::VoxelRemeshCommands};
 use futures_lite::future;
 use ordered_float::OrderedFloat;
 use priority_queue::PriorityQueue;
 
-use super::components::{LoadChunkTask, PendingLoadChunkTask, WorldGeneratorHandler};
+use super::components::{
+    ChunkGenFailed,
+    ChunkGenRetry,
+    ChunkGenTask,
+    ChunkGenTime,
+    ChunkLoadSource,
+    LoadChunkTask,
+    PendingLoadChunkTask,
+    WorldGenerator,
+    WorldGeneratorHandler,
+    WorldGeneratorZones,
+};
+use super::resources::{
+    ChunkGenFailedEvent,
+    ChunkUnloadSettings,
+    PersistentChunkLoader,
+    WorldGenBudget,
+    WorldGenRetryPolicy,
+    WorldGenTaskMode,
+};
+use crate::error::WorldGenError;
 use crate::WorldGenAnchor;
 
+/// A function that reads a saved chunk from a persistent world.
+type ChunkReader<T> =
+    fn(&PersistentWorld, IVec3) -> Result<Option<VoxelStorage<T>>, PersistenceError>;
+
 pub(crate) fn create_chunk_entities(
     anchors: Query<&ChunkAnchor<WorldGenAnchor>>,
+    pinned: Query<(Entity, &PinnedChunks)>,
+    spatial_worlds: Query<(), (With<VoxelWorld>, With<GlobalTransform>)>,
     mut commands: VoxelCommands,
 ) {
     for anchor in anchors.iter() {
-        let Ok(mut world_commands) = commands.get_world(anchor.world_id) else {
-            continue;
-        };
-
         let Some(region) = anchor.get_region() else {
             continue;
         };
 
-        for chunk_coords in region.into_iter() {
-            let chunk_pos = chunk_coords.as_vec3() * 16.0;
+        let spatial = spatial_worlds.contains(anchor.world_id);
+        spawn_missing_chunks(&mut commands, anchor.world_id, region.into_iter(), spatial);
+    }
 
-            world_commands
-                .spawn_chunk(
-                    chunk_coords,
-                    SpatialBundle {
-                        transform: Transform::from_translation(chunk_pos),
-                        ..default()
-                    },
-                )
-                // Ignore the result of spawn chunk.
-                // If the chunk already exists, an error is thrown and we can safely ignore it.
-                // If no error is returned, a new chunk is correctly created instead.
-                .ok();
-        }
+    // Pinned chunks are kept loaded regardless of anchors, so they are spawned
+    // as well, if they are missing.
+    for (world_id, pinned) in pinned.iter() {
+        let spatial = spatial_worlds.contains(world_id);
+        spawn_missing_chunks(&mut commands, world_id, pinned.iter_chunks(), spatial);
     }
 }
 
+/// Spawns all chunks at the given chunk coordinates within the given world,
+/// that do not exist yet.
+fn spawn_missing_chunks(
+    commands: &mut VoxelCommands,
+    world_id: Entity,
+    chunk_coords: impl Iterator<Item = IVec3>,
+    spatial: bool,
+) {
+    let Ok(mut world_commands) = commands.get_world(world_id) else {
+        return;
+    };
+
+    // Wrapped worlds may contain the same chunk multiple times within a
+    // single region, so only spawn each canonical chunk once.
+    let topology = world_commands.topology();
+    let chunks: HashSet<IVec3> = chunk_coords
+        .map(|c| topology.wrap_chunk_coords(c))
+        .filter(|c| world_commands.get_chunk_id(*c).is_none())
+        .collect();
+
+    // Logical worlds without a transform have no render presence, so their
+    // chunks are spawned without a spatial bundle.
+    if !spatial {
+        world_commands.spawn_chunks(chunks.into_iter().map(|chunk_coords| (chunk_coords, ())));
+        return;
+    }
+
+    // Chunks that already exist by the time the command is executed are
+    // skipped.
+    world_commands.spawn_chunks(chunks.into_iter().map(|chunk_coords| {
+        // The chunk transform is set by the core plugin once the chunk has
+        // been spawned.
+        (chunk_coords, SpatialBundle::default())
+    }));
+}
+
+/// Despawns chunks that are no longer within range of any world generation
+/// anchor.
+///
+/// Only a limited number of chunks are despawned each frame, starting with the
+/// chunks that are farthest from the nearest anchor within their world. Chunks
+/// that are still out of range are despawned on later frames, and chunks that
+/// come back into range before then are kept. Each despawned chunk has its
+/// pointer removed and a `ChunkDespawned` event sent within the same command.
+/// Chunks that are pinned by the [`PinnedChunks`] of their world are never
+/// despawned.
 pub(crate) fn unload_chunks(
+    settings: Res<ChunkUnloadSettings>,
+    anchors: Query<&ChunkAnchor<WorldGenAnchor>>,
     chunks: Query<(&ChunkAnchorRecipient<WorldGenAnchor>, &VoxelChunk)>,
+    pinned: Query<&PinnedChunks>,
     mut commands: VoxelCommands,
 ) {
+    let mut unloading: Vec<(OrderedFloat<f32>, Entity, IVec3)> = vec![];
     for (anchor_recipient, chunk_meta) in chunks.iter() {
-        if anchor_recipient.priority.is_none() {
-            let Ok(mut world_commands) = commands.get_world(chunk_meta.world_id()) else {
-                continue;
-            };
+        if anchor_recipient.priority.is_some() || is_chunk_pinned(&pinned, chunk_meta) {
+            continue;
+        }
 
-            let Ok(chunk_commands) = world_commands.get_chunk(chunk_meta.chunk_coords()) else {
-                continue;
-            };
+        let world_id = chunk_meta.world_id();
+        let Ok(world_commands) = commands.get_world(world_id) else {
+            continue;
+        };
 
-            chunk_commands.despawn();
-        }
+        let topology = world_commands.topology();
+        let chunk_coords = chunk_meta.chunk_coords();
+        let distance = anchors
+            .iter()
+            .filter(|anchor| anchor.world_id == world_id)
+            .filter_map(|anchor| anchor.coords)
+            .map(|coords| {
+                topology
+                    .chunk_delta(coords, chunk_coords)
+                    .as_vec3()
+                    .length()
+            })
+            .min_by(f32::total_cmp)
+            .unwrap_or(f32::INFINITY);
+
+        unloading.push((OrderedFloat(distance), world_id, chunk_coords));
+    }
+
+    let max_despawns = settings.max_despawns_per_frame;
+    if max_despawns > 0 && unloading.len() > max_despawns {
+        unloading.sort_unstable_by_key(|(distance, ..)| Reverse(*distance));
+        unloading.truncate(max_despawns);
+    }
+
+    for (_, world_id, chunk_coords) in unloading {
+        let Ok(mut world_commands) = commands.get_world(world_id) else {
+            continue;
+        };
+
+        let Ok(chunk_commands) = world_commands.get_chunk(chunk_coords) else {
+            continue;
+        };
+
+        chunk_commands.despawn();
     }
 }
 
@@ -71,6 +178,8 @@ pub(crate) fn queue_chunks<T>(
             Without<VoxelStorage<T>>,
             Without<PendingLoadChunkTask>,
             Without<LoadChunkTask<T>>,
+            Without<ChunkGenFailed>,
+            Without<ChunkGenRetry>,
         ),
     >,
     mut commands: Commands,
@@ -82,73 +191,272 @@ pub(crate) fn queue_chunks<T>(
     }
 }
 
+/// Queues chunks that failed to generate for another attempt, once their
+/// backoff delay has passed.
+pub(crate) fn retry_failed_chunks<T>(
+    time: Res<Time>,
+    chunks: Query<
+        (Entity, &ChunkGenRetry),
+        (
+            Without<VoxelStorage<T>>,
+            Without<PendingLoadChunkTask>,
+            Without<LoadChunkTask<T>>,
+        ),
+    >,
+    mut commands: Commands,
+) where
+    T: BlockData,
+{
+    let now = time.elapsed_seconds();
+    for (chunk_id, retry) in chunks.iter() {
+        if now >= retry.retry_at {
+            commands.entity(chunk_id).insert(PendingLoadChunkTask);
+        }
+    }
+}
+
 /// Moves queued chunk loading tasks to an active async chunk loading task.
+///
+/// If the world of a chunk has a [`PersistentWorld`] chunk store and the
+/// [`PersistentChunkLoader`] resource exists, the task first tries to read the
+/// chunk from the store, and only falls back to the world generator if the
+/// chunk has not been saved. Chunks in worlds without a chunk store or world
+/// generator are filled with empty storage right away.
+///
+/// The number of tasks that may exist at once is determined by the
+/// [`WorldGenBudget`]. If the [`WorldGenTaskMode`] is not async, the chunks are
+/// generated right away instead.
+///
+/// Chunks that are pinned by the [`PinnedChunks`] of their world, but are not
+/// within range of any anchor, are loaded after all other chunks.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn push_chunk_async_queue<T>(
+    mode: Res<WorldGenTaskMode>,
+    budget: Res<WorldGenBudget>,
+    loader: Option<Res<PersistentChunkLoader<T>>>,
     active_tasks: Query<(Entity, &LoadChunkTask<T>)>,
     chunks: Query<
         (&ChunkAnchorRecipient<WorldGenAnchor>, &VoxelChunk, Entity),
         With<PendingLoadChunkTask>,
     >,
-    generators: Query<&WorldGeneratorHandler<T>, With<VoxelWorld>>,
+    pinned: Query<&PinnedChunks>,
+    generators: Query<
+        (
+            Option<&WorldGeneratorHandler<T>>,
+            Option<&WorldGeneratorZones<T>>,
+            Option<&PersistentWorld>,
+        ),
+        With<VoxelWorld>,
+    >,
     mut commands: Commands,
 ) where
     T: BlockData,
 {
-    // TODO Move this value to a resource.
-    /// The maximum number of async world generations tasks that can exist at
-    /// once.
-    const MAX_TASKS: i32 = 3;
-
-    let available_slots = MAX_TASKS - active_tasks.iter().len() as i32;
-    if available_slots <= 0 {
+    let available_slots = budget.max_tasks().saturating_sub(active_tasks.iter().len());
+    if available_slots == 0 {
         return;
     }
 
-    let pool = AsyncComputeTaskPool::get();
-    for (chunk_coords, chunk_id, world_id) in get_max_chunks(&chunks, available_slots as usize) {
-        match generators.get(world_id).ok().map(|g| g.generator()) {
-            Some(gen) => {
-                let task = pool.spawn(async move { gen.generate_chunk(chunk_coords) });
-                commands
-                    .entity(chunk_id)
-                    .remove::<PendingLoadChunkTask>()
-                    .insert(LoadChunkTask(task));
+    for (chunk_coords, chunk_id, world_id) in get_max_chunks(&chunks, &pinned, available_slots) {
+        let (generator, persistent) = match generators.get(world_id) {
+            Ok((handler, zones, persistent)) => {
+                let generator = zones
+                    .and_then(|zones| zones.generator_for(chunk_coords))
+                    .or_else(|| handler.map(|handler| handler.generator()));
+                let persistent = persistent
+                    .zip(loader.as_ref())
+                    .map(|(persistent, loader)| (persistent.clone(), loader.load));
+                (generator, persistent)
             },
+            Err(_) => (None, None),
+        };
 
-            None => {
-                commands
-                    .entity(chunk_id)
-                    .remove::<PendingLoadChunkTask>()
-                    .insert(VoxelStorage::<T>::default());
+        if generator.is_none() && persistent.is_none() {
+            commands
+                .entity(chunk_id)
+                .remove::<PendingLoadChunkTask>()
+                .insert(VoxelStorage::<T>::default());
+            continue;
+        }
+
+        let load = move || {
+            let start = Instant::now();
+            let (result, source) = load_or_generate(chunk_coords, persistent, generator);
+            (result, source, start.elapsed())
+        };
+
+        let task = match *mode {
+            WorldGenTaskMode::Async => {
+                let pool = AsyncComputeTaskPool::get();
+                ChunkGenTask::Async(pool.spawn(async move { load() }))
+            },
+            WorldGenTaskMode::Immediate => {
+                ChunkGenTask::Ready {
+                    result:            Some(load()),
+                    remaining_updates: 0,
+                }
+            },
+            WorldGenTaskMode::Deferred(updates) => {
+                ChunkGenTask::Ready {
+                    result:            Some(load()),
+                    remaining_updates: updates,
+                }
             },
         };
+
+        commands
+            .entity(chunk_id)
+            .remove::<PendingLoadChunkTask>()
+            .insert(LoadChunkTask(task));
+    }
+}
+
+/// Reads the chunk at the given chunk coordinates from the given persistent
+/// world, if any, or generates it using the given world generator if the chunk
+/// has not been saved. Chunks that are neither saved nor generated are left
+/// empty.
+///
+/// Panics within the world generator are caught and returned as errors.
+fn load_or_generate<T>(
+    chunk_coords: IVec3,
+    persistent: Option<(PersistentWorld, ChunkReader<T>)>,
+    generator: Option<Arc<dyn WorldGenerator<T>>>,
+) -> (Result<VoxelStorage<T>, WorldGenError>, ChunkLoadSource)
+where
+    T: BlockData,
+{
+    if let Some((persistent, load)) = persistent {
+        match load(&persistent, chunk_coords) {
+            Ok(Some(storage)) => return (Ok(storage), ChunkLoadSource::Store),
+            Ok(None) => {},
+            Err(err) => {
+                let error = WorldGenError::LoadFailed(err.to_string());
+                return (Err(error), ChunkLoadSource::Store);
+            },
+        }
     }
+
+    let Some(generator) = generator else {
+        return (Ok(VoxelStorage::default()), ChunkLoadSource::Generator);
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| generator.generate_chunk(chunk_coords)))
+        .unwrap_or_else(|payload| Err(WorldGenError::Panicked(panic_message(payload))));
+    (result, ChunkLoadSource::Generator)
 }
 
 /// This system takes in all active async chunk loading tasks and, for each one
 /// that is finished, push the results to the target voxel chunk.
+///
+/// Chunks that failed to generate are scheduled for a retry, or marked as
+/// failed once the [`WorldGenRetryPolicy`] does not allow any more attempts.
 pub(crate) fn finish_chunk_loading<T: BlockData>(
-    mut load_chunk_tasks: Query<(Entity, &mut LoadChunkTask<T>, &VoxelChunk)>,
+    time: Res<Time>,
+    retry_policy: Res<WorldGenRetryPolicy>,
+    mut load_chunk_tasks: Query<(
+        Entity,
+        &mut LoadChunkTask<T>,
+        &VoxelChunk,
+        Option<&ChunkGenRetry>,
+    )>,
+    mut budget: ResMut<WorldGenBudget>,
+    mut failed_events: EventWriter<ChunkGenFailedEvent>,
+    mut stats: Option<ResMut<Bones3StatsCollector>>,
     mut commands: VoxelCommands,
 ) {
-    for (chunk_id, mut task, chunk_meta) in load_chunk_tasks.iter_mut() {
-        let Some(chunk_data) = future::block_on(future::poll_once(&mut task.0)) else {
+    for (chunk_id, mut task, chunk_meta, retry) in load_chunk_tasks.iter_mut() {
+        let result = match &mut task.0 {
+            ChunkGenTask::Async(task) => future::block_on(future::poll_once(task)),
+            ChunkGenTask::Ready {
+                result,
+                remaining_updates,
+            } => {
+                if *remaining_updates > 0 {
+                    *remaining_updates -= 1;
+                    continue;
+                }
+
+                result.take()
+            },
+        };
+
+        let Some((result, source, duration)) = result else {
             continue;
         };
 
         let mut c = commands.commands().entity(chunk_id);
-        c.remove::<LoadChunkTask<T>>().insert(chunk_data);
+        c.remove::<LoadChunkTask<T>>();
 
-        #[cfg(feature = "meshing")]
-        {
-            c.insert(RemeshChunk);
-            commands
-                .get_world(chunk_meta.world_id())
-                .unwrap()
-                .get_chunk(chunk_meta.chunk_coords())
-                .unwrap()
-                .remesh_chunk_neighbors();
+        let chunk_data = match result {
+            Ok(chunk_data) => chunk_data,
+            Err(error) => {
+                let attempts = retry.map_or(0, |r| r.attempts) + 1;
+                let backoff = retry_policy.backoff(attempts);
+
+                match backoff {
+                    Some(delay) => {
+                        c.insert(ChunkGenRetry {
+                            attempts,
+                            retry_at: time.elapsed_seconds() + delay,
+                        });
+                    },
+                    None => {
+                        c.remove::<ChunkGenRetry>().insert(ChunkGenFailed);
+                    },
+                }
+
+                failed_events.send(ChunkGenFailedEvent {
+                    world_id: chunk_meta.world_id(),
+                    chunk_coords: chunk_meta.chunk_coords(),
+                    attempts,
+                    error,
+                    will_retry: backoff.is_some(),
+                });
+                continue;
+            },
+        };
+
+        c.remove::<ChunkGenRetry>();
+        budget.record(duration);
+
+        match source {
+            ChunkLoadSource::Store => {
+                c.insert(ChunkLoadedFromStore);
+            },
+            ChunkLoadSource::Generator => {
+                c.insert(ChunkGenTime(duration));
+
+                if let Some(stats) = stats.as_mut() {
+                    stats.record_chunk_generated(duration);
+                }
+            },
         }
+
+        #[cfg(feature = "meshing")]
+        c.insert(RemeshChunk);
+
+        let Ok(mut world) = commands.get_world(chunk_meta.world_id()) else {
+            continue;
+        };
+
+        let Ok(mut chunk) = world.get_chunk(chunk_meta.chunk_coords()) else {
+            continue;
+        };
+        chunk.replace_storage(chunk_data);
+
+        #[cfg(feature = "meshing")]
+        chunk.remesh_chunk_neighbors();
+    }
+}
+
+/// Gets the message of a caught panic, if it has one.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
     }
 }
 
@@ -157,13 +465,16 @@ fn get_max_chunks(
         (&ChunkAnchorRecipient<WorldGenAnchor>, &VoxelChunk, Entity),
         With<PendingLoadChunkTask>,
     >,
+    pinned: &Query<&PinnedChunks>,
     max_chunks: usize,
 ) -> impl Iterator<Item = (IVec3, Entity, Entity)> {
     let mut queue = PriorityQueue::new();
 
     for (anchor_recipient, chunk_meta, chunk_id) in chunks.iter() {
-        let Some(priority) = anchor_recipient.priority else {
-            continue;
+        let priority = match anchor_recipient.priority {
+            Some(priority) => priority,
+            None if is_chunk_pinned(pinned, chunk_meta) => f32::MIN,
+            None => continue,
         };
 
         queue.push(
@@ -174,3 +485,323 @@ fn get_max_chunks(
 
     queue.into_sorted_iter().take(max_chunks).map(|(e, _)| e)
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::ecs::event::ManualEventReader;
+    use bevy::ecs::query::ReadOnlyWorldQuery;
+    use bones3_core::prelude::*;
+    use bones3_core::util::anchor::LogicalAnchorPosition;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::fill;
+    use crate::{Bones3WorldGenPlugin, WorldGenPersistencePlugin};
+
+    /// Fails to generate the chunk at the origin, panics while generating the
+    /// chunk at `x = 1`, and generates all other chunks.
+    struct Unreliable;
+
+    impl WorldGenerator<u8> for Unreliable {
+        fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<u8>, WorldGenError> {
+            match chunk_coords.x {
+                0 => Err(WorldGenError::new("out of stone")),
+                1 => panic!("generator exploded"),
+                _ => Ok(VoxelStorage::default()),
+            }
+        }
+    }
+
+    fn count_chunks<F: ReadOnlyWorldQuery>(app: &mut App) -> usize {
+        app.world
+            .query_filtered::<(), (With<VoxelChunk>, F)>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn generate_chunks_synchronously() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            Bones3WorldGenPlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(WorldGenTaskMode::Deferred(2));
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                WorldGeneratorHandler::from(fill(1)),
+            ))
+            .id();
+        app.world.spawn((
+            ChunkAnchor::<WorldGenAnchor>::new(world_id, UVec3::ZERO),
+            LogicalAnchorPosition(Vec3::ZERO),
+        ));
+
+        for _ in 0 .. 10 {
+            app.update();
+            if count_chunks::<With<LoadChunkTask<u8>>>(&mut app) > 0 {
+                break;
+            }
+        }
+
+        for _ in 0 .. 2 {
+            assert_eq!(count_chunks::<With<LoadChunkTask<u8>>>(&mut app), 1);
+            assert_eq!(count_chunks::<With<VoxelStorage<u8>>>(&mut app), 0);
+            app.update();
+        }
+        assert_eq!(count_chunks::<With<VoxelStorage<u8>>>(&mut app), 1);
+
+        app.insert_resource(WorldGenTaskMode::Immediate);
+        app.world
+            .query::<&mut ChunkAnchor<WorldGenAnchor>>()
+            .single_mut(&mut app.world)
+            .radius = UVec3::ONE;
+
+        // Only a few chunks are generated each update.
+        for _ in 0 .. 20 {
+            app.update();
+            assert_eq!(count_chunks::<With<LoadChunkTask<u8>>>(&mut app), 0);
+        }
+        assert_eq!(count_chunks::<With<VoxelStorage<u8>>>(&mut app), 27);
+        assert_eq!(count_chunks::<With<ChunkGenTime>>(&mut app), 27);
+        assert_eq!(
+            app.world.resource::<WorldGenBudget>().timings().samples(),
+            27
+        );
+    }
+
+    #[test]
+    fn retry_and_fail_broken_chunks() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            Bones3WorldGenPlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(WorldGenTaskMode::Immediate)
+        .insert_resource(WorldGenRetryPolicy {
+            max_attempts:       2,
+            initial_backoff:    0.0,
+            backoff_multiplier: 1.0,
+        });
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                WorldGeneratorHandler::from(Unreliable),
+            ))
+            .id();
+        app.world.spawn((
+            ChunkAnchor::<WorldGenAnchor>::new(world_id, UVec3::new(1, 0, 0)),
+            LogicalAnchorPosition(Vec3::ZERO),
+        ));
+
+        let mut reader = ManualEventReader::<ChunkGenFailedEvent>::default();
+        let mut failures = vec![];
+        for _ in 0 .. 10 {
+            app.update();
+            let events = app.world.resource::<Events<ChunkGenFailedEvent>>();
+            failures.extend(reader.iter(events).map(|event| {
+                let panicked = matches!(event.error, WorldGenError::Panicked(_));
+                (
+                    event.chunk_coords.x,
+                    event.attempts,
+                    event.will_retry,
+                    panicked,
+                )
+            }));
+        }
+
+        failures.sort();
+        assert_eq!(failures, vec![
+            (0, 1, true, false),
+            (0, 2, false, false),
+            (1, 1, true, true),
+            (1, 2, false, true),
+        ]);
+
+        let mut failed: Vec<_> = app
+            .world
+            .query_filtered::<&VoxelChunk, With<ChunkGenFailed>>()
+            .iter(&app.world)
+            .map(|chunk_meta| chunk_meta.chunk_coords().x)
+            .collect();
+        failed.sort();
+        assert_eq!(failed, vec![0, 1]);
+        assert_eq!(count_chunks::<With<ChunkGenRetry>>(&mut app), 0);
+        assert_eq!(count_chunks::<With<VoxelStorage<u8>>>(&mut app), 1);
+    }
+
+    #[test]
+    fn load_saved_chunks_before_generating() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            Bones3WorldGenPlugin::<u8>::default(),
+            WorldGenPersistencePlugin::<u8>::default(),
+            PersistencePlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(WorldGenTaskMode::Immediate);
+
+        let persistent = PersistentWorld::new(MemoryChunkStore::default());
+        let mut saved = VoxelStorage::default();
+        saved.set_block(IVec3::ZERO, 5);
+        persistent.save_chunk(IVec3::ZERO, &saved).unwrap();
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                WorldGeneratorHandler::from(fill(1)),
+                persistent,
+            ))
+            .id();
+        app.world.spawn((
+            ChunkAnchor::<WorldGenAnchor>::new(world_id, UVec3::new(1, 0, 0)),
+            LogicalAnchorPosition(Vec3::ZERO),
+        ));
+
+        for _ in 0 .. 5 {
+            app.update();
+        }
+
+        let mut blocks: Vec<(IVec3, u8, bool)> = app
+            .world
+            .query_filtered::<(&VoxelChunk, &VoxelStorage<u8>, Option<&DirtyChunk>), ()>()
+            .iter(&app.world)
+            .map(|(meta, storage, dirty)| {
+                let block = storage.get_block(IVec3::ZERO);
+                (meta.chunk_coords(), block, dirty.is_some())
+            })
+            .collect();
+        blocks.sort_by_key(|(coords, ..)| coords.to_array());
+
+        assert_eq!(blocks, vec![
+            (IVec3::new(-1, 0, 0), 1, false),
+            (IVec3::new(0, 0, 0), 5, false),
+            (IVec3::new(1, 0, 0), 1, false),
+        ]);
+        assert_eq!(count_chunks::<With<ChunkGenTime>>(&mut app), 2);
+    }
+
+    #[test]
+    fn unload_chunks_behind_moving_anchor() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            Bones3WorldGenPlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(WorldGenTaskMode::Immediate)
+        .insert_resource(ChunkUnloadSettings {
+            max_despawns_per_frame: 1,
+        });
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                WorldGeneratorHandler::from(fill(1)),
+            ))
+            .id();
+        let anchor_id = app
+            .world
+            .spawn((
+                ChunkAnchor::<WorldGenAnchor>::new(world_id, UVec3::new(1, 0, 0)),
+                LogicalAnchorPosition(Vec3::ZERO),
+            ))
+            .id();
+
+        for _ in 0 .. 5 {
+            app.update();
+        }
+
+        let old_chunks = |app: &mut App| {
+            let mut loaded: Vec<i32> = app
+                .world
+                .query::<&VoxelChunk>()
+                .iter(&app.world)
+                .map(|meta| meta.chunk_coords().x)
+                .filter(|x| *x <= 1)
+                .collect();
+            loaded.sort();
+            loaded
+        };
+        assert_eq!(old_chunks(&mut app), vec![-1, 0, 1]);
+
+        app.world
+            .get_mut::<LogicalAnchorPosition>(anchor_id)
+            .unwrap()
+            .0 = Vec3::new(160.0, 0.0, 0.0);
+
+        // The farthest chunk is unloaded first.
+        for _ in 0 .. 5 {
+            app.update();
+            if old_chunks(&mut app).len() < 3 {
+                break;
+            }
+        }
+        assert_eq!(old_chunks(&mut app), vec![0, 1]);
+
+        // A limit of zero unloads all remaining chunks at once.
+        app.insert_resource(ChunkUnloadSettings {
+            max_despawns_per_frame: 0,
+        });
+        app.update();
+        assert_eq!(old_chunks(&mut app), vec![]);
+    }
+
+    #[test]
+    fn keep_pinned_chunks_loaded() {
+        let mut app = App::new();
+        app.add_plugins((
+            Bones3CorePlugin::<u8>::default(),
+            Bones3WorldGenPlugin::<u8>::default(),
+        ))
+        .init_resource::<Time>()
+        .insert_resource(WorldGenTaskMode::Immediate);
+
+        let spawn = Region::from_points(IVec3::new(5, 0, 0), IVec3::new(6, 0, 0));
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                WorldGeneratorHandler::from(fill(1)),
+                PinnedChunks::default().with_region(spawn),
+            ))
+            .id();
+        let anchor_id = app
+            .world
+            .spawn((
+                ChunkAnchor::<WorldGenAnchor>::new(world_id, UVec3::ZERO),
+                LogicalAnchorPosition(Vec3::ZERO),
+            ))
+            .id();
+
+        for _ in 0 .. 5 {
+            app.update();
+        }
+        assert_eq!(count_chunks::<With<VoxelStorage<u8>>>(&mut app), 3);
+
+        // Only the anchored chunk is unloaded once the anchor is removed.
+        app.world.despawn(anchor_id);
+        for _ in 0 .. 5 {
+            app.update();
+        }
+
+        let mut loaded: Vec<IVec3> = app
+            .world
+            .query::<&VoxelChunk>()
+            .iter(&app.world)
+            .map(|meta| meta.chunk_coords())
+            .collect();
+        loaded.sort_by_key(|coords| coords.to_array());
+        assert_eq!(loaded, vec![IVec3::new(5, 0, 0), IVec3::new(6, 0, 0)]);
+    }
+}
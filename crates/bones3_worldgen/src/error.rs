@@ -0,0 +1,28 @@
+//! Errors that can be triggered while generating chunks.
+
+use thiserror::Error;
+
+/// An error type that is returned when a world generator fails to generate a
+/// chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WorldGenError {
+    /// Thrown by a world generator that could not generate the chunk.
+    #[error("Failed to generate chunk: {0}")]
+    Failed(String),
+
+    /// Thrown when a world generator panicked while generating the chunk.
+    #[error("World generator panicked: {0}")]
+    Panicked(String),
+
+    /// Thrown when a saved chunk could not be read from the chunk store of
+    /// its world.
+    #[error("Failed to load chunk: {0}")]
+    LoadFailed(String),
+}
+
+impl WorldGenError {
+    /// Creates a new world generation error with the given message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self::Failed(message.into())
+    }
+}
@@ -0,0 +1,280 @@
+//! Built-in world generators for prototyping, examples, and tests.
+
+use bevy::prelude::*;
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, VoxelStorage};
+
+use crate::ecs::components::WorldGenerator;
+use crate::error::WorldGenError;
+
+/// Fills every block within the given chunk using the given function, which
+/// receives the block coordinates of each block.
+fn fill_chunk<T, F>(chunk_coords: IVec3, mut block_at: F) -> VoxelStorage<T>
+where
+    T: BlockData,
+    F: FnMut(IVec3) -> T,
+{
+    let mut storage = VoxelStorage::default();
+    for block_coords in Region::CHUNK.shift(chunk_coords * 16).iter() {
+        storage.set_block(block_coords, block_at(block_coords));
+    }
+    storage
+}
+
+/// A world generator that creates a flat world out of a stack of horizontal
+/// block layers.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_worldgen::generators::FlatWorldGenerator;
+/// # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+/// # enum BlockState {
+/// #     #[default]
+/// #     Air,
+/// #     Bedrock,
+/// #     Stone,
+/// #     Grass,
+/// # }
+/// let generator = FlatWorldGenerator::new(vec![
+///     (1, BlockState::Bedrock),
+///     (3, BlockState::Stone),
+///     (1, BlockState::Grass),
+/// ]);
+///
+/// assert_eq!(generator.block_at(2), BlockState::Stone);
+/// assert_eq!(generator.surface_height(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatWorldGenerator<T>
+where
+    T: BlockData,
+{
+    /// The thickness and block of each layer, from bottom to top.
+    layers: Vec<(u32, T)>,
+
+    /// The y coordinate of the bottom of the lowest layer.
+    base_height: i32,
+}
+
+impl<T> FlatWorldGenerator<T>
+where
+    T: BlockData,
+{
+    /// Creates a new flat world generator with the given layers, listed from
+    /// bottom to top as pairs of layer thickness and block. The lowest layer
+    /// starts at `y = 0`.
+    pub fn new(layers: Vec<(u32, T)>) -> Self {
+        Self {
+            layers,
+            base_height: 0,
+        }
+    }
+
+    /// Sets the y coordinate of the bottom of the lowest layer.
+    pub fn with_base_height(mut self, base_height: i32) -> Self {
+        self.base_height = base_height;
+        self
+    }
+
+    /// Gets the y coordinate just above the top layer.
+    pub fn surface_height(&self) -> i32 {
+        self.base_height + self.layers.iter().map(|(t, _)| *t as i32).sum::<i32>()
+    }
+
+    /// Gets the block at the given height.
+    pub fn block_at(&self, y: i32) -> T {
+        let mut bottom = self.base_height;
+        for (thickness, block) in self.layers.iter() {
+            let top = bottom + *thickness as i32;
+            if y >= bottom && y < top {
+                return *block;
+            }
+            bottom = top;
+        }
+
+        T::default()
+    }
+}
+
+impl<T> WorldGenerator<T> for FlatWorldGenerator<T>
+where
+    T: BlockData,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        let min_y = chunk_coords.y * 16;
+        if min_y + 16 <= self.base_height || min_y >= self.surface_height() {
+            return Ok(VoxelStorage::default());
+        }
+
+        Ok(fill_chunk(chunk_coords, |block_coords| {
+            self.block_at(block_coords.y)
+        }))
+    }
+}
+
+/// A world generator that creates simple debugging patterns, useful for
+/// checking block models, chunk borders, and mesher output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugPatternGenerator<T>
+where
+    T: BlockData,
+{
+    /// A single layer floor at `y = -1`, made of alternating square tiles of
+    /// two blocks with the given tile size.
+    Checkerboard {
+        /// The first tile block.
+        a: T,
+
+        /// The second tile block.
+        b: T,
+
+        /// The width of each tile, in blocks.
+        size: u32,
+    },
+
+    /// Lines of blocks that run from the origin along the positive X, Y, and
+    /// Z axes, with the given length.
+    AxisMarkers {
+        /// The block of the X axis line.
+        x: T,
+
+        /// The block of the Y axis line.
+        y: T,
+
+        /// The block of the Z axis line.
+        z: T,
+
+        /// The length of each line, in blocks.
+        length: u32,
+    },
+
+    /// Every given block placed within a square grid on the `y = 0` plane,
+    /// starting from the origin, with the given spacing between blocks.
+    BlockGrid {
+        /// The blocks to place, in order.
+        blocks: Vec<T>,
+
+        /// The distance between neighboring blocks within the grid.
+        spacing: u32,
+    },
+}
+
+impl<T> DebugPatternGenerator<T>
+where
+    T: BlockData,
+{
+    /// Gets the block at the given block coordinates.
+    pub fn block_at(&self, block_coords: IVec3) -> T {
+        match self {
+            DebugPatternGenerator::Checkerboard {
+                a,
+                b,
+                size,
+            } => {
+                if block_coords.y != -1 {
+                    return T::default();
+                }
+
+                let size = (*size).max(1) as i32;
+                let tile_x = block_coords.x.div_euclid(size);
+                let tile_z = block_coords.z.div_euclid(size);
+                match (tile_x + tile_z).rem_euclid(2) {
+                    0 => *a,
+                    _ => *b,
+                }
+            },
+
+            DebugPatternGenerator::AxisMarkers {
+                x,
+                y,
+                z,
+                length,
+            } => {
+                let length = *length as i32;
+                match block_coords.to_array() {
+                    [0, 0, 0] => *y,
+                    [v, 0, 0] if v > 0 && v <= length => *x,
+                    [0, v, 0] if v > 0 && v <= length => *y,
+                    [0, 0, v] if v > 0 && v <= length => *z,
+                    _ => T::default(),
+                }
+            },
+
+            DebugPatternGenerator::BlockGrid {
+                blocks,
+                spacing,
+            } => {
+                let spacing = (*spacing).max(1) as i32;
+                if block_coords.y != 0 || block_coords.x < 0 || block_coords.z < 0 {
+                    return T::default();
+                }
+
+                if block_coords.x % spacing != 0 || block_coords.z % spacing != 0 {
+                    return T::default();
+                }
+
+                let side = (blocks.len() as f32).sqrt().ceil() as i32;
+                let column = block_coords.x / spacing;
+                let row = block_coords.z / spacing;
+                if column >= side {
+                    return T::default();
+                }
+
+                blocks
+                    .get((row * side + column) as usize)
+                    .copied()
+                    .unwrap_or_default()
+            },
+        }
+    }
+}
+
+impl<T> WorldGenerator<T> for DebugPatternGenerator<T>
+where
+    T: BlockData,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        Ok(fill_chunk(chunk_coords, |block_coords| {
+            self.block_at(block_coords)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn flat_layers_and_patterns() {
+        let flat = FlatWorldGenerator::new(vec![(1, 1u8), (3, 2), (1, 3)]).with_base_height(-5);
+        assert_eq!(flat.surface_height(), 0);
+
+        let chunk = flat.generate_chunk(IVec3::NEG_Y).unwrap();
+        let column: Vec<_> = (-6 .. 1)
+            .map(|y| chunk.get_block(IVec3::new(0, y, 0)))
+            .collect();
+        assert_eq!(column, vec![0, 1, 2, 2, 2, 3, 0]);
+
+        let checkers = DebugPatternGenerator::Checkerboard {
+            a:    1u8,
+            b:    2,
+            size: 2,
+        };
+        assert_eq!(checkers.block_at(IVec3::new(1, -1, 1)), 1);
+        assert_eq!(checkers.block_at(IVec3::new(2, -1, 1)), 2);
+        assert_eq!(checkers.block_at(IVec3::new(-1, -1, 1)), 2);
+        assert_eq!(checkers.block_at(IVec3::new(1, 0, 1)), 0);
+
+        let grid = DebugPatternGenerator::BlockGrid {
+            blocks:  vec![1u8, 2, 3, 4, 5],
+            spacing: 2,
+        };
+        assert_eq!(grid.block_at(IVec3::new(0, 0, 0)), 1);
+        assert_eq!(grid.block_at(IVec3::new(4, 0, 0)), 3);
+        assert_eq!(grid.block_at(IVec3::new(2, 0, 2)), 5);
+        assert_eq!(grid.block_at(IVec3::new(6, 0, 0)), 0);
+        assert_eq!(grid.block_at(IVec3::new(1, 0, 0)), 0);
+    }
+}
@@ -12,12 +12,23 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
+use bones3_core::persistence::PersistentBlock;
 use bones3_core::storage::BlockData;
 use bones3_core::util::anchor::{ChunkAnchorPlugin, ChunkAnchorSet};
+use bones3_core::Bones3CoreSet;
 
-use crate::ecs::{components, systems};
+use crate::ecs::{components, resources, systems};
 
+pub mod combinators;
 pub mod ecs;
+pub mod error;
+pub mod generators;
+pub mod pipeline;
+pub mod preview;
+pub mod recording;
+
+#[cfg(test)]
+mod test_util;
 
 #[derive(Default)]
 pub struct Bones3WorldGenPlugin<T>
@@ -34,14 +45,37 @@ where
 {
     fn build(&self, app: &mut App) {
         app.register_type::<components::WorldGeneratorHandler<T>>()
+            .register_type::<components::WorldGeneratorZones<T>>()
             .register_type::<components::LoadChunkTask<T>>()
             .register_type::<components::PendingLoadChunkTask>()
+            .register_type::<components::ChunkGenFailed>()
+            .register_type::<components::ChunkGenRetry>()
+            .register_type::<components::ChunkGenTime>()
+            .register_type::<resources::ChunkUnloadSettings>()
+            .register_type::<resources::WorldGenBudget>()
+            .register_type::<resources::WorldGenRetryPolicy>()
+            .register_type::<resources::WorldGenTaskMode>()
+            .init_resource::<resources::ChunkUnloadSettings>()
+            .init_resource::<resources::WorldGenBudget>()
+            .init_resource::<resources::WorldGenRetryPolicy>()
+            .init_resource::<resources::WorldGenTaskMode>()
+            .add_event::<resources::ChunkGenFailedEvent>()
             .add_plugins(ChunkAnchorPlugin::<WorldGenAnchor>::default())
             .add_systems(
                 Update,
                 (
-                    systems::queue_chunks::<T>.in_set(WorldGenSet::QueueChunks),
+                    (
+                        systems::queue_chunks::<T>,
+                        systems::retry_failed_chunks::<T>,
+                    )
+                        .in_set(WorldGenSet::QueueChunks),
+                    apply_deferred
+                        .after(WorldGenSet::QueueChunks)
+                        .before(WorldGenSet::StartAsyncTask),
                     systems::push_chunk_async_queue::<T>.in_set(WorldGenSet::StartAsyncTask),
+                    apply_deferred
+                        .after(WorldGenSet::StartAsyncTask)
+                        .before(WorldGenSet::FinishAsyncTask),
                     systems::finish_chunk_loading::<T>.in_set(WorldGenSet::FinishAsyncTask),
                 ),
             )
@@ -52,6 +86,15 @@ where
                     systems::unload_chunks.in_set(WorldGenSet::UnloadChunks),
                 ),
             )
+            .configure_sets(
+                Update,
+                (
+                    WorldGenSet::QueueChunks,
+                    WorldGenSet::StartAsyncTask,
+                    WorldGenSet::FinishAsyncTask,
+                )
+                    .chain(),
+            )
             .configure_set(
                 PostUpdate,
                 WorldGenSet::CreateChunks.after(ChunkAnchorSet::UpdateCoords),
@@ -59,16 +102,72 @@ where
             .configure_set(
                 PostUpdate,
                 WorldGenSet::UnloadChunks.after(ChunkAnchorSet::UpdatePriorities),
+            )
+            .configure_set(
+                PostUpdate,
+                WorldGenSet::CreateChunks.before(Bones3CoreSet::FlushBlockWrites),
+            )
+            .configure_set(
+                PostUpdate,
+                WorldGenSet::UnloadChunks.before(Bones3CoreSet::FlushBlockWrites),
             );
     }
 }
 
+/// A plugin that reads chunks of the given block data type from the chunk store
+/// of their world before generating them.
+///
+/// With this plugin, chunks within worlds that have a
+/// [`PersistentWorld`](bones3_core::persistence::PersistentWorld) component
+/// are first read from the chunk store within their async loading task, and
+/// are only generated if they have not been saved before. Both paths share the
+/// same task budget, priority ordering, and retry policy. Chunks that are
+/// loaded from the store are not marked as dirty by the persistence plugin.
+///
+/// This plugin should be added alongside the [`Bones3WorldGenPlugin`].
+#[derive(Default)]
+pub struct WorldGenPersistencePlugin<T>
+where
+    T: PersistentBlock,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+}
+
+impl<T> Plugin for WorldGenPersistencePlugin<T>
+where
+    T: PersistentBlock,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<resources::PersistentChunkLoader<T>>();
+    }
+}
+
+/// The system sets that are used for world generation.
+///
+/// The chunk queueing and async task sets run within the `Update` schedule, in
+/// that order and with commands applied between each of them, while chunk
+/// creation and unloading run within the `PostUpdate` schedule.
+/// Chunks that are created or unloaded are flushed along with all other block
+/// writes in [`Bones3CoreSet::FlushBlockWrites`].
 #[derive(Debug, SystemSet, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum WorldGenSet {
+    /// Spawns chunk entities for all chunks that have entered the range of a
+    /// world generation anchor.
     CreateChunks,
+
+    /// Despawns chunks that are no longer within range of any world
+    /// generation anchor, farthest first, up to the limit set by
+    /// [`ChunkUnloadSettings`](crate::ecs::resources::ChunkUnloadSettings).
     UnloadChunks,
+
+    /// Queues chunks that are waiting to be generated.
     QueueChunks,
+
+    /// Starts the async generation tasks for queued chunks.
     StartAsyncTask,
+
+    /// Applies the results of all finished chunk generation tasks.
     FinishAsyncTask,
 }
 
@@ -0,0 +1,348 @@
+//! A generation stage that carves caves into the terrain.
+
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, UpAxis};
+
+use super::{ChunkRng, GenContext, GenStage, GradientNoise};
+
+/// The settings for large, open caverns that are carved wherever a 3D noise
+/// function exceeds a threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CavernSettings {
+    /// The frequency of the cavern noise. Smaller values create larger
+    /// caverns.
+    pub frequency: f32,
+
+    /// The number of noise octaves to sample.
+    pub octaves: u32,
+
+    /// The noise value above which blocks are carved, between `-1.0` and
+    /// `1.0`. Larger values create fewer caverns.
+    pub threshold: f32,
+
+    /// The range of block heights along the up axis of the world in which
+    /// caverns may be carved, inclusive.
+    pub height_range: (i32, i32),
+}
+
+impl Default for CavernSettings {
+    fn default() -> Self {
+        Self {
+            frequency:    0.02,
+            octaves:      2,
+            threshold:    0.35,
+            height_range: (i32::MIN, 0),
+        }
+    }
+}
+
+/// The settings for long, winding tunnels that are carved by "worms" that
+/// travel through the terrain, steered by a 3D noise function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WormSettings {
+    /// The chance for each chunk to be the starting point of a worm, between
+    /// `0.0` and `1.0`.
+    pub chance: f32,
+
+    /// The number of steps that each worm travels.
+    pub length: u32,
+
+    /// The distance that a worm travels with each step, in blocks.
+    pub step: f32,
+
+    /// The minimum and maximum radius of the tunnels, in blocks.
+    pub radius: (f32, f32),
+
+    /// How strongly the noise function steers each worm.
+    pub turn_strength: f32,
+
+    /// The range of block heights along the up axis of the world in which
+    /// worms may start, inclusive.
+    pub height_range: (i32, i32),
+}
+
+impl Default for WormSettings {
+    fn default() -> Self {
+        Self {
+            chance:        0.1,
+            length:        96,
+            step:          1.0,
+            radius:        (1.5, 3.0),
+            turn_strength: 0.5,
+            height_range:  (-64, 0),
+        }
+    }
+}
+
+impl WormSettings {
+    /// Gets the maximum distance, in blocks, that a worm can carve away from
+    /// the chunk it started in.
+    fn reach(&self) -> f32 {
+        self.length as f32 * self.step + self.radius.1
+    }
+}
+
+/// A generation stage that carves caves into the terrain using 3D noise
+/// caverns and noise-steered worm tunnels.
+///
+/// Caves are fully deterministic for a given world seed. Worm tunnels may
+/// start within any chunk, and are traced again by each nearby chunk that
+/// they pass through, with each chunk carving only its own blocks. This
+/// allows tunnels to cross chunk borders without requiring neighboring chunks
+/// to be generated first.
+pub struct CaveStage<T>
+where
+    T: BlockData,
+{
+    /// The block that carved blocks are replaced with.
+    air: T,
+
+    /// Checks whether a block may be carved.
+    can_carve: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// The cavern settings, if caverns are enabled.
+    caverns: Option<CavernSettings>,
+
+    /// The worm tunnel settings, if worm tunnels are enabled.
+    worms: Option<WormSettings>,
+
+    /// The up axis of the world.
+    up: UpAxis,
+
+    /// The salt that is combined with the world seed for this stage.
+    salt: u64,
+}
+
+impl<T> CaveStage<T>
+where
+    T: BlockData,
+{
+    /// Creates a new cave stage that replaces carved blocks with the given air
+    /// block. Only blocks that pass the given predicate are carved.
+    ///
+    /// No caves are carved until caverns or worm tunnels are enabled.
+    pub fn new<F>(air: T, can_carve: F) -> Self
+    where
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            air,
+            can_carve: Arc::new(can_carve),
+            caverns: None,
+            worms: None,
+            up: UpAxis::default(),
+            salt: 0xCA7E,
+        }
+    }
+
+    /// Enables noise caverns with the given settings.
+    pub fn with_caverns(mut self, settings: CavernSettings) -> Self {
+        self.caverns = Some(settings);
+        self
+    }
+
+    /// Enables worm tunnels with the given settings.
+    pub fn with_worms(mut self, settings: WormSettings) -> Self {
+        self.worms = Some(settings);
+        self
+    }
+
+    /// Sets the up axis of the world. Defaults to [`UpAxis::PosY`].
+    pub fn with_up_axis(mut self, up: UpAxis) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Sets the salt that is combined with the world seed, so that multiple
+    /// cave stages within the same pipeline carve different caves.
+    pub fn with_salt(mut self, salt: u64) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Carves the given block, if it may be carved.
+    fn carve(&self, ctx: &mut GenContext<T>, block_coords: IVec3) {
+        if (self.can_carve)(ctx.get_block(block_coords)) {
+            ctx.set_block(block_coords, self.air);
+        }
+    }
+
+    /// Carves all caverns within the chunk.
+    fn carve_caverns(&self, ctx: &mut GenContext<T>, settings: &CavernSettings) {
+        let noise = GradientNoise::new(ctx.seed() ^ self.salt);
+        let (min_y, max_y) = settings.height_range;
+
+        for block_coords in ctx.region().iter() {
+            let height = self.up.height(block_coords);
+            if height < min_y || height > max_y {
+                continue;
+            }
+
+            let pos = block_coords.as_vec3() * settings.frequency;
+            if noise.fbm(pos, settings.octaves) > settings.threshold {
+                self.carve(ctx, block_coords);
+            }
+        }
+    }
+
+    /// Traces all worms that may reach the chunk, and carves the blocks along
+    /// their paths that are within the chunk.
+    fn carve_worms(&self, ctx: &mut GenContext<T>, settings: &WormSettings) {
+        let noise = GradientNoise::new(ctx.seed() ^ self.salt.rotate_left(17));
+        let region = ctx.region();
+        let range = (settings.reach() / 16.0).ceil() as i32;
+
+        let up = self.up;
+        let (min_y, max_y) = settings.height_range;
+        let up_dir = up.up().as_vec3();
+        let side_dirs = (
+            up.compose(IVec2::X, 0).as_vec3(),
+            up.compose(IVec2::Y, 0).as_vec3(),
+        );
+
+        let origins = Region::from_points(
+            ctx.chunk_coords() - IVec3::splat(range),
+            ctx.chunk_coords() + IVec3::splat(range),
+        );
+
+        for origin in origins.iter() {
+            let heights = up.block_heights(up.height(origin));
+            if *heights.end() < min_y || *heights.start() > max_y {
+                continue;
+            }
+
+            let mut rng = ChunkRng::for_chunk(ctx.seed(), origin, self.salt);
+            if rng.next_f32() >= settings.chance {
+                continue;
+            }
+
+            let start = origin * 16
+                + IVec3::new(rng.range(0 .. 16), rng.range(0 .. 16), rng.range(0 .. 16));
+            let start_height = up.height(start);
+            if start_height < min_y || start_height > max_y {
+                continue;
+            }
+
+            let mut pos = start.as_vec3() + 0.5;
+            let mut yaw = rng.next_f32() * TAU;
+            let mut pitch = (rng.next_f32() - 0.5) * 0.5;
+            let radius =
+                settings.radius.0 + (settings.radius.1 - settings.radius.0) * rng.next_f32();
+
+            for _ in 0 .. settings.length {
+                self.carve_sphere(ctx, region, pos, radius);
+
+                let sample = pos * 0.03;
+                yaw += noise.get(sample) * settings.turn_strength;
+                pitch = (pitch + noise.get(sample + 71.3) * settings.turn_strength * 0.5)
+                    .clamp(-0.7, 0.7);
+
+                let dir = side_dirs.0 * pitch.cos() * yaw.cos()
+                    + up_dir * pitch.sin()
+                    + side_dirs.1 * pitch.cos() * yaw.sin();
+                pos += dir * settings.step;
+            }
+        }
+    }
+
+    /// Carves all blocks within the given sphere that are also within the
+    /// given chunk region.
+    fn carve_sphere(&self, ctx: &mut GenContext<T>, region: Region, center: Vec3, radius: f32) {
+        let min = (center - radius).floor().as_ivec3();
+        let max = (center + radius).ceil().as_ivec3();
+        let Ok(bounds) = Region::intersection(&region, &Region::from_points(min, max)) else {
+            return;
+        };
+
+        for block_coords in bounds.iter() {
+            let offset = block_coords.as_vec3() + 0.5 - center;
+            if offset.length_squared() <= radius * radius {
+                self.carve(ctx, block_coords);
+            }
+        }
+    }
+}
+
+impl<T> GenStage<T> for CaveStage<T>
+where
+    T: BlockData,
+{
+    fn apply(&self, ctx: &mut GenContext<T>) {
+        if let Some(settings) = self.caverns.as_ref() {
+            self.carve_caverns(ctx, settings);
+        }
+
+        if let Some(settings) = self.worms.as_ref() {
+            self.carve_worms(ctx, settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::utils::HashSet;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ecs::components::WorldGenerator;
+    use crate::pipeline::GenPipeline;
+    use crate::test_util::fill;
+
+    #[test]
+    fn worms_cross_chunk_borders() {
+        let pipeline = GenPipeline::new(42, fill(1)).with_stage(
+            CaveStage::new(0, |block: u8| block == 1).with_worms(WormSettings {
+                chance: 1.0,
+                length: 24,
+                height_range: (i32::MIN, i32::MAX),
+                ..default()
+            }),
+        );
+
+        let mut carved = HashSet::new();
+        for chunk_coords in Region::from_points(IVec3::NEG_ONE, IVec3::ONE).iter() {
+            let storage = pipeline.generate_chunk(chunk_coords).unwrap();
+            assert_eq!(
+                storage.get_block(IVec3::new(3, 4, 5)),
+                pipeline
+                    .generate_chunk(chunk_coords)
+                    .unwrap()
+                    .get_block(IVec3::new(3, 4, 5))
+            );
+
+            for block_coords in Region::CHUNK.shift(chunk_coords * 16).iter() {
+                if storage.get_block(block_coords) == 0 {
+                    carved.insert(block_coords);
+                }
+            }
+        }
+
+        assert!(!carved.is_empty());
+        let crosses_border = carved.iter().any(|block_coords| {
+            let neighbor = *block_coords + IVec3::X;
+            carved.contains(&neighbor) && neighbor >> 4 != *block_coords >> 4
+        });
+        assert!(crosses_border);
+    }
+
+    #[test]
+    fn caverns_follow_up_axis() {
+        let pipeline = GenPipeline::new(7, fill(1)).with_stage(
+            CaveStage::new(0, |block: u8| block == 1)
+                .with_up_axis(UpAxis::NegX)
+                .with_caverns(CavernSettings {
+                    threshold: -2.0,
+                    height_range: (i32::MIN, 3),
+                    ..default()
+                }),
+        );
+
+        let storage = pipeline.generate_chunk(IVec3::NEG_X).unwrap();
+        assert_eq!(storage.get_block(IVec3::new(-3, 5, 5)), 0);
+        assert_eq!(storage.get_block(IVec3::new(-4, 5, 5)), 1);
+    }
+}
@@ -0,0 +1,406 @@
+//! A world generation pipeline, which runs a series of post-generation stages
+//! on top of the chunks created by a base terrain generator.
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bones3_core::prelude::*;
+//! # use bones3_worldgen::ecs::components::{WorldGenerator, WorldGeneratorHandler};
+//! # use bones3_worldgen::error::WorldGenError;
+//! # use bones3_worldgen::pipeline::{GenPipeline, SurfaceStage};
+//! # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+//! # enum BlockState {
+//! #     #[default]
+//! #     Air,
+//! #     Stone,
+//! #     Dirt,
+//! #     Grass,
+//! # }
+//! # #[derive(Default)]
+//! # struct TerrainGenerator;
+//! # impl WorldGenerator<BlockState> for TerrainGenerator {
+//! #     fn generate_chunk(&self, _: IVec3) -> Result<VoxelStorage<BlockState>, WorldGenError> {
+//! #         Ok(VoxelStorage::filled(BlockState::Stone))
+//! #     }
+//! # }
+//! # fn spawn_world(mut commands: VoxelCommands, seed: u64) {
+//! let generator = GenPipeline::new(seed, TerrainGenerator::default())
+//!     .with_stage(SurfaceStage::new(|block| block != BlockState::Air)
+//!         .with_layer(1, |block| (block == BlockState::Stone).then_some(BlockState::Grass))
+//!         .with_layer(3, |block| (block == BlockState::Stone).then_some(BlockState::Dirt)));
+//!
+//! commands.spawn_world((SpatialBundle::default(), WorldGeneratorHandler::from(generator)));
+//! # }
+//! ```
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, VoxelStorage};
+
+use crate::ecs::components::WorldGenerator;
+use crate::error::WorldGenError;
+use crate::recording::{GenRecord, GenRecorder, StageTiming};
+
+mod caves;
+mod noise;
+mod ores;
+mod surface;
+mod water;
+
+pub use caves::*;
+pub use noise::*;
+pub use ores::*;
+pub use surface::*;
+pub use water::*;
+
+/// A single post-generation stage within a [`GenPipeline`].
+///
+/// Stages are run in the order they were added to the pipeline, once for each
+/// chunk, after the base terrain of the chunk has been generated.
+pub trait GenStage<T>
+where
+    T: BlockData,
+    Self: Send + Sync,
+{
+    /// Applies this stage to the chunk within the given generation context.
+    fn apply(&self, ctx: &mut GenContext<T>);
+
+    /// Gets the name of this stage, for use within debugging tools such as
+    /// the [`GenRecorder`].
+    ///
+    /// Defaults to the type name of the stage.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// A world generator that creates chunks using a base terrain generator, and
+/// then runs a list of [`GenStage`]s over each chunk.
+///
+/// All stages are given the same world seed, so the output of the pipeline is
+/// deterministic for any given chunk.
+pub struct GenPipeline<T>
+where
+    T: BlockData,
+{
+    /// The seed of the world.
+    seed: u64,
+
+    /// The generator that creates the base terrain of each chunk.
+    base: Arc<dyn WorldGenerator<T>>,
+
+    /// The stages that are applied to each chunk, in order.
+    stages: Vec<Box<dyn GenStage<T>>>,
+
+    /// The recorder that generated chunks are logged to, if any.
+    recorder: Option<GenRecorder>,
+}
+
+impl<T> GenPipeline<T>
+where
+    T: BlockData,
+{
+    /// Creates a new generation pipeline with the given world seed and base
+    /// terrain generator.
+    pub fn new<G>(seed: u64, base: G) -> Self
+    where
+        G: WorldGenerator<T> + 'static,
+    {
+        Self {
+            seed,
+            base: Arc::new(base),
+            stages: vec![],
+            recorder: None,
+        }
+    }
+
+    /// Adds a new stage to the end of this pipeline.
+    pub fn with_stage<S>(mut self, stage: S) -> Self
+    where
+        S: GenStage<T> + 'static,
+    {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Logs every chunk generated by this pipeline, along with the time each
+    /// stage took, to the given recorder.
+    pub fn with_recorder(mut self, recorder: GenRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Gets the seed of the world.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Generates the chunk at the given chunk coordinates, only applying the
+    /// first `stage_count` stages of this pipeline.
+    ///
+    /// This may be used to find which stage of the pipeline is responsible
+    /// for a chunk that generates incorrectly. Chunks generated this way are
+    /// not recorded.
+    pub fn generate_stages(
+        &self,
+        chunk_coords: IVec3,
+        stage_count: usize,
+    ) -> Result<VoxelStorage<T>, WorldGenError> {
+        self.run(chunk_coords, stage_count, None)
+    }
+
+    /// Generates the given chunk using the base terrain generator and the
+    /// first `stage_count` stages, recording the time each stage took if a
+    /// list of stage timings is given.
+    fn run(
+        &self,
+        chunk_coords: IVec3,
+        stage_count: usize,
+        mut timings: Option<&mut Vec<StageTiming>>,
+    ) -> Result<VoxelStorage<T>, WorldGenError> {
+        let mut ctx = GenContext::new(self.seed, chunk_coords, self.base.as_ref())?;
+        for stage in self.stages.iter().take(stage_count) {
+            let start = Instant::now();
+            stage.apply(&mut ctx);
+
+            if let Some(timings) = timings.as_mut() {
+                timings.push(StageTiming {
+                    name:     stage.name(),
+                    duration: start.elapsed(),
+                });
+            }
+        }
+
+        match ctx.error {
+            Some(err) => Err(err),
+            None => Ok(ctx.storage),
+        }
+    }
+}
+
+impl<T> WorldGenerator<T> for GenPipeline<T>
+where
+    T: BlockData,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        let Some(recorder) = &self.recorder else {
+            return self.run(chunk_coords, self.stages.len(), None);
+        };
+
+        let start = Instant::now();
+        let mut stages = vec![];
+        let result = self.run(chunk_coords, self.stages.len(), Some(&mut stages));
+
+        recorder.push(GenRecord {
+            sequence: 0,
+            chunk_coords,
+            seed: Some(self.seed),
+            stages,
+            total: start.elapsed(),
+            error: result.as_ref().err().cloned(),
+        });
+
+        result
+    }
+}
+
+/// The context of a single chunk that is being generated by a
+/// [`GenPipeline`].
+pub struct GenContext<'a, T>
+where
+    T: BlockData,
+{
+    /// The seed of the world.
+    seed: u64,
+
+    /// The coordinates of the chunk being generated.
+    chunk_coords: IVec3,
+
+    /// The block data of the chunk being generated.
+    storage: VoxelStorage<T>,
+
+    /// The generator that creates the base terrain of each chunk.
+    base: &'a dyn WorldGenerator<T>,
+
+    /// The base terrain of all chunks that have been read so far.
+    terrain: HashMap<IVec3, VoxelStorage<T>>,
+
+    /// The first error that occurred while generating the base terrain of a
+    /// neighboring chunk.
+    error: Option<WorldGenError>,
+}
+
+impl<'a, T> GenContext<'a, T>
+where
+    T: BlockData,
+{
+    /// Creates a new generation context for the given chunk, and generates
+    /// its base terrain.
+    fn new(
+        seed: u64,
+        chunk_coords: IVec3,
+        base: &'a dyn WorldGenerator<T>,
+    ) -> Result<Self, WorldGenError> {
+        let storage = base.generate_chunk(chunk_coords)?;
+        let mut terrain = HashMap::new();
+        terrain.insert(chunk_coords, storage.snapshot());
+
+        Ok(Self {
+            seed,
+            chunk_coords,
+            storage,
+            base,
+            terrain,
+            error: None,
+        })
+    }
+
+    /// Gets the seed of the world.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Gets the coordinates of the chunk being generated.
+    pub fn chunk_coords(&self) -> IVec3 {
+        self.chunk_coords
+    }
+
+    /// Gets the region of block coordinates that are contained within the
+    /// chunk being generated.
+    pub fn region(&self) -> Region {
+        Region::CHUNK.shift(self.chunk_coords * 16)
+    }
+
+    /// Checks whether the given block coordinates are within the chunk being
+    /// generated.
+    pub fn contains(&self, block_coords: IVec3) -> bool {
+        block_coords >> 4 == self.chunk_coords
+    }
+
+    /// Gets the current value of the block at the given block coordinates,
+    /// which must be within the chunk being generated.
+    ///
+    /// For blocks outside of the chunk, the base terrain is returned instead.
+    pub fn get_block(&mut self, block_coords: IVec3) -> T {
+        match self.contains(block_coords) {
+            true => self.storage.get_block(block_coords),
+            false => self.terrain_block(block_coords),
+        }
+    }
+
+    /// Sets the value of the block at the given block coordinates.
+    ///
+    /// Blocks outside of the chunk being generated are ignored, and this
+    /// function returns false for them.
+    pub fn set_block(&mut self, block_coords: IVec3, block: T) -> bool {
+        if !self.contains(block_coords) {
+            return false;
+        }
+
+        self.storage.set_block(block_coords, block);
+        true
+    }
+
+    /// Gets the value of the block at the given block coordinates within the
+    /// base terrain, before any stages were applied.
+    ///
+    /// This may be used to read blocks within neighboring chunks. The base
+    /// terrain of each neighboring chunk is generated once per context, the
+    /// first time a block within it is read.
+    ///
+    /// If the base terrain of a neighboring chunk fails to generate, the
+    /// default block is returned for that chunk, and the chunk being generated
+    /// fails with the same error once all stages have been applied.
+    pub fn terrain_block(&mut self, block_coords: IVec3) -> T {
+        let chunk_coords = block_coords >> 4;
+        let base = self.base;
+        let error = &mut self.error;
+        self.terrain
+            .entry(chunk_coords)
+            .or_insert_with(|| {
+                base.generate_chunk(chunk_coords).unwrap_or_else(|err| {
+                    error.get_or_insert(err);
+                    VoxelStorage::default()
+                })
+            })
+            .get_block(block_coords)
+    }
+
+    /// Creates a random number generator that is seeded using the world seed,
+    /// the coordinates of the chunk being generated, and the given salt.
+    ///
+    /// Each stage should use a unique salt, so that separate stages do not
+    /// produce correlated values.
+    pub fn rng(&self, salt: u64) -> ChunkRng {
+        ChunkRng::for_chunk(self.seed, self.chunk_coords, salt)
+    }
+}
+
+/// A small, deterministic random number generator for world generation.
+///
+/// This generator is not suitable for cryptographic use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRng {
+    /// The current state of the generator.
+    state: u64,
+}
+
+impl ChunkRng {
+    /// Creates a new random number generator with the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+        }
+    }
+
+    /// Creates a new random number generator for the chunk at the given chunk
+    /// coordinates, using the given world seed and salt.
+    pub fn for_chunk(seed: u64, chunk_coords: IVec3, salt: u64) -> Self {
+        let mut hash = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for value in chunk_coords.to_array() {
+            hash = mix64(hash ^ value as u32 as u64);
+        }
+
+        Self::new(hash)
+    }
+
+    /// Gets the next random 64 bit value from this generator.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix64(self.state)
+    }
+
+    /// Gets the next random 32 bit value from this generator.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Gets the next random value from this generator, between `0.0`
+    /// inclusive and `1.0` exclusive.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Gets the next random value from this generator within the given range.
+    ///
+    /// If the range is empty, the start of the range is returned.
+    pub fn range(&mut self, range: Range<i32>) -> i32 {
+        if range.is_empty() {
+            return range.start;
+        }
+
+        let size = (range.end as i64 - range.start as i64) as u64;
+        (range.start as i64 + (self.next_u64() % size) as i64) as i32
+    }
+}
+
+/// Mixes the bits of the given value using the SplitMix64 finalizer.
+fn mix64(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
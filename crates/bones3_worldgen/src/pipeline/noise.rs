@@ -0,0 +1,107 @@
+//! Seeded gradient noise functions for use within generation stages.
+
+use bevy::prelude::*;
+
+use super::mix64;
+
+/// A seeded 3D gradient noise function, similar to Perlin noise.
+///
+/// Gradients are derived from a hash of the lattice coordinates and the seed,
+/// so this noise function does not require any lookup tables and is cheap to
+/// create.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GradientNoise {
+    /// The seed of this noise function.
+    seed: u64,
+}
+
+impl GradientNoise {
+    /// Creates a new noise function with the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+        }
+    }
+
+    /// Samples this noise function at the given position.
+    ///
+    /// The returned value is roughly within the range `-1.0` to `1.0`, and is
+    /// always `0.0` at integer lattice positions.
+    pub fn get(&self, pos: Vec3) -> f32 {
+        let cell = pos.floor();
+        let local = pos - cell;
+        let cell = cell.as_ivec3();
+        let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+
+        let mut corners = [0.0; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let offset = IVec3::new(i as i32 & 1, (i as i32 >> 1) & 1, (i as i32 >> 2) & 1);
+            let gradient = self.gradient(cell + offset);
+            *corner = gradient.dot(local - offset.as_vec3());
+        }
+
+        let x0 = lerp(corners[0], corners[1], fade.x);
+        let x1 = lerp(corners[2], corners[3], fade.x);
+        let x2 = lerp(corners[4], corners[5], fade.x);
+        let x3 = lerp(corners[6], corners[7], fade.x);
+        let y0 = lerp(x0, x1, fade.y);
+        let y1 = lerp(x2, x3, fade.y);
+        lerp(y0, y1, fade.z)
+    }
+
+    /// Samples multiple octaves of this noise function at the given position,
+    /// with each octave having double the frequency and half the amplitude of
+    /// the previous octave.
+    ///
+    /// The returned value is normalized to roughly the range `-1.0` to `1.0`.
+    pub fn fbm(&self, pos: Vec3, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut max = 0.0;
+        let mut pos = pos;
+
+        for octave in 0 .. octaves {
+            let offset = Vec3::splat(octave as f32 * 17.31);
+            total += self.get(pos + offset) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            pos *= 2.0;
+        }
+
+        match max > 0.0 {
+            true => total / max,
+            false => 0.0,
+        }
+    }
+
+    /// Gets the gradient vector at the given lattice coordinates.
+    fn gradient(&self, coords: IVec3) -> Vec3 {
+        /// The gradient directions, pointing to the edges of a cube.
+        const GRADIENTS: [Vec3; 12] = [
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, 0.0, 1.0),
+            Vec3::new(-1.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, -1.0),
+            Vec3::new(-1.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 1.0),
+            Vec3::new(0.0, -1.0, 1.0),
+            Vec3::new(0.0, 1.0, -1.0),
+            Vec3::new(0.0, -1.0, -1.0),
+        ];
+
+        let mut hash = self.seed;
+        for value in coords.to_array() {
+            hash = mix64(hash ^ value as u32 as u64);
+        }
+
+        GRADIENTS[(hash % 12) as usize]
+    }
+}
+
+/// Linearly interpolates between two values.
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
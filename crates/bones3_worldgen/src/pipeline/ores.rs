@@ -0,0 +1,287 @@
+//! A generation stage that scatters ore veins throughout the terrain.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, UpAxis};
+
+use super::{ChunkRng, GenContext, GenStage};
+
+/// The placement settings for a single type of ore vein.
+pub struct OreVein<T>
+where
+    T: BlockData,
+{
+    /// The ore block that is placed.
+    pub ore: T,
+
+    /// Checks whether a block may be replaced by the ore.
+    pub can_replace: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// The average number of veins that start within each chunk.
+    pub frequency: f32,
+
+    /// The minimum and maximum number of blocks within each vein, inclusive.
+    pub size: (u32, u32),
+
+    /// The range of block heights along the up axis of the world in which
+    /// veins may start, inclusive.
+    pub height_range: (i32, i32),
+}
+
+impl<T> OreVein<T>
+where
+    T: BlockData,
+{
+    /// Creates a new ore vein that replaces blocks that pass the given
+    /// predicate with the given ore block.
+    ///
+    /// By default, one vein of 4 to 8 blocks is placed per chunk, at any
+    /// height.
+    pub fn new<F>(ore: T, can_replace: F) -> Self
+    where
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            ore,
+            can_replace: Arc::new(can_replace),
+            frequency: 1.0,
+            size: (4, 8),
+            height_range: (i32::MIN, i32::MAX),
+        }
+    }
+
+    /// Sets the average number of veins that start within each chunk.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the minimum and maximum number of blocks within each vein.
+    pub fn with_size(mut self, min: u32, max: u32) -> Self {
+        self.size = (min, max.max(min));
+        self
+    }
+
+    /// Sets the range of block heights in which veins may start.
+    pub fn with_height_range(mut self, min: i32, max: i32) -> Self {
+        self.height_range = (min, max);
+        self
+    }
+
+    /// Gets the maximum distance, in blocks, that a vein can reach away from
+    /// the chunk it started in.
+    fn reach(&self) -> i32 {
+        self.size.1 as i32
+    }
+}
+
+/// A generation stage that scatters clusters of ore blocks throughout the
+/// terrain, as configured by a list of [`OreVein`]s.
+///
+/// Each vein is grown from a random starting block by a random walk. Veins are
+/// deterministic for a given world seed, and veins that start near the edge of
+/// a chunk continue into the neighboring chunks.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_worldgen::pipeline::{OreStage, OreVein};
+/// # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+/// # enum BlockState {
+/// #     #[default]
+/// #     Stone,
+/// #     Coal,
+/// #     Gold,
+/// # }
+/// fn is_stone(block: BlockState) -> bool {
+///     block == BlockState::Stone
+/// }
+///
+/// let ores = OreStage::default()
+///     .with_vein(
+///         OreVein::new(BlockState::Coal, is_stone)
+///             .with_frequency(8.0)
+///             .with_size(6, 16),
+///     )
+///     .with_vein(
+///         OreVein::new(BlockState::Gold, is_stone)
+///             .with_frequency(0.5)
+///             .with_height_range(-256, -32),
+///     );
+/// ```
+pub struct OreStage<T>
+where
+    T: BlockData,
+{
+    /// The types of ore veins that are placed, in order.
+    veins: Vec<OreVein<T>>,
+
+    /// The up axis of the world.
+    up: UpAxis,
+
+    /// The salt that is combined with the world seed for this stage.
+    salt: u64,
+}
+
+impl<T> Default for OreStage<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self {
+            veins: vec![],
+            up:    UpAxis::default(),
+            salt:  0x04E5,
+        }
+    }
+}
+
+impl<T> OreStage<T>
+where
+    T: BlockData,
+{
+    /// Adds a new type of ore vein to this stage. Veins that are added later
+    /// may overwrite ore placed by earlier veins, if allowed by their
+    /// predicate.
+    pub fn with_vein(mut self, vein: OreVein<T>) -> Self {
+        self.veins.push(vein);
+        self
+    }
+
+    /// Sets the up axis of the world. Defaults to [`UpAxis::PosY`].
+    pub fn with_up_axis(mut self, up: UpAxis) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Sets the salt that is combined with the world seed, so that multiple
+    /// ore stages within the same pipeline place different veins.
+    pub fn with_salt(mut self, salt: u64) -> Self {
+        self.salt = salt;
+        self
+    }
+
+    /// Places all veins of the given type that may reach the chunk.
+    fn place_veins(&self, ctx: &mut GenContext<T>, vein: &OreVein<T>, salt: u64) {
+        let region = ctx.region();
+        let range = (vein.reach() + 15) / 16;
+        let up = self.up;
+        let (min_y, max_y) = vein.height_range;
+
+        let origins = Region::from_points(
+            ctx.chunk_coords() - IVec3::splat(range),
+            ctx.chunk_coords() + IVec3::splat(range),
+        );
+
+        for origin in origins.iter() {
+            let heights = up.block_heights(up.height(origin));
+            if *heights.end() < min_y || *heights.start() > max_y {
+                continue;
+            }
+
+            let mut rng = ChunkRng::for_chunk(ctx.seed(), origin, salt);
+            let mut count = vein.frequency.floor() as u32;
+            if rng.next_f32() < vein.frequency.fract() {
+                count += 1;
+            }
+
+            for _ in 0 .. count {
+                let mut pos = origin * 16
+                    + IVec3::new(rng.range(0 .. 16), rng.range(0 .. 16), rng.range(0 .. 16));
+                let size = rng.range(vein.size.0 as i32 .. vein.size.1 as i32 + 1);
+
+                // Always consume the same random values, regardless of whether
+                // the vein is placed, to keep later veins stable.
+                let height = up.height(pos);
+                let valid = height >= min_y && height <= max_y;
+
+                for _ in 0 .. size {
+                    if valid && region.contains(pos) && (vein.can_replace)(ctx.get_block(pos)) {
+                        ctx.set_block(pos, vein.ore);
+                    }
+
+                    pos += match rng.range(0 .. 6) {
+                        0 => IVec3::X,
+                        1 => IVec3::NEG_X,
+                        2 => IVec3::Y,
+                        3 => IVec3::NEG_Y,
+                        4 => IVec3::Z,
+                        _ => IVec3::NEG_Z,
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl<T> GenStage<T> for OreStage<T>
+where
+    T: BlockData,
+{
+    fn apply(&self, ctx: &mut GenContext<T>) {
+        for (index, vein) in self.veins.iter().enumerate() {
+            let salt = self.salt.wrapping_add(index as u64).rotate_left(13);
+            self.place_veins(ctx, vein, salt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ecs::components::WorldGenerator;
+    use crate::pipeline::GenPipeline;
+    use crate::test_util::fill;
+
+    #[test]
+    fn place_veins_in_height_range() {
+        let pipeline = GenPipeline::new(7, fill(1)).with_stage(
+            OreStage::default().with_vein(
+                OreVein::new(2, |block: u8| block == 1)
+                    .with_frequency(4.0)
+                    .with_size(1, 1)
+                    .with_height_range(0, 15),
+            ),
+        );
+
+        let count_ores = |chunk_coords: IVec3| {
+            let storage = pipeline.generate_chunk(chunk_coords).unwrap();
+            Region::CHUNK
+                .iter()
+                .filter(|block_coords| storage.get_block(*block_coords) == 2)
+                .count()
+        };
+
+        assert!((1 ..= 4).contains(&count_ores(IVec3::ZERO)));
+        assert_eq!(count_ores(IVec3::ZERO), count_ores(IVec3::ZERO));
+        assert_eq!(count_ores(IVec3::Y), 0);
+        assert_eq!(count_ores(IVec3::NEG_Y), 0);
+    }
+
+    #[test]
+    fn place_veins_along_up_axis() {
+        let pipeline = GenPipeline::new(7, fill(1)).with_stage(
+            OreStage::default().with_up_axis(UpAxis::NegZ).with_vein(
+                OreVein::new(2, |block: u8| block == 1)
+                    .with_frequency(4.0)
+                    .with_size(1, 1)
+                    .with_height_range(1, 16),
+            ),
+        );
+
+        let count_ores = |chunk_coords: IVec3| {
+            let storage = pipeline.generate_chunk(chunk_coords).unwrap();
+            Region::CHUNK
+                .iter()
+                .filter(|block_coords| storage.get_block(*block_coords) == 2)
+                .count()
+        };
+
+        assert!((1 ..= 4).contains(&count_ores(IVec3::NEG_Z)));
+        assert_eq!(count_ores(IVec3::ZERO), 0);
+        assert_eq!(count_ores(IVec3::NEG_Y), 0);
+    }
+}
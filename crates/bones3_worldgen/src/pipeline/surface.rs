@@ -0,0 +1,227 @@
+//! A generation stage that converts the top blocks of the terrain into
+//! surface and soil variants.
+
+use std::sync::Arc;
+
+use bones3_core::storage::{BlockData, UpAxis};
+
+use super::{GenContext, GenStage};
+
+/// A function that maps a block to its surface variant, or returns `None` if
+/// the block should be left unchanged.
+type SurfaceMapping<T> = Arc<dyn Fn(T) -> Option<T> + Send + Sync>;
+
+/// A generation stage that replaces the top exposed blocks of each block
+/// column with surface variants, such as converting stone into a layer of
+/// grass above a few layers of dirt.
+///
+/// Layers are applied from the top down, in the order they were added. The
+/// depth of each block is measured from the first non-solid block above it,
+/// using the base terrain of the chunks above the generated chunk where
+/// needed, so layers continue correctly across chunk borders.
+///
+/// By default, only the first surface that is found within the lookahead
+/// distance above each block is considered exposed to the sky. Blocks beneath
+/// overhangs that start further above the chunk than the lookahead distance
+/// cannot be detected, and are treated as exposed.
+pub struct SurfaceStage<T>
+where
+    T: BlockData,
+{
+    /// Checks whether a block is solid.
+    is_solid: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// The surface layers, from top to bottom, as pairs of layer thicknesses
+    /// and block mappings.
+    layers: Vec<(u32, SurfaceMapping<T>)>,
+
+    /// The up axis of the world.
+    up: UpAxis,
+
+    /// The number of blocks above each chunk that are checked when looking
+    /// for the surface, or `None` to use the total thickness of all layers.
+    lookahead: Option<u32>,
+
+    /// If true, only the first surface below the sky is converted, instead of
+    /// every surface, such as cave floors.
+    sky_only: bool,
+}
+
+impl<T> SurfaceStage<T>
+where
+    T: BlockData,
+{
+    /// Creates a new surface stage without any layers, using the given
+    /// function to check whether a block is solid.
+    pub fn new<F>(is_solid: F) -> Self
+    where
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            is_solid:  Arc::new(is_solid),
+            layers:    vec![],
+            up:        UpAxis::default(),
+            lookahead: None,
+            sky_only:  true,
+        }
+    }
+
+    /// Adds a new layer below all existing layers with the given thickness.
+    ///
+    /// The mapping function is called for each block within this layer, and
+    /// returns the block to replace it with, or `None` to keep the block.
+    pub fn with_layer<F>(mut self, thickness: u32, mapping: F) -> Self
+    where
+        F: Fn(T) -> Option<T> + Send + Sync + 'static,
+    {
+        self.layers.push((thickness, Arc::new(mapping)));
+        self
+    }
+
+    /// Sets the up axis of the world. Defaults to [`UpAxis::PosY`].
+    pub fn with_up_axis(mut self, up: UpAxis) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Sets the number of blocks above each chunk that are checked when
+    /// looking for the surface. Defaults to the total thickness of all layers.
+    pub fn with_lookahead(mut self, lookahead: u32) -> Self {
+        self.lookahead = Some(lookahead);
+        self
+    }
+
+    /// Sets whether every exposed surface is converted, including cave floors
+    /// and the tops of overhangs, rather than only the first surface below the
+    /// sky.
+    pub fn with_all_surfaces(mut self, all_surfaces: bool) -> Self {
+        self.sky_only = !all_surfaces;
+        self
+    }
+
+    /// Gets the layer mapping for a block at the given depth below the
+    /// surface, starting at 1 for the topmost block.
+    fn layer_at(&self, depth: u32) -> Option<&SurfaceMapping<T>> {
+        let mut bottom = 0;
+        for (thickness, mapping) in self.layers.iter() {
+            bottom += thickness;
+            if depth <= bottom {
+                return Some(mapping);
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> GenStage<T> for SurfaceStage<T>
+where
+    T: BlockData,
+{
+    fn apply(&self, ctx: &mut GenContext<T>) {
+        let total: u32 = self.layers.iter().map(|(thickness, _)| thickness).sum();
+        if total == 0 {
+            return;
+        }
+
+        let lookahead = self.lookahead.unwrap_or(total) as i32;
+        let up = self.up;
+        let heights = up.block_heights(up.height(ctx.chunk_coords()));
+        let (bottom, top) = (*heights.start(), *heights.end());
+        let region = ctx.region();
+
+        let columns: Vec<_> = region
+            .iter()
+            .filter(|block_coords| up.height(*block_coords) == top)
+            .map(|block_coords| up.column(block_coords))
+            .collect();
+
+        for column in columns {
+            // `None` means that the surface has not been found yet, such as
+            // when the column starts underground.
+            let mut depth: Option<u32> = None;
+            let mut passed_surface = false;
+
+            for height in (bottom ..= top + lookahead).rev() {
+                let block_coords = up.compose(column, height);
+                let block = ctx.get_block(block_coords);
+
+                if !(self.is_solid)(block) {
+                    if depth.is_some_and(|d| d > 0) {
+                        passed_surface = true;
+                    }
+
+                    depth = match self.sky_only && passed_surface {
+                        true => None,
+                        false => Some(0),
+                    };
+                    continue;
+                }
+
+                let Some(d) = depth.as_mut() else {
+                    continue;
+                };
+
+                *d += 1;
+                if *d > total || height > top {
+                    continue;
+                }
+
+                let Some(mapping) = self.layer_at(*d) else {
+                    continue;
+                };
+
+                if let Some(surface) = mapping(block) {
+                    ctx.set_block(block_coords, surface);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use bones3_core::storage::VoxelStorage;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ecs::components::WorldGenerator;
+    use crate::pipeline::GenPipeline;
+    use crate::test_util::ground;
+
+    fn surface_pipeline(height: i32) -> GenPipeline<u8> {
+        GenPipeline::new(0, ground(1, height)).with_stage(
+            SurfaceStage::new(|block: u8| block != 0)
+                .with_layer(1, |block| (block == 1).then_some(3))
+                .with_layer(2, |block| (block == 1).then_some(2)),
+        )
+    }
+
+    #[test]
+    fn convert_surface_layers() {
+        let column = |storage: &VoxelStorage<u8>| {
+            (0 .. 16)
+                .rev()
+                .map(|y| storage.get_block(IVec3::new(5, y, 9)))
+                .collect::<Vec<_>>()
+        };
+
+        let chunk = surface_pipeline(4).generate_chunk(IVec3::ZERO).unwrap();
+        assert_eq!(column(&chunk), vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 2, 2, 1, 1
+        ]);
+
+        // The surface is within the chunk above, so only the soil layers
+        // reach into this chunk.
+        let chunk = surface_pipeline(16).generate_chunk(IVec3::ZERO).unwrap();
+        assert_eq!(column(&chunk), vec![
+            2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1
+        ]);
+
+        let chunk = surface_pipeline(16)
+            .generate_chunk(IVec3::new(0, -1, 0))
+            .unwrap();
+        assert!(column(&chunk).into_iter().all(|block| block == 1));
+    }
+}
@@ -0,0 +1,466 @@
+//! A generation stage that carves rivers and fills lakes within the terrain.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bones3_core::storage::{BlockData, UpAxis};
+
+use super::{GenContext, GenStage, GradientNoise};
+
+/// The number of blocks along each side of a water plan region.
+const PLAN_SIZE: i32 = 64;
+
+/// The number of blocks around each water plan region that are included when
+/// filling lakes, so that lakes near the edge of a region are planned the same
+/// way by both regions.
+const PLAN_MARGIN: i32 = 32;
+
+/// The maximum number of water plan regions that are kept in the cache.
+const MAX_CACHED_PLANS: usize = 256;
+
+/// The settings for rivers that are carved along the zero crossings of a low
+/// frequency noise function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiverSettings {
+    /// The frequency of the river noise. Smaller values create longer, more
+    /// spread out rivers.
+    pub frequency: f32,
+
+    /// The width of rivers, as a noise value between `0.0` and `1.0`.
+    pub width: f32,
+
+    /// The depth of the river bed below the water surface at the center of a
+    /// river, in blocks.
+    pub depth: i32,
+
+    /// The depth of the water surface below the surrounding terrain, in
+    /// blocks.
+    pub bank_height: i32,
+}
+
+impl Default for RiverSettings {
+    fn default() -> Self {
+        Self {
+            frequency:   0.004,
+            width:       0.04,
+            depth:       3,
+            bank_height: 2,
+        }
+    }
+}
+
+/// The settings for lakes that are filled within depressions in the terrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LakeSettings {
+    /// The minimum depth of a depression, in blocks, for it to be filled with
+    /// water.
+    pub min_depth: i32,
+
+    /// The maximum depth that a lake is filled to, in blocks.
+    pub max_depth: i32,
+}
+
+impl Default for LakeSettings {
+    fn default() -> Self {
+        Self {
+            min_depth: 2,
+            max_depth: 16,
+        }
+    }
+}
+
+/// The planned water features of a single block column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct WaterColumn {
+    /// The height of the terrain surface.
+    surface: i32,
+
+    /// The height of the water surface, if this column contains water.
+    water_level: Option<i32>,
+
+    /// The height of the topmost block that is carved away below the water,
+    /// down to the water bed. Blocks above the water level and below the
+    /// terrain surface are always carved.
+    bed: i32,
+}
+
+/// The planned water features of all block columns within a square region.
+#[derive(Debug)]
+struct WaterPlan {
+    /// The planned block columns, in row major order.
+    columns: Vec<WaterColumn>,
+}
+
+impl WaterPlan {
+    /// Gets the planned block column at the given column coordinates, relative
+    /// to the region.
+    fn get(&self, local: IVec2) -> WaterColumn {
+        self.columns[(local.y * PLAN_SIZE + local.x) as usize]
+    }
+}
+
+/// A cache of water plans that evicts the least recently used plan once it is
+/// full.
+#[derive(Debug)]
+struct WaterPlanCache {
+    /// The cached water plans for each region, along with the tick at which
+    /// they were last used.
+    plans: HashMap<IVec2, (Arc<WaterPlan>, u64)>,
+
+    /// The maximum number of plans that are kept in the cache.
+    capacity: usize,
+
+    /// A counter that is increased each time a plan is used.
+    tick: u64,
+}
+
+impl WaterPlanCache {
+    /// Creates a new, empty water plan cache with the given capacity.
+    fn new(capacity: usize) -> Self {
+        Self {
+            plans: HashMap::new(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    /// Gets the cached plan for the given region and marks it as recently
+    /// used, if it exists.
+    fn get(&mut self, region: IVec2) -> Option<Arc<WaterPlan>> {
+        self.tick += 1;
+        let (plan, last_used) = self.plans.get_mut(&region)?;
+        *last_used = self.tick;
+        Some(plan.clone())
+    }
+
+    /// Inserts the plan for the given region, evicting the least recently
+    /// used plan if the cache is full.
+    fn insert(&mut self, region: IVec2, plan: Arc<WaterPlan>) {
+        if !self.plans.contains_key(&region) && self.plans.len() >= self.capacity {
+            let oldest = self
+                .plans
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(region, _)| *region);
+
+            if let Some(oldest) = oldest {
+                self.plans.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.plans.insert(region, (plan, self.tick));
+    }
+}
+
+/// A generation stage that adds rivers and lakes to the terrain.
+///
+/// Water features are planned for square regions of block columns at a time,
+/// using the given terrain height function, and cached within the stage so
+/// that all chunks within a region share the same plan. This allows rivers
+/// and lakes to span many chunks while keeping a consistent water level.
+///
+/// Block columns and heights are measured along the up axis of the world,
+/// which defaults to [`UpAxis::PosY`].
+pub struct WaterStage<T>
+where
+    T: BlockData,
+{
+    /// The water block.
+    water: T,
+
+    /// The block that is used for carved blocks above the water level.
+    air: T,
+
+    /// Checks whether a block may be replaced with water or air.
+    can_replace: Arc<dyn Fn(T) -> bool + Send + Sync>,
+
+    /// Gets the height of the terrain surface at the given block column, as
+    /// returned by [`UpAxis::column`].
+    height: Arc<dyn Fn(IVec2) -> i32 + Send + Sync>,
+
+    /// The river settings, if rivers are enabled.
+    rivers: Option<RiverSettings>,
+
+    /// The lake settings, if lakes are enabled.
+    lakes: Option<LakeSettings>,
+
+    /// The up axis of the world.
+    up: UpAxis,
+
+    /// The cached water plans for each region.
+    plans: Mutex<WaterPlanCache>,
+
+    /// The salt that is combined with the world seed for this stage.
+    salt: u64,
+}
+
+impl<T> WaterStage<T>
+where
+    T: BlockData,
+{
+    /// Creates a new water stage.
+    ///
+    /// The height function should return the height of the topmost solid
+    /// block of the base terrain for the given block column, which is `(x, z)`
+    /// for worlds that use the default up axis. Only
+    /// blocks that pass the given predicate are replaced by water or air.
+    ///
+    /// No water is placed until rivers or lakes are enabled.
+    pub fn new<H, F>(water: T, air: T, height: H, can_replace: F) -> Self
+    where
+        H: Fn(IVec2) -> i32 + Send + Sync + 'static,
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            water,
+            air,
+            can_replace: Arc::new(can_replace),
+            height: Arc::new(height),
+            rivers: None,
+            lakes: None,
+            up: UpAxis::default(),
+            plans: Mutex::new(WaterPlanCache::new(MAX_CACHED_PLANS)),
+            salt: 0x07A7,
+        }
+    }
+
+    /// Enables rivers with the given settings.
+    pub fn with_rivers(mut self, settings: RiverSettings) -> Self {
+        self.rivers = Some(settings);
+        self
+    }
+
+    /// Enables lakes with the given settings.
+    pub fn with_lakes(mut self, settings: LakeSettings) -> Self {
+        self.lakes = Some(settings);
+        self
+    }
+
+    /// Sets the up axis of the world. Defaults to [`UpAxis::PosY`].
+    pub fn with_up_axis(mut self, up: UpAxis) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Gets the water plan for the given region, planning it if it is not
+    /// cached yet.
+    fn get_plan(&self, seed: u64, region: IVec2) -> Arc<WaterPlan> {
+        if let Some(plan) = self.plans.lock().unwrap().get(region) {
+            return plan;
+        }
+
+        let plan = Arc::new(self.plan_region(seed, region));
+        self.plans.lock().unwrap().insert(region, plan.clone());
+        plan
+    }
+
+    /// Plans the water features of all block columns within the given region.
+    fn plan_region(&self, seed: u64, region: IVec2) -> WaterPlan {
+        let size = PLAN_SIZE + PLAN_MARGIN * 2;
+        let origin = region * PLAN_SIZE - PLAN_MARGIN;
+        let heights: Vec<i32> = (0 .. size * size)
+            .map(|i| (self.height)(origin + IVec2::new(i % size, i / size)))
+            .collect();
+
+        let lake_levels = match self.lakes {
+            Some(settings) => fill_depressions(&heights, size, settings),
+            None => vec![None; heights.len()],
+        };
+
+        let noise = GradientNoise::new(seed ^ self.salt);
+        let mut columns = Vec::with_capacity((PLAN_SIZE * PLAN_SIZE) as usize);
+        for z in 0 .. PLAN_SIZE {
+            for x in 0 .. PLAN_SIZE {
+                let index = ((z + PLAN_MARGIN) * size + x + PLAN_MARGIN) as usize;
+                let surface = heights[index];
+                let mut column = WaterColumn {
+                    surface,
+                    water_level: lake_levels[index],
+                    bed: surface,
+                };
+
+                if let Some(settings) = self.rivers.as_ref() {
+                    let pos =
+                        (region * PLAN_SIZE + IVec2::new(x, z)).as_vec2() * settings.frequency;
+                    let value = noise.get(Vec3::new(pos.x, 0.5, pos.y)).abs();
+                    if value < settings.width {
+                        let strength = 1.0 - value / settings.width;
+                        let level = surface - settings.bank_height;
+                        let bed = level - (settings.depth as f32 * strength).ceil() as i32;
+                        column.water_level = Some(column.water_level.unwrap_or(level).max(level));
+                        column.bed = bed.min(surface);
+                    }
+                }
+
+                columns.push(column);
+            }
+        }
+
+        WaterPlan {
+            columns,
+        }
+    }
+}
+
+impl<T> GenStage<T> for WaterStage<T>
+where
+    T: BlockData,
+{
+    fn apply(&self, ctx: &mut GenContext<T>) {
+        if self.rivers.is_none() && self.lakes.is_none() {
+            return;
+        }
+
+        let up = self.up;
+        let heights = up.block_heights(up.height(ctx.chunk_coords()));
+        let min_column = up.column(ctx.chunk_coords() * 16);
+
+        // Plan regions are aligned to chunks, so each chunk lies within a
+        // single plan region.
+        let plan_region = IVec2::new(
+            min_column.x.div_euclid(PLAN_SIZE),
+            min_column.y.div_euclid(PLAN_SIZE),
+        );
+        let plan = self.get_plan(ctx.seed(), plan_region);
+
+        for v in 0 .. 16 {
+            for u in 0 .. 16 {
+                let column_coords = min_column + IVec2::new(u, v);
+                let column = plan.get(column_coords - plan_region * PLAN_SIZE);
+
+                let Some(water_level) = column.water_level else {
+                    continue;
+                };
+
+                for height in heights.clone() {
+                    let block_coords = up.compose(column_coords, height);
+                    let block = match height {
+                        y if y > water_level && y <= column.surface => self.air,
+                        y if y <= water_level && y > column.bed => self.water,
+                        _ => continue,
+                    };
+
+                    if (self.can_replace)(ctx.get_block(block_coords)) {
+                        ctx.set_block(block_coords, block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds the water level of all depressions within the given height grid,
+/// using a priority flood from the edges of the grid.
+///
+/// Returns the water level of each column, or `None` for columns that are not
+/// within a lake.
+fn fill_depressions(heights: &[i32], size: i32, settings: LakeSettings) -> Vec<Option<i32>> {
+    let mut filled = vec![i32::MIN; heights.len()];
+    let mut queue = BinaryHeap::new();
+
+    for z in 0 .. size {
+        for x in 0 .. size {
+            if x == 0 || z == 0 || x == size - 1 || z == size - 1 {
+                let index = (z * size + x) as usize;
+                filled[index] = heights[index];
+                queue.push(Reverse((heights[index], x, z)));
+            }
+        }
+    }
+
+    while let Some(Reverse((level, x, z))) = queue.pop() {
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (nx, nz) = (x + dx, z + dz);
+            if nx < 0 || nz < 0 || nx >= size || nz >= size {
+                continue;
+            }
+
+            let index = (nz * size + nx) as usize;
+            if filled[index] != i32::MIN {
+                continue;
+            }
+
+            filled[index] = heights[index].max(level);
+            queue.push(Reverse((filled[index], nx, nz)));
+        }
+    }
+
+    filled
+        .into_iter()
+        .zip(heights.iter())
+        .map(|(level, height)| {
+            let depth = level - height;
+            (depth >= settings.min_depth && depth <= settings.max_depth).then_some(level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::combinators::{GenMask, LayeredGenerator, MaskedGenerator};
+    use crate::ecs::components::WorldGenerator;
+    use crate::pipeline::GenPipeline;
+    use crate::test_util::ground;
+
+    /// A flat terrain at height 10 with a pit of height 5 near the origin.
+    fn height(column: IVec2) -> i32 {
+        match (column - IVec2::new(8, 8)).abs().max_element() <= 4 {
+            true => 5,
+            false => 10,
+        }
+    }
+
+    /// Creates a generator for the terrain described by [`height`].
+    fn pit() -> LayeredGenerator<u8> {
+        LayeredGenerator::new(ground(1, 5)).with_layer(MaskedGenerator::new(
+            ground(1, 10),
+            GenMask::custom(|block_coords| {
+                height(IVec2::new(block_coords.x, block_coords.z)) == 10
+            }),
+        ))
+    }
+
+    #[test]
+    fn fill_lake() {
+        let pipeline = GenPipeline::new(0, pit()).with_stage(
+            WaterStage::new(2, 0, height, |block: u8| block != 2)
+                .with_lakes(LakeSettings::default()),
+        );
+
+        let storage = pipeline.generate_chunk(IVec3::ZERO).unwrap();
+        let column = |x, z| {
+            (4 ..= 11)
+                .map(|y| storage.get_block(IVec3::new(x, y, z)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(column(8, 8), vec![1, 1, 2, 2, 2, 2, 2, 0]);
+        assert_eq!(column(0, 0), vec![1, 1, 1, 1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn evict_least_recently_used_plans() {
+        let plan = || {
+            Arc::new(WaterPlan {
+                columns: vec![],
+            })
+        };
+
+        let mut cache = WaterPlanCache::new(2);
+        cache.insert(IVec2::ZERO, plan());
+        cache.insert(IVec2::X, plan());
+        assert!(cache.get(IVec2::ZERO).is_some());
+
+        cache.insert(IVec2::Y, plan());
+        assert!(cache.get(IVec2::ZERO).is_some());
+        assert!(cache.get(IVec2::X).is_none());
+        assert!(cache.get(IVec2::Y).is_some());
+    }
+}
@@ -0,0 +1,128 @@
+//! Synchronous world generation outside of the ECS, for previews, tools, and
+//! tests.
+
+use bones3_core::math::Region;
+use bones3_core::storage::{BlockData, WorldSnapshot};
+
+use crate::ecs::components::WorldGenerator;
+use crate::error::WorldGenError;
+
+/// Generates every chunk within the given region of chunk coordinates using the
+/// given world generator, and returns them as a world snapshot.
+///
+/// This runs synchronously on the calling thread and does not spawn any
+/// entities, so it may be used to generate editor thumbnails or to tune world
+/// generators without running the chunk loading pipeline.
+///
+/// If any chunk fails to generate, the first error is returned. The returned
+/// snapshot can be turned into a single preview mesh using
+/// `build_snapshot_mesh` from the `bones3_remesh` crate.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bones3_core::math::Region;
+/// # use bones3_worldgen::generators::FlatWorldGenerator;
+/// # use bones3_worldgen::preview::generate_region;
+/// let generator = FlatWorldGenerator::new(vec![(4, 1u8)]);
+/// let snapshot = generate_region(
+///     &generator,
+///     Region::from_points(IVec3::NEG_ONE, IVec3::ONE),
+/// )?;
+/// assert_eq!(snapshot.len(), 27);
+/// # Ok::<(), bones3_worldgen::error::WorldGenError>(())
+/// ```
+pub fn generate_region<T, G>(
+    generator: &G,
+    chunk_region: Region,
+) -> Result<WorldSnapshot<T>, WorldGenError>
+where
+    T: BlockData,
+    G: WorldGenerator<T> + ?Sized,
+{
+    let mut snapshot = WorldSnapshot::new();
+    for chunk_coords in chunk_region.iter() {
+        snapshot.insert_chunk(chunk_coords, generator.generate_chunk(chunk_coords)?);
+    }
+
+    Ok(snapshot)
+}
+
+/// Generates every chunk within the given region of chunk coordinates using the
+/// given world generator, spread across the given number of threads, and
+/// returns them as a world snapshot.
+///
+/// Like [`generate_region`], this does not spawn any entities, and is intended
+/// for tools such as world pre-generators and benchmarks. The calling thread
+/// blocks until all chunks have been generated. A thread count of zero is
+/// treated as one. If any chunk fails to generate, the first error is
+/// returned.
+pub fn generate_region_parallel<T, G>(
+    generator: &G,
+    chunk_region: Region,
+    threads: usize,
+) -> Result<WorldSnapshot<T>, WorldGenError>
+where
+    T: BlockData,
+    G: WorldGenerator<T> + ?Sized,
+{
+    let chunks: Vec<_> = chunk_region.iter().collect();
+    let batch_size = chunks.len() / threads.max(1) + 1;
+
+    let batches = std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .chunks(batch_size)
+            .map(|batch| {
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .map(|&chunk_coords| {
+                            Ok((chunk_coords, generator.generate_chunk(chunk_coords)?))
+                        })
+                        .collect::<Result<Vec<_>, WorldGenError>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut snapshot = WorldSnapshot::new();
+    for batch in batches {
+        for (chunk_coords, storage) in batch? {
+            snapshot.insert_chunk(chunk_coords, storage);
+        }
+    }
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::ground;
+
+    #[test]
+    fn generate_snapshot_region() {
+        let region = Region::from_points(IVec3::new(-1, -1, -1), IVec3::new(1, 0, 1));
+        let floor = ground(1, -1);
+        let snapshot = generate_region(&floor, region).unwrap();
+
+        assert_eq!(snapshot.len(), 18);
+        assert_eq!(snapshot.chunk_region(), Some(region));
+        assert_eq!(snapshot.get_block(IVec3::new(-12, -1, 20)), 1);
+        assert_eq!(snapshot.get_block(IVec3::new(-12, 0, 20)), 0);
+        assert_eq!(snapshot.get_block(IVec3::new(0, -40, 0)), 0);
+
+        let parallel = generate_region_parallel(&floor, region, 4).unwrap();
+        assert_eq!(parallel.len(), 18);
+        assert_eq!(parallel.chunk_region(), Some(region));
+        assert_eq!(parallel.get_block(IVec3::new(-12, -1, 20)), 1);
+        assert_eq!(parallel.get_block(IVec3::new(-12, 0, 20)), 0);
+    }
+}
@@ -0,0 +1,363 @@
+//! Recording and replaying of chunk generation, for debugging world
+//! generators.
+//!
+//! When a single chunk out of thousands generates incorrectly, a
+//! [`GenRecorder`] can be attached to the world generator to log every chunk
+//! that is generated, along with how long each stage took. Since world
+//! generators are deterministic, the faulty chunk can then be generated again
+//! in isolation and its contents dumped for inspection.
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bones3_core::prelude::*;
+//! # use bones3_worldgen::ecs::components::WorldGenerator;
+//! # use bones3_worldgen::error::WorldGenError;
+//! # use bones3_worldgen::pipeline::{CaveStage, GenPipeline};
+//! # use bones3_worldgen::recording::{dump_chunk, replay_chunk, GenRecorder};
+//! # #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+//! # enum BlockState {
+//! #     #[default]
+//! #     Air,
+//! #     Stone,
+//! # }
+//! # #[derive(Default)]
+//! # struct TerrainGenerator;
+//! # impl WorldGenerator<BlockState> for TerrainGenerator {
+//! #     fn generate_chunk(&self, _: IVec3) -> Result<VoxelStorage<BlockState>, WorldGenError> {
+//! #         Ok(VoxelStorage::filled(BlockState::Stone))
+//! #     }
+//! # }
+//! # let seed = 42;
+//! let recorder = GenRecorder::default();
+//! let generator = GenPipeline::new(seed, TerrainGenerator::default())
+//!     .with_stage(CaveStage::new(BlockState::Air, |block| block == BlockState::Stone))
+//!     .with_recorder(recorder.clone());
+//! # generator.generate_chunk(IVec3::new(12, -3, 40))?;
+//!
+//! // Later, after finding a broken chunk...
+//! let record = recorder.find(IVec3::new(12, -3, 40)).unwrap();
+//! let storage = replay_chunk(&generator, &record)?;
+//! println!("{}", dump_chunk(&storage));
+//! # Ok::<(), WorldGenError>(())
+//! ```
+
+use std::cell::Cell;
+use std::fmt::{Debug, Write};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bones3_core::storage::{BlockData, VoxelStorage};
+
+use crate::ecs::components::WorldGenerator;
+use crate::error::WorldGenError;
+
+/// The time that a single stage of a generation pipeline took to run for a
+/// chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageTiming {
+    /// The name of the stage.
+    pub name: &'static str,
+
+    /// The time the stage took to run.
+    pub duration: Duration,
+}
+
+/// A record of a single chunk that was generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenRecord {
+    /// The order in which this chunk was generated, relative to all other
+    /// chunks recorded by the same recorder.
+    pub sequence: usize,
+
+    /// The coordinates of the chunk that was generated.
+    pub chunk_coords: IVec3,
+
+    /// The world seed that was used to generate the chunk, if the generator
+    /// has one.
+    pub seed: Option<u64>,
+
+    /// The time each stage took to run, in the order they were applied. This
+    /// is empty for generators that do not have stages.
+    pub stages: Vec<StageTiming>,
+
+    /// The total time that it took to generate the chunk.
+    pub total: Duration,
+
+    /// The error the chunk failed to generate with, if any.
+    pub error: Option<WorldGenError>,
+}
+
+/// A shared, thread-safe log of all chunks that have been generated by the
+/// world generators it is attached to.
+///
+/// Clones of a recorder share the same log, so one clone may be attached to a
+/// generator while another is kept for inspection.
+#[derive(Debug, Clone, Default)]
+pub struct GenRecorder {
+    /// The shared state of the recorder.
+    inner: Arc<Mutex<RecorderState>>,
+}
+
+/// The state that is shared between clones of a [`GenRecorder`].
+#[derive(Debug, Default)]
+struct RecorderState {
+    /// The recorded chunks, oldest first.
+    records: Vec<GenRecord>,
+
+    /// The sequence number to assign to the next record.
+    next_sequence: usize,
+
+    /// The maximum number of records to keep, if limited.
+    limit: Option<usize>,
+}
+
+impl GenRecorder {
+    /// Creates a new recorder that only keeps the given number of most recent
+    /// records.
+    pub fn with_limit(limit: usize) -> Self {
+        let recorder = Self::default();
+        recorder.lock().limit = Some(limit);
+        recorder
+    }
+
+    /// Gets a copy of all records, oldest first.
+    pub fn records(&self) -> Vec<GenRecord> {
+        self.lock().records.clone()
+    }
+
+    /// Finds the most recent record of the chunk at the given chunk
+    /// coordinates.
+    pub fn find(&self, chunk_coords: IVec3) -> Option<GenRecord> {
+        self.lock()
+            .records
+            .iter()
+            .rev()
+            .find(|record| record.chunk_coords == chunk_coords)
+            .cloned()
+    }
+
+    /// Gets the number of records that are currently kept.
+    pub fn len(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    /// Checks whether there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.lock().records.is_empty()
+    }
+
+    /// Removes all records.
+    pub fn clear(&self) {
+        self.lock().records.clear();
+    }
+
+    /// Adds a new record, assigning it the next sequence number.
+    ///
+    /// Chunks that are generated by [`replay_chunk`] are not recorded.
+    pub(crate) fn push(&self, mut record: GenRecord) {
+        if is_replaying() {
+            return;
+        }
+
+        let mut state = self.lock();
+        record.sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.records.push(record);
+
+        if let Some(limit) = state.limit {
+            let excess = state.records.len().saturating_sub(limit);
+            state.records.drain(.. excess);
+        }
+    }
+
+    /// Locks the shared state of this recorder.
+    ///
+    /// A poisoned lock is ignored, as a panic while recording cannot leave the
+    /// records in an invalid state.
+    fn lock(&self) -> MutexGuard<'_, RecorderState> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// A world generator wrapper that records every chunk generated by the inner
+/// generator.
+///
+/// Use [`GenPipeline::with_recorder`](crate::pipeline::GenPipeline::with_recorder)
+/// instead to also record the seed and stage timings of a pipeline.
+pub struct RecordingGenerator<T, G>
+where
+    T: BlockData,
+    G: WorldGenerator<T>,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// The generator being recorded.
+    generator: G,
+
+    /// The recorder to log chunks to.
+    recorder: GenRecorder,
+}
+
+impl<T, G> RecordingGenerator<T, G>
+where
+    T: BlockData,
+    G: WorldGenerator<T>,
+{
+    /// Wraps the given generator, logging all chunks it generates to the given
+    /// recorder.
+    pub fn new(generator: G, recorder: GenRecorder) -> Self {
+        Self {
+            _phantom: PhantomData,
+            generator,
+            recorder,
+        }
+    }
+}
+
+impl<T, G> WorldGenerator<T> for RecordingGenerator<T, G>
+where
+    T: BlockData,
+    G: WorldGenerator<T>,
+{
+    fn generate_chunk(&self, chunk_coords: IVec3) -> Result<VoxelStorage<T>, WorldGenError> {
+        let start = Instant::now();
+        let result = self.generator.generate_chunk(chunk_coords);
+
+        self.recorder.push(GenRecord {
+            sequence: 0,
+            chunk_coords,
+            seed: None,
+            stages: vec![],
+            total: start.elapsed(),
+            error: result.as_ref().err().cloned(),
+        });
+
+        result
+    }
+}
+
+/// Generates the chunk of the given record again, in isolation.
+///
+/// World generators are expected to be deterministic, so the result should
+/// match the chunk that was originally generated. This does not add a new
+/// record to any recorder attached to the generator.
+pub fn replay_chunk<T, G>(
+    generator: &G,
+    record: &GenRecord,
+) -> Result<VoxelStorage<T>, WorldGenError>
+where
+    T: BlockData,
+    G: WorldGenerator<T> + ?Sized,
+{
+    let _guard = ReplayGuard::enter();
+    generator.generate_chunk(record.chunk_coords)
+}
+
+/// Dumps the contents of the given chunk as text, one horizontal layer at a
+/// time from the bottom up, with one row of blocks per line.
+pub fn dump_chunk<T>(storage: &VoxelStorage<T>) -> String
+where
+    T: BlockData + Debug,
+{
+    let mut dump = String::new();
+    for y in 0 .. 16 {
+        let _ = writeln!(dump, "y = {y}");
+        for z in 0 .. 16 {
+            let row: Vec<_> = (0 .. 16)
+                .map(|x| format!("{:?}", storage.get_block(IVec3::new(x, y, z))))
+                .collect();
+            let _ = writeln!(dump, "  z = {z:>2}: {}", row.join(" "));
+        }
+    }
+
+    dump
+}
+
+thread_local! {
+    /// Whether a chunk is currently being replayed on this thread.
+    static REPLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as replaying a chunk while it is alive, so that
+/// replayed chunks are not recorded again.
+struct ReplayGuard {
+    /// Whether the thread was already replaying when this guard was created.
+    was_replaying: bool,
+}
+
+impl ReplayGuard {
+    /// Marks the current thread as replaying.
+    fn enter() -> Self {
+        Self {
+            was_replaying: REPLAYING.with(|r| r.replace(true)),
+        }
+    }
+}
+
+impl Drop for ReplayGuard {
+    fn drop(&mut self) {
+        REPLAYING.with(|r| r.set(self.was_replaying));
+    }
+}
+
+/// Checks whether a chunk is currently being replayed on this thread.
+fn is_replaying() -> bool {
+    REPLAYING.with(|r| r.get())
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::pipeline::{GenContext, GenPipeline, GenStage};
+    use crate::test_util::ground;
+
+    struct Marker;
+
+    impl GenStage<u8> for Marker {
+        fn apply(&self, ctx: &mut GenContext<u8>) {
+            let origin = ctx.chunk_coords() * 16;
+            ctx.set_block(origin + IVec3::ONE, 2);
+        }
+
+        fn name(&self) -> &'static str {
+            "marker"
+        }
+    }
+
+    #[test]
+    fn record_and_replay_chunks() {
+        let recorder = GenRecorder::with_limit(2);
+        let pipeline = GenPipeline::new(7, ground(1, -1))
+            .with_stage(Marker)
+            .with_recorder(recorder.clone());
+
+        for y in -1 ..= 1 {
+            pipeline.generate_chunk(IVec3::new(0, y, 0)).unwrap();
+        }
+
+        let records = recorder.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].sequence, 1);
+        assert_eq!(records[0].seed, Some(7));
+        assert_eq!(records[0].stages.len(), 1);
+        assert_eq!(records[0].stages[0].name, "marker");
+
+        let record = recorder.find(IVec3::new(0, 1, 0)).unwrap();
+        let replayed = replay_chunk(&pipeline, &record).unwrap();
+        assert_eq!(replayed.get_block(IVec3::ONE), 2);
+        assert_eq!(recorder.len(), 2);
+
+        let base_only = pipeline.generate_stages(IVec3::new(0, 1, 0), 0).unwrap();
+        assert_eq!(base_only.get_block(IVec3::ONE), 0);
+
+        let dump = dump_chunk(&replayed);
+        assert!(dump.starts_with("y = 0\n  z =  0: 0 0"));
+        assert!(dump.contains("y = 1\n  z =  0: 0 0"));
+        assert!(dump.contains("  z =  1: 0 2 0"));
+    }
+}
@@ -0,0 +1,15 @@
+//! Shared world generators for the unit tests of this crate.
+
+use crate::generators::FlatWorldGenerator;
+
+/// Creates a generator that fills the 64 blocks at and below the given height
+/// with the given block.
+pub(crate) fn ground(block: u8, height: i32) -> FlatWorldGenerator<u8> {
+    FlatWorldGenerator::new(vec![(64, block)]).with_base_height(height - 63)
+}
+
+/// Creates a generator that fills all blocks between `y = -32` and `y = 31`
+/// with the given block.
+pub(crate) fn fill(block: u8) -> FlatWorldGenerator<u8> {
+    ground(block, 31)
+}
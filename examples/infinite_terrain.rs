@@ -4,17 +4,18 @@ use bones3_core::util::anchor::ChunkAnchor;
 use bones3_remesh::ecs::resources::ChunkMaterialList;
 use bones3_remesh::mesh::block_model::{BlockOcclusion, BlockShape};
 use bones3_remesh::vertex_data::{CubeModelBuilder, ShapeBuilder};
-use bones3_remesh::{Bones3RemeshPlugin, RemeshAnchor};
+use bones3_remesh::RemeshAnchor;
 use bones3_worldgen::ecs::components::{WorldGenerator, WorldGeneratorHandler};
-use bones3_worldgen::{Bones3WorldGenPlugin, WorldGenAnchor};
+use bones3_worldgen::error::WorldGenError;
+use bones3_worldgen::WorldGenAnchor;
 
 fn main() {
     App::new()
         .add_plugins((
             DefaultPlugins,
-            Bones3CorePlugin::<BlockState>::default(),
-            Bones3RemeshPlugin::<BlockState>::default(),
-            Bones3WorldGenPlugin::<BlockState>::default(),
+            Bones3PluginGroup::<BlockState>::new()
+                .add_mesh_support()
+                .add_world_generation(),
         ))
         .add_systems(Startup, init)
         .add_systems(Update, fly)
@@ -54,7 +55,10 @@ struct GrassyHillsWorld {
 }
 
 impl WorldGenerator<BlockState> for GrassyHillsWorld {
-    fn generate_chunk(&self, chunk_coords: IVec3) -> VoxelStorage<BlockState> {
+    fn generate_chunk(
+        &self,
+        chunk_coords: IVec3,
+    ) -> Result<VoxelStorage<BlockState>, WorldGenError> {
         let mut block_storage = VoxelStorage::default();
 
         for block_pos in Region::CHUNK.shift(chunk_coords * 16).iter() {
@@ -68,7 +72,7 @@ impl WorldGenerator<BlockState> for GrassyHillsWorld {
             block_storage.set_block(block_pos, block_state);
         }
 
-        block_storage
+        Ok(block_storage)
     }
 }
 
@@ -0,0 +1,644 @@
+//! A small voxel world editor, combining brush editing, copy and paste,
+//! undo and redo, saving and loading, and a gizmo-based debug overlay.
+//!
+//! Controls:
+//! - `WASD`, `Space`, `Shift`, and the right mouse button move the camera.
+//! - `Left Click` paints blocks onto the targeted face, and `Middle Click`
+//!   erases blocks around the targeted block.
+//! - `1`, `2`, and `3` select the block material, `Tab` switches the brush
+//!   shape, and `[` and `]` change the brush radius.
+//! - `C` copies the blocks within the brush area, and `V` pastes them at the
+//!   targeted face.
+//! - `Ctrl + Z` undoes the last edit, and `Ctrl + Y` redoes it.
+//! - `F5` saves all modified chunks, and `F9` reloads all chunks from disk.
+//!   Chunks that were never saved are reset to flat ground.
+//! - `F3` toggles the debug overlay.
+
+use std::time::Duration;
+
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_bones3::core::util::brush::{BrushOp, BrushShape, VoxelBrush, VoxelEditHistory};
+use bevy_bones3::prelude::*;
+use bones3_remesh::ecs::resources::ChunkMaterialList;
+use bones3_remesh::mesh::block_model::{BlockOcclusion, BlockShape};
+use bones3_remesh::vertex_data::{CubeModelBuilder, ShapeBuilder};
+
+/// The directory that the edited world is saved into.
+const SAVE_DIR: &str = "target/world_editor_save";
+
+/// The maximum distance, in blocks, at which blocks can be targeted.
+const REACH: f32 = 64.0;
+
+fn main() {
+    let store = DirectoryChunkStore::open(SAVE_DIR).expect("Failed to open save directory");
+
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            Bones3PluginGroup::<BlockState>::new().add_mesh_support(),
+            FlyCameraPlugin,
+            WorldEditorPlugin,
+        ))
+        .insert_resource(EditorStore(PersistentWorld::new(store)))
+        .add_systems(Startup, init)
+        .add_systems(
+            Update,
+            (
+                update_cursor.before(EditorSet),
+                (draw_overlay, update_title).after(EditorSet),
+            ),
+        )
+        .run();
+}
+
+#[derive(Debug, Default, Reflect, Clone, Copy, PartialEq, Eq)]
+enum BlockState {
+    #[default]
+    Empty,
+    Solid(u16),
+}
+
+impl BlockShape for BlockState {
+    fn write_shape(&self, shape_builder: &mut ShapeBuilder) {
+        match self {
+            BlockState::Empty => {},
+            BlockState::Solid(material) => {
+                shape_builder.add_shape(
+                    CubeModelBuilder::new().set_occlusion(shape_builder.get_occlusion()),
+                    *material,
+                );
+            },
+        }
+    }
+
+    fn check_occlude(&self, _: BlockOcclusion, _: Self) -> bool {
+        match self {
+            BlockState::Empty => false,
+            BlockState::Solid(_) => true,
+        }
+    }
+}
+
+impl PersistentBlock for BlockState {
+    fn encode_block(&self, out: &mut Vec<u8>) {
+        match self {
+            BlockState::Empty => out.push(0),
+            BlockState::Solid(material) => {
+                out.push(1);
+                material.encode_block(out);
+            },
+        }
+    }
+
+    fn decode_block(input: &mut &[u8]) -> Result<Self, PersistenceError> {
+        match u8::decode_block(input)? {
+            0 => Ok(BlockState::Empty),
+            1 => Ok(BlockState::Solid(u16::decode_block(input)?)),
+            _ => Err(PersistenceError::InvalidBlock),
+        }
+    }
+}
+
+/// The editing systems of the world editor. These only depend on the core
+/// plugin, so they can also run within a headless app.
+struct WorldEditorPlugin;
+
+impl Plugin for WorldEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PersistencePlugin::<BlockState>::default())
+            .init_resource::<EditorCursor>()
+            .init_resource::<EditorBrush>()
+            .init_resource::<Clipboard>()
+            .init_resource::<OverlaySettings>()
+            .init_resource::<VoxelEditHistory<BlockState>>()
+            .insert_resource(PersistenceSettings {
+                // Only save when requested.
+                autosave_delay: f32::INFINITY,
+                ..default()
+            })
+            .add_systems(
+                Update,
+                (
+                    configure_brush,
+                    paint_blocks,
+                    copy_blocks.run_if(input_just_pressed(KeyCode::C)),
+                    paste_blocks.run_if(input_just_pressed(KeyCode::V)),
+                    undo_redo,
+                    save_world.run_if(input_just_pressed(KeyCode::F5)),
+                    load_world.run_if(input_just_pressed(KeyCode::F9)),
+                    toggle_overlay.run_if(input_just_pressed(KeyCode::F3)),
+                )
+                    .chain()
+                    .in_set(EditorSet),
+            );
+    }
+}
+
+/// The system set containing all editing systems.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+struct EditorSet;
+
+/// The block that is currently targeted by the camera.
+#[derive(Debug, Default, Resource)]
+struct EditorCursor {
+    /// The targeted block, if any.
+    hit: Option<BlockHit>,
+}
+
+impl EditorCursor {
+    /// Gets the coordinates of the empty block in front of the targeted face.
+    fn place_coords(&self) -> Option<IVec3> {
+        let hit = self.hit?;
+        Some(hit.block_coords + hit.face.map_or(IVec3::ZERO, |face| face.normal()))
+    }
+}
+
+/// The current brush settings.
+#[derive(Debug, Resource)]
+struct EditorBrush {
+    /// The brush that is applied when painting or erasing.
+    brush: VoxelBrush,
+
+    /// The block that is painted.
+    block: BlockState,
+}
+
+impl Default for EditorBrush {
+    fn default() -> Self {
+        Self {
+            brush: VoxelBrush::sphere(2.0),
+            block: BlockState::Solid(0),
+        }
+    }
+}
+
+/// The blocks that were most recently copied, relative to the center of the
+/// copied area.
+#[derive(Debug, Default, Resource)]
+struct Clipboard {
+    /// The copied blocks.
+    blocks: Vec<(IVec3, BlockState)>,
+}
+
+/// The chunk store that the edited world is saved into.
+#[derive(Resource)]
+struct EditorStore(PersistentWorld);
+
+/// Whether the debug overlay is shown.
+#[derive(Debug, Resource)]
+struct OverlaySettings {
+    /// Whether to draw chunk borders and the brush preview.
+    enabled: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+        }
+    }
+}
+
+fn init(
+    store: Res<EditorStore>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut chunk_materials: ResMut<ChunkMaterialList>,
+    mut commands: VoxelCommands,
+) {
+    for color in [Color::GRAY, Color::DARK_GREEN, Color::ORANGE_RED] {
+        chunk_materials.add_material(materials.add(color.into()), None);
+    }
+
+    let mut world = commands.spawn_world((SpatialBundle::default(), store.0.clone()));
+    let world_id = world.id();
+
+    let chunk_region = Region::from_points(IVec3::new(-2, -1, -2), IVec3::new(1, 0, 1));
+    for chunk_coords in chunk_region.iter() {
+        // Use the saved chunk from a previous session, if there is one.
+        let storage = match store.0.load_chunk(chunk_coords) {
+            Ok(Some(storage)) => storage,
+            Ok(None) => flat_chunk(chunk_coords),
+            Err(err) => {
+                warn!("Failed to load chunk {chunk_coords}: {err}");
+                flat_chunk(chunk_coords)
+            },
+        };
+
+        world.spawn_chunk(chunk_coords, storage).unwrap();
+    }
+
+    let commands = commands.commands();
+
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -0.5, 0.25, 0.0)),
+        directional_light: DirectionalLight {
+            illuminance: 50000.0,
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.insert_resource(AmbientLight {
+        color:      Color::WHITE,
+        brightness: 2.5,
+    });
+
+    spawn_fly_camera(
+        commands,
+        world_id,
+        Transform::from_xyz(0.0, 12.0, 24.0).looking_at(Vec3::ZERO, Vec3::Y),
+        UVec3::new(4, 2, 4),
+    );
+}
+
+/// Creates a chunk with flat ground below `y = 0`.
+fn flat_chunk(chunk_coords: IVec3) -> VoxelStorage<BlockState> {
+    let mut storage = VoxelStorage::default();
+    if chunk_coords.y < 0 {
+        for block_coords in Region::CHUNK.shift(chunk_coords * 16).iter() {
+            let material = if block_coords.y == -1 { 1 } else { 0 };
+            storage.set_block(block_coords, BlockState::Solid(material));
+        }
+    }
+    storage
+}
+
+/// Gets the id of the edited world.
+fn world_id(worlds: &Query<Entity, With<VoxelWorld>>) -> Option<Entity> {
+    worlds.iter().next()
+}
+
+/// This system targets the block in the center of the screen.
+///
+/// The world sits at the origin with the default block scale, so world space
+/// positions are equal to block coordinates.
+fn update_cursor(
+    cameras: Query<&GlobalTransform, With<FlyCamera>>,
+    worlds: Query<Entity, With<VoxelWorld>>,
+    query: VoxelQuery<&VoxelStorage<BlockState>>,
+    mut cursor: ResMut<EditorCursor>,
+) {
+    let (Ok(camera), Some(world_id)) = (cameras.get_single(), world_id(&worlds)) else {
+        return;
+    };
+
+    let ray = Ray {
+        origin:    camera.translation(),
+        direction: camera.forward(),
+    };
+
+    cursor.hit = query
+        .get_world(world_id)
+        .ok()
+        .and_then(|world| world.raycast(ray, REACH, |block| block != BlockState::Empty));
+}
+
+/// This system changes the brush settings based on keyboard input.
+fn configure_brush(keys: Res<Input<KeyCode>>, mut brush: ResMut<EditorBrush>) {
+    for (key, material) in [(KeyCode::Key1, 0), (KeyCode::Key2, 1), (KeyCode::Key3, 2)] {
+        if keys.just_pressed(key) {
+            brush.block = BlockState::Solid(material);
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Tab) {
+        brush.brush.shape = match brush.brush.shape {
+            BrushShape::Sphere => BrushShape::Cube,
+            BrushShape::Cube => BrushShape::Sphere,
+        };
+    }
+
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        brush.brush.radius = (brush.brush.radius - 1.0).max(0.0);
+    }
+
+    if keys.just_pressed(KeyCode::BracketRight) {
+        brush.brush.radius = (brush.brush.radius + 1.0).min(8.0);
+    }
+}
+
+/// This system paints or erases blocks around the targeted block.
+fn paint_blocks(
+    mouse: Res<Input<MouseButton>>,
+    cursor: Res<EditorCursor>,
+    brush: Res<EditorBrush>,
+    mut commands: VoxelCommands,
+) {
+    let Some(hit) = cursor.hit else {
+        return;
+    };
+
+    let (center, op) = if mouse.just_pressed(MouseButton::Left) {
+        (cursor.place_coords().unwrap(), BrushOp::Set(brush.block))
+    } else if mouse.just_pressed(MouseButton::Middle) {
+        (hit.block_coords, BrushOp::Erase)
+    } else {
+        return;
+    };
+
+    if let Ok(mut world) = commands.get_world(hit.world_id) {
+        world.apply_brush(center, brush.brush, op);
+    }
+}
+
+/// This system copies all blocks within the brush area around the targeted
+/// block into the clipboard.
+fn copy_blocks(
+    cursor: Res<EditorCursor>,
+    brush: Res<EditorBrush>,
+    query: VoxelQuery<&VoxelStorage<BlockState>>,
+    mut clipboard: ResMut<Clipboard>,
+) {
+    let Some(hit) = cursor.hit else {
+        return;
+    };
+
+    let Ok(world) = query.get_world(hit.world_id) else {
+        return;
+    };
+
+    let center = hit.block_coords;
+    clipboard.blocks = brush
+        .brush
+        .region(center)
+        .iter()
+        .filter(|block_coords| brush.brush.affects(center, *block_coords))
+        .filter_map(|block_coords| {
+            let block = world.get_chunk(block_coords >> 4)?.get_block(block_coords);
+            Some((block_coords - center, block))
+        })
+        .collect();
+
+    info!("Copied {} blocks", clipboard.blocks.len());
+}
+
+/// This system pastes the clipboard in front of the targeted face.
+///
+/// The paste is applied as a single transaction, so it is undone in one step,
+/// and is rolled back if any pasted block lies outside of the loaded chunks.
+fn paste_blocks(cursor: Res<EditorCursor>, clipboard: Res<Clipboard>, mut commands: VoxelCommands) {
+    let (Some(hit), Some(center)) = (cursor.hit, cursor.place_coords()) else {
+        return;
+    };
+
+    let blocks = clipboard.blocks.clone();
+    commands.transaction::<BlockState, _>(hit.world_id, move |tx| {
+        for (offset, block) in blocks {
+            tx.set_block(center + offset, block)?;
+        }
+        Ok(())
+    });
+}
+
+/// This system undoes or redoes the most recent edit.
+fn undo_redo(keys: Res<Input<KeyCode>>, mut commands: VoxelCommands) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Z) {
+        commands.undo_edit::<BlockState>();
+    }
+
+    if keys.just_pressed(KeyCode::Y) {
+        commands.redo_edit::<BlockState>();
+    }
+}
+
+/// This system saves all modified chunks.
+fn save_world(world: &mut World) {
+    let report = flush_all::<BlockState>(world, Duration::from_secs(5));
+    info!(
+        "Saved {} chunks, {} failed",
+        report.saved,
+        report.failed + report.remaining
+    );
+}
+
+/// This system replaces all loaded chunks with their last saved version, or
+/// with flat ground if they were never saved.
+fn load_world(
+    chunks: Query<&VoxelChunk, With<VoxelStorage<BlockState>>>,
+    worlds: Query<&PersistentWorld>,
+    mut history: ResMut<VoxelEditHistory<BlockState>>,
+    mut commands: VoxelCommands,
+) {
+    for chunk in chunks.iter() {
+        let Ok(persistent) = worlds.get(chunk.world_id()) else {
+            continue;
+        };
+
+        let storage = match persistent.load_chunk::<BlockState>(chunk.chunk_coords()) {
+            Ok(Some(storage)) => storage,
+            Ok(None) => flat_chunk(chunk.chunk_coords()),
+            Err(err) => {
+                warn!("Failed to load chunk {}: {err}", chunk.chunk_coords());
+                continue;
+            },
+        };
+
+        let Ok(mut world) = commands.get_world(chunk.world_id()) else {
+            continue;
+        };
+
+        if let Ok(mut chunk_commands) = world.get_chunk(chunk.chunk_coords()) {
+            chunk_commands.replace_storage(storage);
+        }
+    }
+
+    // Edits made before loading can no longer be undone safely.
+    history.clear();
+}
+
+/// This system toggles the debug overlay.
+fn toggle_overlay(mut overlay: ResMut<OverlaySettings>) {
+    overlay.enabled = !overlay.enabled;
+}
+
+/// This system draws the chunk borders, the targeted block, and the brush
+/// area.
+fn draw_overlay(
+    overlay: Res<OverlaySettings>,
+    cursor: Res<EditorCursor>,
+    brush: Res<EditorBrush>,
+    chunks: Query<&VoxelChunk>,
+    mut gizmos: Gizmos,
+) {
+    if !overlay.enabled {
+        return;
+    }
+
+    for chunk in chunks.iter() {
+        let center = (chunk.chunk_coords() * 16).as_vec3() + Vec3::splat(8.0);
+        gizmos.cuboid(
+            Transform::from_translation(center).with_scale(Vec3::splat(16.0)),
+            Color::YELLOW,
+        );
+    }
+
+    let Some(hit) = cursor.hit else {
+        return;
+    };
+
+    gizmos.cuboid(
+        Transform::from_translation(hit.block_coords.as_vec3() + Vec3::splat(0.5))
+            .with_scale(Vec3::splat(1.01)),
+        Color::WHITE,
+    );
+
+    let center = hit.block_coords.as_vec3() + Vec3::splat(0.5);
+    match brush.brush.shape {
+        BrushShape::Sphere => {
+            gizmos.sphere(center, Quat::IDENTITY, brush.brush.radius, Color::CYAN);
+        },
+        BrushShape::Cube => {
+            let size = brush.brush.radius * 2.0 + 1.0;
+            gizmos.cuboid(
+                Transform::from_translation(center).with_scale(Vec3::splat(size)),
+                Color::CYAN,
+            );
+        },
+    }
+}
+
+/// This system shows the current editor state within the window title.
+fn update_title(
+    brush: Res<EditorBrush>,
+    clipboard: Res<Clipboard>,
+    history: Res<VoxelEditHistory<BlockState>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    window.title = format!(
+        "World Editor | {:?} {:?} r={} | Clipboard: {} blocks | Undo: {} Redo: {}",
+        brush.block,
+        brush.brush.shape,
+        brush.brush.radius,
+        clipboard.blocks.len(),
+        history.undo_len(),
+        history.redo_len(),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn get_block(app: &mut App, block_coords: IVec3) -> BlockState {
+        app.world
+            .query::<(&VoxelChunk, &VoxelStorage<BlockState>)>()
+            .iter(&app.world)
+            .find(|(chunk, _)| chunk.chunk_coords() == block_coords >> 4)
+            .map(|(_, storage)| storage.get_block(block_coords))
+            .unwrap()
+    }
+
+    fn press<T>(app: &mut App, inputs: &[T])
+    where
+        T: Copy + Eq + std::hash::Hash + Send + Sync + 'static,
+    {
+        let mut input = app.world.resource_mut::<Input<T>>();
+        for key in inputs {
+            input.press(*key);
+        }
+        app.update();
+
+        let mut input = app.world.resource_mut::<Input<T>>();
+        input.release_all();
+        input.clear();
+    }
+
+    #[test]
+    fn edit_save_and_load() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            Bones3CorePlugin::<BlockState>::default(),
+            WorldEditorPlugin,
+        ))
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<Input<MouseButton>>();
+
+        let world_id = app
+            .world
+            .spawn((
+                VoxelWorldBundle::new(),
+                PersistentWorld::new(MemoryChunkStore::default()),
+            ))
+            .id();
+        for x in -1 ..= 0 {
+            for y in -1 ..= 0 {
+                app.world.spawn(
+                    VoxelChunkBundle::new(world_id, IVec3::new(x, y, 0))
+                        .with_storage(flat_chunk(IVec3::new(x, y, 0))),
+                );
+            }
+        }
+        app.update();
+
+        // Target the top of the ground.
+        app.world.resource_mut::<EditorCursor>().hit = Some(BlockHit {
+            world_id,
+            block_coords: IVec3::new(0, -1, 4),
+            face: Some(BlockFace::PosY),
+            distance: 1.0,
+        });
+        app.world.resource_mut::<EditorBrush>().brush = VoxelBrush::cube(0.0);
+
+        press(&mut app, &[KeyCode::F5]);
+        press(&mut app, &[KeyCode::Key3]);
+        press(&mut app, &[MouseButton::Left]);
+        assert_eq!(
+            get_block(&mut app, IVec3::new(0, 0, 4)),
+            BlockState::Solid(2)
+        );
+
+        // Copy the grass block and paste it on top of the new block.
+        press(&mut app, &[KeyCode::C]);
+        assert_eq!(app.world.resource::<Clipboard>().blocks, vec![(
+            IVec3::ZERO,
+            BlockState::Solid(1)
+        )]);
+        app.world
+            .resource_mut::<EditorCursor>()
+            .hit
+            .as_mut()
+            .unwrap()
+            .block_coords = IVec3::new(0, 0, 4);
+        press(&mut app, &[KeyCode::V]);
+        assert_eq!(
+            get_block(&mut app, IVec3::new(0, 1, 4)),
+            BlockState::Solid(1)
+        );
+
+        // Undo the paste.
+        press(&mut app, &[KeyCode::ControlLeft, KeyCode::Z]);
+        assert_eq!(get_block(&mut app, IVec3::new(0, 1, 4)), BlockState::Empty);
+        assert_eq!(
+            get_block(&mut app, IVec3::new(0, 0, 4)),
+            BlockState::Solid(2)
+        );
+
+        // Loading restores the ground as it was when saved.
+        press(&mut app, &[KeyCode::F9]);
+        app.update();
+        assert_eq!(get_block(&mut app, IVec3::new(0, 0, 4)), BlockState::Empty);
+        assert_eq!(
+            get_block(&mut app, IVec3::new(0, -1, 4)),
+            BlockState::Solid(1)
+        );
+        assert_eq!(
+            app.world
+                .resource::<VoxelEditHistory<BlockState>>()
+                .undo_len(),
+            0
+        );
+    }
+}
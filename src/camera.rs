@@ -0,0 +1,187 @@
+//! A simple fly camera that is wired to the chunk anchors of a voxel world,
+//! for use within examples and quick prototypes.
+
+use bevy::ecs::system::EntityCommands;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+#[cfg(any(feature = "meshing", feature = "worldgen"))]
+use bones3_core::util::anchor::ChunkAnchor;
+
+/// This plugin adds keyboard and mouse controls for all cameras with a
+/// [`FlyCamera`] component.
+///
+/// * `W`, `A`, `S`, `D` move the camera along its view direction.
+/// * `Space` and `Shift` move the camera up and down.
+/// * `Ctrl` moves the camera faster.
+/// * Holding the right mouse button rotates the camera with the mouse.
+#[derive(Default)]
+pub struct FlyCameraPlugin;
+
+impl Plugin for FlyCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<FlyCamera>()
+            .add_systems(Update, (rotate_fly_camera, move_fly_camera).chain());
+    }
+}
+
+/// A component that allows a camera to be controlled by the
+/// [`FlyCameraPlugin`].
+#[derive(Debug, Component, Reflect, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct FlyCamera {
+    /// The movement speed of the camera, in world units per second.
+    pub speed: f32,
+
+    /// The multiplier that is applied to the movement speed while sprinting.
+    pub sprint_multiplier: f32,
+
+    /// The rotation speed of the camera, in radians per pixel of mouse
+    /// movement.
+    pub sensitivity: f32,
+
+    /// The rotation of the camera around the Y axis, in radians.
+    pub yaw: f32,
+
+    /// The rotation of the camera around its local X axis, in radians.
+    pub pitch: f32,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self {
+            speed:             10.0,
+            sprint_multiplier: 4.0,
+            sensitivity:       0.003,
+            yaw:               0.0,
+            pitch:             0.0,
+        }
+    }
+}
+
+impl FlyCamera {
+    /// Creates a new fly camera with a yaw and pitch that match the rotation
+    /// of the given transform.
+    pub fn from_transform(transform: &Transform) -> Self {
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        Self {
+            yaw,
+            pitch,
+            ..default()
+        }
+    }
+
+    /// Sets the movement speed of this camera.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Gets the rotation of this camera, based off its yaw and pitch.
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+}
+
+/// Spawns a new 3D camera with a [`FlyCamera`] controller at the given
+/// transform.
+///
+/// The camera is given a chunk anchor with the given view distance for each
+/// of the enabled Bones Cubed features, so that chunks around the camera are
+/// generated and meshed as it moves through the given voxel world.
+pub fn spawn_fly_camera<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    world_id: Entity,
+    transform: Transform,
+    view_distance: UVec3,
+) -> EntityCommands<'w, 's, 'a> {
+    #[cfg_attr(not(any(feature = "meshing", feature = "worldgen")), allow(unused_mut))]
+    let mut camera = commands.spawn((
+        Camera3dBundle {
+            transform,
+            ..default()
+        },
+        FlyCamera::from_transform(&transform),
+    ));
+
+    #[cfg(feature = "meshing")]
+    camera.insert(ChunkAnchor::<bones3_remesh::RemeshAnchor>::new(
+        world_id,
+        view_distance,
+    ));
+
+    #[cfg(feature = "worldgen")]
+    camera.insert(ChunkAnchor::<bones3_worldgen::WorldGenAnchor>::new(
+        world_id,
+        view_distance,
+    ));
+
+    #[cfg(not(any(feature = "meshing", feature = "worldgen")))]
+    let _ = (world_id, view_distance);
+
+    camera
+}
+
+/// This system rotates all fly cameras based off mouse movement while the
+/// right mouse button is held.
+fn rotate_fly_camera(
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut cameras: Query<&mut FlyCamera>,
+) {
+    let delta: Vec2 = mouse_motion.iter().map(|motion| motion.delta).sum();
+    if !mouse_buttons.pressed(MouseButton::Right) || delta == Vec2::ZERO {
+        return;
+    }
+
+    for mut camera in cameras.iter_mut() {
+        let sensitivity = camera.sensitivity;
+        camera.yaw -= delta.x * sensitivity;
+        camera.pitch = (camera.pitch - delta.y * sensitivity).clamp(-1.54, 1.54);
+    }
+}
+
+/// This system moves all fly cameras based off keyboard input, and applies
+/// their rotation to their transforms.
+fn move_fly_camera(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mut cameras: Query<(&FlyCamera, &mut Transform)>,
+) {
+    let mut input = Vec3::ZERO;
+    let key_axes = [
+        (KeyCode::W, Vec3::NEG_Z),
+        (KeyCode::S, Vec3::Z),
+        (KeyCode::A, Vec3::NEG_X),
+        (KeyCode::D, Vec3::X),
+        (KeyCode::Space, Vec3::Y),
+        (KeyCode::ShiftLeft, Vec3::NEG_Y),
+    ];
+
+    for (key, axis) in key_axes {
+        if keys.pressed(key) {
+            input += axis;
+        }
+    }
+
+    let sprint = keys.pressed(KeyCode::ControlLeft);
+    for (camera, mut transform) in cameras.iter_mut() {
+        let rotation = camera.rotation();
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+
+        if input == Vec3::ZERO {
+            continue;
+        }
+
+        let yaw = Quat::from_rotation_y(camera.yaw);
+        let direction = yaw * Vec3::new(input.x, 0.0, input.z) + Vec3::Y * input.y;
+
+        let mut speed = camera.speed * time.delta_seconds();
+        if sprint {
+            speed *= camera.sprint_multiplier;
+        }
+
+        transform.translation += direction.normalize_or_zero() * speed;
+    }
+}
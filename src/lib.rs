@@ -13,7 +13,20 @@ pub use bones3_remesh as remesh;
 #[cfg(feature = "worldgen")]
 pub use bones3_worldgen as worldgen;
 
+#[cfg(feature = "camera")]
+pub mod camera;
+mod plugins;
+
+pub use plugins::*;
+
 /// Used to import common components and systems for Bones Cubed.
 pub mod prelude {
+    #[cfg(feature = "camera")]
+    pub use super::camera::{spawn_fly_camera, FlyCamera, FlyCameraPlugin};
     pub use super::core::prelude::*;
+    #[cfg(feature = "meshing")]
+    pub use super::remesh::RemeshSet;
+    #[cfg(feature = "worldgen")]
+    pub use super::worldgen::WorldGenSet;
+    pub use super::Bones3PluginGroup;
 }
@@ -0,0 +1,152 @@
+//! A plugin group builder for adding all Bones Cubed plugins for a block type
+//! at once.
+
+use std::marker::PhantomData;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+use bones3_core::storage::BlockData;
+use bones3_core::util::anchor::ChunkAnchorSet;
+use bones3_core::{Bones3CorePlugin, Bones3CoreSet};
+
+/// A deferred function that adds a plugin to a plugin group.
+type AddPlugin = Box<dyn FnOnce(PluginGroupBuilder) -> PluginGroupBuilder + Send + Sync>;
+
+/// A plugin group that adds the core plugin for the given block data type,
+/// along with any optional meshing and world generation plugins, in the
+/// correct order.
+///
+/// ```no_run
+/// # #[cfg(all(feature = "meshing", feature = "worldgen"))]
+/// # fn main() {
+/// # use bevy::prelude::*;
+/// # use bevy_bones3::prelude::*;
+/// # use bevy_bones3::remesh::mesh::block_model::BlockShape;
+/// # use bevy_bones3::remesh::vertex_data::ShapeBuilder;
+/// # #[derive(Debug, Default, Clone, Copy, Reflect)]
+/// # struct BlockState;
+/// # impl BlockShape for BlockState {
+/// #     fn write_shape(&self, _: &mut ShapeBuilder) {}
+/// # }
+/// # let mut app = App::new();
+/// app.add_plugins(
+///     Bones3PluginGroup::<BlockState>::new()
+///         .add_mesh_support()
+///         .add_world_generation(),
+/// );
+/// # }
+/// # #[cfg(not(all(feature = "meshing", feature = "worldgen")))]
+/// # fn main() {}
+/// ```
+pub struct Bones3PluginGroup<T>
+where
+    T: BlockData,
+{
+    /// Phantom data for T.
+    _phantom: PhantomData<T>,
+
+    /// The optional plugins that have been added to this group.
+    plugins: Vec<AddPlugin>,
+}
+
+impl<T> Default for Bones3PluginGroup<T>
+where
+    T: BlockData,
+{
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+            plugins:  vec![],
+        }
+    }
+}
+
+impl<T> Bones3PluginGroup<T>
+where
+    T: BlockData,
+{
+    /// Creates a new plugin group that only contains the core plugin for the
+    /// given block data type.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the block-based remesh plugin for the given block data type.
+    #[cfg(feature = "meshing")]
+    pub fn add_mesh_support(mut self) -> Self
+    where
+        T: bones3_remesh::mesh::block_model::BlockShape,
+    {
+        self.plugins.push(Box::new(|group| {
+            group.add(bones3_remesh::Bones3RemeshPlugin::<T>::default())
+        }));
+        self
+    }
+
+    /// Adds the smooth remesh plugin for the given block data type.
+    #[cfg(feature = "meshing")]
+    pub fn add_smooth_mesh_support(mut self) -> Self
+    where
+        T: bones3_remesh::mesh::smooth::BlockDensity,
+    {
+        self.plugins.push(Box::new(|group| {
+            group.add(bones3_remesh::Bones3SmoothRemeshPlugin::<T>::default())
+        }));
+        self
+    }
+
+    /// Adds the world generation plugin for the given block data type.
+    #[cfg(feature = "worldgen")]
+    pub fn add_world_generation(mut self) -> Self {
+        self.plugins.push(Box::new(|group| {
+            group.add(bones3_worldgen::Bones3WorldGenPlugin::<T>::default())
+        }));
+        self
+    }
+}
+
+impl<T> PluginGroup for Bones3PluginGroup<T>
+where
+    T: BlockData,
+{
+    fn build(self) -> PluginGroupBuilder {
+        let group = PluginGroupBuilder::start::<Self>()
+            .add(Bones3SetOrderingPlugin)
+            .add(Bones3CorePlugin::<T>::default());
+        self.plugins
+            .into_iter()
+            .fold(group, |group, add_plugin| add_plugin(group))
+    }
+}
+
+/// A plugin that configures a single, deterministic ordering between the
+/// system sets of all Bones Cubed crates within the `PostUpdate` schedule.
+///
+/// Chunk anchors are updated first, followed by block writes and world
+/// generation chunk changes, then block updates, and finally remeshing.
+struct Bones3SetOrderingPlugin;
+
+impl Plugin for Bones3SetOrderingPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            PostUpdate,
+            (
+                ChunkAnchorSet::UpdateCoords,
+                ChunkAnchorSet::UpdatePriorities,
+            )
+                .before(Bones3CoreSet::BlockWrites),
+        );
+
+        #[cfg(all(feature = "meshing", feature = "worldgen"))]
+        app.configure_set(
+            PostUpdate,
+            bones3_remesh::RemeshSet
+                .after(bones3_worldgen::WorldGenSet::CreateChunks)
+                .after(bones3_worldgen::WorldGenSet::UnloadChunks),
+        );
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}